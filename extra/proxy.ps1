@@ -1,7 +1,9 @@
 [CmdletBinding()]
 param(
     [string]$version,
-    [string]$directory
+    [string]$directory,
+    # version of the running Lapce, which the proxy has to match
+    [string]$expected
 )
 
 $proxy = (Join-Path $directory 'lapce.exe')
@@ -13,8 +15,14 @@ if ($LapceProcesses -ne 0) {
 }
 
 if (Test-Path $proxy) {
-    Write-Host 'Proxy already installed'
-    exit
+    # `lapce --version` prints "Lapce <version>"
+    $installed = (& $proxy --version).Split(' ')[1]
+    if (-not $expected -or $expected -eq 'debug' -or $installed -eq $expected) {
+        Write-Host 'Proxy already installed'
+        exit
+    }
+    Write-Host 'Proxy outdated. Replacing proxy'
+    Remove-Item $proxy
 }
 
 switch ($env:PROCESSOR_ARCHITECTURE) {
@@ -13,24 +13,27 @@ test_cmd() {
 # have 'local' keyword
 
 _TMP='/tmp'
-# proxy version
+# proxy release tag
 _VER="${1}"
 # proxy directory
 # eval to resolve '~' into proper user dir
 eval _DIR="'${2}'"
+# version of the running Lapce, which the proxy has to match
+_EXPECTED="${3:-}"
 
 if [ -e "${_DIR}/lapce" ]; then
   chmod +x "${_DIR}/lapce"
 
-  _ver=$("${_DIR}/lapce" --version | cut -d'v' -f2)
+  # `lapce --version` prints "Lapce <version>"
+  _ver=$("${_DIR}/lapce" --version | cut -d' ' -f2)
 
-  printf '[DEBUG]: %s = %s' "${_ver}" "${_VER}"
-  if [ "${_ver}" = "${_VER}" ]; then
-    printf 'Proxy outdated. Replacing proxy\n'
-    rm "${_DIR}/lapce"
-  else
+  printf '[DEBUG]: %s = %s\n' "${_ver}" "${_EXPECTED}"
+  if [ -z "${_EXPECTED}" ] || [ "${_EXPECTED}" = "debug" ] || [ "${_ver}" = "${_EXPECTED}" ]; then
     printf 'Proxy already exists\n'
     exit 0
+  else
+    printf 'Proxy outdated. Replacing proxy\n'
+    rm "${_DIR}/lapce"
   fi
 fi
 
@@ -1,12 +1,13 @@
 use std::{
     borrow::Cow,
     cmp::Ordering,
-    collections::BTreeSet,
+    collections::{BTreeSet, HashSet},
     ops::Range,
     sync::{
         atomic::{self, AtomicU64},
         Arc,
     },
+    time::SystemTime,
 };
 
 use lsp_types::Position;
@@ -22,10 +23,11 @@
     cursor::CursorMode,
     editor::EditType,
     indent::{auto_detect_indent_style, IndentStyle},
+    line_ending::LineEnding,
     mode::Mode,
     selection::Selection,
     syntax::Syntax,
-    word::WordCursor,
+    word::{select_subword, WordCursor},
 };
 
 pub mod rope_text;
@@ -72,10 +74,22 @@ pub struct InvalLines {
     pub new_count: usize,
 }
 
+/// A state in the undo tree, reached by applying the undo group of the same
+/// index on top of the state of `parent`. Group 0 is the initial content and
+/// is its own parent.
+#[derive(Debug, Clone)]
+pub struct UndoTreeNode {
+    pub parent: usize,
+    pub time: SystemTime,
+}
+
 #[derive(Clone)]
 pub struct Buffer {
     rev_counter: u64,
     pristine_rev_id: u64,
+    /// The text at the pristine revision, which is the content of the file
+    /// as last loaded or saved
+    pristine_text: Rope,
     atomic_rev: Arc<AtomicU64>,
 
     text: Rope,
@@ -84,6 +98,9 @@ pub struct Buffer {
     undos: BTreeSet<usize>,
     undo_group_id: usize,
     live_undos: Vec<usize>,
+    /// Every undo group ever made, indexed by group, so that the states
+    /// left behind by an edit after an undo can still be reached.
+    undo_tree: Vec<UndoTreeNode>,
     deletes_from_union: Subset,
     undone_groups: BTreeSet<usize>,
     tombstones: Rope,
@@ -91,6 +108,10 @@ pub struct Buffer {
     last_edit_type: EditType,
 
     indent_style: IndentStyle,
+    /// The line ending of new lines
+    line_ending: LineEnding,
+    /// Whether edits, including undo and redo, are rejected
+    read_only: bool,
 
     max_len: usize,
     max_len_line: usize,
@@ -109,6 +130,7 @@ pub fn new(text: &str) -> Self {
 
             rev_counter: 1,
             pristine_rev_id: 0,
+            pristine_text: Rope::from(text),
             atomic_rev: Arc::new(AtomicU64::new(0)),
 
             revs: vec![Revision {
@@ -125,6 +147,10 @@ pub fn new(text: &str) -> Self {
             undos: BTreeSet::new(),
             undo_group_id: 1,
             live_undos: vec![0],
+            undo_tree: vec![UndoTreeNode {
+                parent: 0,
+                time: SystemTime::now(),
+            }],
             deletes_from_union: Subset::new(text.len()),
             undone_groups: BTreeSet::new(),
             tombstones: Rope::default(),
@@ -132,6 +158,8 @@ pub fn new(text: &str) -> Self {
             this_edit_type: EditType::Other,
             last_edit_type: EditType::Other,
             indent_style: IndentStyle::DEFAULT_INDENT,
+            line_ending: LineEnding::Lf,
+            read_only: false,
 
             max_len: 0,
             max_len_line: 0,
@@ -144,12 +172,25 @@ pub fn rev(&self) -> u64 {
 
     pub fn set_pristine(&mut self) {
         self.pristine_rev_id = self.rev();
+        self.pristine_text = self.text.clone();
+    }
+
+    pub fn pristine_text(&self) -> &Rope {
+        &self.pristine_text
     }
 
     pub fn is_pristine(&self) -> bool {
         self.is_equivalent_revision(self.pristine_rev_id, self.rev())
     }
 
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
     pub fn set_cursor_before(&mut self, cursor: CursorMode) {
         if let Some(rev) = self.revs.last_mut() {
             rev.cursor_before = Some(cursor);
@@ -263,6 +304,24 @@ pub fn init_content(&mut self, content: Rope) {
         self.set_pristine();
     }
 
+    /// Add a chunk of a file that is loaded a chunk at a time to the end of
+    /// the text. Like the initial content, it can't be undone.
+    pub fn append_content(&mut self, content: Rope) -> (RopeDelta, InvalLines) {
+        let len = self.len();
+        let delta = Delta::simple_edit(Interval::new(len, len), content, len);
+        let (new_rev, new_text, new_tombstones, new_deletes_from_union) =
+            self.mk_new_rev(0, delta.clone());
+        let inval_lines = self.apply_edit(
+            &delta,
+            new_rev,
+            new_text,
+            new_tombstones,
+            new_deletes_from_union,
+        );
+        self.set_pristine();
+        (delta, inval_lines)
+    }
+
     pub fn reload(
         &mut self,
         content: Rope,
@@ -286,6 +345,29 @@ pub fn detect_indent(&mut self, syntax: Option<&Syntax>) {
             });
     }
 
+    pub fn indent_style(&self) -> IndentStyle {
+        self.indent_style
+    }
+
+    pub fn set_indent_style(&mut self, indent_style: IndentStyle) {
+        self.indent_style = indent_style;
+    }
+
+    /// Use the line ending of most lines, or the platform's one if there's
+    /// only one line
+    pub fn detect_line_ending(&mut self) {
+        self.line_ending =
+            LineEnding::detect(&self.text).unwrap_or(LineEnding::DEFAULT);
+    }
+
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
     pub fn indent_unit(&self) -> &'static str {
         self.indent_style.as_str()
     }
@@ -299,6 +381,14 @@ pub fn edit(
         edits: &[(impl AsRef<Selection>, &str)],
         edit_type: EditType,
     ) -> (RopeDelta, InvalLines) {
+        if self.read_only {
+            let inval_lines = InvalLines {
+                start_line: 0,
+                inval_count: 0,
+                new_count: 0,
+            };
+            return (DeltaBuilder::new(self.len()).build(), inval_lines);
+        }
         let mut builder = DeltaBuilder::new(self.len());
         let mut interval_rope = Vec::new();
         for (selection, content) in edits {
@@ -385,6 +475,10 @@ fn calculate_undo_group(&mut self) -> usize {
         } else {
             let undo_group = self.undo_group_id;
             self.live_undos.truncate(self.cur_undo);
+            self.undo_tree.push(UndoTreeNode {
+                parent: *self.live_undos.last().unwrap_or(&0),
+                time: SystemTime::now(),
+            });
             self.live_undos.push(undo_group);
             self.cur_undo += 1;
             self.undo_group_id += 1;
@@ -652,6 +746,9 @@ fn undo(
     pub fn do_undo(
         &mut self,
     ) -> Option<(RopeDelta, InvalLines, Option<CursorMode>)> {
+        if self.read_only {
+            return None;
+        }
         if self.cur_undo <= 1 {
             return None;
         }
@@ -668,6 +765,9 @@ pub fn do_undo(
     pub fn do_redo(
         &mut self,
     ) -> Option<(RopeDelta, InvalLines, Option<CursorMode>)> {
+        if self.read_only {
+            return None;
+        }
         if self.cur_undo >= self.live_undos.len() {
             return None;
         }
@@ -681,6 +781,111 @@ pub fn do_redo(
         Some((delta, inval_lines, cursor_after))
     }
 
+    /// All the undo groups of the buffer, indexed by group
+    pub fn undo_tree(&self) -> &[UndoTreeNode] {
+        &self.undo_tree
+    }
+
+    /// The undo group whose state the buffer is in
+    pub fn current_undo_group(&self) -> usize {
+        self.live_undos[self.cur_undo - 1]
+    }
+
+    /// Move the buffer to the state of any group of the undo tree, undoing
+    /// and redoing whatever is needed to get there. Redo afterwards follows
+    /// the newest branch below that group.
+    pub fn jump_to_undo_group(
+        &mut self,
+        group: usize,
+    ) -> Option<(RopeDelta, InvalLines, Option<CursorMode>)> {
+        if self.read_only {
+            return None;
+        }
+        if group >= self.undo_tree.len() || group == self.current_undo_group() {
+            return None;
+        }
+
+        let mut live_undos = vec![group];
+        let mut node = group;
+        while node != 0 {
+            node = self.undo_tree[node].parent;
+            live_undos.push(node);
+        }
+        live_undos.reverse();
+        let cur_undo = live_undos.len();
+
+        let mut node = group;
+        while let Some(child) = (node + 1..self.undo_tree.len())
+            .rev()
+            .find(|g| self.undo_tree[*g].parent == node)
+        {
+            live_undos.push(child);
+            node = child;
+        }
+
+        let done: HashSet<usize> = live_undos[..cur_undo].iter().copied().collect();
+        self.undos = (1..self.undo_tree.len())
+            .filter(|g| !done.contains(g))
+            .collect();
+        self.live_undos = live_undos;
+        self.cur_undo = cur_undo;
+        self.last_edit_type = EditType::Undo;
+        let (delta, inval_lines, _cursor_before, cursor_after) =
+            self.undo(self.undos.clone());
+
+        Some((delta, inval_lines, cursor_after))
+    }
+
+    /// The deltas that undo each group of the undo history in turn, newest
+    /// first, for at most `max_groups` groups. They can be restored with
+    /// `restore_undo_deltas` onto a buffer with the same text.
+    pub fn undo_deltas(&self, max_groups: usize) -> Vec<RopeDelta> {
+        let mut buffer = self.clone();
+        // The undos shouldn't show up as revisions of this buffer
+        buffer.atomic_rev = Arc::new(AtomicU64::new(self.rev_counter));
+
+        let mut deltas = Vec::new();
+        while deltas.len() < max_groups {
+            match buffer.do_undo() {
+                Some((delta, _, _)) => deltas.push(delta),
+                None => break,
+            }
+        }
+        deltas
+    }
+
+    /// Bring back an undo history from `undo_deltas`, so that its groups can
+    /// be undone again. This is only done for a buffer that has just been
+    /// loaded, and returns `false` if the deltas don't apply to its text.
+    pub fn restore_undo_deltas(&mut self, deltas: &[RopeDelta]) -> bool {
+        if self.live_undos.len() > 1 {
+            return false;
+        }
+
+        let mut text = self.text.clone();
+        let mut redo_deltas = Vec::with_capacity(deltas.len());
+        for delta in deltas {
+            if delta.base_len != text.len() {
+                return false;
+            }
+            redo_deltas.push(invert_delta(delta, &text));
+            text = delta.apply(&text);
+        }
+
+        let mut buffer = Buffer::new("");
+        buffer.rev_counter = self.rev_counter;
+        buffer.atomic_rev = self.atomic_rev.clone();
+        buffer.indent_style = self.indent_style;
+        buffer.init_content(text);
+        for delta in redo_deltas.into_iter().rev() {
+            buffer.this_edit_type = EditType::Other;
+            buffer.add_delta(delta);
+        }
+        buffer.set_pristine();
+        *self = buffer;
+        true
+    }
+
     pub fn last_line(&self) -> usize {
         RopeText::new(&self.text).last_line()
     }
@@ -726,6 +931,10 @@ pub fn first_non_blank_character_on_line(&self, line: usize) -> usize {
         RopeText::new(&self.text).first_non_blank_character_on_line(line)
     }
 
+    pub fn end_of_non_blank_on_line(&self, line: usize) -> usize {
+        RopeText::new(&self.text).end_of_non_blank_on_line(line)
+    }
+
     pub fn indent_on_line(&self, line: usize) -> String {
         RopeText::new(&self.text).indent_on_line(line)
     }
@@ -818,6 +1027,13 @@ pub fn select_word(&self, offset: usize) -> (usize, usize) {
         WordCursor::new(&self.text, offset).select_word()
     }
 
+    pub fn select_subword(&self, offset: usize) -> (usize, usize) {
+        let (start, end) = self.select_word(offset);
+        let word = self.slice_to_cow(start..end);
+        let (subword_start, subword_end) = select_subword(&word, offset - start);
+        (start + subword_start, start + subword_end)
+    }
+
     pub fn char_at_offset(&self, offset: usize) -> Option<char> {
         if self.is_empty() {
             return None;
@@ -913,6 +1129,46 @@ pub fn move_n_words_backward(&self, offset: usize, count: usize) -> usize {
     pub fn move_word_backward_deletion(&self, offset: usize) -> usize {
         self.find_nth_word(offset, 1, |cursor| cursor.prev_deletion_boundary())
     }
+
+    pub fn move_n_subwords_forward(&self, offset: usize, count: usize) -> usize {
+        self.find_nth_word(offset, count, |cursor| cursor.next_subword_boundary())
+    }
+
+    pub fn move_n_subwords_backward(&self, offset: usize, count: usize) -> usize {
+        self.find_nth_word(offset, count, |cursor| cursor.prev_subword_boundary())
+    }
+}
+
+/// The delta that takes the result of applying `delta` to `text` back to
+/// `text`
+fn invert_delta(delta: &RopeDelta, text: &Rope) -> RopeDelta {
+    let mut builder = DeltaBuilder::new(delta.new_document_len());
+    let mut old_offset = 0;
+    let mut new_offset = 0;
+    let mut inserted_start = 0;
+    for el in delta.els.iter() {
+        match el {
+            DeltaElement::Copy(start, end) => {
+                if *start > old_offset || new_offset > inserted_start {
+                    builder.replace(
+                        inserted_start..new_offset,
+                        text.slice(old_offset..*start),
+                    );
+                }
+                new_offset += end - start;
+                old_offset = *end;
+                inserted_start = new_offset;
+            }
+            DeltaElement::Insert(rope) => new_offset += rope.len(),
+        }
+    }
+    if text.len() > old_offset || new_offset > inserted_start {
+        builder.replace(
+            inserted_start..new_offset,
+            text.slice(old_offset..text.len()),
+        );
+    }
+    builder.build()
 }
 
 fn shuffle_tombstones(
@@ -1157,6 +1413,25 @@ pub fn rope_diff(
     Some(changes)
 }
 
+/// The byte ranges of the changed parts of a pair of lines, which are what's
+/// left of each line between their common prefix and suffix
+pub fn line_diff_ranges(left: &str, right: &str) -> (Range<usize>, Range<usize>) {
+    let prefix: usize = left
+        .chars()
+        .zip(right.chars())
+        .take_while(|(l, r)| l == r)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    let suffix: usize = left[prefix..]
+        .chars()
+        .rev()
+        .zip(right[prefix..].chars().rev())
+        .take_while(|(l, r)| l == r)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    (prefix..left.len() - suffix, prefix..right.len() - suffix)
+}
+
 pub struct DeltaValueRegion<'a, N: NodeInfo + 'a> {
     pub old_offset: usize,
     pub new_offset: usize,
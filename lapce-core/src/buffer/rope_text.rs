@@ -176,6 +176,13 @@ pub fn first_non_blank_character_on_line(&self, line: usize) -> usize {
         WordCursor::new(self.text, line_start_offset).next_non_blank_char()
     }
 
+    /// The offset right after the last character of the line that isn't
+    /// whitespace, or the start of the line when it's blank
+    pub fn end_of_non_blank_on_line(&self, line: usize) -> usize {
+        let line_start_offset = self.text.offset_of_line(line);
+        line_start_offset + self.line_content(line).trim_end().len()
+    }
+
     pub fn indent_on_line(&self, line: usize) -> String {
         let line_start_offset = self.text.offset_of_line(line);
         let word_boundary =
@@ -15,6 +15,92 @@ fn is_pristine() {
         buffer.do_undo();
         assert!(buffer.is_pristine());
     }
+
+    #[test]
+    fn read_only() {
+        let mut buffer = Buffer::new("");
+        buffer.init_content(Rope::from("abc"));
+        buffer.set_read_only(true);
+        let (delta, _) =
+            buffer.edit(&[(Selection::caret(0), "d")], EditType::InsertChars);
+        assert!(delta.is_identity());
+        assert_eq!(buffer.text().to_string(), "abc");
+        assert!(buffer.is_pristine());
+    }
+
+    #[test]
+    fn restore_undo_deltas() {
+        let mut buffer = Buffer::new("");
+        buffer.init_content(Rope::from("abc"));
+        buffer.edit(&[(Selection::caret(3), "d")], EditType::InsertChars);
+        buffer.edit(&[(Selection::region(1, 3), "xy z")], EditType::Paste);
+        buffer.edit(&[(Selection::caret(0), "e")], EditType::InsertChars);
+        buffer.do_undo();
+        let deltas = buffer.undo_deltas(10);
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(buffer.text().to_string(), "axy zd");
+
+        let mut restored = Buffer::new("");
+        restored.init_content(buffer.text().clone());
+        assert!(restored.restore_undo_deltas(&deltas));
+        assert_eq!(restored.text().to_string(), "axy zd");
+        assert!(restored.is_pristine());
+
+        restored.do_undo();
+        assert_eq!(restored.text().to_string(), "abcd");
+        restored.do_undo();
+        assert_eq!(restored.text().to_string(), "abc");
+        assert!(restored.do_undo().is_none());
+        restored.do_redo();
+        assert_eq!(restored.text().to_string(), "abcd");
+
+        let mut changed = Buffer::new("");
+        changed.init_content(Rope::from("abc"));
+        assert!(!changed.restore_undo_deltas(&deltas));
+        assert_eq!(changed.text().to_string(), "abc");
+    }
+
+    #[test]
+    fn jump_to_undo_group() {
+        let mut buffer = Buffer::new("");
+        buffer.init_content(Rope::from("abc"));
+        buffer.edit(&[(Selection::caret(3), "d")], EditType::InsertChars);
+        buffer.edit(&[(Selection::caret(0), "e")], EditType::Other);
+        buffer.do_undo();
+        buffer.edit(&[(Selection::caret(0), "f")], EditType::Other);
+        assert_eq!(buffer.text().to_string(), "fabcd");
+
+        let tree = buffer.undo_tree();
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree[1].parent, 0);
+        assert_eq!(tree[2].parent, 1);
+        assert_eq!(tree[3].parent, 1);
+        assert_eq!(buffer.current_undo_group(), 3);
+
+        // The branch left behind by the undo can still be reached
+        assert!(buffer.jump_to_undo_group(2).is_some());
+        assert_eq!(buffer.text().to_string(), "eabcd");
+        assert_eq!(buffer.current_undo_group(), 2);
+        assert!(buffer.jump_to_undo_group(2).is_none());
+
+        buffer.do_undo();
+        assert_eq!(buffer.text().to_string(), "abcd");
+        buffer.do_redo();
+        assert_eq!(buffer.text().to_string(), "eabcd");
+
+        buffer.jump_to_undo_group(0);
+        assert_eq!(buffer.text().to_string(), "abc");
+        assert!(buffer.is_pristine());
+        // Redo follows the newest branch
+        buffer.do_redo();
+        buffer.do_redo();
+        assert_eq!(buffer.text().to_string(), "fabcd");
+
+        buffer.jump_to_undo_group(2);
+        buffer.edit(&[(Selection::caret(0), "g")], EditType::Other);
+        assert_eq!(buffer.text().to_string(), "geabcd");
+        assert_eq!(buffer.undo_tree()[4].parent, 2);
+    }
 }
 
 mod motion {
@@ -158,3 +244,20 @@ fn v(buf: &Buffer, off: usize, n: usize, end: usize) {
         }
     }
 }
+
+mod diff {
+    use crate::buffer::line_diff_ranges;
+
+    #[test]
+    fn changed_part_of_lines() {
+        assert_eq!(
+            line_diff_ranges("let a = 1;\n", "let b = 1;\n"),
+            (4..5, 4..5)
+        );
+        assert_eq!(line_diff_ranges("foo(a)\n", "foo(a, b)\n"), (5..5, 5..8));
+        assert_eq!(line_diff_ranges("same\n", "same\n"), (5..5, 5..5));
+        // The prefix and the suffix never overlap
+        assert_eq!(line_diff_ranges("aa", "aaa"), (2..2, 2..3));
+        assert_eq!(line_diff_ranges("é1é", "é2é"), (2..3, 2..3));
+    }
+}
@@ -28,6 +28,9 @@ pub enum EditCommand {
     NewLineBelow,
     #[strum(serialize = "delete_backward")]
     DeleteBackward,
+    #[strum(message = "Delete Whitespace Backward")]
+    #[strum(serialize = "delete_whitespace_backward")]
+    DeleteWhitespaceBackward,
     #[strum(serialize = "delete_forward")]
     DeleteForward,
     #[strum(serialize = "delete_forward_and_insert")]
@@ -36,11 +39,24 @@ pub enum EditCommand {
     DeleteWordForward,
     #[strum(serialize = "delete_word_backward")]
     DeleteWordBackward,
+    #[strum(serialize = "delete_subword_forward")]
+    DeleteSubwordForward,
+    #[strum(serialize = "delete_subword_backward")]
+    DeleteSubwordBackward,
     #[strum(serialize = "delete_to_beginning_of_line")]
     DeleteToBeginningOfLine,
     #[strum(message = "Join Lines")]
     #[strum(serialize = "join_lines")]
     JoinLines,
+    #[strum(message = "Transpose Characters")]
+    #[strum(serialize = "transpose_characters")]
+    TransposeCharacters,
+    #[strum(message = "Transpose Words")]
+    #[strum(serialize = "transpose_words")]
+    TransposeWords,
+    #[strum(message = "Split Arguments Across Lines")]
+    #[strum(serialize = "split_arguments")]
+    SplitArguments,
     #[strum(message = "Indent Line")]
     #[strum(serialize = "indent_line")]
     IndentLine,
@@ -50,6 +66,9 @@ pub enum EditCommand {
     #[strum(message = "Toggle Line Comment")]
     #[strum(serialize = "toggle_line_comment")]
     ToggleLineComment,
+    #[strum(message = "Toggle Block Comment")]
+    #[strum(serialize = "toggle_block_comment")]
+    ToggleBlockComment,
     #[strum(serialize = "undo")]
     Undo,
     #[strum(serialize = "redo")]
@@ -90,6 +109,61 @@ pub enum EditCommand {
     DuplicateLineUp,
     #[strum(serialize = "duplicate_line_down")]
     DuplicateLineDown,
+    #[strum(message = "Move Lines Up")]
+    #[strum(serialize = "move_lines_up")]
+    MoveLinesUp,
+    #[strum(message = "Move Lines Down")]
+    #[strum(serialize = "move_lines_down")]
+    MoveLinesDown,
+    #[strum(message = "Duplicate Selection")]
+    #[strum(serialize = "duplicate_selection")]
+    DuplicateSelection,
+    #[strum(message = "Sort Lines Ascending")]
+    #[strum(serialize = "sort_lines_ascending")]
+    SortLinesAscending,
+    #[strum(message = "Sort Lines Descending")]
+    #[strum(serialize = "sort_lines_descending")]
+    SortLinesDescending,
+    #[strum(message = "Sort Lines Numerically")]
+    #[strum(serialize = "sort_lines_numerically")]
+    SortLinesNumerically,
+    #[strum(message = "Delete Duplicate Lines")]
+    #[strum(serialize = "delete_duplicate_lines")]
+    DeleteDuplicateLines,
+    #[strum(message = "Reverse Lines")]
+    #[strum(serialize = "reverse_lines")]
+    ReverseLines,
+    #[strum(message = "Transform to Uppercase")]
+    #[strum(serialize = "transform_to_uppercase")]
+    TransformToUppercase,
+    #[strum(message = "Transform to Lowercase")]
+    #[strum(serialize = "transform_to_lowercase")]
+    TransformToLowercase,
+    #[strum(message = "Transform to Title Case")]
+    #[strum(serialize = "transform_to_title_case")]
+    TransformToTitleCase,
+    #[strum(message = "Transform to Snake Case")]
+    #[strum(serialize = "transform_to_snake_case")]
+    TransformToSnakeCase,
+    #[strum(message = "Transform to Camel Case")]
+    #[strum(serialize = "transform_to_camel_case")]
+    TransformToCamelCase,
+}
+
+impl EditCommand {
+    /// Whether the command can change the text of the buffer, which isn't
+    /// allowed when it's read-only
+    pub fn changes_text(&self) -> bool {
+        !matches!(
+            self,
+            EditCommand::ClipboardCopy
+                | EditCommand::Yank
+                | EditCommand::NormalMode
+                | EditCommand::ToggleVisualMode
+                | EditCommand::ToggleLinewiseVisualMode
+                | EditCommand::ToggleBlockwiseVisualMode
+        )
+    }
 }
 
 #[derive(
@@ -118,6 +192,10 @@ pub enum MoveCommand {
     WordForward,
     #[strum(serialize = "word_end_forward")]
     WordEndForward,
+    #[strum(serialize = "word_backward_subword")]
+    WordBackwardSubword,
+    #[strum(serialize = "word_forward_subword")]
+    WordForwardSubword,
     #[strum(message = "Document Start")]
     #[strum(serialize = "document_start")]
     DocumentStart,
@@ -130,6 +208,8 @@ pub enum MoveCommand {
     LineStart,
     #[strum(serialize = "line_start_non_blank")]
     LineStartNonBlank,
+    #[strum(serialize = "line_end_non_blank")]
+    LineEndNonBlank,
     #[strum(serialize = "go_to_line_default_last")]
     GotoLineDefaultLast,
     #[strum(serialize = "go_to_line_default_first")]
@@ -159,6 +239,7 @@ pub fn to_movement(&self, count: Option<usize>) -> Movement {
             LineStart => Movement::StartOfLine,
             LineStartNonBlank => Movement::FirstNonBlank,
             LineEnd => Movement::EndOfLine,
+            LineEndNonBlank => Movement::LastNonBlank,
             GotoLineDefaultFirst => match count {
                 Some(n) => Movement::Line(LinePosition::Line(n)),
                 None => Movement::Line(LinePosition::First),
@@ -170,6 +251,8 @@ pub fn to_movement(&self, count: Option<usize>) -> Movement {
             WordBackward => Movement::WordBackward,
             WordForward => Movement::WordForward,
             WordEndForward => Movement::WordEndForward,
+            WordBackwardSubword => Movement::SubwordBackward,
+            WordForwardSubword => Movement::SubwordForward,
             MatchPairs => Movement::MatchPairs,
             NextUnmatchedRightBracket => Movement::NextUnmatched(')'),
             PreviousUnmatchedLeftBracket => Movement::PreviousUnmatched('('),
@@ -231,10 +314,25 @@ pub enum FocusCommand {
     ListPreviousPage,
     #[strum(serialize = "list.expand")]
     ListExpand,
+    #[strum(serialize = "signature.next")]
+    SignatureNext,
+    #[strum(serialize = "signature.previous")]
+    SignaturePrevious,
+    /// Keep the hover open when the mouse leaves it, or let it close again
+    #[strum(message = "Toggle Pin Hover")]
+    #[strum(serialize = "toggle_pin_hover")]
+    TogglePinHover,
+    /// Add a breakpoint to the line of the cursor, or remove the one it has
+    #[strum(message = "Toggle Breakpoint")]
+    #[strum(serialize = "toggle_breakpoint")]
+    ToggleBreakpoint,
     #[strum(serialize = "jump_to_next_snippet_placeholder")]
     JumpToNextSnippetPlaceholder,
     #[strum(serialize = "jump_to_prev_snippet_placeholder")]
     JumpToPrevSnippetPlaceholder,
+    #[strum(serialize = "expand_emmet_abbreviation")]
+    #[strum(message = "Emmet: Expand Abbreviation")]
+    ExpandEmmetAbbreviation,
     #[strum(serialize = "page_up")]
     PageUp,
     #[strum(serialize = "page_down")]
@@ -263,6 +361,24 @@ pub enum FocusCommand {
     #[strum(message = "Go to Type Definition")]
     #[strum(serialize = "goto_type_definition")]
     GotoTypeDefinition,
+    #[strum(message = "Go to Declaration")]
+    #[strum(serialize = "goto_declaration")]
+    GotoDeclaration,
+    #[strum(message = "Go to Implementation")]
+    #[strum(serialize = "goto_implementation")]
+    GotoImplementation,
+    #[strum(message = "Peek Definition")]
+    #[strum(serialize = "peek_definition")]
+    PeekDefinition,
+    #[strum(message = "Peek References")]
+    #[strum(serialize = "peek_references")]
+    PeekReferences,
+    #[strum(message = "Show Call Hierarchy")]
+    #[strum(serialize = "show_call_hierarchy")]
+    ShowCallHierarchy,
+    #[strum(message = "Show Type Hierarchy")]
+    #[strum(serialize = "show_type_hierarchy")]
+    ShowTypeHierarchy,
     #[strum(serialize = "jump_location_backward")]
     JumpLocationBackward,
     #[strum(serialize = "jump_location_forward")]
@@ -272,6 +388,12 @@ pub enum FocusCommand {
     NextError,
     #[strum(serialize = "previous_error")]
     PreviousError,
+    #[strum(message = "Go to Next Occurrence")]
+    #[strum(serialize = "next_occurrence")]
+    NextOccurrence,
+    #[strum(message = "Go to Previous Occurrence")]
+    #[strum(serialize = "previous_occurrence")]
+    PreviousOccurrence,
     #[strum(message = "Go to Next Difference")]
     #[strum(serialize = "next_diff")]
     NextDiff,
@@ -281,11 +403,47 @@ pub enum FocusCommand {
     #[strum(message = "Toggle Code Lens")]
     #[strum(serialize = "toggle_code_lens")]
     ToggleCodeLens,
+    #[strum(message = "Toggle Fold")]
+    #[strum(serialize = "toggle_fold")]
+    ToggleFold,
+    #[strum(message = "Fold")]
+    #[strum(serialize = "fold")]
+    Fold,
+    #[strum(message = "Unfold")]
+    #[strum(serialize = "unfold")]
+    Unfold,
+    #[strum(message = "Fold All")]
+    #[strum(serialize = "fold_all")]
+    FoldAll,
+    #[strum(message = "Unfold All")]
+    #[strum(serialize = "unfold_all")]
+    UnfoldAll,
+    #[strum(message = "Fold All Comments")]
+    #[strum(serialize = "fold_comments")]
+    FoldComments,
+    #[strum(message = "Fold Imports")]
+    #[strum(serialize = "fold_imports")]
+    FoldImports,
     #[strum(serialize = "format_document")]
     #[strum(message = "Format Document")]
     FormatDocument,
+    #[strum(serialize = "format_selection")]
+    #[strum(message = "Format Selection")]
+    FormatSelection,
+    #[strum(serialize = "run_selection_in_terminal")]
+    #[strum(message = "Run Selection in Terminal")]
+    RunSelectionInTerminal,
     #[strum(serialize = "search")]
     Search,
+    #[strum(serialize = "replace")]
+    #[strum(message = "Replace")]
+    Replace,
+    #[strum(serialize = "replace_next")]
+    #[strum(message = "Replace Next")]
+    ReplaceNext,
+    #[strum(serialize = "replace_all")]
+    #[strum(message = "Replace All")]
+    ReplaceAll,
     #[strum(serialize = "inline_find_right")]
     InlineFindRight,
     #[strum(serialize = "inline_find_left")]
@@ -295,6 +453,9 @@ pub enum FocusCommand {
     #[strum(message = "Save")]
     #[strum(serialize = "save")]
     Save,
+    #[strum(message = "Save Without Formatting")]
+    #[strum(serialize = "save_without_formatting")]
+    SaveWithoutFormatting,
     #[strum(serialize = "save_and_exit")]
     SaveAndExit,
     #[strum(serialize = "force_exit")]
@@ -304,10 +465,24 @@ pub enum FocusCommand {
     Rename,
     #[strum(serialize = "confirm_rename")]
     ConfirmRename,
+    #[strum(serialize = "debug_console.evaluate")]
+    DebugConsoleEvaluate,
+    #[strum(serialize = "debug_console.history_previous")]
+    DebugConsoleHistoryPrevious,
+    #[strum(serialize = "debug_console.history_next")]
+    DebugConsoleHistoryNext,
+    #[strum(serialize = "debug_console.complete")]
+    DebugConsoleComplete,
     #[strum(serialize = "select_next_syntax_item")]
     SelectNextSyntaxItem,
     #[strum(serialize = "select_previous_syntax_item")]
     SelectPreviousSyntaxItem,
+    #[strum(serialize = "expand_selection")]
+    #[strum(message = "Expand Selection")]
+    ExpandSelection,
+    #[strum(serialize = "shrink_selection")]
+    #[strum(message = "Shrink Selection")]
+    ShrinkSelection,
 }
 
 #[derive(
@@ -362,4 +537,12 @@ pub enum MultiSelectionCommand {
     SelectSkipCurrent,
     #[strum(serialize = "select_all")]
     SelectAll,
+    #[strum(serialize = "select_block_up")]
+    SelectBlockUp,
+    #[strum(serialize = "select_block_down")]
+    SelectBlockDown,
+    #[strum(serialize = "select_block_left")]
+    SelectBlockLeft,
+    #[strum(serialize = "select_block_right")]
+    SelectBlockRight,
 }
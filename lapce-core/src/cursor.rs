@@ -20,6 +20,10 @@ pub struct Cursor {
     pub horiz: Option<ColPosition>,
     pub motion_mode: Option<MotionMode>,
     pub history_selections: Vec<Selection>,
+    /// The cursor mode before each syntax expansion of the selection, with
+    /// the selection it was expanded to
+    #[serde(skip)]
+    expanded_selections: Vec<(CursorMode, Selection)>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -54,6 +58,7 @@ pub fn new(
             horiz,
             motion_mode,
             history_selections: Vec::new(),
+            expanded_selections: Vec::new(),
         }
     }
 
@@ -157,7 +162,74 @@ pub fn edit_selection(&self, buffer: &Buffer) -> Selection {
         }
     }
 
+    /// Expand every region of the selection to the range returned by
+    /// `expand`, usually the smallest syntax node around it, and remember
+    /// the selection so that `shrink_selection` can go back to it exactly
+    pub fn expand_selection(
+        &mut self,
+        buffer: &Buffer,
+        expand: impl Fn(usize, usize) -> Option<(usize, usize)>,
+    ) {
+        let current = self.expandable_selection(buffer);
+        if self
+            .expanded_selections
+            .last()
+            .map(|(_, expanded)| *expanded != current)
+            .unwrap_or(false)
+        {
+            // The selection was changed since the last expansion
+            self.expanded_selections.clear();
+        }
+
+        let mut selection = Selection::new();
+        for region in current.regions() {
+            let (start, end) = expand(region.min(), region.max())
+                .unwrap_or((region.min(), region.max()));
+            selection.add_region(SelRegion::new(start, end, None));
+        }
+        if selection == current {
+            return;
+        }
+
+        let mode = self.mode.clone();
+        self.mode = match &self.mode {
+            CursorMode::Insert(_) => CursorMode::Insert(selection),
+            CursorMode::Normal(_) | CursorMode::Visual { .. } => {
+                let start = selection.min_offset();
+                let end = buffer.prev_grapheme_offset(selection.max_offset(), 1, 0);
+                CursorMode::Visual {
+                    start,
+                    end: end.max(start),
+                    mode: VisualMode::Normal,
+                }
+            }
+        };
+        let expanded = self.expandable_selection(buffer);
+        self.expanded_selections.push((mode, expanded));
+    }
+
+    /// Go back to the selection before the last `expand_selection`, unless
+    /// the selection was changed since
+    pub fn shrink_selection(&mut self, buffer: &Buffer) {
+        let current = self.expandable_selection(buffer);
+        match self.expanded_selections.pop() {
+            Some((mode, expanded)) if expanded == current => {
+                self.mode = mode;
+            }
+            _ => self.expanded_selections.clear(),
+        }
+    }
+
+    /// The selection that `expand_selection` expands
+    pub fn expandable_selection(&self, buffer: &Buffer) -> Selection {
+        match &self.mode {
+            CursorMode::Normal(offset) => Selection::caret(*offset),
+            _ => self.edit_selection(buffer),
+        }
+    }
+
     pub fn apply_delta(&mut self, delta: &RopeDelta) {
+        self.expanded_selections.clear();
         match &self.mode {
             CursorMode::Normal(offset) => {
                 let mut transformer = Transformer::new(delta);
@@ -476,3 +548,78 @@ pub fn get_first_selection_after(
             Cursor::new(cursor_mode, None, None)
         })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Expand to the smallest of the nodes of "((a) (b))" that contains the
+    /// range
+    fn expand(start: usize, end: usize) -> Option<(usize, usize)> {
+        [(2, 3), (6, 7), (1, 4), (5, 8), (0, 9)]
+            .into_iter()
+            .find(|(s, e)| *s <= start && end <= *e && (*s, *e) != (start, end))
+    }
+
+    fn regions(cursor: &Cursor, buffer: &Buffer) -> Vec<(usize, usize)> {
+        cursor
+            .edit_selection(buffer)
+            .regions()
+            .iter()
+            .map(|region| (region.min(), region.max()))
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_and_shrink_selection() {
+        let buffer = Buffer::new("((a) (b))");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::caret(2));
+        selection.add_region(SelRegion::caret(6));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+
+        cursor.expand_selection(&buffer, expand);
+        assert_eq!(regions(&cursor, &buffer), vec![(2, 3), (6, 7)]);
+        cursor.expand_selection(&buffer, expand);
+        assert_eq!(regions(&cursor, &buffer), vec![(1, 4), (5, 8)]);
+        cursor.expand_selection(&buffer, expand);
+        assert_eq!(regions(&cursor, &buffer), vec![(0, 9)]);
+        cursor.expand_selection(&buffer, expand);
+        assert_eq!(regions(&cursor, &buffer), vec![(0, 9)]);
+
+        cursor.shrink_selection(&buffer);
+        assert_eq!(regions(&cursor, &buffer), vec![(1, 4), (5, 8)]);
+        cursor.shrink_selection(&buffer);
+        assert_eq!(regions(&cursor, &buffer), vec![(2, 3), (6, 7)]);
+        cursor.shrink_selection(&buffer);
+        assert_eq!(regions(&cursor, &buffer), vec![(2, 2), (6, 6)]);
+    }
+
+    #[test]
+    fn test_shrink_selection_after_change() {
+        let buffer = Buffer::new("((a) (b))");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(2)), None, None);
+        cursor.expand_selection(&buffer, expand);
+        cursor.set_insert(Selection::caret(6));
+        cursor.shrink_selection(&buffer);
+        assert_eq!(regions(&cursor, &buffer), vec![(6, 6)]);
+    }
+
+    #[test]
+    fn test_expand_selection_in_normal_mode() {
+        let buffer = Buffer::new("((a) (b))");
+        let mut cursor = Cursor::new(CursorMode::Normal(5), None, None);
+        cursor.expand_selection(&buffer, expand);
+        assert_eq!(
+            cursor.mode,
+            CursorMode::Visual {
+                start: 5,
+                end: 7,
+                mode: VisualMode::Normal
+            }
+        );
+        cursor.shrink_selection(&buffer);
+        assert_eq!(cursor.mode, CursorMode::Normal(5));
+    }
+}
@@ -1,10 +1,13 @@
-use std::collections::HashSet;
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet},
+};
 
 use itertools::Itertools;
 use xi_rope::RopeDelta;
 
 use crate::{
-    buffer::{Buffer, InvalLines},
+    buffer::{line_diff_ranges, Buffer, InvalLines},
     command::EditCommand,
     cursor::{get_first_selection_after, Cursor, CursorMode},
     mode::{Mode, MotionMode, VisualMode},
@@ -17,6 +20,7 @@
         },
         Syntax,
     },
+    transform::{self, CaseTransform, LineTransform},
     word::{get_word_property, WordProperty},
 };
 
@@ -59,6 +63,9 @@ pub fn insert(
         syntax: Option<&Syntax>,
         auto_closing_matching_pairs: bool,
     ) -> Vec<(RopeDelta, InvalLines)> {
+        if buffer.is_read_only() {
+            return vec![];
+        }
         let mut deltas = Vec::new();
         if let CursorMode::Insert(selection) = &cursor.mode {
             if s.chars().count() != 1 {
@@ -321,7 +328,7 @@ fn insert_new_line(
                 } else {
                     &line_indent
                 };
-                format!("\n{indent}")
+                format!("{}{indent}", buffer.line_ending().as_str())
             };
 
             let selection = Selection::region(region.min(), region.max());
@@ -453,8 +460,33 @@ pub fn do_paste(
         buffer: &mut Buffer,
         data: &RegisterData,
     ) -> Vec<(RopeDelta, InvalLines)> {
+        // Pasted lines take the line ending of the buffer
+        let normalized;
+        let data = match buffer.line_ending().normalize(&data.content) {
+            Cow::Borrowed(_) => data,
+            Cow::Owned(content) => {
+                normalized = RegisterData {
+                    content,
+                    mode: data.mode,
+                };
+                &normalized
+            }
+        };
+
         let mut deltas = Vec::new();
         match data.mode {
+            VisualMode::Normal
+                if Self::is_multi_cursor_paste(cursor, &data.content) =>
+            {
+                deltas.push(Self::do_multi_cursor_paste(
+                    cursor,
+                    buffer,
+                    &data.content,
+                ));
+            }
+            VisualMode::Blockwise if !cursor.is_visual() => {
+                deltas.push(Self::do_block_paste(cursor, buffer, &data.content));
+            }
             VisualMode::Normal => {
                 let selection = match cursor.mode {
                     CursorMode::Normal(offset) => {
@@ -513,7 +545,10 @@ pub fn do_paste(
                         let selection = cursor.edit_selection(buffer);
                         let data = match mode {
                             VisualMode::Linewise => data.content.clone(),
-                            _ => "\n".to_string() + &data.content,
+                            _ => {
+                                buffer.line_ending().as_str().to_string()
+                                    + &data.content
+                            }
                         };
                         (selection, data)
                     }
@@ -530,7 +565,7 @@ pub fn do_paste(
                     CursorMode::Normal(_) | CursorMode::Visual { .. } => {
                         let offset = selection.min_offset();
                         let offset = if cursor.is_visual() {
-                            offset + 1
+                            offset + buffer.line_ending().as_str().len()
                         } else {
                             offset
                         };
@@ -547,6 +582,121 @@ pub fn do_paste(
         deltas
     }
 
+    /// Whether pasting `content` should put one of its lines in each region
+    /// of the selection, which is the case when there are as many lines as
+    /// regions, like after copying from several cursors or a block.
+    fn is_multi_cursor_paste(cursor: &Cursor, content: &str) -> bool {
+        match &cursor.mode {
+            CursorMode::Insert(selection) => {
+                selection.len() > 1 && content.lines().count() == selection.len()
+            }
+            CursorMode::Normal(_) | CursorMode::Visual { .. } => false,
+        }
+    }
+
+    fn do_multi_cursor_paste(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        content: &str,
+    ) -> (RopeDelta, InvalLines) {
+        let selection = cursor.edit_selection(buffer);
+        let edits = selection
+            .regions()
+            .iter()
+            .zip(content.lines())
+            .map(|(region, line)| {
+                (Selection::region(region.min(), region.max()), line)
+            })
+            .collect::<Vec<_>>();
+        let (delta, inval_lines) = buffer.edit(&edits, EditType::Paste);
+        let selection = selection.apply_delta(&delta, true, InsertDrift::Default);
+        cursor.mode = CursorMode::Insert(selection);
+        (delta, inval_lines)
+    }
+
+    /// Paste the lines of a block at the same column of the lines from the
+    /// cursor down. Lines that are too short are padded with spaces, and
+    /// lines are added past the end of the buffer when needed.
+    fn do_block_paste(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        content: &str,
+    ) -> (RopeDelta, InvalLines) {
+        let offset = match cursor.mode {
+            CursorMode::Normal(offset) => {
+                let line_end = buffer.offset_line_end(offset, true);
+                buffer.next_grapheme_offset(offset, 1, line_end)
+            }
+            CursorMode::Insert(_) | CursorMode::Visual { .. } => cursor.offset(),
+        };
+        let first_line = buffer.line_of_offset(offset);
+        let col = buffer
+            .slice_to_cow(buffer.offset_of_line(first_line)..offset)
+            .chars()
+            .count();
+        let last_line = buffer.last_line();
+
+        let mut edits: Vec<(Selection, String)> = Vec::new();
+        let mut appended = String::new();
+        for (i, block_line) in content.lines().enumerate() {
+            let line = first_line + i;
+            if line > last_line {
+                appended.push_str(buffer.line_ending().as_str());
+                appended.push_str(&" ".repeat(col));
+                appended.push_str(block_line);
+                continue;
+            }
+
+            let line_start = buffer.offset_of_line(line);
+            let line_content = buffer.line_content(line);
+            let line_content = line_content.trim_end_matches(['\n', '\r']);
+            let len = line_content.chars().count();
+            let edit = if len < col {
+                (
+                    line_start + line_content.len(),
+                    " ".repeat(col - len) + block_line,
+                )
+            } else {
+                let line_col = line_content
+                    .char_indices()
+                    .nth(col)
+                    .map(|(i, _)| i)
+                    .unwrap_or(line_content.len());
+                (line_start + line_col, block_line.to_string())
+            };
+            edits.push((Selection::caret(edit.0), edit.1));
+        }
+        if !appended.is_empty() {
+            // The last line can end where the added lines start
+            match edits.last_mut() {
+                Some((selection, text))
+                    if selection.min_offset() == buffer.len() =>
+                {
+                    text.push_str(&appended);
+                }
+                _ => edits.push((Selection::caret(buffer.len()), appended)),
+            }
+        }
+
+        let edits = edits
+            .iter()
+            .map(|(selection, text)| (selection, text.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines) = buffer.edit(&edits, EditType::Paste);
+        let selection = Selection::caret(offset).apply_delta(
+            &delta,
+            cursor.is_insert(),
+            InsertDrift::Default,
+        );
+        cursor.mode = match cursor.mode {
+            CursorMode::Insert(_) => CursorMode::Insert(selection),
+            CursorMode::Normal(_) | CursorMode::Visual { .. } => {
+                CursorMode::Normal(selection.min_offset())
+            }
+        };
+        (delta, inval_lines)
+    }
+
     fn do_indent(
         buffer: &mut Buffer,
         selection: Selection,
@@ -654,11 +804,701 @@ fn duplicate_line(
             .iter()
             .map(|(sel, content)| (sel, content.as_str()))
             .collect::<Vec<_>>();
-
-        let (delta, inval_lines) = buffer.edit(&edits, EditType::InsertChars);
-
-        *selection = selection.apply_delta(&delta, true, InsertDrift::Default);
-
+
+        let (delta, inval_lines) = buffer.edit(&edits, EditType::InsertChars);
+
+        *selection = selection.apply_delta(&delta, true, InsertDrift::Default);
+
+        vec![(delta, inval_lines)]
+    }
+
+    /// The start of the whitespace, including line endings, before the offset
+    fn whitespace_start(buffer: &Buffer, offset: usize) -> usize {
+        let mut line = buffer.line_of_offset(offset);
+        let mut end = offset;
+        loop {
+            let line_start = buffer.offset_of_line(line);
+            let text = buffer.slice_to_cow(line_start..end);
+            let content = text.trim_end();
+            if !content.is_empty() || line == 0 {
+                return line_start + content.len();
+            }
+            line -= 1;
+            end = line_start;
+        }
+    }
+
+    /// Move the lines of each region one line up or down, keeping the regions
+    /// apart, and reindent them when they move into or out of a block
+    fn move_lines(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        up: bool,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        // the empty line after a final line ending can't be moved
+        let mut last_line = buffer.last_line();
+        if last_line > 0 && buffer.offset_of_line(last_line) == buffer.len() {
+            last_line -= 1;
+        }
+
+        let mut blocks: Vec<(usize, usize)> = Vec::new();
+        for region in cursor.edit_selection(buffer).regions() {
+            let start_line = buffer.line_of_offset(region.min()).min(last_line);
+            let mut end_line = buffer.line_of_offset(region.max());
+            if end_line > start_line
+                && region.max() == buffer.offset_of_line(end_line)
+            {
+                end_line -= 1;
+            }
+            let end_line = end_line.min(last_line);
+            // adjacent regions move together, since one would otherwise
+            // move past the other
+            match blocks.last_mut() {
+                Some((_, end)) if start_line <= *end + 1 => {
+                    *end = (*end).max(end_line);
+                }
+                _ => blocks.push((start_line, end_line)),
+            }
+        }
+
+        let line_ending = buffer.line_ending().as_str();
+        let mut edits = Vec::new();
+        // the line each line ends up on, and the change of its indentation
+        let mut moves = HashMap::new();
+        for (start_line, end_line) in blocks {
+            if (up && start_line == 0) || (!up && end_line >= last_line) {
+                continue;
+            }
+            let crossed_line = if up { start_line - 1 } else { end_line + 1 };
+            let crossed = Self::line_text(buffer, crossed_line);
+            let lines = (start_line..end_line + 1)
+                .map(|line| Self::line_text(buffer, line))
+                .collect::<Vec<_>>();
+            let (mut lines, shift) = match transform::moved_lines_indent(
+                &crossed,
+                up,
+                buffer.indent_unit(),
+            ) {
+                Some(indent) => transform::reindent_lines(&lines, &indent),
+                None => (lines, 0),
+            };
+
+            for (i, line) in (start_line..end_line + 1).enumerate() {
+                let new_line = if up { line - 1 } else { line + 1 };
+                let shift = if lines[i].trim().is_empty() { 0 } else { shift };
+                moves.insert(line, (new_line, shift));
+            }
+            let (first, last) = if up {
+                moves.insert(crossed_line, (end_line, 0));
+                lines.push(crossed);
+                (crossed_line, end_line)
+            } else {
+                moves.insert(crossed_line, (start_line, 0));
+                lines.insert(0, crossed);
+                (start_line, crossed_line)
+            };
+            let start = buffer.offset_of_line(first);
+            let end =
+                buffer.offset_of_line(last) + Self::line_text(buffer, last).len();
+            edits.push((Selection::region(start, end), lines.join(line_ending)));
+        }
+        if edits.is_empty() {
+            return vec![];
+        }
+
+        // the cursor moves with the lines, on the same line and column
+        let offsets = match &cursor.mode {
+            CursorMode::Normal(offset) => vec![*offset],
+            CursorMode::Visual { start, end, .. } => vec![*start, *end],
+            CursorMode::Insert(selection) => selection
+                .regions()
+                .iter()
+                .flat_map(|region| [region.start, region.end])
+                .collect(),
+        };
+        let positions = offsets
+            .into_iter()
+            .map(|offset| {
+                let line = buffer.line_of_offset(offset);
+                let col = offset - buffer.offset_of_line(line);
+                match moves.get(&line) {
+                    Some((new_line, shift)) => {
+                        (*new_line, (col as isize + shift).max(0) as usize)
+                    }
+                    None => (line, col),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let edits = edits
+            .iter()
+            .map(|(sel, content)| (sel, content.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines) = buffer.edit(&edits, EditType::MoveLine);
+
+        let offsets = positions
+            .into_iter()
+            .map(|(line, col)| {
+                buffer.offset_of_line(line)
+                    + col.min(Self::line_text(buffer, line).len())
+            })
+            .collect::<Vec<_>>();
+        cursor.mode = match &cursor.mode {
+            CursorMode::Normal(_) => CursorMode::Normal(offsets[0]),
+            CursorMode::Visual { mode, .. } => CursorMode::Visual {
+                start: offsets[0],
+                end: offsets[1],
+                mode: *mode,
+            },
+            CursorMode::Insert(_) => {
+                let mut selection = Selection::new();
+                for region in offsets.chunks(2) {
+                    selection.add_region(SelRegion::new(region[0], region[1], None));
+                }
+                CursorMode::Insert(selection)
+            }
+        };
+        vec![(delta, inval_lines)]
+    }
+
+    /// Put a copy of each selection right after it and select the copy, or
+    /// duplicate the line of each caret
+    fn duplicate_selection(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        let selection = match &cursor.mode {
+            CursorMode::Normal(offset) => Selection::caret(*offset),
+            CursorMode::Insert(selection) => selection.clone(),
+            CursorMode::Visual { .. } => cursor.edit_selection(buffer),
+        };
+        let linewise = matches!(
+            cursor.mode,
+            CursorMode::Visual {
+                mode: VisualMode::Linewise,
+                ..
+            }
+        );
+
+        let line_ending = buffer.line_ending().as_str();
+        let mut edits = Vec::new();
+        let mut new_selection = Selection::new();
+        let mut last_edit = 0;
+        // the length of the text inserted before the current region
+        let mut inserted = 0;
+        for region in selection.regions() {
+            if region.is_caret() {
+                let line = buffer.line_of_offset(region.start);
+                let line_start = buffer.offset_of_line(line);
+                // several carets on a line duplicate it once
+                if line_start >= last_edit {
+                    let text =
+                        format!("{}{line_ending}", Self::line_text(buffer, line));
+                    inserted += text.len();
+                    last_edit = line_start + 1;
+                    edits.push((Selection::caret(line_start), text));
+                }
+                new_selection.add_region(SelRegion::caret(region.start + inserted));
+            } else if linewise {
+                // the copy of whole lines goes above them, so that the last
+                // line gets a line ending even when the buffer has none
+                let (start, end) = (region.min(), region.max());
+                let mut text = buffer.slice_to_cow(start..end).to_string();
+                if !text.ends_with('\n') {
+                    text.push_str(line_ending);
+                }
+                let len = text.len();
+                last_edit = end;
+                edits.push((Selection::caret(start), text));
+                new_selection.add_region(SelRegion::new(
+                    start + inserted + len,
+                    end + inserted + len,
+                    None,
+                ));
+                inserted += len;
+            } else {
+                let (start, end) = (region.min(), region.max());
+                let text = buffer.slice_to_cow(start..end).to_string();
+                let len = text.len();
+                last_edit = end;
+                edits.push((Selection::caret(end), text));
+                let (start, end) = (start + inserted + len, end + inserted + len);
+                new_selection.add_region(if region.start < region.end {
+                    SelRegion::new(start, end, None)
+                } else {
+                    SelRegion::new(end, start, None)
+                });
+                inserted += len;
+            }
+        }
+
+        let edits = edits
+            .iter()
+            .map(|(sel, content)| (sel, content.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines) = buffer.edit(&edits, EditType::InsertChars);
+        cursor.mode = match &cursor.mode {
+            CursorMode::Normal(_) => {
+                CursorMode::Normal(new_selection.get_cursor_offset())
+            }
+            CursorMode::Visual { start, end, mode } => {
+                let first = new_selection.min_offset();
+                // the end of a visual selection is on its last character
+                let last = buffer
+                    .prev_grapheme_offset(new_selection.max_offset(), 1, 0)
+                    .max(first);
+                let (start, end) = if start <= end {
+                    (first, last)
+                } else {
+                    (last, first)
+                };
+                CursorMode::Visual {
+                    start,
+                    end,
+                    mode: *mode,
+                }
+            }
+            CursorMode::Insert(_) => CursorMode::Insert(new_selection),
+        };
+        vec![(delta, inval_lines)]
+    }
+
+    /// The selection the transformation commands work on, which is the whole
+    /// buffer when nothing is selected
+    fn transform_selection(cursor: &Cursor, buffer: &Buffer) -> Selection {
+        let whole = Selection::region(0, buffer.len());
+        match &cursor.mode {
+            CursorMode::Normal(_) => whole,
+            CursorMode::Visual { .. } => cursor.edit_selection(buffer),
+            CursorMode::Insert(selection) => {
+                if selection.is_caret() {
+                    whole
+                } else {
+                    selection.clone()
+                }
+            }
+        }
+    }
+
+    /// Replace each range of the buffer with its transformation, editing only
+    /// the part that changed so that the cursors around it stay where they are
+    fn transform_ranges(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        ranges: Vec<(usize, usize)>,
+        transform: impl Fn(&str) -> String,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        let mut edits = vec![];
+        for (start, end) in ranges {
+            let old = buffer.slice_to_cow(start..end);
+            let new = transform(&old);
+            let (old_range, new_range) = line_diff_ranges(&old, &new);
+            if old_range.is_empty() && new_range.is_empty() {
+                continue;
+            }
+            edits.push((
+                Selection::region(start + old_range.start, start + old_range.end),
+                new[new_range].to_string(),
+            ));
+        }
+        if edits.is_empty() {
+            return vec![];
+        }
+
+        let edits = edits
+            .iter()
+            .map(|(sel, content)| (sel, content.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines) = buffer.edit(&edits, EditType::Other);
+        cursor.apply_delta(&delta);
+        vec![(delta, inval_lines)]
+    }
+
+    fn transform_lines(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        transform: LineTransform,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        let selection = Self::transform_selection(cursor, buffer);
+        let mut line_ranges: Vec<(usize, usize)> = Vec::new();
+        for region in selection.regions() {
+            let start_line = buffer.line_of_offset(region.min());
+            let mut end_line = buffer.line_of_offset(region.max());
+            // a selection ending at the start of a line doesn't include it
+            if end_line > start_line
+                && region.max() == buffer.offset_of_line(end_line)
+            {
+                end_line -= 1;
+            }
+            match line_ranges.last_mut() {
+                Some((_, last_end)) if start_line <= *last_end => {
+                    *last_end = (*last_end).max(end_line);
+                }
+                _ => line_ranges.push((start_line, end_line)),
+            }
+        }
+
+        let ranges = line_ranges
+            .into_iter()
+            .map(|(start_line, end_line)| {
+                (
+                    buffer.offset_of_line(start_line),
+                    buffer.line_end_offset(end_line, true),
+                )
+            })
+            .collect();
+        Self::transform_ranges(cursor, buffer, ranges, |text| {
+            let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+            let lines = text
+                .split('\n')
+                .map(|line| line.strip_suffix('\r').unwrap_or(line))
+                .collect();
+            transform.apply(lines).join(line_ending)
+        })
+    }
+
+    fn transform_case(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        transform: CaseTransform,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        let ranges = Self::transform_selection(cursor, buffer)
+            .regions()
+            .iter()
+            .filter(|region| !region.is_caret())
+            .map(|region| (region.min(), region.max()))
+            .collect();
+        Self::transform_ranges(cursor, buffer, ranges, |text| transform.apply(text))
+    }
+
+    /// The content of the line without its line ending
+    fn line_text(buffer: &Buffer, line: usize) -> String {
+        let content = buffer.line_content(line);
+        content
+            .trim_end_matches(|c| c == '\r' || c == '\n')
+            .to_string()
+    }
+
+    /// Join each line of the selections with the next one, or the line of
+    /// the cursor with the next one when nothing is selected
+    fn join_lines(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        syntax: Option<&Syntax>,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        let comment_token =
+            syntax.map(|s| s.language.comment_token()).unwrap_or("//");
+        let last_line = buffer.last_line();
+        let mut lines = BTreeSet::new();
+        for region in cursor.edit_selection(buffer).regions() {
+            let start_line = buffer.line_of_offset(region.min());
+            let mut end_line = buffer.line_of_offset(region.max());
+            // a selection ending at the start of a line doesn't include it
+            if end_line > start_line
+                && region.max() == buffer.offset_of_line(end_line)
+            {
+                end_line -= 1;
+            }
+            lines.extend(
+                (start_line..end_line.max(start_line + 1))
+                    .filter(|line| *line < last_line),
+            );
+        }
+
+        let mut edits = Vec::new();
+        let mut last_end = 0;
+        for line in lines {
+            let (kept, dropped, separator) = transform::join_lines(
+                &Self::line_text(buffer, line),
+                &Self::line_text(buffer, line + 1),
+                comment_token,
+            );
+            // a blank line joined with both of its neighbours is dropped by
+            // the first join
+            let start = (buffer.offset_of_line(line) + kept).max(last_end);
+            let end = buffer.offset_of_line(line + 1) + dropped;
+            last_end = end;
+            edits.push((Selection::region(start, end), separator));
+        }
+        if edits.is_empty() {
+            return vec![];
+        }
+
+        let edits = edits
+            .iter()
+            .map(|(sel, content)| (sel, *content))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines) = buffer.edit(&edits, EditType::Other);
+        cursor.apply_delta(&delta);
+        vec![(delta, inval_lines)]
+    }
+
+    /// The selection covering all the lines of each region, without the line
+    /// ending of the last line
+    fn lines_selection(buffer: &Buffer, selection: &Selection) -> Selection {
+        let mut lines = Selection::new();
+        for region in selection.regions() {
+            let start_line = buffer.line_of_offset(region.min());
+            let mut end_line = buffer.line_of_offset(region.max());
+            if end_line > start_line
+                && region.max() == buffer.offset_of_line(end_line)
+            {
+                end_line -= 1;
+            }
+            let end = buffer.offset_of_line(end_line)
+                + Self::line_text(buffer, end_line).len();
+            lines.add_region(SelRegion::new(
+                buffer.offset_of_line(start_line),
+                end,
+                None,
+            ));
+        }
+        lines
+    }
+
+    /// Put each region between the block comment tokens, or remove the tokens
+    /// when the region is already a block comment. A caret comments its line.
+    fn toggle_block_comment(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        selection: &Selection,
+        (start_token, end_token): (&str, &str),
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        let mut ranges = BTreeSet::new();
+        for region in selection.regions() {
+            let (start, end) = if region.is_caret() {
+                let line = buffer.line_of_offset(region.start);
+                let start = buffer.offset_of_line(line);
+                (start, start + Self::line_text(buffer, line).len())
+            } else {
+                (region.min(), region.max())
+            };
+            let text = buffer.slice_to_cow(start..end);
+            let content = text.trim();
+            if !content.is_empty() {
+                let start = start + text.len() - text.trim_start().len();
+                ranges.insert((start, start + content.len()));
+            }
+        }
+
+        let mut edits = Vec::new();
+        let mut last_end = 0;
+        for (start, end) in ranges {
+            // a line with several carets is only commented once
+            if start < last_end {
+                continue;
+            }
+            last_end = end;
+
+            let content = buffer.slice_to_cow(start..end);
+            let is_comment = content.len() >= start_token.len() + end_token.len()
+                && content.starts_with(start_token)
+                && content.ends_with(end_token);
+            if is_comment {
+                let inner =
+                    &content[start_token.len()..content.len() - end_token.len()];
+                let leading_space = usize::from(inner.starts_with(' '));
+                let trailing_space =
+                    usize::from(inner.len() > 1 && inner.ends_with(' '));
+                edits.push((
+                    Selection::region(
+                        start,
+                        start + start_token.len() + leading_space,
+                    ),
+                    String::new(),
+                ));
+                edits.push((
+                    Selection::region(end - end_token.len() - trailing_space, end),
+                    String::new(),
+                ));
+            } else {
+                edits.push((Selection::caret(start), format!("{start_token} ")));
+                edits.push((Selection::caret(end), format!(" {end_token}")));
+            }
+        }
+        if edits.is_empty() {
+            return vec![];
+        }
+
+        let edits = edits
+            .iter()
+            .map(|(sel, content)| (sel, content.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines) = buffer.edit(&edits, EditType::ToggleComment);
+        cursor.apply_delta(&delta);
+        vec![(delta, inval_lines)]
+    }
+
+    /// Apply the swaps of text, which keep the length of the text, around
+    /// each caret, moving the caret to the given offset. Selections that
+    /// aren't carets are left alone.
+    fn transpose(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        swap: impl Fn(&Buffer, usize) -> Option<(usize, usize, String, usize)>,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        let offsets = match &cursor.mode {
+            CursorMode::Normal(offset) => vec![(*offset, *offset)],
+            CursorMode::Insert(selection) => selection
+                .regions()
+                .iter()
+                .map(|region| (region.start, region.end))
+                .collect(),
+            CursorMode::Visual { .. } => return vec![],
+        };
+
+        let mut edits = Vec::new();
+        let mut regions = Vec::new();
+        let mut last_end = 0;
+        for (start, end) in offsets {
+            if start != end {
+                regions.push(SelRegion::new(start, end, None));
+                continue;
+            }
+            match swap(buffer, end) {
+                Some((swap_start, swap_end, text, caret))
+                    if swap_start >= last_end =>
+                {
+                    edits.push((Selection::region(swap_start, swap_end), text));
+                    regions.push(SelRegion::caret(caret));
+                    last_end = swap_end;
+                }
+                _ => regions.push(SelRegion::caret(end)),
+            }
+        }
+        if edits.is_empty() {
+            return vec![];
+        }
+
+        let edits = edits
+            .iter()
+            .map(|(sel, content)| (sel, content.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines) = buffer.edit(&edits, EditType::Other);
+        match &cursor.mode {
+            CursorMode::Normal(_) => {
+                let offset = regions[0].end;
+                let line = buffer.line_of_offset(offset);
+                cursor.mode = CursorMode::Normal(
+                    offset.min(buffer.line_end_offset(line, false)),
+                );
+            }
+            _ => {
+                let mut selection = Selection::new();
+                for region in regions {
+                    selection.add_region(region);
+                }
+                cursor.mode = CursorMode::Insert(selection);
+            }
+        }
+        vec![(delta, inval_lines)]
+    }
+
+    /// Swap the characters before and after each caret, or the two before it
+    /// at the end of a line, and move the caret past them
+    fn transpose_characters(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        Self::transpose(cursor, buffer, |buffer, offset| {
+            let line = buffer.line_of_offset(offset);
+            let line_start = buffer.offset_of_line(line);
+            let line_end = buffer.line_end_offset(line, true);
+            let middle = if offset >= line_end {
+                buffer.prev_grapheme_offset(line_end, 1, line_start)
+            } else {
+                offset
+            };
+            let start = buffer.prev_grapheme_offset(middle, 1, line_start);
+            let end = buffer.next_grapheme_offset(middle, 1, line_end);
+            if start == middle || middle == end {
+                return None;
+            }
+            let text = format!(
+                "{}{}",
+                buffer.slice_to_cow(middle..end),
+                buffer.slice_to_cow(start..middle)
+            );
+            Some((start, end, text, end))
+        })
+    }
+
+    /// Swap the word around or before each caret with the word after it,
+    /// and move the caret past them
+    fn transpose_words(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        Self::transpose(cursor, buffer, |buffer, offset| {
+            let line = buffer.line_of_offset(offset);
+            let line_start = buffer.offset_of_line(line);
+            let text = Self::line_text(buffer, line);
+            let (first, second) =
+                transform::transpose_words(&text, offset - line_start)?;
+            let swapped = format!(
+                "{}{}{}",
+                &text[second.clone()],
+                &text[first.end..second.start],
+                &text[first.clone()]
+            );
+            Some((
+                line_start + first.start,
+                line_start + second.end,
+                swapped,
+                line_start + second.end,
+            ))
+        })
+    }
+
+    /// Put the items in the brackets around each cursor on lines of their own
+    fn split_arguments(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        let offsets = match &cursor.mode {
+            CursorMode::Normal(offset) => vec![*offset],
+            CursorMode::Insert(selection) => selection
+                .regions()
+                .iter()
+                .map(|region| region.end)
+                .collect(),
+            CursorMode::Visual { .. } => vec![cursor.offset()],
+        };
+
+        let mut edits = Vec::new();
+        let mut last_line = None;
+        for offset in offsets {
+            let line = buffer.line_of_offset(offset);
+            if last_line == Some(line) {
+                continue;
+            }
+            last_line = Some(line);
+            let line_start = buffer.offset_of_line(line);
+            if let Some((range, text)) = transform::split_arguments(
+                &Self::line_text(buffer, line),
+                offset - line_start,
+                buffer.indent_unit(),
+                buffer.line_ending().as_str(),
+            ) {
+                edits.push((
+                    Selection::region(
+                        line_start + range.start,
+                        line_start + range.end,
+                    ),
+                    text,
+                ));
+            }
+        }
+        if edits.is_empty() {
+            return vec![];
+        }
+
+        let edits = edits
+            .iter()
+            .map(|(sel, content)| (sel, content.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines) = buffer.edit(&edits, EditType::Other);
+        cursor.apply_delta(&delta);
         vec![(delta, inval_lines)]
     }
 
@@ -671,6 +1511,9 @@ pub fn do_edit<T: Clipboard>(
         modal: bool,
         register: &mut Register,
     ) -> Vec<(RopeDelta, InvalLines)> {
+        if buffer.is_read_only() && cmd.changes_text() {
+            return vec![];
+        }
         use crate::command::EditCommand::*;
         match cmd {
             MoveLineUp => {
@@ -800,20 +1643,10 @@ pub fn do_edit<T: Clipboard>(
                 cursor.apply_delta(&delta);
                 vec![(delta, inval_lines)]
             }
-            JoinLines => {
-                let offset = cursor.offset();
-                let (line, _col) = buffer.offset_to_line_col(offset);
-                if line < buffer.last_line() {
-                    let start = buffer.line_end_offset(line, true);
-                    let end = buffer.first_non_blank_character_on_line(line + 1);
-                    vec![buffer.edit(
-                        &[(&Selection::region(start, end), " ")],
-                        EditType::Other,
-                    )]
-                } else {
-                    vec![]
-                }
-            }
+            JoinLines => Self::join_lines(cursor, buffer, syntax),
+            TransposeCharacters => Self::transpose_characters(cursor, buffer),
+            TransposeWords => Self::transpose_words(cursor, buffer),
+            SplitArguments => Self::split_arguments(cursor, buffer),
             OutdentLine => {
                 let selection = cursor.edit_selection(buffer);
                 let (delta, inval_lines) = Self::do_outdent(buffer, selection);
@@ -823,8 +1656,23 @@ pub fn do_edit<T: Clipboard>(
             ToggleLineComment => {
                 let mut lines = HashSet::new();
                 let selection = cursor.edit_selection(buffer);
+                let language = syntax.map(|s| s.language_at(selection.min_offset()));
                 let comment_token =
-                    syntax.map(|s| s.language.comment_token()).unwrap_or("//");
+                    language.map(|l| l.comment_token()).unwrap_or("//");
+                if comment_token.is_empty() {
+                    // languages like html only have block comments, which
+                    // are put around the whole lines instead
+                    return match language.and_then(|l| l.block_comment_tokens()) {
+                        Some(tokens) => {
+                            let selection =
+                                Self::lines_selection(buffer, &selection);
+                            Self::toggle_block_comment(
+                                cursor, buffer, &selection, tokens,
+                            )
+                        }
+                        None => vec![],
+                    };
+                }
                 let mut had_comment = true;
                 let mut smallest_indent = usize::MAX;
                 for region in selection.regions() {
@@ -890,25 +1738,31 @@ pub fn do_edit<T: Clipboard>(
                 cursor.apply_delta(&delta);
                 vec![(delta, inval_lines)]
             }
+            ToggleBlockComment => {
+                let selection = cursor.edit_selection(buffer);
+                let tokens = match syntax {
+                    Some(syntax) => {
+                        match syntax
+                            .language_at(selection.min_offset())
+                            .block_comment_tokens()
+                        {
+                            Some(tokens) => tokens,
+                            None => return vec![],
+                        }
+                    }
+                    None => ("/*", "*/"),
+                };
+                Self::toggle_block_comment(cursor, buffer, &selection, tokens)
+            }
             Undo => {
                 if let Some((delta, inval_lines, cursor_mode)) = buffer.do_undo() {
-                    if let Some(cursor_mode) = cursor_mode {
-                        cursor.mode = if modal {
-                            CursorMode::Normal(cursor_mode.offset())
-                        } else if cursor.is_insert() {
-                            cursor_mode
-                        } else {
-                            CursorMode::Insert(Selection::caret(
-                                cursor_mode.offset(),
-                            ))
-                        };
-                    } else if let Some(new_cursor) =
-                        get_first_selection_after(cursor, buffer, &delta)
-                    {
-                        *cursor = new_cursor
-                    } else {
-                        cursor.apply_delta(&delta);
-                    }
+                    Self::set_undo_cursor(
+                        cursor,
+                        buffer,
+                        &delta,
+                        cursor_mode,
+                        modal,
+                    );
                     vec![(delta, inval_lines)]
                 } else {
                     vec![]
@@ -916,23 +1770,13 @@ pub fn do_edit<T: Clipboard>(
             }
             Redo => {
                 if let Some((delta, inval_lines, cursor_mode)) = buffer.do_redo() {
-                    if let Some(cursor_mode) = cursor_mode {
-                        cursor.mode = if modal {
-                            CursorMode::Normal(cursor_mode.offset())
-                        } else if cursor.is_insert() {
-                            cursor_mode
-                        } else {
-                            CursorMode::Insert(Selection::caret(
-                                cursor_mode.offset(),
-                            ))
-                        };
-                    } else if let Some(new_cursor) =
-                        get_first_selection_after(cursor, buffer, &delta)
-                    {
-                        *cursor = new_cursor
-                    } else {
-                        cursor.apply_delta(&delta);
-                    }
+                    Self::set_undo_cursor(
+                        cursor,
+                        buffer,
+                        &delta,
+                        cursor_mode,
+                        modal,
+                    );
                     vec![(delta, inval_lines)]
                 } else {
                     vec![]
@@ -1185,7 +2029,7 @@ pub fn do_edit<T: Clipboard>(
                 cursor.update_selection(buffer, selection);
                 vec![(delta, inval_lines)]
             }
-            DeleteWordForward => {
+            DeleteWordForward | DeleteSubwordForward => {
                 let selection = match cursor.mode {
                     CursorMode::Normal(_) | CursorMode::Visual { .. } => {
                         cursor.edit_selection(buffer)
@@ -1195,7 +2039,11 @@ pub fn do_edit<T: Clipboard>(
                         let selection = cursor.edit_selection(buffer);
 
                         for region in selection.regions() {
-                            let end = buffer.move_word_forward(region.end);
+                            let end = if *cmd == DeleteSubwordForward {
+                                buffer.move_n_subwords_forward(region.end, 1)
+                            } else {
+                                buffer.move_word_forward(region.end)
+                            };
                             let new_region = SelRegion::new(region.start, end, None);
                             new_selection.add_region(new_region);
                         }
@@ -1210,7 +2058,7 @@ pub fn do_edit<T: Clipboard>(
                 cursor.update_selection(buffer, selection);
                 vec![(delta, inval_lines)]
             }
-            DeleteWordBackward => {
+            DeleteWordBackward | DeleteSubwordBackward => {
                 let selection = match cursor.mode {
                     CursorMode::Normal(_) | CursorMode::Visual { .. } => {
                         cursor.edit_selection(buffer)
@@ -1220,7 +2068,11 @@ pub fn do_edit<T: Clipboard>(
                         let selection = cursor.edit_selection(buffer);
 
                         for region in selection.regions() {
-                            let end = buffer.move_word_backward_deletion(region.end);
+                            let end = if *cmd == DeleteSubwordBackward {
+                                buffer.move_n_subwords_backward(region.end, 1)
+                            } else {
+                                buffer.move_word_backward_deletion(region.end)
+                            };
                             let new_region = SelRegion::new(region.start, end, None);
                             new_selection.add_region(new_region);
                         }
@@ -1246,7 +2098,15 @@ pub fn do_edit<T: Clipboard>(
                         let mut new_selection = Selection::new();
                         for region in selection.regions() {
                             let line = buffer.line_of_offset(region.end);
-                            let end = buffer.offset_of_line(line);
+                            // the indentation is kept unless there is
+                            // nothing else left to delete
+                            let indent_end =
+                                buffer.first_non_blank_character_on_line(line);
+                            let end = if region.end > indent_end {
+                                indent_end
+                            } else {
+                                buffer.offset_of_line(line)
+                            };
                             let new_region = SelRegion::new(region.start, end, None);
                             new_selection.add_region(new_region);
                         }
@@ -1261,6 +2121,41 @@ pub fn do_edit<T: Clipboard>(
                 cursor.update_selection(buffer, selection);
                 vec![(delta, inval_lines)]
             }
+            DeleteWhitespaceBackward => {
+                if !cursor.is_insert() {
+                    return Self::do_edit(
+                        cursor,
+                        buffer,
+                        &DeleteBackward,
+                        syntax,
+                        clipboard,
+                        modal,
+                        register,
+                    );
+                }
+                let selection = cursor.edit_selection(buffer);
+                let mut new_selection = Selection::new();
+                for region in selection.regions() {
+                    let new_region = if region.is_caret() {
+                        let start = Self::whitespace_start(buffer, region.start);
+                        let start = if start < region.start {
+                            start
+                        } else {
+                            buffer.prev_grapheme_offset(region.start, 1, 0)
+                        };
+                        SelRegion::new(region.start, start, None)
+                    } else {
+                        *region
+                    };
+                    new_selection.add_region(new_region);
+                }
+                let (delta, inval_lines) =
+                    buffer.edit(&[(&new_selection, "")], EditType::Delete);
+                let selection =
+                    new_selection.apply_delta(&delta, true, InsertDrift::Default);
+                cursor.update_selection(buffer, selection);
+                vec![(delta, inval_lines)]
+            }
             DeleteForwardAndInsert => {
                 let selection = cursor.edit_selection(buffer);
                 let (delta, inval_lines) =
@@ -1365,12 +2260,88 @@ pub fn do_edit<T: Clipboard>(
                 Self::toggle_visual(cursor, VisualMode::Blockwise, modal);
                 vec![]
             }
+            MoveLinesUp => Self::move_lines(cursor, buffer, true),
+            MoveLinesDown => Self::move_lines(cursor, buffer, false),
+            DuplicateSelection => Self::duplicate_selection(cursor, buffer),
             DuplicateLineUp => {
                 Self::duplicate_line(cursor, buffer, DuplicateDirection::Up)
             }
             DuplicateLineDown => {
                 Self::duplicate_line(cursor, buffer, DuplicateDirection::Down)
             }
+            SortLinesAscending => {
+                Self::transform_lines(cursor, buffer, LineTransform::SortAscending)
+            }
+            SortLinesDescending => {
+                Self::transform_lines(cursor, buffer, LineTransform::SortDescending)
+            }
+            SortLinesNumerically => {
+                Self::transform_lines(cursor, buffer, LineTransform::SortNumerically)
+            }
+            DeleteDuplicateLines => Self::transform_lines(
+                cursor,
+                buffer,
+                LineTransform::DeleteDuplicates,
+            ),
+            ReverseLines => {
+                Self::transform_lines(cursor, buffer, LineTransform::Reverse)
+            }
+            TransformToUppercase => {
+                Self::transform_case(cursor, buffer, CaseTransform::Upper)
+            }
+            TransformToLowercase => {
+                Self::transform_case(cursor, buffer, CaseTransform::Lower)
+            }
+            TransformToTitleCase => {
+                Self::transform_case(cursor, buffer, CaseTransform::Title)
+            }
+            TransformToSnakeCase => {
+                Self::transform_case(cursor, buffer, CaseTransform::Snake)
+            }
+            TransformToCamelCase => {
+                Self::transform_case(cursor, buffer, CaseTransform::Camel)
+            }
+        }
+    }
+
+    /// Move the buffer to the state of `group` in its undo tree
+    pub fn jump_to_undo_group(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        group: usize,
+        modal: bool,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        if let Some((delta, inval_lines, cursor_mode)) =
+            buffer.jump_to_undo_group(group)
+        {
+            Self::set_undo_cursor(cursor, buffer, &delta, cursor_mode, modal);
+            vec![(delta, inval_lines)]
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_undo_cursor(
+        cursor: &mut Cursor,
+        buffer: &Buffer,
+        delta: &RopeDelta,
+        cursor_mode: Option<CursorMode>,
+        modal: bool,
+    ) {
+        if let Some(cursor_mode) = cursor_mode {
+            cursor.mode = if modal {
+                CursorMode::Normal(cursor_mode.offset())
+            } else if cursor.is_insert() {
+                cursor_mode
+            } else {
+                CursorMode::Insert(Selection::caret(cursor_mode.offset()))
+            };
+        } else if let Some(new_cursor) =
+            get_first_selection_after(cursor, buffer, delta)
+        {
+            *cursor = new_cursor
+        } else {
+            cursor.apply_delta(delta);
         }
     }
 }
@@ -1383,9 +2354,13 @@ enum DuplicateDirection {
 #[cfg(test)]
 mod test {
     use crate::buffer::Buffer;
+    use crate::command::EditCommand;
     use crate::cursor::{Cursor, CursorMode};
     use crate::editor::{DuplicateDirection, Editor};
+    use crate::mode::VisualMode;
+    use crate::register::{Clipboard, Register, RegisterData};
     use crate::selection::{SelRegion, Selection};
+    use crate::transform::{CaseTransform, LineTransform};
 
     #[test]
     fn test_insert_simple() {
@@ -1455,6 +2430,50 @@ fn test_insert_pair_without_auto_closing() {
         assert_eq!("a{} bc\ne{} fg\n", buffer.slice_to_cow(0..buffer.len()));
     }
 
+    #[test]
+    fn test_multi_cursor_paste() {
+        let mut buffer = Buffer::new("ab\ncd\nef");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::caret(1));
+        selection.add_region(SelRegion::caret(4));
+        selection.add_region(SelRegion::caret(7));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+        let data = RegisterData {
+            content: "1\n2\n3".to_string(),
+            mode: VisualMode::Normal,
+        };
+
+        Editor::do_paste(&mut cursor, &mut buffer, &data);
+        assert_eq!("a1b\nc2d\ne3f", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.edit_selection(&buffer).len(), 3);
+    }
+
+    #[test]
+    fn test_block_paste() {
+        let mut buffer = Buffer::new("abc\nd\nefg");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(1)), None, None);
+        let data = RegisterData {
+            content: "X\nY\nZ\nW\n".to_string(),
+            mode: VisualMode::Blockwise,
+        };
+
+        Editor::do_paste(&mut cursor, &mut buffer, &data);
+        assert_eq!("aXbc\ndY\neZfg\n W", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 2);
+
+        let mut buffer = Buffer::new("abc\n\nabc");
+        let mut cursor = Cursor::new(CursorMode::Normal(1), None, None);
+        let data = RegisterData {
+            content: "X\nY\n".to_string(),
+            mode: VisualMode::Blockwise,
+        };
+
+        Editor::do_paste(&mut cursor, &mut buffer, &data);
+        assert_eq!("abXc\n  Y\nabc", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 2);
+    }
+
     #[test]
     fn duplicate_down_simple() {
         let mut buffer = Buffer::new("first line\nsecond line\n");
@@ -1584,4 +2603,346 @@ fn duplicate_up_multiple_with_swapped_cursor_order() {
     }
 
     // TODO(dbuga): add tests duplicating selections (multiple line blocks)
+
+    #[test]
+    fn sort_lines_whole_buffer() {
+        let mut buffer = Buffer::new("c\na\nb\n");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(2)), None, None);
+
+        Editor::transform_lines(
+            &mut cursor,
+            &mut buffer,
+            LineTransform::SortAscending,
+        );
+
+        assert_eq!("a\nb\nc\n", buffer.slice_to_cow(0..buffer.len()));
+    }
+
+    #[test]
+    fn reverse_lines_of_each_selection() {
+        let mut buffer = Buffer::new("1\n2\n3\n4\n5\n6\n");
+        let mut selection = Selection::new();
+        // the second line isn't included as the selection ends at its start
+        selection.add_region(SelRegion::new(0, 2, None));
+        selection.add_region(SelRegion::new(4, 9, None));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+
+        Editor::transform_lines(&mut cursor, &mut buffer, LineTransform::Reverse);
+
+        assert_eq!("1\n2\n5\n4\n3\n6\n", buffer.slice_to_cow(0..buffer.len()));
+    }
+
+    #[test]
+    fn transform_case_of_selections() {
+        let mut buffer = Buffer::new("fooBar bazQux");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::new(0, 6, None));
+        selection.add_region(SelRegion::caret(10));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+
+        Editor::transform_case(&mut cursor, &mut buffer, CaseTransform::Snake);
+
+        assert_eq!("foo_bar bazQux", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 11);
+    }
+
+    #[test]
+    fn join_lines_of_selection() {
+        let mut buffer = Buffer::new("// a\n  // b\nfoo(\n    x\n)\n");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::new(0, 6, None));
+        selection.add_region(SelRegion::new(14, 24, None));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+
+        Editor::join_lines(&mut cursor, &mut buffer, None);
+
+        assert_eq!("// a b\nfoo(x)\n", buffer.slice_to_cow(0..buffer.len()));
+    }
+
+    struct TestClipboard;
+
+    impl Clipboard for TestClipboard {
+        fn get_string(&self) -> Option<String> {
+            None
+        }
+
+        fn put_string(&mut self, _s: impl AsRef<str>) {}
+    }
+
+    fn do_edit(cursor: &mut Cursor, buffer: &mut Buffer, cmd: EditCommand) {
+        Editor::do_edit(
+            cursor,
+            buffer,
+            &cmd,
+            None,
+            &mut TestClipboard,
+            false,
+            &mut Register::default(),
+        );
+    }
+
+    #[test]
+    fn read_only_buffer_rejects_edits() {
+        let mut buffer = Buffer::new("abc");
+        buffer.set_read_only(true);
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(1)), None, None);
+
+        assert!(Editor::insert(&mut cursor, &mut buffer, "e", None, true).is_empty());
+        do_edit(&mut cursor, &mut buffer, EditCommand::DeleteBackward);
+        assert_eq!("abc", buffer.slice_to_cow(0..buffer.len()));
+
+        buffer.set_read_only(false);
+        do_edit(&mut cursor, &mut buffer, EditCommand::DeleteBackward);
+        assert_eq!("bc", buffer.slice_to_cow(0..buffer.len()));
+    }
+
+    #[test]
+    fn delete_whitespace_backward() {
+        let mut buffer = Buffer::new("foo  \n\n    bar");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(11)), None, None);
+
+        do_edit(
+            &mut cursor,
+            &mut buffer,
+            EditCommand::DeleteWhitespaceBackward,
+        );
+        assert_eq!("foobar", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 3);
+
+        // without whitespace before the cursor a single character is deleted
+        do_edit(
+            &mut cursor,
+            &mut buffer,
+            EditCommand::DeleteWhitespaceBackward,
+        );
+        assert_eq!("fobar", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 2);
+    }
+
+    #[test]
+    fn delete_to_beginning_of_line_keeps_indent() {
+        let mut buffer = Buffer::new("    foo");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(7)), None, None);
+
+        do_edit(
+            &mut cursor,
+            &mut buffer,
+            EditCommand::DeleteToBeginningOfLine,
+        );
+        assert_eq!("    ", buffer.slice_to_cow(0..buffer.len()));
+
+        do_edit(
+            &mut cursor,
+            &mut buffer,
+            EditCommand::DeleteToBeginningOfLine,
+        );
+        assert_eq!("", buffer.slice_to_cow(0..buffer.len()));
+    }
+
+    #[test]
+    fn move_lines_into_and_out_of_block() {
+        let mut buffer = Buffer::new("fn a() {\n    b();\n}\nc();\n");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(20)), None, None);
+
+        Editor::move_lines(&mut cursor, &mut buffer, true);
+        assert_eq!(
+            "fn a() {\n    b();\n    c();\n}\n",
+            buffer.slice_to_cow(0..buffer.len())
+        );
+        assert_eq!(cursor.offset(), 22);
+
+        Editor::move_lines(&mut cursor, &mut buffer, true);
+        Editor::move_lines(&mut cursor, &mut buffer, true);
+        assert_eq!(
+            "c();\nfn a() {\n    b();\n}\n",
+            buffer.slice_to_cow(0..buffer.len())
+        );
+        assert_eq!(cursor.offset(), 0);
+    }
+
+    #[test]
+    fn move_lines_keeps_regions_apart() {
+        let mut buffer = Buffer::new("a\nb\nc\nd\n");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::caret(2));
+        selection.add_region(SelRegion::caret(6));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+
+        Editor::move_lines(&mut cursor, &mut buffer, false);
+
+        // the last line can't move down, but the other one still does
+        assert_eq!("a\nc\nb\nd\n", buffer.slice_to_cow(0..buffer.len()));
+        let offsets = match &cursor.mode {
+            CursorMode::Insert(selection) => selection
+                .regions()
+                .iter()
+                .map(|region| region.start)
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        };
+        assert_eq!(offsets, vec![4, 6]);
+    }
+
+    #[test]
+    fn duplicate_selection_and_caret_line() {
+        let mut buffer = Buffer::new("foo bar\nbaz\n");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::new(0, 3, None));
+        selection.add_region(SelRegion::caret(9));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+
+        Editor::duplicate_selection(&mut cursor, &mut buffer);
+
+        assert_eq!(
+            "foofoo bar\nbaz\nbaz\n",
+            buffer.slice_to_cow(0..buffer.len())
+        );
+        let regions = match &cursor.mode {
+            CursorMode::Insert(selection) => selection
+                .regions()
+                .iter()
+                .map(|region| (region.start, region.end))
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        };
+        assert_eq!(regions, vec![(3, 6), (16, 16)]);
+    }
+
+    #[test]
+    fn duplicate_visual_selection() {
+        let mut buffer = Buffer::new("foo bar\nbaz");
+        let mut cursor = Cursor::new(
+            CursorMode::Visual {
+                start: 4,
+                end: 9,
+                mode: VisualMode::Linewise,
+            },
+            None,
+            None,
+        );
+
+        Editor::duplicate_selection(&mut cursor, &mut buffer);
+
+        assert_eq!(
+            "foo bar\nbaz\nfoo bar\nbaz",
+            buffer.slice_to_cow(0..buffer.len())
+        );
+        assert_eq!(
+            cursor.mode,
+            CursorMode::Visual {
+                start: 12,
+                end: 22,
+                mode: VisualMode::Linewise,
+            }
+        );
+
+        let mut buffer = Buffer::new("foo bar\n");
+        let mut cursor = Cursor::new(
+            CursorMode::Visual {
+                start: 2,
+                end: 0,
+                mode: VisualMode::Normal,
+            },
+            None,
+            None,
+        );
+
+        Editor::duplicate_selection(&mut cursor, &mut buffer);
+
+        assert_eq!("foofoo bar\n", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(
+            cursor.mode,
+            CursorMode::Visual {
+                start: 5,
+                end: 3,
+                mode: VisualMode::Normal,
+            }
+        );
+    }
+
+    #[test]
+    fn toggle_block_comment_of_selection_and_line() {
+        let mut buffer = Buffer::new("foo(a, b);\n  bar\n");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::new(4, 8, None));
+        selection.add_region(SelRegion::caret(13));
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(selection.clone()), None, None);
+
+        Editor::toggle_block_comment(
+            &mut cursor,
+            &mut buffer,
+            &selection,
+            ("/*", "*/"),
+        );
+        assert_eq!(
+            "foo(/* a, b */);\n  /* bar */\n",
+            buffer.slice_to_cow(0..buffer.len())
+        );
+
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::new(4, 14, None));
+        selection.add_region(SelRegion::caret(20));
+        Editor::toggle_block_comment(
+            &mut cursor,
+            &mut buffer,
+            &selection,
+            ("/*", "*/"),
+        );
+        assert_eq!("foo(a, b);\n  bar\n", buffer.slice_to_cow(0..buffer.len()));
+    }
+
+    #[test]
+    fn toggle_block_comment_of_lines() {
+        let mut buffer = Buffer::new("a\n  b\nc\n");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::region(0, 4)), None, None);
+
+        let selection = Editor::lines_selection(&buffer, &Selection::region(0, 4));
+        Editor::toggle_block_comment(
+            &mut cursor,
+            &mut buffer,
+            &selection,
+            ("<!--", "-->"),
+        );
+        assert_eq!("<!-- a\n  b -->\nc\n", buffer.slice_to_cow(0..buffer.len()));
+    }
+
+    #[test]
+    fn transpose_characters_and_words() {
+        let mut buffer = Buffer::new("abc\nfoo bar\n");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::caret(1));
+        // at the end of the line the two characters before the caret swap
+        selection.add_region(SelRegion::caret(11));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+
+        Editor::transpose_characters(&mut cursor, &mut buffer);
+        assert_eq!("bac\nfoo bra\n", buffer.slice_to_cow(0..buffer.len()));
+
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(5)), None, None);
+        Editor::transpose_words(&mut cursor, &mut buffer);
+        assert_eq!("bac\nbra foo\n", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 11);
+    }
+
+    #[test]
+    fn split_arguments_at_cursor() {
+        let mut buffer = Buffer::new("  foo(a, b);\n");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(7)), None, None);
+
+        Editor::split_arguments(&mut cursor, &mut buffer);
+
+        assert_eq!(
+            "  foo(\n      a,\n      b\n  );\n",
+            buffer.slice_to_cow(0..buffer.len())
+        );
+    }
 }
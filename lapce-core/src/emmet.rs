@@ -0,0 +1,978 @@
+use crate::language::LapceLanguage;
+
+/// The kind of markup that Emmet abbreviations are expanded to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmmetSyntax {
+    Html,
+    /// HTML with the attribute names of JSX, like `className`
+    Jsx,
+    Css,
+}
+
+impl EmmetSyntax {
+    pub fn from_language(language: LapceLanguage) -> Option<Self> {
+        match language {
+            #[cfg(feature = "lang-html")]
+            LapceLanguage::Html => Some(EmmetSyntax::Html),
+            #[cfg(feature = "lang-svelte")]
+            LapceLanguage::Svelte => Some(EmmetSyntax::Html),
+            #[cfg(feature = "lang-glimmer")]
+            LapceLanguage::Glimmer => Some(EmmetSyntax::Html),
+            #[cfg(feature = "lang-javascript")]
+            LapceLanguage::Jsx => Some(EmmetSyntax::Jsx),
+            #[cfg(feature = "lang-typescript")]
+            LapceLanguage::Tsx => Some(EmmetSyntax::Jsx),
+            #[cfg(feature = "lang-css")]
+            LapceLanguage::Css => Some(EmmetSyntax::Css),
+            #[cfg(feature = "lang-scss")]
+            LapceLanguage::Scss => Some(EmmetSyntax::Css),
+            _ => None,
+        }
+    }
+}
+
+/// The abbreviation at the end of `line`, which is the text of the line
+/// before the cursor, with the byte offset it starts at in the line.
+/// Markup abbreviations have to start the line or follow a tag, and
+/// stylesheet ones have to start a declaration.
+pub fn abbreviation(syntax: EmmetSyntax, line: &str) -> Option<(usize, &str)> {
+    let start = match syntax {
+        EmmetSyntax::Html | EmmetSyntax::Jsx => markup_start(syntax, line)?,
+        EmmetSyntax::Css => {
+            let start = line
+                .char_indices()
+                .rev()
+                .take_while(|(_, c)| {
+                    c.is_ascii_alphanumeric() || matches!(c, '-' | ':' | '#' | '.')
+                })
+                .last()?
+                .0;
+            let prefix = line[..start].trim_end();
+            if !(prefix.is_empty() || prefix.ends_with('{') || prefix.ends_with(';'))
+            {
+                return None;
+            }
+            start
+        }
+    };
+    let abbreviation = &line[start..];
+    expand(syntax, abbreviation, "", "")?;
+    Some((start, abbreviation))
+}
+
+fn markup_start(syntax: EmmetSyntax, line: &str) -> Option<usize> {
+    // Going backwards, text and attributes can contain anything until the
+    // brace or bracket that opens them
+    let mut closing = None;
+    let mut start = None;
+    for (i, c) in line.char_indices().rev() {
+        if let Some(close) = closing {
+            if (close, c) == ('}', '{') || (close, c) == (']', '[') {
+                closing = None;
+            }
+        } else if c == '}' || c == ']' {
+            closing = Some(c);
+        } else if c.is_ascii_alphanumeric()
+            || matches!(
+                c,
+                '-' | '_'
+                    | ':'
+                    | '!'
+                    | '$'
+                    | '@'
+                    | '#'
+                    | '.'
+                    | '>'
+                    | '+'
+                    | '^'
+                    | '*'
+                    | '('
+                    | ')'
+            )
+        {
+        } else {
+            break;
+        }
+        start = Some(i);
+    }
+    let mut start = start?;
+    if closing.is_some() {
+        return None;
+    }
+
+    // The abbreviation can't start inside of a tag, like `<div>p`, so it
+    // starts after the tag's `>` then
+    loop {
+        let prefix = line[..start].trim_end();
+        if prefix.is_empty()
+            || prefix.ends_with('>')
+            || (syntax == EmmetSyntax::Jsx && prefix.ends_with('('))
+        {
+            break;
+        }
+        start += line[start..].find('>')? + 1;
+    }
+    let start = start + line[start..].len() - line[start..].trim_start().len();
+    if start == line.len() {
+        None
+    } else {
+        Some(start)
+    }
+}
+
+/// Expand the abbreviation to a snippet, whose placeholders are the places
+/// to fill in. The lines after the first are indented with `line_indent`,
+/// and nested lines with `indent` more.
+pub fn expand(
+    syntax: EmmetSyntax,
+    abbreviation: &str,
+    indent: &str,
+    line_indent: &str,
+) -> Option<String> {
+    match syntax {
+        EmmetSyntax::Html | EmmetSyntax::Jsx => {
+            expand_markup(syntax, abbreviation, indent, line_indent)
+        }
+        EmmetSyntax::Css => expand_css(abbreviation),
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Element {
+    name: String,
+    id: Option<String>,
+    classes: Vec<String>,
+    attrs: Vec<(String, Option<String>)>,
+    text: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+enum NodeKind {
+    Element(Element),
+    Group(Vec<Node>),
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    kind: NodeKind,
+    count: usize,
+    children: Vec<Node>,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().map(&f).unwrap_or(false) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// The nodes up to the end of the abbreviation or of the group, where
+    /// climbing up with `^` can't go further
+    fn parse_level(&mut self, in_group: bool) -> Option<Vec<Node>> {
+        let mut nodes = Vec::new();
+        loop {
+            let (level, climbs) = self.parse_sequence(in_group)?;
+            nodes.extend(level);
+            if climbs == 0 {
+                return Some(nodes);
+            }
+        }
+    }
+
+    /// The sibling nodes, and how many levels up the next node is when the
+    /// sequence ended by climbing up
+    fn parse_sequence(&mut self, in_group: bool) -> Option<(Vec<Node>, usize)> {
+        let mut nodes = Vec::new();
+        loop {
+            let mut node = self.parse_term()?;
+            match self.peek() {
+                Some('>') => {
+                    self.pos += 1;
+                    let (children, climbs) = self.parse_sequence(in_group)?;
+                    node.children = children;
+                    nodes.push(node);
+                    match climbs {
+                        0 => return Some((nodes, 0)),
+                        1 => {}
+                        _ => return Some((nodes, climbs - 1)),
+                    }
+                }
+                Some('+') => {
+                    self.pos += 1;
+                    nodes.push(node);
+                }
+                Some('^') => {
+                    let climbs = self.take_while(|c| c == '^').len();
+                    nodes.push(node);
+                    return Some((nodes, climbs));
+                }
+                Some(')') if in_group => {
+                    nodes.push(node);
+                    return Some((nodes, 0));
+                }
+                None => {
+                    nodes.push(node);
+                    return Some((nodes, 0));
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn parse_term(&mut self) -> Option<Node> {
+        let kind = if self.eat('(') {
+            let nodes = self.parse_level(true)?;
+            if !self.eat(')') {
+                return None;
+            }
+            NodeKind::Group(nodes)
+        } else {
+            NodeKind::Element(self.parse_element()?)
+        };
+        let count = if self.eat('*') {
+            self.take_while(|c| c.is_ascii_digit()).parse().ok()?
+        } else {
+            1
+        };
+        Some(Node {
+            kind,
+            count,
+            children: Vec::new(),
+        })
+    }
+
+    fn parse_element(&mut self) -> Option<Element> {
+        let is_word =
+            |c: char| c.is_alphanumeric() || matches!(c, '-' | '_' | '$' | '@');
+        let mut element = Element {
+            name: self.take_while(|c| is_word(c) || c == ':' || c == '!'),
+            ..Default::default()
+        };
+        let mut empty = element.name.is_empty();
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.pos += 1;
+                    element.id = Some(self.take_while(is_word));
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    element.classes.push(self.take_while(is_word));
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    let attrs = self.take_while(|c| c != ']');
+                    if !self.eat(']') {
+                        return None;
+                    }
+                    element.attrs.extend(parse_attributes(&attrs));
+                }
+                Some('{') => {
+                    self.pos += 1;
+                    let text = self.take_while(|c| c != '}');
+                    if !self.eat('}') {
+                        return None;
+                    }
+                    element.text = Some(text);
+                }
+                _ => break,
+            }
+            empty = false;
+        }
+        if empty {
+            None
+        } else {
+            Some(element)
+        }
+    }
+}
+
+/// The attributes in the brackets of an element, like `href=# title="a b"`
+fn parse_attributes(text: &str) -> Vec<(String, Option<String>)> {
+    let mut attrs = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '=') {
+            name.push(c);
+        }
+        if name.is_empty() {
+            return attrs;
+        }
+        let value = if chars.next_if_eq(&'=').is_some() {
+            let mut value = String::new();
+            match chars.next_if(|c| *c == '"' || *c == '\'') {
+                Some(quote) => {
+                    for c in chars.by_ref() {
+                        if c == quote {
+                            break;
+                        }
+                        value.push(c);
+                    }
+                }
+                None => {
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                        value.push(c);
+                    }
+                }
+            }
+            Some(value)
+        } else {
+            None
+        };
+        attrs.push((name, value));
+    }
+}
+
+/// Replace the runs of `$` with the number of the repeated element, padded
+/// with zeros to the length of the run. `$@-` counts down, and `$@3`
+/// starts from 3.
+fn number(text: &str, index: usize, count: usize) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            result.push(c);
+            continue;
+        }
+        let mut width = 1;
+        while chars.next_if_eq(&'$').is_some() {
+            width += 1;
+        }
+        let mut reverse = false;
+        let mut base = 1;
+        if chars.next_if_eq(&'@').is_some() {
+            reverse = chars.next_if_eq(&'-').is_some();
+            let mut digits = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                digits.push(c);
+            }
+            base = digits.parse().unwrap_or(1);
+        }
+        let n = if reverse {
+            base + count - index
+        } else {
+            base + index - 1
+        };
+        result.push_str(&format!("{n:0width$}"));
+    }
+    result
+}
+
+const INLINE_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "br", "button", "cite", "code", "data", "dfn",
+    "em", "i", "img", "input", "kbd", "label", "mark", "q", "s", "samp", "select",
+    "small", "span", "strong", "sub", "sup", "textarea", "time", "u", "var", "wbr",
+];
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+    "source", "track", "wbr",
+];
+
+/// The elements that are expanded from their name alone, so that any other
+/// word isn't taken for an abbreviation
+const KNOWN_ELEMENTS: &[&str] = &[
+    "a",
+    "abbr",
+    "address",
+    "area",
+    "article",
+    "aside",
+    "audio",
+    "b",
+    "base",
+    "blockquote",
+    "body",
+    "br",
+    "button",
+    "canvas",
+    "caption",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "data",
+    "datalist",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "embed",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "i",
+    "iframe",
+    "img",
+    "input",
+    "ins",
+    "kbd",
+    "label",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "mark",
+    "meta",
+    "meter",
+    "nav",
+    "noscript",
+    "object",
+    "ol",
+    "optgroup",
+    "option",
+    "output",
+    "p",
+    "param",
+    "picture",
+    "pre",
+    "progress",
+    "q",
+    "s",
+    "samp",
+    "script",
+    "section",
+    "select",
+    "small",
+    "source",
+    "span",
+    "strong",
+    "style",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "textarea",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "title",
+    "tr",
+    "track",
+    "u",
+    "ul",
+    "var",
+    "video",
+    "wbr",
+];
+
+fn default_attributes(name: &str) -> &'static [&'static str] {
+    match name {
+        "a" => &["href"],
+        "img" => &["src", "alt"],
+        "input" => &["type"],
+        "link" => &["rel", "href"],
+        "form" | "iframe" => &["src"],
+        "label" => &["for"],
+        "script" => &["src"],
+        _ => &[],
+    }
+}
+
+/// The name of an element that has none in the abbreviation, like `.item`
+fn implicit_name(parent: &str) -> &'static str {
+    match parent {
+        "ul" | "ol" => "li",
+        "table" | "tbody" | "thead" | "tfoot" => "tr",
+        "tr" => "td",
+        "select" | "optgroup" => "option",
+        "em" => "span",
+        parent if INLINE_ELEMENTS.contains(&parent) => "span",
+        _ => "div",
+    }
+}
+
+struct Tag {
+    name: String,
+    attrs: Vec<(String, Option<String>)>,
+    text: Option<String>,
+    children: Vec<Tag>,
+}
+
+impl Tag {
+    fn is_inline(&self) -> bool {
+        INLINE_ELEMENTS.contains(&self.name.as_str())
+    }
+
+    /// Whether the children are on their own lines
+    fn is_block(&self) -> bool {
+        self.children
+            .iter()
+            .any(|child| !child.is_inline() || child.is_block())
+    }
+}
+
+/// The elements of the nodes, with the repeated ones numbered
+fn tags(nodes: &[Node], parent: &str, numbering: (usize, usize)) -> Vec<Tag> {
+    let mut tags = Vec::new();
+    for node in nodes {
+        for i in 1..=node.count {
+            let numbering = if node.count > 1 {
+                (i, node.count)
+            } else {
+                numbering
+            };
+            let number = |text: &str| number(text, numbering.0, numbering.1);
+            match &node.kind {
+                NodeKind::Element(element) => {
+                    let name = if element.name.is_empty() {
+                        implicit_name(parent).to_string()
+                    } else {
+                        number(&element.name)
+                    };
+                    let mut attrs = Vec::new();
+                    if let Some(id) = &element.id {
+                        attrs.push(("id".to_string(), Some(number(id))));
+                    }
+                    if !element.classes.is_empty() {
+                        let classes = element
+                            .classes
+                            .iter()
+                            .map(|class| number(class))
+                            .collect::<Vec<_>>();
+                        attrs.push(("class".to_string(), Some(classes.join(" "))));
+                    }
+                    for (attr, value) in &element.attrs {
+                        attrs.push((number(attr), value.as_deref().map(number)));
+                    }
+                    for attr in default_attributes(&name) {
+                        if !attrs.iter().any(|(a, _)| a == attr) {
+                            attrs.push((attr.to_string(), None));
+                        }
+                    }
+                    let children = self::tags(&node.children, &name, numbering);
+                    tags.push(Tag {
+                        name,
+                        attrs,
+                        text: element.text.as_deref().map(number),
+                        children,
+                    });
+                }
+                NodeKind::Group(group) => {
+                    let mut group = self::tags(group, parent, numbering);
+                    let children = self::tags(&node.children, parent, numbering);
+                    if let Some(last) = group.last_mut() {
+                        last.children.extend(children);
+                    }
+                    tags.extend(group);
+                }
+            }
+        }
+    }
+    tags
+}
+
+fn escape_snippet(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('$', "\\$")
+        .replace('}', "\\}")
+}
+
+struct MarkupWriter<'a> {
+    syntax: EmmetSyntax,
+    indent: &'a str,
+    line_indent: &'a str,
+    tab: usize,
+    text: String,
+}
+
+impl MarkupWriter<'_> {
+    fn placeholder(&mut self) {
+        self.tab += 1;
+        self.text.push_str(&format!("${}", self.tab));
+    }
+
+    fn new_line(&mut self, depth: usize) {
+        self.text.push('\n');
+        self.text.push_str(self.line_indent);
+        for _ in 0..depth {
+            self.text.push_str(self.indent);
+        }
+    }
+
+    fn write(&mut self, tag: &Tag, depth: usize) {
+        self.text.push('<');
+        self.text.push_str(&tag.name);
+        for (attr, value) in &tag.attrs {
+            let attr = match (self.syntax, attr.as_str()) {
+                (EmmetSyntax::Jsx, "class") => "className",
+                (EmmetSyntax::Jsx, "for") => "htmlFor",
+                _ => attr,
+            };
+            self.text.push_str(&format!(" {attr}=\""));
+            match value.as_deref() {
+                Some(value) if !value.is_empty() => {
+                    self.text.push_str(&escape_snippet(value))
+                }
+                _ => self.placeholder(),
+            }
+            self.text.push('"');
+        }
+        if VOID_ELEMENTS.contains(&tag.name.as_str()) {
+            self.text.push_str(match self.syntax {
+                EmmetSyntax::Jsx => " />",
+                _ => ">",
+            });
+            return;
+        }
+        self.text.push('>');
+
+        if let Some(text) = &tag.text {
+            self.text.push_str(&escape_snippet(text));
+        }
+        if tag.is_block() {
+            for child in &tag.children {
+                self.new_line(depth + 1);
+                self.write(child, depth + 1);
+            }
+            self.new_line(depth);
+        } else if tag.children.is_empty() {
+            if tag.text.is_none() {
+                self.placeholder();
+            }
+        } else {
+            for child in &tag.children {
+                self.write(child, depth);
+            }
+        }
+        self.text.push_str(&format!("</{}>", tag.name));
+    }
+}
+
+fn expand_markup(
+    syntax: EmmetSyntax,
+    abbreviation: &str,
+    indent: &str,
+    line_indent: &str,
+) -> Option<String> {
+    let mut parser = Parser {
+        chars: abbreviation.chars().collect(),
+        pos: 0,
+    };
+    let nodes = parser.parse_level(false)?;
+    if parser.pos != parser.chars.len() {
+        return None;
+    }
+
+    // A single word is only an abbreviation if it's an element, or a
+    // component in JSX
+    if let [Node {
+        kind: NodeKind::Element(element),
+        count: 1,
+        children,
+    }] = nodes.as_slice()
+    {
+        let is_plain_word = children.is_empty()
+            && element.id.is_none()
+            && element.classes.is_empty()
+            && element.attrs.is_empty()
+            && element.text.is_none();
+        let is_element = KNOWN_ELEMENTS.contains(&element.name.as_str())
+            || (syntax == EmmetSyntax::Jsx
+                && element.name.starts_with(|c: char| c.is_ascii_uppercase()));
+        if is_plain_word && !is_element {
+            return None;
+        }
+    }
+
+    let mut writer = MarkupWriter {
+        syntax,
+        indent,
+        line_indent,
+        tab: 0,
+        text: String::new(),
+    };
+    for (i, tag) in tags(&nodes, "", (1, 1)).iter().enumerate() {
+        if i > 0 {
+            writer.new_line(0);
+        }
+        writer.write(tag, 0);
+    }
+    Some(writer.text)
+}
+
+const CSS_PROPERTIES: &[(&str, &str)] = &[
+    ("ai", "align-items"),
+    ("b", "bottom"),
+    ("bd", "border"),
+    ("bg", "background"),
+    ("bgc", "background-color"),
+    ("br", "border-radius"),
+    ("c", "color"),
+    ("cur", "cursor"),
+    ("d", "display"),
+    ("fl", "float"),
+    ("fw", "font-weight"),
+    ("fxd", "flex-direction"),
+    ("fz", "font-size"),
+    ("g", "gap"),
+    ("h", "height"),
+    ("jc", "justify-content"),
+    ("l", "left"),
+    ("lh", "line-height"),
+    ("m", "margin"),
+    ("mah", "max-height"),
+    ("maw", "max-width"),
+    ("mb", "margin-bottom"),
+    ("mih", "min-height"),
+    ("miw", "min-width"),
+    ("ml", "margin-left"),
+    ("mr", "margin-right"),
+    ("mt", "margin-top"),
+    ("op", "opacity"),
+    ("ov", "overflow"),
+    ("p", "padding"),
+    ("pb", "padding-bottom"),
+    ("pl", "padding-left"),
+    ("pos", "position"),
+    ("pr", "padding-right"),
+    ("pt", "padding-top"),
+    ("r", "right"),
+    ("t", "top"),
+    ("ta", "text-align"),
+    ("td", "text-decoration"),
+    ("w", "width"),
+    ("z", "z-index"),
+];
+
+const UNITLESS_PROPERTIES: &[&str] =
+    &["font-weight", "line-height", "opacity", "z-index"];
+
+fn css_keyword(property: &str, value: &str) -> Option<&'static str> {
+    let keyword = match (property, value) {
+        ("display", "b") => "block",
+        ("display", "i") => "inline",
+        ("display", "ib") => "inline-block",
+        ("display", "f") => "flex",
+        ("display", "if") => "inline-flex",
+        ("display", "g") => "grid",
+        ("display", "n") => "none",
+        ("position", "a") => "absolute",
+        ("position", "r") => "relative",
+        ("position", "f") => "fixed",
+        ("position", "s") => "static",
+        ("position", "st") => "sticky",
+        ("text-align", "l") | ("float", "l") => "left",
+        ("text-align", "r") | ("float", "r") => "right",
+        ("text-align", "c") => "center",
+        ("text-align", "j") => "justify",
+        ("float", "n") | ("text-decoration", "n") => "none",
+        ("text-decoration", "u") => "underline",
+        ("font-weight", "b") => "bold",
+        ("font-weight", "n") => "normal",
+        ("overflow", "h") => "hidden",
+        ("overflow", "a") => "auto",
+        ("overflow", "s") => "scroll",
+        ("overflow", "v") => "visible",
+        ("cursor", "p") => "pointer",
+        ("cursor", "d") => "default",
+        ("justify-content", "c") | ("align-items", "c") => "center",
+        ("justify-content", "fs") | ("align-items", "fs") => "flex-start",
+        ("justify-content", "fe") | ("align-items", "fe") => "flex-end",
+        ("justify-content", "sb") => "space-between",
+        ("justify-content", "sa") => "space-around",
+        ("flex-direction", "r") => "row",
+        ("flex-direction", "c") => "column",
+        _ => return None,
+    };
+    Some(keyword)
+}
+
+/// The numbers of a value like `10-20` or `-5p`, with their units
+fn css_numbers(property: &str, value: &str) -> Option<String> {
+    let mut numbers = Vec::new();
+    let mut chars = value.chars().peekable();
+    while chars.peek().is_some() {
+        // A `-` between numbers separates them, and a second one makes the
+        // next number negative
+        if !numbers.is_empty() && chars.next_if_eq(&'-').is_none() {
+            return None;
+        }
+        let mut number = String::new();
+        if chars.next_if_eq(&'-').is_some() {
+            number.push('-');
+        }
+        while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+            number.push(c);
+        }
+        if number.trim_start_matches('-').is_empty() {
+            return None;
+        }
+        let mut unit = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic() || *c == '%') {
+            unit.push(c);
+        }
+        let unit = match unit.as_str() {
+            "" if number.parse::<f64>().ok()? == 0.0 => "",
+            "" if UNITLESS_PROPERTIES.contains(&property) => "",
+            "" => "px",
+            "p" => "%",
+            "e" => "em",
+            "r" => "rem",
+            "x" => "ex",
+            unit => unit,
+        };
+        numbers.push(format!("{number}{unit}"));
+    }
+    Some(numbers.join(" "))
+}
+
+fn css_color(value: &str) -> Option<String> {
+    let hex = value.strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = match hex.len() {
+        1 | 2 => hex.repeat(3),
+        3 | 6 => hex.to_string(),
+        _ => return None,
+    };
+    Some(format!("#{hex}"))
+}
+
+fn expand_css(abbreviation: &str) -> Option<String> {
+    let letters = abbreviation
+        .find(|c: char| !c.is_ascii_lowercase())
+        .unwrap_or(abbreviation.len());
+    // The longest property the value of which makes sense, so that `df` is
+    // `display: flex`
+    (1..=letters).rev().find_map(|end| {
+        let (_, property) = CSS_PROPERTIES
+            .iter()
+            .find(|(abbr, _)| *abbr == &abbreviation[..end])?;
+        let value = &abbreviation[end..];
+        let value = value.strip_prefix(':').unwrap_or(value);
+        let value = if value.is_empty() {
+            "$1".to_string()
+        } else {
+            css_keyword(property, value)
+                .map(|keyword| keyword.to_string())
+                .or_else(|| css_color(value))
+                .or_else(|| css_numbers(property, value))?
+        };
+        Some(format!("{property}: {value};"))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn html(abbreviation: &str) -> Option<String> {
+        expand(EmmetSyntax::Html, abbreviation, "\t", "")
+    }
+
+    #[test]
+    fn test_expand_children_and_repeat() {
+        assert_eq!(
+            html("ul>li*3").unwrap(),
+            "<ul>\n\t<li>$1</li>\n\t<li>$2</li>\n\t<li>$3</li>\n</ul>"
+        );
+    }
+
+    #[test]
+    fn test_expand_attributes_and_numbering() {
+        assert_eq!(
+            html("div#main.a.b$*2").unwrap(),
+            "<div id=\"main\" class=\"a b1\">$1</div>\n<div id=\"main\" class=\"a b2\">$2</div>"
+        );
+        assert_eq!(
+            html("a[href=#top]{Top}").unwrap(),
+            "<a href=\"#top\">Top</a>"
+        );
+        assert_eq!(html("img").unwrap(), "<img src=\"$1\" alt=\"$2\">");
+    }
+
+    #[test]
+    fn test_expand_siblings_climbing_and_groups() {
+        assert_eq!(
+            html("div>p>span^h1+(ul>.item)").unwrap(),
+            "<div>\n\t<p><span>$1</span></p>\n\t<h1>$2</h1>\n\t<ul>\n\t\t<li class=\"item\">$3</li>\n\t</ul>\n</div>"
+        );
+    }
+
+    #[test]
+    fn test_expand_jsx() {
+        assert_eq!(
+            expand(EmmetSyntax::Jsx, ".a>input", "  ", "  ").unwrap(),
+            "<div className=\"a\"><input type=\"$1\" /></div>"
+        );
+    }
+
+    #[test]
+    fn test_plain_words_are_not_abbreviations() {
+        assert_eq!(html("foo"), None);
+        assert_eq!(html("ul>"), None);
+        assert!(html("section").is_some());
+    }
+
+    #[test]
+    fn test_abbreviation_in_line() {
+        assert_eq!(
+            abbreviation(EmmetSyntax::Html, "  <div>ul>li{a b}*2"),
+            Some((7, "ul>li{a b}*2"))
+        );
+        assert_eq!(abbreviation(EmmetSyntax::Html, "some text p"), None);
+        assert_eq!(
+            abbreviation(EmmetSyntax::Css, "a { m10-20"),
+            Some((4, "m10-20"))
+        );
+    }
+
+    #[test]
+    fn test_expand_css() {
+        let css = |abbreviation| expand(EmmetSyntax::Css, abbreviation, "", "");
+        assert_eq!(css("m10-20").unwrap(), "margin: 10px 20px;");
+        assert_eq!(css("m-10").unwrap(), "margin: -10px;");
+        assert_eq!(css("w100p").unwrap(), "width: 100%;");
+        assert_eq!(css("df").unwrap(), "display: flex;");
+        assert_eq!(css("pos:a").unwrap(), "position: absolute;");
+        assert_eq!(css("z10").unwrap(), "z-index: 10;");
+        assert_eq!(css("c#f").unwrap(), "color: #fff;");
+        assert_eq!(css("bgc").unwrap(), "background-color: $1;");
+        assert_eq!(css("foo"), None);
+    }
+}
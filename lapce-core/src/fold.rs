@@ -0,0 +1,289 @@
+/// What a folding range covers, when the language server tells us
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FoldingRangeKind {
+    Comment,
+    Imports,
+    Region,
+}
+
+/// A range of lines that can be folded away. The first line stays visible
+/// and the remaining lines up to and including `end_line` are hidden.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FoldingRange {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub kind: Option<FoldingRangeKind>,
+}
+
+impl FoldingRange {
+    pub fn new(start_line: usize, end_line: usize) -> Self {
+        Self {
+            start_line,
+            end_line,
+            kind: None,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: FoldingRangeKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn contains(&self, line: usize) -> bool {
+        self.start_line <= line && line <= self.end_line
+    }
+}
+
+impl From<&lsp_types::FoldingRange> for FoldingRange {
+    fn from(range: &lsp_types::FoldingRange) -> Self {
+        Self {
+            start_line: range.start_line as usize,
+            end_line: range.end_line as usize,
+            kind: range.kind.as_ref().map(|kind| match kind {
+                lsp_types::FoldingRangeKind::Comment => FoldingRangeKind::Comment,
+                lsp_types::FoldingRangeKind::Imports => FoldingRangeKind::Imports,
+                lsp_types::FoldingRangeKind::Region => FoldingRangeKind::Region,
+            }),
+        }
+    }
+}
+
+/// Folding ranges from the indentation of the lines, for when neither the
+/// language server nor the syntax tree can provide any. A range starts at
+/// each line followed by more indented lines, and ends at the last of them,
+/// ignoring blank lines.
+pub fn indentation_folding_ranges<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+    tab_width: usize,
+) -> Vec<FoldingRange> {
+    let mut ranges = Vec::new();
+    // The lines that can still start a range, with their indentation
+    let mut starts: Vec<(usize, usize)> = Vec::new();
+    let mut last_line = 0;
+    for (line, content) in lines.into_iter().enumerate() {
+        let content = content.as_ref();
+        if content.trim().is_empty() {
+            continue;
+        }
+        let indent = content
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { tab_width } else { 1 })
+            .sum::<usize>();
+        while let Some((start, _)) = starts
+            .last()
+            .filter(|(_, start_indent)| *start_indent >= indent)
+        {
+            if last_line > *start {
+                ranges.push(FoldingRange::new(*start, last_line));
+            }
+            starts.pop();
+        }
+        starts.push((line, indent));
+        last_line = line;
+    }
+    for (start, _) in starts.into_iter().rev() {
+        if last_line > start {
+            ranges.push(FoldingRange::new(start, last_line));
+        }
+    }
+    ranges.sort_by_key(|range| (range.start_line, range.end_line));
+    ranges
+}
+
+/// Merge the folding ranges from the language server with the `fallback`
+/// ones, which are only kept for lines that the server has no range
+/// starting at.
+pub fn merge_folding_ranges(
+    ranges: &[FoldingRange],
+    fallback: Vec<FoldingRange>,
+) -> Vec<FoldingRange> {
+    let mut merged = ranges.to_vec();
+    merged.extend(
+        fallback.into_iter().filter(|range| {
+            !ranges.iter().any(|r| r.start_line == range.start_line)
+        }),
+    );
+    merged.sort_by_key(|range| (range.start_line, range.end_line));
+    merged
+}
+
+/// The lines hidden by the folded ranges of a document, used to map between
+/// buffer lines and the lines that are actually shown in the editor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FoldedLines {
+    /// Sorted and non overlapping `(start, end)` line ranges, end exclusive
+    hidden: Vec<(usize, usize)>,
+}
+
+impl FoldedLines {
+    pub fn new(folded: impl IntoIterator<Item = FoldingRange>) -> Self {
+        let mut ranges = folded
+            .into_iter()
+            .filter(|range| range.end_line > range.start_line)
+            .map(|range| (range.start_line + 1, range.end_line + 1))
+            .collect::<Vec<_>>();
+        ranges.sort();
+
+        let mut hidden: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in ranges {
+            if let Some(last) = hidden.last_mut() {
+                if start <= last.1 {
+                    last.1 = last.1.max(end);
+                    continue;
+                }
+            }
+            hidden.push((start, end));
+        }
+        Self { hidden }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hidden.is_empty()
+    }
+
+    /// Whether the line is inside a folded range and isn't shown
+    pub fn is_hidden(&self, line: usize) -> bool {
+        self.hidden
+            .iter()
+            .any(|(start, end)| *start <= line && line < *end)
+    }
+
+    /// Whether the line is shown, with folded lines right after it
+    pub fn is_folded(&self, line: usize) -> bool {
+        !self.is_hidden(line)
+            && self.hidden.iter().any(|(start, _)| *start == line + 1)
+    }
+
+    /// The line shown in place of `line`, which is the line itself unless
+    /// it's hidden, in which case it's the first line of its fold.
+    pub fn visible_line(&self, line: usize) -> usize {
+        for (start, end) in self.hidden.iter() {
+            if *start > line {
+                break;
+            }
+            if line < *end {
+                return start - 1;
+            }
+        }
+        line
+    }
+
+    /// The position of `line` counting only the lines that are shown
+    pub fn visual_line(&self, line: usize) -> usize {
+        let line = self.visible_line(line);
+        let hidden: usize = self
+            .hidden
+            .iter()
+            .take_while(|(start, _)| *start <= line)
+            .map(|(start, end)| end - start)
+            .sum();
+        line - hidden
+    }
+
+    /// The buffer line shown at `visual_line`
+    pub fn actual_line(&self, visual_line: usize) -> usize {
+        let mut line = visual_line;
+        for (start, end) in self.hidden.iter() {
+            if *start > line {
+                break;
+            }
+            line += end - start;
+        }
+        line
+    }
+
+    /// The number of lines shown for a buffer with `num_lines` lines
+    pub fn num_visual_lines(&self, num_lines: usize) -> usize {
+        let hidden: usize = self
+            .hidden
+            .iter()
+            .filter(|(start, _)| *start < num_lines)
+            .map(|(start, end)| (*end).min(num_lines) - start)
+            .sum();
+        num_lines - hidden
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        indentation_folding_ranges, merge_folding_ranges, FoldedLines, FoldingRange,
+        FoldingRangeKind,
+    };
+
+    #[test]
+    fn test_merge_nested_folds() {
+        let folded = FoldedLines::new([
+            FoldingRange::new(10, 12),
+            FoldingRange::new(2, 8),
+            FoldingRange::new(3, 5),
+            FoldingRange::new(8, 9),
+            FoldingRange::new(20, 20),
+        ]);
+        assert_eq!(folded.hidden, vec![(3, 10), (11, 13)]);
+    }
+
+    #[test]
+    fn test_line_mapping() {
+        let folded =
+            FoldedLines::new([FoldingRange::new(2, 5), FoldingRange::new(10, 11)]);
+
+        assert!(folded.is_folded(2));
+        assert!(!folded.is_folded(3));
+        assert!(folded.is_hidden(3));
+        assert!(folded.is_hidden(5));
+        assert!(!folded.is_hidden(6));
+
+        assert_eq!(folded.visible_line(1), 1);
+        assert_eq!(folded.visible_line(4), 2);
+        assert_eq!(folded.visible_line(11), 10);
+
+        assert_eq!(folded.visual_line(2), 2);
+        assert_eq!(folded.visual_line(4), 2);
+        assert_eq!(folded.visual_line(6), 3);
+        assert_eq!(folded.visual_line(12), 8);
+
+        assert_eq!(folded.actual_line(2), 2);
+        assert_eq!(folded.actual_line(3), 6);
+        assert_eq!(folded.actual_line(7), 10);
+        assert_eq!(folded.actual_line(8), 12);
+
+        assert_eq!(folded.num_visual_lines(13), 9);
+        assert_eq!(folded.num_visual_lines(4), 3);
+    }
+
+    #[test]
+    fn test_indentation_folding_ranges() {
+        let text = "fn main() {\n    if a {\n        b();\n\n    }\n}\ntop:\n\tone\n\n\ttwo\n";
+        let ranges = indentation_folding_ranges(text.lines(), 4);
+        assert_eq!(
+            ranges,
+            vec![
+                FoldingRange::new(0, 4),
+                FoldingRange::new(1, 2),
+                FoldingRange::new(6, 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_folding_ranges() {
+        let lsp = [
+            FoldingRange::new(0, 3).with_kind(FoldingRangeKind::Imports),
+            FoldingRange::new(5, 9),
+        ];
+        let merged = merge_folding_ranges(
+            &lsp,
+            vec![FoldingRange::new(5, 8), FoldingRange::new(6, 7)],
+        );
+        assert_eq!(
+            merged,
+            vec![
+                FoldingRange::new(0, 3).with_kind(FoldingRangeKind::Imports),
+                FoldingRange::new(5, 9),
+                FoldingRange::new(6, 7),
+            ]
+        );
+    }
+}
@@ -18,6 +18,13 @@ pub enum IndentStyle {
 impl IndentStyle {
     pub const LONGEST_INDENT: &'static str = "        "; // 8 spaces
     pub const DEFAULT_INDENT: IndentStyle = IndentStyle::Spaces(4);
+    /// The styles that can be picked for a file
+    pub const ALL: [IndentStyle; 4] = [
+        IndentStyle::Tabs,
+        IndentStyle::Spaces(2),
+        IndentStyle::Spaces(4),
+        IndentStyle::Spaces(8),
+    ];
 
     /// Creates an `IndentStyle` from an indentation string.
     ///
@@ -50,6 +57,14 @@ pub fn as_str(&self) -> &'static str {
             }
         }
     }
+
+    /// The name shown in the status bar
+    pub fn label(&self) -> String {
+        match self {
+            IndentStyle::Tabs => "Tabs".to_string(),
+            IndentStyle::Spaces(n) => format!("Spaces: {n}"),
+        }
+    }
 }
 
 pub fn create_edit<'s>(
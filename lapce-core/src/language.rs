@@ -44,6 +44,7 @@
 //            highlight: tree_sitter_foo::HIGHLIGHT_QUERY,
 //            injection: Some(tree_sitter_foo::INJECTION_QUERY), // or None if there is no injections
 //            comment: "//",
+//            block_comment: ("/*", "*/"),
 //            indent: "    ",
 //            code_lens: (&[/* ... */], &[/* ... */]),
 //            extensions: &["foo"],
@@ -84,6 +85,9 @@ struct SyntaxProperties {
     injection: Option<&'static str>,
     /// The comment token.  "#" for python, "//" for rust for example.
     comment: &'static str,
+    /// The start and end tokens of block comments.  `("/*", "*/")` for rust,
+    /// `("<!--", "-->")` for html, or empty when there are no block comments.
+    block_comment: (&'static str, &'static str),
     /// The indent unit.  "\t" for python, "    " for rust, for example.
     indent: &'static str,
     /// TODO: someone more knowledgeable please describe what the two lists are.
@@ -210,6 +214,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_rust::HIGHLIGHT_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "    ",
         code_lens: (
             &["source_file", "impl_item", "trait_item", "declaration_list"],
@@ -225,6 +230,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_go::HIGHLIGHT_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "    ",
         code_lens: (
             &[
@@ -246,6 +252,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/javascript/highlights.scm"),
         injection: Some(tree_sitter_javascript::INJECTION_QUERY),
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (&["source_file", "program"], &["source_file"]),
         sticky_headers: &[],
@@ -259,6 +266,7 @@ pub enum LapceLanguage {
         // TODO: Does jsx use the javascript injection query too?
         injection: Some(tree_sitter_javascript::INJECTION_QUERY),
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (&["source_file", "program"], &["source_file"]),
         sticky_headers: &[],
@@ -271,6 +279,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/typescript/highlights.scm"),
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "    ",
         code_lens: (&["source_file", "program"], &["source_file"]),
         sticky_headers: &[],
@@ -283,6 +292,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/typescript/highlights.scm"),
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "    ",
         code_lens: (&["source_file", "program"], &["source_file"]),
         sticky_headers: &[],
@@ -295,6 +305,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_python::HIGHLIGHT_QUERY,
         injection: None,
         comment: "#",
+        block_comment: ("", ""),
         indent: "\t",
         code_lens: (
             &[
@@ -318,6 +329,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_toml::HIGHLIGHT_QUERY,
         injection: None,
         comment: "#",
+        block_comment: ("", ""),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -330,6 +342,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_php::HIGHLIGHT_QUERY,
         injection: Some(tree_sitter_php::INJECTIONS_QUERY),
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -342,6 +355,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_elixir::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "#",
+        block_comment: ("", ""),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -354,6 +368,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/c/highlights.scm"),
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &["function_definition", "struct_specifier"],
@@ -366,6 +381,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/cpp/highlights.scm"),
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[
@@ -382,6 +398,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_json::HIGHLIGHT_QUERY,
         injection: None,
         comment: "",
+        block_comment: ("", ""),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -394,6 +411,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/markdown/highlights.scm"),
         injection: Some(include_str!("../queries/markdown/injections.scm")),
         comment: "",
+        block_comment: ("<!--", "-->"),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -406,6 +424,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/markdown.inline/highlights.scm"),
         injection: Some(include_str!("../queries/markdown.inline/injections.scm")),
         comment: "",
+        block_comment: ("<!--", "-->"),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -419,6 +438,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_ruby::HIGHLIGHT_QUERY,
         injection: None,
         comment: "#",
+        block_comment: ("", ""),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -431,6 +451,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_html::HIGHLIGHT_QUERY,
         injection: Some(tree_sitter_html::INJECTION_QUERY),
         comment: "",
+        block_comment: ("<!--", "-->"),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -443,6 +464,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_java::HIGHLIGHT_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -454,7 +476,8 @@ pub enum LapceLanguage {
         language: tree_sitter_elm::language,
         highlight: include_str!("../queries/elm/highlights.scm"),
         injection: Some(tree_sitter_elm::INJECTIONS_QUERY),
-        comment: "#",
+        comment: "--",
+        block_comment: ("{-", "-}"),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -467,6 +490,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_swift::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -479,6 +503,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_ql::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -491,6 +516,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_haskell::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "--",
+        block_comment: ("{-", "-}"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -502,7 +528,8 @@ pub enum LapceLanguage {
         language: tree_sitter_glimmer::language,
         highlight: tree_sitter_glimmer::HIGHLIGHTS_QUERY,
         injection: None,
-        comment: "{{!",
+        comment: "",
+        block_comment: ("{{!--", "--}}"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -515,6 +542,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_haxe::HIGHLIGHTS_QUERY,
         injection: Some(tree_sitter_haxe::INJECTIONS_QUERY),
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -527,6 +555,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_hcl::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -538,7 +567,8 @@ pub enum LapceLanguage {
         language: tree_sitter_ocaml::language_ocaml,
         highlight: tree_sitter_ocaml::HIGHLIGHTS_QUERY,
         injection: None,
-        comment: "(*",
+        comment: "",
+        block_comment: ("(*", "*)"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -550,7 +580,8 @@ pub enum LapceLanguage {
         language: tree_sitter_ocaml::language_ocaml_interface,
         highlight: tree_sitter_ocaml::HIGHLIGHTS_QUERY,
         injection: None,
-        comment: "(*",
+        comment: "",
+        block_comment: ("(*", "*)"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -563,6 +594,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_scss::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -575,6 +607,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_hare::HIGHLIGHT_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("", ""),
         indent: "        ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -586,7 +619,8 @@ pub enum LapceLanguage {
         language: tree_sitter_css::language,
         highlight: include_str!("../queries/css/highlights.scm"),
         injection: None,
-        comment: "/*",
+        comment: "",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -599,6 +633,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/zig/highlights.scm"),
         injection: Some(tree_sitter_zig::INJECTIONS_QUERY),
         comment: "//",
+        block_comment: ("", ""),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -611,6 +646,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/bash/highlights.scm"),
         injection: None,
         comment: "#",
+        block_comment: ("", ""),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -623,6 +659,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_yaml::HIGHLIGHTS_QUERY,
         injection: Some(tree_sitter_yaml::INJECTIONS_QUERY),
         comment: "#",
+        block_comment: ("", ""),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -635,6 +672,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/julia/highlights.scm"),
         injection: None,
         comment: "#",
+        block_comment: ("#=", "=#"),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -647,6 +685,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_wgsl::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -659,6 +698,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_dockerfile::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "#",
+        block_comment: ("", ""),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -670,7 +710,8 @@ pub enum LapceLanguage {
         language: tree_sitter_c_sharp::language,
         highlight: tree_sitter_c_sharp::HIGHLIGHT_QUERY,
         injection: None,
-        comment: "#",
+        comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -683,6 +724,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_nix::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "#",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -695,6 +737,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_dart::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -706,7 +749,8 @@ pub enum LapceLanguage {
         language: tree_sitter_svelte::language,
         highlight: tree_sitter_svelte::HIGHLIGHT_QUERY,
         injection: Some(tree_sitter_svelte::INJECTION_QUERY),
-        comment: "//",
+        comment: "",
+        block_comment: ("<!--", "-->"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -719,6 +763,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/latex/highlights.scm"),
         injection: Some(include_str!("../queries/latex/injections.scm")),
         comment: "%",
+        block_comment: ("", ""),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -731,6 +776,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/kotlin/highlights.scm"),
         injection: Some(include_str!("../queries/kotlin/injections.scm")),
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "  ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -743,6 +789,7 @@ pub enum LapceLanguage {
         highlight: tree_sitter_d::HIGHLIGHTS_QUERY,
         injection: None,
         comment: "//",
+        block_comment: ("/*", "*/"),
         indent: "    ",
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
         sticky_headers: &[],
@@ -755,6 +802,7 @@ pub enum LapceLanguage {
         highlight: include_str!("../queries/lua/highlights.scm"),
         injection: None,
         comment: "--",
+        block_comment: ("--[[", "]]"),
         indent: "  ",
         sticky_headers: &[],
         code_lens: (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST),
@@ -764,7 +812,11 @@ pub enum LapceLanguage {
 
 impl LapceLanguage {
     pub fn from_path(path: &Path) -> Option<LapceLanguage> {
-        let extension = path.extension()?.to_str()?.to_lowercase();
+        LapceLanguage::from_extension(path.extension()?.to_str()?)
+    }
+
+    pub fn from_extension(extension: &str) -> Option<LapceLanguage> {
+        let extension = extension.to_lowercase();
         // NOTE: This is a linear search.  It is assumed that this function
         // isn't called in any tight loop.
         for properties in LANGUAGES {
@@ -816,6 +868,15 @@ pub fn comment_token(&self) -> &str {
         self.properties().comment
     }
 
+    pub fn block_comment_tokens(&self) -> Option<(&str, &str)> {
+        let (start, end) = self.properties().block_comment;
+        if start.is_empty() {
+            None
+        } else {
+            Some((start, end))
+        }
+    }
+
     pub fn indent_unit(&self) -> &str {
         self.properties().indent
     }
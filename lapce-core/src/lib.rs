@@ -3,14 +3,20 @@
 pub mod command;
 pub mod cursor;
 pub mod editor;
+pub mod emmet;
 pub mod encoding;
+pub mod fold;
 pub mod indent;
 pub mod language;
 pub mod lens;
+pub mod line_ending;
+pub mod merge;
 pub mod mode;
 pub mod movement;
 pub mod register;
 pub mod selection;
 pub mod style;
 pub mod syntax;
+pub mod transform;
 pub mod word;
+pub mod wrap;
@@ -0,0 +1,166 @@
+use std::borrow::Cow;
+
+use xi_rope::Rope;
+
+use crate::selection::Selection;
+
+/// The sequence that ends the lines of a buffer
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 3] =
+        [LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr];
+
+    #[cfg(windows)]
+    pub const DEFAULT: LineEnding = LineEnding::CrLf;
+    #[cfg(not(windows))]
+    pub const DEFAULT: LineEnding = LineEnding::Lf;
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+
+    /// The name shown in the status bar
+    pub fn label(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+            LineEnding::Cr => "CR",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<LineEnding> {
+        Self::ALL.into_iter().find(|e| e.label() == label)
+    }
+
+    /// The line ending used by most lines of the text, or `None` if the text
+    /// has a single line
+    pub fn detect(text: &Rope) -> Option<LineEnding> {
+        let mut counts = [0usize; 3];
+        for_each_line_ending(text.iter_chunks(..), |_, line_ending| {
+            counts[line_ending as usize] += 1;
+        });
+        Self::ALL
+            .into_iter()
+            .filter(|e| counts[*e as usize] > 0)
+            .max_by_key(|e| counts[*e as usize])
+    }
+
+    /// Replace the line endings in the text with this one
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut result = String::new();
+        let mut last = 0;
+        for_each_line_ending(std::iter::once(text), |offset, line_ending| {
+            if line_ending != *self {
+                result.push_str(&text[last..offset]);
+                result.push_str(self.as_str());
+                last = offset + line_ending.as_str().len();
+            }
+        });
+        if last == 0 {
+            return Cow::Borrowed(text);
+        }
+        result.push_str(&text[last..]);
+        Cow::Owned(result)
+    }
+
+    /// The edits that change every line ending of the text to this one
+    pub fn edits(&self, text: &Rope) -> Vec<(Selection, &'static str)> {
+        let mut edits = Vec::new();
+        for_each_line_ending(text.iter_chunks(..), |offset, line_ending| {
+            if line_ending != *self {
+                edits.push((
+                    Selection::region(offset, offset + line_ending.as_str().len()),
+                    self.as_str(),
+                ));
+            }
+        });
+        edits
+    }
+}
+
+/// Call `f` with the offset and the kind of every line ending in the text
+fn for_each_line_ending<'a>(
+    chunks: impl Iterator<Item = &'a str>,
+    mut f: impl FnMut(usize, LineEnding),
+) {
+    let mut offset = 0;
+    // A "\r\n" can be split across two chunks
+    let mut pending_cr = None;
+    for chunk in chunks {
+        let bytes = chunk.as_bytes();
+        let mut i = 0;
+        if let Some(cr) = pending_cr.take() {
+            if bytes.first() == Some(&b'\n') {
+                f(cr, LineEnding::CrLf);
+                i = 1;
+            } else {
+                f(cr, LineEnding::Cr);
+            }
+        }
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\n' => f(offset + i, LineEnding::Lf),
+                b'\r' => match bytes.get(i + 1) {
+                    Some(b'\n') => {
+                        f(offset + i, LineEnding::CrLf);
+                        i += 1;
+                    }
+                    Some(_) => f(offset + i, LineEnding::Cr),
+                    None => pending_cr = Some(offset + i),
+                },
+                _ => {}
+            }
+            i += 1;
+        }
+        offset += bytes.len();
+    }
+    if let Some(cr) = pending_cr {
+        f(cr, LineEnding::Cr);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(LineEnding::detect(&Rope::from("a")), None);
+        assert_eq!(
+            LineEnding::detect(&Rope::from("a\r\nb\r\nc\n")),
+            Some(LineEnding::CrLf)
+        );
+        assert_eq!(
+            LineEnding::detect(&Rope::from("a\rb\r")),
+            Some(LineEnding::Cr)
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(LineEnding::Lf.normalize("a\r\nb\rc\n"), "a\nb\nc\n");
+        assert_eq!(LineEnding::CrLf.normalize("a\nb\r\n"), "a\r\nb\r\n");
+        assert!(matches!(LineEnding::Lf.normalize("a\nb"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_edits() {
+        let text = Rope::from("a\r\nb\nc\r");
+        let edits = LineEnding::Lf.edits(&text);
+        let edits = edits
+            .iter()
+            .map(|(s, text)| (s.min_offset(), s.max_offset(), *text))
+            .collect::<Vec<_>>();
+        assert_eq!(edits, vec![(1, 3, "\n"), (6, 7, "\n")]);
+    }
+}
@@ -0,0 +1,157 @@
+/// The marker line that starts the lines of a conflict from our side
+pub const CONFLICT_START: &str = "<<<<<<< Unsaved changes";
+/// The marker line between the two sides of a conflict
+pub const CONFLICT_SEPARATOR: &str = "=======";
+/// The marker line that ends the lines of a conflict from their side
+pub const CONFLICT_END: &str = ">>>>>>> File on disk";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Merge {
+    pub text: String,
+    /// The number of hunks that were changed differently on both sides,
+    /// which are surrounded by conflict markers in the text
+    pub conflicts: usize,
+}
+
+/// Merge the changes that `ours` and `theirs` made to `base` line by line.
+/// A hunk that only one side changed takes that side, and a hunk that both
+/// changed differently keeps both, between conflict markers, to be merged
+/// by hand.
+pub fn three_way_merge(base: &str, ours: &str, theirs: &str) -> Merge {
+    let base = base.split_inclusive('\n').collect::<Vec<_>>();
+    let ours = ours.split_inclusive('\n').collect::<Vec<_>>();
+    let theirs = theirs.split_inclusive('\n').collect::<Vec<_>>();
+    let ours_matches = matching_lines(&base, &ours);
+    let theirs_matches = matching_lines(&base, &theirs);
+
+    let mut merge = Merge {
+        text: String::new(),
+        conflicts: 0,
+    };
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    loop {
+        // The next base line that both sides kept is where the hunk ends
+        let stable = (b..base.len())
+            .find_map(|i| Some((i, ours_matches[i]?, theirs_matches[i]?)));
+        let (b_end, o_end, t_end) =
+            stable.unwrap_or((base.len(), ours.len(), theirs.len()));
+        merge_hunk(
+            &mut merge,
+            &base[b..b_end],
+            &ours[o..o_end],
+            &theirs[t..t_end],
+        );
+        match stable {
+            Some((b_end, o_end, t_end)) => {
+                merge.text.push_str(base[b_end]);
+                b = b_end + 1;
+                o = o_end + 1;
+                t = t_end + 1;
+            }
+            None => break,
+        }
+    }
+    merge
+}
+
+fn merge_hunk(merge: &mut Merge, base: &[&str], ours: &[&str], theirs: &[&str]) {
+    if ours == base || ours == theirs {
+        merge.text.extend(theirs.iter().copied());
+    } else if theirs == base {
+        merge.text.extend(ours.iter().copied());
+    } else {
+        merge.conflicts += 1;
+        push_conflict_lines(&mut merge.text, &[CONFLICT_START]);
+        push_conflict_lines(&mut merge.text, ours);
+        push_conflict_lines(&mut merge.text, &[CONFLICT_SEPARATOR]);
+        push_conflict_lines(&mut merge.text, theirs);
+        push_conflict_lines(&mut merge.text, &[CONFLICT_END]);
+    }
+}
+
+/// Push the lines, making sure that each of them ends with a line break so
+/// that the markers are on their own lines
+fn push_conflict_lines(text: &mut String, lines: &[&str]) {
+    for line in lines {
+        text.push_str(line);
+        if !line.ends_with('\n') {
+            text.push('\n');
+        }
+    }
+}
+
+/// The line of `right` that each line of `left` is matched with in their
+/// longest common subsequence
+fn matching_lines(left: &[&str], right: &[&str]) -> Vec<Option<usize>> {
+    let mut table = vec![vec![0u32; right.len() + 1]; left.len() + 1];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            table[i][j] = if left[i] == right[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut matches = vec![None; left.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i] == right[j] {
+            matches[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_merge_changes_of_both_sides() {
+        let base = "a\nb\nc\nd\n";
+        let ours = "a\nB\nc\nd\n";
+        let theirs = "a\nb\nc\nD\ne\n";
+        let merge = three_way_merge(base, ours, theirs);
+        assert_eq!(merge.text, "a\nB\nc\nD\ne\n");
+        assert_eq!(merge.conflicts, 0);
+    }
+
+    #[test]
+    fn test_merge_same_change() {
+        let merge = three_way_merge("a\nb\n", "a\nc\n", "a\nc\n");
+        assert_eq!(merge.text, "a\nc\n");
+        assert_eq!(merge.conflicts, 0);
+    }
+
+    #[test]
+    fn test_merge_conflict() {
+        let merge = three_way_merge("a\nb\nc", "a\nx\nc", "a\ny\nc");
+        assert_eq!(
+            merge.text,
+            format!(
+                "a\n{CONFLICT_START}\nx\n{CONFLICT_SEPARATOR}\ny\n{CONFLICT_END}\nc"
+            )
+        );
+        assert_eq!(merge.conflicts, 1);
+    }
+
+    #[test]
+    fn test_merge_conflict_at_end_without_line_break() {
+        let merge = three_way_merge("a\nb", "a\nx", "a\ny");
+        assert_eq!(
+            merge.text,
+            format!(
+                "a\n{CONFLICT_START}\nx\n{CONFLICT_SEPARATOR}\ny\n{CONFLICT_END}\n"
+            )
+        );
+        assert_eq!(merge.conflicts, 1);
+    }
+}
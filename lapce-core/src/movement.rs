@@ -14,6 +14,7 @@ pub enum Movement {
     DocumentStart,
     DocumentEnd,
     FirstNonBlank,
+    LastNonBlank,
     StartOfLine,
     EndOfLine,
     Line(LinePosition),
@@ -21,6 +22,8 @@ pub enum Movement {
     WordEndForward,
     WordForward,
     WordBackward,
+    SubwordForward,
+    SubwordBackward,
     NextUnmatched(char),
     PreviousUnmatched(char),
     MatchPairs,
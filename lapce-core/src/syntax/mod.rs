@@ -8,7 +8,7 @@
 
 use std::{
     cell::RefCell,
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     mem,
     path::Path,
     sync::{atomic::AtomicUsize, Arc},
@@ -25,6 +25,7 @@
 };
 
 use crate::{
+    fold::FoldingRange,
     language::LapceLanguage,
     lens::{Lens, LensBuilder},
     style::SCOPES,
@@ -73,6 +74,7 @@ pub enum Error {
 pub struct LanguageLayer {
     // mode
     // grammar
+    pub language: LapceLanguage,
     pub config: Arc<HighlightConfiguration>,
     pub(crate) tree: Option<Tree>,
     pub ranges: Vec<tree_sitter::Range>,
@@ -124,17 +126,15 @@ pub struct SyntaxLayers {
     root: LayerId,
 }
 impl SyntaxLayers {
-    pub fn new_empty(config: Arc<HighlightConfiguration>) -> SyntaxLayers {
-        Self::new(None, config)
+    pub fn new_empty(language: LapceLanguage) -> SyntaxLayers {
+        Self::new(None, language)
     }
 
-    pub fn new(
-        source: Option<&Rope>,
-        config: Arc<HighlightConfiguration>,
-    ) -> SyntaxLayers {
+    pub fn new(source: Option<&Rope>, language: LapceLanguage) -> SyntaxLayers {
         let root_layer = LanguageLayer {
             tree: None,
-            config,
+            language,
+            config: get_highlight_config(language),
             depth: 0,
             ranges: vec![tree_sitter::Range {
                 start_byte: 0,
@@ -166,7 +166,8 @@ pub fn update(
         queue.push_back(self.root);
 
         let injection_callback = |language: &str| {
-            LapceLanguage::from_name(language).map(get_highlight_config)
+            LapceLanguage::from_name(language)
+                .map(|language| (language, get_highlight_config(language)))
         };
 
         let mut edits = Vec::new();
@@ -319,12 +320,12 @@ fn point_sub(a: Point, b: Point) -> Point {
                     // to the highlighted document.
                     if let (Some(language_name), Some(content_node)) = (language_name, content_node)
                     {
-                        if let Some(config) = (injection_callback)(&language_name) {
+                        if let Some((language, config)) = (injection_callback)(&language_name) {
                             let ranges =
                                 intersect_ranges(&layer.ranges, &[content_node], included_children);
 
                             if !ranges.is_empty() {
-                                injections.push((config, ranges));
+                                injections.push((language, config, ranges));
                             }
                         }
                     }
@@ -361,14 +362,14 @@ fn point_sub(a: Point, b: Point) -> Point {
                     for (lang_name, content_nodes, included_children) in injections_by_pattern_index
                     {
                         if let (Some(lang_name), false) = (lang_name, content_nodes.is_empty()) {
-                            if let Some(config) = (injection_callback)(&lang_name) {
+                            if let Some((language, config)) = (injection_callback)(&lang_name) {
                                 let ranges = intersect_ranges(
                                     &layer.ranges,
                                     &content_nodes,
                                     included_children,
                                 );
                                 if !ranges.is_empty() {
-                                    injections.push((config, ranges));
+                                    injections.push((language, config, ranges));
                                 }
                             }
                         }
@@ -377,7 +378,7 @@ fn point_sub(a: Point, b: Point) -> Point {
 
                 let depth = layer.depth + 1;
                 // TODO: can't inline this since matches borrows self.layers
-                for (config, ranges) in injections {
+                for (language, config, ranges) in injections {
                     // Find an existing layer
                     let layer = self
                         .layers
@@ -392,6 +393,7 @@ fn point_sub(a: Point, b: Point) -> Point {
                     let layer_id = layer.unwrap_or_else(|| {
                         self.layers.insert(LanguageLayer {
                             tree: None,
+                            language,
                             config,
                             depth,
                             ranges,
@@ -419,6 +421,21 @@ pub fn tree(&self) -> &Tree {
         self.layers[self.root].tree()
     }
 
+    /// The language of the deepest layer covering the offset, which is the
+    /// language of an injection like the javascript of a `<script>` in html
+    pub fn language_at(&self, offset: usize) -> LapceLanguage {
+        self.layers
+            .values()
+            .filter(|layer| {
+                layer.ranges.iter().any(|range| {
+                    range.start_byte <= offset && offset <= range.end_byte
+                })
+            })
+            .max_by_key(|layer| layer.depth)
+            .unwrap_or(&self.layers[self.root])
+            .language
+    }
+
     pub fn try_tree(&self) -> Option<&Tree> {
         self.layers[self.root].try_tree()
     }
@@ -509,8 +526,6 @@ pub fn highlight_iter<'a>(
     // suggested_indent_for_line_at_buffer_row
     // suggested_indent_for_buffer_row
     // indent_level_for_line
-
-    // TODO: Folding
 }
 
 #[derive(Clone)]
@@ -550,7 +565,7 @@ pub fn from_language(language: LapceLanguage) -> Syntax {
             rev: 0,
             language,
             text: Rope::from(""),
-            layers: SyntaxLayers::new_empty(get_highlight_config(language)),
+            layers: SyntaxLayers::new_empty(language),
             lens: Self::lens_from_normal_lines(0, 0, 0, &Vec::new()),
             line_height: 0,
             lens_height: 0,
@@ -601,6 +616,7 @@ pub fn parse(
                                     Interval::new(start, end),
                                     Style {
                                         fg_color: Some(hl.to_string()),
+                                        modifiers: Default::default(),
                                     },
                                 );
                             }
@@ -686,6 +702,12 @@ pub fn lens_from_normal_lines(
         builder.build()
     }
 
+    /// The language at the offset, which is the injected language inside of
+    /// embedded code
+    pub fn language_at(&self, offset: usize) -> LapceLanguage {
+        self.layers.language_at(offset)
+    }
+
     pub fn find_matching_pair(&self, offset: usize) -> Option<usize> {
         let tree = self.layers.try_tree()?;
         let node = tree
@@ -767,6 +789,47 @@ fn find_tag_in_children(&self, node: Node, tag: &str) -> Option<usize> {
         None
     }
 
+    /// The offsets of the brackets in `start..end` with their nesting depth,
+    /// where the outermost bracket pairs have a depth of 0. Brackets in
+    /// strings and comments aren't nodes of their own, so they aren't counted.
+    pub fn bracket_depths(&self, start: usize, end: usize) -> Vec<(usize, usize)> {
+        let mut brackets = Vec::new();
+        if let Some(tree) = self.layers.try_tree() {
+            collect_bracket_depths(tree.root_node(), 0, start, end, &mut brackets);
+        }
+        brackets
+    }
+
+    /// The offsets of the open and close brackets of the innermost bracket
+    /// pair that encloses `offset`
+    pub fn enclosing_bracket_pair(&self, offset: usize) -> Option<(usize, usize)> {
+        let tree = self.layers.try_tree()?;
+        let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
+        loop {
+            if let Some((open, close)) = bracket_pair(node) {
+                if open.end_byte() <= offset && offset <= close.start_byte() {
+                    return Some((open.start_byte(), close.start_byte()));
+                }
+            }
+            node = node.parent()?;
+        }
+    }
+
+    /// The range of the smallest node that contains `start..end` and is
+    /// larger than it
+    pub fn expand_selection(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        let tree = self.layers.try_tree()?;
+        let mut node = tree.root_node().descendant_for_byte_range(start, end)?;
+        while node.start_byte() >= start && node.end_byte() <= end {
+            node = node.parent()?;
+        }
+        Some((node.start_byte(), node.end_byte()))
+    }
+
     pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         let tree = self.layers.try_tree()?;
         let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
@@ -784,6 +847,107 @@ pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         }
         Some(offsets)
     }
+
+    /// The ranges that can be folded, one for each line where a node
+    /// spanning multiple lines starts. A closing delimiter on the last line
+    /// of a node is kept visible, so folding `{ ... }` leaves the `}` shown.
+    pub fn folding_ranges(&self) -> Vec<FoldingRange> {
+        let tree = match self.layers.try_tree() {
+            Some(tree) => tree,
+            None => return Vec::new(),
+        };
+
+        let mut ends: HashMap<usize, usize> = HashMap::new();
+        let mut cursor = tree.walk();
+        if !cursor.goto_first_child() {
+            return Vec::new();
+        }
+        loop {
+            let node = cursor.node();
+            let start = node.start_position().row;
+            let end = node.end_position();
+            let mut end_line = if end.column == 0 {
+                end.row.saturating_sub(1)
+            } else {
+                end.row
+            };
+            if end_line > start {
+                if let Some(last) = node
+                    .child_count()
+                    .checked_sub(1)
+                    .and_then(|i| node.child(i))
+                {
+                    if !last.is_named() && last.start_position().row == end_line {
+                        end_line -= 1;
+                    }
+                }
+                if end_line > start {
+                    let e = ends.entry(start).or_insert(end_line);
+                    *e = (*e).min(end_line);
+                }
+                if cursor.goto_first_child() {
+                    continue;
+                }
+            }
+
+            // Nodes on a single line can't have children spanning lines
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() || cursor.node() == tree.root_node() {
+                    return ends
+                        .into_iter()
+                        .map(|(start, end)| FoldingRange::new(start, end))
+                        .sorted_by_key(|range| range.start_line)
+                        .collect();
+                }
+            }
+        }
+    }
+}
+
+fn is_bracket(node: Node, brackets: &[&str]) -> bool {
+    !node.is_named() && node.child_count() == 0 && brackets.contains(&node.kind())
+}
+
+/// The first open bracket and the last close bracket among the children of
+/// the node
+fn bracket_pair(node: Node) -> Option<(Node, Node)> {
+    let mut cursor = node.walk();
+    let children = node.children(&mut cursor).collect::<Vec<_>>();
+    let open = children
+        .iter()
+        .find(|child| is_bracket(**child, &["(", "[", "{"]))?;
+    let close = children
+        .iter()
+        .rev()
+        .find(|child| is_bracket(**child, &[")", "]", "}"]))?;
+    Some((*open, *close))
+}
+
+/// Collect the brackets in `start..end` among the descendants of `node`,
+/// which is inside `depth` bracket pairs
+fn collect_bracket_depths(
+    node: Node,
+    depth: usize,
+    start: usize,
+    end: usize,
+    brackets: &mut Vec<(usize, usize)>,
+) {
+    let child_depth = if bracket_pair(node).is_some() {
+        depth + 1
+    } else {
+        depth
+    };
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.end_byte() <= start || child.start_byte() >= end {
+            continue;
+        }
+        if is_bracket(child, &["(", "[", "{", ")", "]", "}"]) {
+            brackets.push((child.start_byte(), depth));
+        } else {
+            collect_bracket_depths(child, child_depth, start, end, brackets);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -804,6 +968,32 @@ fn test_lens() {
         assert_eq!(33, lens.height_of_line(5));
     }
 
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn test_brackets() {
+        let text = Rope::from("fn f() { g(\"(\", [1]) }");
+        let mut syntax = Syntax::from_language(LapceLanguage::Rust);
+        syntax.parse(0, text.clone(), None);
+
+        assert_eq!(
+            syntax.bracket_depths(0, text.len()),
+            vec![
+                (4, 0),
+                (5, 0),
+                (7, 0),
+                (10, 1),
+                (16, 2),
+                (18, 2),
+                (19, 1),
+                (21, 0)
+            ]
+        );
+        assert_eq!(syntax.bracket_depths(10, 17), vec![(10, 1), (16, 2)]);
+        assert_eq!(syntax.enclosing_bracket_pair(12), Some((10, 19)));
+        assert_eq!(syntax.enclosing_bracket_pair(8), Some((7, 21)));
+        assert_eq!(syntax.enclosing_bracket_pair(1), None);
+    }
+
     #[test]
     fn test_lens_iter() {
         let lens = Syntax::lens_from_normal_lines(5, 25, 2, &[0, 2, 4]);
@@ -0,0 +1,577 @@
+use std::{cmp::Ordering, ops::Range};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineTransform {
+    SortAscending,
+    SortDescending,
+    /// Sort by the number each line starts with, lines without one first
+    SortNumerically,
+    DeleteDuplicates,
+    Reverse,
+}
+
+impl LineTransform {
+    pub fn apply<'a>(&self, mut lines: Vec<&'a str>) -> Vec<&'a str> {
+        match self {
+            LineTransform::SortAscending => lines.sort(),
+            LineTransform::SortDescending => lines.sort_by(|a, b| b.cmp(a)),
+            LineTransform::SortNumerically => {
+                lines.sort_by(|a, b| match (leading_number(a), leading_number(b)) {
+                    (Some(a), Some(b)) => {
+                        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+                    }
+                    (Some(_), None) => Ordering::Greater,
+                    (None, Some(_)) => Ordering::Less,
+                    (None, None) => Ordering::Equal,
+                })
+            }
+            LineTransform::DeleteDuplicates => {
+                let mut seen = std::collections::HashSet::new();
+                lines.retain(|line| seen.insert(*line));
+            }
+            LineTransform::Reverse => lines.reverse(),
+        }
+        lines
+    }
+}
+
+/// The number at the start of the line, ignoring leading whitespace
+fn leading_number(line: &str) -> Option<f64> {
+    let line = line.trim_start();
+    let mut end = 0;
+    let mut seen_dot = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '-' | '+' if i == 0 => {}
+            '.' if !seen_dot => seen_dot = true,
+            c if c.is_ascii_digit() => {}
+            _ => break,
+        }
+        end = i + c.len_utf8();
+    }
+    line[..end].parse().ok()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaseTransform {
+    Upper,
+    Lower,
+    Title,
+    Snake,
+    Camel,
+}
+
+impl CaseTransform {
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            CaseTransform::Upper => text.to_uppercase(),
+            CaseTransform::Lower => text.to_lowercase(),
+            CaseTransform::Title => title_case(text),
+            CaseTransform::Snake => map_identifiers(text, |words| {
+                words
+                    .iter()
+                    .map(|word| word.to_lowercase())
+                    .collect::<Vec<_>>()
+                    .join("_")
+            }),
+            CaseTransform::Camel => map_identifiers(text, |words| {
+                words
+                    .iter()
+                    .enumerate()
+                    .map(|(i, word)| {
+                        if i == 0 {
+                            word.to_lowercase()
+                        } else {
+                            capitalize(word)
+                        }
+                    })
+                    .collect()
+            }),
+        }
+    }
+}
+
+/// Upper case the first letter of every word and lower case the rest
+fn title_case(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut word_start = true;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if word_start {
+                result.extend(c.to_uppercase());
+            } else {
+                result.extend(c.to_lowercase());
+            }
+            word_start = false;
+        } else {
+            result.push(c);
+            // keeps "don't" from becoming "Don'T"
+            word_start = c != '\'';
+        }
+    }
+    result
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(|c| c.to_lowercase()))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Change the case of the replacement of a match to follow the case of the
+/// matched text, so that replacing "foo" with "bar" turns "Foo" into "Bar"
+/// and "FOO" into "BAR"
+pub fn preserve_case(matched: &str, replacement: &str) -> String {
+    let mut letters = matched.chars().filter(|c| c.is_alphabetic());
+    let first = match letters.next() {
+        Some(first) => first,
+        None => return replacement.to_string(),
+    };
+    let rest = letters.collect::<Vec<_>>();
+    if !rest.is_empty() && matched.to_uppercase() == matched {
+        return replacement.to_uppercase();
+    }
+    if matched.to_lowercase() == matched {
+        return replacement.to_lowercase();
+    }
+
+    // Mixed case only decides the case of the first letter, which keeps the
+    // camel case of a replacement like "fooBar"
+    let mut chars = replacement.chars();
+    match chars.next() {
+        Some(c) if first.is_uppercase() => c.to_uppercase().chain(chars).collect(),
+        Some(c) => c.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Rewrite every identifier in the text from the words it is made of,
+/// keeping its leading underscores and everything between identifiers
+fn map_identifiers(text: &str, join: impl Fn(&[&str]) -> String) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+        let is_identifier = c.is_alphanumeric() || c == '_';
+        match start {
+            None if is_identifier => start = Some(i),
+            Some(s) if !is_identifier => {
+                let identifier = &text[s..i];
+                let body = identifier.trim_start_matches('_');
+                result.push_str(&identifier[..identifier.len() - body.len()]);
+                result.push_str(&join(&split_words(body)));
+                start = None;
+            }
+            _ => {}
+        }
+        if start.is_none() && i < text.len() {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Split an identifier into its words at underscores and at the humps of
+/// camel case, so "parseHTTPResponse_v2" is "parse", "HTTP", "Response", "v2"
+fn split_words(identifier: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = identifier.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if let Some(s) = start.take() {
+                words.push(&identifier[s..offset]);
+            }
+            continue;
+        }
+        if let Some(s) = start {
+            let prev = chars[i - 1].1;
+            let next_is_lower = chars
+                .get(i + 1)
+                .map(|(_, n)| n.is_lowercase())
+                .unwrap_or(false);
+            if c.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_numeric()
+                    || (prev.is_uppercase() && next_is_lower))
+            {
+                words.push(&identifier[s..offset]);
+                start = Some(offset);
+            }
+        } else {
+            start = Some(offset);
+        }
+    }
+    if let Some(s) = start {
+        words.push(&identifier[s..]);
+    }
+    words
+}
+
+/// How two lines are joined: the length of the first line that is kept, the
+/// length of the start of the second line that is dropped, and the separator
+/// put between them. The indentation of the second line is dropped, and so
+/// is its comment leader when both lines are comments.
+pub fn join_lines(
+    first: &str,
+    second: &str,
+    comment_token: &str,
+) -> (usize, usize, &'static str) {
+    let kept = first.trim_end();
+    let mut rest = second.trim_start();
+    if !comment_token.is_empty() && kept.trim_start().starts_with(comment_token) {
+        if let Some(comment) = rest.strip_prefix(comment_token) {
+            // doc comment leaders like `///` repeat the comment token
+            rest = comment
+                .trim_start_matches(|c| comment_token.contains(c))
+                .trim_start();
+        }
+    }
+    let separator = if kept.is_empty()
+        || rest.is_empty()
+        || kept.ends_with(|c| matches!(c, '(' | '['))
+        || rest.starts_with(|c| matches!(c, ')' | ']'))
+    {
+        ""
+    } else {
+        " "
+    };
+    (kept.len(), second.len() - rest.len(), separator)
+}
+
+/// The byte ranges of the words of the text, which are runs of alphanumeric
+/// characters and underscores
+fn word_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+        let is_word = c.is_alphanumeric() || c == '_';
+        match start {
+            None if is_word => start = Some(i),
+            Some(s) if !is_word => {
+                words.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// The two words that are swapped when transposing words at `offset`: the
+/// word around or before the offset and the one after it, or the last two
+/// words when there is none after it
+pub fn transpose_words(
+    text: &str,
+    offset: usize,
+) -> Option<(Range<usize>, Range<usize>)> {
+    let words = word_ranges(text);
+    let i = words
+        .iter()
+        .position(|w| w.start < offset && offset < w.end)
+        .or_else(|| words.iter().rposition(|w| w.end <= offset))
+        .unwrap_or(0);
+    let i = if i + 1 < words.len() {
+        i
+    } else {
+        i.checked_sub(1)?
+    };
+    Some((words.get(i)?.clone(), words.get(i + 1)?.clone()))
+}
+
+/// Split the text at the separators that aren't nested in brackets or
+/// strings
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if let Some(quote) = in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                in_string = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '`' => in_string = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Put each of the comma separated items in the innermost brackets around
+/// `offset` of the line on a line of its own, indented one unit deeper than
+/// the line. Returns the range between the brackets and its replacement.
+pub fn split_arguments(
+    line: &str,
+    offset: usize,
+    indent_unit: &str,
+    line_ending: &str,
+) -> Option<(Range<usize>, String)> {
+    let mut stack = Vec::new();
+    let mut pair = None;
+    let mut in_string = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if let Some(quote) = in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                in_string = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '`' => in_string = Some(c),
+            '(' | '[' | '{' => stack.push((i, c)),
+            ')' | ']' | '}' => {
+                if let Some((open, open_char)) = stack.pop() {
+                    let matched = matches!(
+                        (open_char, c),
+                        ('(', ')') | ('[', ']') | ('{', '}')
+                    );
+                    // the innermost of the pairs around the offset closes
+                    // first
+                    if matched && open < offset && offset <= i && pair.is_none() {
+                        pair = Some((open, i));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let (open, close) = pair?;
+    let mut items: Vec<&str> = split_top_level(&line[open + 1..close], ',')
+        .into_iter()
+        .map(|item| item.trim())
+        .collect();
+    let trailing_comma = items.len() > 1 && items.last() == Some(&"");
+    if trailing_comma {
+        items.pop();
+    }
+    if items.iter().all(|item| item.is_empty()) {
+        return None;
+    }
+
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let mut text = String::new();
+    for (i, item) in items.iter().enumerate() {
+        text.push_str(line_ending);
+        text.push_str(indent);
+        text.push_str(indent_unit);
+        text.push_str(item);
+        if i + 1 < items.len() || trailing_comma {
+            text.push(',');
+        }
+    }
+    text.push_str(line_ending);
+    text.push_str(indent);
+    Some((open + 1..close, text))
+}
+
+/// The indentation lines moved past the `crossed` line get when that line
+/// opens or closes a block, so that they end up inside or outside of it
+pub fn moved_lines_indent(
+    crossed: &str,
+    up: bool,
+    indent_unit: &str,
+) -> Option<String> {
+    let trimmed = crossed.trim();
+    let indent = &crossed[..crossed.len() - crossed.trim_start().len()];
+    let opens = trimmed.ends_with(|c| matches!(c, '{' | '(' | '['));
+    let closes = trimmed.starts_with(|c| matches!(c, '}' | ')' | ']'));
+    // moving up past the end of a block or down past its start enters it
+    let (enters, leaves) = if up { (closes, opens) } else { (opens, closes) };
+    if enters {
+        Some(format!("{indent}{indent_unit}"))
+    } else if leaves {
+        Some(indent.to_string())
+    } else {
+        None
+    }
+}
+
+/// Replace the indentation the lines have in common with `indent`, leaving
+/// blank lines alone. Also returns by how much the indentation of the lines
+/// that aren't blank changed.
+pub fn reindent_lines(lines: &[String], indent: &str) -> (Vec<String>, isize) {
+    let common = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let lines = lines
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                line.clone()
+            } else {
+                format!("{indent}{}", &line[common..])
+            }
+        })
+        .collect();
+    (lines, indent.len() as isize - common as isize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sort_lines() {
+        let lines = vec!["b", "10 c", "a", "9 d", "-1 e"];
+        assert_eq!(
+            LineTransform::SortAscending.apply(lines.clone()),
+            vec!["-1 e", "10 c", "9 d", "a", "b"]
+        );
+        assert_eq!(
+            LineTransform::SortDescending.apply(lines.clone()),
+            vec!["b", "a", "9 d", "10 c", "-1 e"]
+        );
+        assert_eq!(
+            LineTransform::SortNumerically.apply(lines),
+            vec!["b", "a", "-1 e", "9 d", "10 c"]
+        );
+    }
+
+    #[test]
+    fn test_delete_duplicates_and_reverse() {
+        let lines = vec!["a", "b", "a", "c", "b"];
+        assert_eq!(
+            LineTransform::DeleteDuplicates.apply(lines.clone()),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(
+            LineTransform::Reverse.apply(lines),
+            vec!["b", "c", "a", "b", "a"]
+        );
+    }
+
+    #[test]
+    fn test_case() {
+        assert_eq!(CaseTransform::Upper.apply("abc Def"), "ABC DEF");
+        assert_eq!(CaseTransform::Lower.apply("abc Def"), "abc def");
+        assert_eq!(
+            CaseTransform::Title.apply("hello WORLD, don't"),
+            "Hello World, Don't"
+        );
+    }
+
+    #[test]
+    fn test_identifier_case() {
+        let text = "let parseHTTPResponse_v2 = _fooBar(some_value);";
+        assert_eq!(
+            CaseTransform::Snake.apply(text),
+            "let parse_http_response_v2 = _foo_bar(some_value);"
+        );
+        assert_eq!(
+            CaseTransform::Camel.apply(text),
+            "let parseHttpResponseV2 = _fooBar(someValue);"
+        );
+    }
+
+    #[test]
+    fn test_preserve_case() {
+        assert_eq!(preserve_case("foo", "bar"), "bar");
+        assert_eq!(preserve_case("Foo", "bar"), "Bar");
+        assert_eq!(preserve_case("FOO", "bar"), "BAR");
+        assert_eq!(preserve_case("foo", "BAR"), "bar");
+        assert_eq!(preserve_case("Foo", "barBaz"), "BarBaz");
+        assert_eq!(preserve_case("fooBar", "BazQux"), "bazQux");
+        assert_eq!(preserve_case("F", "bar"), "Bar");
+        assert_eq!(preserve_case("123", "Bar"), "Bar");
+    }
+
+    #[test]
+    fn test_join_lines() {
+        assert_eq!(join_lines("let a = 1;  ", "    b", "//"), (10, 4, " "));
+        assert_eq!(join_lines("foo(", "  a,", "//"), (4, 2, ""));
+        assert_eq!(join_lines("  a", "  )", "//"), (3, 2, ""));
+        assert_eq!(join_lines("  // foo", "  /// bar", "//"), (8, 6, " "));
+        assert_eq!(join_lines("a", "  // bar", "//"), (1, 2, " "));
+        assert_eq!(join_lines("a", "   ", "//"), (1, 3, ""));
+    }
+
+    #[test]
+    fn test_transpose_words() {
+        let text = "foo(bar, baz_1)";
+        assert_eq!(transpose_words(text, 0), Some((0..3, 4..7)));
+        assert_eq!(transpose_words(text, 5), Some((4..7, 9..14)));
+        assert_eq!(transpose_words(text, 8), Some((4..7, 9..14)));
+        assert_eq!(transpose_words(text, 15), Some((4..7, 9..14)));
+        assert_eq!(transpose_words("foo", 1), None);
+    }
+
+    #[test]
+    fn test_split_arguments() {
+        let line = "    foo(a, bar(1, 2), \"c, d\")";
+        assert_eq!(
+            split_arguments(line, 9, "    ", "\n"),
+            Some((
+                8..28,
+                "\n        a,\n        bar(1, 2),\n        \"c, d\"\n    "
+                    .to_string()
+            ))
+        );
+        assert_eq!(
+            split_arguments(line, 16, "\t", "\n"),
+            Some((15..19, "\n    \t1,\n    \t2\n    ".to_string()))
+        );
+        assert_eq!(
+            split_arguments("[a, b,]", 1, "  ", "\n"),
+            Some((1..6, "\n  a,\n  b,\n".to_string()))
+        );
+        assert_eq!(split_arguments("foo()", 4, "  ", "\n"), None);
+        assert_eq!(split_arguments("foo(a)", 0, "  ", "\n"), None);
+    }
+
+    #[test]
+    fn test_moved_lines_indent() {
+        assert_eq!(
+            moved_lines_indent("    }", true, "    ").unwrap(),
+            "        "
+        );
+        assert_eq!(moved_lines_indent("  if a {", true, "  ").unwrap(), "  ");
+        assert_eq!(moved_lines_indent("  if a {", false, "  ").unwrap(), "    ");
+        assert_eq!(
+            moved_lines_indent("  } else {", false, "  ").unwrap(),
+            "    "
+        );
+        assert_eq!(moved_lines_indent("  };", false, "  ").unwrap(), "  ");
+        assert_eq!(moved_lines_indent("  a();", true, "  "), None);
+    }
+
+    #[test]
+    fn test_reindent_lines() {
+        let lines = vec![
+            "        a {".to_string(),
+            "".to_string(),
+            "            b".to_string(),
+        ];
+        let (lines, shift) = reindent_lines(&lines, "    ");
+        assert_eq!(lines, vec!["    a {", "", "        b"]);
+        assert_eq!(shift, -4);
+    }
+}
@@ -142,6 +142,64 @@ pub fn next_boundary(&mut self) -> Option<usize> {
         None
     }
 
+    /// Get the next boundary, also stopping at the start of each subword of
+    /// camelCase and snake_case identifiers, and set the cursor there.
+    pub fn next_subword_boundary(&mut self) -> Option<usize> {
+        if let Some(ch) = self.inner.next_codepoint() {
+            let mut prev = ch;
+            let mut prop = get_word_property(ch);
+            let mut candidate = self.inner.pos();
+            while let Some(next) = self.inner.next_codepoint() {
+                let prop_next = get_word_property(next);
+                if classify_boundary(prop, prop_next).is_start()
+                    || (prop == WordProperty::Other
+                        && prop_next == WordProperty::Other
+                        && is_subword_start(
+                            prev,
+                            next,
+                            self.inner.peek_next_codepoint(),
+                        ))
+                {
+                    break;
+                }
+                prev = next;
+                prop = prop_next;
+                candidate = self.inner.pos();
+            }
+            self.inner.set(candidate);
+            return Some(candidate);
+        }
+        None
+    }
+
+    /// Get the previous boundary, also stopping at the start of each subword
+    /// of camelCase and snake_case identifiers, and set the cursor there.
+    pub fn prev_subword_boundary(&mut self) -> Option<usize> {
+        let mut after = self.inner.peek_next_codepoint();
+        if let Some(ch) = self.inner.prev_codepoint() {
+            let mut next = ch;
+            let mut prop = get_word_property(ch);
+            let mut candidate = self.inner.pos();
+            while let Some(prev) = self.inner.prev_codepoint() {
+                let prop_prev = get_word_property(prev);
+                if classify_boundary(prop_prev, prop).is_start()
+                    || (prop_prev == WordProperty::Other
+                        && prop == WordProperty::Other
+                        && is_subword_start(prev, next, after))
+                {
+                    break;
+                }
+                after = Some(next);
+                next = prev;
+                prop = prop_prev;
+                candidate = self.inner.pos();
+            }
+            self.inner.set(candidate);
+            return Some(candidate);
+        }
+        None
+    }
+
     pub fn end_boundary(&mut self) -> Option<usize> {
         self.inner.next_codepoint();
         if let Some(ch) = self.inner.next_codepoint() {
@@ -239,6 +297,49 @@ pub fn select_word(&mut self) -> (usize, usize) {
     }
 }
 
+/// The range of the subword of `word` around `offset`, leaving out the
+/// underscores that separate it from the next subword
+pub fn select_subword(word: &str, offset: usize) -> (usize, usize) {
+    let mut start = 0;
+    let mut end = word.len();
+    let mut chars = word.char_indices().peekable();
+    let mut prev = None;
+    while let Some((i, ch)) = chars.next() {
+        let next = chars.peek().map(|(_, c)| *c);
+        if let Some(prev) = prev {
+            if is_subword_start(prev, ch, next) {
+                if i > offset {
+                    end = i;
+                    break;
+                }
+                start = i;
+            }
+        }
+        prev = Some(ch);
+    }
+
+    let trimmed = word[start..end].trim_end_matches('_');
+    if !trimmed.is_empty() && offset < start + trimmed.len() {
+        end = start + trimmed.len();
+    }
+    (start, end)
+}
+
+/// Whether `ch` starts a new subword after `prev`, which is after an
+/// underscore, at a lower to upper case change, or at the last upper case
+/// letter of an acronym followed by a lower case letter, like the `S` of
+/// `HTTPServer`
+fn is_subword_start(prev: char, ch: char, next: Option<char>) -> bool {
+    if ch == '_' {
+        return false;
+    }
+    prev == '_'
+        || (ch.is_uppercase() && (prev.is_lowercase() || prev.is_numeric()))
+        || (ch.is_uppercase()
+            && prev.is_uppercase()
+            && next.map(|c| c.is_lowercase()).unwrap_or(false))
+}
+
 pub fn get_word_property(codepoint: char) -> WordProperty {
     if codepoint <= ' ' {
         if codepoint == '\r' {
@@ -1,4 +1,4 @@
-use super::WordCursor;
+use super::{select_subword, WordCursor};
 use crate::buffer::Buffer;
 
 #[test]
@@ -83,3 +83,43 @@ fn next_boundary_failure_to_be_fixed_2() {
     // assert_eq!(cursor.next_boundary(), Some(6));
     // assert_eq!(cursor.next_boundary(), None);
 }
+
+#[test]
+fn test_next_subword_boundary() {
+    let buffer = Buffer::new("fooBar HTTPServer foo_bar2Baz");
+    //                      ->0123456789012345678901234567<-
+    let mut cursor = WordCursor::new(buffer.text(), 0);
+    assert_eq!(cursor.next_subword_boundary(), Some(3));
+    assert_eq!(cursor.next_subword_boundary(), Some(7));
+    assert_eq!(cursor.next_subword_boundary(), Some(11));
+    assert_eq!(cursor.next_subword_boundary(), Some(18));
+    assert_eq!(cursor.next_subword_boundary(), Some(22));
+    assert_eq!(cursor.next_subword_boundary(), Some(26));
+    assert_eq!(cursor.next_subword_boundary(), Some(buffer.len()));
+    assert_eq!(cursor.next_subword_boundary(), None);
+}
+
+#[test]
+fn test_prev_subword_boundary() {
+    let buffer = Buffer::new("fooBar HTTPServer foo_bar2Baz");
+    let mut cursor = WordCursor::new(buffer.text(), buffer.len());
+    assert_eq!(cursor.prev_subword_boundary(), Some(26));
+    assert_eq!(cursor.prev_subword_boundary(), Some(22));
+    assert_eq!(cursor.prev_subword_boundary(), Some(18));
+    assert_eq!(cursor.prev_subword_boundary(), Some(11));
+    assert_eq!(cursor.prev_subword_boundary(), Some(7));
+    assert_eq!(cursor.prev_subword_boundary(), Some(3));
+    assert_eq!(cursor.prev_subword_boundary(), Some(0));
+    assert_eq!(cursor.prev_subword_boundary(), None);
+}
+
+#[test]
+fn test_select_subword() {
+    assert_eq!(select_subword("fooBar", 1), (0, 3));
+    assert_eq!(select_subword("fooBar", 3), (3, 6));
+    assert_eq!(select_subword("fooBar", 6), (3, 6));
+    assert_eq!(select_subword("HTTPServer", 2), (0, 4));
+    assert_eq!(select_subword("foo_bar", 1), (0, 3));
+    assert_eq!(select_subword("foo_bar", 3), (0, 4));
+    assert_eq!(select_subword("foo_bar", 5), (4, 7));
+}
@@ -0,0 +1,274 @@
+use crate::fold::FoldedLines;
+
+/// How a line longer than the wrap column is broken into several visual
+/// lines. The first visual line holds `column` columns, and the following
+/// ones are indented by `indent` columns and hold the rest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LineWrap {
+    pub column: usize,
+    pub indent: usize,
+}
+
+impl LineWrap {
+    /// `line_indent` is the indentation of the line itself, which the
+    /// continuation lines keep, and `wrap_indent` is added on top of it.
+    /// The indent is capped at half the column so that very indented lines
+    /// still make progress on every visual line.
+    pub fn new(column: usize, line_indent: usize, wrap_indent: usize) -> Self {
+        let column = column.max(2);
+        Self {
+            column,
+            indent: (line_indent + wrap_indent).min(column / 2),
+        }
+    }
+
+    fn continuation_width(&self) -> usize {
+        self.column - self.indent
+    }
+
+    /// The number of visual lines of a line that is `width` columns wide
+    pub fn num_segments(&self, width: usize) -> usize {
+        if width <= self.column {
+            return 1;
+        }
+        let rest = width - self.column;
+        let continuation_width = self.continuation_width();
+        1 + (rest + continuation_width - 1) / continuation_width
+    }
+
+    /// The visual line, within the wrapped line, that shows column `col` of
+    /// a line that is `width` columns wide
+    pub fn segment_of_col(&self, col: usize, width: usize) -> usize {
+        let segment = if col < self.column {
+            0
+        } else {
+            1 + (col - self.column) / self.continuation_width()
+        };
+        segment.min(self.num_segments(width) - 1)
+    }
+
+    /// The column of the line where the visual line `segment` starts
+    pub fn segment_start(&self, segment: usize) -> usize {
+        if segment == 0 {
+            0
+        } else {
+            self.column + (segment - 1) * self.continuation_width()
+        }
+    }
+}
+
+/// The visual lines of a document with soft wrapped lines, where the lines
+/// hidden by folds take no room. A line can also have virtual lines above
+/// it, like the code lenses of a function, which show no text of the buffer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VisualLines {
+    /// The first visual line of each buffer line, virtual lines included,
+    /// with one extra entry for the end of the document
+    starts: Vec<usize>,
+    /// The number of virtual lines above each buffer line
+    virtual_lines: Vec<usize>,
+}
+
+impl VisualLines {
+    /// `segments` is the number of visual lines of each buffer line
+    pub fn new(
+        segments: impl IntoIterator<Item = usize>,
+        folded: &FoldedLines,
+    ) -> Self {
+        Self::with_virtual_lines(segments.into_iter().map(|n| (0, n)), folded)
+    }
+
+    /// `lines` is the number of virtual lines above each buffer line, and
+    /// the number of visual lines of its text
+    pub fn with_virtual_lines(
+        lines: impl IntoIterator<Item = (usize, usize)>,
+        folded: &FoldedLines,
+    ) -> Self {
+        let mut starts = Vec::new();
+        let mut virtual_lines = Vec::new();
+        let mut total = 0;
+        for (line, (above, n)) in lines.into_iter().enumerate() {
+            starts.push(total);
+            if folded.is_hidden(line) {
+                virtual_lines.push(0);
+            } else {
+                virtual_lines.push(above);
+                total += above + n;
+            }
+        }
+        starts.push(total);
+        Self {
+            starts,
+            virtual_lines,
+        }
+    }
+
+    fn num_lines(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    /// The first visual line of the text of `line`, below its virtual lines
+    pub fn visual_line(&self, line: usize) -> usize {
+        if line >= self.num_lines() {
+            return self.starts[self.num_lines()];
+        }
+        self.starts[line] + self.virtual_lines[line]
+    }
+
+    /// The number of visual lines `line` is wrapped into
+    pub fn num_segments(&self, line: usize) -> usize {
+        if line >= self.num_lines() {
+            return 1;
+        }
+        self.starts[line + 1] - self.visual_line(line)
+    }
+
+    /// The number of virtual lines above `line`
+    pub fn num_virtual_lines(&self, line: usize) -> usize {
+        self.virtual_lines.get(line).copied().unwrap_or(0)
+    }
+
+    /// The buffer line shown at `visual_line`, and which of its visual
+    /// lines it is. The virtual lines above a line belong to it, as its
+    /// first visual line.
+    pub fn line_of_visual_line(&self, visual_line: usize) -> (usize, usize) {
+        if self.num_lines() == 0 {
+            return (0, 0);
+        }
+        // Hidden lines start at the same visual line as the line after
+        // them, so the last line starting at or before it is the shown one
+        let line = self
+            .starts
+            .partition_point(|start| *start <= visual_line)
+            .saturating_sub(1)
+            .min(self.num_lines() - 1);
+        let segment = visual_line
+            .saturating_sub(self.visual_line(line))
+            .min(self.num_segments(line).saturating_sub(1));
+        (line, segment)
+    }
+
+    /// If `visual_line` is a virtual line, which shows no text
+    pub fn is_virtual(&self, visual_line: usize) -> bool {
+        let (line, _) = self.line_of_visual_line(visual_line);
+        visual_line < self.visual_line(line)
+    }
+
+    /// The visual line `count` lines of text below or above `visual_line`,
+    /// skipping the virtual lines
+    pub fn move_visual_line(
+        &self,
+        visual_line: usize,
+        count: usize,
+        down: bool,
+    ) -> usize {
+        let last = self.num_visual_lines().saturating_sub(1);
+        let mut visual_line = visual_line.min(last);
+        for _ in 0..count {
+            if down {
+                if visual_line >= last {
+                    break;
+                }
+                visual_line += 1;
+                while visual_line < last && self.is_virtual(visual_line) {
+                    visual_line += 1;
+                }
+            } else {
+                let (line, segment) = self.line_of_visual_line(visual_line);
+                if segment > 0 {
+                    visual_line -= 1;
+                } else if self.starts[line] > 0 {
+                    // the last visual line of the line above
+                    visual_line = self.starts[line] - 1;
+                } else {
+                    break;
+                }
+            }
+        }
+        visual_line
+    }
+
+    /// The number of visual lines of the whole document
+    pub fn num_visual_lines(&self) -> usize {
+        *self.starts.last().unwrap_or(&0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LineWrap, VisualLines};
+    use crate::fold::{FoldedLines, FoldingRange};
+
+    #[test]
+    fn test_line_wrap() {
+        let wrap = LineWrap::new(10, 2, 2);
+        assert_eq!(wrap.indent, 4);
+
+        assert_eq!(wrap.num_segments(0), 1);
+        assert_eq!(wrap.num_segments(10), 1);
+        assert_eq!(wrap.num_segments(11), 2);
+        assert_eq!(wrap.num_segments(16), 2);
+        assert_eq!(wrap.num_segments(17), 3);
+
+        assert_eq!(wrap.segment_start(0), 0);
+        assert_eq!(wrap.segment_start(1), 10);
+        assert_eq!(wrap.segment_start(2), 16);
+
+        assert_eq!(wrap.segment_of_col(9, 20), 0);
+        assert_eq!(wrap.segment_of_col(10, 20), 1);
+        assert_eq!(wrap.segment_of_col(16, 20), 2);
+        // The end of a line that exactly fills the column stays on it
+        assert_eq!(wrap.segment_of_col(10, 10), 0);
+
+        assert_eq!(LineWrap::new(10, 8, 4).indent, 5);
+    }
+
+    #[test]
+    fn test_visual_lines() {
+        let folded = FoldedLines::new([FoldingRange::new(1, 2)]);
+        let lines = VisualLines::new([2, 1, 3, 3, 1], &folded);
+
+        assert_eq!(lines.num_visual_lines(), 7);
+        assert_eq!(lines.visual_line(0), 0);
+        assert_eq!(lines.visual_line(1), 2);
+        assert_eq!(lines.visual_line(3), 3);
+        assert_eq!(lines.visual_line(4), 6);
+        assert_eq!(lines.num_segments(2), 0);
+
+        assert_eq!(lines.line_of_visual_line(0), (0, 0));
+        assert_eq!(lines.line_of_visual_line(1), (0, 1));
+        assert_eq!(lines.line_of_visual_line(2), (1, 0));
+        assert_eq!(lines.line_of_visual_line(3), (3, 0));
+        assert_eq!(lines.line_of_visual_line(5), (3, 2));
+        assert_eq!(lines.line_of_visual_line(6), (4, 0));
+        assert_eq!(lines.line_of_visual_line(9), (4, 0));
+    }
+
+    #[test]
+    fn test_virtual_lines() {
+        let folded = FoldedLines::new([FoldingRange::new(1, 2)]);
+        let lines = VisualLines::with_virtual_lines(
+            [(1, 2), (0, 1), (1, 3), (1, 1)],
+            &folded,
+        );
+
+        assert_eq!(lines.num_visual_lines(), 6);
+        assert_eq!(lines.visual_line(0), 1);
+        assert_eq!(lines.visual_line(1), 3);
+        assert_eq!(lines.visual_line(3), 5);
+        assert_eq!(lines.num_segments(0), 2);
+        assert_eq!(lines.num_virtual_lines(2), 0);
+
+        assert!(lines.is_virtual(0));
+        assert!(!lines.is_virtual(1));
+        assert!(lines.is_virtual(4));
+        assert_eq!(lines.line_of_visual_line(0), (0, 0));
+        assert_eq!(lines.line_of_visual_line(2), (0, 1));
+        assert_eq!(lines.line_of_visual_line(4), (3, 0));
+
+        assert_eq!(lines.move_visual_line(1, 1, true), 2);
+        assert_eq!(lines.move_visual_line(2, 2, true), 5);
+        assert_eq!(lines.move_visual_line(5, 2, false), 2);
+        assert_eq!(lines.move_visual_line(1, 1, false), 1);
+    }
+}
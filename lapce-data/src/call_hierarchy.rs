@@ -0,0 +1,362 @@
+use std::{path::PathBuf, sync::Arc};
+
+use druid::{ExtEventSink, Target, WidgetId};
+use lapce_rpc::{plugin::PluginId, proxy::ProxyResponse};
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall,
+    Position,
+};
+
+use crate::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    proxy::{path_from_url, LapceProxy},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallHierarchyDirection {
+    /// The functions calling the item
+    Incoming,
+    /// The functions the item calls
+    Outgoing,
+}
+
+/// A function in the call hierarchy, below the function it calls or is
+/// called by
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallHierarchyNode {
+    pub item: CallHierarchyItem,
+    /// Where the call is, or where the function is for the root
+    pub path: PathBuf,
+    pub position: Position,
+    pub open: bool,
+    /// The calls of the function, which are only asked for when the node is
+    /// first opened
+    pub children: Option<Vec<CallHierarchyNode>>,
+}
+
+impl CallHierarchyNode {
+    pub fn new(item: CallHierarchyItem) -> Self {
+        Self {
+            path: path_from_url(&item.uri),
+            position: item.selection_range.start,
+            item,
+            open: false,
+            children: None,
+        }
+    }
+
+    /// The caller, with the first place it calls the parent
+    pub fn incoming(call: CallHierarchyIncomingCall) -> Self {
+        let mut node = Self::new(call.from);
+        if let Some(range) = call.from_ranges.first() {
+            node.position = range.start;
+        }
+        node
+    }
+
+    /// The function called by the parent, with the first place the parent
+    /// calls it, which is in the file of the parent
+    pub fn outgoing(
+        parent: &CallHierarchyItem,
+        call: CallHierarchyOutgoingCall,
+    ) -> Self {
+        let mut node = Self::new(call.to);
+        if let Some(range) = call.from_ranges.first() {
+            node.path = path_from_url(&parent.uri);
+            node.position = range.start;
+        }
+        node
+    }
+}
+
+/// A visible row of the call hierarchy panel
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallHierarchyRow {
+    /// The index of the child to follow at each level from the root
+    pub indices: Vec<usize>,
+    pub depth: usize,
+}
+
+/// The functions or types shown by a hierarchy panel, whose children are
+/// asked for as they are opened
+#[derive(Clone, Default)]
+pub struct HierarchyTree {
+    pub root: Option<Arc<CallHierarchyNode>>,
+    /// Counts the roots that were shown, so that the children that arrive for
+    /// an earlier root are dropped
+    pub generation: u64,
+}
+
+impl HierarchyTree {
+    /// Show the hierarchy of the item, with the root open
+    pub fn set_root(&mut self, item: CallHierarchyItem) {
+        let mut root = CallHierarchyNode::new(item);
+        root.open = true;
+        self.root = Some(Arc::new(root));
+        self.generation += 1;
+    }
+
+    /// Drop the children of the root, to ask for them again
+    pub fn reset(&mut self) {
+        if let Some(root) = self.root.as_mut() {
+            let root = Arc::make_mut(root);
+            root.open = true;
+            root.children = None;
+        }
+        self.generation += 1;
+    }
+
+    pub fn node(&self, indices: &[usize]) -> Option<&CallHierarchyNode> {
+        let mut node = self.root.as_deref()?;
+        for i in indices {
+            node = node.children.as_ref()?.get(*i)?;
+        }
+        Some(node)
+    }
+
+    fn node_mut(&mut self, indices: &[usize]) -> Option<&mut CallHierarchyNode> {
+        let mut node = Arc::make_mut(self.root.as_mut()?);
+        for i in indices {
+            node = node.children.as_mut()?.get_mut(*i)?;
+        }
+        Some(node)
+    }
+
+    /// The open nodes and their children, in the order they are shown
+    pub fn rows(&self) -> Vec<CallHierarchyRow> {
+        let mut rows = Vec::new();
+        if let Some(root) = self.root.as_deref() {
+            push_rows(root, Vec::new(), &mut rows);
+        }
+        rows
+    }
+
+    /// Open or close the node, and give the item whose children should be
+    /// asked for if it was opened for the first time
+    pub fn toggle(&mut self, indices: &[usize]) -> Option<CallHierarchyItem> {
+        let node = self.node_mut(indices)?;
+        node.open = !node.open;
+        if node.open && node.children.is_none() {
+            Some(node.item.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Set the children of the node, if they were asked for the current root
+    pub fn set_children(
+        &mut self,
+        generation: u64,
+        indices: &[usize],
+        children: Vec<CallHierarchyNode>,
+    ) {
+        if generation != self.generation {
+            return;
+        }
+        if let Some(node) = self.node_mut(indices) {
+            node.children = Some(children);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CallHierarchyData {
+    pub widget_id: WidgetId,
+    pub split_id: WidgetId,
+    pub tree_widget_id: WidgetId,
+    pub direction: CallHierarchyDirection,
+    /// The language server that prepared the root, which is asked for the
+    /// calls
+    pub plugin_id: PluginId,
+    pub tree: HierarchyTree,
+}
+
+impl CallHierarchyData {
+    pub fn new() -> Self {
+        Self {
+            widget_id: WidgetId::next(),
+            split_id: WidgetId::next(),
+            tree_widget_id: WidgetId::next(),
+            direction: CallHierarchyDirection::Incoming,
+            plugin_id: PluginId(0),
+            tree: HierarchyTree::default(),
+        }
+    }
+
+    pub fn set_root(&mut self, plugin_id: PluginId, item: CallHierarchyItem) {
+        self.plugin_id = plugin_id;
+        self.tree.set_root(item);
+    }
+
+    /// Switch between the incoming and outgoing calls, which are asked for
+    /// again from the root
+    pub fn set_direction(&mut self, direction: CallHierarchyDirection) {
+        if self.direction == direction {
+            return;
+        }
+        self.direction = direction;
+        self.tree.reset();
+    }
+
+    /// Ask the language server for the calls of the item at the node, in the
+    /// current direction
+    pub fn request_calls(
+        &self,
+        proxy: &LapceProxy,
+        indices: Vec<usize>,
+        item: CallHierarchyItem,
+        event_sink: ExtEventSink,
+        tab_id: WidgetId,
+    ) {
+        let generation = self.tree.generation;
+        let send = move |calls: Vec<CallHierarchyNode>| {
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::UpdateCallHierarchyCalls {
+                    generation,
+                    indices,
+                    calls,
+                },
+                Target::Widget(tab_id),
+            );
+        };
+        match self.direction {
+            CallHierarchyDirection::Incoming => {
+                proxy.proxy_rpc.call_hierarchy_incoming(
+                    self.plugin_id,
+                    item,
+                    move |result| {
+                        let calls = match result {
+                            Ok(ProxyResponse::CallHierarchyIncomingResponse {
+                                calls,
+                            }) => calls
+                                .into_iter()
+                                .map(CallHierarchyNode::incoming)
+                                .collect(),
+                            _ => Vec::new(),
+                        };
+                        send(calls);
+                    },
+                );
+            }
+            CallHierarchyDirection::Outgoing => {
+                let parent = item.clone();
+                proxy.proxy_rpc.call_hierarchy_outgoing(
+                    self.plugin_id,
+                    item,
+                    move |result| {
+                        let calls = match result {
+                            Ok(ProxyResponse::CallHierarchyOutgoingResponse {
+                                calls,
+                            }) => calls
+                                .into_iter()
+                                .map(|call| {
+                                    CallHierarchyNode::outgoing(&parent, call)
+                                })
+                                .collect(),
+                            _ => Vec::new(),
+                        };
+                        send(calls);
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl Default for CallHierarchyData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn push_rows(
+    node: &CallHierarchyNode,
+    indices: Vec<usize>,
+    rows: &mut Vec<CallHierarchyRow>,
+) {
+    let depth = indices.len();
+    rows.push(CallHierarchyRow {
+        indices: indices.clone(),
+        depth,
+    });
+    if !node.open {
+        return;
+    }
+    for (i, child) in node.children.iter().flatten().enumerate() {
+        let mut indices = indices.clone();
+        indices.push(i);
+        push_rows(child, indices, rows);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Range, SymbolKind, Url};
+
+    use super::*;
+
+    fn item(name: &str) -> CallHierarchyItem {
+        CallHierarchyItem {
+            name: name.to_string(),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: Url::parse(&format!("file:///{name}.rs")).unwrap(),
+            range: Range::default(),
+            selection_range: Range::default(),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn test_hierarchy_tree_rows() {
+        let mut tree = HierarchyTree::default();
+        tree.set_root(item("a"));
+        let generation = tree.generation;
+        tree.set_children(
+            generation,
+            &[],
+            vec![
+                CallHierarchyNode::new(item("b")),
+                CallHierarchyNode::new(item("c")),
+            ],
+        );
+        assert_eq!(
+            tree.toggle(&[1]).map(|item| item.name),
+            Some("c".to_string())
+        );
+        tree.set_children(generation, &[1], vec![CallHierarchyNode::new(item("d"))]);
+        let rows = tree
+            .rows()
+            .into_iter()
+            .map(|row| (row.indices, row.depth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![(vec![], 0), (vec![0], 1), (vec![1], 1), (vec![1, 0], 2)]
+        );
+        assert_eq!(tree.node(&[1, 0]).unwrap().item.name, "d");
+
+        // closing doesn't ask for the children again
+        assert_eq!(tree.toggle(&[1]), None);
+        assert_eq!(tree.rows().len(), 3);
+        assert_eq!(tree.toggle(&[1]), None);
+        assert_eq!(tree.rows().len(), 4);
+    }
+
+    #[test]
+    fn test_call_hierarchy_drops_stale_calls() {
+        let mut data = CallHierarchyData::new();
+        data.set_root(PluginId(1), item("a"));
+        let generation = data.tree.generation;
+        data.set_direction(CallHierarchyDirection::Outgoing);
+        data.tree.set_children(
+            generation,
+            &[],
+            vec![CallHierarchyNode::new(item("b"))],
+        );
+        assert_eq!(data.tree.rows().len(), 1);
+        assert!(data.tree.node(&[]).unwrap().children.is_none());
+    }
+}
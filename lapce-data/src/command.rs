@@ -9,30 +9,48 @@
 use lapce_core::command::{
     EditCommand, FocusCommand, MotionModeCommand, MoveCommand, MultiSelectionCommand,
 };
+use lapce_core::fold::FoldingRange;
+use lapce_core::indent::IndentStyle;
+use lapce_core::line_ending::LineEnding;
+use lapce_core::selection::Selection;
 use lapce_core::syntax::Syntax;
 use lapce_rpc::plugin::{PluginId, VoltInfo, VoltMetadata};
 use lapce_rpc::{
-    buffer::BufferId, file::FileNodeItem, source_control::DiffInfo, style::Style,
+    buffer::BufferId,
+    core::LspLogKind,
+    dap_types::{
+        Breakpoint, Capabilities, CompletionTarget, DapId, DataBreakpointInfo,
+        EvaluateResult, ReadMemoryResult, RunDebugConfig, Scope, StackFrame,
+        Stopped, Thread, ThreadId, Variable,
+    },
+    file::FileNodeItem,
+    source_control::DiffInfo,
+    style::Style,
     terminal::TermId,
+    type_hierarchy::TypeHierarchyItem,
 };
 use lsp_types::{
-    CodeActionOrCommand, CodeActionResponse, CompletionItem, CompletionResponse,
-    InlayHint, Location, Position, ProgressParams, PublishDiagnosticsParams,
-    SelectionRange, TextEdit, WorkspaceEdit,
+    CallHierarchyItem, CodeActionOrCommand, CodeActionResponse, CompletionItem,
+    CompletionResponse, Diagnostic, InlayHint, LinkedEditingRanges, Location,
+    Position, ProgressParams, PublishDiagnosticsParams, SelectionRange,
+    SignatureHelp, TextEdit, Url, WorkspaceEdit,
 };
 use serde_json::Value;
 use strum::{self, EnumMessage, IntoEnumIterator};
 use strum_macros::{Display, EnumIter, EnumMessage, EnumString, IntoStaticStr};
-use xi_rope::{spans::Spans, Rope};
+use xi_rope::{spans::Spans, Rope, RopeDelta};
 
 use crate::alert::AlertContentData;
+use crate::call_hierarchy::CallHierarchyNode;
+use crate::config::AutoSave;
 use crate::data::{LapceMainSplitData, LapceTabData, LapceWorkspace};
-use crate::document::BufferContent;
+use crate::debug::BreakpointOptionKind;
+use crate::document::{BufferContent, SymbolOccurrence};
 use crate::editor::{EditorPosition, Line, LineCol};
 use crate::menu::MenuKind;
 use crate::rich_text::RichText;
 use crate::selection_range::SelectionRangeDirection;
-use crate::update::ReleaseInfo;
+use crate::update::{ReleaseInfo, UpdateDismissal, UpdateProgress, UpdateSettings};
 use crate::{
     data::{EditorTabChild, SplitContent},
     editor::EditorLocation,
@@ -41,6 +59,7 @@
     proxy::ProxyStatus,
     search::Match,
     split::{SplitDirection, SplitMoveDirection},
+    terminal::{TerminalAlert, TerminalLaunch},
 };
 
 pub const LAPCE_OPEN_FOLDER: Selector<FileInfo> = Selector::new("lapce.open-folder");
@@ -102,6 +121,15 @@ pub fn is_palette_command(&self) -> bool {
                 | LapceWorkbenchCommand::PaletteSymbol
                 | LapceWorkbenchCommand::PaletteCommand
                 | LapceWorkbenchCommand::ChangeFileLanguage
+                | LapceWorkbenchCommand::CompareActiveFileWith
+                | LapceWorkbenchCommand::ReopenWithEncoding
+                | LapceWorkbenchCommand::SaveWithEncoding
+                | LapceWorkbenchCommand::ChangeLineEnding
+                | LapceWorkbenchCommand::ChangeIndentation
+                | LapceWorkbenchCommand::RestartLanguageServer
+                | LapceWorkbenchCommand::StopLanguageServer
+                | LapceWorkbenchCommand::DebugStart
+                | LapceWorkbenchCommand::DebugAddWatchExpression
                 | LapceWorkbenchCommand::ChangeTheme
                 | LapceWorkbenchCommand::ConnectSshHost
                 | LapceWorkbenchCommand::ConnectWsl
@@ -210,6 +238,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Reveal Active File in File Explorer")]
     RevealActiveFileInFileExplorer,
 
+    #[strum(serialize = "compare_active_file_with")]
+    #[strum(message = "Compare Active File With...")]
+    CompareActiveFileWith,
+
     #[strum(serialize = "change_theme")]
     #[strum(message = "Change Theme")]
     ChangeTheme,
@@ -353,6 +385,54 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_terminal_focus")]
     ToggleTerminalFocus,
 
+    #[strum(message = "Terminal: New Terminal")]
+    #[strum(serialize = "new_terminal")]
+    NewTerminal,
+
+    #[strum(message = "Terminal: New Terminal with Profile")]
+    #[strum(serialize = "new_terminal_with_profile")]
+    NewTerminalWithProfile,
+
+    #[strum(message = "Run Task")]
+    #[strum(serialize = "run_task")]
+    RunTask,
+
+    #[strum(message = "Terminal: Scroll to Previous Command")]
+    #[strum(serialize = "terminal_scroll_to_previous_command")]
+    TerminalScrollToPreviousCommand,
+
+    #[strum(message = "Terminal: Scroll to Next Command")]
+    #[strum(serialize = "terminal_scroll_to_next_command")]
+    TerminalScrollToNextCommand,
+
+    #[strum(message = "Terminal: Rerun Last Command")]
+    #[strum(serialize = "terminal_rerun_last_command")]
+    TerminalRerunLastCommand,
+
+    #[strum(message = "Terminal: Clear")]
+    #[strum(serialize = "terminal_clear")]
+    TerminalClear,
+
+    #[strum(message = "Terminal: Scroll to Top")]
+    #[strum(serialize = "terminal_scroll_to_top")]
+    TerminalScrollToTop,
+
+    #[strum(message = "Terminal: Scroll to Bottom")]
+    #[strum(serialize = "terminal_scroll_to_bottom")]
+    TerminalScrollToBottom,
+
+    #[strum(message = "Terminal: Rename")]
+    #[strum(serialize = "terminal_rename")]
+    TerminalRename,
+
+    #[strum(message = "Terminal: Set Color")]
+    #[strum(serialize = "terminal_set_color")]
+    TerminalSetColor,
+
+    #[strum(message = "Terminal: Set Icon")]
+    #[strum(serialize = "terminal_set_icon")]
+    TerminalSetIcon,
+
     #[strum(serialize = "toggle_source_control_focus")]
     ToggleSourceControlFocus,
 
@@ -368,10 +448,126 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_problem_focus")]
     ToggleProblemFocus,
 
+    #[strum(message = "Go to Next Problem")]
+    #[strum(serialize = "next_problem")]
+    NextProblem,
+
+    #[strum(message = "Go to Previous Problem")]
+    #[strum(serialize = "previous_problem")]
+    PreviousProblem,
+
+    #[strum(message = "Toggle Undo Tree Focus")]
+    #[strum(serialize = "toggle_undo_tree_focus")]
+    ToggleUndoTreeFocus,
+
+    #[strum(message = "Toggle Call Hierarchy Focus")]
+    #[strum(serialize = "toggle_call_hierarchy_focus")]
+    ToggleCallHierarchyFocus,
+
+    #[strum(message = "Toggle Type Hierarchy Focus")]
+    #[strum(serialize = "toggle_type_hierarchy_focus")]
+    ToggleTypeHierarchyFocus,
+
+    #[strum(message = "Toggle Rename Preview Focus")]
+    #[strum(serialize = "toggle_rename_preview_focus")]
+    ToggleRenamePreviewFocus,
+
+    #[strum(message = "Apply Rename Preview")]
+    #[strum(serialize = "apply_rename_preview")]
+    ApplyRenamePreview,
+
+    #[strum(message = "Discard Rename Preview")]
+    #[strum(serialize = "discard_rename_preview")]
+    DiscardRenamePreview,
+
+    #[strum(message = "Toggle Language Server Log Focus")]
+    #[strum(serialize = "toggle_lsp_log_focus")]
+    ToggleLspLogFocus,
+
+    #[strum(message = "Toggle Language Server Trace")]
+    #[strum(serialize = "toggle_lsp_trace")]
+    ToggleLspTrace,
+
+    #[strum(message = "Clear Language Server Log")]
+    #[strum(serialize = "clear_lsp_log")]
+    ClearLspLog,
+
+    #[strum(message = "LSP: Restart Server")]
+    #[strum(serialize = "restart_language_server")]
+    RestartLanguageServer,
+
+    #[strum(message = "LSP: Stop Server")]
+    #[strum(serialize = "stop_language_server")]
+    StopLanguageServer,
+
+    #[strum(message = "LSP: Show Server Status")]
+    #[strum(serialize = "show_language_servers")]
+    ShowLanguageServers,
+
+    #[strum(message = "Toggle Debug Focus")]
+    #[strum(serialize = "toggle_debug_focus")]
+    ToggleDebugFocus,
+
+    #[strum(message = "Debug: Start")]
+    #[strum(serialize = "debug_start")]
+    DebugStart,
+
+    #[strum(message = "Debug: Continue")]
+    #[strum(serialize = "debug_continue")]
+    DebugContinue,
+
+    #[strum(message = "Debug: Pause")]
+    #[strum(serialize = "debug_pause")]
+    DebugPause,
+
+    #[strum(message = "Debug: Step Over")]
+    #[strum(serialize = "debug_step_over")]
+    DebugStepOver,
+
+    #[strum(message = "Debug: Step Into")]
+    #[strum(serialize = "debug_step_into")]
+    DebugStepInto,
+
+    #[strum(message = "Debug: Step Out")]
+    #[strum(serialize = "debug_step_out")]
+    DebugStepOut,
+
+    #[strum(message = "Debug: Stop")]
+    #[strum(serialize = "debug_stop")]
+    DebugStop,
+
+    #[strum(message = "Debug: Add Watch Expression")]
+    #[strum(serialize = "debug_add_watch_expression")]
+    DebugAddWatchExpression,
+
+    #[strum(message = "Toggle Debug Console Focus")]
+    #[strum(serialize = "toggle_debug_console_focus")]
+    ToggleDebugConsoleFocus,
+
+    #[strum(message = "Debug: Clear Console")]
+    #[strum(serialize = "debug_clear_console")]
+    DebugClearConsole,
+
     #[strum(message = "Toggle Search Focus")]
     #[strum(serialize = "toggle_search_focus")]
     ToggleSearchFocus,
 
+    #[strum(message = "Toggle Regex in Global Search")]
+    #[strum(serialize = "toggle_global_search_regex")]
+    ToggleGlobalSearchRegex,
+
+    #[strum(message = "Toggle Preserve Case in Global Replace")]
+    #[strum(serialize = "toggle_global_replace_preserve_case")]
+    ToggleGlobalReplacePreserveCase,
+
+    #[strum(message = "Toggle Preserve Case in Replace")]
+    #[strum(serialize = "toggle_replace_preserve_case")]
+    ToggleReplacePreserveCase,
+
+    #[strum(message = "Replace All in Global Search")]
+    #[strum(serialize = "global_replace_all")]
+    GlobalReplaceAll,
+
     // Visual toggle commands
     #[strum(serialize = "toggle_terminal_visual")]
     ToggleTerminalVisual,
@@ -388,6 +584,27 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_problem_visual")]
     ToggleProblemVisual,
 
+    #[strum(serialize = "toggle_undo_tree_visual")]
+    ToggleUndoTreeVisual,
+
+    #[strum(serialize = "toggle_call_hierarchy_visual")]
+    ToggleCallHierarchyVisual,
+
+    #[strum(serialize = "toggle_type_hierarchy_visual")]
+    ToggleTypeHierarchyVisual,
+
+    #[strum(serialize = "toggle_rename_preview_visual")]
+    ToggleRenamePreviewVisual,
+
+    #[strum(serialize = "toggle_lsp_log_visual")]
+    ToggleLspLogVisual,
+
+    #[strum(serialize = "toggle_debug_visual")]
+    ToggleDebugVisual,
+
+    #[strum(serialize = "toggle_debug_console_visual")]
+    ToggleDebugConsoleVisual,
+
     #[strum(serialize = "toggle_search_visual")]
     ToggleSearchVisual,
 
@@ -424,6 +641,39 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Change current file language")]
     ChangeFileLanguage,
 
+    #[strum(serialize = "reopen_with_encoding")]
+    #[strum(message = "Reopen current file with encoding")]
+    ReopenWithEncoding,
+
+    #[strum(serialize = "save_with_encoding")]
+    #[strum(message = "Save current file with encoding")]
+    SaveWithEncoding,
+
+    #[strum(serialize = "change_line_ending")]
+    #[strum(message = "Change end of line sequence")]
+    ChangeLineEnding,
+
+    #[strum(serialize = "change_indentation")]
+    #[strum(message = "Change indentation of new lines")]
+    ChangeIndentation,
+
+    #[strum(serialize = "toggle_read_only")]
+    #[strum(message = "Toggle Read-only")]
+    ToggleReadOnly,
+
+    /// Resolve the conflict of a file that was changed on disk while it
+    /// had unsaved changes by saving over it, with the path as data
+    #[strum(serialize = "overwrite_file_on_disk")]
+    OverwriteFileOnDisk,
+
+    /// Resolve the conflict by reloading the file and losing the changes
+    #[strum(serialize = "reload_file_from_disk")]
+    ReloadFileFromDisk,
+
+    /// Merge the unsaved changes with the file on disk and compare them
+    #[strum(serialize = "compare_with_file_on_disk")]
+    CompareWithFileOnDisk,
+
     #[strum(serialize = "next_editor_tab")]
     #[strum(message = "Next editor tab")]
     NextEditorTab,
@@ -439,6 +689,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "restart_to_update")]
     RestartToUpdate,
 
+    #[strum(serialize = "cancel_update")]
+    #[strum(message = "Cancel Update Download")]
+    CancelUpdate,
+
+    #[strum(serialize = "rollback_update")]
+    #[strum(message = "Rollback Update")]
+    RollbackUpdate,
+
     #[strum(serialize = "show_about")]
     #[strum(message = "About Lapce")]
     ShowAbout,
@@ -467,7 +725,9 @@ pub enum EnsureVisiblePosition {
 
 pub enum LapceUICommand {
     InitChildren,
-    InitTerminalPanel(bool),
+    /// Start the first terminal of the panel, focusing it if asked, with
+    /// the profile of the name or the default one
+    InitTerminalPanel(bool, TerminalLaunch),
     ReloadConfig,
     /// UTF8 offsets into the file
     InitBufferContent(InitBufferContent<usize>),
@@ -499,6 +759,9 @@ pub enum LapceUICommand {
     },
     ShowAbout,
     ShowAlert(AlertContentData),
+    /// Dismiss the message in the status bar
+    ClearStatusMessage,
+    ShowReleaseNotes(ReleaseInfo),
     ShowMenu(Point, Arc<Vec<MenuKind>>),
     ShowWindow,
     ShowGitBranches {
@@ -507,7 +770,10 @@ pub enum LapceUICommand {
     },
     UpdateSearchInput(String),
     UpdateSearch(String),
-    GlobalSearchResult(String, Arc<HashMap<PathBuf, Vec<Match>>>),
+    UpdateSearchReplace(String),
+    /// The replace input of the find bar changed
+    UpdateFindReplace(String),
+    GlobalSearchResult(String, bool, Arc<HashMap<PathBuf, Vec<Match>>>),
     CancelFilePicker,
     SetWorkspace(LapceWorkspace),
     SetTheme(String, bool),
@@ -520,21 +786,88 @@ pub enum LapceUICommand {
     },
     OpenFile(PathBuf, bool),
     OpenFileDiff(PathBuf, String),
+    CompareActiveFileWith(PathBuf),
     RevealInFileExplorer(PathBuf),
     CancelCompletion(usize),
     ResolveCompletion(BufferId, u64, usize, Box<CompletionItem>),
-    UpdateCompletion(usize, String, CompletionResponse, PluginId),
+    /// The completion item of the plugin, resolved when it was highlighted
+    CompletionItemResolved {
+        plugin_id: PluginId,
+        item: Box<CompletionItem>,
+        resolved: Box<CompletionItem>,
+    },
+    UpdateCompletion(usize, String, CompletionResponse, PluginId, i64),
     UpdateHover(usize, Arc<Vec<RichText>>),
+    /// The signature help of a request, or `None` when the cursor isn't in a
+    /// call anymore
+    UpdateSignature(usize, Option<SignatureHelp>),
     UpdateVoltReadme(RichText),
     UpdateInlayHints {
         path: PathBuf,
         rev: u64,
         hints: Spans<InlayHint>,
     },
+    UpdateCodeLenses {
+        path: PathBuf,
+        rev: u64,
+        plugin_id: PluginId,
+        lenses: Spans<Vec<lsp_types::Command>>,
+    },
+    UpdateDocumentLinks {
+        path: PathBuf,
+        rev: u64,
+        links: Spans<Url>,
+    },
+    UpdateDocumentHighlights {
+        path: PathBuf,
+        rev: u64,
+        highlights: Vec<SymbolOccurrence>,
+    },
+    /// Run the command of a code lens, with the language server it's from
+    RunCodeLens {
+        plugin_id: PluginId,
+        command: lsp_types::Command,
+    },
+    UpdateFoldingRanges {
+        path: PathBuf,
+        rev: u64,
+        ranges: Vec<FoldingRange>,
+    },
+    ToggleFold(usize),
+    JumpToUndoGroup(usize),
+    ShowCallHierarchy {
+        plugin_id: PluginId,
+        item: Box<CallHierarchyItem>,
+    },
+    UpdateCallHierarchyCalls {
+        generation: u64,
+        indices: Vec<usize>,
+        calls: Vec<CallHierarchyNode>,
+    },
+    ShowTypeHierarchy {
+        plugin_id: PluginId,
+        item: Box<TypeHierarchyItem>,
+    },
+    UpdateTypeHierarchyTypes {
+        generation: u64,
+        indices: Vec<usize>,
+        types: Vec<CallHierarchyNode>,
+    },
     UpdateCodeActions(PathBuf, u64, usize, CodeActionResponse),
+    /// The ranges edited together with the one at the cursor of the editor
+    /// view, for the revision of the document they were asked for
+    UpdateLinkedEditingRanges {
+        path: PathBuf,
+        view_id: WidgetId,
+        rev: u64,
+        ranges: Option<LinkedEditingRanges>,
+    },
     CancelPalette,
     RunCodeAction(CodeActionOrCommand),
-    ApplyWorkspaceEdit(WorkspaceEdit),
+    ShowRenamePreview {
+        new_name: String,
+        edit: WorkspaceEdit,
+    },
     ShowCodeActions(Option<Point>),
     Hide,
     ResignFocus,
@@ -553,6 +886,7 @@ pub enum LapceUICommand {
     UpdatePaletteItems(String, im::Vector<PaletteItem>),
     FilterPaletteItems(String, String, im::Vector<PaletteItem>),
     UpdateKeymapsFilter(String),
+    UpdateProblemFilter(String),
     ResetSettingsFile(String, String),
     UpdateSettingsFile(String, String, Value),
     UpdateSettingsFilter(String),
@@ -585,7 +919,10 @@ pub enum LapceUICommand {
     NextEditorTab,
     PreviousEditorTab,
     FilterItems,
-    RestartToUpdate(PathBuf, ReleaseInfo),
+    RestartToUpdate(PathBuf, ReleaseInfo, UpdateSettings),
+    DismissUpdate(UpdateDismissal),
+    UpdateDownloadProgress(Option<UpdateProgress>),
+    RollbackUpdate(PathBuf),
     NewWindow(WindowId),
     CloseWindow(WindowId),
     ReloadWindow,
@@ -595,10 +932,33 @@ pub enum LapceUICommand {
     ApplyEditsAndSave(usize, u64, Result<Value>),
     DocumentFormat(PathBuf, u64, Result<Vec<TextEdit>>),
     DocumentFormatAndSave(PathBuf, u64, Result<Vec<TextEdit>>, Option<WidgetId>),
+    /// The code actions of a kind that runs on saving the file, after which
+    /// the rest of the kinds run before the file is formatted and saved
+    DocumentCodeActionsAndSave {
+        path: PathBuf,
+        rev: u64,
+        kind: String,
+        result: Result<CodeActionResponse>,
+        kinds: Vec<String>,
+        exit_widget_id: Option<WidgetId>,
+    },
     DocumentSave(PathBuf, Option<WidgetId>),
+    /// Save the modified files, or only the given one, if auto save is set to
+    /// this trigger
+    AutoSave(AutoSave, Option<PathBuf>),
     BufferSave(PathBuf, u64, Option<WidgetId>),
     UpdateSemanticStyles(BufferId, PathBuf, u64, Arc<Spans<Style>>),
     UpdateTerminalTitle(TermId, String),
+    UpdateTerminalProcess(TermId, Option<String>),
+    /// Give the terminal a name, or `None` to show its title again
+    RenameTerminal(TermId, Option<String>),
+    SetTerminalColor(TermId, Option<String>),
+    SetTerminalIcon(TermId, Option<String>),
+    /// Something happened in the terminal, which is shown if it isn't
+    /// focused and the settings ask for it
+    TerminalAlert(TermId, TerminalAlert),
+    /// Focus the first terminal that has an alert
+    ShowTerminalAlert,
     UpdateHistoryStyle {
         id: BufferId,
         path: PathBuf,
@@ -619,7 +979,149 @@ pub enum LapceUICommand {
     CenterOfWindow,
     UpdateLineChanges(BufferId),
     PublishDiagnostics(PublishDiagnosticsParams),
-    WorkDoneProgress(ProgressParams),
+    WorkDoneProgress {
+        plugin_id: PluginId,
+        server: String,
+        progress: ProgressParams,
+    },
+    LspLog {
+        plugin_id: PluginId,
+        server: String,
+        kind: LspLogKind,
+        message: String,
+    },
+    /// Debug the program of the configuration
+    DebugStart(RunDebugConfig),
+    /// Debug the program of the configuration, attaching to the process
+    /// that was picked for it
+    DebugAttachProcess {
+        config: RunDebugConfig,
+        pid: u32,
+    },
+    /// Add a breakpoint to the line, starting at 0, or remove the one it has
+    ToggleBreakpoint {
+        path: PathBuf,
+        line: usize,
+    },
+    /// Set the condition, hit count or log message of the breakpoint of the
+    /// line, or clear it with an empty value
+    SetBreakpointOption {
+        path: PathBuf,
+        line: usize,
+        kind: BreakpointOptionKind,
+        value: String,
+    },
+    DapStopped {
+        dap_id: DapId,
+        stopped: Stopped,
+        threads: Vec<Thread>,
+        stack_frames: Vec<StackFrame>,
+        variables: Vec<(Scope, Vec<Variable>)>,
+    },
+    DapCapabilities {
+        dap_id: DapId,
+        capabilities: Capabilities,
+    },
+    DapContinued(DapId),
+    DapTerminated(DapId),
+    DapBreakpoints {
+        dap_id: DapId,
+        path: PathBuf,
+        breakpoints: Vec<Breakpoint>,
+    },
+    /// Show the stack of the thread of the active program
+    SelectDebugThread(ThreadId),
+    /// Make the frame at the index of the stack the one the variables and
+    /// the watch expressions are for
+    SelectDebugFrame(usize),
+    /// Expand or collapse the run of subtle frames from the index
+    ToggleDebugFramesExpanded(usize),
+    /// Run the frame of the id again from its start
+    RestartDebugFrame(usize),
+    DapThreadStack {
+        dap_id: DapId,
+        thread_id: ThreadId,
+        stack_frames: Vec<StackFrame>,
+    },
+    DapFrameVariables {
+        dap_id: DapId,
+        frame_id: usize,
+        variables: Vec<(Scope, Vec<Variable>)>,
+    },
+    /// Change the watch expression at the index, or add one without an
+    /// index
+    SetWatchExpression {
+        index: Option<usize>,
+        expression: String,
+    },
+    RemoveWatchExpression(usize),
+    /// Expand or collapse the children of a watch expression or of one of
+    /// its children
+    ToggleWatchExpanded {
+        path: Vec<String>,
+        variables_reference: usize,
+    },
+    DapWatchValue {
+        dap_id: DapId,
+        expression: String,
+        value: Result<EvaluateResult, String>,
+    },
+    DapWatchChildren {
+        dap_id: DapId,
+        path: Vec<String>,
+        variables: Vec<Variable>,
+    },
+    /// What the program or the adapter printed, with the category of the
+    /// `output` event
+    DapOutput {
+        dap_id: DapId,
+        category: Option<String>,
+        output: String,
+    },
+    /// Evaluate the expression of the debug console input
+    DebugConsoleEvaluate,
+    /// Put the previous expression of the history in the debug console
+    /// input, or the next one
+    DebugConsoleHistory {
+        previous: bool,
+    },
+    /// Complete the expression of the debug console input at the cursor
+    DebugConsoleComplete,
+    DapConsoleResult {
+        dap_id: DapId,
+        result: Result<EvaluateResult, String>,
+    },
+    DapConsoleCompletions {
+        dap_id: DapId,
+        text: String,
+        cursor: usize,
+        targets: Vec<CompletionTarget>,
+    },
+    /// Show the memory from the offset of the reference in the memory
+    /// section of the debug panel
+    ReadDebugMemory {
+        memory_reference: String,
+        offset: i64,
+    },
+    CloseDebugMemory,
+    DapMemory {
+        dap_id: DapId,
+        memory_reference: String,
+        offset: i64,
+        result: Result<ReadMemoryResult, String>,
+    },
+    /// Stop the program when the variable, which is a child of the
+    /// reference, changes
+    AddDataBreakpoint {
+        variables_reference: usize,
+        name: String,
+    },
+    RemoveDataBreakpoint(usize),
+    DapDataBreakpointInfo {
+        dap_id: DapId,
+        name: String,
+        info: Result<DataBreakpointInfo, String>,
+    },
     UpdateDiffInfo(DiffInfo),
     EnsureVisible((Rect, (f64, f64), Option<EnsureVisiblePosition>)),
     EnsureRectVisible(Rect),
@@ -636,8 +1138,26 @@ pub enum LapceUICommand {
     ProxyUpdateStatus(ProxyStatus),
     CloseTerminal(TermId),
     OpenPluginInfo(VoltInfo),
-    SplitTerminal(bool, WidgetId),
+    /// Start a terminal next to the one of the widget, vertically or not
+    SplitTerminal(bool, WidgetId, TerminalLaunch),
+    /// Start a terminal next to the active terminal
+    NewTerminal(TerminalLaunch),
+    /// Type the text in the active terminal, or in a new terminal when
+    /// there's none
+    RunInTerminal(String),
+    /// Set the problems found in the output of the task of the name, which
+    /// replace those of its last run
+    PublishTaskProblems(String, Vec<(PathBuf, Diagnostic)>),
     SplitTerminalClose(TermId, WidgetId),
+    /// Show the find bar of the terminal panel, and focus it
+    OpenTerminalFind,
+    CloseTerminalFind,
+    UpdateTerminalFind(String),
+    ToggleTerminalFindRegex,
+    /// Go to the next match of the find bar in the active terminal
+    TerminalFindNext {
+        backward: bool,
+    },
     SplitEditor(bool, WidgetId),
     SplitEditorMove(SplitMoveDirection, WidgetId),
     SplitEditorExchange(WidgetId),
@@ -660,6 +1180,19 @@ pub enum LapceUICommand {
     JumpToLineLocation(Option<WidgetId>, EditorLocation<Line>),
     JumpToLineColLocation(Option<WidgetId>, EditorLocation<LineCol>, bool),
     ToggleProblem(PathBuf),
+    /// Ask for the quick fixes of the diagnostic of the file, to apply one
+    ApplyQuickFix {
+        path: PathBuf,
+        diagnostic: Diagnostic,
+    },
+    /// The quick fixes of a diagnostic, for the revision of the document
+    /// they were asked for if it was open
+    ApplyQuickFixResponse {
+        path: PathBuf,
+        rev: Option<u64>,
+        diagnostic: Diagnostic,
+        resp: CodeActionResponse,
+    },
     TerminalJumpToLine(i32),
     GoToLocation(Option<WidgetId>, EditorLocation, bool),
     GotoDefinition {
@@ -667,6 +1200,12 @@ pub enum LapceUICommand {
         offset: usize,
         location: EditorLocation<Position>,
     },
+    /// Show the locations in the peek view below the line of the offset
+    ShowPeek {
+        editor_view_id: WidgetId,
+        offset: usize,
+        locations: Vec<EditorLocation<Position>>,
+    },
     PrepareRename {
         path: PathBuf,
         rev: u64,
@@ -723,6 +1262,33 @@ pub enum LapceUICommand {
     CopyPath(PathBuf),
     CopyRelativePath(PathBuf),
     SetLanguage(String),
+    ReopenWithEncoding(String),
+    SaveWithEncoding(String),
+    SetLineEnding(LineEnding),
+    /// Set the indentation of new lines, or detect it from the content when
+    /// it's `None`
+    SetIndentStyle(Option<IndentStyle>),
+    RestartLanguageServer(PluginId),
+    StopLanguageServer(PluginId),
+    /// The encoding a file was decoded with when it was loaded, whether it
+    /// was loaded as a large file, and whether it can't be written to
+    InitBufferInfo {
+        path: PathBuf,
+        encoding: String,
+        large_file: bool,
+        read_only: bool,
+    },
+    /// The next chunk of a large file, which is loaded a chunk at a time
+    AppendBufferContent {
+        path: PathBuf,
+        content: Rope,
+        last: bool,
+    },
+    ReopenedWithEncoding {
+        path: PathBuf,
+        content: Rope,
+        encoding: String,
+    },
     ApplySelectionRange {
         buffer_id: BufferId,
         rev: u64,
@@ -735,6 +1301,15 @@ pub enum LapceUICommand {
         ranges: Vec<SelectionRange>,
         direction: SelectionRangeDirection,
     },
+    /// The selection ranges for the regions of the `selection` of the editor
+    /// view, to expand it with
+    ExpandSelectionWithRanges {
+        path: PathBuf,
+        view_id: WidgetId,
+        rev: u64,
+        selection: Selection,
+        ranges: Vec<SelectionRange>,
+    },
 
     /// An item in a list was chosen  
     /// This is typically targeted at the widget which contains the list
@@ -755,6 +1330,7 @@ pub enum LapceUICommand {
 pub struct InitBufferContent<P: EditorPosition> {
     pub path: PathBuf,
     pub content: Rope,
+    pub undo_deltas: Vec<RopeDelta>,
     pub locations: Vec<(WidgetId, EditorLocation<P>)>,
     pub edits: Option<Rope>,
     pub cb: Option<InitBufferContentCb>,
@@ -764,10 +1340,10 @@ impl<P: EditorPosition + Clone + Send + 'static> InitBufferContent<P> {
     pub fn execute(&self, ctx: &mut EventCtx, data: &mut LapceTabData) {
         let doc = data.main_split.open_docs.get_mut(&self.path).unwrap();
         let doc = Arc::make_mut(doc);
-        doc.init_content(self.content.to_owned());
+        doc.init_content(self.content.to_owned(), &self.undo_deltas);
 
         if let Some(rope) = &self.edits {
-            doc.reload(rope.clone(), false);
+            doc.restore_unsaved(rope.clone());
         }
         if let BufferContent::File(path) = doc.content() {
             if let Some(d) = data.main_split.diagnostics.get(path) {
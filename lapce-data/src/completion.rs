@@ -1,228 +1,17 @@
-use std::{fmt::Display, path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc};
 
-use anyhow::Error;
-use druid::{EventCtx, Size, WidgetId};
+use druid::{EventCtx, ExtEventSink, Size, Target, WidgetId};
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
-use itertools::Itertools;
 use lapce_core::command::FocusCommand;
-use lapce_rpc::{buffer::BufferId, plugin::PluginId};
+use lapce_rpc::{buffer::BufferId, plugin::PluginId, proxy::ProxyResponse};
 use lsp_types::{CompletionItem, CompletionResponse, Position};
-use regex::Regex;
-use std::str::FromStr;
 
-use crate::{config::Config, list::ListData, proxy::LapceProxy};
-
-#[derive(Debug)]
-pub struct Snippet {
-    elements: Vec<SnippetElement>,
-}
-
-impl Snippet {
-    fn extract_elements(
-        s: &str,
-        pos: usize,
-        escs: Vec<&str>,
-        loose_escs: Vec<&str>,
-    ) -> (Vec<SnippetElement>, usize) {
-        let mut elements = Vec::new();
-        let mut pos = pos;
-        loop {
-            if s.len() == pos {
-                break;
-            } else if let Some((ele, end)) = Self::extract_tabstop(s, pos) {
-                elements.push(ele);
-                pos = end;
-            } else if let Some((ele, end)) = Self::extract_placeholder(s, pos) {
-                elements.push(ele);
-                pos = end;
-            } else if let Some((ele, end)) =
-                Self::extract_text(s, pos, escs.clone(), loose_escs.clone())
-            {
-                elements.push(ele);
-                pos = end;
-            } else {
-                break;
-            }
-        }
-        (elements, pos)
-    }
-
-    fn extract_tabstop(s: &str, pos: usize) -> Option<(SnippetElement, usize)> {
-        for re in &[
-            Regex::new(r#"^\$(\d+)"#).unwrap(),
-            Regex::new(r#"^\$\{(\d+)\}"#).unwrap(),
-        ] {
-            if let Some(caps) = re.captures(&s[pos..]) {
-                let end = pos + re.find(&s[pos..])?.end();
-                let m = caps.get(1)?;
-                let n = m.as_str().parse::<usize>().ok()?;
-                return Some((SnippetElement::Tabstop(n), end));
-            }
-        }
-
-        None
-    }
-
-    fn extract_placeholder(s: &str, pos: usize) -> Option<(SnippetElement, usize)> {
-        let re = Regex::new(r#"^\$\{(\d+):(.*?)\}"#).unwrap();
-        let end = pos + re.find(&s[pos..])?.end();
-
-        let caps = re.captures(&s[pos..])?;
-
-        let tab = caps.get(1)?.as_str().parse::<usize>().ok()?;
-
-        let m = caps.get(2)?;
-        let content = m.as_str();
-        if content.is_empty() {
-            return Some((
-                SnippetElement::PlaceHolder(
-                    tab,
-                    vec![SnippetElement::Text("".to_string())],
-                ),
-                end,
-            ));
-        }
-        let (els, pos) =
-            Self::extract_elements(s, pos + m.start(), vec!["$", "}", "\\"], vec![]);
-        Some((SnippetElement::PlaceHolder(tab, els), pos + 1))
-    }
-
-    fn extract_text(
-        s: &str,
-        pos: usize,
-        escs: Vec<&str>,
-        loose_escs: Vec<&str>,
-    ) -> Option<(SnippetElement, usize)> {
-        let mut s = &s[pos..];
-        let mut ele = "".to_string();
-        let mut end = pos;
-
-        while !s.is_empty() {
-            if s.len() >= 2 {
-                let esc = &s[..2];
-                let mut new_escs = escs.clone();
-                new_escs.extend_from_slice(&loose_escs);
-
-                if new_escs
-                    .iter()
-                    .map(|e| format!("\\{}", e))
-                    .any(|x| x == *esc)
-                {
-                    ele += &s[1..2];
-                    end += 2;
-                    s = &s[2..];
-                    continue;
-                }
-            }
-            if escs.contains(&&s[0..1]) {
-                break;
-            }
-            ele += &s[0..1];
-            end += 1;
-            s = &s[1..];
-        }
-        if ele.is_empty() {
-            return None;
-        }
-        Some((SnippetElement::Text(ele), end))
-    }
-
-    pub fn text(&self) -> String {
-        self.elements.iter().map(|e| e.text()).join("")
-    }
-
-    pub fn tabs(&self, pos: usize) -> Vec<(usize, (usize, usize))> {
-        Self::elements_tabs(&self.elements, pos)
-    }
-
-    pub fn elements_tabs(
-        elements: &[SnippetElement],
-        start: usize,
-    ) -> Vec<(usize, (usize, usize))> {
-        let mut tabs = Vec::new();
-        let mut pos = start;
-        for el in elements {
-            match el {
-                SnippetElement::Text(t) => {
-                    pos += t.len();
-                }
-                SnippetElement::PlaceHolder(tab, els) => {
-                    let placeholder_tabs = Self::elements_tabs(els, pos);
-                    let end = pos + els.iter().map(|e| e.len()).sum::<usize>();
-                    tabs.push((*tab, (pos, end)));
-                    tabs.extend_from_slice(&placeholder_tabs);
-                    pos = end;
-                }
-                SnippetElement::Tabstop(tab) => {
-                    tabs.push((*tab, (pos, pos)));
-                }
-            }
-        }
-        tabs
-    }
-}
-
-impl FromStr for Snippet {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (elements, _) = Self::extract_elements(s, 0, vec!["$", "\\"], vec!["}"]);
-        Ok(Snippet { elements })
-    }
-}
-
-impl Display for Snippet {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let text = self.elements.iter().map(|e| e.to_string()).join("");
-        f.write_str(&text)
-    }
-}
-
-#[derive(Debug)]
-pub enum SnippetElement {
-    Text(String),
-    PlaceHolder(usize, Vec<SnippetElement>),
-    Tabstop(usize),
-}
-
-impl SnippetElement {
-    pub fn len(&self) -> usize {
-        match &self {
-            SnippetElement::Text(text) => text.len(),
-            SnippetElement::PlaceHolder(_, elements) => {
-                elements.iter().map(|e| e.len()).sum()
-            }
-            SnippetElement::Tabstop(_) => 0,
-        }
-    }
-
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
-    }
-
-    pub fn text(&self) -> String {
-        match &self {
-            SnippetElement::Text(t) => t.to_string(),
-            SnippetElement::PlaceHolder(_, elements) => {
-                elements.iter().map(|e| e.text()).join("")
-            }
-            SnippetElement::Tabstop(_) => "".to_string(),
-        }
-    }
-}
-
-impl Display for SnippetElement {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self {
-            SnippetElement::Text(text) => f.write_str(text),
-            SnippetElement::PlaceHolder(tab, elements) => {
-                let elements = elements.iter().map(|e| e.to_string()).join("");
-                write!(f, "${{{}:{}}}", tab, elements)
-            }
-            SnippetElement::Tabstop(tab) => write!(f, "${}", tab),
-        }
-    }
-}
+use crate::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    config::Config,
+    list::ListData,
+    proxy::LapceProxy,
+};
 
 #[derive(Clone, PartialEq, Eq)]
 pub enum CompletionStatus {
@@ -241,6 +30,12 @@ pub struct CompletionData {
     pub buffer_id: BufferId,
     pub input: String,
     pub input_items: im::HashMap<String, im::Vector<ScoredCompletionItem>>,
+    /// The user's snippets, which are offered along with the items of the
+    /// plugins
+    pub snippets: im::Vector<ScoredCompletionItem>,
+    /// The Emmet abbreviation before the cursor, which is offered first
+    /// whatever the input is
+    pub emmet: Option<ScoredCompletionItem>,
     empty: im::Vector<ScoredCompletionItem>,
     pub completion_list: ListData<ScoredCompletionItem, ()>,
     pub matcher: Arc<SkimMatcherV2>,
@@ -264,6 +59,8 @@ pub fn new(config: Arc<Config>) -> Self {
             buffer_id: BufferId(0),
             input: "".to_string(),
             input_items: im::HashMap::new(),
+            snippets: im::Vector::new(),
+            emmet: None,
             completion_list,
             matcher: Arc::new(SkimMatcherV2::default().ignore_case()),
             // TODO: Make this configurable
@@ -327,9 +124,67 @@ pub fn cancel(&mut self) {
         self.status = CompletionStatus::Inactive;
         self.input = "".to_string();
         self.input_items.clear();
+        self.snippets.clear();
+        self.emmet = None;
         self.completion_list.clear_items();
     }
 
+    pub fn set_snippets(&mut self, items: Vec<CompletionItem>) {
+        self.snippets = Self::scored_items(&items, PluginId(0), 0);
+        self.filter_items();
+    }
+
+    pub fn set_emmet(&mut self, item: Option<CompletionItem>) {
+        self.emmet = item.map(|item| ScoredCompletionItem {
+            item,
+            plugin_id: PluginId(0),
+            priority: 0,
+            score: 0,
+            label_score: 0,
+            indices: Vec::new(),
+            resolved: false,
+        });
+        self.filter_items();
+    }
+
+    /// Show the items at the offset, instead of the ones the plugins would
+    /// complete there
+    pub fn show_items(
+        &mut self,
+        buffer_id: BufferId,
+        offset: usize,
+        items: Vec<CompletionItem>,
+    ) {
+        self.cancel();
+        self.status = CompletionStatus::Started;
+        self.buffer_id = buffer_id;
+        self.offset = offset;
+        self.request_id += 1;
+        self.input_items
+            .insert("".to_string(), Self::scored_items(&items, PluginId(0), 0));
+        self.filter_items();
+        self.completion_list.selected_index = 0;
+    }
+
+    fn scored_items(
+        items: &[CompletionItem],
+        plugin_id: PluginId,
+        priority: i64,
+    ) -> im::Vector<ScoredCompletionItem> {
+        items
+            .iter()
+            .map(|i| ScoredCompletionItem {
+                item: i.to_owned(),
+                plugin_id,
+                priority,
+                score: 0,
+                label_score: 0,
+                indices: Vec::new(),
+                resolved: false,
+            })
+            .collect()
+    }
+
     pub fn update_input(&mut self, input: String) {
         self.input = input;
         self.completion_list.selected_index = 0;
@@ -345,6 +200,7 @@ pub fn receive(
         input: String,
         resp: CompletionResponse,
         plugin_id: PluginId,
+        priority: i64,
     ) {
         if self.status == CompletionStatus::Inactive || self.request_id != request_id
         {
@@ -355,18 +211,18 @@ pub fn receive(
             CompletionResponse::Array(items) => items,
             CompletionResponse::List(list) => list.items,
         };
-        let items: im::Vector<ScoredCompletionItem> = items
+        // Several language servers can complete the same input, so their
+        // items are merged, those of the servers with a higher priority first
+        let mut merged = self.input_items.remove(&input).unwrap_or_default();
+        merged.retain(|i| i.plugin_id != plugin_id);
+        let index = merged
             .iter()
-            .map(|i| ScoredCompletionItem {
-                item: i.to_owned(),
-                plugin_id,
-                score: 0,
-                label_score: 0,
-                indices: Vec::new(),
-            })
-            .collect();
-
-        self.input_items.insert(input, items);
+            .position(|i| i.priority < priority)
+            .unwrap_or(merged.len());
+        let lower = merged.split_off(index);
+        merged.append(Self::scored_items(&items, plugin_id, priority));
+        merged.append(lower);
+        self.input_items.insert(input, merged);
         self.filter_items();
 
         if self.completion_list.selected_index >= self.len() {
@@ -374,15 +230,86 @@ pub fn receive(
         }
     }
 
+    /// Ask the plugin of the highlighted item for the rest of it, like its
+    /// documentation and the additional edits to apply with it, which
+    /// language servers can leave out of the completion list
+    pub fn resolve_current_item(
+        &self,
+        proxy: &LapceProxy,
+        event_sink: ExtEventSink,
+    ) {
+        let item = match self.current_item() {
+            Some(item) => item,
+            None => return,
+        };
+        if item.resolved || item.item.data.is_none() {
+            return;
+        }
+
+        let widget_id = self.id;
+        let plugin_id = item.plugin_id;
+        let unresolved = item.item.clone();
+        proxy.proxy_rpc.completion_resolve(
+            plugin_id,
+            item.item.clone(),
+            move |result| {
+                if let Ok(ProxyResponse::CompletionResolveResponse { item }) = result
+                {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::CompletionItemResolved {
+                            plugin_id,
+                            item: Box::new(unresolved),
+                            resolved: item,
+                        },
+                        Target::Widget(widget_id),
+                    );
+                }
+            },
+        );
+    }
+
+    /// Replace the item of the plugin with the resolved one, wherever it's
+    /// listed
+    pub fn set_resolved_item(
+        &mut self,
+        plugin_id: PluginId,
+        item: &CompletionItem,
+        resolved: CompletionItem,
+    ) {
+        if self.status == CompletionStatus::Inactive {
+            return;
+        }
+
+        let resolve = |items: &mut im::Vector<ScoredCompletionItem>| {
+            for i in items.iter_mut() {
+                if i.plugin_id == plugin_id && !i.resolved && &i.item == item {
+                    i.item = resolved.clone();
+                    i.resolved = true;
+                }
+            }
+        };
+        for (_, items) in self.input_items.iter_mut() {
+            resolve(items);
+        }
+        resolve(&mut self.completion_list.items);
+    }
+
     pub fn filter_items(&mut self) {
         if self.input.is_empty() {
-            self.completion_list.items = self.all_items().clone();
+            let mut items = self.all_items().clone();
+            items.append(self.snippets.clone());
+            if let Some(emmet) = self.emmet.clone() {
+                items.push_front(emmet);
+            }
+            self.completion_list.items = items;
             return;
         }
 
         let mut items: im::Vector<ScoredCompletionItem> = self
             .all_items()
             .iter()
+            .chain(self.snippets.iter())
             .filter_map(|i| {
                 let filter_text =
                     i.item.filter_text.as_ref().unwrap_or(&i.item.label);
@@ -414,8 +341,12 @@ pub fn filter_items(&mut self) {
             b.score
                 .cmp(&a.score)
                 .then_with(|| b.label_score.cmp(&a.label_score))
+                .then_with(|| b.priority.cmp(&a.priority))
                 .then_with(|| a.item.label.len().cmp(&b.item.label.len()))
         });
+        if let Some(emmet) = self.emmet.clone() {
+            items.push_front(emmet);
+        }
         self.completion_list.items = items;
     }
 
@@ -428,27 +359,11 @@ pub fn run_focus_command(&mut self, ctx: &mut EventCtx, command: &FocusCommand)
 pub struct ScoredCompletionItem {
     pub item: CompletionItem,
     pub plugin_id: PluginId,
+    /// The priority of the language server the item is from
+    pub priority: i64,
     pub score: i64,
     pub label_score: i64,
     pub indices: Vec<usize>,
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_snippet() {
-        let s = "start $1${2:second ${3:third}} $0";
-        let parsed = Snippet::from_str(s).unwrap();
-        assert_eq!(s, parsed.to_string());
-
-        let text = "start second third ";
-        assert_eq!(text, parsed.text());
-
-        assert_eq!(
-            vec![(1, (6, 6)), (2, (6, 18)), (3, (13, 18)), (0, (19, 19))],
-            parsed.tabs(0)
-        );
-    }
+    /// Whether the item was resolved by its plugin already
+    pub resolved: bool,
 }
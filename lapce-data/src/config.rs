@@ -10,7 +10,12 @@
     Color, ExtEventSink, FontFamily, Size, Target,
 };
 use indexmap::IndexMap;
+use lapce_core::language::LapceLanguage;
 use lapce_proxy::{directory::Directory, plugin::wasi::find_all_volts};
+use lapce_rpc::{
+    dap_types::DebuggerConfig, plugin::LanguageServerConfig,
+    proxy::DocumentFormatter, terminal::TerminalProfile,
+};
 use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -21,6 +26,7 @@
 use crate::{
     command::{LapceUICommand, LAPCE_UI_COMMAND},
     data::{LapceWorkspace, LapceWorkspaceType},
+    snippet::UserSnippets,
 };
 
 pub use lapce_proxy::APPLICATION_NAME;
@@ -50,6 +56,14 @@ impl LapceTheme {
     pub const EDITOR_CURRENT_LINE: &str = "editor.current_line";
     pub const EDITOR_LINK: &str = "editor.link";
     pub const EDITOR_VISIBLE_WHITESPACE: &str = "editor.visible_whitespace";
+    pub const EDITOR_BRACKET_COLORS: [&str; 3] =
+        ["editor.bracket.1", "editor.bracket.2", "editor.bracket.3"];
+    pub const EDITOR_BRACKET_GUIDE: &str = "editor.bracket_guide";
+    pub const EDITOR_RULER: &str = "editor.ruler";
+    pub const EDITOR_DOCUMENT_HIGHLIGHT_READ: &str =
+        "editor.document_highlight.read";
+    pub const EDITOR_DOCUMENT_HIGHLIGHT_WRITE: &str =
+        "editor.document_highlight.write";
 
     pub const INLAY_HINT_FOREGROUND: &str = "inlay_hint.foreground";
     pub const INLAY_HINT_BACKGROUND: &str = "inlay_hint.background";
@@ -149,6 +163,37 @@ pub struct LapceConfig {
         desc = "Enable customised titlebar and disable OS native one (Linux, BSD, Windows)"
     )]
     pub custom_titlebar: bool,
+    #[field_names(
+        desc = "Set to \"offline\" to turn off all update checks and downloads, and hide the update UI.\nOptions: online, offline."
+    )]
+    pub network: String,
+    #[field_names(
+        desc = "Set the channel Lapce updates from. If empty, it uses the channel of the running build.\nOptions: stable, beta, nightly."
+    )]
+    pub update_channel: String,
+    #[field_names(
+        desc = "Set how often Lapce checks for updates.\nOptions: startup, hourly, daily, never."
+    )]
+    pub update_check_frequency: String,
+    #[field_names(
+        desc = "Set the proxy used to download updates. If empty, it uses the HTTP_PROXY/HTTPS_PROXY environment variables."
+    )]
+    pub update_proxy: String,
+    #[field_names(
+        desc = "Set the path to an extra root CA certificate (PEM) trusted when downloading updates. If empty, it uses SSL_CERT_FILE."
+    )]
+    pub update_ca_certificate: String,
+    #[field_names(
+        desc = "Set the URL of a server hosting Lapce releases, in the same JSON format as the GitHub releases API. If empty, it uses GitHub."
+    )]
+    pub update_server_url: String,
+}
+
+impl LapceConfig {
+    /// In offline mode Lapce doesn't look for or download updates.
+    pub fn is_offline(&self) -> bool {
+        self.network.trim().eq_ignore_ascii_case("offline")
+    }
 }
 
 #[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
@@ -176,6 +221,20 @@ pub struct EditorConfig {
         desc = "Show code context like functions and classes at the top of editor when scroll"
     )]
     pub sticky_header: bool,
+    #[field_names(
+        desc = "Set the maximum number of lines of code context shown at the top of the editor"
+    )]
+    pub sticky_header_max_lines: usize,
+    #[field_names(desc = "Color matching brackets by how deeply they are nested")]
+    pub bracket_pair_colorization: bool,
+    #[field_names(
+        desc = "Show a vertical guide for the bracket pair that encloses the cursor"
+    )]
+    pub bracket_pair_guide: bool,
+    #[field_names(
+        desc = "How the guides at each level of indentation are shown.\nOptions: none, plain, rainbow."
+    )]
+    pub indent_guides: String,
     #[field_names(
         desc = "If the editor should show the documentation of the current completion item"
     )]
@@ -184,6 +243,14 @@ pub struct EditorConfig {
         desc = "Whether the editor should disable automatic closing of matching pairs"
     )]
     pub auto_closing_matching_pairs: bool,
+    #[field_names(
+        desc = "If backspace should delete all the whitespace back to the previous non-blank character"
+    )]
+    pub hungry_delete: bool,
+    #[field_names(
+        desc = "If Emmet abbreviations should be expanded with Tab and offered as completions in HTML, CSS and JSX files"
+    )]
+    pub emmet: bool,
     #[field_names(
         desc = "How long (in ms) it should take before the hover information appears"
     )]
@@ -196,8 +263,38 @@ pub struct EditorConfig {
         desc = "Whether it should format the document on save (if there is an available formatter)"
     )]
     pub format_on_save: bool,
+    #[field_names(
+        desc = "Set what formats documents: \"lsp\" for the language server with the highest priority, the name of a language server, or \"command:\" followed by a command that reads the document from stdin and writes it formatted to stdout."
+    )]
+    pub formatter: String,
+    #[field_names(
+        desc = "Set how long formatting may take before it's skipped, in milliseconds"
+    )]
+    pub format_timeout: u64,
+    #[field_names(
+        desc = "Set which kinds of code actions run before a document is saved and formatted, in order and separated by commas, e.g. \"source.organizeImports, source.fixAll\". They are skipped when saving without formatting."
+    )]
+    pub code_actions_on_save: String,
+    #[field_names(
+        desc = "Set whether the language server formats the document as you type the characters it asks for, like } or ;: \"on\" or \"off\"."
+    )]
+    pub format_on_type: String,
+    #[field_names(
+        desc = "Set which colors win where the semantic tokens of the language server and the tree-sitter highlighting overlap: \"semantic\" or \"syntax\". The other source still colors what the preferred one leaves out, and the token modifiers of the language server are always drawn."
+    )]
+    pub semantic_highlighting: String,
     #[field_names(desc = "If inlay hints should be displayed")]
     pub enable_inlay_hints: bool,
+    #[field_names(desc = "If the inlay hints of types should be displayed")]
+    pub enable_type_inlay_hints: bool,
+    #[field_names(
+        desc = "If the inlay hints of parameter names should be displayed"
+    )]
+    pub enable_parameter_inlay_hints: bool,
+    #[field_names(
+        desc = "If inlay hints should only be displayed while Ctrl+Alt is held"
+    )]
+    pub inlay_hints_while_held: bool,
     #[field_names(
         desc = "Set the inlay hint font family. If empty, it uses the editor font family."
     )]
@@ -220,6 +317,10 @@ pub struct EditorConfig {
         desc = "Set the error lens font size. If 0 it uses the inlay hint font size."
     )]
     pub error_lens_font_size: usize,
+    #[field_names(
+        desc = "If the code lenses of the language server, like \"Run test\" or \"N references\", should be displayed on a line above the code they are for"
+    )]
+    pub enable_lsp_code_lens: bool,
     #[field_names(
         desc = "Set the cursor blink interval (in milliseconds). Set to 0 to completely disable."
     )]
@@ -233,13 +334,39 @@ pub struct EditorConfig {
     )]
     pub multicursor_whole_words: bool,
     #[field_names(
-        desc = "How the editor should render whitespace characters.\nOptions: none, all, boundary, trailing."
+        desc = "Whether double clicking selects the camelCase or snake_case part of the word under the mouse instead of the whole word."
+    )]
+    pub double_click_selects_subword: bool,
+    #[field_names(
+        desc = "How the editor should render whitespace characters.\nOptions: none, all, boundary, trailing, selection."
     )]
     pub render_whitespace: String,
     #[field_names(
-        desc = "Set the auto save delay (in milliseconds), Set to 0 to completely disable"
+        desc = "When files are saved automatically.\nOptions: off, after-delay, on-focus-change, on-window-change.\nWhen empty, a non-zero autosave-interval turns on after-delay."
+    )]
+    pub autosave: String,
+    #[field_names(
+        desc = "Set the delay (in milliseconds) after the last edit before a file is saved when auto save is after-delay. Setting it to 0 uses a delay of 1000 ms for after-delay, and a non-zero value turns on after-delay when autosave is empty"
     )]
     pub autosave_interval: u64,
+    #[field_names(
+        desc = "Set the file size (in MB) above which files are opened as large files, which are loaded a chunk at a time without syntax highlighting, language servers or the diff against the repository. Set to 0 to disable"
+    )]
+    pub large_file_size: u64,
+    #[field_names(
+        desc = "How long lines are soft wrapped.\nOptions: none, column."
+    )]
+    pub wrap: String,
+    #[field_names(desc = "Set the column at which long lines are soft wrapped")]
+    pub wrap_column: usize,
+    #[field_names(
+        desc = "Set how many columns wrapped lines are indented by, on top of the indentation of the line itself"
+    )]
+    pub wrap_indent: usize,
+    #[field_names(
+        desc = "Set the columns at which vertical rulers are drawn, e.g. [80, 100].\nSet rulers in a [languages.<language>] table to set them per language, e.g. rulers = [100] in [languages.rust], which works for wrap, formatter, code-actions-on-save, format-on-type and semantic-highlighting too."
+    )]
+    pub rulers: Vec<usize>,
 }
 
 impl EditorConfig {
@@ -297,6 +424,77 @@ pub fn error_lens_font_size(&self) -> usize {
             self.error_lens_font_size
         }
     }
+
+    /// The file size in bytes above which files are opened as large files,
+    /// or 0 if they never are
+    pub fn large_file_size_bytes(&self) -> u64 {
+        self.large_file_size.saturating_mul(1024 * 1024)
+    }
+
+    pub fn autosave(&self) -> AutoSave {
+        match self.autosave.trim().to_lowercase().as_str() {
+            "after-delay" => AutoSave::AfterDelay,
+            "on-focus-change" => AutoSave::OnFocusChange,
+            "on-window-change" => AutoSave::OnWindowChange,
+            "" if self.autosave_interval > 0 => AutoSave::AfterDelay,
+            _ => AutoSave::Off,
+        }
+    }
+
+    /// The delay after the last edit before an after-delay auto save.
+    pub fn autosave_delay(&self) -> u64 {
+        if self.autosave_interval > 0 {
+            self.autosave_interval
+        } else {
+            1000
+        }
+    }
+}
+
+/// The settings of the documents in one language, which take precedence
+/// over the ones of the editor
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct LanguageConfig {
+    pub rulers: Option<Vec<usize>>,
+    pub wrap: Option<String>,
+    pub formatter: Option<String>,
+    pub code_actions_on_save: Option<String>,
+    pub format_on_type: Option<String>,
+    pub semantic_highlighting: Option<String>,
+}
+
+/// Parse the names of the `[languages.<language>]` tables, skipping the ones
+/// which aren't a known language
+fn deserialize_languages<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<LapceLanguage, LanguageConfig>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let languages = HashMap::<String, LanguageConfig>::deserialize(deserializer)?;
+    Ok(languages
+        .into_iter()
+        .filter_map(|(name, config)| match name.parse::<LapceLanguage>() {
+            Ok(language) => Some((language, config)),
+            Err(_) => {
+                log::warn!("there's no language {name}");
+                None
+            }
+        })
+        .collect())
+}
+
+/// What triggers the automatic saving of modified files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoSave {
+    Off,
+    /// Save a file once it hasn't been edited for `autosave_interval`
+    AfterDelay,
+    /// Save the files when the focus moves away from their editor
+    OnFocusChange,
+    /// Save the files when another window gets the focus
+    OnWindowChange,
 }
 
 #[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
@@ -411,6 +609,56 @@ pub struct TerminalConfig {
     pub line_height: usize,
     #[field_names(desc = "Set the terminal Shell")]
     pub shell: String,
+    #[field_names(
+        desc = "How many lines of output each terminal keeps above the screen, up to 100000"
+    )]
+    pub scrollback_lines: usize,
+    #[field_names(
+        desc = "Start bash, zsh, fish and PowerShell with a script that marks where each command starts and ends, to scroll between the commands and see how they exited"
+    )]
+    pub shell_integration: bool,
+    #[field_names(
+        desc = "Run the terminals of remote workspaces in tmux sessions on the remote machine, which keep running when Lapce closes, so that reopening the workspace reattaches to them"
+    )]
+    pub persistent_sessions: bool,
+    #[field_names(
+        desc = "Show in the status bar when a terminal that isn't focused rings its bell"
+    )]
+    pub bell_notification: bool,
+    #[field_names(
+        desc = "Show in the status bar when a command that ran for at least this many seconds finishes in a terminal that isn't focused, which needs the shell integration. If 0, it's never shown"
+    )]
+    pub command_notification_seconds: usize,
+    #[field_names(
+        desc = "The profile of the [terminal-profiles] settings that new terminals use on Linux and the BSDs. If empty, they use the terminal Shell"
+    )]
+    pub default_profile_linux: String,
+    #[field_names(
+        desc = "The profile of the [terminal-profiles] settings that new terminals use on macOS. If empty, they use the terminal Shell"
+    )]
+    pub default_profile_macos: String,
+    #[field_names(
+        desc = "The profile of the [terminal-profiles] settings that new terminals use on Windows. If empty, they use the terminal Shell"
+    )]
+    pub default_profile_windows: String,
+}
+
+impl TerminalConfig {
+    /// The name of the profile that new terminals use on this system
+    pub fn default_profile(&self) -> Option<&str> {
+        let name = if cfg!(target_os = "windows") {
+            &self.default_profile_windows
+        } else if cfg!(target_os = "macos") {
+            &self.default_profile_macos
+        } else {
+            &self.default_profile_linux
+        };
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -592,6 +840,22 @@ pub struct Config {
     pub editor: EditorConfig,
     pub terminal: TerminalConfig,
     pub theme: ThemeConfig,
+    /// The language servers to start besides those of the plugins, and the
+    /// priorities of the servers, by name
+    #[serde(default, rename = "language-servers")]
+    pub language_servers: HashMap<String, LanguageServerConfig>,
+    /// The debug adapters by the type of the debug configurations they
+    /// debug, which are used over the ones of the plugins
+    #[serde(default)]
+    pub debuggers: HashMap<String, DebuggerConfig>,
+    /// The ways to start a terminal by name, which the settings of the
+    /// workspace can add to or override
+    #[serde(default, rename = "terminal-profiles")]
+    pub terminal_profiles: HashMap<String, TerminalProfile>,
+    /// The settings of the documents in a language, by the language, which
+    /// is parsed from the name of its table once when the config is loaded
+    #[serde(default, deserialize_with = "deserialize_languages")]
+    pub languages: HashMap<LapceLanguage, LanguageConfig>,
     #[serde(flatten)]
     pub plugins: HashMap<String, serde_json::Value>,
     #[serde(skip)]
@@ -601,7 +865,13 @@ pub struct Config {
     #[serde(skip)]
     pub available_themes: HashMap<String, (String, config::Config)>,
     #[serde(skip)]
+    pub snippets: Arc<UserSnippets>,
+    #[serde(skip)]
     tab_layout_info: Arc<RwLock<HashMap<(FontFamily, usize), f64>>>,
+    /// If Ctrl+Alt is held, to show the inlay hints that are only displayed
+    /// while it is
+    #[serde(skip)]
+    pub inlay_hints_held: bool,
 }
 
 pub struct ConfigWatcher {
@@ -676,6 +946,7 @@ pub fn load(workspace: &LapceWorkspace) -> Result<Self> {
         }
         config.update_id();
         config.available_themes = available_themes;
+        config.snippets = Arc::new(UserSnippets::load());
         config.resolve_colors(Some(&default_config));
         config.default_theme = default_config.theme.clone();
 
@@ -915,12 +1186,27 @@ pub fn update_file(
     }
 
     fn update_id(&mut self) {
+        // the id changes more than once a second while keys are held
         self.id = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs())
+            .map(|d| d.as_nanos() as u64)
             .unwrap_or(0);
     }
 
+    /// Whether inlay hints are displayed, which in the mode that only shows
+    /// them while Ctrl+Alt is held depends on the keys
+    pub fn inlay_hints_visible(&self) -> bool {
+        self.editor.enable_inlay_hints
+            && (!self.editor.inlay_hints_while_held || self.inlay_hints_held)
+    }
+
+    pub fn set_inlay_hints_held(&mut self, held: bool) {
+        if self.inlay_hints_held != held {
+            self.inlay_hints_held = held;
+            self.update_id();
+        }
+    }
+
     pub fn set_theme(&mut self, theme: &str, preview: bool) -> bool {
         self.update_id();
 
@@ -985,6 +1271,121 @@ pub fn terminal_font_size(&self) -> usize {
         }
     }
 
+    /// The profile of the name, or the default one, along with the name of
+    /// the profile that was found. Without a profile, the terminal runs the
+    /// terminal Shell
+    pub fn terminal_profile(
+        &self,
+        name: Option<&str>,
+    ) -> (Option<String>, TerminalProfile) {
+        let name = name.or_else(|| self.terminal.default_profile());
+        if let Some(name) = name {
+            match self.terminal_profiles.get(name) {
+                Some(profile) => return (Some(name.to_string()), profile.clone()),
+                None => log::warn!("there's no terminal profile {name}"),
+            }
+        }
+
+        let mut parts = self.terminal.shell.split_whitespace();
+        let profile = TerminalProfile {
+            command: parts.next().map(|command| command.to_string()),
+            arguments: parts.map(|arg| arg.to_string()).collect(),
+            ..Default::default()
+        };
+        (None, profile)
+    }
+
+    /// The settings of the documents in `language`, if there are any
+    pub fn language(
+        &self,
+        language: Option<LapceLanguage>,
+    ) -> Option<&LanguageConfig> {
+        self.languages.get(&language?)
+    }
+
+    /// The columns of the rulers of a document in `language`, which the
+    /// `rulers` of the language take precedence over
+    pub fn rulers_at(&self, language: Option<LapceLanguage>) -> &[usize] {
+        self.language(language)
+            .and_then(|config| config.rulers.as_deref())
+            .unwrap_or(&self.editor.rulers)
+    }
+
+    /// The column at which the lines of a document in `language` are soft
+    /// wrapped, or `None` if they aren't
+    pub fn wrap_at(&self, language: Option<LapceLanguage>) -> Option<usize> {
+        let wrap = self
+            .language(language)
+            .and_then(|config| config.wrap.as_deref())
+            .unwrap_or(&self.editor.wrap);
+        match wrap.trim().to_lowercase().as_str() {
+            "column" => Some(self.editor.wrap_column.max(1)),
+            _ => None,
+        }
+    }
+
+    /// The kinds of the code actions to run on saving a document in
+    /// `language`, in the order they run
+    pub fn code_actions_on_save_at(
+        &self,
+        language: Option<LapceLanguage>,
+    ) -> Vec<String> {
+        self.language(language)
+            .and_then(|config| config.code_actions_on_save.as_deref())
+            .unwrap_or(&self.editor.code_actions_on_save)
+            .split(',')
+            .map(|kind| kind.trim())
+            .filter(|kind| !kind.is_empty())
+            .map(|kind| kind.to_string())
+            .collect()
+    }
+
+    /// The formatter of a document in `language`
+    pub fn formatter_at(
+        &self,
+        language: Option<LapceLanguage>,
+    ) -> DocumentFormatter {
+        let formatter = self
+            .language(language)
+            .and_then(|config| config.formatter.as_deref())
+            .unwrap_or(&self.editor.formatter)
+            .trim();
+        if formatter.is_empty() || formatter == "lsp" {
+            DocumentFormatter::LanguageServer
+        } else if let Some(command) = formatter.strip_prefix("command:") {
+            DocumentFormatter::Command(command.trim().to_string())
+        } else {
+            DocumentFormatter::NamedLanguageServer(formatter.to_string())
+        }
+    }
+
+    /// Whether the language server formats a document in `language` as the
+    /// characters it asks for are typed
+    pub fn format_on_type_at(&self, language: Option<LapceLanguage>) -> bool {
+        self.language(language)
+            .and_then(|config| config.format_on_type.as_deref())
+            .unwrap_or(&self.editor.format_on_type)
+            .trim()
+            .eq_ignore_ascii_case("on")
+    }
+
+    /// Whether the colors of the semantic tokens are drawn over the ones of
+    /// the tree-sitter highlighting in a document in `language`, rather
+    /// than under them.
+    pub fn semantic_highlighting_at(&self, language: Option<LapceLanguage>) -> bool {
+        !self
+            .language(language)
+            .and_then(|config| config.semantic_highlighting.as_deref())
+            .unwrap_or(&self.editor.semantic_highlighting)
+            .trim()
+            .eq_ignore_ascii_case("syntax")
+    }
+
+    /// The color of the terminal theme of the name, like `red`
+    pub fn terminal_color(&self, name: &str) -> Option<&Color> {
+        self.color.ui.get(&format!("terminal.{name}"))
+    }
+
     pub fn terminal_line_height(&self) -> usize {
         if self.terminal.line_height > 0 {
             self.terminal.line_height
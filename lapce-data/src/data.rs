@@ -1,20 +1,21 @@
 use std::{
     cell::RefCell,
     cmp::Ordering,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     io::{BufReader, Read, Write},
     path::{Path, PathBuf},
     rc::Rc,
     sync::Arc,
     thread,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 #[cfg(target_os = "windows")]
 use std::env;
 
+use alacritty_terminal::grid::Scroll;
 use anyhow::{anyhow, Result};
-use crossbeam_channel::{unbounded, Receiver, Sender};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
 use druid::{
     piet::PietText, theme, Command, Data, Env, EventCtx, ExtEventSink,
     FileDialogOptions, Lens, Point, Rect, Size, Target, Vec2, WidgetId, WindowId,
@@ -22,9 +23,13 @@
 
 use itertools::Itertools;
 use lapce_core::{
+    buffer::Buffer,
     command::{FocusCommand, MultiSelectionCommand},
     cursor::{Cursor, CursorMode},
     editor::EditType,
+    indent::IndentStyle,
+    line_ending::LineEnding,
+    merge::three_way_merge,
     mode::MotionMode,
     movement::Movement,
     register::Register,
@@ -34,55 +39,87 @@
 use lapce_rpc::{
     buffer::BufferId,
     core::{CoreMessage, CoreNotification},
-    plugin::VoltInfo,
+    dap_types::{
+        CompletionTarget, DapId, DataBreakpoint, DataBreakpointAccessType,
+        DataBreakpointInfo, EvaluateContext, EvaluateResult, RunDebugConfig,
+        StackFrame, ThreadId, Variable,
+    },
+    plugin::{PluginId, VoltInfo},
     proxy::ProxyResponse,
     source_control::FileDiff,
     terminal::TermId,
     RpcMessage,
 };
 
-use lsp_types::{Diagnostic, DiagnosticSeverity, Position, ProgressToken, TextEdit};
+use lsp_types::{
+    CodeActionKind, CodeActionOrCommand, CodeActionResponse, Diagnostic,
+    DiagnosticSeverity, DocumentChangeOperation, DocumentChanges,
+    LinkedEditingRanges, Position, ProgressToken, Range, TextEdit,
+};
 use notify::Watcher;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use xi_rope::{Rope, RopeDelta};
+use xi_rope::{Rope, RopeDelta, Transformer};
 
 use crate::{
     about::AboutData,
     alert::{AlertContentData, AlertData},
+    call_hierarchy::CallHierarchyData,
     command::{
         CommandKind, EnsureVisiblePosition, InitBufferContentCb, LapceCommand,
         LapceUICommand, LapceWorkbenchCommand, LAPCE_COMMAND, LAPCE_OPEN_FILE,
         LAPCE_OPEN_FOLDER, LAPCE_UI_COMMAND,
     },
     completion::CompletionData,
-    config::{Config, ConfigWatcher, GetConfig, LapceTheme},
+    config::{AutoSave, Config, ConfigWatcher, GetConfig, LapceTheme},
     db::{
         EditorInfo, EditorTabChildInfo, EditorTabInfo, LapceDb, SplitContentInfo,
         SplitInfo, TabsInfo, WindowInfo, WorkspaceInfo,
     },
+    debug::{
+        picks_process, BreakpointOptionKind, DebugData, LapceDataBreakpoint,
+        LaunchVariables, MemoryView, MEMORY_PAGE_BYTES,
+    },
+    debug_console::{self, ConsoleLineKind, DebugConsoleData},
     document::{BufferContent, Document, LocalBufferKind},
-    editor::{EditorLocation, EditorPosition, LapceEditorBufferData, Line, TabRect},
+    editor::{
+        url_matches_path, workspace_edits, workspace_operation, EditorLocation,
+        EditorPosition, LapceEditorBufferData, Line, TabRect,
+    },
     explorer::FileExplorerData,
     find::Find,
+    history::{
+        compare_version, MERGE_BASE_VERSION, MERGE_MINE_VERSION,
+        MERGE_THEIRS_VERSION,
+    },
     hover::HoverData,
     keypress::KeyPressData,
+    lsp_log::LspLogData,
     palette::{PaletteData, PaletteType, PaletteViewData},
     panel::{
         PanelContainerPosition, PanelData, PanelKind, PanelOrder, PanelPosition,
     },
+    peek::PeekData,
     picker::FilePickerData,
     plugin::PluginData,
-    problem::ProblemData,
-    proxy::{LapceProxy, ProxyStatus, TermEvent},
+    problem::{next_problem, ProblemData},
+    proxy::{path_from_url, LapceProxy, ProxyStatus, TermEvent},
+    release_notes::ReleaseNotesData,
     rename::RenameData,
-    search::SearchData,
+    rename_preview::RenamePreviewData,
+    search::{SearchData, SearchReplacement},
+    selection_range::ExpandSelectionRanges,
     settings::LapceSettingsPanelData,
+    signature::SignatureData,
     source_control::SourceControlData,
     split::{SplitDirection, SplitMoveDirection},
-    terminal::TerminalSplitData,
+    terminal::{
+        LapceTerminalData, TerminalAlert, TerminalLaunch, TerminalSplitData,
+    },
     title::TitleData,
-    update::ReleaseInfo,
+    type_hierarchy::TypeHierarchyData,
+    undo_tree::UndoTreeData,
+    update::{ReleaseInfo, UpdateCheckFrequency, UpdateProgress, UpdateSettings},
 };
 
 /// `LapceData` is the topmost structure in a tree of structures that holds
@@ -103,6 +140,8 @@ pub struct LapceData {
     pub panel_orders: PanelOrder,
     /// The latest release information
     pub latest_release: Arc<Option<ReleaseInfo>>,
+    /// The progress of the update download, if one is running
+    pub update_progress: Arc<Option<UpdateProgress>>,
     /// The window on focus
     pub active_window: Arc<WindowId>,
 }
@@ -219,15 +258,56 @@ pub fn load(event_sink: ExtEventSink, paths: Vec<PathBuf>) -> Self {
         #[cfg(feature = "updater")]
         {
             let local_event_sink = event_sink.clone();
-            std::thread::spawn(move || loop {
-                if let Ok(release) = crate::update::get_latest_release() {
-                    let _ = local_event_sink.submit_command(
-                        LAPCE_UI_COMMAND,
-                        LapceUICommand::UpdateLatestRelease(release),
-                        Target::Global,
+            let local_db = db.clone();
+            std::thread::spawn(move || {
+                // The settings are read again at least this often, so that
+                // changing the frequency applies without a restart.
+                let max_wait = std::time::Duration::from_secs(60 * 60);
+                let mut checked = false;
+                loop {
+                    let config =
+                        Config::load(&LapceWorkspace::default()).unwrap_or_default();
+                    if config.lapce.is_offline() {
+                        std::thread::sleep(max_wait);
+                        continue;
+                    }
+                    let wait = crate::update::time_until_check(
+                        UpdateCheckFrequency::from_config(&config),
+                        crate::update::last_check(),
+                        std::time::SystemTime::now(),
+                        checked,
                     );
+                    if wait.map(|wait| !wait.is_zero()).unwrap_or(true) {
+                        std::thread::sleep(wait.unwrap_or(max_wait).min(max_wait));
+                        continue;
+                    }
+
+                    checked = true;
+                    let settings = UpdateSettings::from_config(&config);
+                    match crate::update::get_latest_release(&settings) {
+                        Ok(mut release) => {
+                            crate::update::record_check();
+                            release.dismissed = local_db
+                                .get_update_dismissal()
+                                .map(|dismissal| {
+                                    dismissal.hides(
+                                        &release.version,
+                                        std::time::SystemTime::now(),
+                                    )
+                                })
+                                .unwrap_or(false);
+                            let _ = local_event_sink.submit_command(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::UpdateLatestRelease(release),
+                                Target::Global,
+                            );
+                        }
+                        Err(err) => {
+                            log::info!("update check failed: {err}");
+                            std::thread::sleep(max_wait);
+                        }
+                    }
                 }
-                std::thread::sleep(std::time::Duration::from_secs(60 * 60));
             });
         }
 
@@ -249,6 +329,7 @@ pub fn load(event_sink: ExtEventSink, paths: Vec<PathBuf>) -> Self {
             db,
             panel_orders,
             latest_release,
+            update_progress: Arc::new(None),
         }
     }
 
@@ -264,7 +345,18 @@ pub fn default_panel_orders() -> PanelOrder {
         );
         order.insert(
             PanelPosition::BottomLeft,
-            im::vector![PanelKind::Terminal, PanelKind::Search, PanelKind::Problem,],
+            im::vector![
+                PanelKind::Terminal,
+                PanelKind::Search,
+                PanelKind::Problem,
+                PanelKind::UndoTree,
+                PanelKind::CallHierarchy,
+                PanelKind::TypeHierarchy,
+                PanelKind::RenamePreview,
+                PanelKind::LspLog,
+                PanelKind::Debug,
+                PanelKind::DebugConsole,
+            ],
         );
 
         order
@@ -418,6 +510,7 @@ pub struct LapceWindowData {
     pub pos: Point,
     pub panel_orders: PanelOrder,
     pub latest_release: Arc<Option<ReleaseInfo>>,
+    pub update_progress: Arc<Option<UpdateProgress>>,
 }
 
 impl LapceWindowData {
@@ -500,6 +593,9 @@ pub fn new(
         if let Some(path) = Directory::plugins_directory() {
             let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
         }
+        if let Some(path) = Directory::snippets_directory() {
+            let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
+        }
 
         Self {
             window_id,
@@ -516,6 +612,7 @@ pub fn new(
             maximised: info.maximised,
             panel_orders,
             latest_release,
+            update_progress: Arc::new(None),
         }
     }
 
@@ -554,8 +651,32 @@ pub struct EditorDiagnostic {
     pub lines: usize,
 }
 
+impl EditorDiagnostic {
+    pub fn new(diagnostic: Diagnostic) -> Self {
+        let lines = diagnostic
+            .related_information
+            .as_ref()
+            .map(|r| {
+                r.iter()
+                    .map(|r| r.message.matches('\n').count() + 1 + 1)
+                    .sum()
+            })
+            .unwrap_or(0)
+            + diagnostic.message.matches('\n').count()
+            + 1;
+        Self {
+            range: (0, 0),
+            diagnostic,
+            lines,
+        }
+    }
+}
+
+/// The work a language server reported it is doing, like indexing
 #[derive(Clone)]
 pub struct WorkProgress {
+    pub plugin_id: PluginId,
+    pub server: String,
     pub token: ProgressToken,
     pub title: String,
     pub message: Option<String>,
@@ -567,6 +688,7 @@ pub enum FocusArea {
     Palette,
     Editor,
     Rename,
+    Peek,
     Panel(PanelKind),
     FilePicker,
     BranchPicker,
@@ -589,12 +711,21 @@ pub struct LapceTabData {
     pub main_split: LapceMainSplitData,
     pub completion: Arc<CompletionData>,
     pub hover: Arc<HoverData>,
+    pub signature: Arc<SignatureData>,
     pub rename: Arc<RenameData>,
+    pub peek: Arc<PeekData>,
     pub terminal: Arc<TerminalSplitData>,
     pub palette: Arc<PaletteData>,
     pub find: Arc<Find>,
     pub source_control: Arc<SourceControlData>,
     pub problem: Arc<ProblemData>,
+    pub undo_tree: Arc<UndoTreeData>,
+    pub call_hierarchy: Arc<CallHierarchyData>,
+    pub type_hierarchy: Arc<TypeHierarchyData>,
+    pub rename_preview: Arc<RenamePreviewData>,
+    pub lsp_log: Arc<LspLogData>,
+    pub debug: Arc<DebugData>,
+    pub debug_console: Arc<DebugConsoleData>,
     pub search: Arc<SearchData>,
     pub plugin: Arc<PluginData>,
     pub picker: Arc<FilePickerData>,
@@ -606,6 +737,7 @@ pub struct LapceTabData {
     pub settings: Arc<LapceSettingsPanelData>,
     pub about: Arc<AboutData>,
     pub alert: Arc<AlertData>,
+    pub release_notes: Arc<ReleaseNotesData>,
     #[data(ignore)]
     pub term_tx: Arc<Sender<(TermId, TermEvent)>>,
     #[data(ignore)]
@@ -619,8 +751,12 @@ pub struct LapceTabData {
     #[data(ignore)]
     pub db: Arc<LapceDb>,
     pub progresses: Arc<Vec<WorkProgress>>,
+    /// A message about something that failed in the background, like a
+    /// code action on save, shown in the status bar until it's clicked
+    pub status_message: Arc<Option<String>>,
     pub drag: Arc<Option<(Vec2, Vec2, DragContent)>>,
     pub latest_release: Arc<Option<ReleaseInfo>>,
+    pub update_progress: Arc<Option<UpdateProgress>>,
 }
 
 impl GetConfig for LapceTabData {
@@ -662,6 +798,7 @@ pub fn new(
             workspace.clone(),
             all_disabled_volts,
             config.plugins.clone(),
+            config.language_servers.clone(),
             term_sender.clone(),
             event_sink.clone(),
         ));
@@ -669,11 +806,14 @@ pub fn new(
         let palette = Arc::new(PaletteData::new(config.clone(), proxy.clone()));
         let completion = Arc::new(CompletionData::new(config.clone()));
         let hover = Arc::new(HoverData::new());
+        let signature = Arc::new(SignatureData::new());
         let rename = Arc::new(RenameData::new());
+        let peek = Arc::new(PeekData::new(config.clone()));
         let source_control = Arc::new(SourceControlData::new());
         let settings = Arc::new(LapceSettingsPanelData::new());
         let about = Arc::new(AboutData::new());
         let alert = Arc::new(AlertData::new());
+        let release_notes = Arc::new(ReleaseNotesData::new());
         let plugin = Arc::new(PluginData::new(
             tab_id,
             disabled_volts,
@@ -687,6 +827,12 @@ pub fn new(
             event_sink.clone(),
         ));
         let search = Arc::new(SearchData::new());
+        let problem = Arc::new(ProblemData::new());
+        let debug_console = DebugConsoleData::new();
+        let mut terminal = TerminalSplitData::new(proxy.clone());
+        if let Some(info) = workspace_info.as_ref() {
+            terminal.restore = info.terminals.clone();
+        }
         let file_picker = Arc::new(FilePickerData::new());
 
         let unsaved_buffers = match db.get_unsaved_buffers() {
@@ -737,6 +883,43 @@ pub fn new(
             &config,
             event_sink.clone(),
         );
+        main_split.add_editor(
+            search.replace_view_id,
+            None,
+            LocalBufferKind::SearchReplace,
+            &config,
+            event_sink.clone(),
+        );
+        main_split.add_editor(
+            problem.filter_view_id,
+            None,
+            LocalBufferKind::ProblemFilter,
+            &config,
+            event_sink.clone(),
+        );
+        main_split.add_editor(
+            debug_console.input_view_id,
+            None,
+            LocalBufferKind::DebugConsole,
+            &config,
+            event_sink.clone(),
+        );
+        main_split.add_editor(
+            terminal.find_view_id,
+            None,
+            LocalBufferKind::TerminalFind,
+            &config,
+            event_sink.clone(),
+        );
+        main_split.local_docs.insert(
+            LocalBufferKind::FindReplace,
+            Arc::new(Document::new(
+                BufferContent::Local(LocalBufferKind::FindReplace),
+                tab_id,
+                event_sink.clone(),
+                proxy.clone(),
+            )),
+        );
         main_split.add_editor(
             palette.input_editor,
             None,
@@ -758,9 +941,25 @@ pub fn new(
             &config,
             event_sink.clone(),
         );
-
-        let terminal = Arc::new(TerminalSplitData::new(proxy.clone()));
-        let problem = Arc::new(ProblemData::new());
+        let peek_editor = LapceEditorData::new(
+            Some(peek.view_id),
+            None,
+            None,
+            BufferContent::Local(LocalBufferKind::Empty),
+            &config,
+        );
+        main_split
+            .editors
+            .insert(peek_editor.view_id, Arc::new(peek_editor));
+
+        let terminal = Arc::new(terminal);
+        let undo_tree = Arc::new(UndoTreeData::new());
+        let call_hierarchy = Arc::new(CallHierarchyData::new());
+        let type_hierarchy = Arc::new(TypeHierarchyData::new());
+        let rename_preview = Arc::new(RenamePreviewData::new());
+        let lsp_log = Arc::new(LspLogData::new());
+        let debug = Arc::new(DebugData::new());
+        let debug_console = Arc::new(debug_console);
         let panel = workspace_info
             .map(|i| {
                 let mut panel = i.panel;
@@ -781,10 +980,19 @@ pub fn new(
             main_split,
             completion,
             hover,
+            signature,
             rename,
+            peek,
             terminal,
             plugin,
             problem,
+            undo_tree,
+            call_hierarchy,
+            type_hierarchy,
+            rename_preview,
+            lsp_log,
+            debug,
+            debug_console,
             search,
             find: Arc::new(Find::new(0)),
             picker: file_picker,
@@ -797,6 +1005,7 @@ pub fn new(
             settings,
             about,
             alert,
+            release_notes,
             proxy_status: Arc::new(ProxyStatus::Connecting),
             keypress,
             window_origin: Rc::new(RefCell::new(Point::ZERO)),
@@ -805,8 +1014,10 @@ pub fn new(
             focus_area: FocusArea::Editor,
             db,
             progresses: Arc::new(Vec::new()),
+            status_message: Arc::new(None),
             drag: Arc::new(None),
             latest_release,
+            update_progress: Arc::new(None),
         };
         tab.start_update_process(event_sink);
         tab
@@ -818,9 +1029,16 @@ pub fn workspace_info(&self) -> WorkspaceInfo {
             .splits
             .get(&self.main_split.split_id)
             .unwrap();
+        let mut terminals: Vec<&Arc<LapceTerminalData>> =
+            self.terminal.terminals.values().collect();
+        terminals.sort_by_key(|terminal| terminal.term_id.0);
         WorkspaceInfo {
             split: main_split_data.split_info(self),
             panel: (*self.panel).clone(),
+            terminals: terminals
+                .into_iter()
+                .map(|terminal| terminal.info())
+                .collect(),
         }
     }
 
@@ -875,6 +1093,7 @@ pub fn editor_view_content(
             main_split: self.main_split.clone(),
             completion: self.completion.clone(),
             hover: self.hover.clone(),
+            signature: self.signature.clone(),
             rename: self.rename.clone(),
             focus_area: self.focus_area.clone(),
             source_control: self.source_control.clone(),
@@ -885,6 +1104,7 @@ pub fn editor_view_content(
             editor: editor.clone(),
             command_keymaps: self.keypress.command_keymaps.clone(),
             config: self.config.clone(),
+            debug: self.debug.clone(),
         }
     }
 
@@ -923,6 +1143,7 @@ pub fn update_from_editor_buffer_data(
     ) {
         self.completion = editor_buffer_data.completion.clone();
         self.hover = editor_buffer_data.hover.clone();
+        self.signature = editor_buffer_data.signature.clone();
         self.rename = editor_buffer_data.rename.clone();
         self.main_split = editor_buffer_data.main_split.clone();
         self.find = editor_buffer_data.find.clone();
@@ -1011,6 +1232,32 @@ pub fn completion_origin(
         }
     }
 
+    /// The origin of the peek view, below the line it was asked for, or above
+    /// it when there's no room below
+    pub fn peek_origin(
+        &self,
+        text: &mut PietText,
+        tab_size: Size,
+        peek_size: Size,
+        config: &Config,
+    ) -> Point {
+        let editor = match self.main_split.editors.get(&self.peek.from_editor) {
+            Some(editor) => editor,
+            None => return Point::ZERO,
+        };
+        let doc = self.main_split.editor_doc(editor.view_id);
+        let (point_above, point_below) =
+            doc.points_of_offset(text, self.peek.offset, &editor.view, config);
+
+        let editor_origin =
+            *editor.window_origin.borrow() - self.window_origin.borrow().to_vec2();
+        let mut origin = editor_origin + Vec2::new(0.0, point_below.y);
+        if origin.y + peek_size.height + 1.0 > tab_size.height {
+            origin.y = editor_origin.y + point_above.y - peek_size.height;
+        }
+        origin
+    }
+
     pub fn rename_origin(
         &self,
         text: &mut PietText,
@@ -1115,6 +1362,41 @@ pub fn hover_origin(
         }
     }
 
+    /// The origin of the signature, above the line of the cursor, or below
+    /// it when there is no room
+    pub fn signature_origin(
+        &self,
+        text: &mut PietText,
+        tab_size: Size,
+        config: &Config,
+    ) -> Point {
+        let editor = self.main_split.editors.get(&self.signature.editor_view_id);
+        let editor = match editor {
+            Some(editor) => editor,
+            None => return Point::ZERO,
+        };
+
+        let doc = self.main_split.editor_doc(editor.view_id);
+        let (point_above, point_below) =
+            doc.points_of_offset(text, self.signature.offset, &editor.view, config);
+        let content_size = *self.signature.content_size.borrow();
+        let editor_origin =
+            *editor.window_origin.borrow() - self.window_origin.borrow().to_vec2();
+        let mut origin = editor_origin
+            + Vec2::new(point_above.x, point_above.y - content_size.height);
+        if origin.y < 0.0 {
+            origin.y = editor_origin.y + point_below.y;
+        }
+        if origin.x + content_size.width + 1.0 > tab_size.width {
+            origin.x = tab_size.width - content_size.width - 1.0;
+        }
+        if origin.x <= 0.0 {
+            origin.x = 0.0;
+        }
+
+        origin
+    }
+
     pub fn palette_view_data(&self) -> PaletteViewData {
         PaletteViewData {
             palette: self.palette.clone(),
@@ -1138,21 +1420,31 @@ pub fn run_workbench_command(
     ) {
         match command {
             LapceWorkbenchCommand::RestartToUpdate => {
+                if self.config.lapce.is_offline() {
+                    return;
+                }
                 if let Some(release) = (*self.latest_release).clone() {
                     if release.version != *VERSION {
-                        if let Ok(process_path) = std::env::current_exe() {
-                            ctx.submit_command(Command::new(
-                                LAPCE_UI_COMMAND,
-                                LapceUICommand::RestartToUpdate(
-                                    process_path,
-                                    release,
-                                ),
-                                Target::Global,
-                            ));
-                        }
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::ShowReleaseNotes(release),
+                            Target::Widget(self.id),
+                        ));
                     }
                 }
             }
+            LapceWorkbenchCommand::CancelUpdate => {
+                crate::update::cancel_download();
+            }
+            LapceWorkbenchCommand::RollbackUpdate => {
+                if let Ok(process_path) = std::env::current_exe() {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::RollbackUpdate(process_path),
+                        Target::Global,
+                    ));
+                }
+            }
             LapceWorkbenchCommand::CloseFolder => {
                 if self.workspace.path.is_some() {
                     let mut workspace = (*self.workspace).clone();
@@ -1213,6 +1505,13 @@ pub fn run_workbench_command(
                     }
                 }
             }
+            LapceWorkbenchCommand::CompareActiveFileWith => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::CompareFile)),
+                    Target::Auto,
+                ))
+            }
             LapceWorkbenchCommand::RevealActiveFileInFileExplorer => {
                 let path = if let Some(editor) = self.main_split.active_editor() {
                     match &editor.content {
@@ -1457,6 +1756,27 @@ pub fn run_workbench_command(
             LapceWorkbenchCommand::ToggleProblemVisual => {
                 self.toggle_panel_visual(ctx, PanelKind::Problem);
             }
+            LapceWorkbenchCommand::ToggleUndoTreeVisual => {
+                self.toggle_panel_visual(ctx, PanelKind::UndoTree);
+            }
+            LapceWorkbenchCommand::ToggleCallHierarchyVisual => {
+                self.toggle_panel_visual(ctx, PanelKind::CallHierarchy);
+            }
+            LapceWorkbenchCommand::ToggleTypeHierarchyVisual => {
+                self.toggle_panel_visual(ctx, PanelKind::TypeHierarchy);
+            }
+            LapceWorkbenchCommand::ToggleRenamePreviewVisual => {
+                self.toggle_panel_visual(ctx, PanelKind::RenamePreview);
+            }
+            LapceWorkbenchCommand::ToggleLspLogVisual => {
+                self.toggle_panel_visual(ctx, PanelKind::LspLog);
+            }
+            LapceWorkbenchCommand::ToggleDebugVisual => {
+                self.toggle_panel_visual(ctx, PanelKind::Debug);
+            }
+            LapceWorkbenchCommand::ToggleDebugConsoleVisual => {
+                self.toggle_panel_visual(ctx, PanelKind::DebugConsole);
+            }
             LapceWorkbenchCommand::ToggleTerminalVisual => {
                 self.toggle_panel_visual(ctx, PanelKind::Terminal);
             }
@@ -1491,12 +1811,229 @@ pub fn run_workbench_command(
             LapceWorkbenchCommand::ToggleSearchFocus => {
                 self.toggle_panel_focus(ctx, PanelKind::Search);
             }
+            LapceWorkbenchCommand::ToggleGlobalSearchRegex => {
+                let search = Arc::make_mut(&mut self.search);
+                search.is_regex = !search.is_regex;
+                let pattern = self
+                    .main_split
+                    .local_docs
+                    .get(&LocalBufferKind::Search)
+                    .unwrap()
+                    .buffer()
+                    .to_string();
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::UpdateSearch(pattern),
+                    Target::Widget(self.id),
+                ));
+            }
+            LapceWorkbenchCommand::ToggleGlobalReplacePreserveCase => {
+                let search = Arc::make_mut(&mut self.search);
+                search.preserve_case = !search.preserve_case;
+            }
+            LapceWorkbenchCommand::ToggleReplacePreserveCase => {
+                let find = Arc::make_mut(&mut self.find);
+                find.preserve_case = !find.preserve_case;
+            }
+            LapceWorkbenchCommand::GlobalReplaceAll => {
+                let replacements = self.search.replacements();
+                if !replacements.is_empty() {
+                    let files = self.main_split.replace_in_files(
+                        ctx,
+                        replacements,
+                        &self.config,
+                    );
+                    self.status_message = Arc::new(Some(format!(
+                        "Replaced the matches in {files} file{}",
+                        if files == 1 { "" } else { "s" }
+                    )));
+                    // the matches are out of date with the files now
+                    Arc::make_mut(&mut self.search).clear_matches();
+                }
+            }
             LapceWorkbenchCommand::ToggleProblemFocus => {
                 self.toggle_panel_focus(ctx, PanelKind::Problem);
             }
+            LapceWorkbenchCommand::NextProblem => {
+                self.next_problem(ctx, true);
+            }
+            LapceWorkbenchCommand::PreviousProblem => {
+                self.next_problem(ctx, false);
+            }
+            LapceWorkbenchCommand::ToggleUndoTreeFocus => {
+                self.toggle_panel_focus(ctx, PanelKind::UndoTree);
+            }
+            LapceWorkbenchCommand::ToggleCallHierarchyFocus => {
+                self.toggle_panel_focus(ctx, PanelKind::CallHierarchy);
+            }
+            LapceWorkbenchCommand::ToggleTypeHierarchyFocus => {
+                self.toggle_panel_focus(ctx, PanelKind::TypeHierarchy);
+            }
+            LapceWorkbenchCommand::ToggleRenamePreviewFocus => {
+                self.toggle_panel_focus(ctx, PanelKind::RenamePreview);
+            }
+            LapceWorkbenchCommand::ApplyRenamePreview => {
+                self.apply_rename_preview(ctx);
+            }
+            LapceWorkbenchCommand::DiscardRenamePreview => {
+                Arc::make_mut(&mut self.rename_preview).clear();
+                self.hide_panel(ctx, PanelKind::RenamePreview);
+            }
+            LapceWorkbenchCommand::ToggleLspLogFocus => {
+                self.toggle_panel_focus(ctx, PanelKind::LspLog);
+            }
+            LapceWorkbenchCommand::ToggleDebugConsoleFocus => {
+                self.toggle_panel_focus(ctx, PanelKind::DebugConsole);
+            }
+            LapceWorkbenchCommand::DebugClearConsole => {
+                Arc::make_mut(&mut self.debug_console).clear();
+            }
+            LapceWorkbenchCommand::ToggleDebugFocus => {
+                self.toggle_panel_focus(ctx, PanelKind::Debug);
+            }
+            LapceWorkbenchCommand::DebugStart => ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::RunPalette(Some(PaletteType::RunAndDebug)),
+                Target::Auto,
+            )),
+            LapceWorkbenchCommand::DebugAddWatchExpression => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::WatchExpression {
+                        index: None,
+                        expression: String::new(),
+                    })),
+                    Target::Auto,
+                ))
+            }
+            LapceWorkbenchCommand::DebugContinue
+            | LapceWorkbenchCommand::DebugPause
+            | LapceWorkbenchCommand::DebugStepOver
+            | LapceWorkbenchCommand::DebugStepInto
+            | LapceWorkbenchCommand::DebugStepOut
+            | LapceWorkbenchCommand::DebugStop => {
+                self.run_debug_command(command);
+            }
+            LapceWorkbenchCommand::ToggleLspTrace => {
+                let lsp_log = Arc::make_mut(&mut self.lsp_log);
+                lsp_log.trace = !lsp_log.trace;
+                self.proxy.proxy_rpc.set_lsp_trace(lsp_log.trace);
+            }
+            LapceWorkbenchCommand::ClearLspLog => {
+                Arc::make_mut(&mut self.lsp_log).clear_active();
+            }
+            LapceWorkbenchCommand::RestartLanguageServer => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(
+                        PaletteType::RestartLanguageServer,
+                    )),
+                    Target::Auto,
+                ))
+            }
+            LapceWorkbenchCommand::StopLanguageServer => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(
+                        PaletteType::StopLanguageServer,
+                    )),
+                    Target::Auto,
+                ))
+            }
+            LapceWorkbenchCommand::ShowLanguageServers => {
+                self.show_language_servers(ctx);
+            }
             LapceWorkbenchCommand::ToggleTerminalFocus => {
                 self.toggle_panel_focus(ctx, PanelKind::Terminal);
             }
+            LapceWorkbenchCommand::NewTerminal => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::NewTerminal(TerminalLaunch::Profile(None)),
+                    Target::Auto,
+                ));
+            }
+            LapceWorkbenchCommand::NewTerminalWithProfile => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::TerminalProfile)),
+                    Target::Auto,
+                ));
+            }
+            LapceWorkbenchCommand::RunTask => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::RunTask)),
+                    Target::Auto,
+                ));
+            }
+            LapceWorkbenchCommand::TerminalScrollToPreviousCommand
+            | LapceWorkbenchCommand::TerminalScrollToNextCommand => {
+                if let Some(terminal) =
+                    self.terminal.terminals.get(&self.terminal.active_term_id)
+                {
+                    terminal.raw.lock().scroll_to_command(
+                        *command
+                            == LapceWorkbenchCommand::TerminalScrollToPreviousCommand,
+                    );
+                    ctx.request_paint();
+                }
+            }
+            LapceWorkbenchCommand::TerminalRerunLastCommand => {
+                if let Some(terminal) =
+                    self.terminal.terminals.get(&self.terminal.active_term_id)
+                {
+                    terminal.rerun_last_command();
+                }
+            }
+            LapceWorkbenchCommand::TerminalClear => {
+                if let Some(terminal) =
+                    self.terminal.terminals.get(&self.terminal.active_term_id)
+                {
+                    terminal.raw.lock().clear();
+                    ctx.request_paint();
+                }
+            }
+            LapceWorkbenchCommand::TerminalScrollToTop
+            | LapceWorkbenchCommand::TerminalScrollToBottom => {
+                if let Some(terminal) =
+                    self.terminal.terminals.get(&self.terminal.active_term_id)
+                {
+                    let scroll =
+                        if *command == LapceWorkbenchCommand::TerminalScrollToTop {
+                            Scroll::Top
+                        } else {
+                            Scroll::Bottom
+                        };
+                    terminal.raw.lock().term.scroll_display(scroll);
+                    ctx.request_paint();
+                }
+            }
+            LapceWorkbenchCommand::TerminalRename
+            | LapceWorkbenchCommand::TerminalSetColor
+            | LapceWorkbenchCommand::TerminalSetIcon => {
+                if let Some(terminal) =
+                    self.terminal.terminals.get(&self.terminal.active_term_id)
+                {
+                    let palette_type = match command {
+                        LapceWorkbenchCommand::TerminalRename => {
+                            PaletteType::RenameTerminal {
+                                term_id: terminal.term_id,
+                                name: terminal.display_title(),
+                            }
+                        }
+                        LapceWorkbenchCommand::TerminalSetColor => {
+                            PaletteType::TerminalColor(terminal.term_id)
+                        }
+                        _ => PaletteType::TerminalIcon(terminal.term_id),
+                    };
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::RunPalette(Some(palette_type)),
+                        Target::Auto,
+                    ));
+                }
+            }
             LapceWorkbenchCommand::TogglePanelFocus => {
                 if let Some(data) = data {
                     if let Ok(kind) = serde_json::from_value::<PanelKind>(data) {
@@ -1622,6 +2159,64 @@ pub fn run_workbench_command(
                     Target::Auto,
                 ))
             }
+            LapceWorkbenchCommand::ReopenWithEncoding => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(
+                        PaletteType::ReopenWithEncoding,
+                    )),
+                    Target::Auto,
+                ))
+            }
+            LapceWorkbenchCommand::ChangeLineEnding => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::LineEnding)),
+                    Target::Auto,
+                ))
+            }
+            LapceWorkbenchCommand::ToggleReadOnly => {
+                self.main_split.toggle_read_only();
+            }
+            LapceWorkbenchCommand::OverwriteFileOnDisk => {
+                if let Some(data) = data {
+                    if let Ok(path) = serde_json::from_value::<PathBuf>(data) {
+                        self.main_split.overwrite_file_on_disk(ctx, &path);
+                    }
+                }
+            }
+            LapceWorkbenchCommand::ReloadFileFromDisk => {
+                if let Some(data) = data {
+                    if let Ok(path) = serde_json::from_value::<PathBuf>(data) {
+                        self.main_split.reload_file_from_disk(&path);
+                    }
+                }
+            }
+            LapceWorkbenchCommand::CompareWithFileOnDisk => {
+                if let Some(data) = data {
+                    if let Ok(path) = serde_json::from_value::<PathBuf>(data) {
+                        self.main_split.compare_with_file_on_disk(
+                            ctx,
+                            &path,
+                            &self.config,
+                        );
+                    }
+                }
+            }
+            LapceWorkbenchCommand::ChangeIndentation => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::Indentation)),
+                    Target::Auto,
+                ))
+            }
+            LapceWorkbenchCommand::SaveWithEncoding => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::SaveWithEncoding)),
+                    Target::Auto,
+                ))
+            }
             LapceWorkbenchCommand::NextEditorTab => {
                 if let Some(active) = *self.main_split.active_tab {
                     ctx.submit_command(Command::new(
@@ -1757,14 +2352,40 @@ pub fn terminal_update_process(
                 }
                 TermEvent::UpdateContent(content) => {
                     if let Some(raw) = terminals.get_mut(&term_id) {
-                        raw.lock().update_content(&content);
-                        last_event = receiver.try_recv().ok();
-                        if last_event.is_some() {
-                            if last_redraw.elapsed().as_millis() > 10 {
-                                last_redraw = Instant::now();
-                                let _ = event_sink.submit_command(
-                                    LAPCE_UI_COMMAND,
-                                    LapceUICommand::RequestPaint,
+                        let (problems, finished) = {
+                            let mut raw = raw.lock();
+                            raw.update_content(&content);
+                            let problems = raw.task.as_mut().and_then(|task| {
+                                Some((task.name.clone(), task.take_changed()?))
+                            });
+                            (problems, raw.commands.take_finished())
+                        };
+                        for command in finished {
+                            let alert = TerminalAlert::CommandFinished {
+                                command: command.command,
+                                duration: command.duration.unwrap_or_default(),
+                                exit_code: command.exit_code,
+                            };
+                            let _ = event_sink.submit_command(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::TerminalAlert(term_id, alert),
+                                Target::Widget(tab_id),
+                            );
+                        }
+                        if let Some((name, problems)) = problems {
+                            let _ = event_sink.submit_command(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::PublishTaskProblems(name, problems),
+                                Target::Widget(tab_id),
+                            );
+                        }
+                        last_event = receiver.try_recv().ok();
+                        if last_event.is_some() {
+                            if last_redraw.elapsed().as_millis() > 10 {
+                                last_redraw = Instant::now();
+                                let _ = event_sink.submit_command(
+                                    LAPCE_UI_COMMAND,
+                                    LapceUICommand::RequestPaint,
                                     Target::Widget(tab_id),
                                 );
                             }
@@ -1772,21 +2393,1010 @@ pub fn terminal_update_process(
                             last_redraw = Instant::now();
                             let _ = event_sink.submit_command(
                                 LAPCE_UI_COMMAND,
-                                LapceUICommand::RequestPaint,
-                                Target::Widget(tab_id),
-                            );
+                                LapceUICommand::RequestPaint,
+                                Target::Widget(tab_id),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether the terminal is the one that has the focus, in the terminal
+    /// panel that is shown
+    pub fn is_terminal_focused(&self, term_id: TermId) -> bool {
+        self.is_panel_focused(PanelKind::Terminal)
+            && self.terminal.active_term_id == term_id
+    }
+
+    fn is_panel_focused(&self, kind: PanelKind) -> bool {
+        // Moving between e.g. Search and Problems doesn't affect focus, so we need to also check
+        // visibility.
+        self.focus_area == FocusArea::Panel(kind)
+            && self.panel.is_panel_visible(&kind)
+    }
+
+    /// Apply the selected file operations and edits of the rename preview,
+    /// and close it
+    fn apply_rename_preview(&mut self, ctx: &mut EventCtx) {
+        for op in self.rename_preview.selected_operations().iter() {
+            if let Some(cmd) = workspace_operation(op) {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    cmd,
+                    Target::Auto,
+                ));
+            }
+        }
+        let edits = self.rename_preview.selected_edits();
+        if !edits.is_empty() {
+            self.main_split.apply_text_edits(ctx, edits, &self.config);
+        }
+        Arc::make_mut(&mut self.rename_preview).clear();
+        self.hide_panel(ctx, PanelKind::RenamePreview);
+    }
+
+    /// Debug the program of the configuration, with the adapter of the
+    /// settings for its type, or else the one a plugin registered. A
+    /// configuration that attaches to a process to be picked has it picked
+    /// first, and is then started with its id.
+    pub fn start_debug(
+        &mut self,
+        ctx: &mut EventCtx,
+        config: RunDebugConfig,
+        pid: Option<u32>,
+    ) {
+        if pid.is_none() && picks_process(&config) {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::RunPalette(Some(PaletteType::AttachProcess(config))),
+                Target::Auto,
+            ));
+            return;
+        }
+        let variables = LaunchVariables {
+            workspace: self.workspace.path.clone(),
+            file: self
+                .main_split
+                .active_editor()
+                .and_then(|editor| editor.content.path())
+                .map(|path| path.to_path_buf()),
+            pid,
+        };
+        let config = variables.apply(&config);
+        let dap_id = DapId::next();
+        let debugger = self.config.debuggers.get(&config.ty).cloned();
+        let breakpoints = self.debug.all_source_breakpoints();
+        Arc::make_mut(&mut self.debug).start(dap_id, config.clone());
+        self.proxy
+            .proxy_rpc
+            .dap_start(dap_id, config, debugger, breakpoints);
+        self.show_panel(ctx, PanelKind::Debug);
+    }
+
+    fn run_debug_command(&mut self, command: &LapceWorkbenchCommand) {
+        let dap = match self.debug.active() {
+            Some(dap) => dap,
+            None => return,
+        };
+        let proxy_rpc = &self.proxy.proxy_rpc;
+        if let LapceWorkbenchCommand::DebugStop = command {
+            proxy_rpc.dap_stop(dap.dap_id);
+            return;
+        }
+        if let LapceWorkbenchCommand::DebugPause = command {
+            // The adapters pause all the threads whichever is given, and a
+            // program that never stopped has no thread to give
+            if dap.stopped.is_none() {
+                proxy_rpc.dap_pause(dap.dap_id, dap.thread_id.unwrap_or(0));
+            }
+            return;
+        }
+
+        let dap_id = dap.dap_id;
+        let thread_id = match (dap.stopped.as_ref(), dap.thread_id) {
+            (Some(_), Some(thread_id)) => thread_id,
+            _ => return,
+        };
+        match command {
+            LapceWorkbenchCommand::DebugContinue => {
+                proxy_rpc.dap_continue(dap.dap_id, thread_id)
+            }
+            LapceWorkbenchCommand::DebugStepOver => {
+                proxy_rpc.dap_step_over(dap.dap_id, thread_id)
+            }
+            LapceWorkbenchCommand::DebugStepInto => {
+                proxy_rpc.dap_step_into(dap.dap_id, thread_id)
+            }
+            LapceWorkbenchCommand::DebugStepOut => {
+                proxy_rpc.dap_step_out(dap.dap_id, thread_id)
+            }
+            _ => return,
+        }
+        // The adapters don't tell when the program runs again because it
+        // was asked to
+        Arc::make_mut(&mut self.debug).continued(dap_id);
+    }
+
+    /// Add a breakpoint to the line of the file, or remove the one it has,
+    /// and give the programs being debugged the new breakpoints of the file
+    pub fn toggle_breakpoint(&mut self, path: &Path, line: usize) {
+        Arc::make_mut(&mut self.debug).toggle_breakpoint(path, line);
+        self.send_breakpoints(path);
+    }
+
+    pub fn set_breakpoint_option(
+        &mut self,
+        path: &Path,
+        line: usize,
+        kind: BreakpointOptionKind,
+        value: &str,
+    ) {
+        Arc::make_mut(&mut self.debug)
+            .set_breakpoint_option(path, line, kind, value);
+        self.send_breakpoints(path);
+    }
+
+    /// Give the breakpoints of the file to the programs being debugged
+    fn send_breakpoints(&self, path: &Path) {
+        let breakpoints = self.debug.source_breakpoints(path);
+        for dap_id in self.debug.daps.keys() {
+            self.proxy.proxy_rpc.dap_set_breakpoints(
+                *dap_id,
+                path.to_path_buf(),
+                breakpoints.clone(),
+            );
+        }
+    }
+
+    /// Make the program the one the debug commands and the panel are for,
+    /// and show where it stopped
+    pub fn select_debug_session(&mut self, ctx: &mut EventCtx, dap_id: DapId) {
+        if Arc::make_mut(&mut self.debug).set_active(dap_id) {
+            self.evaluate_watches(ctx);
+            self.jump_to_stopped_location(ctx);
+        }
+    }
+
+    /// Show the stack of the thread of the active program, and the
+    /// variables of its top frame
+    pub fn select_debug_thread(&mut self, ctx: &mut EventCtx, thread_id: ThreadId) {
+        let dap_id = match self.debug.active_dap {
+            Some(dap_id) => dap_id,
+            None => return,
+        };
+        if !Arc::make_mut(&mut self.debug).select_thread(dap_id, thread_id) {
+            return;
+        }
+        let event_sink = ctx.get_external_handle();
+        let tab_id = self.id;
+        self.proxy
+            .proxy_rpc
+            .dap_stack_trace(dap_id, thread_id, move |result| {
+                if let Ok(ProxyResponse::DapStackTraceResponse { stack_frames }) =
+                    result
+                {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::DapThreadStack {
+                            dap_id,
+                            thread_id,
+                            stack_frames,
+                        },
+                        Target::Widget(tab_id),
+                    );
+                }
+            });
+    }
+
+    pub fn thread_stack(
+        &mut self,
+        ctx: &mut EventCtx,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        stack_frames: Vec<StackFrame>,
+    ) {
+        if Arc::make_mut(&mut self.debug).set_thread_stack(
+            dap_id,
+            thread_id,
+            stack_frames,
+        ) && self.debug.active_dap == Some(dap_id)
+        {
+            self.select_debug_frame(ctx, 0);
+        }
+    }
+
+    /// Make the frame at the index of the stack of the active program the
+    /// one the variables and the watch expressions are for, and go to it
+    pub fn select_debug_frame(&mut self, ctx: &mut EventCtx, index: usize) {
+        let dap_id = match self.debug.active_dap {
+            Some(dap_id) => dap_id,
+            None => return,
+        };
+        let frame_id =
+            match Arc::make_mut(&mut self.debug).select_frame(dap_id, index) {
+                Some(frame_id) => frame_id,
+                None => return,
+            };
+        let event_sink = ctx.get_external_handle();
+        let tab_id = self.id;
+        self.proxy
+            .proxy_rpc
+            .dap_scopes(dap_id, frame_id, move |result| {
+                if let Ok(ProxyResponse::DapScopesResponse { variables }) = result {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::DapFrameVariables {
+                            dap_id,
+                            frame_id,
+                            variables,
+                        },
+                        Target::Widget(tab_id),
+                    );
+                }
+            });
+        self.jump_to_stopped_location(ctx);
+        self.evaluate_watches(ctx);
+    }
+
+    /// Run the frame of the active program again from its start, which the
+    /// adapter stops at after
+    pub fn restart_debug_frame(&mut self, frame_id: usize) {
+        let dap_id = match self.debug.active() {
+            Some(dap) if dap.capabilities.supports_restart_frame => dap.dap_id,
+            _ => return,
+        };
+        self.proxy.proxy_rpc.dap_restart_frame(dap_id, frame_id);
+        Arc::make_mut(&mut self.debug).continued(dap_id);
+    }
+
+    /// Go to where the active program stopped
+    pub fn jump_to_stopped_location(&self, ctx: &mut EventCtx) {
+        if let Some((path, line)) = self.debug.stopped_location() {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::JumpToLspLocation(
+                    None,
+                    EditorLocation {
+                        path: path.to_path_buf(),
+                        position: Some(Position::new(line as u32, 0)),
+                        scroll_offset: None,
+                        history: None,
+                    },
+                    true,
+                ),
+                Target::Widget(self.id),
+            ));
+        }
+    }
+
+    /// Change or add the watch expression, and evaluate it if the program
+    /// is stopped
+    pub fn set_watch_expression(
+        &mut self,
+        ctx: &mut EventCtx,
+        index: Option<usize>,
+        expression: &str,
+    ) {
+        if let Some(index) =
+            Arc::make_mut(&mut self.debug).set_watch(index, expression)
+        {
+            let expression = self.debug.watches[index].expression.clone();
+            self.evaluate_watch(ctx, expression);
+        }
+    }
+
+    /// Evaluate the watch expressions where the active program stopped
+    pub fn evaluate_watches(&self, ctx: &mut EventCtx) {
+        let expressions: HashSet<String> = self
+            .debug
+            .watches
+            .iter()
+            .map(|watch| watch.expression.clone())
+            .collect();
+        for expression in expressions {
+            self.evaluate_watch(ctx, expression);
+        }
+    }
+
+    fn evaluate_watch(&self, ctx: &mut EventCtx, expression: String) {
+        let dap = match self.debug.active() {
+            Some(dap) if dap.stopped.is_some() => dap,
+            _ => return,
+        };
+        let dap_id = dap.dap_id;
+        let frame_id = dap.frame().map(|frame| frame.id);
+        let event_sink = ctx.get_external_handle();
+        let tab_id = self.id;
+        self.proxy.proxy_rpc.dap_evaluate(
+            dap_id,
+            expression.clone(),
+            frame_id,
+            EvaluateContext::Watch,
+            move |result| {
+                let value = match result {
+                    Ok(ProxyResponse::DapEvaluateResponse { result }) => Ok(result),
+                    Ok(_) => return,
+                    Err(e) => Err(e.message),
+                };
+                let _ = event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::DapWatchValue {
+                        dap_id,
+                        expression,
+                        value,
+                    },
+                    Target::Widget(tab_id),
+                );
+            },
+        );
+    }
+
+    /// Show the value of the watch expression, with its children if it's
+    /// expanded
+    pub fn watch_value(
+        &mut self,
+        ctx: &mut EventCtx,
+        dap_id: DapId,
+        expression: &str,
+        value: Result<EvaluateResult, String>,
+    ) {
+        if self.debug.active_dap != Some(dap_id) {
+            return;
+        }
+        let variables_reference =
+            value.as_ref().map(|v| v.variables_reference).unwrap_or(0);
+        Arc::make_mut(&mut self.debug).set_watch_value(expression, value);
+        let path = vec![expression.to_string()];
+        if variables_reference > 0 && self.debug.expanded_watches.contains(&path) {
+            self.request_watch_children(ctx, dap_id, path, variables_reference);
+        }
+    }
+
+    /// Show the children of the watch expression or child, with theirs if
+    /// they're expanded
+    pub fn watch_children(
+        &mut self,
+        ctx: &mut EventCtx,
+        dap_id: DapId,
+        path: &[String],
+        variables: Vec<Variable>,
+    ) {
+        if self.debug.active_dap != Some(dap_id)
+            || !self.debug.expanded_watches.contains(path)
+        {
+            return;
+        }
+        for variable in variables.iter() {
+            let mut child = path.to_vec();
+            child.push(variable.name.clone());
+            if variable.variables_reference > 0
+                && self.debug.expanded_watches.contains(&child)
+            {
+                self.request_watch_children(
+                    ctx,
+                    dap_id,
+                    child,
+                    variable.variables_reference,
+                );
+            }
+        }
+        Arc::make_mut(&mut self.debug)
+            .watch_children
+            .insert(path.to_vec(), variables);
+    }
+
+    pub fn toggle_watch_expanded(
+        &mut self,
+        ctx: &mut EventCtx,
+        path: &[String],
+        variables_reference: usize,
+    ) {
+        if Arc::make_mut(&mut self.debug).toggle_watch_expanded(path) {
+            if let Some(dap_id) = self.debug.active_dap {
+                self.request_watch_children(
+                    ctx,
+                    dap_id,
+                    path.to_vec(),
+                    variables_reference,
+                );
+            }
+        }
+    }
+
+    fn request_watch_children(
+        &self,
+        ctx: &mut EventCtx,
+        dap_id: DapId,
+        path: Vec<String>,
+        variables_reference: usize,
+    ) {
+        let event_sink = ctx.get_external_handle();
+        let tab_id = self.id;
+        self.proxy.proxy_rpc.dap_variables(
+            dap_id,
+            variables_reference,
+            move |result| {
+                if let Ok(ProxyResponse::DapVariablesResponse { variables }) = result
+                {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::DapWatchChildren {
+                            dap_id,
+                            path,
+                            variables,
+                        },
+                        Target::Widget(tab_id),
+                    );
+                }
+            },
+        );
+    }
+
+    /// Read the memory from the offset of the reference, for the memory
+    /// section of the debug panel to show
+    pub fn read_debug_memory(
+        &mut self,
+        ctx: &mut EventCtx,
+        memory_reference: &str,
+        offset: i64,
+    ) {
+        let dap_id = match self.debug.active() {
+            Some(dap) if dap.capabilities.supports_read_memory_request => dap.dap_id,
+            _ => return,
+        };
+        Arc::make_mut(&mut self.debug).memory = Some(MemoryView {
+            dap_id,
+            memory_reference: memory_reference.to_string(),
+            offset,
+            result: None,
+        });
+        self.show_panel(ctx, PanelKind::Debug);
+
+        let event_sink = ctx.get_external_handle();
+        let tab_id = self.id;
+        let memory_reference = memory_reference.to_string();
+        self.proxy.proxy_rpc.dap_read_memory(
+            dap_id,
+            memory_reference.clone(),
+            offset,
+            MEMORY_PAGE_BYTES,
+            move |result| {
+                let result = match result {
+                    Ok(ProxyResponse::DapReadMemoryResponse { result }) => {
+                        Ok(result)
+                    }
+                    Ok(_) => return,
+                    Err(e) => Err(e.message),
+                };
+                let _ = event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::DapMemory {
+                        dap_id,
+                        memory_reference,
+                        offset,
+                        result,
+                    },
+                    Target::Widget(tab_id),
+                );
+            },
+        );
+    }
+
+    /// Read the memory the memory section shows again, as it can have
+    /// changed since the program last stopped
+    pub fn refresh_debug_memory(&mut self, ctx: &mut EventCtx, dap_id: DapId) {
+        if let Some(memory) = self.debug.memory.clone() {
+            if memory.dap_id == dap_id {
+                self.read_debug_memory(ctx, &memory.memory_reference, memory.offset);
+            }
+        }
+    }
+
+    /// Ask the adapter if the variable, which is a child of the reference,
+    /// can have a data breakpoint, which is added if it can
+    pub fn request_data_breakpoint(
+        &self,
+        ctx: &mut EventCtx,
+        variables_reference: usize,
+        name: String,
+    ) {
+        let dap_id = match self.debug.active() {
+            Some(dap) if dap.capabilities.supports_data_breakpoints => dap.dap_id,
+            _ => return,
+        };
+        let event_sink = ctx.get_external_handle();
+        let tab_id = self.id;
+        self.proxy.proxy_rpc.dap_data_breakpoint_info(
+            dap_id,
+            variables_reference,
+            name.clone(),
+            move |result| {
+                let info = match result {
+                    Ok(ProxyResponse::DapDataBreakpointInfoResponse { info }) => {
+                        Ok(info)
+                    }
+                    Ok(_) => return,
+                    Err(e) => Err(e.message),
+                };
+                let _ = event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::DapDataBreakpointInfo { dap_id, name, info },
+                    Target::Widget(tab_id),
+                );
+            },
+        );
+    }
+
+    /// Add the data breakpoint the adapter gave the id of, which stops the
+    /// program when the data is written, or tell in the debug console why
+    /// it can't be added
+    pub fn add_data_breakpoint(
+        &mut self,
+        dap_id: DapId,
+        name: &str,
+        info: Result<DataBreakpointInfo, String>,
+    ) {
+        let (info, data_id) = match info {
+            Ok(info) => match info.data_id.clone() {
+                Some(data_id) => (info, data_id),
+                None => {
+                    Arc::make_mut(&mut self.debug_console).push_line(
+                        ConsoleLineKind::Error,
+                        &format!("can't break on {name}: {}", info.description),
+                    );
+                    return;
+                }
+            },
+            Err(e) => {
+                Arc::make_mut(&mut self.debug_console).push_line(
+                    ConsoleLineKind::Error,
+                    &format!("can't break on {name}: {e}"),
+                );
+                return;
+            }
+        };
+        let access_type = info
+            .access_types
+            .filter(|types| types.contains(&DataBreakpointAccessType::Write))
+            .map(|_| DataBreakpointAccessType::Write);
+        let breakpoints = Arc::make_mut(&mut self.debug).add_data_breakpoint(
+            dap_id,
+            LapceDataBreakpoint {
+                description: info.description,
+                breakpoint: DataBreakpoint {
+                    data_id,
+                    access_type,
+                },
+            },
+        );
+        if let Some(breakpoints) = breakpoints {
+            self.proxy
+                .proxy_rpc
+                .dap_set_data_breakpoints(dap_id, breakpoints);
+        }
+    }
+
+    /// Remove the data breakpoint at the index from the active program
+    pub fn remove_data_breakpoint(&mut self, index: usize) {
+        let dap_id = match self.debug.active_dap {
+            Some(dap_id) => dap_id,
+            None => return,
+        };
+        if let Some(breakpoints) =
+            Arc::make_mut(&mut self.debug).remove_data_breakpoint(dap_id, index)
+        {
+            self.proxy
+                .proxy_rpc
+                .dap_set_data_breakpoints(dap_id, breakpoints);
+        }
+    }
+
+    /// The text of the debug console input, with the offset of the cursor
+    fn debug_console_input(&self) -> (String, usize) {
+        let text = self
+            .main_split
+            .local_docs
+            .get(&LocalBufferKind::DebugConsole)
+            .unwrap()
+            .buffer()
+            .text()
+            .to_string();
+        let cursor = self
+            .main_split
+            .editors
+            .get(&self.debug_console.input_view_id)
+            .map(|editor| editor.cursor.offset())
+            .unwrap_or(text.len());
+        (text, cursor.min(text.len()))
+    }
+
+    fn set_debug_console_input(&mut self, text: &str, cursor: usize) {
+        let doc = self
+            .main_split
+            .local_docs
+            .get_mut(&LocalBufferKind::DebugConsole)
+            .unwrap();
+        Arc::make_mut(doc).reload(Rope::from(text), true);
+        if let Some(editor) = self
+            .main_split
+            .editors
+            .get_mut(&self.debug_console.input_view_id)
+        {
+            Arc::make_mut(editor).cursor.mode =
+                CursorMode::Insert(Selection::caret(cursor));
+        }
+    }
+
+    /// Evaluate the expression of the debug console input in the frame the
+    /// active program stopped in
+    pub fn debug_console_evaluate(&mut self, ctx: &mut EventCtx) {
+        let (text, _) = self.debug_console_input();
+        let expression = text.trim();
+        if expression.is_empty() {
+            return;
+        }
+        let expression = expression.to_string();
+        let debug_console = Arc::make_mut(&mut self.debug_console);
+        debug_console.push_line(ConsoleLineKind::Input, &expression);
+        debug_console.add_history(&expression);
+        self.set_debug_console_input("", 0);
+
+        let dap = match self.debug.active() {
+            Some(dap) => dap,
+            None => {
+                Arc::make_mut(&mut self.debug_console).push_line(
+                    ConsoleLineKind::Error,
+                    "No program is being debugged",
+                );
+                return;
+            }
+        };
+        let dap_id = dap.dap_id;
+        let frame_id = dap.frame().map(|frame| frame.id);
+        let event_sink = ctx.get_external_handle();
+        let tab_id = self.id;
+        self.proxy.proxy_rpc.dap_evaluate(
+            dap_id,
+            expression,
+            frame_id,
+            EvaluateContext::Repl,
+            move |result| {
+                let result = match result {
+                    Ok(ProxyResponse::DapEvaluateResponse { result }) => Ok(result),
+                    Ok(_) => return,
+                    Err(e) => Err(e.message),
+                };
+                let _ = event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::DapConsoleResult { dap_id, result },
+                    Target::Widget(tab_id),
+                );
+            },
+        );
+    }
+
+    /// Put the previous or the next expression of the history in the debug
+    /// console input
+    pub fn debug_console_history(&mut self, previous: bool) {
+        let debug_console = Arc::make_mut(&mut self.debug_console);
+        let expression = if previous {
+            debug_console.history_previous()
+        } else {
+            debug_console.history_next()
+        };
+        if let Some(expression) = expression {
+            self.set_debug_console_input(&expression, expression.len());
+        }
+    }
+
+    /// Ask the active adapter for the completions of the debug console input
+    /// at the cursor
+    pub fn debug_console_complete(&self, ctx: &mut EventCtx) {
+        let dap = match self.debug.active() {
+            Some(dap) => dap,
+            None => return,
+        };
+        let dap_id = dap.dap_id;
+        let frame_id = dap.frame().map(|frame| frame.id);
+        let (text, cursor) = self.debug_console_input();
+        // The column is in UTF-16 code units, from 1
+        let column = text[..cursor].encode_utf16().count() + 1;
+        let event_sink = ctx.get_external_handle();
+        let tab_id = self.id;
+        self.proxy.proxy_rpc.dap_completions(
+            dap_id,
+            text.clone(),
+            column,
+            frame_id,
+            move |result| {
+                if let Ok(ProxyResponse::DapCompletionsResponse { targets }) = result
+                {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::DapConsoleCompletions {
+                            dap_id,
+                            text,
+                            cursor,
+                            targets,
+                        },
+                        Target::Widget(tab_id),
+                    );
+                }
+            },
+        );
+    }
+
+    /// Complete the debug console input, if it hasn't changed since the
+    /// completions were asked for, and list the completions when there are
+    /// several
+    pub fn debug_console_completions(
+        &mut self,
+        dap_id: DapId,
+        text: &str,
+        cursor: usize,
+        targets: &[CompletionTarget],
+    ) {
+        if self.debug.active_dap != Some(dap_id)
+            || self.debug_console_input() != (text.to_string(), cursor)
+        {
+            return;
+        }
+        if let Some((new_text, new_cursor)) =
+            debug_console::complete(text, cursor, targets)
+        {
+            self.set_debug_console_input(&new_text, new_cursor);
+        }
+        if targets.len() > 1 {
+            let labels = targets
+                .iter()
+                .map(|target| target.label.as_str())
+                .collect::<Vec<_>>()
+                .join("  ");
+            Arc::make_mut(&mut self.debug_console)
+                .push_line(ConsoleLineKind::Info, &labels);
+        }
+    }
+
+    /// Go to the error or warning after the cursor of the active editor, or
+    /// before it, out of the ones the problems panel lists with its filter
+    fn next_problem(&self, ctx: &mut EventCtx, forward: bool) {
+        let mut problems: BTreeMap<&PathBuf, Vec<Position>> = BTreeMap::new();
+        for severity in [DiagnosticSeverity::ERROR, DiagnosticSeverity::WARNING] {
+            for (path, diagnostics) in self.main_split.diagnostics_items(severity) {
+                problems.entry(path).or_default().extend(
+                    diagnostics
+                        .iter()
+                        .filter(|d| self.problem.filter.matches(&d.diagnostic))
+                        .map(|d| d.diagnostic.range.start),
+                );
+            }
+        }
+        let problems = problems
+            .into_iter()
+            .filter(|(_, positions)| !positions.is_empty())
+            .map(|(path, mut positions)| {
+                positions.sort_by_key(|p| (p.line, p.character));
+                positions.dedup();
+                (path, positions)
+            })
+            .collect::<Vec<_>>();
+
+        let mut path = None;
+        let mut position = Position::default();
+        if let Some(editor) = self.main_split.active_editor() {
+            if let BufferContent::File(editor_path) = &editor.content {
+                if let Some(doc) = self.main_split.open_docs.get(editor_path) {
+                    path = Some(editor_path.as_path());
+                    position =
+                        doc.buffer().offset_to_position(editor.cursor.offset());
+                }
+            }
+        }
+
+        if let Some((path, position)) =
+            next_problem(&problems, path, position, forward)
+        {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::JumpToLspLocation(
+                    None,
+                    EditorLocation {
+                        path,
+                        position: Some(position),
+                        scroll_offset: None,
+                        history: None,
+                    },
+                    true,
+                ),
+                Target::Widget(self.id),
+            ));
+        }
+    }
+
+    /// Ask the language servers for the quick fixes of the diagnostic of
+    /// the file
+    pub fn apply_quick_fix(
+        &self,
+        ctx: &mut EventCtx,
+        path: &Path,
+        diagnostic: &Diagnostic,
+    ) {
+        let rev = self.main_split.open_docs.get(path).map(|doc| doc.rev());
+        let event_sink = ctx.get_external_handle();
+        let tab_id = self.id;
+        let local_path = path.to_path_buf();
+        let local_diagnostic = diagnostic.clone();
+        self.proxy.proxy_rpc.get_quick_fixes(
+            path.to_path_buf(),
+            diagnostic.clone(),
+            move |result| {
+                if let Ok(ProxyResponse::GetCodeActionsResponse { resp }) = result {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::ApplyQuickFixResponse {
+                            path: local_path,
+                            rev,
+                            diagnostic: local_diagnostic,
+                            resp,
+                        },
+                        Target::Widget(tab_id),
+                    );
+                }
+            },
+        );
+    }
+
+    /// Apply the preferred quick fix of the diagnostic, or its only one,
+    /// unless the document changed since they were asked for. When there's
+    /// no fix to pick, the diagnostic is shown in its file instead, for the
+    /// code actions there to be chosen from.
+    pub fn apply_quick_fix_response(
+        &mut self,
+        ctx: &mut EventCtx,
+        path: &Path,
+        rev: Option<u64>,
+        diagnostic: &Diagnostic,
+        resp: &CodeActionResponse,
+    ) {
+        if rev.is_some()
+            && self.main_split.open_docs.get(path).map(|doc| doc.rev()) != rev
+        {
+            return;
+        }
+
+        let actions = resp
+            .iter()
+            .filter_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => Some(action),
+                CodeActionOrCommand::Command(_) => None,
+            })
+            .filter(|action| action.edit.is_some())
+            .filter(|action| {
+                action.diagnostics.as_ref().map_or(true, |diagnostics| {
+                    diagnostics.iter().any(|d| {
+                        d.range == diagnostic.range
+                            && d.message == diagnostic.message
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        let action = actions
+            .iter()
+            .find(|action| action.is_preferred == Some(true))
+            .or_else(|| {
+                if actions.len() == 1 {
+                    actions.first()
+                } else {
+                    None
+                }
+            });
+
+        match action.and_then(|action| action.edit.as_ref()) {
+            Some(edit) => {
+                if let Some(DocumentChanges::Operations(ops)) =
+                    edit.document_changes.as_ref()
+                {
+                    for op in ops.iter().filter_map(|op| match op {
+                        DocumentChangeOperation::Op(op) => Some(op),
+                        DocumentChangeOperation::Edit(_) => None,
+                    }) {
+                        if let Some(cmd) = workspace_operation(op) {
+                            ctx.submit_command(Command::new(
+                                LAPCE_UI_COMMAND,
+                                cmd,
+                                Target::Auto,
+                            ));
                         }
                     }
                 }
+                let edits = workspace_edits(edit)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(url, edits)| (path_from_url(&url), edits))
+                    .collect::<Vec<_>>();
+                if !edits.is_empty() {
+                    self.main_split.apply_text_edits(ctx, edits, &self.config);
+                }
+            }
+            None => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::JumpToLspLocation(
+                        None,
+                        EditorLocation {
+                            path: path.to_path_buf(),
+                            position: Some(diagnostic.range.start),
+                            scroll_offset: None,
+                            history: None,
+                        },
+                        true,
+                    ),
+                    Target::Widget(self.id),
+                ));
             }
         }
     }
 
-    fn is_panel_focused(&self, kind: PanelKind) -> bool {
-        // Moving between e.g. Search and Problems doesn't affect focus, so we need to also check
-        // visibility.
-        self.focus_area == FocusArea::Panel(kind)
-            && self.panel.is_panel_visible(&kind)
+    /// Show the language servers that are running, with what they can do
+    fn show_language_servers(&self, ctx: &mut EventCtx) {
+        let event_sink = ctx.get_external_handle();
+        let tab_id = self.id;
+        self.proxy.proxy_rpc.get_language_servers(move |result| {
+            if let Ok(ProxyResponse::GetLanguageServersResponse { servers }) = result
+            {
+                let msg = if servers.is_empty() {
+                    "No language server is running.".to_string()
+                } else {
+                    servers
+                        .iter()
+                        .map(|server| {
+                            let uptime = server.uptime;
+                            let mut command = server.command.clone();
+                            if server.command_overridden {
+                                command.push_str(" (from the settings)");
+                            }
+                            if let Some(error) = server.override_error.as_ref() {
+                                command.push_str(&format!(
+                                    "\nOverride Command Failed: {error}"
+                                ));
+                            }
+                            format!(
+                                "{} ({})\nPID: {}\nCommand: {}\nRoot: {}\n\
+                                 Uptime: {}h {}m {}s\nCapabilities: {}",
+                                server.name,
+                                server.volt_id,
+                                server
+                                    .pid
+                                    .map(|pid| pid.to_string())
+                                    .unwrap_or_else(|| "None".to_string()),
+                                command,
+                                server
+                                    .root
+                                    .as_ref()
+                                    .map(|root| root.display().to_string())
+                                    .unwrap_or_else(|| "None".to_string()),
+                                uptime / 3600,
+                                uptime % 3600 / 60,
+                                uptime % 60,
+                                server.capabilities.join(", "),
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n\n")
+                };
+                let _ = event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::ShowAlert(AlertContentData {
+                        title: "Language Servers".to_string(),
+                        msg,
+                        buttons: Vec::new(),
+                    }),
+                    Target::Widget(tab_id),
+                );
+            }
+        });
     }
 
     fn hide_panel(&mut self, ctx: &mut EventCtx, kind: PanelKind) {
@@ -1809,6 +3419,13 @@ pub fn show_panel(&mut self, ctx: &mut EventCtx, kind: PanelKind) {
             PanelKind::Terminal => self.terminal.widget_id,
             PanelKind::Search => self.search.active,
             PanelKind::Problem => self.problem.widget_id,
+            PanelKind::UndoTree => self.undo_tree.widget_id,
+            PanelKind::CallHierarchy => self.call_hierarchy.widget_id,
+            PanelKind::TypeHierarchy => self.type_hierarchy.widget_id,
+            PanelKind::RenamePreview => self.rename_preview.widget_id,
+            PanelKind::LspLog => self.lsp_log.widget_id,
+            PanelKind::Debug => self.debug.widget_id,
+            PanelKind::DebugConsole => self.debug_console.input_view_id,
         };
         if let PanelKind::Search = kind {
             ctx.submit_command(Command::new(
@@ -1839,14 +3456,23 @@ fn toggle_panel_visual(&mut self, ctx: &mut EventCtx, kind: PanelKind) {
 
     fn toggle_panel_focus(&mut self, ctx: &mut EventCtx, kind: PanelKind) {
         let should_hide = match kind {
-            PanelKind::FileExplorer | PanelKind::Plugin | PanelKind::Problem => {
+            PanelKind::FileExplorer
+            | PanelKind::Plugin
+            | PanelKind::Problem
+            | PanelKind::UndoTree
+            | PanelKind::CallHierarchy
+            | PanelKind::TypeHierarchy
+            | PanelKind::RenamePreview
+            | PanelKind::LspLog
+            | PanelKind::Debug => {
                 // Some panels don't accept focus (yet). Fall back to visibility check
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
             }
-            PanelKind::Terminal | PanelKind::SourceControl | PanelKind::Search => {
-                self.is_panel_focused(kind)
-            }
+            PanelKind::Terminal
+            | PanelKind::SourceControl
+            | PanelKind::Search
+            | PanelKind::DebugConsole => self.is_panel_focused(kind),
         };
         if should_hide {
             self.hide_panel(ctx, kind);
@@ -1922,6 +3548,7 @@ fn with_mut<V, F: FnOnce(&mut LapceTabData) -> V>(
         let mut tab = data.tabs.get(&self.0).unwrap().clone();
         tab.keypress = data.keypress.clone();
         tab.latest_release = data.latest_release.clone();
+        tab.update_progress = data.update_progress.clone();
         tab.multiple_tab = data.tabs.len() > 1;
         if !tab.panel.order.same(&data.panel_orders) {
             Arc::make_mut(&mut tab.panel).order = data.panel_orders.clone();
@@ -1958,6 +3585,7 @@ fn with_mut<V, F: FnOnce(&mut LapceWindowData) -> V>(
         let mut win = data.windows.get(&self.0).unwrap().clone();
         win.keypress = data.keypress.clone();
         win.latest_release = data.latest_release.clone();
+        win.update_progress = data.update_progress.clone();
         win.panel_orders = data.panel_orders.clone();
         let result = f(&mut win);
         data.keypress = win.keypress.clone();
@@ -2082,12 +3710,21 @@ pub struct LapceMainSplitData {
     pub proxy: Arc<LapceProxy>,
     pub palette_preview_editor: Arc<WidgetId>,
     pub diagnostics: im::HashMap<PathBuf, Arc<Vec<EditorDiagnostic>>>,
+    /// The diagnostics the language servers published, by file, which the
+    /// problems of the tasks are added to
+    pub server_diagnostics: im::HashMap<PathBuf, Arc<Vec<Diagnostic>>>,
+    /// The problems found in the output of the tasks, by the name of the
+    /// task
+    pub task_problems: im::HashMap<String, Arc<Vec<(PathBuf, Diagnostic)>>>,
     pub error_count: usize,
     pub warning_count: usize,
     pub workspace: Arc<LapceWorkspace>,
     pub db: Arc<LapceDb>,
     pub locations: Arc<Vec<EditorLocation>>,
     pub current_location: usize,
+    /// The files changed by the last workspace edit, with their revisions
+    /// after it, which are undone together
+    pub workspace_edit_undo: Option<Arc<Vec<(PathBuf, u64)>>>,
 }
 
 impl LapceMainSplitData {
@@ -2145,6 +3782,302 @@ pub fn document_format(
         }
     }
 
+    /// Ask the formatter set for the language of the document for the edits
+    /// formatting it, or only the range if there is one, giving up after the
+    /// format timeout
+    pub fn get_document_formatting(
+        &self,
+        path: &Path,
+        range: Option<Range>,
+        config: &Config,
+        f: impl FnOnce(PathBuf, Result<Vec<TextEdit>>) + Send + 'static,
+    ) {
+        let language = self
+            .open_docs
+            .get(path)
+            .and_then(|doc| doc.syntax().map(|syntax| syntax.language));
+        let formatter = config.formatter_at(language);
+        let timeout = config.editor.format_timeout;
+        let path = path.to_path_buf();
+        let proxy = self.proxy.clone();
+        let (sender, receiver) = bounded(1);
+        thread::spawn(move || {
+            proxy.proxy_rpc.get_document_formatting(
+                path.clone(),
+                range,
+                formatter,
+                timeout,
+                Box::new(move |result| {
+                    let _ = sender.send(result);
+                }),
+            );
+
+            let result = receiver
+                .recv_timeout(Duration::from_millis(timeout))
+                .map_or_else(
+                    |e| Err(anyhow!("{}", e)),
+                    |v| {
+                        v.map_err(|e| anyhow!("{:?}", e)).and_then(|r| {
+                            if let ProxyResponse::GetDocumentFormatting { edits } = r
+                            {
+                                Ok(edits)
+                            } else {
+                                Err(anyhow!("wrong response"))
+                            }
+                        })
+                    },
+                );
+            f(path, result);
+        });
+    }
+
+    /// Save the file, first running the code actions on save and formatting
+    /// it if `actions` is set. An action is skipped if the language server
+    /// takes too long, and so is the formatting if the formatter does.
+    pub fn save_file(
+        &self,
+        ctx: &mut EventCtx,
+        path: &Path,
+        actions: bool,
+        exit_widget_id: Option<WidgetId>,
+        config: &Config,
+    ) {
+        if !actions {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::DocumentSave(path.to_path_buf(), exit_widget_id),
+                Target::Widget(*self.tab_id),
+            ));
+            return;
+        }
+
+        let language = self
+            .open_docs
+            .get(path)
+            .and_then(|doc| doc.syntax().map(|syntax| syntax.language));
+        let kinds = config.code_actions_on_save_at(language);
+        self.run_code_actions_on_save(ctx, path, kinds, exit_widget_id, config);
+    }
+
+    /// Run the code actions of the first of the kinds, one kind after the
+    /// other because each one needs the text the one before left, and
+    /// then format and save the file
+    fn run_code_actions_on_save(
+        &self,
+        ctx: &mut EventCtx,
+        path: &Path,
+        mut kinds: Vec<String>,
+        exit_widget_id: Option<WidgetId>,
+        config: &Config,
+    ) {
+        if kinds.is_empty() {
+            self.format_and_save_file(ctx, path, exit_widget_id, config);
+            return;
+        }
+
+        let doc = match self.open_docs.get(path) {
+            Some(doc) => doc,
+            None => return,
+        };
+        let kind = kinds.remove(0);
+        let rev = doc.rev();
+        let buffer = doc.buffer();
+        let range =
+            Range::new(Position::new(0, 0), buffer.offset_to_position(buffer.len()));
+        let timeout = config.editor.format_timeout;
+        let path = path.to_path_buf();
+        let proxy = self.proxy.clone();
+        let event_sink = ctx.get_external_handle();
+        let tab_id = *self.tab_id;
+        let (sender, receiver) = bounded(1);
+        thread::spawn(move || {
+            proxy.proxy_rpc.get_code_actions_of_kinds(
+                path.clone(),
+                range,
+                vec![CodeActionKind::from(kind.clone())],
+                Box::new(move |result| {
+                    let _ = sender.send(result);
+                }),
+            );
+
+            let result = receiver
+                .recv_timeout(Duration::from_millis(timeout))
+                .map_or_else(
+                    |e| Err(anyhow!("{}", e)),
+                    |v| {
+                        v.map_err(|e| anyhow!("{}", e.message)).and_then(|r| {
+                            if let ProxyResponse::GetCodeActionsResponse { resp } = r
+                            {
+                                Ok(resp)
+                            } else {
+                                Err(anyhow!("wrong response"))
+                            }
+                        })
+                    },
+                );
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::DocumentCodeActionsAndSave {
+                    path,
+                    rev,
+                    kind,
+                    result,
+                    kinds,
+                    exit_widget_id,
+                },
+                Target::Widget(tab_id),
+            );
+        });
+    }
+
+    /// Format the file if formatting on save is on, and save it
+    fn format_and_save_file(
+        &self,
+        ctx: &mut EventCtx,
+        path: &Path,
+        exit_widget_id: Option<WidgetId>,
+        config: &Config,
+    ) {
+        if !config.editor.format_on_save {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::DocumentSave(path.to_path_buf(), exit_widget_id),
+                Target::Widget(*self.tab_id),
+            ));
+            return;
+        }
+
+        let doc = match self.open_docs.get(path) {
+            Some(doc) => doc,
+            None => return,
+        };
+        let rev = doc.rev();
+        let event_sink = ctx.get_external_handle();
+        let tab_id = *self.tab_id;
+        self.get_document_formatting(path, None, config, move |path, result| {
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::DocumentFormatAndSave(
+                    path,
+                    rev,
+                    result,
+                    exit_widget_id,
+                ),
+                Target::Widget(tab_id),
+            );
+        });
+    }
+
+    /// Save the modified files in response to an auto save trigger, unless
+    /// auto save is set to another trigger. They are formatted like files
+    /// that are saved by hand, and if the text changed while the formatter
+    /// was running the formatting is skipped.
+    pub fn autosave(
+        &self,
+        ctx: &mut EventCtx,
+        config: &Config,
+        trigger: AutoSave,
+        path: Option<&Path>,
+    ) {
+        if config.editor.autosave() != trigger {
+            return;
+        }
+
+        let paths: Vec<PathBuf> = self
+            .open_docs
+            .iter()
+            .filter(|(p, doc)| {
+                path.map(|path| path == p.as_path()).unwrap_or(true)
+                    && doc.loaded()
+                    && !doc.buffer().is_pristine()
+                    && doc.disk_content().is_none()
+            })
+            .map(|(p, _)| p.clone())
+            .collect();
+        for path in paths {
+            self.save_file(ctx, &path, true, None, config);
+        }
+    }
+
+    /// Apply the edit of the code action of the kind that runs on save, or
+    /// of the preferred one if there are several, unless the text changed
+    /// since it was asked for, and go on with the rest of the kinds. The
+    /// error is why the action couldn't be applied.
+    #[allow(clippy::too_many_arguments)]
+    pub fn document_code_actions_and_save(
+        &mut self,
+        ctx: &mut EventCtx,
+        path: &Path,
+        rev: u64,
+        kind: &str,
+        result: &Result<CodeActionResponse>,
+        kinds: &[String],
+        exit_widget_id: Option<WidgetId>,
+        config: &Config,
+    ) -> Result<()> {
+        let applied = match result {
+            Ok(resp) => self.apply_code_action_on_save(path, rev, kind, resp),
+            Err(e) => Err(anyhow!("{}", e)),
+        };
+        self.run_code_actions_on_save(
+            ctx,
+            path,
+            kinds.to_vec(),
+            exit_widget_id,
+            config,
+        );
+        applied
+    }
+
+    fn apply_code_action_on_save(
+        &mut self,
+        path: &Path,
+        rev: u64,
+        kind: &str,
+        resp: &CodeActionResponse,
+    ) -> Result<()> {
+        // the servers may answer with actions of other kinds too
+        let actions = resp
+            .iter()
+            .filter_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => Some(action),
+                CodeActionOrCommand::Command(_) => None,
+            })
+            .filter(|action| {
+                action.kind.as_ref().map_or(false, |action_kind| {
+                    let action_kind = action_kind.as_str();
+                    action_kind == kind
+                        || action_kind
+                            .strip_prefix(kind)
+                            .map_or(false, |rest| rest.starts_with('.'))
+                })
+            })
+            .collect::<Vec<_>>();
+        let action = match actions
+            .iter()
+            .find(|action| action.is_preferred == Some(true))
+            .or_else(|| actions.first())
+        {
+            Some(action) => action,
+            None => return Ok(()),
+        };
+
+        let edit = action.edit.as_ref().ok_or_else(|| {
+            anyhow!(
+                "\"{}\" only has a command, which isn't run on save",
+                action.title
+            )
+        })?;
+        let edits = workspace_edits(edit)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(url, _)| url_matches_path(path, url))
+            .flat_map(|(_, edits)| edits)
+            .collect::<Vec<_>>();
+        self.document_format(path, rev, &Ok(edits));
+        Ok(())
+    }
+
     pub fn document_format_and_save(
         &mut self,
         ctx: &mut EventCtx,
@@ -2157,25 +4090,231 @@ pub fn document_format_and_save(
         self.document_save(ctx, path, exit_widget_id);
     }
 
-    pub fn document_save(
+    pub fn document_save(
+        &mut self,
+        ctx: &mut EventCtx,
+        path: &Path,
+        exit_widget_id: Option<WidgetId>,
+    ) {
+        if self.open_docs.get(path).unwrap().disk_content().is_some() {
+            self.show_disk_conflict(ctx, path);
+            return;
+        }
+
+        let edits = self.open_docs.get(path).unwrap().editorconfig_save_edits();
+        if !edits.is_empty() {
+            let edits = edits
+                .iter()
+                .map(|(selection, text)| (selection, text.as_str()))
+                .collect::<Vec<_>>();
+            self.edit(path, &edits, EditType::Other);
+        }
+
+        let doc = self.open_docs.get(path).unwrap();
+        let rev = doc.rev();
+        let encoding = doc.encoding().to_string();
+        let event_sink = ctx.get_external_handle();
+        let path = PathBuf::from(path);
+        let tab_id = *self.tab_id;
+        self.proxy.proxy_rpc.save(
+            rev,
+            path.clone(),
+            encoding,
+            Box::new(move |result| match result {
+                Ok(ProxyResponse::SaveResponse {}) => {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::BufferSave(path, rev, exit_widget_id),
+                        Target::Widget(tab_id),
+                    );
+                }
+                Ok(ProxyResponse::SaveConflictResponse { content }) => {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::OpenFileChanged {
+                            path,
+                            content: Rope::from(content),
+                        },
+                        Target::Widget(tab_id),
+                    );
+                }
+                Err(e) => {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::ShowAlert(AlertContentData {
+                            title: format!("Failed to save {}", path.display()),
+                            msg: e.message,
+                            buttons: Vec::new(),
+                        }),
+                        Target::Widget(tab_id),
+                    );
+                }
+                _ => {}
+            }),
+        );
+    }
+
+    /// Ask how to resolve the conflict of the file that was changed on disk
+    /// while it had unsaved changes
+    pub fn show_disk_conflict(&self, ctx: &mut EventCtx, path: &Path) {
+        let button = |text: &str, command: LapceWorkbenchCommand| {
+            (
+                text.to_string(),
+                *self.tab_id,
+                LapceCommand {
+                    kind: CommandKind::Workbench(command),
+                    data: serde_json::to_value(path).ok(),
+                },
+            )
+        };
+        ctx.submit_command(Command::new(
+            LAPCE_UI_COMMAND,
+            LapceUICommand::ShowAlert(AlertContentData {
+                title: format!(
+                    "{} has been changed on disk",
+                    path.file_name().and_then(|s| s.to_str()).unwrap_or("")
+                ),
+                msg: "Your unsaved changes can be saved over it, lost by \
+                      reloading it, or compared and merged with it."
+                    .to_string(),
+                buttons: vec![
+                    button("Overwrite", LapceWorkbenchCommand::OverwriteFileOnDisk),
+                    button("Reload", LapceWorkbenchCommand::ReloadFileFromDisk),
+                    button("Compare", LapceWorkbenchCommand::CompareWithFileOnDisk),
+                ],
+            }),
+            Target::Widget(*self.tab_id),
+        ));
+    }
+
+    /// Save the unsaved changes over the file that was changed on disk
+    pub fn overwrite_file_on_disk(&mut self, ctx: &mut EventCtx, path: &Path) {
+        if let Some(doc) = self.open_docs.get_mut(path) {
+            Arc::make_mut(doc).take_disk_content();
+            self.document_save(ctx, path, None);
+        }
+    }
+
+    /// Reload the file that was changed on disk, losing the unsaved changes
+    pub fn reload_file_from_disk(&mut self, path: &Path) {
+        if let Some(doc) = self.open_docs.get_mut(path) {
+            let doc = Arc::make_mut(doc);
+            if let Some(content) = doc.take_disk_content() {
+                doc.reload(content, true);
+            }
+        }
+    }
+
+    /// Merge the unsaved changes with the changes made on disk since the
+    /// file was last loaded or saved, leaving conflict markers where both
+    /// changed the same lines, and show the base, the unsaved text and the
+    /// file on disk next to the result to finish the merge by hand
+    pub fn compare_with_file_on_disk(
+        &mut self,
+        ctx: &mut EventCtx,
+        path: &Path,
+        config: &Config,
+    ) {
+        let doc = match self.open_docs.get_mut(path) {
+            Some(doc) => Arc::make_mut(doc),
+            None => return,
+        };
+        let disk_content = match doc.take_disk_content() {
+            Some(content) => content,
+            None => return,
+        };
+        let base = doc.buffer().pristine_text().clone();
+        let mine = doc.buffer().text().clone();
+        let merge = three_way_merge(
+            &base.to_string(),
+            &mine.to_string(),
+            &disk_content.to_string(),
+        );
+        doc.load_history(MERGE_BASE_VERSION, base);
+        doc.load_history(MERGE_MINE_VERSION, mine);
+        doc.load_history(MERGE_THEIRS_VERSION, disk_content);
+        let len = doc.buffer().len();
+        self.edit(
+            path,
+            &[(Selection::region(0, len), merge.text.as_str())],
+            EditType::Other,
+        );
+
+        let is_doc_editor = |editor: &LapceEditorData| {
+            editor.view.is_normal()
+                && matches!(&editor.content, BufferContent::File(p) if p == path)
+        };
+        let view_id = match self.active_editor() {
+            Some(editor) if is_doc_editor(editor) => Some(editor.view_id),
+            _ => self
+                .editors
+                .values()
+                .find(|editor| is_doc_editor(editor))
+                .map(|editor| editor.view_id),
+        };
+        if let Some(view_id) = view_id {
+            self.open_merge(ctx, view_id, config);
+        }
+    }
+
+    /// Show the base, the unsaved text and the file on disk of a merge side
+    /// by side, from the editor on, next to a new editor that shows the
+    /// merge result, which stays editable on the right
+    fn open_merge(
         &mut self,
         ctx: &mut EventCtx,
-        path: &Path,
-        exit_widget_id: Option<WidgetId>,
+        view_id: WidgetId,
+        config: &Config,
     ) {
-        let doc = self.open_docs.get(path).unwrap();
-        let rev = doc.rev();
+        let editor = Arc::make_mut(self.editors.get_mut(&view_id).unwrap());
+        editor.view =
+            EditorView::Compare(MERGE_BASE_VERSION.to_string(), DiffSide::Left);
+        editor.compare = Some(MERGE_BASE_VERSION.to_string());
+
+        let mut view_id = view_id;
+        for version in [MERGE_MINE_VERSION, MERGE_THEIRS_VERSION] {
+            view_id = match self.split_editor(
+                ctx,
+                view_id,
+                SplitDirection::Vertical,
+                config,
+            ) {
+                Some(view_id) => view_id,
+                None => return,
+            };
+            let editor = Arc::make_mut(self.editors.get_mut(&view_id).unwrap());
+            editor.view = EditorView::Compare(version.to_string(), DiffSide::Left);
+            editor.compare = Some(version.to_string());
+        }
+
+        // the result is aligned with the file on disk, next to it
+        self.open_comparison(ctx, view_id, MERGE_THEIRS_VERSION.to_string(), config);
+    }
+
+    /// Decode the file of the active editor again with the encoding
+    pub fn reopen_with_encoding(&mut self, ctx: &mut EventCtx, encoding: String) {
+        let path = match self.active_editor().map(|e| &e.content) {
+            Some(BufferContent::File(path)) => path.clone(),
+            _ => return,
+        };
         let event_sink = ctx.get_external_handle();
-        let path = PathBuf::from(path);
         let tab_id = *self.tab_id;
-        self.proxy.proxy_rpc.save(
-            rev,
+        self.proxy.proxy_rpc.reopen_with_encoding(
             path.clone(),
+            encoding,
             Box::new(move |result| {
-                if let Ok(ProxyResponse::SaveResponse {}) = result {
+                if let Ok(ProxyResponse::ReopenWithEncodingResponse {
+                    content,
+                    encoding,
+                }) = result
+                {
                     let _ = event_sink.submit_command(
                         LAPCE_UI_COMMAND,
-                        LapceUICommand::BufferSave(path, rev, exit_widget_id),
+                        LapceUICommand::ReopenedWithEncoding {
+                            path,
+                            content: Rope::from(content),
+                            encoding,
+                        },
                         Target::Widget(tab_id),
                     );
                 }
@@ -2183,6 +4322,125 @@ pub fn document_save(
         );
     }
 
+    /// Change every line ending of the file of the active editor, and the
+    /// line ending of new lines
+    pub fn change_line_ending(&mut self, line_ending: LineEnding) {
+        let path = match self.active_editor().map(|e| &e.content) {
+            Some(BufferContent::File(path)) => path.clone(),
+            _ => return,
+        };
+        let edits = if let Some(doc) = self.open_docs.get_mut(&path) {
+            let doc = Arc::make_mut(doc);
+            doc.set_line_ending(line_ending);
+            line_ending.edits(doc.buffer().text())
+        } else {
+            return;
+        };
+        if !edits.is_empty() {
+            self.edit(&path, &edits, EditType::Other);
+        }
+    }
+
+    /// Allow or reject the edits of the document of the active editor
+    pub fn toggle_read_only(&mut self) {
+        let content = match self.active_editor() {
+            Some(editor) => editor.content.clone(),
+            None => return,
+        };
+        let doc = match &content {
+            BufferContent::File(path) => self.open_docs.get_mut(path),
+            BufferContent::Scratch(id, _) => self.scratch_docs.get_mut(id),
+            _ => None,
+        };
+        if let Some(doc) = doc {
+            // a large file is read-only until all of it is loaded
+            if doc.loading_chunks() {
+                return;
+            }
+            let buffer = Arc::make_mut(doc).buffer_mut();
+            buffer.set_read_only(!buffer.is_read_only());
+        }
+    }
+
+    /// Whether the editor can't be edited, because its document is
+    /// read-only or it shows a history version
+    pub fn is_editor_read_only(&self, editor_view_id: WidgetId) -> bool {
+        let editor = self.editors.get(&editor_view_id).unwrap();
+        editor.view.is_read_only()
+            || self.content_doc(&editor.content).buffer().is_read_only()
+    }
+
+    /// Set the indentation of new lines in the file of the active editor, or
+    /// detect it from the file's content when it's `None`
+    pub fn change_indent_style(&mut self, indent_style: Option<IndentStyle>) {
+        let path = match self.active_editor().map(|e| &e.content) {
+            Some(BufferContent::File(path)) => path.clone(),
+            _ => return,
+        };
+        if let Some(doc) = self.open_docs.get_mut(&path) {
+            Arc::make_mut(doc).set_indent_style(indent_style);
+        }
+    }
+
+    /// Save the file of the active editor with the encoding
+    pub fn save_with_encoding(&mut self, ctx: &mut EventCtx, encoding: String) {
+        let path = match self.active_editor().map(|e| &e.content) {
+            Some(BufferContent::File(path)) => path.clone(),
+            _ => return,
+        };
+        if let Some(doc) = self.open_docs.get_mut(&path) {
+            Arc::make_mut(doc).set_encoding(encoding);
+            self.document_save(ctx, &path, None);
+        }
+    }
+
+    /// Set the diagnostics of the file to those of the language servers
+    /// and the problems of the tasks in it, and count the errors and the
+    /// warnings again
+    pub fn update_diagnostics(&mut self, path: &Path) {
+        let server_diagnostics = self
+            .server_diagnostics
+            .get(path)
+            .into_iter()
+            .flat_map(|diagnostics| diagnostics.iter());
+        let task_problems = self.task_problems.values().flat_map(|problems| {
+            problems
+                .iter()
+                .filter(|(problem_path, _)| problem_path == path)
+                .map(|(_, diagnostic)| diagnostic)
+        });
+        let diagnostics: Vec<EditorDiagnostic> = server_diagnostics
+            .chain(task_problems)
+            .map(|d| EditorDiagnostic::new(d.clone()))
+            .sorted_by_key(|d| d.diagnostic.range.start)
+            .collect();
+        let diagnostics = Arc::new(diagnostics);
+
+        // inform the document about the diagnostics
+        if let Some(document) = self.open_docs.get_mut(path) {
+            let document = Arc::make_mut(document);
+            document.set_diagnostics(&diagnostics);
+        }
+
+        self.diagnostics.insert(path.to_path_buf(), diagnostics);
+
+        let mut errors = 0;
+        let mut warnings = 0;
+        for (_, diagnostics) in self.diagnostics.iter() {
+            for diagnostic in diagnostics.iter() {
+                if let Some(severity) = diagnostic.diagnostic.severity {
+                    match severity {
+                        DiagnosticSeverity::ERROR => errors += 1,
+                        DiagnosticSeverity::WARNING => warnings += 1,
+                        _ => (),
+                    }
+                }
+            }
+        }
+        self.error_count = errors;
+        self.warning_count = warnings;
+    }
+
     pub fn diagnostics_items(
         &self,
         severity: DiagnosticSeverity,
@@ -2237,6 +4495,9 @@ pub fn edit(
         edit_type: EditType,
     ) -> Option<RopeDelta> {
         let doc = self.open_docs.get_mut(path)?;
+        if doc.buffer().is_read_only() {
+            return None;
+        }
 
         let buffer_len = doc.buffer().len();
         let mut move_cursor = true;
@@ -2257,6 +4518,190 @@ pub fn edit(
         Some(delta)
     }
 
+    /// Replace the matches of a workspace search. The files that are open
+    /// get the edits in their editors, while the other ones are loaded
+    /// without an editor, edited and saved right away. Returns the number
+    /// of files that are changed.
+    pub fn replace_in_files(
+        &mut self,
+        ctx: &mut EventCtx,
+        replacements: HashMap<PathBuf, Vec<SearchReplacement>>,
+        config: &Config,
+    ) -> usize {
+        let files = replacements.len();
+        for (path, replacements) in replacements {
+            if self.open_docs.contains_key(&path) {
+                self.apply_search_replacements(&path, &replacements);
+                continue;
+            }
+
+            let mut doc = Document::new(
+                BufferContent::File(path.clone()),
+                *self.tab_id,
+                ctx.get_external_handle(),
+                self.proxy.clone(),
+            );
+            let save_path = path.clone();
+            let callback: InitBufferContentCb = Box::new(
+                move |ctx: &mut EventCtx, main_split: &mut LapceMainSplitData| {
+                    main_split.apply_search_replacements(&save_path, &replacements);
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::DocumentSave(save_path.clone(), None),
+                        Target::Widget(*main_split.tab_id),
+                    ));
+                },
+            );
+            doc.retrieve_file::<usize>(
+                Vec::new(),
+                None,
+                Some(callback),
+                config.editor.large_file_size_bytes(),
+            );
+            self.open_docs.insert(path, Arc::new(doc));
+        }
+        files
+    }
+
+    fn apply_search_replacements(
+        &mut self,
+        path: &Path,
+        replacements: &[SearchReplacement],
+    ) {
+        let buffer = match self.open_docs.get(path) {
+            Some(doc) => doc.buffer(),
+            None => return,
+        };
+        let edits = replacements
+            .iter()
+            .filter_map(|r| {
+                if r.line >= buffer.num_lines() {
+                    return None;
+                }
+                // skip the matches that have changed since the search
+                let line = buffer.line_content(r.line);
+                if line.get(r.start..r.end) != Some(r.matched.as_str()) {
+                    return None;
+                }
+                let offset = buffer.offset_of_line(r.line);
+                Some((
+                    Selection::region(offset + r.start, offset + r.end),
+                    r.text.as_str(),
+                ))
+            })
+            .collect::<Vec<_>>();
+        if !edits.is_empty() {
+            self.edit(path, &edits, EditType::Other);
+        }
+    }
+
+    /// Apply the edits of a workspace edit to all of its files at once, once
+    /// the files that aren't open yet are loaded, so that they can be undone
+    /// together
+    pub fn apply_text_edits(
+        &mut self,
+        ctx: &mut EventCtx,
+        edits: Vec<(PathBuf, Vec<TextEdit>)>,
+        config: &Config,
+    ) {
+        let edits = Arc::new(edits);
+        let mut loading = false;
+        for (path, file_edits) in edits.iter() {
+            if self.open_docs.contains_key(path) {
+                continue;
+            }
+            loading = true;
+
+            let location = EditorLocation {
+                path: path.clone(),
+                position: file_edits.first().map(|edit| edit.range.start),
+                scroll_offset: None,
+                history: None,
+            };
+            let edits = edits.clone();
+            let callback =
+                move |_: &mut EventCtx, main_split: &mut LapceMainSplitData| {
+                    // the edits wait for the last of the files to be loaded
+                    let loaded = edits.iter().all(|(path, _)| {
+                        main_split
+                            .open_docs
+                            .get(path)
+                            .map(|doc| doc.loaded())
+                            .unwrap_or(false)
+                    });
+                    if loaded {
+                        main_split.apply_loaded_text_edits(&edits);
+                    }
+                };
+            self.jump_to_location_cb(
+                ctx,
+                None,
+                false,
+                location,
+                config,
+                Some(callback),
+            );
+        }
+        if !loading {
+            self.apply_loaded_text_edits(&edits);
+        }
+    }
+
+    fn apply_loaded_text_edits(&mut self, edits: &[(PathBuf, Vec<TextEdit>)]) {
+        let mut undo = Vec::new();
+        for (path, edits) in edits {
+            let buffer = match self.open_docs.get(path) {
+                Some(doc) => doc.buffer(),
+                None => continue,
+            };
+            let edits = edits
+                .iter()
+                .map(|edit| {
+                    let selection = Selection::region(
+                        buffer.offset_of_position(&edit.range.start),
+                        buffer.offset_of_position(&edit.range.end),
+                    );
+                    (selection, edit.new_text.as_str())
+                })
+                .collect::<Vec<_>>();
+            if self.edit(path, &edits, EditType::Other).is_some() {
+                let rev = self.open_docs.get(path).unwrap().rev();
+                undo.push((path.clone(), rev));
+            }
+        }
+        self.workspace_edit_undo = Some(Arc::new(undo));
+    }
+
+    /// Undo the last workspace edit in all of its files, when undoing in one
+    /// of them and none of them was edited since
+    pub fn undo_workspace_edit(&mut self, path: &Path) -> bool {
+        let undo = match self.workspace_edit_undo.as_ref() {
+            Some(undo) => undo.clone(),
+            None => return false,
+        };
+        if !undo.iter().any(|(p, _)| p == path) {
+            return false;
+        }
+        self.workspace_edit_undo = None;
+
+        let unchanged = undo.iter().all(|(path, rev)| {
+            self.open_docs
+                .get(path)
+                .map(|doc| doc.rev() == *rev)
+                .unwrap_or(false)
+        });
+        if !unchanged {
+            return false;
+        }
+        for (path, _) in undo.iter() {
+            let doc = Arc::make_mut(self.open_docs.get_mut(path).unwrap());
+            if let Some(delta) = doc.do_raw_undo() {
+                self.cursor_apply_delta(path, &delta);
+            }
+        }
+        true
+    }
+
     pub fn get_active_tab_mut(
         &mut self,
         ctx: &mut EventCtx,
@@ -2936,12 +5381,18 @@ pub fn go_to_location_cb<
                 doc.scroll_offset =
                     Vec2::new(info.scroll_offset.0, info.scroll_offset.1);
                 doc.cursor_offset = info.cursor_offset;
+                doc.folded = info.folded;
             }
 
             let cb: Option<InitBufferContentCb> = cb.map(|cb| Box::new(cb) as _);
 
             // We don't already have the document loaded, so go load it.
-            doc.retrieve_file(vec![(editor_view_id, location)], None, cb);
+            doc.retrieve_file(
+                vec![(editor_view_id, location)],
+                None,
+                cb,
+                config.editor.large_file_size_bytes(),
+            );
             self.open_docs.insert(path.clone(), Arc::new(doc));
         } else {
             let doc = self.open_docs.get_mut(&path).unwrap().clone();
@@ -3120,12 +5571,15 @@ pub fn new(
             proxy,
             palette_preview_editor: Arc::new(palette_preview_editor),
             diagnostics: im::HashMap::new(),
+            server_diagnostics: im::HashMap::new(),
+            task_problems: im::HashMap::new(),
             error_count: 0,
             warning_count: 0,
             workspace,
             db,
             locations: Arc::new(Vec::new()),
             current_location: 0,
+            workspace_edit_undo: None,
         };
 
         if let Some(info) = workspace_info {
@@ -3144,7 +5598,12 @@ pub fn new(
                     .get(&path.to_str().unwrap().to_string())
                     .map(Rope::from);
                 Arc::make_mut(main_split_data.open_docs.get_mut(&path).unwrap())
-                    .retrieve_file(locations.clone(), unsaved_buffer, None);
+                    .retrieve_file(
+                        locations.clone(),
+                        unsaved_buffer,
+                        None,
+                        config.editor.large_file_size_bytes(),
+                    );
             }
         } else {
             main_split_data.splits.insert(
@@ -3162,7 +5621,9 @@ pub fn new(
     }
 
     pub fn insert_editor(&mut self, editor: Arc<LapceEditorData>, config: &Config) {
-        if let Some((find_view_id, find_editor_id)) = editor.find_view_id {
+        if let Some((find_view_id, find_editor_id, replace_view_id)) =
+            editor.find_view_id
+        {
             let mut find_editor = LapceEditorData::new(
                 Some(find_view_id),
                 Some(find_editor_id),
@@ -3173,6 +5634,17 @@ pub fn insert_editor(&mut self, editor: Arc<LapceEditorData>, config: &Config) {
             find_editor.parent_view_id = Some(editor.view_id);
             self.editors
                 .insert(find_editor.view_id, Arc::new(find_editor));
+
+            let mut replace_editor = LapceEditorData::new(
+                Some(replace_view_id),
+                None,
+                None,
+                BufferContent::Local(LocalBufferKind::FindReplace),
+                config,
+            );
+            replace_editor.parent_view_id = Some(editor.view_id);
+            self.editors
+                .insert(replace_editor.view_id, Arc::new(replace_editor));
         }
         self.editors.insert(editor.view_id, editor);
     }
@@ -3276,6 +5748,7 @@ pub fn save_as_success(
                     let mut_doc = Arc::make_mut(&mut doc);
                     mut_doc.buffer_mut().set_pristine();
                     mut_doc.set_content(new_content);
+                    mut_doc.save_undo_history();
                     self.open_docs.insert(path.to_path_buf(), doc);
                     if exit {
                         ctx.submit_command(Command::new(
@@ -3314,6 +5787,7 @@ pub fn save_as(
                     path.to_path_buf(),
                     doc.rev(),
                     doc.buffer().to_string(),
+                    doc.encoding().to_string(),
                     Box::new(move |result| {
                         if let Ok(_r) = result {
                             let _ = event_sink.submit_command(
@@ -3730,54 +6204,153 @@ pub fn tab_split(
         }
     }
 
+    /// Split the editor into a new editor tab, returning the view id of the
+    /// new editor
     pub fn split_editor(
         &mut self,
         ctx: &mut EventCtx,
         view_id: WidgetId,
         direction: SplitDirection,
         config: &Config,
-    ) {
+    ) -> Option<WidgetId> {
         let editor = self.editors.get(&view_id).unwrap();
-        if let Some(editor_tab_id) = editor.tab_id {
-            let editor_tab = self.editor_tabs.get(&editor_tab_id).unwrap();
-            let split_id = editor_tab.split;
-            let mut new_editor = editor.copy();
-            let mut new_editor_tab = LapceEditorTabData {
-                widget_id: WidgetId::next(),
-                split: split_id,
-                active: 0,
-                children: vec![EditorTabChild::Editor(
-                    new_editor.view_id,
-                    new_editor.editor_id,
-                    new_editor.find_view_id,
-                )],
-                layout_rect: Rc::new(RefCell::new(Rect::ZERO)),
-                content_is_hot: Rc::new(RefCell::new(false)),
-            };
-            new_editor.tab_id = Some(new_editor_tab.widget_id);
+        let editor_tab_id = editor.tab_id?;
+        let editor_tab = self.editor_tabs.get(&editor_tab_id).unwrap();
+        let split_id = editor_tab.split;
+        let mut new_editor = editor.copy();
+        let mut new_editor_tab = LapceEditorTabData {
+            widget_id: WidgetId::next(),
+            split: split_id,
+            active: 0,
+            children: vec![EditorTabChild::Editor(
+                new_editor.view_id,
+                new_editor.editor_id,
+                new_editor.find_view_id,
+            )],
+            layout_rect: Rc::new(RefCell::new(Rect::ZERO)),
+            content_is_hot: Rc::new(RefCell::new(false)),
+        };
+        new_editor.tab_id = Some(new_editor_tab.widget_id);
 
-            let new_split_id = self.split(
-                ctx,
-                split_id,
-                SplitContent::EditorTab(editor_tab_id),
-                SplitContent::EditorTab(new_editor_tab.widget_id),
-                direction,
-                false,
-                false,
-            );
+        let new_split_id = self.split(
+            ctx,
+            split_id,
+            SplitContent::EditorTab(editor_tab_id),
+            SplitContent::EditorTab(new_editor_tab.widget_id),
+            direction,
+            false,
+            false,
+        );
+
+        new_editor_tab.split = new_split_id;
+        if split_id != new_split_id {
+            let editor_tab = self.editor_tabs.get_mut(&editor_tab_id).unwrap();
+            let editor_tab = Arc::make_mut(editor_tab);
+            editor_tab.split = new_split_id;
+        }
+
+        let new_view_id = new_editor.view_id;
+        self.insert_editor(Arc::new(new_editor), config);
+        self.editor_tabs
+            .insert(new_editor_tab.widget_id, Arc::new(new_editor_tab));
+        Some(new_view_id)
+    }
 
-            new_editor_tab.split = new_split_id;
-            if split_id != new_split_id {
-                let editor_tab = self.editor_tabs.get_mut(&editor_tab_id).unwrap();
-                let editor_tab = Arc::make_mut(editor_tab);
-                editor_tab.split = new_split_id;
+    /// Compare the file of the active editor with the file at `path`, which
+    /// is shown on the left of it, while the active file stays editable on
+    /// the right
+    pub fn compare_active_file_with(
+        &mut self,
+        ctx: &mut EventCtx,
+        path: &Path,
+        config: &Config,
+    ) {
+        let editor = match self.active_editor() {
+            Some(editor) => editor,
+            None => return,
+        };
+        let active_path = match &editor.content {
+            BufferContent::File(active_path) if active_path != path => {
+                active_path.clone()
             }
+            _ => return,
+        };
+        let view_id = editor.view_id;
+        let version = compare_version(path);
 
-            self.insert_editor(Arc::new(new_editor), config);
-            self.editor_tabs
-                .insert(new_editor_tab.widget_id, Arc::new(new_editor_tab));
+        if let Some(compared_doc) = self.open_docs.get(path) {
+            let content = compared_doc.buffer().text().clone();
+            let doc = self.open_docs.get_mut(&active_path).unwrap();
+            Arc::make_mut(doc).load_history(&version, content);
+        } else {
+            let tab_id = *self.tab_id;
+            let event_sink = ctx.get_external_handle();
+            let version = version.clone();
+            self.proxy
+                .proxy_rpc
+                .read_file(path.to_path_buf(), move |result| {
+                    if let Ok(ProxyResponse::ReadFileResponse { content }) = result {
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::LoadBufferHead {
+                                path: active_path,
+                                content: Rope::from(content),
+                                version,
+                            },
+                            Target::Widget(tab_id),
+                        );
+                    }
+                });
+        }
+
+        self.open_comparison(ctx, view_id, version, config);
+    }
+
+    /// Show the history `version` in the editor, next to a new editor that
+    /// shows the document, which stays editable on the right
+    fn open_comparison(
+        &mut self,
+        ctx: &mut EventCtx,
+        view_id: WidgetId,
+        version: String,
+        config: &Config,
+    ) {
+        let editor = Arc::make_mut(self.editors.get_mut(&view_id).unwrap());
+        editor.view = EditorView::Compare(version.clone(), DiffSide::Left);
+        editor.compare = Some(version.clone());
+        if let Some(new_view_id) =
+            self.split_editor(ctx, view_id, SplitDirection::Vertical, config)
+        {
+            let new_editor =
+                Arc::make_mut(self.editors.get_mut(&new_view_id).unwrap());
+            new_editor.view = EditorView::Compare(version, DiffSide::Right);
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::Focus,
+                Target::Widget(new_view_id),
+            ));
         }
     }
+
+    /// The editor that shows the other side of the comparison that the
+    /// editor is a side of
+    pub fn compare_sibling(&self, view_id: WidgetId) -> Option<&LapceEditorData> {
+        let editor = self.editors.get(&view_id)?;
+        let (version, side) = match &editor.view {
+            EditorView::Compare(version, side) => (version, *side),
+            _ => return None,
+        };
+        self.editors
+            .values()
+            .find(|e| {
+                e.content == editor.content
+                    && matches!(
+                        &e.view,
+                        EditorView::Compare(v, s) if v == version && *s != side
+                    )
+            })
+            .map(|e| e.as_ref())
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -3794,7 +6367,7 @@ pub enum InlineFindDirection {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum EditorTabChild {
-    Editor(WidgetId, WidgetId, Option<(WidgetId, WidgetId)>),
+    Editor(WidgetId, WidgetId, Option<(WidgetId, WidgetId, WidgetId)>),
     Settings {
         settings_widget_id: WidgetId,
         editor_tab_id: WidgetId,
@@ -3897,6 +6470,9 @@ pub struct SelectionHistory {
 pub enum EditorView {
     Normal,
     Diff(String),
+    /// One side of the document shown next to the history version, with
+    /// their hunks aligned
+    Compare(String, DiffSide),
     Lens,
 }
 
@@ -3904,6 +6480,18 @@ impl EditorView {
     pub fn is_normal(&self) -> bool {
         matches!(self, EditorView::Normal)
     }
+
+    /// The left side of a comparison shows the history version instead of
+    /// the document, so it can't be edited
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, EditorView::Compare(_, DiffSide::Left))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffSide {
+    Left,
+    Right,
 }
 
 #[derive(Debug)]
@@ -3913,13 +6501,64 @@ pub struct StickyHeaderInfo {
     pub last_y_diff: f64,
 }
 
+/// The ranges of the text that are edited together, like the names of an
+/// opening and a closing tag, as offsets in a revision of the document
+#[derive(Clone, Debug)]
+pub struct LinkedEditing {
+    pub rev: u64,
+    /// The ranges in the order they are in the document
+    pub ranges: Vec<(usize, usize)>,
+    /// What the text of the ranges has to match for them to stay linked
+    pub word_pattern: Option<String>,
+}
+
+impl LinkedEditing {
+    pub fn new(buffer: &Buffer, rev: u64, linked: &LinkedEditingRanges) -> Self {
+        let ranges = linked
+            .ranges
+            .iter()
+            .map(|range| {
+                (
+                    buffer.offset_of_position(&range.start),
+                    buffer.offset_of_position(&range.end),
+                )
+            })
+            .sorted()
+            .collect();
+        Self {
+            rev,
+            ranges,
+            word_pattern: linked.word_pattern.clone(),
+        }
+    }
+
+    /// The index of the range the region is in
+    pub fn range_of(&self, start: usize, end: usize) -> Option<usize> {
+        self.ranges.iter().position(|(range_start, range_end)| {
+            *range_start <= start && end <= *range_end
+        })
+    }
+
+    /// Move the ranges along with an edit that made the next revision
+    pub fn apply_delta(&mut self, delta: &RopeDelta) {
+        let mut transformer = Transformer::new(delta);
+        for (start, end) in self.ranges.iter_mut() {
+            *start = transformer.transform(*start, false);
+            *end = transformer.transform(*end, true);
+        }
+        self.rev += 1;
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LapceEditorData {
     pub tab_id: Option<WidgetId>,
     pub view_id: WidgetId,
     pub editor_id: WidgetId,
     pub parent_view_id: Option<WidgetId>,
-    pub find_view_id: Option<(WidgetId, WidgetId)>,
+    /// The view id and editor id of the find input of the find bar, and the
+    /// view id of its replace input
+    pub find_view_id: Option<(WidgetId, WidgetId, WidgetId)>,
     pub content: BufferContent,
     pub view: EditorView,
     pub compare: Option<String>,
@@ -3930,6 +6569,13 @@ pub struct LapceEditorData {
     pub sticky_header: Rc<RefCell<StickyHeaderInfo>>,
     pub window_origin: Rc<RefCell<Point>>,
     pub snippet: Option<Vec<(usize, (usize, usize))>>,
+    /// The ranges of the choices of the snippet and their options
+    pub snippet_choices: Vec<((usize, usize), Vec<String>)>,
+    /// The ranges edited together with the one the cursor is in
+    pub linked_editing: Option<LinkedEditing>,
+    /// The selection ranges from the language server to expand the
+    /// selection with
+    pub expand_selection_ranges: Option<ExpandSelectionRanges>,
     pub last_movement_new: Movement,
     pub last_inline_find: Option<(InlineFindDirection, String)>,
     pub inline_find: Option<InlineFindDirection>,
@@ -3953,7 +6599,7 @@ pub fn new(
             find_view_id: if content.is_special() {
                 None
             } else {
-                Some((WidgetId::next(), WidgetId::next()))
+                Some((WidgetId::next(), WidgetId::next(), WidgetId::next()))
             },
             scroll_offset: Vec2::ZERO,
             cursor: if content.is_input() {
@@ -3974,6 +6620,9 @@ pub fn new(
             compare: None,
             window_origin: Rc::new(RefCell::new(Point::ZERO)),
             snippet: None,
+            snippet_choices: Vec::new(),
+            linked_editing: None,
+            expand_selection_ranges: None,
             last_movement_new: Movement::Left,
             inline_find: None,
             last_inline_find: None,
@@ -3987,7 +6636,7 @@ pub fn copy(&self) -> LapceEditorData {
         new_editor.editor_id = WidgetId::next();
         new_editor.find_view_id = new_editor
             .find_view_id
-            .map(|_| (WidgetId::next(), WidgetId::next()));
+            .map(|_| (WidgetId::next(), WidgetId::next(), WidgetId::next()));
         new_editor.size = Rc::new(RefCell::new(Size::ZERO));
         new_editor.window_origin = Rc::new(RefCell::new(Point::ZERO));
         new_editor
@@ -25,6 +25,7 @@
     editor::EditorLocation,
     panel::{PanelData, PanelOrder},
     split::SplitDirection,
+    update::UpdateDismissal,
 };
 
 pub enum SaveEvent {
@@ -241,6 +242,27 @@ pub fn to_data(
 pub struct WorkspaceInfo {
     pub split: SplitInfo,
     pub panel: PanelData,
+    #[serde(default)]
+    pub terminals: Vec<TerminalInfo>,
+}
+
+/// A terminal of the workspace, which is started again when the workspace
+/// is opened
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerminalInfo {
+    /// The name of the profile, or `None` for the default profile
+    pub profile: Option<String>,
+    pub cwd: Option<PathBuf>,
+    /// The tmux session the terminal runs in on a remote machine, which it
+    /// attaches to again
+    pub session: Option<String>,
+    /// The name the terminal was given, the color and the icon of its header
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -263,6 +285,8 @@ pub struct BufferInfo {
     pub path: PathBuf,
     pub scroll_offset: (f64, f64),
     pub cursor_offset: usize,
+    #[serde(default)]
+    pub folded: Vec<usize>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -579,6 +603,24 @@ pub fn get_last_window_info(&self) -> Result<WindowInfo> {
         Ok(info)
     }
 
+    pub fn get_update_dismissal(&self) -> Result<UpdateDismissal> {
+        let sled_db = self.get_db()?;
+        let dismissal = sled_db
+            .get("update_dismissal")?
+            .ok_or_else(|| anyhow!("can't find update dismissal"))?;
+        let dismissal = std::str::from_utf8(&dismissal)?;
+        let dismissal: UpdateDismissal = serde_json::from_str(dismissal)?;
+        Ok(dismissal)
+    }
+
+    pub fn save_update_dismissal(&self, dismissal: &UpdateDismissal) -> Result<()> {
+        let info = serde_json::to_string(dismissal)?;
+        let sled_db = self.get_db()?;
+        sled_db.insert("update_dismissal", info.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
     pub fn get_panel_orders(&self) -> Result<PanelOrder> {
         let sled_db = self.get_db()?;
         let panel_orders = sled_db
@@ -661,6 +703,7 @@ pub fn save_doc_position(&self, workspace: &LapceWorkspace, doc: &Document) {
                 path: path.clone(),
                 scroll_offset: (doc.scroll_offset.x, doc.scroll_offset.y),
                 cursor_offset: doc.cursor_offset,
+                folded: doc.folded.clone(),
             };
             let _ = self.save_tx.send(SaveEvent::Buffer(info));
         }
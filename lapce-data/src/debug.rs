@@ -0,0 +1,1370 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use druid::WidgetId;
+use lapce_rpc::dap_types::{
+    Breakpoint, Capabilities, DapId, DataBreakpoint, EvaluateResult,
+    ReadMemoryResult, RunDebugConfig, Scope, SourceBreakpoint, StackFrame, Stopped,
+    Thread, ThreadId, Variable,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The file in the workspace that has the launch configurations, as
+/// `[[configs]]` tables, and the groups of them that start together, as
+/// `[[compounds]]` tables
+pub const LAUNCH_CONFIGS_PATH: &str = ".lapce/launch.toml";
+
+/// How many lines above the line the program stopped at get the values of
+/// the variables in them
+pub const INLINE_VALUE_LINES: usize = 100;
+
+/// How long a value shown after a line can be before it's shortened
+const INLINE_VALUE_MAX_CHARS: usize = 50;
+
+/// How many bytes of memory the memory section shows at once
+pub const MEMORY_PAGE_BYTES: usize = 256;
+
+/// How many bytes a line of the memory section has
+const MEMORY_LINE_BYTES: usize = 16;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LaunchConfigs {
+    #[serde(default)]
+    pub configs: Vec<RunDebugConfig>,
+    #[serde(default)]
+    pub compounds: Vec<CompoundConfig>,
+}
+
+impl LaunchConfigs {
+    /// The configurations the compound starts, by their names
+    pub fn compound_configs(
+        &self,
+        compound: &CompoundConfig,
+    ) -> Result<Vec<RunDebugConfig>, String> {
+        compound
+            .configs
+            .iter()
+            .map(|name| {
+                self.configs
+                    .iter()
+                    .find(|config| &config.name == name)
+                    .cloned()
+                    .ok_or_else(|| {
+                        format!("{}: no configuration named {name}", compound.name)
+                    })
+            })
+            .collect()
+    }
+}
+
+/// A group of launch configurations that are debugged together, like a
+/// client and its server
+#[derive(Clone, Debug, Deserialize)]
+pub struct CompoundConfig {
+    pub name: String,
+    /// The names of the configurations
+    pub configs: Vec<String>,
+}
+
+/// The variables that are replaced with the id of a process picked from the
+/// running ones, for the configurations that attach to it
+const PICK_PROCESS_VARIABLES: &[&str] =
+    &["${pickProcess}", "${command:pickProcess}"];
+
+/// Whether the configuration has a process to be picked before it's started
+pub fn picks_process(config: &RunDebugConfig) -> bool {
+    serde_json::to_string(config)
+        .map(|config| {
+            PICK_PROCESS_VARIABLES
+                .iter()
+                .any(|variable| config.contains(variable))
+        })
+        .unwrap_or(false)
+}
+
+/// What the `${name}` variables of the launch configurations are replaced
+/// with when they're started
+#[derive(Clone, Debug, Default)]
+pub struct LaunchVariables {
+    pub workspace: Option<PathBuf>,
+    /// The file of the active editor
+    pub file: Option<PathBuf>,
+    /// The id of the process that was picked to attach to
+    pub pid: Option<u32>,
+}
+
+impl LaunchVariables {
+    fn value(&self, name: &str) -> Option<String> {
+        if name == "pickProcess" || name == "command:pickProcess" {
+            return self.pid.map(|pid| pid.to_string());
+        }
+        let file = self.file.as_deref();
+        let path = match name {
+            "workspace" | "workspaceFolder" => self.workspace.clone()?,
+            "file" => file?.to_path_buf(),
+            "fileDirname" => file?.parent()?.to_path_buf(),
+            "fileBasename" => PathBuf::from(file?.file_name()?),
+            "fileBasenameNoExtension" => PathBuf::from(file?.file_stem()?),
+            "relativeFile" => file?
+                .strip_prefix(self.workspace.as_deref()?)
+                .ok()?
+                .to_path_buf(),
+            _ => return None,
+        };
+        Some(path.to_string_lossy().to_string())
+    }
+
+    /// Replace the variables of the text, leaving the unknown ones as they
+    /// are
+    pub fn substitute(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = match rest.find('}') {
+                Some(end) => end,
+                None => break,
+            };
+            match self.value(&rest[2..end]) {
+                Some(value) => result.push_str(&value),
+                None => result.push_str(&rest[..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    fn substitute_value(&self, value: &mut Value) {
+        match value {
+            // The adapters take the id of the process as a number
+            Value::String(s)
+                if self.pid.is_some()
+                    && PICK_PROCESS_VARIABLES.contains(&s.as_str()) =>
+            {
+                *value = Value::from(self.pid);
+            }
+            Value::String(s) => *s = self.substitute(s),
+            Value::Array(values) => {
+                for value in values.iter_mut() {
+                    self.substitute_value(value);
+                }
+            }
+            Value::Object(values) => {
+                for value in values.values_mut() {
+                    self.substitute_value(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The configuration with the variables of its program, arguments,
+    /// working directory, environment and adapter arguments replaced
+    pub fn apply(&self, config: &RunDebugConfig) -> RunDebugConfig {
+        let mut config = config.clone();
+        if let Some(program) = config.program.as_mut() {
+            *program = self.substitute(program);
+        }
+        for arg in config.args.iter_mut() {
+            *arg = self.substitute(arg);
+        }
+        if let Some(cwd) = config.cwd.as_mut() {
+            *cwd = PathBuf::from(self.substitute(&cwd.to_string_lossy()));
+        }
+        if let Some(env) = config.env.as_mut() {
+            for value in env.values_mut() {
+                *value = self.substitute(value);
+            }
+        }
+        if let Some(arguments) = config.arguments.as_mut() {
+            self.substitute_value(arguments);
+        }
+        config
+    }
+}
+
+/// A breakpoint of a line, which the debug adapter tells if it could set
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LapceBreakpoint {
+    pub verified: bool,
+    pub condition: Option<String>,
+    pub hit_condition: Option<String>,
+    /// The message of a logpoint, which logs it instead of stopping
+    pub log_message: Option<String>,
+}
+
+impl LapceBreakpoint {
+    pub fn option(&self, kind: BreakpointOptionKind) -> Option<&String> {
+        match kind {
+            BreakpointOptionKind::Condition => self.condition.as_ref(),
+            BreakpointOptionKind::HitCondition => self.hit_condition.as_ref(),
+            BreakpointOptionKind::LogMessage => self.log_message.as_ref(),
+        }
+    }
+
+    fn option_mut(&mut self, kind: BreakpointOptionKind) -> &mut Option<String> {
+        match kind {
+            BreakpointOptionKind::Condition => &mut self.condition,
+            BreakpointOptionKind::HitCondition => &mut self.hit_condition,
+            BreakpointOptionKind::LogMessage => &mut self.log_message,
+        }
+    }
+
+    pub fn is_logpoint(&self) -> bool {
+        self.log_message.is_some()
+    }
+
+    pub fn is_conditional(&self) -> bool {
+        self.condition.is_some() || self.hit_condition.is_some()
+    }
+}
+
+/// What a breakpoint can be given besides its line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakpointOptionKind {
+    Condition,
+    HitCondition,
+    LogMessage,
+}
+
+impl BreakpointOptionKind {
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            BreakpointOptionKind::Condition => {
+                "Enter the condition to stop at, like x > 5"
+            }
+            BreakpointOptionKind::HitCondition => {
+                "Enter the hit count to stop after, like >= 3"
+            }
+            BreakpointOptionKind::LogMessage => {
+                "Enter the message to log, with expressions in {}"
+            }
+        }
+    }
+}
+
+/// The option of the breakpoint of a line that the palette edits
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BreakpointOptionEdit {
+    pub path: PathBuf,
+    /// The line, starting at 0
+    pub line: usize,
+    pub kind: BreakpointOptionKind,
+    /// The value the option had, which the palette starts with
+    pub value: String,
+}
+
+/// An expression that is evaluated in the frame the program stops at
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchExpression {
+    pub expression: String,
+    /// The value at the last stop, or why it couldn't be evaluated
+    pub value: Option<Result<EvaluateResult, String>>,
+}
+
+/// A line of the watch expressions, which is an expression or one of the
+/// children of an expanded value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchRow {
+    /// The expression, followed by the names of the children down to the
+    /// row
+    pub path: Vec<String>,
+    /// The index of the expression, if the row is one
+    pub index: Option<usize>,
+    pub value: Option<Result<String, String>>,
+    /// The reference to the children, or 0 if it has none
+    pub variables_reference: usize,
+    pub expanded: bool,
+}
+
+impl WatchRow {
+    pub fn name(&self) -> &str {
+        self.path.last().map(|name| name.as_str()).unwrap_or("")
+    }
+
+    pub fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+/// A data breakpoint of a program, with the description of the data the
+/// adapter gave
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LapceDataBreakpoint {
+    pub description: String,
+    pub breakpoint: DataBreakpoint,
+}
+
+/// The memory of a program read from a reference, which the memory section
+/// shows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryView {
+    pub dap_id: DapId,
+    pub memory_reference: String,
+    /// The offset from the reference the memory is read from
+    pub offset: i64,
+    /// The memory, or `None` while it's being read
+    pub result: Option<Result<ReadMemoryResult, String>>,
+}
+
+/// A line of the call stack
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallStackRow {
+    /// The frame at the index
+    Frame(usize),
+    /// The frame at the index, which only separates the ones around it
+    Label(usize),
+    /// The collapsed run of subtle frames from the index
+    Subtle { start: usize, count: usize },
+}
+
+/// A program being debugged
+#[derive(Clone)]
+pub struct DapData {
+    pub dap_id: DapId,
+    pub config: RunDebugConfig,
+    /// What the adapter supports, which is nothing until it's initialized
+    pub capabilities: Capabilities,
+    /// Why the program stopped, unless it's running
+    pub stopped: Option<Stopped>,
+    /// The thread that stopped last, or the one chosen after, which the
+    /// commands step
+    pub thread_id: Option<ThreadId>,
+    pub threads: Vec<Thread>,
+    /// The stack of the thread
+    pub stack_frames: Vec<StackFrame>,
+    /// The index of the frame the variables and the expressions are for,
+    /// which is the top one until another is chosen
+    pub frame_index: usize,
+    /// The starts of the runs of subtle frames that are expanded
+    pub expanded_frames: HashSet<usize>,
+    /// The variables of the frame, by scope
+    pub variables: Vec<(Scope, Vec<Variable>)>,
+    /// The data breakpoints, which only last as long as the program
+    pub data_breakpoints: Vec<LapceDataBreakpoint>,
+}
+
+#[derive(Clone)]
+pub struct DebugData {
+    pub widget_id: WidgetId,
+    pub split_id: WidgetId,
+    pub content_id: WidgetId,
+    /// The breakpoints of the files, by their lines starting at 0
+    pub breakpoints: BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>,
+    pub daps: HashMap<DapId, DapData>,
+    /// The program the commands and the panel are for
+    pub active_dap: Option<DapId>,
+    pub watch_id: WidgetId,
+    pub watches: Vec<WatchExpression>,
+    /// The paths of the expanded expressions and children, as in
+    /// [`WatchRow::path`], which stay expanded from stop to stop
+    pub expanded_watches: HashSet<Vec<String>>,
+    /// The children of the expanded paths at the last stop
+    pub watch_children: HashMap<Vec<String>, Vec<Variable>>,
+    pub memory_id: WidgetId,
+    pub memory: Option<MemoryView>,
+}
+
+impl DapData {
+    /// The frame the program stopped in that the variables and the
+    /// expressions are for
+    pub fn frame(&self) -> Option<&StackFrame> {
+        self.stopped.as_ref()?;
+        self.stack_frames.get(self.frame_index)
+    }
+
+    /// The lines of the call stack, where the runs of more than one subtle
+    /// frame are collapsed unless they're expanded or have the frame
+    pub fn call_stack_rows(&self) -> Vec<CallStackRow> {
+        let is_subtle = |frame: &StackFrame| {
+            frame.presentation_hint.as_deref() == Some("subtle")
+        };
+        let mut rows = Vec::new();
+        let mut i = 0;
+        while i < self.stack_frames.len() {
+            let frame = &self.stack_frames[i];
+            if frame.presentation_hint.as_deref() == Some("label") {
+                rows.push(CallStackRow::Label(i));
+                i += 1;
+                continue;
+            }
+            let count = self.stack_frames[i..]
+                .iter()
+                .take_while(|frame| is_subtle(frame))
+                .count();
+            if count > 1
+                && !self.expanded_frames.contains(&i)
+                && !(i..i + count).contains(&self.frame_index)
+            {
+                rows.push(CallStackRow::Subtle { start: i, count });
+            } else {
+                rows.extend((i..i + count.max(1)).map(CallStackRow::Frame));
+            }
+            i += count.max(1);
+        }
+        rows
+    }
+}
+
+impl DebugData {
+    pub fn new() -> Self {
+        Self {
+            widget_id: WidgetId::next(),
+            split_id: WidgetId::next(),
+            content_id: WidgetId::next(),
+            breakpoints: BTreeMap::new(),
+            daps: HashMap::new(),
+            active_dap: None,
+            watch_id: WidgetId::next(),
+            watches: Vec::new(),
+            expanded_watches: HashSet::new(),
+            watch_children: HashMap::new(),
+            memory_id: WidgetId::next(),
+            memory: None,
+        }
+    }
+
+    pub fn active(&self) -> Option<&DapData> {
+        self.active_dap.and_then(|dap_id| self.daps.get(&dap_id))
+    }
+
+    /// The programs being debugged, in the order they were started
+    pub fn sessions(&self) -> Vec<&DapData> {
+        let mut sessions = self.daps.values().collect::<Vec<_>>();
+        sessions.sort_by_key(|dap| dap.dap_id);
+        sessions
+    }
+
+    /// Make the program the one the commands and the panel are for, and
+    /// tell if it wasn't already. The values of the watch expressions are
+    /// cleared, as they were the other program's.
+    pub fn set_active(&mut self, dap_id: DapId) -> bool {
+        if self.active_dap == Some(dap_id) || !self.daps.contains_key(&dap_id) {
+            return false;
+        }
+        self.active_dap = Some(dap_id);
+        for watch in self.watches.iter_mut() {
+            watch.value = None;
+        }
+        self.watch_children.clear();
+        true
+    }
+
+    pub fn start(&mut self, dap_id: DapId, config: RunDebugConfig) {
+        self.daps.insert(
+            dap_id,
+            DapData {
+                dap_id,
+                config,
+                capabilities: Capabilities::default(),
+                stopped: None,
+                thread_id: None,
+                threads: Vec::new(),
+                stack_frames: Vec::new(),
+                frame_index: 0,
+                expanded_frames: HashSet::new(),
+                variables: Vec::new(),
+                data_breakpoints: Vec::new(),
+            },
+        );
+        self.active_dap = Some(dap_id);
+    }
+
+    pub fn set_capabilities(&mut self, dap_id: DapId, capabilities: Capabilities) {
+        if let Some(dap) = self.daps.get_mut(&dap_id) {
+            dap.capabilities = capabilities;
+        }
+    }
+
+    /// Add a breakpoint to the line, or remove the one it has
+    pub fn toggle_breakpoint(&mut self, path: &Path, line: usize) {
+        let breakpoints = self.breakpoints.entry(path.to_path_buf()).or_default();
+        if breakpoints.remove(&line).is_none() {
+            breakpoints.insert(line, LapceBreakpoint::default());
+        }
+        if breakpoints.is_empty() {
+            self.breakpoints.remove(path);
+        }
+    }
+
+    /// Set an option of the breakpoint of the line, adding the breakpoint if
+    /// it has none, or clear the option when the value is empty
+    pub fn set_breakpoint_option(
+        &mut self,
+        path: &Path,
+        line: usize,
+        kind: BreakpointOptionKind,
+        value: &str,
+    ) {
+        let breakpoint = self
+            .breakpoints
+            .entry(path.to_path_buf())
+            .or_default()
+            .entry(line)
+            .or_default();
+        *breakpoint.option_mut(kind) = if value.trim().is_empty() {
+            None
+        } else {
+            Some(value.to_string())
+        };
+    }
+
+    /// The breakpoints of the file, as the debug adapters take them
+    pub fn source_breakpoints(&self, path: &Path) -> Vec<SourceBreakpoint> {
+        self.breakpoints
+            .get(path)
+            .map(|breakpoints| {
+                breakpoints
+                    .iter()
+                    .map(|(line, breakpoint)| SourceBreakpoint {
+                        line: line + 1,
+                        condition: breakpoint.condition.clone(),
+                        hit_condition: breakpoint.hit_condition.clone(),
+                        log_message: breakpoint.log_message.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn all_source_breakpoints(&self) -> HashMap<PathBuf, Vec<SourceBreakpoint>> {
+        self.breakpoints
+            .keys()
+            .map(|path| (path.clone(), self.source_breakpoints(path)))
+            .collect()
+    }
+
+    /// Record which breakpoints of the file the adapter set, which it gives
+    /// in the order they were sent
+    pub fn set_verified(&mut self, path: &Path, verified: &[Breakpoint]) {
+        if let Some(breakpoints) = self.breakpoints.get_mut(path) {
+            for (breakpoint, verified) in breakpoints.values_mut().zip(verified) {
+                breakpoint.verified = verified.verified;
+            }
+        }
+    }
+
+    pub fn stopped(
+        &mut self,
+        dap_id: DapId,
+        stopped: Stopped,
+        threads: Vec<Thread>,
+        stack_frames: Vec<StackFrame>,
+        variables: Vec<(Scope, Vec<Variable>)>,
+    ) {
+        if let Some(dap) = self.daps.get_mut(&dap_id) {
+            dap.thread_id = stopped.thread_id;
+            dap.stopped = Some(stopped);
+            dap.threads = threads;
+            dap.stack_frames = stack_frames;
+            dap.frame_index = 0;
+            dap.expanded_frames.clear();
+            dap.variables = variables;
+            self.active_dap = Some(dap_id);
+            // The references to the children are only valid until the
+            // program continues
+            self.watch_children.clear();
+        }
+    }
+
+    pub fn continued(&mut self, dap_id: DapId) {
+        if let Some(dap) = self.daps.get_mut(&dap_id) {
+            dap.stopped = None;
+            dap.stack_frames.clear();
+            dap.variables.clear();
+        }
+    }
+
+    pub fn terminated(&mut self, dap_id: DapId) {
+        self.daps.remove(&dap_id);
+        if self.memory.as_ref().map(|memory| memory.dap_id) == Some(dap_id) {
+            self.memory = None;
+        }
+        if self.active_dap == Some(dap_id) {
+            self.active_dap = self.daps.keys().next().copied();
+        }
+        if self.daps.is_empty() {
+            for breakpoints in self.breakpoints.values_mut() {
+                for breakpoint in breakpoints.values_mut() {
+                    breakpoint.verified = false;
+                }
+            }
+            for watch in self.watches.iter_mut() {
+                watch.value = None;
+            }
+            self.watch_children.clear();
+        }
+    }
+
+    /// Make the thread of the stopped program the one its stack is shown
+    /// for and the commands step, and tell if it wasn't already
+    pub fn select_thread(&mut self, dap_id: DapId, thread_id: ThreadId) -> bool {
+        let dap = match self.daps.get_mut(&dap_id) {
+            Some(dap) if dap.stopped.is_some() => dap,
+            _ => return false,
+        };
+        if dap.thread_id == Some(thread_id) {
+            return false;
+        }
+        dap.thread_id = Some(thread_id);
+        dap.stack_frames.clear();
+        dap.variables.clear();
+        true
+    }
+
+    /// Show the stack of the thread, if it's still the chosen one
+    pub fn set_thread_stack(
+        &mut self,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        stack_frames: Vec<StackFrame>,
+    ) -> bool {
+        match self.daps.get_mut(&dap_id) {
+            Some(dap) if dap.thread_id == Some(thread_id) => {
+                dap.stack_frames = stack_frames;
+                dap.frame_index = 0;
+                dap.expanded_frames.clear();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Make the frame at the index the one the variables and the watch
+    /// expressions are for, and give its id for its variables to be got
+    pub fn select_frame(&mut self, dap_id: DapId, index: usize) -> Option<usize> {
+        let dap = self.daps.get_mut(&dap_id)?;
+        let frame_id = dap.stack_frames.get(index)?.id;
+        dap.frame_index = index;
+        dap.variables.clear();
+        self.watch_children.clear();
+        Some(frame_id)
+    }
+
+    /// Show the variables of the frame, if it's still the chosen one
+    pub fn set_frame_variables(
+        &mut self,
+        dap_id: DapId,
+        frame_id: usize,
+        variables: Vec<(Scope, Vec<Variable>)>,
+    ) {
+        if let Some(dap) = self.daps.get_mut(&dap_id) {
+            if dap.frame().map(|frame| frame.id) == Some(frame_id) {
+                dap.variables = variables;
+            }
+        }
+    }
+
+    /// Expand or collapse the run of subtle frames of the active program
+    pub fn toggle_frames_expanded(&mut self, start: usize) {
+        if let Some(dap) = self
+            .active_dap
+            .and_then(|dap_id| self.daps.get_mut(&dap_id))
+        {
+            if !dap.expanded_frames.remove(&start) {
+                dap.expanded_frames.insert(start);
+            }
+        }
+    }
+
+    /// The file and the line, starting at 0, of the frame the active
+    /// program is stopped in
+    pub fn stopped_location(&self) -> Option<(&Path, usize)> {
+        let dap = self.active()?;
+        let frame = dap.frame()?;
+        let path = frame.source.as_ref()?.path.as_deref()?;
+        Some((path, frame.line.saturating_sub(1)))
+    }
+
+    /// The local variables of the frame the active program is stopped in,
+    /// which are the ones of the first scope if none is marked as locals
+    pub fn stopped_locals(&self) -> Vec<&Variable> {
+        let dap = match self.active() {
+            Some(dap) if dap.stopped.is_some() => dap,
+            _ => return Vec::new(),
+        };
+        let is_locals = |scope: &Scope| {
+            scope.presentation_hint.as_deref() == Some("locals")
+                || scope.name.to_lowercase().starts_with("local")
+        };
+        let variables = dap
+            .variables
+            .iter()
+            .filter(|(scope, _)| is_locals(scope))
+            .flat_map(|(_, variables)| variables.iter())
+            .collect::<Vec<_>>();
+        if variables.is_empty() {
+            dap.variables
+                .first()
+                .map(|(_, variables)| variables.iter().collect())
+                .unwrap_or_default()
+        } else {
+            variables
+        }
+    }
+}
+
+impl DebugData {
+    /// Add the data breakpoint to the program, unless it has one for the
+    /// data, and give the breakpoints the program then has
+    pub fn add_data_breakpoint(
+        &mut self,
+        dap_id: DapId,
+        breakpoint: LapceDataBreakpoint,
+    ) -> Option<Vec<DataBreakpoint>> {
+        let dap = self.daps.get_mut(&dap_id)?;
+        if dap
+            .data_breakpoints
+            .iter()
+            .any(|b| b.breakpoint.data_id == breakpoint.breakpoint.data_id)
+        {
+            return None;
+        }
+        dap.data_breakpoints.push(breakpoint);
+        Some(Self::dap_data_breakpoints(dap))
+    }
+
+    /// Remove the data breakpoint at the index from the program, and give
+    /// the breakpoints the program then has
+    pub fn remove_data_breakpoint(
+        &mut self,
+        dap_id: DapId,
+        index: usize,
+    ) -> Option<Vec<DataBreakpoint>> {
+        let dap = self.daps.get_mut(&dap_id)?;
+        if index >= dap.data_breakpoints.len() {
+            return None;
+        }
+        dap.data_breakpoints.remove(index);
+        Some(Self::dap_data_breakpoints(dap))
+    }
+
+    fn dap_data_breakpoints(dap: &DapData) -> Vec<DataBreakpoint> {
+        dap.data_breakpoints
+            .iter()
+            .map(|b| b.breakpoint.clone())
+            .collect()
+    }
+
+    /// Show the memory that was read, if it's still the one the memory
+    /// section is for
+    pub fn set_memory(
+        &mut self,
+        dap_id: DapId,
+        memory_reference: &str,
+        offset: i64,
+        result: Result<ReadMemoryResult, String>,
+    ) {
+        if let Some(memory) = self.memory.as_mut() {
+            if memory.dap_id == dap_id
+                && memory.memory_reference == memory_reference
+                && memory.offset == offset
+            {
+                memory.result = Some(result);
+            }
+        }
+    }
+}
+
+/// The lines of the memory section for the bytes read from the address, as
+/// the address, the bytes in hex and the bytes that are printable ASCII
+pub fn memory_lines(address: u64, data: &[u8]) -> Vec<String> {
+    data.chunks(MEMORY_LINE_BYTES)
+        .enumerate()
+        .map(|(i, bytes)| {
+            let address = address.wrapping_add((i * MEMORY_LINE_BYTES) as u64);
+            let hex = bytes
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii = bytes
+                .iter()
+                .map(|byte| {
+                    if byte.is_ascii_graphic() || *byte == b' ' {
+                        *byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            format!(
+                "{address:016x}  {hex:width$}  {ascii}",
+                width = MEMORY_LINE_BYTES * 3 - 1
+            )
+        })
+        .collect()
+}
+
+/// The values of the variables that appear in the text of a line, as
+/// `name = value` in the order they appear, with long values shortened
+pub fn line_inline_values(variables: &[&Variable], text: &str) -> Option<String> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut found: Vec<(usize, &Variable)> = Vec::new();
+    for variable in variables {
+        if variable.name.is_empty()
+            || found.iter().any(|(_, v)| v.name == variable.name)
+        {
+            continue;
+        }
+        let position = text.match_indices(variable.name.as_str()).find(|(i, _)| {
+            let before = text[..*i].chars().next_back();
+            let after = text[i + variable.name.len()..].chars().next();
+            !before.map(is_ident).unwrap_or(false)
+                && !after.map(is_ident).unwrap_or(false)
+        });
+        if let Some((i, _)) = position {
+            found.push((i, variable));
+        }
+    }
+    if found.is_empty() {
+        return None;
+    }
+    found.sort_by_key(|(i, _)| *i);
+    let values = found
+        .iter()
+        .map(|(_, variable)| {
+            let value = variable.value.lines().next().unwrap_or("");
+            let value = if value.chars().count() > INLINE_VALUE_MAX_CHARS {
+                let value: String =
+                    value.chars().take(INLINE_VALUE_MAX_CHARS).collect();
+                format!("{value}…")
+            } else {
+                value.to_string()
+            };
+            format!("{} = {value}", variable.name)
+        })
+        .collect::<Vec<_>>();
+    Some(values.join(", "))
+}
+
+impl DebugData {
+    /// Change the watch expression at the index, or add one without an
+    /// index, and give the index of the expression to evaluate. An empty
+    /// expression removes the one at the index.
+    pub fn set_watch(
+        &mut self,
+        index: Option<usize>,
+        expression: &str,
+    ) -> Option<usize> {
+        let expression = expression.trim();
+        match index {
+            Some(index) if index < self.watches.len() => {
+                if expression.is_empty() {
+                    self.remove_watch(index);
+                    return None;
+                }
+                self.watches[index] = WatchExpression {
+                    expression: expression.to_string(),
+                    value: None,
+                };
+                Some(index)
+            }
+            Some(_) => None,
+            None => {
+                if expression.is_empty() {
+                    return None;
+                }
+                self.watches.push(WatchExpression {
+                    expression: expression.to_string(),
+                    value: None,
+                });
+                Some(self.watches.len() - 1)
+            }
+        }
+    }
+
+    pub fn remove_watch(&mut self, index: usize) {
+        if index < self.watches.len() {
+            let watch = self.watches.remove(index);
+            if !self
+                .watches
+                .iter()
+                .any(|w| w.expression == watch.expression)
+            {
+                self.expanded_watches
+                    .retain(|path| path[0] != watch.expression);
+            }
+        }
+    }
+
+    pub fn set_watch_value(
+        &mut self,
+        expression: &str,
+        value: Result<EvaluateResult, String>,
+    ) {
+        for watch in self.watches.iter_mut() {
+            if watch.expression == expression {
+                watch.value = Some(value.clone());
+            }
+        }
+    }
+
+    /// Expand the path, or collapse it with its children, and tell if it's
+    /// now expanded
+    pub fn toggle_watch_expanded(&mut self, path: &[String]) -> bool {
+        if self.expanded_watches.remove(path) {
+            self.watch_children.retain(|p, _| !p.starts_with(path));
+            false
+        } else {
+            self.expanded_watches.insert(path.to_vec());
+            true
+        }
+    }
+
+    /// The expressions, each followed by its children when it's expanded
+    pub fn watch_rows(&self) -> Vec<WatchRow> {
+        let mut rows = Vec::new();
+        for (index, watch) in self.watches.iter().enumerate() {
+            let path = vec![watch.expression.clone()];
+            let variables_reference = match watch.value.as_ref() {
+                Some(Ok(result)) => result.variables_reference,
+                _ => 0,
+            };
+            let expanded =
+                variables_reference > 0 && self.expanded_watches.contains(&path);
+            rows.push(WatchRow {
+                path: path.clone(),
+                index: Some(index),
+                value: watch.value.as_ref().map(|value| {
+                    value
+                        .as_ref()
+                        .map(|result| result.result.clone())
+                        .map_err(|e| e.clone())
+                }),
+                variables_reference,
+                expanded,
+            });
+            if expanded {
+                self.push_watch_children(&mut rows, &path);
+            }
+        }
+        rows
+    }
+
+    fn push_watch_children(&self, rows: &mut Vec<WatchRow>, path: &[String]) {
+        if let Some(children) = self.watch_children.get(path) {
+            for variable in children {
+                let mut path = path.to_vec();
+                path.push(variable.name.clone());
+                let expanded = variable.variables_reference > 0
+                    && self.expanded_watches.contains(&path);
+                rows.push(WatchRow {
+                    path: path.clone(),
+                    index: None,
+                    value: Some(Ok(variable.value.clone())),
+                    variables_reference: variable.variables_reference,
+                    expanded,
+                });
+                if expanded {
+                    self.push_watch_children(rows, &path);
+                }
+            }
+        }
+    }
+}
+
+impl Default for DebugData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lapce_rpc::dap_types::DebugRequest;
+
+    use super::*;
+
+    #[test]
+    fn test_toggle_breakpoint() {
+        let path = PathBuf::from("/a.rs");
+        let mut debug = DebugData::new();
+        debug.toggle_breakpoint(&path, 4);
+        debug.toggle_breakpoint(&path, 1);
+        assert_eq!(
+            debug.source_breakpoints(&path),
+            vec![
+                SourceBreakpoint {
+                    line: 2,
+                    ..Default::default()
+                },
+                SourceBreakpoint {
+                    line: 5,
+                    ..Default::default()
+                }
+            ]
+        );
+
+        let verified = |verified| Breakpoint {
+            id: None,
+            verified,
+            message: None,
+            line: None,
+        };
+        debug.set_verified(&path, &[verified(false), verified(true)]);
+        assert!(debug.breakpoints[&path][&4].verified);
+        assert!(!debug.breakpoints[&path][&1].verified);
+
+        debug.toggle_breakpoint(&path, 4);
+        debug.toggle_breakpoint(&path, 1);
+        assert!(debug.breakpoints.is_empty());
+        assert!(debug.source_breakpoints(&path).is_empty());
+    }
+
+    #[test]
+    fn test_set_breakpoint_option() {
+        let path = PathBuf::from("/a.rs");
+        let mut debug = DebugData::new();
+        debug.set_breakpoint_option(
+            &path,
+            3,
+            BreakpointOptionKind::Condition,
+            "x > 5",
+        );
+        debug.set_breakpoint_option(
+            &path,
+            3,
+            BreakpointOptionKind::LogMessage,
+            "x is {x}",
+        );
+        let breakpoint = &debug.breakpoints[&path][&3];
+        assert!(breakpoint.is_conditional());
+        assert!(breakpoint.is_logpoint());
+        assert_eq!(
+            debug.source_breakpoints(&path),
+            vec![SourceBreakpoint {
+                line: 4,
+                condition: Some("x > 5".to_string()),
+                hit_condition: None,
+                log_message: Some("x is {x}".to_string()),
+            }]
+        );
+
+        debug.set_breakpoint_option(&path, 3, BreakpointOptionKind::Condition, " ");
+        assert!(!debug.breakpoints[&path][&3].is_conditional());
+    }
+
+    #[test]
+    fn test_watch_rows() {
+        let mut debug = DebugData::new();
+        assert_eq!(debug.set_watch(None, " point "), Some(0));
+        assert_eq!(debug.set_watch(None, "count"), Some(1));
+        assert_eq!(debug.set_watch(None, ""), None);
+
+        debug.set_watch_value(
+            "point",
+            Ok(EvaluateResult {
+                result: "Point".to_string(),
+                ty: None,
+                variables_reference: 7,
+            }),
+        );
+        debug.set_watch_value("count", Err("not found".to_string()));
+        let path = vec!["point".to_string()];
+        assert!(debug.toggle_watch_expanded(&path));
+        debug.watch_children.insert(
+            path.clone(),
+            vec![Variable {
+                name: "x".to_string(),
+                value: "1".to_string(),
+                ty: None,
+                variables_reference: 0,
+                memory_reference: None,
+            }],
+        );
+
+        let rows = debug.watch_rows();
+        assert_eq!(
+            rows.iter()
+                .map(|row| row.path.join("."))
+                .collect::<Vec<_>>(),
+            vec!["point", "point.x", "count"]
+        );
+        assert!(rows[0].expanded);
+        assert_eq!(rows[1].depth(), 1);
+        assert_eq!(rows[1].value, Some(Ok("1".to_string())));
+        assert_eq!(rows[2].value, Some(Err("not found".to_string())));
+
+        assert!(!debug.toggle_watch_expanded(&path));
+        assert!(debug.watch_children.is_empty());
+
+        debug.set_watch(Some(0), "");
+        assert_eq!(debug.watches.len(), 1);
+        assert_eq!(debug.watches[0].expression, "count");
+    }
+
+    #[test]
+    fn test_launch_variables() {
+        let variables = LaunchVariables {
+            workspace: Some(PathBuf::from("/project")),
+            file: Some(PathBuf::from("/project/src/main.rs")),
+            pid: None,
+        };
+        assert_eq!(
+            variables.substitute("${workspace}/target/${fileBasenameNoExtension}"),
+            "/project/target/main"
+        );
+        assert_eq!(variables.substitute("${relativeFile}"), "src/main.rs");
+        assert_eq!(
+            variables.substitute("${unknown} ${file"),
+            "${unknown} ${file"
+        );
+
+        let config: RunDebugConfig = toml_edit::easy::from_str(
+            r#"
+            name = "Current File"
+            adapter = "lldb"
+            program = "${workspace}/bin"
+            args = ["${file}"]
+            cwd = "${fileDirname}"
+            arguments = { sourceMap = ["${workspace}"] }
+            "#,
+        )
+        .unwrap();
+        let config = variables.apply(&config);
+        assert_eq!(config.request, DebugRequest::Launch);
+        assert_eq!(config.program.as_deref(), Some("/project/bin"));
+        assert_eq!(config.args, vec!["/project/src/main.rs"]);
+        assert_eq!(config.cwd, Some(PathBuf::from("/project/src")));
+        assert_eq!(
+            config.arguments,
+            Some(serde_json::json!({ "sourceMap": ["/project"] }))
+        );
+    }
+
+    #[test]
+    fn test_pick_process() {
+        let config: RunDebugConfig = toml_edit::easy::from_str(
+            r#"
+            name = "Attach"
+            request = "attach"
+            adapter = "lldb"
+            arguments = { pid = "${pickProcess}", name = "process ${pickProcess}" }
+            "#,
+        )
+        .unwrap();
+        assert!(picks_process(&config));
+        let variables = LaunchVariables {
+            pid: Some(42),
+            ..Default::default()
+        };
+        let config = variables.apply(&config);
+        assert!(!picks_process(&config));
+        assert_eq!(config.request, DebugRequest::Attach);
+        assert_eq!(
+            config.arguments,
+            Some(serde_json::json!({ "pid": 42, "name": "process 42" }))
+        );
+    }
+
+    #[test]
+    fn test_line_inline_values() {
+        let variable = |name: &str, value: &str| Variable {
+            name: name.to_string(),
+            value: value.to_string(),
+            ty: None,
+            variables_reference: 0,
+            memory_reference: None,
+        };
+        let x = variable("x", "1");
+        let xs = variable("xs", "[1, 2]");
+        let long = variable("long", &"a".repeat(60));
+        let variables = vec![&x, &xs, &long];
+
+        assert_eq!(
+            line_inline_values(&variables, "let y = xs.len() + x;"),
+            Some("xs = [1, 2], x = 1".to_string())
+        );
+        assert_eq!(line_inline_values(&variables, "let max = 0;"), None);
+        assert_eq!(
+            line_inline_values(&variables, "print(long)"),
+            Some(format!("long = {}…", "a".repeat(50)))
+        );
+    }
+
+    #[test]
+    fn test_sessions() {
+        let configs: LaunchConfigs = toml_edit::easy::from_str(
+            r#"
+            [[configs]]
+            name = "Server"
+            adapter = "lldb"
+            program = "server"
+
+            [[configs]]
+            name = "Client"
+            adapter = "lldb"
+            program = "client"
+
+            [[compounds]]
+            name = "All"
+            configs = ["Server", "Client"]
+
+            [[compounds]]
+            name = "Broken"
+            configs = ["Server", "Missing"]
+            "#,
+        )
+        .unwrap();
+        let all = configs.compound_configs(&configs.compounds[0]).unwrap();
+        assert_eq!(all[0].name, "Server");
+        assert_eq!(all[1].name, "Client");
+        assert!(configs.compound_configs(&configs.compounds[1]).is_err());
+
+        let mut debug = DebugData::new();
+        debug.start(DapId(2), all[1].clone());
+        debug.start(DapId(1), all[0].clone());
+        assert_eq!(debug.active_dap, Some(DapId(1)));
+        assert_eq!(
+            debug
+                .sessions()
+                .iter()
+                .map(|dap| dap.config.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Server", "Client"]
+        );
+
+        debug.set_watch(None, "x");
+        debug.set_watch_value("x", Err("not found".to_string()));
+        assert!(debug.set_active(DapId(2)));
+        assert!(!debug.set_active(DapId(2)));
+        assert!(!debug.set_active(DapId(3)));
+        assert_eq!(debug.watches[0].value, None);
+
+        debug.terminated(DapId(2));
+        assert_eq!(debug.active_dap, Some(DapId(1)));
+    }
+
+    #[test]
+    fn test_call_stack_rows() {
+        let config: RunDebugConfig = toml_edit::easy::from_str(
+            r#"
+            name = "Server"
+            adapter = "lldb"
+            "#,
+        )
+        .unwrap();
+        let frame = |id: usize, hint: Option<&str>| StackFrame {
+            id,
+            name: format!("frame {id}"),
+            source: None,
+            line: 1,
+            column: 1,
+            presentation_hint: hint.map(|hint| hint.to_string()),
+        };
+        let mut debug = DebugData::new();
+        debug.start(DapId(1), config);
+        debug.stopped(
+            DapId(1),
+            Stopped {
+                reason: "breakpoint".to_string(),
+                description: None,
+                thread_id: Some(1),
+                all_threads_stopped: true,
+                text: None,
+            },
+            Vec::new(),
+            vec![
+                frame(10, None),
+                frame(11, Some("subtle")),
+                frame(12, Some("subtle")),
+                frame(13, Some("label")),
+                frame(14, Some("subtle")),
+                frame(15, None),
+            ],
+            Vec::new(),
+        );
+        let rows = |debug: &DebugData| debug.active().unwrap().call_stack_rows();
+        assert_eq!(
+            rows(&debug),
+            vec![
+                CallStackRow::Frame(0),
+                CallStackRow::Subtle { start: 1, count: 2 },
+                CallStackRow::Label(3),
+                CallStackRow::Frame(4),
+                CallStackRow::Frame(5),
+            ]
+        );
+
+        debug.toggle_frames_expanded(1);
+        assert_eq!(
+            rows(&debug)[1..3],
+            [CallStackRow::Frame(1), CallStackRow::Frame(2)]
+        );
+        debug.toggle_frames_expanded(1);
+        assert_eq!(debug.select_frame(DapId(1), 2), Some(12));
+        assert_eq!(rows(&debug)[2], CallStackRow::Frame(2));
+        assert_eq!(debug.select_frame(DapId(1), 6), None);
+
+        assert!(!debug.select_thread(DapId(1), 1));
+        assert!(debug.select_thread(DapId(1), 2));
+        assert!(!debug.set_thread_stack(DapId(1), 1, vec![frame(20, None)]));
+        assert!(debug.set_thread_stack(DapId(1), 2, vec![frame(30, None)]));
+        assert_eq!(debug.active().unwrap().frame().map(|f| f.id), Some(30));
+    }
+
+    #[test]
+    fn test_data_breakpoints() {
+        let config: RunDebugConfig = toml_edit::easy::from_str(
+            r#"
+            name = "Server"
+            adapter = "lldb"
+            "#,
+        )
+        .unwrap();
+        let mut debug = DebugData::new();
+        debug.start(DapId(1), config);
+        let breakpoint = |data_id: &str| LapceDataBreakpoint {
+            description: data_id.to_string(),
+            breakpoint: DataBreakpoint {
+                data_id: data_id.to_string(),
+                access_type: None,
+            },
+        };
+        assert_eq!(
+            debug
+                .add_data_breakpoint(DapId(1), breakpoint("a"))
+                .map(|b| b.len()),
+            Some(1)
+        );
+        assert_eq!(debug.add_data_breakpoint(DapId(1), breakpoint("a")), None);
+        assert_eq!(debug.add_data_breakpoint(DapId(2), breakpoint("b")), None);
+        assert_eq!(
+            debug
+                .add_data_breakpoint(DapId(1), breakpoint("b"))
+                .map(|b| b.len()),
+            Some(2)
+        );
+        assert_eq!(
+            debug.remove_data_breakpoint(DapId(1), 0),
+            Some(vec![breakpoint("b").breakpoint])
+        );
+        assert_eq!(debug.remove_data_breakpoint(DapId(1), 1), None);
+    }
+
+    #[test]
+    fn test_memory_lines() {
+        let data = (0x40..0x54).collect::<Vec<u8>>();
+        let lines = memory_lines(0x1000, &data);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "0000000000001000  40 41 42 43 44 45 46 47 48 49 4a 4b 4c 4d 4e 4f  \
+             @ABCDEFGHIJKLMNO"
+        );
+        assert_eq!(
+            lines[1],
+            format!("0000000000001010  50 51 52 53{}  PQRS", " ".repeat(36))
+        );
+        assert_eq!(
+            memory_lines(0, &[0, b'a', 0x7f])[0],
+            format!("0000000000000000  00 61 7f{}  .a.", " ".repeat(39))
+        );
+    }
+}
@@ -0,0 +1,485 @@
+use std::ops::Range;
+
+use druid::WidgetId;
+use lapce_rpc::dap_types::CompletionTarget;
+
+use crate::config::LapceTheme;
+
+/// The number of lines kept, after which the oldest lines are dropped
+const MAX_LINES: usize = 10_000;
+
+/// The colors of the ANSI escapes, which are drawn with the terminal colors
+/// of the theme
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnsiColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl AnsiColor {
+    fn from_index(index: u32, bright: bool) -> Option<Self> {
+        let color = match (index, bright) {
+            (0, false) => AnsiColor::Black,
+            (1, false) => AnsiColor::Red,
+            (2, false) => AnsiColor::Green,
+            (3, false) => AnsiColor::Yellow,
+            (4, false) => AnsiColor::Blue,
+            (5, false) => AnsiColor::Magenta,
+            (6, false) => AnsiColor::Cyan,
+            (7, false) => AnsiColor::White,
+            (0, true) => AnsiColor::BrightBlack,
+            (1, true) => AnsiColor::BrightRed,
+            (2, true) => AnsiColor::BrightGreen,
+            (3, true) => AnsiColor::BrightYellow,
+            (4, true) => AnsiColor::BrightBlue,
+            (5, true) => AnsiColor::BrightMagenta,
+            (6, true) => AnsiColor::BrightCyan,
+            (7, true) => AnsiColor::BrightWhite,
+            _ => return None,
+        };
+        Some(color)
+    }
+
+    pub fn theme_key(&self) -> &'static str {
+        match self {
+            AnsiColor::Black => LapceTheme::TERMINAL_BLACK,
+            AnsiColor::Red => LapceTheme::TERMINAL_RED,
+            AnsiColor::Green => LapceTheme::TERMINAL_GREEN,
+            AnsiColor::Yellow => LapceTheme::TERMINAL_YELLOW,
+            AnsiColor::Blue => LapceTheme::TERMINAL_BLUE,
+            AnsiColor::Magenta => LapceTheme::TERMINAL_MAGENTA,
+            AnsiColor::Cyan => LapceTheme::TERMINAL_CYAN,
+            AnsiColor::White => LapceTheme::TERMINAL_WHITE,
+            AnsiColor::BrightBlack => LapceTheme::TERMINAL_BRIGHT_BLACK,
+            AnsiColor::BrightRed => LapceTheme::TERMINAL_BRIGHT_RED,
+            AnsiColor::BrightGreen => LapceTheme::TERMINAL_BRIGHT_GREEN,
+            AnsiColor::BrightYellow => LapceTheme::TERMINAL_BRIGHT_YELLOW,
+            AnsiColor::BrightBlue => LapceTheme::TERMINAL_BRIGHT_BLUE,
+            AnsiColor::BrightMagenta => LapceTheme::TERMINAL_BRIGHT_MAGENTA,
+            AnsiColor::BrightCyan => LapceTheme::TERMINAL_BRIGHT_CYAN,
+            AnsiColor::BrightWhite => LapceTheme::TERMINAL_BRIGHT_WHITE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsoleLineKind {
+    /// What the program printed to stdout
+    Output,
+    /// What the program printed to stderr
+    Stderr,
+    /// What the adapter printed, like the messages of its own
+    Info,
+    /// An expression that was typed in
+    Input,
+    /// The value of an expression that was typed in
+    Result,
+    /// Why an expression couldn't be evaluated
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsoleLine {
+    pub kind: ConsoleLineKind,
+    /// The text without the ANSI escapes
+    pub text: String,
+    /// The colored ranges of the text
+    pub colors: Vec<(Range<usize>, AnsiColor)>,
+}
+
+#[derive(Clone)]
+pub struct DebugConsoleData {
+    pub widget_id: WidgetId,
+    pub split_id: WidgetId,
+    pub content_id: WidgetId,
+    /// The editor the expressions are typed in
+    pub input_view_id: WidgetId,
+    pub lines: im::Vector<ConsoleLine>,
+    /// Whether the last line of the output hasn't ended yet, for the next
+    /// output to continue it
+    partial: bool,
+    /// The color the ANSI escapes of the output left set
+    color: Option<AnsiColor>,
+    /// The expressions that were typed in, the latest last
+    history: Vec<String>,
+    /// The expression of the history that is in the input
+    history_index: Option<usize>,
+}
+
+impl DebugConsoleData {
+    pub fn new() -> Self {
+        Self {
+            widget_id: WidgetId::next(),
+            split_id: WidgetId::next(),
+            content_id: WidgetId::next(),
+            input_view_id: WidgetId::next(),
+            lines: im::Vector::new(),
+            partial: false,
+            color: None,
+            history: Vec::new(),
+            history_index: None,
+        }
+    }
+
+    /// Add what the program or the adapter printed, by the category of the
+    /// `output` event
+    pub fn push_output(&mut self, category: Option<&str>, output: &str) {
+        if output.is_empty() {
+            return;
+        }
+        let kind = match category {
+            Some("stderr") => ConsoleLineKind::Stderr,
+            Some("console") | Some("important") => ConsoleLineKind::Info,
+            _ => ConsoleLineKind::Output,
+        };
+        let (text, colors) = parse_ansi(output, &mut self.color);
+        let ended = text.ends_with('\n');
+        let text = text.strip_suffix('\n').unwrap_or(&text);
+
+        let mut start = 0;
+        for (i, line) in text.split('\n').enumerate() {
+            let end = start + line.len();
+            let line_colors = colors
+                .iter()
+                .filter(|(range, _)| range.start < end && range.end > start)
+                .map(|(range, color)| {
+                    let range =
+                        range.start.max(start) - start..range.end.min(end) - start;
+                    (range, *color)
+                })
+                .collect::<Vec<_>>();
+            let continues = i == 0
+                && self.partial
+                && self.lines.back().map(|last| last.kind) == Some(kind);
+            if continues {
+                let last = self.lines.back_mut().unwrap();
+                let offset = last.text.len();
+                last.text.push_str(line);
+                last.colors
+                    .extend(line_colors.into_iter().map(|(range, color)| {
+                        (range.start + offset..range.end + offset, color)
+                    }));
+            } else {
+                self.push(ConsoleLine {
+                    kind,
+                    text: line.to_string(),
+                    colors: line_colors,
+                });
+            }
+            start = end + 1;
+        }
+        self.partial = !ended;
+    }
+
+    /// Add a line that isn't output, like a typed expression or its value
+    pub fn push_line(&mut self, kind: ConsoleLineKind, text: &str) {
+        self.partial = false;
+        for line in text.lines() {
+            self.push(ConsoleLine {
+                kind,
+                text: line.to_string(),
+                colors: Vec::new(),
+            });
+        }
+    }
+
+    fn push(&mut self, line: ConsoleLine) {
+        self.lines.push_back(line);
+        if self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.partial = false;
+        self.color = None;
+    }
+
+    /// Remember the typed expression, for the history to go back to it
+    pub fn add_history(&mut self, expression: &str) {
+        if self.history.last().map(|e| e.as_str()) != Some(expression) {
+            self.history.push(expression.to_string());
+        }
+        self.history_index = None;
+    }
+
+    /// The expression typed before the one in the input
+    pub fn history_previous(&mut self) -> Option<String> {
+        let index = match self.history_index {
+            Some(0) => return None,
+            Some(index) => index - 1,
+            None => self.history.len().checked_sub(1)?,
+        };
+        self.history_index = Some(index);
+        self.history.get(index).cloned()
+    }
+
+    /// The expression typed after the one in the input, or an empty input
+    /// past the latest one
+    pub fn history_next(&mut self) -> Option<String> {
+        let index = self.history_index? + 1;
+        if index >= self.history.len() {
+            self.history_index = None;
+            return Some(String::new());
+        }
+        self.history_index = Some(index);
+        self.history.get(index).cloned()
+    }
+}
+
+impl Default for DebugConsoleData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Take the ANSI escapes out of the text, and give the ranges that they
+/// colored. The color is the one set before the text, and is left as the
+/// text sets it.
+pub fn parse_ansi(
+    text: &str,
+    color: &mut Option<AnsiColor>,
+) -> (String, Vec<(Range<usize>, AnsiColor)>) {
+    let mut plain = String::with_capacity(text.len());
+    let mut colors = Vec::new();
+    // Where the text of the current color started
+    let mut run_start = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                if chars.peek() != Some(&'[') {
+                    continue;
+                }
+                chars.next();
+                let mut params = String::new();
+                let mut command = None;
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        command = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if command != Some('m') {
+                    continue;
+                }
+                let mut new = *color;
+                for code in params.split(';') {
+                    let code = code.parse::<u32>().unwrap_or(0);
+                    new = match code {
+                        0 | 39 => None,
+                        30..=37 => AnsiColor::from_index(code - 30, false),
+                        90..=97 => AnsiColor::from_index(code - 90, true),
+                        _ => new,
+                    };
+                }
+                if new != *color {
+                    if let Some(c) = *color {
+                        if run_start < plain.len() {
+                            colors.push((run_start..plain.len(), c));
+                        }
+                    }
+                    run_start = plain.len();
+                    *color = new;
+                }
+            }
+            '\r' => {}
+            c => plain.push(c),
+        }
+    }
+
+    if let Some(c) = *color {
+        if run_start < plain.len() {
+            colors.push((run_start..plain.len(), c));
+        }
+    }
+    (plain, colors)
+}
+
+/// The text the completions complete the text to, with the cursor after the
+/// completion. With several completions, the text is completed up to where
+/// they differ.
+pub fn complete(
+    text: &str,
+    cursor: usize,
+    targets: &[CompletionTarget],
+) -> Option<(String, usize)> {
+    let mut completed = targets.iter().map(|target| {
+        // The start and the length are in UTF-16 code units, from 1
+        let start = match target.start {
+            Some(start) => utf16_offset(text, start.saturating_sub(1)),
+            None => word_start(text, cursor),
+        };
+        let end = match target.length {
+            Some(length) => {
+                let start = text[..start].encode_utf16().count();
+                utf16_offset(text, start + length)
+            }
+            None => cursor,
+        };
+        let insert = target.text.as_ref().unwrap_or(&target.label);
+        (start.min(cursor), end.max(start), insert.as_str())
+    });
+
+    let (start, end, first) = completed.next()?;
+    let mut insert = first;
+    for (other_start, other_end, other) in completed {
+        if other_start != start || other_end != end {
+            return None;
+        }
+        let common = insert
+            .char_indices()
+            .zip(other.chars())
+            .find(|((_, a), b)| a != b)
+            .map(|((i, _), _)| i)
+            .unwrap_or_else(|| insert.len().min(other.len()));
+        insert = &insert[..common];
+    }
+
+    if targets.len() > 1 && insert.len() <= end - start {
+        return None;
+    }
+
+    let mut new = text[..start].to_string();
+    new.push_str(insert);
+    let cursor = new.len();
+    new.push_str(&text[end.min(text.len())..]);
+    Some((new, cursor))
+}
+
+/// The byte offset of the UTF-16 offset in the text
+fn utf16_offset(text: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in text.char_indices() {
+        if units >= utf16 {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+/// The start of the identifier that ends at the offset
+fn word_start(text: &str, offset: usize) -> usize {
+    text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(offset)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_ansi() {
+        let mut color = None;
+        let (text, colors) =
+            parse_ansi("a \x1b[31mred\x1b[0m b \x1b[1;92mgreen", &mut color);
+        assert_eq!(text, "a red b green");
+        assert_eq!(
+            colors,
+            vec![(2..5, AnsiColor::Red), (8..13, AnsiColor::BrightGreen)]
+        );
+        assert_eq!(color, Some(AnsiColor::BrightGreen));
+
+        let (text, colors) = parse_ansi("still\x1b[m\x1b[2K done\r", &mut color);
+        assert_eq!(text, "still done");
+        assert_eq!(colors, vec![(0..5, AnsiColor::BrightGreen)]);
+        assert_eq!(color, None);
+    }
+
+    #[test]
+    fn test_push_output() {
+        let mut console = DebugConsoleData::new();
+        console.push_output(Some("stdout"), "one\ntw");
+        console.push_output(Some("stdout"), "o\x1b[33m!\x1b[0m\n");
+        console.push_output(Some("stderr"), "error\n");
+        let lines = console
+            .lines
+            .iter()
+            .map(|line| (line.kind, line.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                (ConsoleLineKind::Output, "one"),
+                (ConsoleLineKind::Output, "two!"),
+                (ConsoleLineKind::Stderr, "error"),
+            ]
+        );
+        assert_eq!(console.lines[1].colors, vec![(3..4, AnsiColor::Yellow)]);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut console = DebugConsoleData::new();
+        assert_eq!(console.history_previous(), None);
+        console.add_history("a");
+        console.add_history("b");
+        console.add_history("b");
+        assert_eq!(console.history_previous(), Some("b".to_string()));
+        assert_eq!(console.history_previous(), Some("a".to_string()));
+        assert_eq!(console.history_previous(), None);
+        assert_eq!(console.history_next(), Some("b".to_string()));
+        assert_eq!(console.history_next(), Some("".to_string()));
+        assert_eq!(console.history_next(), None);
+    }
+
+    #[test]
+    fn test_complete() {
+        let target = |label: &str, start: Option<usize>, length: Option<usize>| {
+            CompletionTarget {
+                label: label.to_string(),
+                text: None,
+                start,
+                length,
+            }
+        };
+        assert_eq!(
+            complete("x.fo", 4, &[target("foo", None, None)]),
+            Some(("x.foo".to_string(), 5))
+        );
+        assert_eq!(
+            complete("x.fo + 1", 4, &[target("foo", Some(3), Some(2))]),
+            Some(("x.foo + 1".to_string(), 5))
+        );
+        assert_eq!(
+            complete(
+                "x.f",
+                3,
+                &[target("format", None, None), target("formula", None, None)]
+            ),
+            Some(("x.form".to_string(), 6))
+        );
+        assert_eq!(
+            complete(
+                "x.f",
+                3,
+                &[target("foo", None, None), target("bar", None, None)]
+            ),
+            None
+        );
+        assert_eq!(complete("x.f", 3, &[]), None);
+    }
+}
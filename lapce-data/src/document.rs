@@ -11,7 +11,7 @@
     piet::{
         PietText, PietTextLayout, Text, TextAttribute, TextLayout, TextLayoutBuilder,
     },
-    Color, ExtEventSink, Point, Size, Target, Vec2, WidgetId,
+    Color, ExtEventSink, FontStyle, Point, Size, Target, Vec2, WidgetId,
 };
 use itertools::Itertools;
 use lapce_core::{
@@ -19,7 +19,13 @@
     command::{EditCommand, MultiSelectionCommand},
     cursor::{ColPosition, Cursor, CursorMode},
     editor::{EditType, Editor},
+    fold::{
+        indentation_folding_ranges, merge_folding_ranges, FoldedLines, FoldingRange,
+        FoldingRangeKind,
+    },
+    indent::IndentStyle,
     language::LapceLanguage,
+    line_ending::LineEnding,
     mode::{Mode, MotionMode},
     movement::{LinePosition, Movement},
     register::{Clipboard, Register, RegisterData},
@@ -27,15 +33,18 @@
     style::line_styles,
     syntax::Syntax,
     word::WordCursor,
+    wrap::{LineWrap, VisualLines},
 };
 use lapce_rpc::{
     buffer::BufferId,
+    plugin::PluginId,
     proxy::ProxyResponse,
     style::{LineStyle, LineStyles, Style},
 };
 use lsp_types::{
-    CodeActionOrCommand, CodeActionResponse, DiagnosticSeverity, InlayHint,
-    InlayHintLabel,
+    CodeActionOrCommand, CodeActionResponse, Command as LspCommand,
+    DiagnosticSeverity, DocumentHighlightKind, InlayHint, InlayHintKind,
+    InlayHintLabel, Url,
 };
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
@@ -50,11 +59,13 @@
     config::{Config, LapceTheme},
     data::{EditorDiagnostic, EditorView},
     editor::{EditorLocation, EditorPosition},
+    editorconfig::EditorConfigProperties,
     find::{Find, FindProgress},
     history::DocumentHistory,
     proxy::LapceProxy,
     selection_range::SyntaxSelectionRanges,
     settings::SettingsValueKind,
+    undo_history,
 };
 
 pub struct SystemClipboard {}
@@ -88,6 +99,74 @@ pub struct TextLayoutLine {
     pub whitespace: Option<PietTextLayout>,
 }
 
+/// A soft wrapped line of the normal view, used to map between the x
+/// positions of its text layout and where they're drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WrappedLine {
+    pub wrap: LineWrap,
+    /// The width of the line in columns
+    pub width: usize,
+    pub char_width: f64,
+}
+
+impl WrappedLine {
+    pub fn num_segments(&self) -> usize {
+        self.wrap.num_segments(self.width)
+    }
+
+    /// The visual line, within the wrapped line, that shows the text layout
+    /// position `x`
+    pub fn segment_of_x(&self, x: f64) -> usize {
+        let col = (x.max(0.0) / self.char_width).round() as usize;
+        self.wrap.segment_of_col(col, self.width)
+    }
+
+    /// How far the text layout is shifted when drawn on the visual line
+    /// `segment`
+    pub fn x_shift(&self, segment: usize) -> f64 {
+        if segment == 0 {
+            0.0
+        } else {
+            (self.wrap.indent as f64 - self.wrap.segment_start(segment) as f64)
+                * self.char_width
+        }
+    }
+
+    /// The range of text layout x positions shown on the visual line
+    /// `segment`, which is unbounded on the right for the last one
+    pub fn x_range(&self, segment: usize) -> (f64, f64) {
+        let start = self.wrap.segment_start(segment) as f64 * self.char_width;
+        let end = if segment + 1 < self.num_segments() {
+            self.wrap.segment_start(segment + 1) as f64 * self.char_width
+        } else {
+            f64::INFINITY
+        };
+        (start, end)
+    }
+}
+
+/// What a large file goes back to once its last chunk is loaded
+#[derive(Clone)]
+struct LoadingChunks {
+    /// Whether the document was read-only before, since it's read-only
+    /// while it's loading
+    read_only: bool,
+    /// The unsaved content of the file from the last session, which is
+    /// loaded over the file once all of it is there
+    unsaved: Option<Rope>,
+}
+
+/// The visual lines of a document, with the soft wrap column and the code
+/// lenses they were computed for
+struct VisualLinesCache {
+    rev: u64,
+    config_id: u64,
+    column: Option<usize>,
+    folded_lines: Arc<FoldedLines>,
+    code_lenses: Option<Arc<Spans<Vec<LspCommand>>>>,
+    lines: Arc<VisualLines>,
+}
+
 #[derive(Clone, Default)]
 pub struct TextLayoutCache {
     config_id: u64,
@@ -121,12 +200,17 @@ pub enum LocalBufferKind {
     Empty,
     Palette,
     Search,
+    SearchReplace,
     SourceControl,
     FilePicker,
     Keymap,
     Settings,
     PathName,
     Rename,
+    ProblemFilter,
+    DebugConsole,
+    TerminalFind,
+    FindReplace,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -155,13 +239,18 @@ pub fn is_special(&self) -> bool {
             BufferContent::File(_) => false,
             BufferContent::Local(local) => match local {
                 LocalBufferKind::Search
+                | LocalBufferKind::SearchReplace
                 | LocalBufferKind::Palette
                 | LocalBufferKind::SourceControl
                 | LocalBufferKind::FilePicker
                 | LocalBufferKind::Settings
                 | LocalBufferKind::Keymap
                 | LocalBufferKind::PathName
-                | LocalBufferKind::Rename => true,
+                | LocalBufferKind::Rename
+                | LocalBufferKind::ProblemFilter
+                | LocalBufferKind::DebugConsole
+                | LocalBufferKind::TerminalFind
+                | LocalBufferKind::FindReplace => true,
                 LocalBufferKind::Empty => false,
             },
             BufferContent::SettingsValue(..) => true,
@@ -174,12 +263,17 @@ pub fn is_input(&self) -> bool {
             BufferContent::File(_) => false,
             BufferContent::Local(local) => match local {
                 LocalBufferKind::Search
+                | LocalBufferKind::SearchReplace
                 | LocalBufferKind::Palette
                 | LocalBufferKind::FilePicker
                 | LocalBufferKind::Settings
                 | LocalBufferKind::Keymap
                 | LocalBufferKind::PathName
-                | LocalBufferKind::Rename => true,
+                | LocalBufferKind::Rename
+                | LocalBufferKind::ProblemFilter
+                | LocalBufferKind::DebugConsole
+                | LocalBufferKind::TerminalFind
+                | LocalBufferKind::FindReplace => true,
                 LocalBufferKind::Empty | LocalBufferKind::SourceControl => false,
             },
             BufferContent::SettingsValue(..) => true,
@@ -399,6 +493,16 @@ pub fn end_offset_size_iter(
     }
 }
 
+/// An occurrence of the symbol under the cursor, as highlighted by the
+/// language server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolOccurrence {
+    pub start: usize,
+    pub end: usize,
+    /// Whether the symbol is written to, rather than read
+    pub write: bool,
+}
+
 #[derive(Clone)]
 pub struct Document {
     id: BufferId,
@@ -408,6 +512,7 @@ pub struct Document {
     syntax: Option<Syntax>,
     line_styles: Rc<RefCell<LineStyles>>,
     semantic_styles: Option<Arc<Spans<Style>>>,
+    semantic_line_styles: Rc<RefCell<LineStyles>>,
     pub text_layouts: Rc<RefCell<TextLayoutCache>>,
     pub sticky_headers: Rc<RefCell<HashMap<usize, Option<Vec<usize>>>>>,
     load_started: Rc<RefCell<bool>>,
@@ -417,7 +522,36 @@ pub struct Document {
     pub scroll_offset: Vec2,
     pub code_actions: im::HashMap<usize, CodeActionResponse>,
     pub inlay_hints: Option<Spans<InlayHint>>,
+    /// The commands of the code lenses of the language server, at the start
+    /// of their ranges, with the server that runs them. They are shown on a
+    /// virtual line above their line.
+    code_lenses: Option<(PluginId, Arc<Spans<Vec<LspCommand>>>)>,
+    /// The targets of the links of the language server, like the paths of
+    /// imports
+    document_links: Option<Spans<Url>>,
+    /// The occurrences of the symbol under the cursor, sorted by offset
+    document_highlights: Arc<Vec<SymbolOccurrence>>,
+    /// Folding ranges from the language server, used instead of the ones
+    /// from the syntax tree when the server provides them
+    lsp_folding_ranges: Option<Vec<FoldingRange>>,
+    folding_ranges: Arc<Vec<FoldingRange>>,
+    /// An offset in the first line of each folded range
+    pub folded: Vec<usize>,
+    folded_lines: Arc<FoldedLines>,
+    visual_lines: Rc<RefCell<Option<VisualLinesCache>>>,
     pub diagnostics: Option<Arc<Vec<EditorDiagnostic>>>,
+    /// The `.editorconfig` properties of the file
+    editorconfig: EditorConfigProperties,
+    /// The encoding of the file on disk
+    encoding: String,
+    /// Whether the file is too large for syntax highlighting, the language
+    /// servers and the diff against the repository
+    large_file: bool,
+    /// Set while the rest of a large file is loaded a chunk at a time
+    loading_chunks: Option<LoadingChunks>,
+    /// The content of the file on disk, when it was changed there while the
+    /// document had unsaved changes, until the conflict is resolved
+    disk_content: Option<Rope>,
     pub syntax_selection_range: Option<SyntaxSelectionRanges>,
     pub find: Rc<RefCell<Find>>,
     find_progress: Rc<RefCell<FindProgress>>,
@@ -453,6 +587,7 @@ pub fn new(
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             semantic_styles: None,
+            semantic_line_styles: Rc::new(RefCell::new(HashMap::new())),
             load_started: Rc::new(RefCell::new(false)),
             histories: im::HashMap::new(),
             loaded: false,
@@ -460,7 +595,20 @@ pub fn new(
             scroll_offset: Vec2::ZERO,
             code_actions: im::HashMap::new(),
             inlay_hints: None,
+            code_lenses: None,
+            document_links: None,
+            document_highlights: Arc::new(Vec::new()),
+            lsp_folding_ranges: None,
+            folding_ranges: Arc::new(Vec::new()),
+            folded: Vec::new(),
+            folded_lines: Arc::new(FoldedLines::default()),
+            visual_lines: Rc::new(RefCell::new(None)),
             diagnostics: None,
+            editorconfig: EditorConfigProperties::default(),
+            encoding: "UTF-8".to_string(),
+            large_file: false,
+            loading_chunks: None,
+            disk_content: None,
             find: Rc::new(RefCell::new(Find::new(0))),
             find_progress: Rc::new(RefCell::new(FindProgress::Ready)),
             event_sink,
@@ -496,14 +644,127 @@ pub fn rev(&self) -> u64 {
         self.buffer.rev()
     }
 
-    pub fn init_content(&mut self, content: Rope) {
+    pub fn encoding(&self) -> &str {
+        &self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: String) {
+        self.encoding = encoding;
+    }
+
+    pub fn large_file(&self) -> bool {
+        self.large_file
+    }
+
+    /// Turn off everything that's too slow for a large file, before its
+    /// first chunk is loaded. It's read-only until its last chunk is.
+    pub fn set_large_file(&mut self) {
+        self.large_file = true;
+        self.syntax = None;
+        self.semantic_styles = None;
+        self.histories.clear();
+        self.loading_chunks = Some(LoadingChunks {
+            read_only: self.buffer.is_read_only(),
+            unsaved: None,
+        });
+        self.buffer.set_read_only(true);
+    }
+
+    /// Load the unsaved content of the file from the last session over it,
+    /// once the whole file is loaded
+    pub fn restore_unsaved(&mut self, content: Rope) {
+        match self.loading_chunks.as_mut() {
+            Some(loading) => loading.unsaved = Some(content),
+            None => self.reload(content, false),
+        }
+    }
+
+    /// Add the next chunk of a large file to the end of the document. The
+    /// proxy already has the whole file, so it isn't sent back to it.
+    pub fn append_content(&mut self, content: Rope, last: bool) {
+        if !content.is_empty() {
+            self.buffer.append_content(content);
+            self.on_update(None);
+        }
+        if last {
+            if let Some(loading) = self.loading_chunks.take() {
+                self.buffer.set_read_only(loading.read_only);
+                if let Some(unsaved) = loading.unsaved {
+                    self.reload(unsaved, false);
+                }
+            }
+        }
+    }
+
+    /// Whether the rest of a large file is still being loaded
+    pub fn loading_chunks(&self) -> bool {
+        self.loading_chunks.is_some()
+    }
+
+    /// Load the content of the document, along with the undo history that
+    /// was kept for it by `undo_history::load`
+    pub fn init_content(&mut self, content: Rope, undo_deltas: &[RopeDelta]) {
         self.buffer.init_content(content);
-        self.buffer.detect_indent(self.syntax.as_ref());
+        if let BufferContent::File(path) = &self.content {
+            if !undo_deltas.is_empty() {
+                self.buffer.restore_undo_deltas(undo_deltas);
+            }
+            self.editorconfig = EditorConfigProperties::for_path(path);
+            self.buffer.detect_line_ending();
+            if let Some(line_ending) = self.editorconfig.end_of_line {
+                self.buffer.set_line_ending(line_ending);
+            }
+        }
+        self.detect_indent();
         self.loaded = true;
         self.on_update(None);
     }
 
+    /// Use the indentation of most lines for new lines, unless the
+    /// `.editorconfig` says otherwise
+    fn detect_indent(&mut self) {
+        self.buffer.detect_indent(self.syntax.as_ref());
+        self.buffer.set_indent_style(
+            self.editorconfig.indent_style(self.buffer.indent_style()),
+        );
+    }
+
+    /// Set the indentation of new lines, or detect it from the content again
+    /// when it's `None`
+    pub fn set_indent_style(&mut self, indent_style: Option<IndentStyle>) {
+        match indent_style {
+            Some(indent_style) => self.buffer.set_indent_style(indent_style),
+            None => self.detect_indent(),
+        }
+    }
+
+    /// Set the line ending of new lines
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.buffer.set_line_ending(line_ending);
+    }
+
+    /// The edits that the `.editorconfig` properties require before the
+    /// file is saved
+    pub fn editorconfig_save_edits(&self) -> Vec<(Selection, String)> {
+        self.editorconfig.save_edits(&self.buffer)
+    }
+
+    /// Keep the undo history of the file, which has just been saved, so that
+    /// it can still be undone after reopening it
+    pub fn save_undo_history(&self) {
+        if let BufferContent::File(path) = &self.content {
+            undo_history::save_async(
+                self.proxy.workspace_kind.clone(),
+                path.clone(),
+                self.buffer.clone(),
+            );
+        }
+    }
+
     pub fn set_language(&mut self, language: LapceLanguage) {
+        if self.large_file {
+            return;
+        }
         self.syntax = Some(Syntax::from_language(language));
     }
 
@@ -551,21 +812,49 @@ fn update_diagnostics(&mut self, delta: &RopeDelta) {
     pub fn reload(&mut self, content: Rope, set_pristine: bool) {
         self.code_actions.clear();
         self.inlay_hints = None;
+        self.code_lenses = None;
+        self.document_links = None;
+        self.document_highlights = Arc::new(Vec::new());
+        self.lsp_folding_ranges = None;
         let delta = self.buffer.reload(content, set_pristine);
         self.apply_deltas(&[delta]);
     }
 
-    pub fn handle_file_changed(&mut self, content: Rope) {
+    /// Reload the file that was changed on disk, unless the document has
+    /// unsaved changes, in which case the content on disk is kept as a
+    /// conflict to be resolved. Returns whether it's a conflict.
+    pub fn handle_file_changed(&mut self, content: Rope) -> bool {
         if self.buffer.is_pristine() {
+            self.disk_content = None;
             self.reload(content, true);
+            false
+        } else if self.buffer.text().slice_to_cow(..) == content.slice_to_cow(..) {
+            self.disk_content = None;
+            self.buffer.set_pristine();
+            false
+        } else {
+            self.disk_content = Some(content);
+            true
         }
     }
 
+    pub fn disk_content(&self) -> Option<&Rope> {
+        self.disk_content.as_ref()
+    }
+
+    /// Take the content on disk of a conflict, to resolve it
+    pub fn take_disk_content(&mut self) -> Option<Rope> {
+        self.disk_content.take()
+    }
+
+    /// Load the file from the proxy, as a large file if it's bigger than
+    /// `large_file_size` bytes, unless that's 0
     pub fn retrieve_file<P: EditorPosition + Send + 'static>(
         &mut self,
         locations: Vec<(WidgetId, EditorLocation<P>)>,
         unsaved_buffer: Option<Rope>,
         cb: Option<InitBufferContentCb>,
+        large_file_size: u64,
     ) {
         if self.loaded || *self.load_started.borrow() {
             return;
@@ -578,29 +867,110 @@ pub fn retrieve_file<P: EditorPosition + Send + 'static>(
             let path = path.clone();
             let event_sink = self.event_sink.clone();
             let proxy = self.proxy.clone();
+            let workspace_kind = self.proxy.workspace_kind.clone();
             std::thread::spawn(move || {
-                proxy.proxy_rpc.new_buffer(id, path.clone(), move |result| {
-                    if let Ok(ProxyResponse::NewBufferResponse { content }) = result
-                    {
-                        let _ = event_sink.submit_command(
-                            LAPCE_UI_COMMAND,
-                            P::init_buffer_content_cmd(
-                                path,
-                                Rope::from(content),
-                                locations,
-                                unsaved_buffer,
-                                cb,
-                            ),
-                            Target::Widget(tab_id),
-                        );
-                    };
-                })
+                let chunks_proxy = proxy.clone();
+                proxy.proxy_rpc.new_buffer(
+                    id,
+                    path.clone(),
+                    large_file_size,
+                    move |result| {
+                        if let Ok(ProxyResponse::NewBufferResponse {
+                            content,
+                            encoding,
+                            large_file,
+                            read_only,
+                        }) = result
+                        {
+                            let content = Rope::from(content);
+                            let len = content.len();
+                            let undo_deltas = if large_file {
+                                Vec::new()
+                            } else {
+                                undo_history::load(&workspace_kind, &path, &content)
+                            };
+                            let _ = event_sink.submit_command(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::InitBufferInfo {
+                                    path: path.clone(),
+                                    encoding,
+                                    large_file,
+                                    read_only,
+                                },
+                                Target::Widget(tab_id),
+                            );
+                            let _ = event_sink.submit_command(
+                                LAPCE_UI_COMMAND,
+                                P::init_buffer_content_cmd(
+                                    path.clone(),
+                                    content,
+                                    undo_deltas,
+                                    locations,
+                                    unsaved_buffer,
+                                    cb,
+                                ),
+                                Target::Widget(tab_id),
+                            );
+                            if large_file {
+                                Self::load_chunks(
+                                    chunks_proxy,
+                                    path,
+                                    len,
+                                    event_sink,
+                                    tab_id,
+                                );
+                            }
+                        };
+                    },
+                )
             });
         }
 
         self.retrieve_history("head");
     }
 
+    /// Load the chunks of a large file from `offset` on, one after the
+    /// other, and add each of them to the document
+    fn load_chunks(
+        proxy: Arc<LapceProxy>,
+        path: PathBuf,
+        offset: usize,
+        event_sink: ExtEventSink,
+        tab_id: WidgetId,
+    ) {
+        let chunks_proxy = proxy.clone();
+        proxy
+            .proxy_rpc
+            .buffer_chunk(path.clone(), offset, move |result| {
+                // A failed chunk ends the loading with what was loaded
+                let (content, last) = match result {
+                    Ok(ProxyResponse::BufferChunkResponse { content, last }) => {
+                        (content, last)
+                    }
+                    _ => (String::new(), true),
+                };
+                let len = content.len();
+                let _ = event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::AppendBufferContent {
+                        path: path.clone(),
+                        content: Rope::from(content),
+                        last,
+                    },
+                    Target::Widget(tab_id),
+                );
+                if !last {
+                    Self::load_chunks(
+                        chunks_proxy,
+                        path,
+                        offset + len,
+                        event_sink,
+                        tab_id,
+                    );
+                }
+            });
+    }
+
     pub fn retrieve_history(&mut self, version: &str) {
         if self.histories.contains_key(version) {
             return;
@@ -618,6 +988,9 @@ pub fn reload_history(&self, version: &str) {
     }
 
     pub fn load_history(&mut self, version: &str, content: Rope) {
+        if self.large_file {
+            return;
+        }
         let mut history = DocumentHistory::new(version.to_string());
         history.load_content(content, self);
         self.histories.insert(version.to_string(), history);
@@ -715,9 +1088,12 @@ pub fn history_actual_line_from_visual(
         }
     }
 
-    fn trigger_head_change(&self) {
-        if let Some(head) = self.histories.get("head") {
-            head.trigger_update_change(self);
+    fn trigger_history_change(&self) {
+        if self.large_file {
+            return;
+        }
+        for history in self.histories.values() {
+            history.trigger_update_change(self);
         }
     }
 
@@ -746,7 +1122,7 @@ pub fn update_history_styles(
     }
 
     fn get_semantic_styles(&self) {
-        if !self.loaded() {
+        if !self.loaded() || self.large_file {
             return;
         }
 
@@ -791,7 +1167,7 @@ fn get_semantic_styles(&self) {
     }
 
     pub fn get_inlay_hints(&self) {
-        if !self.loaded() {
+        if !self.loaded() || self.large_file {
             return;
         }
 
@@ -837,15 +1213,209 @@ pub fn get_inlay_hints(&self) {
         }
     }
 
+    pub fn get_code_lenses(&self) {
+        if !self.loaded() || self.large_file {
+            return;
+        }
+
+        if let BufferContent::File(path) = self.content() {
+            let tab_id = self.tab_id;
+            let path = path.clone();
+            let buffer = self.buffer().clone();
+            let rev = buffer.rev();
+            let len = buffer.len();
+            let event_sink = self.event_sink.clone();
+            self.proxy
+                .proxy_rpc
+                .get_code_lens(path.clone(), move |result| {
+                    if let Ok(ProxyResponse::GetCodeLens {
+                        plugin_id,
+                        mut lenses,
+                    }) = result
+                    {
+                        lenses.sort_by(|left, right| {
+                            left.range.start.cmp(&right.range.start)
+                        });
+
+                        // the lenses that couldn't be resolved have nothing to show
+                        let mut lenses_span = SpansBuilder::new(len);
+                        for (offset, commands) in &lenses
+                            .into_iter()
+                            .filter_map(|lens| {
+                                let offset = buffer
+                                    .offset_of_position(&lens.range.start)
+                                    .min(len);
+                                Some((offset, lens.command?))
+                            })
+                            .group_by(|(offset, _)| *offset)
+                        {
+                            lenses_span.add_span(
+                                Interval::new(offset, (offset + 1).min(len)),
+                                commands.map(|(_, command)| command).collect(),
+                            );
+                        }
+                        let lenses = lenses_span.build();
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateCodeLenses {
+                                path,
+                                rev,
+                                plugin_id,
+                                lenses,
+                            },
+                            Target::Widget(tab_id),
+                        );
+                    }
+                });
+        }
+    }
+
+    /// Ask the language server for the occurrences of the symbol at the
+    /// offset
+    pub fn get_document_highlights(&self, offset: usize) {
+        if !self.loaded() || self.large_file {
+            return;
+        }
+
+        if let BufferContent::File(path) = self.content() {
+            let tab_id = self.tab_id;
+            let path = path.clone();
+            let buffer = self.buffer().clone();
+            let rev = buffer.rev();
+            let len = buffer.len();
+            let position = buffer.offset_to_position(offset);
+            let event_sink = self.event_sink.clone();
+            self.proxy.proxy_rpc.get_document_highlights(
+                path.clone(),
+                position,
+                move |result| {
+                    if let Ok(ProxyResponse::GetDocumentHighlights { highlights }) =
+                        result
+                    {
+                        let mut highlights = highlights
+                            .into_iter()
+                            .map(|highlight| SymbolOccurrence {
+                                start: buffer
+                                    .offset_of_position(&highlight.range.start)
+                                    .min(len),
+                                end: buffer
+                                    .offset_of_position(&highlight.range.end)
+                                    .min(len),
+                                write: highlight.kind
+                                    == Some(DocumentHighlightKind::WRITE),
+                            })
+                            .collect::<Vec<_>>();
+                        highlights.sort_by_key(|highlight| highlight.start);
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateDocumentHighlights {
+                                path,
+                                rev,
+                                highlights,
+                            },
+                            Target::Widget(tab_id),
+                        );
+                    }
+                },
+            );
+        }
+    }
+
+    pub fn get_folding_ranges(&self) {
+        if !self.loaded() || self.large_file {
+            return;
+        }
+
+        if let BufferContent::File(path) = self.content() {
+            let tab_id = self.tab_id;
+            let path = path.clone();
+            let rev = self.rev();
+            let event_sink = self.event_sink.clone();
+            self.proxy
+                .proxy_rpc
+                .get_folding_ranges(path.clone(), move |result| {
+                    if let Ok(ProxyResponse::GetFoldingRanges { ranges }) = result {
+                        let ranges = ranges.iter().map(FoldingRange::from).collect();
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateFoldingRanges {
+                                path,
+                                rev,
+                                ranges,
+                            },
+                            Target::Widget(tab_id),
+                        );
+                    }
+                });
+        }
+    }
+
+    pub fn get_document_links(&self) {
+        if !self.loaded() || self.large_file {
+            return;
+        }
+
+        if let BufferContent::File(path) = self.content() {
+            let tab_id = self.tab_id;
+            let path = path.clone();
+            let buffer = self.buffer().clone();
+            let rev = buffer.rev();
+            let len = buffer.len();
+            let event_sink = self.event_sink.clone();
+            self.proxy
+                .proxy_rpc
+                .get_document_links(path.clone(), move |result| {
+                    if let Ok(ProxyResponse::GetDocumentLinks { links }) = result {
+                        // the links without a target would have to be
+                        // resolved first
+                        let mut links = links
+                            .into_iter()
+                            .filter_map(|link| {
+                                let start = buffer
+                                    .offset_of_position(&link.range.start)
+                                    .min(len);
+                                let end = buffer
+                                    .offset_of_position(&link.range.end)
+                                    .min(len);
+                                Some((start, end, link.target?))
+                            })
+                            .filter(|(start, end, _)| start < end)
+                            .collect::<Vec<_>>();
+                        links.sort_by_key(|(start, _, _)| *start);
+
+                        let mut links_span = SpansBuilder::new(len);
+                        let mut last_end = 0;
+                        for (start, end, target) in links {
+                            if start < last_end {
+                                continue;
+                            }
+                            links_span.add_span(Interval::new(start, end), target);
+                            last_end = end;
+                        }
+                        let links = links_span.build();
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateDocumentLinks { path, rev, links },
+                            Target::Widget(tab_id),
+                        );
+                    }
+                });
+        }
+    }
+
     fn on_update(&mut self, deltas: Option<SmallVec<[RopeDelta; 3]>>) {
         self.find.borrow_mut().unset();
         *self.find_progress.borrow_mut() = FindProgress::Started;
         self.get_inlay_hints();
+        self.get_code_lenses();
+        self.get_document_links();
+        self.get_folding_ranges();
         self.get_semantic_styles();
         self.clear_style_cache();
         self.clear_sticky_headers_cache();
         self.trigger_syntax_change(deltas);
-        self.trigger_head_change();
+        self.update_folding_ranges();
+        self.trigger_history_change();
         self.notify_special();
     }
 
@@ -863,6 +1433,13 @@ fn notify_special(&self) {
                             Target::Widget(self.tab_id),
                         );
                     }
+                    LocalBufferKind::SearchReplace => {
+                        let _ = self.event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateSearchReplace(s),
+                            Target::Widget(self.tab_id),
+                        );
+                    }
                     LocalBufferKind::SourceControl => {}
                     LocalBufferKind::Empty => {}
                     LocalBufferKind::Rename => {}
@@ -898,18 +1475,347 @@ fn notify_special(&self) {
                     LocalBufferKind::PathName => {
                         // TODO: anything to update with this?
                     }
+                    LocalBufferKind::ProblemFilter => {
+                        let _ = self.event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateProblemFilter(s),
+                            Target::Widget(self.tab_id),
+                        );
+                    }
+                    LocalBufferKind::DebugConsole => {}
+                    LocalBufferKind::TerminalFind => {
+                        let _ = self.event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateTerminalFind(s),
+                            Target::Widget(self.tab_id),
+                        );
+                    }
+                    LocalBufferKind::FindReplace => {
+                        let _ = self.event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::UpdateFindReplace(s),
+                            Target::Widget(self.tab_id),
+                        );
+                    }
                 }
             }
-            BufferContent::SettingsValue(..) => {}
+            BufferContent::SettingsValue(..) => {}
+        }
+    }
+
+    pub fn set_syntax(&mut self, syntax: Option<Syntax>) {
+        self.syntax = syntax;
+        self.clear_style_cache();
+        self.clear_sticky_headers_cache();
+        self.update_folding_ranges();
+    }
+
+    pub fn set_lsp_folding_ranges(&mut self, ranges: Vec<FoldingRange>) {
+        self.lsp_folding_ranges = if ranges.is_empty() {
+            None
+        } else {
+            Some(ranges)
+        };
+        self.update_folding_ranges();
+    }
+
+    /// The folding ranges from the language server, merged with the ones
+    /// from the syntax tree, or from the indentation when there's no syntax.
+    fn update_folding_ranges(&mut self) {
+        let fallback = match self.syntax.as_ref() {
+            Some(syntax) => syntax.folding_ranges(),
+            None if !self.large_file => indentation_folding_ranges(
+                self.buffer.text().lines(..),
+                self.buffer.indent_unit().len(),
+            ),
+            None => Vec::new(),
+        };
+        let ranges = match self.lsp_folding_ranges.as_ref() {
+            Some(ranges) => merge_folding_ranges(ranges, fallback),
+            None => fallback,
+        };
+        self.folding_ranges = Arc::new(ranges);
+        self.update_folded_lines();
+    }
+
+    fn update_folded_lines(&mut self) {
+        let folded = self
+            .folded
+            .iter()
+            .filter_map(|offset| {
+                self.folding_range_at(self.buffer.line_of_offset(*offset))
+            })
+            .collect::<Vec<_>>();
+        self.folded_lines = Arc::new(FoldedLines::new(folded));
+    }
+
+    fn update_folds(&mut self, delta: &RopeDelta) {
+        let mut transformer = Transformer::new(delta);
+        for offset in self.folded.iter_mut() {
+            *offset = transformer.transform(*offset, false);
+        }
+    }
+
+    pub fn folding_ranges(&self) -> &[FoldingRange] {
+        &self.folding_ranges
+    }
+
+    pub fn folded_lines(&self) -> &FoldedLines {
+        &self.folded_lines
+    }
+
+    /// The largest folding range that starts at `line`
+    pub fn folding_range_at(&self, line: usize) -> Option<FoldingRange> {
+        self.folding_ranges
+            .iter()
+            .filter(|range| range.start_line == line)
+            .max_by_key(|range| range.end_line)
+            .copied()
+    }
+
+    /// Fold the range starting at `line`, or the innermost unfolded range
+    /// that contains it.
+    pub fn fold(&mut self, line: usize) {
+        let range = self
+            .folding_range_at(line)
+            .filter(|range| !self.folded_lines.is_folded(range.start_line))
+            .or_else(|| {
+                self.folding_ranges
+                    .iter()
+                    .filter(|range| {
+                        range.contains(line)
+                            && !self.folded_lines.is_folded(range.start_line)
+                    })
+                    .min_by_key(|range| range.end_line - range.start_line)
+                    .copied()
+            });
+        if let Some(range) = range {
+            self.folded
+                .push(self.buffer.offset_of_line(range.start_line));
+            self.update_folded_lines();
+        }
+    }
+
+    /// Unfold the folded ranges that contain `line`.
+    pub fn unfold(&mut self, line: usize) {
+        let folded = self
+            .folded
+            .iter()
+            .copied()
+            .filter(|offset| {
+                !self
+                    .folding_range_at(self.buffer.line_of_offset(*offset))
+                    .map(|range| range.contains(line))
+                    .unwrap_or(false)
+            })
+            .collect();
+        self.folded = folded;
+        self.update_folded_lines();
+    }
+
+    pub fn toggle_fold(&mut self, line: usize) {
+        if self.folded_lines.is_folded(line) {
+            self.unfold(line);
+        } else {
+            self.fold(line);
+        }
+    }
+
+    pub fn fold_all(&mut self) {
+        self.folded = self
+            .folding_ranges
+            .iter()
+            .map(|range| range.start_line)
+            .dedup()
+            .map(|line| self.buffer.offset_of_line(line))
+            .collect();
+        self.update_folded_lines();
+    }
+
+    /// Fold all the ranges of the given kind, like the comments or imports
+    pub fn fold_kind(&mut self, kind: FoldingRangeKind) {
+        let lines = self
+            .folding_ranges
+            .iter()
+            .filter(|range| range.kind == Some(kind))
+            .map(|range| range.start_line)
+            .dedup()
+            .collect::<Vec<_>>();
+        for line in lines {
+            let offset = self.buffer.offset_of_line(line);
+            if !self.folded.contains(&offset) {
+                self.folded.push(offset);
+            }
+        }
+        self.update_folded_lines();
+    }
+
+    pub fn unfold_all(&mut self) {
+        self.folded.clear();
+        self.update_folded_lines();
+    }
+
+    /// The column the lines are soft wrapped at in the normal view, or `None`
+    /// when wrapping is off for this document
+    fn wrap_column(&self, config: &Config) -> Option<usize> {
+        match &self.content {
+            BufferContent::File(_) | BufferContent::Scratch(..) => {
+                config.wrap_at(self.syntax.as_ref().map(|syntax| syntax.language))
+            }
+            BufferContent::Local(_) | BufferContent::SettingsValue(..) => None,
+        }
+    }
+
+    /// The columns of the rulers drawn in the editors of this document
+    pub fn rulers<'a>(&self, config: &'a Config) -> &'a [usize] {
+        match &self.content {
+            BufferContent::File(_) | BufferContent::Scratch(..) => {
+                config.rulers_at(self.syntax.as_ref().map(|syntax| syntax.language))
+            }
+            BufferContent::Local(_) | BufferContent::SettingsValue(..) => &[],
+        }
+    }
+
+    pub fn is_wrapped(&self, config: &Config) -> bool {
+        self.wrap_column(config).is_some()
+    }
+
+    /// The indentation and the width of `line` in columns, with tabs
+    /// expanded to the next tab stop
+    fn line_columns(&self, line: usize, tab_width: usize) -> (usize, usize) {
+        let tab_width = tab_width.max(1);
+        let mut indent = None;
+        let mut width = 0;
+        for ch in self.buffer.line_content(line).chars() {
+            match ch {
+                '\n' | '\r' => break,
+                '\t' => width += tab_width - width % tab_width,
+                ' ' => width += 1,
+                _ => {
+                    if indent.is_none() {
+                        indent = Some(width);
+                    }
+                    width += 1;
+                }
+            }
+        }
+        (indent.unwrap_or(width), width)
+    }
+
+    /// How `line` is soft wrapped in the normal view, or `None` when
+    /// wrapping is off for this document
+    pub fn wrapped_line(
+        &self,
+        text: &mut PietText,
+        line: usize,
+        config: &Config,
+    ) -> Option<WrappedLine> {
+        let column = self.wrap_column(config)?;
+        let (indent, width) = self.line_columns(line, config.editor.tab_width);
+        Some(WrappedLine {
+            wrap: LineWrap::new(column, indent, config.editor.wrap_indent),
+            width,
+            char_width: config.editor_char_width(text),
+        })
+    }
+
+    /// The visual lines of the normal view, or `None` when every line is
+    /// one visual line because there is no soft wrap and no code lens
+    fn visual_lines(&self, config: &Config) -> Option<Arc<VisualLines>> {
+        let column = self.wrap_column(config);
+        let code_lenses = self.shown_code_lenses(config);
+        if column.is_none() && code_lenses.is_none() {
+            return None;
+        }
+
+        let mut cache = self.visual_lines.borrow_mut();
+        if let Some(cache) = cache.as_ref() {
+            let same_code_lenses = match (&cache.code_lenses, code_lenses) {
+                (None, None) => true,
+                (Some(old), Some(new)) => Arc::ptr_eq(old, new),
+                _ => false,
+            };
+            if cache.rev == self.rev()
+                && cache.config_id == config.id
+                && cache.column == column
+                && Arc::ptr_eq(&cache.folded_lines, &self.folded_lines)
+                && same_code_lenses
+            {
+                return Some(cache.lines.clone());
+            }
         }
+
+        let code_lens_lines: HashSet<usize> = code_lenses
+            .map(|lenses| {
+                lenses
+                    .iter()
+                    .map(|(interval, _)| self.buffer.line_of_offset(interval.start))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let lines = (0..self.buffer.num_lines()).map(|line| {
+            let segments = match column {
+                Some(column) => {
+                    let (indent, width) =
+                        self.line_columns(line, config.editor.tab_width);
+                    LineWrap::new(column, indent, config.editor.wrap_indent)
+                        .num_segments(width)
+                }
+                None => 1,
+            };
+            (usize::from(code_lens_lines.contains(&line)), segments)
+        });
+        let lines =
+            Arc::new(VisualLines::with_virtual_lines(lines, &self.folded_lines));
+        *cache = Some(VisualLinesCache {
+            rev: self.rev(),
+            config_id: config.id,
+            column,
+            folded_lines: self.folded_lines.clone(),
+            code_lenses: code_lenses.cloned(),
+            lines: lines.clone(),
+        });
+        Some(lines)
     }
 
-    pub fn set_syntax(&mut self, syntax: Option<Syntax>) {
-        self.syntax = syntax;
-        if self.semantic_styles.is_none() {
-            self.clear_style_cache();
+    /// The first visual line of `line` in the normal view, where folded
+    /// lines take no room, soft wrapped lines take several and code lenses
+    /// take one above their line
+    pub fn visual_line(&self, line: usize, config: &Config) -> usize {
+        let line = self.folded_lines.visible_line(line);
+        match self.visual_lines(config) {
+            Some(lines) => lines.visual_line(line),
+            None => self.folded_lines.visual_line(line),
         }
-        self.clear_sticky_headers_cache();
+    }
+
+    /// The line shown at `visual_line` in the normal view, and which of its
+    /// soft wrapped visual lines it is
+    pub fn line_of_visual_line(
+        &self,
+        visual_line: usize,
+        config: &Config,
+    ) -> (usize, usize) {
+        match self.visual_lines(config) {
+            Some(lines) => lines.line_of_visual_line(visual_line),
+            None => (self.folded_lines.actual_line(visual_line), 0),
+        }
+    }
+
+    /// The number of visual lines in the normal view
+    pub fn num_visual_lines(&self, config: &Config) -> usize {
+        match self.visual_lines(config) {
+            Some(lines) => lines.num_visual_lines(),
+            None => self.folded_lines.num_visual_lines(self.buffer.num_lines()),
+        }
+    }
+
+    /// If `visual_line` of the normal view is a virtual line, like the one
+    /// of the code lenses above a line, which shows no text of the buffer
+    pub fn is_virtual_line(&self, visual_line: usize, config: &Config) -> bool {
+        self.visual_lines(config)
+            .map(|lines| lines.is_virtual(visual_line))
+            .unwrap_or(false)
     }
 
     fn clear_sticky_headers_cache(&self) {
@@ -923,6 +1829,7 @@ pub fn set_semantic_styles(&mut self, styles: Option<Arc<Spans<Style>>>) {
 
     fn clear_style_cache(&self) {
         self.line_styles.borrow_mut().clear();
+        self.semantic_line_styles.borrow_mut().clear();
         self.clear_text_layout_cache();
     }
 
@@ -949,6 +1856,124 @@ pub fn set_inlay_hints(&mut self, hints: Spans<InlayHint>) {
         self.clear_text_layout_cache();
     }
 
+    pub fn set_code_lenses(
+        &mut self,
+        plugin_id: PluginId,
+        lenses: Spans<Vec<LspCommand>>,
+    ) {
+        self.code_lenses = Some((plugin_id, Arc::new(lenses)));
+    }
+
+    /// If the code lenses are different from the ones of `old`, which moves
+    /// the lines below them in the normal view
+    pub fn code_lenses_changed(&self, old: &Document) -> bool {
+        match (&self.code_lenses, &old.code_lenses) {
+            (None, None) => false,
+            (Some((_, new)), Some((_, old))) => !Arc::ptr_eq(new, old),
+            _ => true,
+        }
+    }
+
+    /// The code lenses shown above their lines in the normal view
+    fn shown_code_lenses(
+        &self,
+        config: &Config,
+    ) -> Option<&Arc<Spans<Vec<LspCommand>>>> {
+        if !config.editor.enable_lsp_code_lens {
+            return None;
+        }
+        match &self.content {
+            BufferContent::File(_) => {
+                self.code_lenses.as_ref().map(|(_, lenses)| lenses)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn set_document_links(&mut self, links: Spans<Url>) {
+        self.document_links = Some(links);
+    }
+
+    /// The link at the offset with the range it covers, which is a link of
+    /// the language server, or else a web address in the text
+    pub fn link_at(&self, offset: usize) -> Option<(usize, usize, Url)> {
+        if let Some(links) = self.document_links.as_ref() {
+            let link =
+                links.iter_chunks(offset..offset + 1).find(|(interval, _)| {
+                    interval.start <= offset && offset < interval.end
+                });
+            if let Some((interval, target)) = link {
+                return Some((interval.start, interval.end, target.clone()));
+            }
+        }
+
+        let line = self.buffer.line_of_offset(offset);
+        let line_start = self.buffer.offset_of_line(line);
+        let line_content = self.buffer.line_content(line);
+        web_addresses(&line_content)
+            .into_iter()
+            .map(|(start, end)| (line_start + start, line_start + end))
+            .find(|(start, end)| *start <= offset && offset < *end)
+            .and_then(|(start, end)| {
+                let url = Url::parse(&self.buffer.slice_to_cow(start..end)).ok()?;
+                Some((start, end, url))
+            })
+    }
+
+    pub fn set_document_highlights(&mut self, highlights: Vec<SymbolOccurrence>) {
+        self.document_highlights = Arc::new(highlights);
+    }
+
+    pub fn document_highlights(&self) -> &[SymbolOccurrence] {
+        &self.document_highlights
+    }
+
+    /// The start of the next occurrence of the symbol after the offset, or
+    /// the previous one before it, wrapping around the document
+    pub fn next_document_highlight(
+        &self,
+        offset: usize,
+        forward: bool,
+    ) -> Option<usize> {
+        let highlights = &self.document_highlights;
+        let next = if forward {
+            highlights
+                .iter()
+                .find(|highlight| highlight.start > offset)
+                .or_else(|| highlights.first())
+        } else {
+            highlights
+                .iter()
+                .rev()
+                .find(|highlight| highlight.end < offset)
+                .or_else(|| highlights.last())
+        };
+        next.map(|highlight| highlight.start)
+    }
+
+    /// The commands of the code lenses on the line, with the language server
+    /// that runs them
+    pub fn line_code_lenses(
+        &self,
+        line: usize,
+    ) -> Option<(PluginId, Vec<&LspCommand>)> {
+        let (plugin_id, lenses) = self.code_lenses.as_ref()?;
+        let start_offset = self.buffer.offset_of_line(line);
+        let end_offset = self.buffer.offset_of_line(line + 1);
+        let commands = lenses
+            .iter_chunks(start_offset..end_offset)
+            .filter(|(interval, _)| {
+                interval.start >= start_offset && interval.start < end_offset
+            })
+            .flat_map(|(_, commands)| commands.iter())
+            .collect::<Vec<_>>();
+        if commands.is_empty() {
+            None
+        } else {
+            Some((*plugin_id, commands))
+        }
+    }
+
     pub fn buffer(&self) -> &Buffer {
         &self.buffer
     }
@@ -964,7 +1989,8 @@ pub fn syntax(&self) -> Option<&Syntax> {
     fn update_styles(&mut self, delta: &RopeDelta) {
         if let Some(styles) = self.semantic_styles.as_mut() {
             Arc::make_mut(styles).apply_shape(delta);
-        } else if let Some(syntax) = self.syntax.as_mut() {
+        }
+        if let Some(syntax) = self.syntax.as_mut() {
             if let Some(styles) = syntax.styles.as_mut() {
                 Arc::make_mut(styles).apply_shape(delta);
             }
@@ -975,9 +2001,42 @@ fn update_styles(&mut self, delta: &RopeDelta) {
         }
     }
 
+    /// Move the inlay hints with the edit, dropping the hints of the edited
+    /// lines, which are out of date until the language server sends new ones
     fn update_inlay_hints(&mut self, delta: &RopeDelta) {
-        if let Some(hints) = self.inlay_hints.as_mut() {
-            hints.apply_shape(delta);
+        let hints = match self.inlay_hints.as_mut() {
+            Some(hints) => hints,
+            None => return,
+        };
+        hints.apply_shape(delta);
+
+        let (iv, new_len) = delta.summary();
+        let start_line = self.buffer.line_of_offset(iv.start);
+        let end_line = self.buffer.line_of_offset(iv.start + new_len);
+        let start = self.buffer.offset_of_line(start_line);
+        let end = self.buffer.offset_of_line(end_line + 1);
+        if hints.iter_chunks(start..end).next().is_none() {
+            return;
+        }
+
+        let mut builder = SpansBuilder::new(hints.len());
+        for (interval, hint) in hints.iter() {
+            if interval.start < start || interval.start >= end {
+                builder.add_span(interval, hint.clone());
+            }
+        }
+        *hints = builder.build();
+    }
+
+    fn update_code_lenses(&mut self, delta: &RopeDelta) {
+        if let Some((_, lenses)) = self.code_lenses.as_mut() {
+            Arc::make_mut(lenses).apply_shape(delta);
+        }
+    }
+
+    fn update_document_links(&mut self, delta: &RopeDelta) {
+        if let Some(links) = self.document_links.as_mut() {
+            links.apply_shape(delta);
         }
     }
 
@@ -988,11 +2047,20 @@ pub fn line_phantom_text(
     ) -> PhantomTextLine {
         let start_offset = self.buffer.offset_of_line(line);
         let end_offset = self.buffer.offset_of_line(line + 1);
-        let hints = if config.editor.enable_inlay_hints {
+        let hints = if config.inlay_hints_visible() {
             self.inlay_hints.as_ref().map(|hints| {
                 hints.iter_chunks(start_offset..end_offset).filter_map(
                     |(interval, inlay_hint)| {
-                        if interval.start >= start_offset
+                        let enabled = if inlay_hint.kind == Some(InlayHintKind::TYPE)
+                        {
+                            config.editor.enable_type_inlay_hints
+                        } else if inlay_hint.kind == Some(InlayHintKind::PARAMETER) {
+                            config.editor.enable_parameter_inlay_hints
+                        } else {
+                            true
+                        };
+                        if enabled
+                            && interval.start >= start_offset
                             && interval.start < end_offset
                         {
                             let (_, col) =
@@ -1033,7 +2101,12 @@ fn apply_deltas(&mut self, deltas: &[(RopeDelta, InvalLines)]) {
         for (i, (delta, _)) in deltas.iter().enumerate() {
             self.update_styles(delta);
             self.update_inlay_hints(delta);
+            self.update_code_lenses(delta);
+            self.update_document_links(delta);
             self.update_diagnostics(delta);
+            // the occurrences are out of date until the cursor rests again
+            self.document_highlights = Arc::new(Vec::new());
+            self.update_folds(delta);
             if let BufferContent::File(path) = &self.content {
                 self.proxy.proxy_rpc.update(
                     path.clone(),
@@ -1076,10 +2149,19 @@ pub fn do_raw_edit(
         edit_type: EditType,
     ) -> (RopeDelta, InvalLines) {
         let (delta, inval_lines) = self.buffer.edit(edits, edit_type);
-        self.apply_deltas(&[(delta.clone(), inval_lines.clone())]);
+        if !delta.is_identity() {
+            self.apply_deltas(&[(delta.clone(), inval_lines.clone())]);
+        }
         (delta, inval_lines)
     }
 
+    /// Undo the last edit without an editor cursor to restore
+    pub fn do_raw_undo(&mut self) -> Option<RopeDelta> {
+        let (delta, inval_lines, _) = self.buffer.do_undo()?;
+        self.apply_deltas(&[(delta.clone(), inval_lines)]);
+        Some(delta)
+    }
+
     pub fn do_edit(
         &mut self,
         cursor: &mut Cursor,
@@ -1104,6 +2186,18 @@ pub fn do_edit(
         deltas
     }
 
+    pub fn do_jump_to_undo_group(
+        &mut self,
+        cursor: &mut Cursor,
+        group: usize,
+        modal: bool,
+    ) -> Vec<(RopeDelta, InvalLines)> {
+        let deltas =
+            Editor::jump_to_undo_group(cursor, &mut self.buffer, group, modal);
+        self.apply_deltas(&deltas);
+        deltas
+    }
+
     pub fn do_multi_selection(
         &self,
         text: &mut PietText,
@@ -1330,7 +2424,152 @@ pub fn do_multi_selection(
                 let new_selection = Selection::region(0, self.buffer.len());
                 cursor.set_insert(new_selection);
             }
+            SelectBlockUp | SelectBlockDown | SelectBlockLeft | SelectBlockRight => {
+                if let CursorMode::Insert(selection) = cursor.mode.clone() {
+                    if let Some((anchor, (line, x))) =
+                        self.block_corners(text, &selection, config)
+                    {
+                        let char_width = config.editor_char_width(text);
+                        let head = match cmd {
+                            SelectBlockUp => (
+                                self.folded_lines
+                                    .visible_line(line.saturating_sub(1)),
+                                x,
+                            ),
+                            SelectBlockDown => {
+                                let last_line = self.buffer.last_line();
+                                let mut line = (line + 1).min(last_line);
+                                while line < last_line
+                                    && self.folded_lines.is_hidden(line)
+                                {
+                                    line += 1;
+                                }
+                                (line, x)
+                            }
+                            SelectBlockLeft => (line, (x - char_width).max(0.0)),
+                            _ => (line, x + char_width),
+                        };
+                        cursor.set_insert(
+                            self.block_selection(text, anchor, head, config),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// The offset of `line` closest to the x position `x`
+    pub fn offset_of_line_x(
+        &self,
+        text: &mut PietText,
+        line: usize,
+        x: f64,
+        config: &Config,
+    ) -> usize {
+        let col = self.line_horiz_col(
+            text,
+            line,
+            config.editor.font_size,
+            &ColPosition::Col(x),
+            true,
+            config,
+        );
+        self.buffer.offset_of_line_col(line, col)
+    }
+
+    /// The line and x position of `point`, as a corner of a block
+    /// selection. The x position can be past the end of the line, so that
+    /// the block can be wider than the line it starts from.
+    pub fn block_corner_of_point(
+        &self,
+        text: &mut PietText,
+        mode: Mode,
+        point: Point,
+        view: &EditorView,
+        config: &Config,
+    ) -> (usize, f64) {
+        let ((line, col), _) =
+            self.line_col_of_point(text, mode, point, view, config);
+        let x = if self.is_wrapped(config) {
+            self.line_point_of_line_col(
+                text,
+                line,
+                col,
+                config.editor.font_size,
+                config,
+            )
+            .x
+        } else {
+            point.x.max(0.0)
+        };
+        (line, x)
+    }
+
+    /// A rectangular selection with a region on every shown line from the
+    /// `anchor` corner to the `head` corner, both given as a line and an x
+    /// position. Lines that end before the block are clipped to their end,
+    /// so that every line of the block still gets a cursor.
+    pub fn block_selection(
+        &self,
+        text: &mut PietText,
+        anchor: (usize, f64),
+        head: (usize, f64),
+        config: &Config,
+    ) -> Selection {
+        let lines: Vec<usize> = if anchor.0 <= head.0 {
+            (anchor.0..head.0 + 1).collect()
+        } else {
+            (head.0..anchor.0 + 1).rev().collect()
+        };
+
+        // The regions are added from the anchor to the head, so that the
+        // head is the last inserted one
+        let mut selection = Selection::new();
+        for line in lines {
+            if self.folded_lines.is_hidden(line) {
+                continue;
+            }
+            let start = self.offset_of_line_x(text, line, anchor.1, config);
+            let end = self.offset_of_line_x(text, line, head.1, config);
+            selection.add_region(SelRegion::new(
+                start,
+                end,
+                Some(ColPosition::Col(head.1)),
+            ));
         }
+        selection
+    }
+
+    /// The anchor and head corners of `selection` seen as a block. The head
+    /// is the last inserted region, and the anchor is the region at the
+    /// other end of the selection.
+    fn block_corners(
+        &self,
+        text: &mut PietText,
+        selection: &Selection,
+        config: &Config,
+    ) -> Option<((usize, f64), (usize, f64))> {
+        let head = selection.last_inserted()?;
+        let anchor = if selection.last() == Some(head) {
+            selection.first()?
+        } else {
+            selection.last()?
+        };
+
+        let font_size = config.editor.font_size;
+        let anchor = (
+            self.buffer.line_of_offset(anchor.start),
+            self.line_point_of_offset(text, anchor.start, font_size, config)
+                .x,
+        );
+        let x = match head.horiz {
+            Some(ColPosition::Col(x)) => x,
+            _ => {
+                self.line_point_of_offset(text, head.end, font_size, config)
+                    .x
+            }
+        };
+        Some((anchor, (self.buffer.line_of_offset(head.end), x)))
     }
 
     pub fn do_motion_mode(
@@ -1364,26 +2603,75 @@ pub fn do_paste(&mut self, cursor: &mut Cursor, data: &RegisterData) {
         self.apply_deltas(&deltas)
     }
 
-    pub fn styles(&self) -> Option<&Arc<Spans<Style>>> {
-        let styles = self
-            .semantic_styles
-            .as_ref()
-            .or_else(|| self.syntax().and_then(|s| s.styles.as_ref()));
-        styles
+    /// The styles of the tree-sitter highlighting
+    pub fn syntax_styles(&self) -> Option<&Arc<Spans<Style>>> {
+        self.syntax().and_then(|s| s.styles.as_ref())
+    }
+
+    /// The styles of the semantic tokens of the language server
+    pub fn semantic_styles(&self) -> Option<&Arc<Spans<Style>>> {
+        self.semantic_styles.as_ref()
     }
 
     fn line_style(&self, line: usize) -> Arc<Vec<LineStyle>> {
-        if self.line_styles.borrow().get(&line).is_none() {
-            let styles = self.styles();
+        Self::cached_line_style(
+            &self.line_styles,
+            self.buffer.text(),
+            line,
+            self.syntax_styles(),
+        )
+    }
+
+    fn semantic_line_style(&self, line: usize) -> Arc<Vec<LineStyle>> {
+        Self::cached_line_style(
+            &self.semantic_line_styles,
+            self.buffer.text(),
+            line,
+            self.semantic_styles(),
+        )
+    }
 
+    fn cached_line_style(
+        cache: &RefCell<LineStyles>,
+        text: &Rope,
+        line: usize,
+        styles: Option<&Arc<Spans<Style>>>,
+    ) -> Arc<Vec<LineStyle>> {
+        if cache.borrow().get(&line).is_none() {
             let line_styles = styles
-                .map(|styles| line_styles(self.buffer.text(), line, styles))
+                .map(|styles| line_styles(text, line, styles))
                 .unwrap_or_default();
-            self.line_styles
-                .borrow_mut()
-                .insert(line, Arc::new(line_styles));
+            cache.borrow_mut().insert(line, Arc::new(line_styles));
         }
-        self.line_styles.borrow().get(&line).cloned().unwrap()
+        cache.borrow().get(&line).cloned().unwrap()
+    }
+
+    /// The columns of the brackets on the line with their nesting depth
+    fn bracket_depths(&self, line: usize) -> Vec<(usize, usize)> {
+        let syntax = match self.syntax() {
+            Some(syntax) => syntax,
+            None => return Vec::new(),
+        };
+        let start = self.buffer.offset_of_line(line);
+        let end = self.buffer.offset_line_end(start, true);
+        syntax
+            .bracket_depths(start, end)
+            .into_iter()
+            .map(|(offset, depth)| (offset - start, depth))
+            .collect()
+    }
+
+    /// The lines and columns of the open and close brackets of the bracket
+    /// pair that encloses `offset`
+    pub fn enclosing_bracket_pair(
+        &self,
+        offset: usize,
+    ) -> Option<((usize, usize), (usize, usize))> {
+        let (open, close) = self.syntax()?.enclosing_bracket_pair(offset)?;
+        Some((
+            self.buffer.offset_to_line_col(open),
+            self.buffer.offset_to_line_col(close),
+        ))
     }
 
     pub fn line_col_of_point(
@@ -1394,8 +2682,9 @@ pub fn line_col_of_point(
         view: &EditorView,
         config: &Config,
     ) -> ((usize, usize), bool) {
+        let mut wrap_segment = 0;
         let (line, font_size) = match view {
-            EditorView::Diff(version) => {
+            EditorView::Diff(version) | EditorView::Compare(version, _) => {
                 if let Some(history) = self.get_history(version) {
                     let line_height = config.editor.line_height();
                     let mut line = 0;
@@ -1454,15 +2743,29 @@ pub fn line_col_of_point(
                     )
                 }
             }
-            EditorView::Normal => (
-                (point.y / config.editor.line_height() as f64).floor() as usize,
-                config.editor.font_size,
-            ),
+            EditorView::Normal => {
+                let (line, segment) = self.line_of_visual_line(
+                    (point.y / config.editor.line_height() as f64).floor() as usize,
+                    config,
+                );
+                wrap_segment = segment;
+                (line, config.editor.font_size)
+            }
         };
 
         let line = line.min(self.buffer.last_line());
 
         let mut x_shift = 0.0;
+        if *view == EditorView::Normal {
+            if let Some(wrapped) = self.wrapped_line(text, line, config) {
+                // Keep the point on the visual line it's on, since the
+                // positions past its end are shown on the next one
+                let segment = wrap_segment.min(wrapped.num_segments() - 1);
+                let (start, end) = wrapped.x_range(segment);
+                let x = point.x - wrapped.x_shift(segment);
+                x_shift = point.x - x.min(end - wrapped.char_width).max(start);
+            }
+        }
         if font_size < config.editor.font_size {
             let line_content = self.buffer.line_content(line);
             let mut col = 0usize;
@@ -1533,7 +2836,7 @@ pub fn points_of_line_col(
         config: &Config,
     ) -> (Point, Point) {
         let (y, line_height, font_size) = match view {
-            EditorView::Diff(version) => {
+            EditorView::Diff(version) | EditorView::Compare(version, _) => {
                 if let Some(history) = self.get_history(version) {
                     let line_height = config.editor.line_height();
                     let mut current_line = 0;
@@ -1586,7 +2889,7 @@ pub fn points_of_line_col(
                 }
             }
             EditorView::Normal => (
-                config.editor.line_height() * line,
+                config.editor.line_height() * self.visual_line(line, config),
                 config.editor.line_height(),
                 config.editor.font_size,
             ),
@@ -1624,14 +2927,19 @@ pub fn points_of_line_col(
             }
         }
 
-        let x = self
+        let mut x = self
             .line_point_of_line_col(text, line, col, font_size, config)
             .x
             + x_shift;
-        (
-            Point::new(x, y as f64),
-            Point::new(x, (y + line_height) as f64),
-        )
+        let mut y = y as f64;
+        if *view == EditorView::Normal {
+            if let Some(wrapped) = self.wrapped_line(text, line, config) {
+                let segment = wrapped.segment_of_x(x);
+                x += wrapped.x_shift(segment);
+                y += (segment * line_height) as f64;
+            }
+        }
+        (Point::new(x, y), Point::new(x, y + line_height as f64))
     }
 
     fn diff_cursor_line(&self, version: &str, line: usize) -> usize {
@@ -1786,9 +3094,18 @@ fn new_text_layout(
             )
             .set_tab_width(tab_width);
 
-        // Apply various styles to the lines text
-        let styles = self.line_style(line);
-        for line_style in styles.iter() {
+        // Apply various styles to the lines text, with the colors of the
+        // preferred source of highlighting drawn last so that they win
+        let syntax_styles = self.line_style(line);
+        let semantic_styles = self.semantic_line_style(line);
+        let (under, over) = if config
+            .semantic_highlighting_at(self.syntax.as_ref().map(|s| s.language))
+        {
+            (&syntax_styles, &semantic_styles)
+        } else {
+            (&semantic_styles, &syntax_styles)
+        };
+        for line_style in under.iter().chain(over.iter()) {
             if let Some(fg_color) = line_style.style.fg_color.as_ref() {
                 if let Some(fg_color) = config.get_style_color(fg_color) {
                     let start = phantom_text.col_at(line_style.start);
@@ -1800,6 +3117,42 @@ fn new_text_layout(
                 }
             }
         }
+        for line_style in semantic_styles.iter() {
+            let modifiers = line_style.style.modifiers;
+            if modifiers.is_empty() {
+                continue;
+            }
+            let range = phantom_text.col_at(line_style.start)
+                ..phantom_text.col_at(line_style.end);
+            if modifiers.mutable {
+                layout_builder = layout_builder
+                    .range_attribute(range.clone(), TextAttribute::Underline(true));
+            }
+            if modifiers.is_async {
+                layout_builder = layout_builder.range_attribute(
+                    range.clone(),
+                    TextAttribute::Style(FontStyle::Italic),
+                );
+            }
+            if modifiers.deprecated {
+                layout_builder = layout_builder
+                    .range_attribute(range, TextAttribute::Strikethrough(true));
+            }
+        }
+
+        if config.editor.bracket_pair_colorization {
+            for (col, depth) in self.bracket_depths(line) {
+                let col = phantom_text.col_at(col);
+                let color = LapceTheme::EDITOR_BRACKET_COLORS
+                    [depth % LapceTheme::EDITOR_BRACKET_COLORS.len()];
+                layout_builder = layout_builder.range_attribute(
+                    col..col + 1,
+                    TextAttribute::TextColor(
+                        config.get_color_unchecked(color).clone(),
+                    ),
+                );
+            }
+        }
 
         // Give the inlay hints their styling
         for (offset, size, _, col) in phantom_text.offset_size_iter() {
@@ -1971,9 +3324,9 @@ fn new_layout_whitespace(
         let mut render_boundary = false;
         let mut render_between = false;
 
-        // TODO: render whitespaces only on highlighted text
         match config.editor.render_whitespace.as_str() {
-            "all" => {
+            // the selection mode is clipped to the selection when painting
+            "all" | "selection" => {
                 render_leading = true;
                 render_boundary = true;
                 render_between = true;
@@ -2069,6 +3422,60 @@ pub fn line_horiz_col(
         }
     }
 
+    /// Move `count` visual lines up or down in the normal view, keeping the
+    /// x position on the visual line. `None` when lines aren't soft wrapped.
+    #[allow(clippy::too_many_arguments)]
+    fn move_wrapped_offset(
+        &self,
+        text: &mut PietText,
+        offset: usize,
+        horiz: Option<&ColPosition>,
+        count: usize,
+        down: bool,
+        mode: Mode,
+        config: &Config,
+    ) -> Option<(usize, Option<ColPosition>)> {
+        let lines = self.visual_lines(config)?;
+        let font_size = config.editor.font_size;
+
+        let line = self.buffer.line_of_offset(offset);
+        let wrapped = self.wrapped_line(text, line, config)?;
+        let x = self.line_point_of_offset(text, offset, font_size, config).x;
+        let segment = wrapped.segment_of_x(x);
+        let horiz = horiz
+            .cloned()
+            .unwrap_or_else(|| ColPosition::Col(x + wrapped.x_shift(segment)));
+
+        let visual_line = lines.visual_line(self.folded_lines.visible_line(line))
+            + segment.min(lines.num_segments(line).saturating_sub(1));
+        let visual_line = lines.move_visual_line(visual_line, count, down);
+        let (line, segment) = lines.line_of_visual_line(visual_line);
+        let line = line.min(self.buffer.last_line());
+
+        let wrapped = self.wrapped_line(text, line, config)?;
+        let line_horiz = match &horiz {
+            ColPosition::Col(x) => {
+                let (start, end) = wrapped.x_range(segment);
+                ColPosition::Col(
+                    (x - wrapped.x_shift(segment))
+                        .min(end - wrapped.char_width)
+                        .max(start),
+                )
+            }
+            horiz => horiz.clone(),
+        };
+        let col = self.line_horiz_col(
+            text,
+            line,
+            font_size,
+            &line_horiz,
+            mode != Mode::Normal,
+            config,
+        );
+        let new_offset = self.buffer.offset_of_line_col(line, col);
+        Some((new_offset, Some(horiz)))
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn move_region(
         &self,
@@ -2134,9 +3541,9 @@ pub fn move_cursor(
                         config,
                     );
                     let (start, end) = match movement {
-                        Movement::EndOfLine | Movement::WordEndForward => {
-                            (offset, moved_new_offset)
-                        }
+                        Movement::EndOfLine
+                        | Movement::LastNonBlank
+                        | Movement::WordEndForward => (offset, moved_new_offset),
                         Movement::MatchPairs => {
                             if new_offset > offset {
                                 (offset, moved_new_offset)
@@ -2241,6 +3648,14 @@ pub fn move_offset(
                 (new_offset, None)
             }
             Movement::Up => {
+                if *view == EditorView::Normal {
+                    if let Some(moved) = self.move_wrapped_offset(
+                        text, offset, horiz, count, false, mode, config,
+                    ) {
+                        return moved;
+                    }
+                }
+
                 let line = self.buffer.line_of_offset(offset);
                 if line == 0 {
                     return (offset, horiz.cloned());
@@ -2282,7 +3697,7 @@ pub fn move_offset(
                             (line.saturating_sub(count), config.editor.font_size)
                         }
                     }
-                    EditorView::Diff(version) => {
+                    EditorView::Diff(version) | EditorView::Compare(version, _) => {
                         let cursor_line = self.diff_cursor_line(version, line);
                         let cursor_line = if cursor_line > count {
                             cursor_line - count
@@ -2295,7 +3710,11 @@ pub fn move_offset(
                         )
                     }
                     EditorView::Normal => {
-                        (line.saturating_sub(count), config.editor.font_size)
+                        let folded_lines = &self.folded_lines;
+                        let line = folded_lines.actual_line(
+                            folded_lines.visual_line(line).saturating_sub(count),
+                        );
+                        (line, config.editor.font_size)
                     }
                 };
 
@@ -2316,6 +3735,14 @@ pub fn move_offset(
                 (new_offset, Some(horiz))
             }
             Movement::Down => {
+                if *view == EditorView::Normal {
+                    if let Some(moved) = self.move_wrapped_offset(
+                        text, offset, horiz, count, true, mode, config,
+                    ) {
+                        return moved;
+                    }
+                }
+
                 let last_line = self.buffer.last_line();
                 let line = self.buffer.line_of_offset(offset);
 
@@ -2355,7 +3782,7 @@ pub fn move_offset(
                             (line + count, config.editor.font_size)
                         }
                     }
-                    EditorView::Diff(version) => {
+                    EditorView::Diff(version) | EditorView::Compare(version, _) => {
                         let cursor_line = self.diff_cursor_line(version, line);
                         let cursor_line = cursor_line + count;
                         (
@@ -2363,7 +3790,15 @@ pub fn move_offset(
                             config.editor.font_size,
                         )
                     }
-                    EditorView::Normal => (line + count, config.editor.font_size),
+                    EditorView::Normal => {
+                        let folded_lines = &self.folded_lines;
+                        let line = folded_lines
+                            .actual_line(folded_lines.visual_line(line) + count);
+                        (
+                            folded_lines.visible_line(line.min(last_line)),
+                            config.editor.font_size,
+                        )
+                    }
                 };
 
                 let line = line.min(last_line);
@@ -2419,6 +3854,26 @@ pub fn move_offset(
                     self.buffer.offset_line_end(offset, mode != Mode::Normal);
                 (new_offset, Some(ColPosition::End))
             }
+            Movement::LastNonBlank => {
+                let line = self.buffer.line_of_offset(offset);
+                let line_start_offset = self.buffer.offset_of_line(line);
+                let mut non_blank_offset =
+                    self.buffer.end_of_non_blank_on_line(line);
+                if mode == Mode::Normal && non_blank_offset > line_start_offset {
+                    // the cursor is on the last character in normal mode
+                    non_blank_offset =
+                        self.buffer.prev_grapheme_offset(non_blank_offset, 1, 0);
+                }
+                let end_offset =
+                    self.buffer.offset_line_end(offset, mode != Mode::Normal);
+                if offset == non_blank_offset || non_blank_offset == end_offset {
+                    // Jump to the end of the line if we're already at the last
+                    // non-whitespace character
+                    (end_offset, Some(ColPosition::End))
+                } else {
+                    (non_blank_offset, None)
+                }
+            }
             Movement::Line(position) => {
                 let line = match position {
                     LinePosition::Line(line) => {
@@ -2485,6 +3940,14 @@ pub fn move_offset(
                 let new_offset = self.buffer.move_n_words_backward(offset, count);
                 (new_offset, None)
             }
+            Movement::SubwordForward => {
+                let new_offset = self.buffer.move_n_subwords_forward(offset, count);
+                (new_offset, None)
+            }
+            Movement::SubwordBackward => {
+                let new_offset = self.buffer.move_n_subwords_backward(offset, count);
+                (new_offset, None)
+            }
             Movement::NextUnmatched(c) => {
                 if let Some(syntax) = self.syntax.as_ref() {
                     let new_offset = syntax
@@ -2702,3 +4165,40 @@ pub fn change_syntax_selection(
         }
     }
 }
+
+/// The byte ranges of the web addresses in the text, which end before the
+/// punctuation that's more likely to be around them than in them
+pub fn web_addresses(text: &str) -> Vec<(usize, usize)> {
+    let mut addresses = Vec::new();
+    let mut from = 0;
+    while let Some(start) = ["https://", "http://"]
+        .iter()
+        .filter_map(|scheme| text[from..].find(scheme))
+        .min()
+        .map(|start| from + start)
+    {
+        let end = text[start..]
+            .find(|c: char| {
+                c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '<' | '>')
+            })
+            .map(|end| start + end)
+            .unwrap_or(text.len());
+        let address = text[start..end]
+            .trim_end_matches(|c| matches!(c, '.' | ',' | ';' | ':' | '!' | '?'));
+        // a closing bracket belongs to the address only if it opened in it
+        let mut address_end = start + address.len();
+        for (open, close) in [('(', ')'), ('[', ']'), ('{', '}')] {
+            let address = &text[start..address_end];
+            if address.ends_with(close)
+                && address.matches(close).count() > address.matches(open).count()
+            {
+                address_end -= 1;
+            }
+        }
+        if address_end > start + "http://".len() {
+            addresses.push((start, address_end));
+        }
+        from = end.max(start + 1);
+    }
+    addresses
+}
@@ -3,7 +3,7 @@
 use crate::command::LAPCE_COMMAND;
 use crate::command::LAPCE_SAVE_FILE_AS;
 use crate::command::{CommandExecuted, CommandKind};
-use crate::completion::{CompletionData, CompletionStatus, Snippet};
+use crate::completion::{CompletionData, CompletionStatus};
 use crate::config::Config;
 use crate::data::EditorView;
 use crate::data::FocusArea;
@@ -11,17 +11,22 @@
     EditorDiagnostic, InlineFindDirection, LapceEditorData, LapceMainSplitData,
     SplitContent,
 };
+use crate::debug::DebugData;
 use crate::document::BufferContent;
 use crate::document::Document;
 use crate::document::LocalBufferKind;
+use crate::document::SystemClipboard;
 use crate::hover::HoverData;
 use crate::hover::HoverStatus;
 use crate::keypress::KeyMap;
 use crate::keypress::KeyPressFocus;
 use crate::palette::PaletteData;
+use crate::problem::next_problem;
 use crate::proxy::path_from_url;
 use crate::rename::RenameData;
 use crate::selection_range::SelectionRangeDirection;
+use crate::signature::{SignatureData, SignatureStatus};
+use crate::snippet::{Snippet, SnippetVariables};
 use crate::{
     command::{
         EnsureVisiblePosition, InitBufferContent, LapceUICommand, LAPCE_UI_COMMAND,
@@ -30,8 +35,7 @@
 };
 use crate::{find::Find, split::SplitDirection};
 use crate::{proxy::LapceProxy, source_control::SourceControlData};
-use anyhow::{anyhow, Result};
-use crossbeam_channel::{self, bounded};
+use anyhow::Result;
 use druid::piet::PietTextLayout;
 use druid::piet::Svg;
 use druid::FileDialogOptions;
@@ -46,32 +50,38 @@
 use lapce_core::command::{
     EditCommand, FocusCommand, MotionModeCommand, MultiSelectionCommand,
 };
+use lapce_core::cursor::CursorMode;
 use lapce_core::editor::EditType;
-use lapce_core::mode::{Mode, MotionMode};
+use lapce_core::emmet::{self, EmmetSyntax};
+use lapce_core::fold::FoldingRangeKind;
+use lapce_core::mode::{Mode, MotionMode, VisualMode};
+use lapce_core::register::Clipboard;
 use lapce_core::selection::InsertDrift;
-use lapce_core::selection::Selection;
+use lapce_core::selection::{SelRegion, Selection};
 pub use lapce_core::syntax::Syntax;
 use lapce_rpc::proxy::ProxyResponse;
-use lsp_types::request::GotoTypeDefinitionResponse;
+use lapce_rpc::RpcError;
 use lsp_types::CodeActionOrCommand;
 use lsp_types::CompletionTextEdit;
 use lsp_types::DocumentChangeOperation;
 use lsp_types::DocumentChanges;
 use lsp_types::OneOf;
+use lsp_types::Range;
 use lsp_types::ResourceOp;
 use lsp_types::TextEdit;
 use lsp_types::Url;
 use lsp_types::WorkspaceEdit;
 use lsp_types::{
-    CodeActionResponse, CompletionItem, DiagnosticSeverity, GotoDefinitionResponse,
-    Location, Position,
+    CodeActionResponse, CompletionItem, CompletionItemKind, DiagnosticSeverity,
+    Documentation, GotoDefinitionResponse, Location, MarkupContent, MarkupKind,
+    Position,
 };
+use regex::Regex;
 use std::cmp::Ordering;
 use std::path::Path;
-use std::thread;
+use std::str::FromStr;
 use std::{collections::HashMap, sync::Arc};
 use std::{iter::Iterator, path::PathBuf};
-use std::{str::FromStr, time::Duration};
 use xi_rope::Rope;
 use xi_rope::{RopeDelta, Transformer};
 
@@ -93,6 +103,7 @@ pub trait EditorPosition: Sized {
     fn init_buffer_content_cmd(
         path: PathBuf,
         content: Rope,
+        undo_deltas: Vec<RopeDelta>,
         locations: Vec<(WidgetId, EditorLocation<Self>)>,
         edits: Option<Rope>,
         cb: Option<InitBufferContentCb>,
@@ -108,6 +119,7 @@ fn to_utf8_offset(&self, _buffer: &Buffer) -> usize {
     fn init_buffer_content_cmd(
         path: PathBuf,
         content: Rope,
+        undo_deltas: Vec<RopeDelta>,
         locations: Vec<(WidgetId, EditorLocation<Self>)>,
         unsaved_buffers: Option<Rope>,
         cb: Option<InitBufferContentCb>,
@@ -115,6 +127,7 @@ fn init_buffer_content_cmd(
         LapceUICommand::InitBufferContent(InitBufferContent {
             path,
             content,
+            undo_deltas,
             locations,
             edits: unsaved_buffers,
             cb,
@@ -135,6 +148,7 @@ fn to_utf8_offset(&self, buffer: &Buffer) -> usize {
     fn init_buffer_content_cmd(
         path: PathBuf,
         content: Rope,
+        undo_deltas: Vec<RopeDelta>,
         locations: Vec<(WidgetId, EditorLocation<Self>)>,
         edits: Option<Rope>,
         cb: Option<InitBufferContentCb>,
@@ -142,6 +156,7 @@ fn init_buffer_content_cmd(
         LapceUICommand::InitBufferContentLine(InitBufferContent {
             path,
             content,
+            undo_deltas,
             locations,
             edits,
             cb,
@@ -164,6 +179,7 @@ fn to_utf8_offset(&self, buffer: &Buffer) -> usize {
     fn init_buffer_content_cmd(
         path: PathBuf,
         content: Rope,
+        undo_deltas: Vec<RopeDelta>,
         locations: Vec<(WidgetId, EditorLocation<Self>)>,
         edits: Option<Rope>,
         cb: Option<InitBufferContentCb>,
@@ -171,6 +187,7 @@ fn init_buffer_content_cmd(
         LapceUICommand::InitBufferContentLineCol(InitBufferContent {
             path,
             content,
+            undo_deltas,
             locations,
             edits,
             cb,
@@ -186,6 +203,7 @@ fn to_utf8_offset(&self, buffer: &Buffer) -> usize {
     fn init_buffer_content_cmd(
         path: PathBuf,
         content: Rope,
+        undo_deltas: Vec<RopeDelta>,
         locations: Vec<(WidgetId, EditorLocation<Self>)>,
         edits: Option<Rope>,
         cb: Option<InitBufferContentCb>,
@@ -193,6 +211,7 @@ fn init_buffer_content_cmd(
         LapceUICommand::InitBufferContentLsp(InitBufferContent {
             path,
             content,
+            undo_deltas,
             locations,
             edits,
             cb,
@@ -225,6 +244,7 @@ pub struct LapceEditorBufferData {
     pub doc: Arc<Document>,
     pub completion: Arc<CompletionData>,
     pub hover: Arc<HoverData>,
+    pub signature: Arc<SignatureData>,
     pub rename: Arc<RenameData>,
     pub main_split: LapceMainSplitData,
     pub focus_area: FocusArea,
@@ -234,6 +254,9 @@ pub struct LapceEditorBufferData {
     pub proxy: Arc<LapceProxy>,
     pub command_keymaps: Arc<IndexMap<String, Vec<KeyMap>>>,
     pub config: Arc<Config>,
+    /// The breakpoints the gutter shows, and where the debugged program
+    /// stopped
+    pub debug: Arc<DebugData>,
 }
 
 impl LapceEditorBufferData {
@@ -358,6 +381,10 @@ fn has_hover(&self) -> bool {
         self.hover.status != HoverStatus::Inactive && !self.hover.is_empty()
     }
 
+    fn has_signature(&self) -> bool {
+        self.signature.is_active() && !self.signature.is_empty()
+    }
+
     fn has_rename(&self) -> bool {
         self.rename.active
     }
@@ -500,59 +527,13 @@ pub fn apply_completion_item(&mut self, item: &CompletionItem) -> Result<()> {
                             return Ok(());
                         }
                         lsp_types::InsertTextFormat::SNIPPET => {
-                            let snippet = Snippet::from_str(&edit.new_text)?;
-                            let text = snippet.text();
-                            let (delta, inval_lines) = Arc::make_mut(&mut self.doc)
-                                .do_raw_edit(
-                                    &[
-                                        &[(&selection, text.as_str())][..],
-                                        &additional_edit[..],
-                                    ]
-                                    .concat(),
-                                    EditType::Completion,
-                                );
-
-                            let selection = selection.apply_delta(
-                                &delta,
-                                true,
-                                InsertDrift::Default,
-                            );
-
-                            let start_offset = additional_edit
-                                .iter()
-                                .map(|(selection, _)| selection.min_offset())
-                                .min()
-                                .map(|offset| {
-                                    offset.min(start_offset).min(edit_start)
-                                })
-                                .unwrap_or(start_offset);
-
-                            let mut transformer = Transformer::new(&delta);
-                            let offset = transformer.transform(start_offset, false);
-                            let snippet_tabs = snippet.tabs(offset);
-
-                            if snippet_tabs.is_empty() {
-                                Arc::make_mut(&mut self.editor)
-                                    .cursor
-                                    .update_selection(self.doc.buffer(), selection);
-                                self.apply_deltas(&[(delta, inval_lines)]);
-                                return Ok(());
-                            }
-
-                            let mut selection =
-                                lapce_core::selection::Selection::new();
-                            let (_tab, (start, end)) = &snippet_tabs[0];
-                            let region = lapce_core::selection::SelRegion::new(
-                                *start, *end, None,
+                            let snippet = self.parse_snippet(&edit.new_text)?;
+                            return self.insert_snippet(
+                                snippet,
+                                selection,
+                                start_offset.min(edit_start),
+                                &additional_edit,
                             );
-                            selection.add_region(region);
-                            Arc::make_mut(&mut self.editor)
-                                .cursor
-                                .set_insert(selection);
-                            self.apply_deltas(&[(delta, inval_lines)]);
-                            Arc::make_mut(&mut self.editor)
-                                .add_snippet_placeholders(snippet_tabs);
-                            return Ok(());
                         }
                         _ => {}
                     }
@@ -566,6 +547,18 @@ pub fn apply_completion_item(&mut self, item: &CompletionItem) -> Result<()> {
         let end_offset = self.doc.buffer().next_code_boundary(offset);
         let selection = Selection::region(start_offset, end_offset);
 
+        if text_format == lsp_types::InsertTextFormat::SNIPPET {
+            if let Some(text) = item.insert_text.as_deref() {
+                let snippet = self.parse_snippet(text)?;
+                return self.insert_snippet(
+                    snippet,
+                    selection,
+                    start_offset,
+                    &additional_edit,
+                );
+            }
+        }
+
         let (delta, inval_lines) = Arc::make_mut(&mut self.doc).do_raw_edit(
             &[
                 &[(
@@ -585,6 +578,206 @@ pub fn apply_completion_item(&mut self, item: &CompletionItem) -> Result<()> {
         Ok(())
     }
 
+    /// Parse the snippet and fill in its variables from this editor
+    fn parse_snippet(&self, text: &str) -> Result<Snippet> {
+        let buffer = self.doc.buffer();
+        let offset = self.editor.cursor.offset();
+        let line = buffer.line_of_offset(offset);
+        let (word_start, word_end) = buffer.select_word(offset);
+        let selected_text = match &self.editor.cursor.mode {
+            CursorMode::Normal(_) => String::new(),
+            _ => self
+                .editor
+                .cursor
+                .edit_selection(buffer)
+                .regions()
+                .iter()
+                .map(|region| buffer.slice_to_cow(region.min()..region.max()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        let variables = SnippetVariables {
+            selected_text,
+            current_line: buffer
+                .line_content(line)
+                .trim_end_matches(|c: char| c == '\r' || c == '\n')
+                .to_string(),
+            current_word: buffer.slice_to_cow(word_start..word_end).to_string(),
+            line,
+            path: self.doc.content().path().map(|path| path.to_path_buf()),
+            workspace: self.main_split.workspace.path.clone(),
+            clipboard: SystemClipboard {}.get_string(),
+        };
+        Ok(Snippet::from_str(text)?
+            .resolve_variables(&|name| variables.resolve(name)))
+    }
+
+    /// Replace the selection with the text of the snippet, and select its
+    /// first placeholder
+    fn insert_snippet(
+        &mut self,
+        snippet: Snippet,
+        selection: Selection,
+        start_offset: usize,
+        additional_edit: &[(&Selection, &str)],
+    ) -> Result<()> {
+        let text = snippet.text();
+        let (delta, inval_lines) = Arc::make_mut(&mut self.doc).do_raw_edit(
+            &[&[(&selection, text.as_str())][..], additional_edit].concat(),
+            EditType::Completion,
+        );
+
+        let selection = selection.apply_delta(&delta, true, InsertDrift::Default);
+
+        let start_offset = additional_edit
+            .iter()
+            .map(|(selection, _)| selection.min_offset())
+            .min()
+            .map(|offset| offset.min(start_offset))
+            .unwrap_or(start_offset);
+
+        let mut transformer = Transformer::new(&delta);
+        let offset = transformer.transform(start_offset, false);
+        let snippet_tabs = snippet.tabs(offset);
+
+        if snippet_tabs.is_empty() {
+            Arc::make_mut(&mut self.editor)
+                .cursor
+                .update_selection(self.doc.buffer(), selection);
+            self.apply_deltas(&[(delta, inval_lines)]);
+            return Ok(());
+        }
+
+        let mut selection = lapce_core::selection::Selection::new();
+        let (_tab, (start, end)) = &snippet_tabs[0];
+        let region = lapce_core::selection::SelRegion::new(*start, *end, None);
+        selection.add_region(region);
+        Arc::make_mut(&mut self.editor).cursor.set_insert(selection);
+        self.apply_deltas(&[(delta, inval_lines)]);
+        let editor = Arc::make_mut(&mut self.editor);
+        if editor.snippet.is_none() {
+            editor.snippet_choices.clear();
+        }
+        editor.snippet_choices.extend(snippet.choices(offset));
+        editor.add_snippet_placeholders(snippet_tabs);
+        self.show_snippet_choices();
+        Ok(())
+    }
+
+    /// The Emmet abbreviation before the cursor, with the offset it starts
+    /// at and the snippet it expands to
+    fn emmet_abbreviation(&self) -> Option<(usize, String, String)> {
+        if !self.config.editor.emmet
+            || !self.doc.content().is_file()
+            || self.doc.buffer().is_read_only()
+        {
+            return None;
+        }
+        let offset = match &self.editor.cursor.mode {
+            CursorMode::Insert(selection) => match selection.regions() {
+                [region] if region.is_caret() => region.start,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let syntax =
+            EmmetSyntax::from_language(self.doc.syntax()?.language_at(offset))?;
+        let buffer = self.doc.buffer();
+        let line = buffer.line_of_offset(offset);
+        let line_start = buffer.offset_of_line(line);
+        let before = buffer.slice_to_cow(line_start..offset);
+        let (start, abbreviation) = emmet::abbreviation(syntax, &before)?;
+        let snippet = emmet::expand(
+            syntax,
+            abbreviation,
+            buffer.indent_unit(),
+            &buffer.indent_on_line(line),
+        )?;
+        Some((line_start + start, abbreviation.to_string(), snippet))
+    }
+
+    /// Replace the Emmet abbreviation before the cursor with what it expands
+    /// to, returning whether there was one
+    fn expand_emmet_abbreviation(&mut self) -> bool {
+        let (start, _, text) = match self.emmet_abbreviation() {
+            Some(abbreviation) => abbreviation,
+            None => return false,
+        };
+        let offset = self.editor.cursor.offset();
+        let snippet = match self.parse_snippet(&text) {
+            Ok(snippet) => snippet,
+            Err(_) => return false,
+        };
+        self.insert_snippet(snippet, Selection::region(start, offset), start, &[])
+            .is_ok()
+    }
+
+    /// The completion item of the Emmet abbreviation before the cursor, with
+    /// a preview of what it expands to as its documentation
+    fn emmet_completion_item(&self) -> Option<CompletionItem> {
+        let (start, abbreviation, text) = self.emmet_abbreviation()?;
+        let offset = self.editor.cursor.offset();
+        let preview = Snippet::from_str(&text).ok()?.text();
+        let buffer = self.doc.buffer();
+        Some(CompletionItem {
+            label: abbreviation,
+            kind: Some(CompletionItemKind::SNIPPET),
+            detail: Some("Emmet Abbreviation".to_string()),
+            documentation: Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```\n{preview}\n```"),
+            })),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                range: lsp_types::Range {
+                    start: buffer.offset_to_position(start),
+                    end: buffer.offset_to_position(offset),
+                },
+                new_text: text,
+            })),
+            insert_text_format: Some(lsp_types::InsertTextFormat::SNIPPET),
+            ..Default::default()
+        })
+    }
+
+    /// Offer the options of the snippet choice the cursor is on as
+    /// completion items, which replace the choice
+    fn show_snippet_choices(&mut self) {
+        let region = match &self.editor.cursor.mode {
+            CursorMode::Insert(selection) => match selection.regions() {
+                [region] => (region.min(), region.max()),
+                _ => return,
+            },
+            _ => return,
+        };
+        let options = match self
+            .editor
+            .snippet_choices
+            .iter()
+            .find(|(range, _)| *range == region)
+        {
+            Some((_, options)) => options.clone(),
+            None => return,
+        };
+
+        let range = lsp_types::Range::new(
+            self.doc.buffer().offset_to_position(region.0),
+            self.doc.buffer().offset_to_position(region.1),
+        );
+        let items = options
+            .into_iter()
+            .map(|option| CompletionItem {
+                label: option.clone(),
+                kind: Some(lsp_types::CompletionItemKind::ENUM_MEMBER),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                    range, option,
+                ))),
+                ..Default::default()
+            })
+            .collect();
+        let buffer_id = self.doc.id();
+        Arc::make_mut(&mut self.completion).show_items(buffer_id, region.0, items);
+    }
+
     pub fn cancel_completion(&mut self) {
         if self.completion.status == CompletionStatus::Inactive {
             return;
@@ -598,6 +791,94 @@ pub fn cancel_hover(&mut self) {
         hover.cancel();
     }
 
+    pub fn cancel_signature(&mut self) {
+        let signature = Arc::make_mut(&mut self.signature);
+        signature.cancel();
+    }
+
+    /// Ask for the signature of the call the cursor is in, after the
+    /// character was typed, or when there is none, after an edit or a move
+    /// while a signature is shown
+    fn update_signature(
+        &mut self,
+        ctx: &mut EventCtx,
+        trigger_character: Option<&str>,
+    ) {
+        if self.get_mode() != Mode::Insert {
+            self.cancel_signature();
+            return;
+        }
+        let path = match self.doc.content() {
+            BufferContent::File(path) => path.clone(),
+            _ => return,
+        };
+        if self.signature.is_active()
+            && (self.signature.buffer_id != self.doc.id()
+                || self.signature.editor_view_id != self.editor.view_id)
+        {
+            self.cancel_signature();
+        }
+        if trigger_character.is_none() && !self.signature.is_active() {
+            return;
+        }
+
+        let offset = self.editor.cursor.offset();
+        let position = self.doc.buffer().offset_to_position(offset);
+        let signature = Arc::make_mut(&mut self.signature);
+        signature.buffer_id = self.doc.id();
+        signature.editor_view_id = self.editor.view_id;
+        signature.offset = offset;
+        if signature.status == SignatureStatus::Inactive {
+            signature.status = SignatureStatus::Started;
+        }
+        signature.request_id += 1;
+        signature.request(
+            self.proxy.clone(),
+            path,
+            position,
+            trigger_character.map(|c| c.to_string()),
+            signature.id,
+            ctx.get_external_handle(),
+        );
+    }
+
+    /// Ask the language servers for the edits formatting the document around
+    /// the character that was just typed, if formatting on type is on for
+    /// its language. The edits are dropped if the text changed meanwhile.
+    fn format_on_type(&self, ctx: &mut EventCtx, ch: &str) {
+        if self.get_mode() != Mode::Insert {
+            return;
+        }
+        let path = match self.doc.content() {
+            BufferContent::File(path) => path.clone(),
+            _ => return,
+        };
+        let language = self.doc.syntax().map(|syntax| syntax.language);
+        if !self.config.format_on_type_at(language) {
+            return;
+        }
+
+        let offset = self.editor.cursor.offset();
+        let position = self.doc.buffer().offset_to_position(offset);
+        let rev = self.doc.rev();
+        let tab_id = *self.main_split.tab_id;
+        let event_sink = ctx.get_external_handle();
+        self.proxy.proxy_rpc.get_on_type_formatting(
+            path.clone(),
+            position,
+            ch.to_string(),
+            move |result| {
+                if let Ok(ProxyResponse::GetDocumentFormatting { edits }) = result {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::DocumentFormat(path, rev, Ok(edits)),
+                        Target::Widget(tab_id),
+                    );
+                }
+            },
+        );
+    }
+
     pub fn cancel_rename(&mut self, ctx: &mut EventCtx) {
         let rename = Arc::make_mut(&mut self.rename);
         rename.cancel();
@@ -627,7 +908,7 @@ pub fn completion_item_select(&mut self, ctx: &mut EventCtx) {
         };
 
         self.cancel_completion();
-        if item.item.data.is_some() {
+        if !item.resolved && item.item.data.is_some() {
             let view_id = self.editor.view_id;
             let buffer_id = self.doc.id();
             let rev = self.doc.rev();
@@ -697,6 +978,7 @@ fn update_completion(
                 .slice_to_cow(start_offset - 1..start_offset)
                 .to_string()
         };
+        let emmet = self.emmet_completion_item();
         let completion = Arc::make_mut(&mut self.completion);
         if !display_if_empty_input && input.is_empty() && char != "." && char != ":"
         {
@@ -709,6 +991,7 @@ fn update_completion(
             && completion.buffer_id == self.doc.id()
         {
             completion.update_input(input.clone());
+            completion.set_emmet(emmet);
 
             if !completion.input_items.contains_key("") {
                 let start_pos = self.doc.buffer().offset_to_position(start_offset);
@@ -741,6 +1024,14 @@ fn update_completion(
         completion.status = CompletionStatus::Started;
         completion.input_items.clear();
         completion.request_id += 1;
+        let language = self
+            .doc
+            .syntax()
+            .map(|syntax| syntax.language.to_string().to_lowercase());
+        completion.set_snippets(
+            self.config.snippets.completion_items(language.as_deref()),
+        );
+        completion.set_emmet(emmet);
         let start_pos = self.doc.buffer().offset_to_position(start_offset);
         completion.request(
             self.proxy.clone(),
@@ -771,6 +1062,9 @@ pub fn check_hover(
         within_scroll: bool,
     ) -> bool {
         if self.hover.status != HoverStatus::Inactive {
+            if self.hover.pinned {
+                return true;
+            }
             if !is_inside || !within_scroll {
                 let hover = Arc::make_mut(&mut self.hover);
                 hover.cancel();
@@ -859,6 +1153,11 @@ fn update_snippet_offset(&mut self, delta: &RopeDelta) {
                     })
                     .collect(),
             );
+            let editor = Arc::make_mut(&mut self.editor);
+            for ((start, end), _) in editor.snippet_choices.iter_mut() {
+                *start = transformer.transform(*start, false);
+                *end = transformer.transform(*end, true);
+            }
         }
     }
 
@@ -935,36 +1234,41 @@ fn next_diff(&mut self, ctx: &mut EventCtx) {
         }
     }
 
-    fn next_error(&mut self, ctx: &mut EventCtx) {
+    fn next_error(&mut self, ctx: &mut EventCtx, forward: bool) {
         if let BufferContent::File(buffer_path) = self.doc.content() {
             let mut file_diagnostics: Vec<(&PathBuf, Vec<Position>)> = self
                 .main_split
                 .diagnostics_items(DiagnosticSeverity::ERROR)
                 .into_iter()
                 .map(|(p, d)| {
-                    (p, d.iter().map(|d| d.diagnostic.range.start).collect())
+                    let mut positions: Vec<Position> =
+                        d.iter().map(|d| d.diagnostic.range.start).collect();
+                    positions.sort_by_key(|p| (p.line, p.character));
+                    (p, positions)
                 })
                 .collect();
-            if file_diagnostics.is_empty() {
-                return;
-            }
             file_diagnostics.sort_by(|a, b| a.0.cmp(b.0));
 
             let offset = self.editor.cursor.offset();
             let position = self.doc.buffer().offset_to_position(offset);
-            let (path, position) =
-                next_in_file_errors_offset(position, buffer_path, &file_diagnostics);
-            let location = EditorLocation {
-                path,
-                position: Some(position),
-                scroll_offset: None,
-                history: None,
-            };
-            ctx.submit_command(Command::new(
-                LAPCE_UI_COMMAND,
-                LapceUICommand::JumpToLspLocation(None, location, true),
-                Target::Auto,
-            ));
+            if let Some((path, position)) = next_problem(
+                &file_diagnostics,
+                Some(buffer_path.as_path()),
+                position,
+                forward,
+            ) {
+                let location = EditorLocation {
+                    path,
+                    position: Some(position),
+                    scroll_offset: None,
+                    history: None,
+                };
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::JumpToLspLocation(None, location, true),
+                    Target::Auto,
+                ));
+            }
         }
     }
 
@@ -1060,6 +1364,12 @@ fn scroll(
 
         let offset = self.editor.cursor.offset();
         let (line, _col) = self.doc.buffer().offset_to_line_col(offset);
+        // Up and down move over the visual lines of the normal view
+        let line = if self.editor.view == EditorView::Normal {
+            self.doc.visual_line(line, &self.config)
+        } else {
+            line
+        };
         let top = self.editor.scroll_offset.y + diff;
         let bottom = top + self.editor.size.borrow().height;
 
@@ -1104,6 +1414,38 @@ fn scroll(
         ));
     }
 
+    /// Fold or unfold the range starting at `line`
+    pub fn toggle_fold(&mut self, line: usize) {
+        self.doc_mut().toggle_fold(line);
+        self.move_cursor_out_of_folds();
+    }
+
+    /// Move the document to the state of `group` in its undo tree
+    pub fn jump_to_undo_group(&mut self, group: usize) {
+        let modal = self.config.lapce.modal && !self.editor.content.is_input();
+        let doc = Arc::make_mut(&mut self.doc);
+        let cursor = &mut Arc::make_mut(&mut self.editor).cursor;
+        let deltas = doc.do_jump_to_undo_group(cursor, group, modal);
+        self.cancel_completion();
+        self.apply_deltas(&deltas);
+    }
+
+    /// Move the cursor to the first line of its fold when it has just been
+    /// folded away.
+    fn move_cursor_out_of_folds(&mut self) {
+        let line = self
+            .doc
+            .buffer()
+            .line_of_offset(self.editor.cursor.offset());
+        let visible_line = self.doc.folded_lines().visible_line(line);
+        if visible_line != line {
+            let offset = self.doc.buffer().offset_of_line(visible_line);
+            Arc::make_mut(&mut self.editor)
+                .cursor
+                .set_offset(offset, false, false);
+        }
+    }
+
     pub fn current_code_actions(&self) -> Option<&CodeActionResponse> {
         let offset = self.editor.cursor.offset();
         let prev_offset = self.doc.buffer().prev_code_boundary(offset);
@@ -1120,6 +1462,7 @@ pub fn offset_of_mouse(
         pos: Point,
         config: &Config,
     ) -> usize {
+        let mut x = pos.x;
         let (line, char_width) = if self.editor.is_code_lens() {
             let (line, font_size) = if let Some(syntax) = self.doc.syntax() {
                 let line = syntax.lens.line_of_height(pos.y.floor() as usize);
@@ -1148,6 +1491,10 @@ pub fn offset_of_mouse(
             (line, config.editor_char_width(text))
         } else {
             let line = (pos.y / config.editor.line_height() as f64).floor() as usize;
+            let (line, segment) = self.doc.line_of_visual_line(line, config);
+            if let Some(wrapped) = self.doc.wrapped_line(text, line, config) {
+                x -= wrapped.x_shift(segment);
+            }
             (line, config.editor_char_width(text))
         };
 
@@ -1161,9 +1508,9 @@ pub fn offset_of_mouse(
                 .line_end_col(line, self.editor.cursor.get_mode() != Mode::Normal);
 
             let col = (if self.editor.cursor.get_mode() == Mode::Insert {
-                (pos.x / char_width).round() as usize
+                (x / char_width).round() as usize
             } else {
-                (pos.x / char_width).floor() as usize
+                (x / char_width).floor() as usize
             })
             .min(line_end);
             (line, col)
@@ -1171,6 +1518,21 @@ pub fn offset_of_mouse(
         self.doc.buffer().offset_of_line_col(line, col)
     }
 
+    /// Move the cursor to the first non blank character of `line` and
+    /// scroll the line to the top of the editor, e.g. when a line of the
+    /// sticky header is clicked
+    pub fn jump_to_line_at_top(&mut self, ctx: &mut EventCtx, line: usize) {
+        let offset = self.doc.buffer().first_non_blank_character_on_line(line);
+        Arc::make_mut(&mut self.editor)
+            .cursor
+            .set_offset(offset, false, false);
+        ctx.submit_command(Command::new(
+            LAPCE_UI_COMMAND,
+            LapceUICommand::EnsureCursorPosition(EnsureVisiblePosition::TopOfWindow),
+            Target::Widget(self.editor.view_id),
+        ));
+    }
+
     pub fn single_click(
         &mut self,
         ctx: &mut EventCtx,
@@ -1202,19 +1564,51 @@ pub fn single_click(
         }
 
         if go_to_definition {
-            ctx.submit_command(Command::new(
-                LAPCE_COMMAND,
-                LapceCommand {
-                    kind: CommandKind::Focus(FocusCommand::GotoDefinition),
-                    data: None,
-                },
-                Target::Widget(self.editor.view_id),
-            ));
+            if let Some((_, _, target)) = self.doc.link_at(new_offset) {
+                self.open_link(ctx, &target);
+            } else {
+                ctx.submit_command(Command::new(
+                    LAPCE_COMMAND,
+                    LapceCommand {
+                        kind: CommandKind::Focus(FocusCommand::GotoDefinition),
+                        data: None,
+                    },
+                    Target::Widget(self.editor.view_id),
+                ));
+            }
         } else if mouse_event.buttons.has_left() {
             ctx.set_active(true);
         }
     }
 
+    /// Open the target of a link, a file in an editor, at the line of a
+    /// `#L12` or `#L12,3` fragment if it has one, and anything else with
+    /// the app of the system for it, like a web address in the browser
+    fn open_link(&self, ctx: &mut EventCtx, target: &Url) {
+        let tab_id = *self.main_split.tab_id;
+        if target.scheme() == "file" {
+            if let Ok(path) = target.to_file_path() {
+                let location = EditorLocation {
+                    path,
+                    position: target.fragment().and_then(link_fragment_position),
+                    scroll_offset: None,
+                    history: None,
+                };
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::JumpToLspLocation(None, location, true),
+                    Target::Widget(tab_id),
+                ));
+                return;
+            }
+        }
+        ctx.submit_command(Command::new(
+            LAPCE_UI_COMMAND,
+            LapceUICommand::OpenURI(target.to_string()),
+            Target::Widget(tab_id),
+        ));
+    }
+
     pub fn double_click(
         &mut self,
         ctx: &mut EventCtx,
@@ -1229,7 +1623,11 @@ pub fn double_click(
             &self.editor.view,
             config,
         );
-        let (start, end) = self.doc.buffer().select_word(mouse_offset);
+        let (start, end) = if config.editor.double_click_selects_subword {
+            self.doc.buffer().select_subword(mouse_offset)
+        } else {
+            self.doc.buffer().select_word(mouse_offset)
+        };
         let cursor = &mut Arc::make_mut(&mut self.editor).cursor;
         cursor.add_region(
             start,
@@ -1265,14 +1663,276 @@ pub fn triple_click(
         );
     }
 
+    /// Select the block between the point where a drag with alt held
+    /// started and where the mouse is now
+    pub fn block_select(
+        &mut self,
+        text: &mut PietText,
+        anchor: Point,
+        head: Point,
+        config: &Config,
+    ) {
+        let mode = self.get_mode();
+        let view = &self.editor.view;
+        let anchor = self
+            .doc
+            .block_corner_of_point(text, mode, anchor, view, config);
+        let head = self
+            .doc
+            .block_corner_of_point(text, mode, head, view, config);
+        let cursor_mode = if self.editor.cursor.is_insert() {
+            CursorMode::Insert(self.doc.block_selection(text, anchor, head, config))
+        } else {
+            // Modal editing has its own visual mode for blocks
+            CursorMode::Visual {
+                start: self.doc.offset_of_line_x(text, anchor.0, anchor.1, config),
+                end: self.doc.offset_of_line_x(text, head.0, head.1, config),
+                mode: VisualMode::Blockwise,
+            }
+        };
+        Arc::make_mut(&mut self.editor).cursor.set_mode(cursor_mode);
+    }
+
     fn apply_deltas(&mut self, deltas: &[(RopeDelta, InvalLines)]) {
         for (delta, _) in deltas {
             self.inactive_apply_delta(delta);
             self.update_snippet_offset(delta);
         }
+        self.update_linked_editing_offset(deltas);
+    }
+
+    /// Move the linked editing ranges along with the edits, unless they are
+    /// for another revision than the one that was edited
+    fn update_linked_editing_offset(&mut self, deltas: &[(RopeDelta, InvalLines)]) {
+        let linked_rev = match self.editor.linked_editing.as_ref() {
+            Some(linked) => linked.rev,
+            None => return,
+        };
+        let rev = self.doc.rev();
+        let editor = Arc::make_mut(&mut self.editor);
+        if linked_rev + deltas.len() as u64 != rev {
+            editor.linked_editing = None;
+            return;
+        }
+        if let Some(linked) = editor.linked_editing.as_mut() {
+            for (delta, _) in deltas {
+                linked.apply_delta(delta);
+            }
+        }
+    }
+
+    /// Ask for the ranges that are edited together with the one the cursor
+    /// is in, after an edit, or after a move out of the ranges there are
+    fn update_linked_editing(&mut self, ctx: &mut EventCtx, edited: bool) {
+        if self.get_mode() != Mode::Insert {
+            if self.editor.linked_editing.is_some() {
+                Arc::make_mut(&mut self.editor).linked_editing = None;
+            }
+            return;
+        }
+        let path = match self.doc.content() {
+            BufferContent::File(path) => path.clone(),
+            _ => return,
+        };
+        let offset = self.editor.cursor.offset();
+        if !edited {
+            let in_range =
+                self.editor.linked_editing.as_ref().map_or(false, |linked| {
+                    linked.rev == self.doc.rev()
+                        && linked.range_of(offset, offset).is_some()
+                });
+            if in_range {
+                return;
+            }
+            if self.editor.linked_editing.is_some() {
+                Arc::make_mut(&mut self.editor).linked_editing = None;
+            }
+        }
+
+        let rev = self.doc.rev();
+        let position = self.doc.buffer().offset_to_position(offset);
+        let view_id = self.editor.view_id;
+        let tab_id = *self.main_split.tab_id;
+        let event_sink = ctx.get_external_handle();
+        self.proxy.proxy_rpc.get_linked_editing_ranges(
+            path.clone(),
+            position,
+            move |result| {
+                if let Ok(ProxyResponse::GetLinkedEditingRanges { ranges }) = result
+                {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::UpdateLinkedEditingRanges {
+                            path,
+                            view_id,
+                            rev,
+                            ranges,
+                        },
+                        Target::Widget(tab_id),
+                    );
+                }
+            },
+        );
     }
 
-    fn save(&mut self, ctx: &mut EventCtx, exit: bool) {
+    /// The selection of the cursor mirrored into every linked editing
+    /// range, with the index of the range the cursor is in, if the edit
+    /// keeps to that range. `cmd` is the edit command, or `None` for typing
+    /// the inserted text.
+    fn linked_editing_selection(
+        &self,
+        cmd: Option<&EditCommand>,
+        inserted: Option<&str>,
+    ) -> Option<(usize, Selection)> {
+        let linked = self.editor.linked_editing.as_ref()?;
+        if linked.rev != self.doc.rev() {
+            return None;
+        }
+        let region = match &self.editor.cursor.mode {
+            CursorMode::Insert(selection) if selection.len() == 1 => {
+                selection.regions()[0]
+            }
+            _ => return None,
+        };
+        let index = linked.range_of(region.min(), region.max())?;
+        let (start, end) = linked.ranges[index];
+
+        let keeps_to_range = match cmd {
+            None => true,
+            Some(EditCommand::DeleteBackward) => {
+                !region.is_caret() || region.min() > start
+            }
+            Some(EditCommand::DeleteForward) => {
+                !region.is_caret() || region.max() < end
+            }
+            Some(_) => false,
+        };
+        if !keeps_to_range {
+            return None;
+        }
+
+        // the ranges stop being linked once their text isn't a word of the
+        // language anymore, like after typing a space in a tag name
+        if let (Some(pattern), Some(inserted)) =
+            (linked.word_pattern.as_ref(), inserted)
+        {
+            let text = self.doc.buffer().slice_to_cow(start..end);
+            let text = format!(
+                "{}{}{}",
+                &text[..region.min() - start],
+                inserted,
+                &text[region.max() - start..]
+            );
+            let is_word = Regex::new(&format!("^(?:{pattern})$"))
+                .map(|regex| regex.is_match(&text))
+                .unwrap_or(true);
+            if !is_word {
+                return None;
+            }
+        }
+
+        let mut selection = Selection::new();
+        for (range_start, range_end) in linked.ranges.iter() {
+            let region_start = range_start + region.start - start;
+            let region_end = range_start + region.end - start;
+            if region_start.max(region_end) > *range_end {
+                return None;
+            }
+            selection.add_region(SelRegion::new(region_start, region_end, None));
+        }
+        Some((index, selection))
+    }
+
+    /// Leave only the region of the cursor in the linked editing range it
+    /// was in, after an edit of every range
+    fn keep_linked_editing_region(&mut self, index: usize) {
+        let region = match &self.editor.cursor.mode {
+            CursorMode::Insert(selection) => selection.regions().get(index).copied(),
+            _ => None,
+        };
+        if let Some(region) = region {
+            let mut selection = Selection::new();
+            selection.add_region(region);
+            Arc::make_mut(&mut self.editor)
+                .cursor
+                .update_selection(self.doc.buffer(), selection);
+        }
+    }
+
+    /// Format the document, or only the range if there is one
+    fn format(&self, ctx: &mut EventCtx, range: Option<Range>) {
+        if let BufferContent::File(path) = self.doc.content() {
+            let rev = self.doc.rev();
+            let event_sink = ctx.get_external_handle();
+            let tab_id = *self.main_split.tab_id;
+            self.main_split.get_document_formatting(
+                path,
+                range,
+                &self.config,
+                move |path, result| {
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::DocumentFormat(path, rev, result),
+                        Target::Widget(tab_id),
+                    );
+                },
+            );
+        }
+    }
+
+    /// The text of the selection, or of the line of the cursor when nothing
+    /// is selected
+    fn selection_or_line_text(&self) -> String {
+        let buffer = self.doc.buffer();
+        let selection = match &self.editor.cursor.mode {
+            CursorMode::Normal(_) => None,
+            CursorMode::Insert(selection) if selection.is_caret() => None,
+            _ => Some(self.editor.cursor.edit_selection(buffer)),
+        };
+        match selection {
+            Some(selection) => selection
+                .regions()
+                .iter()
+                .map(|region| buffer.slice_to_cow(region.min()..region.max()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => {
+                let line = buffer.line_of_offset(self.editor.cursor.offset());
+                buffer.line_content(line).to_string()
+            }
+        }
+    }
+
+    /// The range of the whole lines with a selection or cursor on them,
+    /// including the line break of the last one
+    fn selected_lines_range(&self) -> Range {
+        let buffer = self.doc.buffer();
+        let selection = self.editor.cursor.edit_selection(buffer);
+        let start = selection.min_offset();
+        let end = selection.max_offset();
+        let start_line = buffer.line_of_offset(start);
+        let mut end_line = buffer.line_of_offset(end);
+        // a selection ending at the start of a line doesn't include it
+        if end > start
+            && end_line > start_line
+            && buffer.offset_of_line(end_line) == end
+        {
+            end_line -= 1;
+        }
+        let end = if end_line < buffer.last_line() {
+            buffer.offset_of_line(end_line + 1)
+        } else {
+            buffer.len()
+        };
+        Range::new(
+            Position::new(start_line as u32, 0),
+            buffer.offset_to_position(end),
+        )
+    }
+
+    /// Save the document, first running the code actions on save and
+    /// formatting it if `actions` is set
+    fn save(&mut self, ctx: &mut EventCtx, exit: bool, actions: bool) {
         if self.doc.buffer().is_pristine() && self.doc.content().is_file() {
             if exit {
                 ctx.submit_command(Command::new(
@@ -1288,52 +1948,14 @@ fn save(&mut self, ctx: &mut EventCtx, exit: bool) {
         }
 
         if let BufferContent::File(path) = self.doc.content() {
-            let format_on_save = self.config.editor.format_on_save;
             let path = path.clone();
-            let proxy = self.proxy.clone();
-            let rev = self.doc.rev();
-            let event_sink = ctx.get_external_handle();
-            let view_id = self.editor.view_id;
-            let tab_id = self.main_split.tab_id.clone();
-            let (sender, receiver) = bounded(1);
-            thread::spawn(move || {
-                proxy.proxy_rpc.get_document_formatting(
-                    path.clone(),
-                    Box::new(move |result| {
-                        let _ = sender.send(result);
-                    }),
-                );
-
-                let result =
-                    receiver.recv_timeout(Duration::from_secs(1)).map_or_else(
-                        |e| Err(anyhow!("{}", e)),
-                        |v| {
-                            v.map_err(|e| anyhow!("{:?}", e)).and_then(|r| {
-                                if let ProxyResponse::GetDocumentFormatting {
-                                    edits,
-                                } = r
-                                {
-                                    Ok(edits)
-                                } else {
-                                    Err(anyhow!("wrong response"))
-                                }
-                            })
-                        },
-                    );
-
-                let exit = if exit { Some(view_id) } else { None };
-                let cmd = if format_on_save {
-                    LapceUICommand::DocumentFormatAndSave(path, rev, result, exit)
-                } else {
-                    LapceUICommand::DocumentSave(path, exit)
-                };
-
-                let _ = event_sink.submit_command(
-                    LAPCE_UI_COMMAND,
-                    cmd,
-                    Target::Widget(*tab_id),
-                );
-            });
+            let exit = if exit {
+                Some(self.editor.view_id)
+            } else {
+                None
+            };
+            self.main_split
+                .save_file(ctx, &path, actions, exit, &self.config);
         } else if let BufferContent::Scratch(..) = self.doc.content() {
             let content = self.doc.content().clone();
             let view_id = self.editor.view_id;
@@ -1391,6 +2013,8 @@ fn run_move_command(
         }
         self.cancel_completion();
         self.cancel_hover();
+        self.update_signature(ctx, None);
+        self.update_linked_editing(ctx, false);
         CommandExecuted::Yes
     }
 
@@ -1399,11 +2023,33 @@ fn run_edit_command(
         ctx: &mut EventCtx,
         cmd: &EditCommand,
     ) -> CommandExecuted {
+        if *cmd == EditCommand::Undo {
+            if let BufferContent::File(path) = &self.editor.content {
+                if self.main_split.undo_workspace_edit(path) {
+                    return CommandExecuted::Yes;
+                }
+            }
+        }
+
+        let linked = self.linked_editing_selection(Some(cmd), None);
+        if let Some((_, selection)) = linked.as_ref() {
+            Arc::make_mut(&mut self.editor)
+                .cursor
+                .update_selection(self.doc.buffer(), selection.clone());
+        }
+
         let modal = self.config.lapce.modal && !self.editor.content.is_input();
         let doc = Arc::make_mut(&mut self.doc);
         let doc_before_edit = doc.buffer().text().clone();
         let register = Arc::make_mut(&mut self.main_split.register);
         let cursor = &mut Arc::make_mut(&mut self.editor).cursor;
+        let cmd = if self.config.editor.hungry_delete
+            && *cmd == EditCommand::DeleteBackward
+        {
+            &EditCommand::DeleteWhitespaceBackward
+        } else {
+            cmd
+        };
         let yank_data =
             if let lapce_core::cursor::CursorMode::Visual { .. } = &cursor.mode {
                 Some(cursor.yank(doc.buffer()))
@@ -1418,6 +2064,9 @@ fn run_edit_command(
                 register.add_delete(data);
             }
         }
+        if let Some((index, _)) = linked {
+            self.keep_linked_editing_region(index);
+        }
 
         if show_completion(cmd, &doc_before_edit, &deltas) {
             self.update_completion(ctx, false);
@@ -1425,10 +2074,84 @@ fn run_edit_command(
             self.cancel_completion();
         }
         self.apply_deltas(&deltas);
+        if !deltas.is_empty() {
+            self.update_signature(ctx, None);
+            self.update_linked_editing(ctx, true);
+            if *cmd == EditCommand::InsertNewLine {
+                self.format_on_type(ctx, "\n");
+            }
+        }
 
         CommandExecuted::Yes
     }
 
+    /// Replace the occurrence of the find bar the cursor is at, if it's at
+    /// one, and go to the next occurrence
+    fn replace_next(&mut self, ctx: &mut EventCtx, mods: Modifiers) {
+        if self.doc.buffer().is_read_only() || self.editor.view.is_read_only() {
+            return;
+        }
+        Arc::make_mut(&mut self.find).visual = true;
+        let mut offset = self.editor.cursor.offset();
+        let occurrence = self
+            .find
+            .all(self.doc.buffer().text())
+            .into_iter()
+            .find(|(start, _)| *start == offset);
+        if let Some((start, end)) = occurrence {
+            let text = self
+                .find
+                .replacement(&self.doc.buffer().slice_to_cow(start..end));
+            let selection = Selection::region(start, end);
+            let (delta, inval_lines) = Arc::make_mut(&mut self.doc)
+                .do_raw_edit(&[(&selection, text.as_str())], EditType::Other);
+            self.apply_deltas(&[(delta, inval_lines)]);
+            offset = start + text.len();
+        }
+
+        let occurrences = self.find.all(self.doc.buffer().text());
+        let next = occurrences
+            .iter()
+            .find(|(start, _)| *start >= offset)
+            .or_else(|| occurrences.first());
+        if let Some((start, _)) = next {
+            self.run_move_command(
+                ctx,
+                &lapce_core::movement::Movement::Offset(*start),
+                None,
+                mods,
+            );
+        }
+    }
+
+    /// Replace all the occurrences of the find bar, as one edit
+    fn replace_all(&mut self) {
+        if self.doc.buffer().is_read_only() || self.editor.view.is_read_only() {
+            return;
+        }
+        let buffer = self.doc.buffer();
+        let edits = self
+            .find
+            .all(buffer.text())
+            .into_iter()
+            .map(|(start, end)| {
+                let text = self.find.replacement(&buffer.slice_to_cow(start..end));
+                (Selection::region(start, end), text)
+            })
+            .collect::<Vec<_>>();
+        if edits.is_empty() {
+            return;
+        }
+        let edits = edits
+            .iter()
+            .map(|(selection, text)| (selection, text.as_str()))
+            .collect::<Vec<_>>();
+        let (delta, inval_lines) =
+            Arc::make_mut(&mut self.doc).do_raw_edit(&edits, EditType::Other);
+        Arc::make_mut(&mut self.editor).cursor.apply_delta(&delta);
+        self.apply_deltas(&[(delta, inval_lines)]);
+    }
+
     fn run_focus_command(
         &mut self,
         ctx: &mut EventCtx,
@@ -1455,6 +2178,9 @@ fn run_focus_command(
                 if self.has_hover() {
                     self.cancel_hover();
                 }
+                if self.has_signature() {
+                    self.cancel_signature();
+                }
                 if self.is_rename() {
                     self.cancel_rename(ctx);
                 }
@@ -1547,7 +2273,15 @@ fn run_focus_command(
                 }
             }
             SearchForward => {
-                if self.editor.content.is_search() {
+                if self.editor.content
+                    == BufferContent::Local(LocalBufferKind::TerminalFind)
+                {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::TerminalFindNext { backward: false },
+                        Target::Widget(*self.main_split.tab_id),
+                    ));
+                } else if self.editor.content.is_search() {
                     if let Some(parent_view_id) = self.editor.parent_view_id {
                         ctx.submit_command(Command::new(
                             LAPCE_COMMAND,
@@ -1580,7 +2314,15 @@ fn run_focus_command(
                 }
             }
             SearchBackward => {
-                if self.editor.content.is_search() {
+                if self.editor.content
+                    == BufferContent::Local(LocalBufferKind::TerminalFind)
+                {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::TerminalFindNext { backward: true },
+                        Target::Widget(*self.main_split.tab_id),
+                    ));
+                } else if self.editor.content.is_search() {
                     if let Some(parent_view_id) = self.editor.parent_view_id {
                         ctx.submit_command(Command::new(
                             LAPCE_COMMAND,
@@ -1618,7 +2360,19 @@ fn run_focus_command(
                 ));
             }
             ClearSearch => {
-                Arc::make_mut(&mut self.find).visual = false;
+                if self.editor.content
+                    == BufferContent::Local(LocalBufferKind::TerminalFind)
+                {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::CloseTerminalFind,
+                        Target::Widget(*self.main_split.tab_id),
+                    ));
+                    return CommandExecuted::Yes;
+                }
+                let find = Arc::make_mut(&mut self.find);
+                find.visual = false;
+                find.replace_visual = false;
                 let view_id =
                     if let Some(parent_view_id) = self.editor.parent_view_id {
                         parent_view_id
@@ -1694,6 +2448,40 @@ fn run_focus_command(
                     completion.run_focus_command(ctx, cmd);
                 }
             }
+            TogglePinHover => {
+                if !self.has_hover() {
+                    return CommandExecuted::No;
+                }
+                let hover = Arc::make_mut(&mut self.hover);
+                hover.pinned = !hover.pinned;
+            }
+            ToggleBreakpoint => {
+                let path = match self.doc.content() {
+                    BufferContent::File(path) => path.clone(),
+                    _ => return CommandExecuted::No,
+                };
+                let line = self
+                    .doc
+                    .buffer()
+                    .line_of_offset(self.editor.cursor.offset());
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::ToggleBreakpoint { path, line },
+                    Target::Auto,
+                ));
+            }
+            SignatureNext => {
+                if self.signature.len() < 2 {
+                    return CommandExecuted::No;
+                }
+                Arc::make_mut(&mut self.signature).next();
+            }
+            SignaturePrevious => {
+                if self.signature.len() < 2 {
+                    return CommandExecuted::No;
+                }
+                Arc::make_mut(&mut self.signature).previous();
+            }
             ListNext => {
                 if self.is_palette() {
                     ctx.submit_command(Command::new(
@@ -1780,6 +2568,12 @@ fn run_focus_command(
                         Arc::make_mut(&mut self.editor).snippet = None;
                     }
                     self.cancel_completion();
+                    self.show_snippet_choices();
+                }
+            }
+            ExpandEmmetAbbreviation => {
+                if !self.expand_emmet_abbreviation() {
+                    return CommandExecuted::No;
                 }
             }
             JumpToPrevSnippetPlaceholder => {
@@ -1806,6 +2600,7 @@ fn run_focus_command(
                                 .set_insert(selection);
                         }
                         self.cancel_completion();
+                        self.show_snippet_choices();
                     }
                 }
             }
@@ -1933,89 +2728,144 @@ fn run_focus_command(
                     );
                 }
             }
-            GotoTypeDefinition => {
+            ShowCallHierarchy => {
                 if let BufferContent::File(path) = self.doc.content() {
                     let offset = self.editor.cursor.offset();
+                    let position = self.doc.buffer().offset_to_position(offset);
                     let event_sink = ctx.get_external_handle();
+                    self.proxy.proxy_rpc.prepare_call_hierarchy(
+                        path.clone(),
+                        position,
+                        move |result| {
+                            if let Ok(
+                                ProxyResponse::PrepareCallHierarchyResponse {
+                                    plugin_id,
+                                    items,
+                                },
+                            ) = result
+                            {
+                                if let Some(item) = items.into_iter().next() {
+                                    let _ = event_sink.submit_command(
+                                        LAPCE_UI_COMMAND,
+                                        LapceUICommand::ShowCallHierarchy {
+                                            plugin_id,
+                                            item: Box::new(item),
+                                        },
+                                        Target::Auto,
+                                    );
+                                }
+                            }
+                        },
+                    );
+                }
+            }
+            ShowTypeHierarchy => {
+                if let BufferContent::File(path) = self.doc.content() {
+                    let offset = self.editor.cursor.offset();
                     let position = self.doc.buffer().offset_to_position(offset);
-                    let editor_view_id = self.editor.view_id;
-                    self.proxy.proxy_rpc.get_type_definition(
-                        offset,
+                    let event_sink = ctx.get_external_handle();
+                    self.proxy.proxy_rpc.prepare_type_hierarchy(
                         path.clone(),
                         position,
                         move |result| {
-                            if let Ok(ProxyResponse::GetTypeDefinition {
-                                definition,
-                                ..
-                            }) = result
+                            if let Ok(
+                                ProxyResponse::PrepareTypeHierarchyResponse {
+                                    plugin_id,
+                                    items,
+                                },
+                            ) = result
                             {
-                                match definition {
-                                    GotoTypeDefinitionResponse::Scalar(location) => {
-                                        let _ = event_sink.submit_command(
-                                            LAPCE_UI_COMMAND,
-                                            LapceUICommand::GotoDefinition {
-                                                editor_view_id,
-                                                offset,
-                                                location: EditorLocation {
-                                                    path: path_from_url(
-                                                        &location.uri,
-                                                    ),
-                                                    position: Some(
-                                                        location.range.start,
-                                                    ),
-                                                    scroll_offset: None,
-                                                    history: None,
-                                                },
-                                            },
-                                            Target::Auto,
-                                        );
-                                    }
-                                    GotoTypeDefinitionResponse::Array(locations) => {
-                                        let len = locations.len();
-                                        match len {
-                                            1 => {
-                                                let _ = event_sink.submit_command(
-                                                    LAPCE_UI_COMMAND,
-                                                    LapceUICommand::GotoDefinition {
-                                                        editor_view_id,
-                                                        offset,
-                                                        location: EditorLocation {
-                                                            path: path_from_url(
-                                                                &locations[0].uri,
-                                                            ),
-                                                            position: Some(
-                                                                locations[0]
-                                                                    .range
-                                                                    .start,
-                                                            ),
-                                                            scroll_offset: None,
-                                                            history: None,
-                                                        },
-                                                    },
-                                                    Target::Auto,
-                                                );
-                                            }
-                                            _ if len > 1 => {
-                                                let _ = event_sink.submit_command(
-                                                LAPCE_UI_COMMAND,
-                                                LapceUICommand::PaletteReferences(
-                                                    offset, locations,
-                                                ),
-                                                Target::Auto,
-                                            );
-                                            }
-                                            _ => (),
-                                        }
-                                    }
-                                    GotoTypeDefinitionResponse::Link(
-                                        _location_links,
-                                    ) => {}
+                                if let Some(item) = items.into_iter().next() {
+                                    let _ = event_sink.submit_command(
+                                        LAPCE_UI_COMMAND,
+                                        LapceUICommand::ShowTypeHierarchy {
+                                            plugin_id,
+                                            item: Box::new(item),
+                                        },
+                                        Target::Auto,
+                                    );
                                 }
                             }
                         },
                     );
                 }
             }
+            GotoTypeDefinition | GotoDeclaration | GotoImplementation => {
+                if let BufferContent::File(path) = self.doc.content() {
+                    let offset = self.editor.cursor.offset();
+                    let event_sink = ctx.get_external_handle();
+                    let position = self.doc.buffer().offset_to_position(offset);
+                    let editor_view_id = self.editor.view_id;
+                    let f = move |result: Result<ProxyResponse, RpcError>| {
+                        if let Ok(response) = result {
+                            process_goto_response(
+                                editor_view_id,
+                                offset,
+                                response,
+                                event_sink,
+                            );
+                        }
+                    };
+                    let path = path.clone();
+                    let proxy_rpc = &self.proxy.proxy_rpc;
+                    match cmd {
+                        GotoTypeDefinition => {
+                            proxy_rpc.get_type_definition(offset, path, position, f)
+                        }
+                        GotoDeclaration => {
+                            proxy_rpc.get_declaration(offset, path, position, f)
+                        }
+                        _ => proxy_rpc.get_implementation(offset, path, position, f),
+                    }
+                }
+            }
+            PeekDefinition | PeekReferences => {
+                if let BufferContent::File(path) = self.doc.content() {
+                    let offset = self.editor.cursor.offset();
+                    let event_sink = ctx.get_external_handle();
+                    let position = self.doc.buffer().offset_to_position(offset);
+                    let editor_view_id = self.editor.view_id;
+                    if cmd == &PeekDefinition {
+                        self.proxy.proxy_rpc.get_definition(
+                            offset,
+                            path.clone(),
+                            position,
+                            move |result| {
+                                if let Ok(ProxyResponse::GetDefinitionResponse {
+                                    definition,
+                                    ..
+                                }) = result
+                                {
+                                    process_peek_locations(
+                                        editor_view_id,
+                                        offset,
+                                        goto_response_locations(definition),
+                                        event_sink,
+                                    );
+                                }
+                            },
+                        );
+                    } else {
+                        self.proxy.proxy_rpc.get_references(
+                            path.clone(),
+                            position,
+                            move |result| {
+                                if let Ok(ProxyResponse::GetReferencesResponse {
+                                    references,
+                                }) = result
+                                {
+                                    process_peek_locations(
+                                        editor_view_id,
+                                        offset,
+                                        references,
+                                        event_sink,
+                                    );
+                                }
+                            },
+                        );
+                    }
+                }
+            }
             JumpLocationBackward => {
                 self.jump_location_backward(ctx);
             }
@@ -2023,62 +2873,107 @@ fn run_focus_command(
                 self.jump_location_forward(ctx);
             }
             NextError => {
-                self.next_error(ctx);
+                self.next_error(ctx, true);
+            }
+            PreviousError => {
+                self.next_error(ctx, false);
             }
             NextDiff => {
                 self.next_diff(ctx);
             }
+            NextOccurrence | PreviousOccurrence => {
+                let offset = self.editor.cursor.offset();
+                if let Some(offset) = self
+                    .doc
+                    .next_document_highlight(offset, cmd == &NextOccurrence)
+                {
+                    self.run_move_command(
+                        ctx,
+                        &lapce_core::movement::Movement::Offset(offset),
+                        None,
+                        Modifiers::empty(),
+                    );
+                }
+            }
             ToggleCodeLens => {
                 let editor = Arc::make_mut(&mut self.editor);
                 editor.view = match editor.view {
                     EditorView::Normal => EditorView::Lens,
                     EditorView::Lens => EditorView::Normal,
-                    EditorView::Diff(_) => return CommandExecuted::Yes,
+                    EditorView::Diff(_) | EditorView::Compare(..) => {
+                        return CommandExecuted::Yes
+                    }
                 };
             }
+            ToggleFold | Fold | Unfold | FoldAll | UnfoldAll | FoldComments
+            | FoldImports => {
+                let line = self
+                    .doc
+                    .buffer()
+                    .line_of_offset(self.editor.cursor.offset());
+                let doc = self.doc_mut();
+                match cmd {
+                    ToggleFold => doc.toggle_fold(line),
+                    Fold => doc.fold(line),
+                    Unfold => doc.unfold(line),
+                    FoldAll => doc.fold_all(),
+                    FoldComments => doc.fold_kind(FoldingRangeKind::Comment),
+                    FoldImports => doc.fold_kind(FoldingRangeKind::Imports),
+                    _ => doc.unfold_all(),
+                }
+                self.move_cursor_out_of_folds();
+            }
             FormatDocument => {
-                if let BufferContent::File(path) = self.doc.content() {
-                    let path = path.clone();
-                    let proxy = self.proxy.clone();
-                    let rev = self.doc.rev();
-                    let event_sink = ctx.get_external_handle();
-                    let (sender, receiver) = bounded(1);
-                    let tab_id = self.main_split.tab_id.clone();
-                    thread::spawn(move || {
-                        proxy.proxy_rpc.get_document_formatting(
-                            path.clone(),
-                            Box::new(move |result| {
-                                let _ = sender.send(result);
-                            }),
-                        );
-
-                        let result = receiver
-                            .recv_timeout(Duration::from_secs(1))
-                            .map_or_else(
-                                |e| Err(anyhow!("{}", e)),
-                                |v| {
-                                    v.map_err(|e| anyhow!("{:?}", e)).and_then(|r| {
-                                        if let ProxyResponse::GetDocumentFormatting {
-                                    edits,
-                                } = r
-                                {
-                                    Ok(edits)
-                                } else {
-                                    Err(anyhow!("wrong response"))
-                                }
-                                    })
-                                },
-                            );
-                        let _ = event_sink.submit_command(
-                            LAPCE_UI_COMMAND,
-                            LapceUICommand::DocumentFormat(path, rev, result),
-                            Target::Widget(*tab_id),
-                        );
-                    });
+                self.format(ctx, None);
+            }
+            FormatSelection => {
+                let range = self.selected_lines_range();
+                self.format(ctx, Some(range));
+            }
+            RunSelectionInTerminal => {
+                let text = self.selection_or_line_text();
+                if !text.trim().is_empty() {
+                    // Each line is entered like with the return key
+                    let mut input = text.replace("\r\n", "\n").replace('\n', "\r");
+                    if !input.ends_with('\r') {
+                        input.push('\r');
+                    }
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::RunInTerminal(input),
+                        Target::Widget(*self.main_split.tab_id),
+                    ));
+                }
+            }
+            Replace | ReplaceNext | ReplaceAll
+                if self.editor.parent_view_id.is_some() =>
+            {
+                // the inputs of the find bar pass them on to their editor
+                if let Some(parent_view_id) = self.editor.parent_view_id {
+                    ctx.submit_command(Command::new(
+                        LAPCE_COMMAND,
+                        LapceCommand {
+                            kind: CommandKind::Focus(cmd.clone()),
+                            data: None,
+                        },
+                        Target::Widget(parent_view_id),
+                    ));
                 }
             }
+            Replace => {
+                self.run_focus_command(ctx, &Search, count, mods);
+                Arc::make_mut(&mut self.find).replace_visual = true;
+            }
+            ReplaceNext => {
+                self.replace_next(ctx, mods);
+            }
+            ReplaceAll => {
+                self.replace_all();
+            }
             Search => {
-                Arc::make_mut(&mut self.find).visual = true;
+                let find = Arc::make_mut(&mut self.find);
+                find.visual = true;
+                find.replace_visual = false;
                 let region = match &self.editor.cursor.mode {
                     lapce_core::cursor::CursorMode::Normal(offset) => {
                         lapce_core::selection::SelRegion::caret(*offset)
@@ -2118,7 +3013,7 @@ fn run_focus_command(
                         Target::Widget(*self.main_split.tab_id),
                     ));
                 }
-                if let Some((find_view_id, _)) = self.editor.find_view_id {
+                if let Some((find_view_id, _, _)) = self.editor.find_view_id {
                     ctx.submit_command(Command::new(
                         LAPCE_COMMAND,
                         LapceCommand {
@@ -2150,10 +3045,13 @@ fn run_focus_command(
                 }
             }
             SaveAndExit => {
-                self.save(ctx, true);
+                self.save(ctx, true, true);
             }
             Save => {
-                self.save(ctx, false);
+                self.save(ctx, false, true);
+            }
+            SaveWithoutFormatting => {
+                self.save(ctx, false, false);
             }
             Rename => {
                 if let BufferContent::File(path) = self.doc.content() {
@@ -2200,17 +3098,21 @@ fn run_focus_command(
                 let new_name = new_name.trim();
                 if !new_name.is_empty() {
                     let event_sink = ctx.get_external_handle();
-                    let view_id = self.rename.from_editor;
+                    let tab_id = *self.main_split.tab_id;
+                    let new_name = new_name.to_string();
                     self.proxy.proxy_rpc.rename(
                         self.rename.path.clone(),
                         self.rename.position,
-                        new_name.to_string(),
+                        new_name.clone(),
                         move |result| {
                             if let Ok(ProxyResponse::Rename { edit }) = result {
                                 let _ = event_sink.submit_command(
                                     LAPCE_UI_COMMAND,
-                                    LapceUICommand::ApplyWorkspaceEdit(edit),
-                                    Target::Widget(view_id),
+                                    LapceUICommand::ShowRenamePreview {
+                                        new_name,
+                                        edit,
+                                    },
+                                    Target::Widget(tab_id),
                                 );
                             }
                         },
@@ -2225,16 +3127,119 @@ fn run_focus_command(
                     Target::Widget(self.rename.view_id),
                 ));
             }
+            DebugConsoleEvaluate
+            | DebugConsoleHistoryPrevious
+            | DebugConsoleHistoryNext
+            | DebugConsoleComplete => {
+                if self.editor.content
+                    != BufferContent::Local(LocalBufferKind::DebugConsole)
+                {
+                    return CommandExecuted::No;
+                }
+                let command = match cmd {
+                    DebugConsoleEvaluate => LapceUICommand::DebugConsoleEvaluate,
+                    DebugConsoleHistoryPrevious => {
+                        LapceUICommand::DebugConsoleHistory { previous: true }
+                    }
+                    DebugConsoleHistoryNext => {
+                        LapceUICommand::DebugConsoleHistory { previous: false }
+                    }
+                    _ => LapceUICommand::DebugConsoleComplete,
+                };
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    command,
+                    Target::Widget(*self.main_split.tab_id),
+                ));
+            }
             SelectNextSyntaxItem => {
                 self.run_selection_range_command(ctx, SelectionRangeDirection::Next)
             }
             SelectPreviousSyntaxItem => self
                 .run_selection_range_command(ctx, SelectionRangeDirection::Previous),
+            ExpandSelection => self.expand_selection(ctx),
+            ShrinkSelection => {
+                Arc::make_mut(&mut self.editor)
+                    .cursor
+                    .shrink_selection(self.doc.buffer());
+            }
             _ => return CommandExecuted::No,
         }
         CommandExecuted::Yes
     }
 
+    /// Expand the selection with the selection ranges from the language
+    /// server, which are asked for first unless they're already known for
+    /// the current selection, or else with the syntax tree
+    fn expand_selection(&mut self, ctx: &mut EventCtx) {
+        let rev = self.doc.rev();
+        let selection = self.editor.cursor.expandable_selection(self.doc.buffer());
+        let ranges = self
+            .editor
+            .expand_selection_ranges
+            .as_ref()
+            .filter(|ranges| ranges.rev == rev && ranges.selection == selection)
+            .cloned();
+
+        if ranges.is_none() {
+            if let BufferContent::File(path) = self.doc.content() {
+                let positions = selection
+                    .regions()
+                    .iter()
+                    .map(|region| self.doc.buffer().offset_to_position(region.min()))
+                    .collect();
+                let path = path.clone();
+                let view_id = self.editor.view_id;
+                let tab_id = *self.main_split.tab_id;
+                let event_sink = ctx.get_external_handle();
+                self.proxy.proxy_rpc.get_selection_range(
+                    path.clone(),
+                    positions,
+                    move |result| {
+                        // Without selection ranges the syntax tree is used
+                        let ranges = match result {
+                            Ok(ProxyResponse::GetSelectionRange { ranges }) => {
+                                ranges
+                            }
+                            _ => Vec::new(),
+                        };
+                        let _ = event_sink.submit_command(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::ExpandSelectionWithRanges {
+                                path,
+                                view_id,
+                                rev,
+                                selection,
+                                ranges,
+                            },
+                            Target::Widget(tab_id),
+                        );
+                    },
+                );
+                return;
+            }
+        }
+
+        let syntax = self.doc.syntax();
+        let editor = Arc::make_mut(&mut self.editor);
+        editor
+            .cursor
+            .expand_selection(self.doc.buffer(), |start, end| {
+                ranges
+                    .as_ref()
+                    .and_then(|ranges| ranges.expand(start, end))
+                    .or_else(|| {
+                        syntax.and_then(|syntax| syntax.expand_selection(start, end))
+                    })
+            });
+        if ranges.is_some() {
+            if let Some(ranges) = editor.expand_selection_ranges.as_mut() {
+                ranges.selection =
+                    editor.cursor.expandable_selection(self.doc.buffer());
+            }
+        }
+    }
+
     fn run_selection_range_command(
         &mut self,
         ctx: &mut EventCtx,
@@ -2344,13 +3349,24 @@ fn expect_char(&self) -> bool {
     fn check_condition(&self, condition: &str) -> bool {
         match condition {
             "search_focus" => {
-                self.editor.content == BufferContent::Local(LocalBufferKind::Search)
-                    && self.editor.parent_view_id.is_some()
+                (self.editor.content
+                    == BufferContent::Local(LocalBufferKind::Search)
+                    && self.editor.parent_view_id.is_some())
+                    || self.editor.content
+                        == BufferContent::Local(LocalBufferKind::TerminalFind)
             }
             "global_search_focus" => {
                 self.editor.content == BufferContent::Local(LocalBufferKind::Search)
                     && self.editor.parent_view_id.is_none()
             }
+            "find_replace_focus" => {
+                self.editor.content
+                    == BufferContent::Local(LocalBufferKind::FindReplace)
+            }
+            "global_replace_focus" => {
+                self.editor.content
+                    == BufferContent::Local(LocalBufferKind::SearchReplace)
+            }
             "input_focus" => self.editor.content.is_input(),
             "editor_focus" => match self.editor.content {
                 BufferContent::File(_) => true,
@@ -2366,11 +3382,17 @@ fn check_condition(&self, condition: &str) -> bool {
             "in_snippet" => self.editor.snippet.is_some(),
             "completion_focus" => self.has_completions(),
             "hover_focus" => self.has_hover(),
+            "signature_focus" => self.has_signature() && self.signature.len() > 1,
             "list_focus" => self.has_completions() || self.is_palette(),
             "rename_focus" => self.has_rename(),
+            "debug_console_focus" => {
+                self.editor.content
+                    == BufferContent::Local(LocalBufferKind::DebugConsole)
+            }
             "modal_focus" => {
                 (self.has_completions() && !self.config.lapce.modal)
                     || self.has_hover()
+                    || self.has_signature()
                     || self.is_palette()
                     || self.has_rename()
             }
@@ -2379,10 +3401,23 @@ fn check_condition(&self, condition: &str) -> bool {
     }
 
     fn receive_char(&mut self, ctx: &mut EventCtx, c: &str) {
+        if self.editor.view.is_read_only() {
+            return;
+        }
         if self.get_mode() == Mode::Insert {
+            let linked = self.linked_editing_selection(None, Some(c));
+            if let Some((_, selection)) = linked.as_ref() {
+                Arc::make_mut(&mut self.editor)
+                    .cursor
+                    .update_selection(self.doc.buffer(), selection.clone());
+            }
+
             let doc = Arc::make_mut(&mut self.doc);
             let cursor = &mut Arc::make_mut(&mut self.editor).cursor;
             let deltas = doc.do_insert(cursor, c, &self.config);
+            if let Some((index, _)) = linked {
+                self.keep_linked_editing_region(index);
+            }
 
             if !c
                 .chars()
@@ -2394,6 +3429,9 @@ fn receive_char(&mut self, ctx: &mut EventCtx, c: &str) {
             }
             self.cancel_hover();
             self.apply_deltas(&deltas);
+            self.update_signature(ctx, Some(c));
+            self.update_linked_editing(ctx, true);
+            self.format_on_type(ctx, c);
         } else if let Some(direction) = self.editor.inline_find.clone() {
             self.inline_find(ctx, direction.clone(), c);
             let editor = Arc::make_mut(&mut self.editor);
@@ -2410,6 +3448,16 @@ fn run_command(
         mods: Modifiers,
         _env: &Env,
     ) -> CommandExecuted {
+        if self.editor.view.is_read_only() {
+            match &command.kind {
+                CommandKind::Edit(_)
+                | CommandKind::Move(_)
+                | CommandKind::MotionMode(_)
+                | CommandKind::MultiSelection(_) => return CommandExecuted::Yes,
+                CommandKind::Focus(_) | CommandKind::Workbench(_) => {}
+            }
+        }
+
         let old_doc = self.doc.clone();
         let executed = match &command.kind {
             CommandKind::Edit(cmd) => self.run_edit_command(ctx, cmd),
@@ -2442,6 +3490,9 @@ pub struct TabRect {
     pub rect: Rect,
     pub close_rect: Rect,
     pub text_layout: PietTextLayout,
+    /// Whether a lock is shown after the name, for an editor that can't be
+    /// edited
+    pub read_only: bool,
 }
 
 #[derive(Clone)]
@@ -2471,27 +3522,92 @@ fn next_in_file_diff_offset<'a>(
     (file_diffs[0].0.as_ref(), file_diffs[0].1[0])
 }
 
-fn next_in_file_errors_offset(
-    position: Position,
-    path: &Path,
-    file_diagnostics: &[(&PathBuf, Vec<Position>)],
-) -> (PathBuf, Position) {
-    for (current_path, positions) in file_diagnostics {
-        if &path == current_path {
-            for error_position in positions {
-                if error_position.line > position.line
-                    || (error_position.line == position.line
-                        && error_position.character > position.character)
-                {
-                    return ((*current_path).clone(), *error_position);
-                }
-            }
+/// Go to the location of a type definition, declaration or implementation,
+/// or list the locations in the palette when there are several
+fn process_goto_response(
+    editor_view_id: WidgetId,
+    offset: usize,
+    response: ProxyResponse,
+    event_sink: ExtEventSink,
+) {
+    let response = match response {
+        ProxyResponse::GetTypeDefinition { definition, .. } => definition,
+        ProxyResponse::GetDeclaration { declaration, .. } => declaration,
+        ProxyResponse::GetImplementation { implementation, .. } => implementation,
+        _ => return,
+    };
+    let locations = goto_response_locations(response);
+    match locations.len() {
+        0 => {}
+        1 => {
+            let location = &locations[0];
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::GotoDefinition {
+                    editor_view_id,
+                    offset,
+                    location: EditorLocation {
+                        path: path_from_url(&location.uri),
+                        position: Some(location.range.start),
+                        scroll_offset: None,
+                        history: None,
+                    },
+                },
+                Target::Auto,
+            );
         }
-        if current_path > &path {
-            return ((*current_path).clone(), positions[0]);
+        _ => {
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::PaletteReferences(offset, locations),
+                Target::Auto,
+            );
         }
     }
-    ((*file_diagnostics[0].0).clone(), file_diagnostics[0].1[0])
+}
+
+fn goto_response_locations(response: GotoDefinitionResponse) -> Vec<Location> {
+    match response {
+        GotoDefinitionResponse::Scalar(location) => vec![location],
+        GotoDefinitionResponse::Array(locations) => locations,
+        GotoDefinitionResponse::Link(links) => links
+            .into_iter()
+            .map(|link| Location {
+                uri: link.target_uri,
+                range: link.target_selection_range,
+            })
+            .collect(),
+    }
+}
+
+/// Show the locations in the peek view of the editor
+fn process_peek_locations(
+    editor_view_id: WidgetId,
+    offset: usize,
+    locations: Vec<Location>,
+    event_sink: ExtEventSink,
+) {
+    if locations.is_empty() {
+        return;
+    }
+    let locations = locations
+        .iter()
+        .map(|location| EditorLocation {
+            path: path_from_url(&location.uri),
+            position: Some(location.range.start),
+            scroll_offset: None,
+            history: None,
+        })
+        .collect();
+    let _ = event_sink.submit_command(
+        LAPCE_UI_COMMAND,
+        LapceUICommand::ShowPeek {
+            editor_view_id,
+            offset,
+            locations,
+        },
+        Target::Auto,
+    );
 }
 
 fn process_get_references(
@@ -2528,7 +3644,7 @@ fn process_get_references(
     }
 }
 
-fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>> {
+pub fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>> {
     if let Some(changes) = edit.changes.as_ref() {
         return Some(changes.clone());
     }
@@ -2570,7 +3686,8 @@ fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>>
     Some(edits)
 }
 
-fn workspace_operation(op: &ResourceOp) -> Option<LapceUICommand> {
+/// The command doing the file operation of a workspace edit
+pub fn workspace_operation(op: &ResourceOp) -> Option<LapceUICommand> {
     Some(match op {
         ResourceOp::Create(p) => LapceUICommand::CreateFileOpen {
             path: p.uri.to_file_path().ok()?,
@@ -2585,8 +3702,24 @@ fn workspace_operation(op: &ResourceOp) -> Option<LapceUICommand> {
     })
 }
 
+/// The position of a `L12` or `L12,3` fragment of the target of a link,
+/// with the line and column starting at 1
+fn link_fragment_position(fragment: &str) -> Option<Position> {
+    let fragment = fragment.strip_prefix('L')?;
+    let (line, character) = match fragment.split_once(',') {
+        Some((line, character)) => (line, Some(character)),
+        None => (fragment, None),
+    };
+    let line = line.parse::<u32>().ok()?.saturating_sub(1);
+    let character = match character {
+        Some(character) => character.parse::<u32>().ok()?.saturating_sub(1),
+        None => 0,
+    };
+    Some(Position { line, character })
+}
+
 /// Check if a [`Url`] matches the path
-fn url_matches_path(path: &Path, url: &Url) -> bool {
+pub fn url_matches_path(path: &Path, url: &Url) -> bool {
     // TODO: Neither of these methods work for paths
     // on different filesystems (i.e. windows and linux),
     // as pathbuf is meant to represent a path on the host
@@ -2632,9 +3765,12 @@ fn show_completion(
 ) -> bool {
     let show_completion = match cmd {
         EditCommand::DeleteBackward
+        | EditCommand::DeleteWhitespaceBackward
         | EditCommand::DeleteForward
         | EditCommand::DeleteWordBackward
         | EditCommand::DeleteWordForward
+        | EditCommand::DeleteSubwordBackward
+        | EditCommand::DeleteSubwordForward
         | EditCommand::DeleteForwardAndInsert => {
             let start = match deltas.get(0).and_then(|delta| delta.0.els.get(0)) {
                 Some(xi_rope::DeltaElement::Copy(_, start)) => *start,
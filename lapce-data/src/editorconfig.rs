@@ -0,0 +1,356 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use globset::GlobBuilder;
+use lapce_core::{
+    buffer::Buffer, indent::IndentStyle, line_ending::LineEnding,
+    selection::Selection,
+};
+
+const EDITORCONFIG_FILE: &str = ".editorconfig";
+
+/// The properties from `.editorconfig` files that apply to a file, which
+/// take precedence over the global settings for its buffer
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EditorConfigProperties {
+    /// `true` to indent with tabs and `false` to indent with spaces
+    pub indent_with_tabs: Option<bool>,
+    pub indent_size: Option<u8>,
+    pub end_of_line: Option<LineEnding>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+}
+
+impl EditorConfigProperties {
+    /// Read the `.editorconfig` files in the folders of `path` and above,
+    /// up to the first one marked as root. The files closer to `path` take
+    /// precedence.
+    pub fn for_path(path: &Path) -> Self {
+        let mut files = Vec::new();
+        for dir in path.ancestors().skip(1) {
+            if let Ok(content) = std::fs::read_to_string(dir.join(EDITORCONFIG_FILE))
+            {
+                let file = EditorConfigFile::parse(&content);
+                let root = file.root;
+                files.push((dir.to_path_buf(), file));
+                if root {
+                    break;
+                }
+            }
+        }
+        Self::from_files(&files, path)
+    }
+
+    /// The properties for `path` from the given files, which are ordered
+    /// from the closest one to the farthest one
+    fn from_files(files: &[(PathBuf, EditorConfigFile)], path: &Path) -> Self {
+        let mut properties = HashMap::new();
+        for (dir, file) in files.iter().rev() {
+            if let Ok(relative) = path.strip_prefix(dir) {
+                let relative = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                file.apply(&relative, &mut properties);
+            }
+        }
+
+        let get = |key: &str| properties.get(key).map(|v| v.as_str());
+        let get_bool = |key: &str| match get(key) {
+            Some("true") => Some(true),
+            Some("false") => Some(false),
+            _ => None,
+        };
+        let get_size = |key: &str| {
+            get(key).and_then(|v| v.parse::<u8>().ok()).filter(|size| {
+                (1..=IndentStyle::LONGEST_INDENT.len() as u8).contains(size)
+            })
+        };
+
+        Self {
+            indent_with_tabs: match get("indent_style") {
+                Some("tab") => Some(true),
+                Some("space") => Some(false),
+                _ => None,
+            },
+            indent_size: get_size("indent_size").or_else(|| get_size("tab_width")),
+            end_of_line: match get("end_of_line") {
+                Some("lf") => Some(LineEnding::Lf),
+                Some("crlf") => Some(LineEnding::CrLf),
+                Some("cr") => Some(LineEnding::Cr),
+                _ => None,
+            },
+            trim_trailing_whitespace: get_bool("trim_trailing_whitespace"),
+            insert_final_newline: get_bool("insert_final_newline"),
+        }
+    }
+
+    /// The indentation to use in place of the one detected from the content
+    /// of the buffer
+    pub fn indent_style(&self, detected: IndentStyle) -> IndentStyle {
+        match (self.indent_with_tabs, self.indent_size) {
+            (Some(true), _) => IndentStyle::Tabs,
+            (Some(false), Some(size)) => IndentStyle::Spaces(size),
+            (Some(false), None) => match detected {
+                IndentStyle::Spaces(_) => detected,
+                IndentStyle::Tabs => IndentStyle::DEFAULT_INDENT,
+            },
+            (None, Some(size)) => match detected {
+                IndentStyle::Spaces(_) => IndentStyle::Spaces(size),
+                IndentStyle::Tabs => IndentStyle::Tabs,
+            },
+            (None, None) => detected,
+        }
+    }
+
+    /// The edits to make to the buffer before it's saved, for trimming
+    /// trailing whitespace, converting the line endings, and adding or
+    /// removing the final newline
+    pub fn save_edits(&self, buffer: &Buffer) -> Vec<(Selection, String)> {
+        let trim = self.trim_trailing_whitespace == Some(true);
+        let mut edits = Vec::new();
+        let last_line = buffer.last_line();
+        for line in 0..last_line + 1 {
+            let line_start = buffer.offset_of_line(line);
+            let content = buffer.line_content(line);
+            let body = content.trim_end_matches(['\n', '\r']);
+            let body_end = line_start + body.len();
+            let line_end = line_start + content.len();
+
+            if trim {
+                let trimmed = body.trim_end_matches([' ', '\t']);
+                if trimmed.len() < body.len() {
+                    edits.push((
+                        Selection::region(line_start + trimmed.len(), body_end),
+                        String::new(),
+                    ));
+                }
+            }
+
+            if body_end == line_end {
+                // The last line, which has no line ending
+                if self.insert_final_newline == Some(true) && !body.is_empty() {
+                    let ending = self.end_of_line.unwrap_or(buffer.line_ending());
+                    edits.push((
+                        Selection::caret(line_end),
+                        ending.as_str().to_string(),
+                    ));
+                }
+                continue;
+            }
+
+            let is_final = line_end == buffer.len();
+            if is_final && self.insert_final_newline == Some(false) {
+                edits.push((Selection::region(body_end, line_end), String::new()));
+            } else if let Some(ending) = self.end_of_line {
+                if &content[body.len()..] != ending.as_str() {
+                    edits.push((
+                        Selection::region(body_end, line_end),
+                        ending.as_str().to_string(),
+                    ));
+                }
+            }
+        }
+        edits
+    }
+}
+
+/// The content of a `.editorconfig` file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct EditorConfigFile {
+    root: bool,
+    /// The glob of each section with its properties, in order
+    sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl EditorConfigFile {
+    fn parse(content: &str) -> Self {
+        let mut file = EditorConfigFile::default();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let glob = line[1..line.len() - 1].to_string();
+                file.sections.push((glob, Vec::new()));
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim().to_lowercase();
+                let value = value.trim().to_lowercase();
+                match file.sections.last_mut() {
+                    Some((_, properties)) => properties.push((key, value)),
+                    None => {
+                        if key == "root" {
+                            file.root = value == "true";
+                        }
+                    }
+                }
+            }
+        }
+        file
+    }
+
+    /// Set the properties of the sections that match `relative`, the path
+    /// of a file relative to the folder of this file
+    fn apply(&self, relative: &str, properties: &mut HashMap<String, String>) {
+        for (glob, section) in &self.sections {
+            if !glob_matches(glob, relative) {
+                continue;
+            }
+            for (key, value) in section {
+                if value == "unset" {
+                    properties.remove(key);
+                } else {
+                    properties.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+/// A glob without a slash matches files of that name in any folder, while
+/// one with a slash is relative to the folder of the `.editorconfig` file
+fn glob_matches(glob: &str, relative: &str) -> bool {
+    let glob = if let Some(glob) = glob.strip_prefix('/') {
+        glob.to_string()
+    } else if glob.contains('/') {
+        glob.to_string()
+    } else {
+        format!("**/{glob}")
+    };
+    GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher().is_match(relative))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use xi_rope::Rope;
+
+    use super::*;
+
+    const ROOT: &str = "
+root = true
+
+[*]
+indent_style = space
+indent_size = 2
+end_of_line = lf
+insert_final_newline = true
+
+[*.{rs,toml}]
+indent_size = 4
+trim_trailing_whitespace = true
+
+[Makefile]
+indent_style = tab
+";
+
+    const NESTED: &str = "
+[/generated/**]
+insert_final_newline = unset
+
+[*.rs]
+end_of_line = CRLF
+";
+
+    #[test]
+    fn test_properties() {
+        let files = vec![
+            (PathBuf::from("/p/src"), EditorConfigFile::parse(NESTED)),
+            (PathBuf::from("/p"), EditorConfigFile::parse(ROOT)),
+        ];
+        assert!(files[1].1.root);
+
+        let properties =
+            EditorConfigProperties::from_files(&files, Path::new("/p/src/main.rs"));
+        assert_eq!(
+            properties,
+            EditorConfigProperties {
+                indent_with_tabs: Some(false),
+                indent_size: Some(4),
+                end_of_line: Some(LineEnding::CrLf),
+                trim_trailing_whitespace: Some(true),
+                insert_final_newline: Some(true),
+            }
+        );
+
+        let properties = EditorConfigProperties::from_files(
+            &files,
+            Path::new("/p/src/generated/a.js"),
+        );
+        assert_eq!(properties.indent_size, Some(2));
+        assert_eq!(properties.insert_final_newline, None);
+        assert_eq!(properties.trim_trailing_whitespace, None);
+
+        let properties =
+            EditorConfigProperties::from_files(&files, Path::new("/p/lib/Makefile"));
+        assert_eq!(
+            properties.indent_style(IndentStyle::Spaces(8)),
+            IndentStyle::Tabs
+        );
+    }
+
+    #[test]
+    fn test_indent_style() {
+        let properties = EditorConfigProperties {
+            indent_size: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            properties.indent_style(IndentStyle::Spaces(4)),
+            IndentStyle::Spaces(2)
+        );
+        assert_eq!(
+            properties.indent_style(IndentStyle::Tabs),
+            IndentStyle::Tabs
+        );
+    }
+
+    #[test]
+    fn test_save_edits() {
+        let apply = |properties: &EditorConfigProperties, text: &str| {
+            let mut buffer = Buffer::new("");
+            buffer.init_content(Rope::from(text));
+            buffer.detect_line_ending();
+            let edits = properties.save_edits(&buffer);
+            let edits = edits
+                .iter()
+                .map(|(selection, text)| (selection, text.as_str()))
+                .collect::<Vec<_>>();
+            buffer.edit(&edits, lapce_core::editor::EditType::Other);
+            buffer.to_string()
+        };
+
+        let properties = EditorConfigProperties {
+            end_of_line: Some(LineEnding::Lf),
+            trim_trailing_whitespace: Some(true),
+            insert_final_newline: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(apply(&properties, "a  \r\nb\t\n\nc "), "a\nb\n\nc\n");
+        assert_eq!(apply(&properties, "a\n"), "a\n");
+        assert_eq!(apply(&properties, ""), "");
+
+        let properties = EditorConfigProperties {
+            insert_final_newline: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(apply(&properties, "a \r\nb\r\n"), "a \r\nb");
+
+        let properties = EditorConfigProperties {
+            insert_final_newline: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(apply(&properties, "a\r\nb"), "a\r\nb\r\n");
+    }
+}
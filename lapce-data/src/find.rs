@@ -1,5 +1,6 @@
 use lapce_core::{
     selection::{InsertDrift, SelRegion, Selection},
+    transform::preserve_case,
     word::WordCursor,
 };
 use regex::{Regex, RegexBuilder};
@@ -60,6 +61,16 @@ pub struct Find {
 
     pub visual: bool,
 
+    /// The replace input of the find bar is shown
+    pub replace_visual: bool,
+
+    /// The text the find bar replaces the occurrences with
+    pub replace: String,
+
+    /// Whether the replacement follows the case of each occurrence, so that
+    /// replacing "foo" with "bar" also turns "Foo" into "Bar"
+    pub preserve_case: bool,
+
     /// The currently active search string.
     pub search_string: Option<String>,
 
@@ -86,6 +97,9 @@ pub fn new(id: usize) -> Find {
             regex: None,
             whole_words: false,
             visual: false,
+            replace_visual: false,
+            replace: String::new(),
+            preserve_case: false,
             occurrences: Selection::new(),
         }
     }
@@ -279,6 +293,44 @@ pub fn next(
         None
     }
 
+    /// All the occurrences of the search in the text, in order
+    pub fn all(&self, text: &Rope) -> Vec<(usize, usize)> {
+        let search_string = match self.search_string.as_ref() {
+            Some(search_string) => search_string,
+            None => return Vec::new(),
+        };
+        let mut occurrences = Vec::new();
+        let mut raw_lines = text.lines_raw(0..text.len());
+        let mut find_cursor = Cursor::new(text, 0);
+        while let Some(start) = find(
+            &mut find_cursor,
+            &mut raw_lines,
+            self.case_matching,
+            search_string,
+            self.regex.as_ref(),
+        ) {
+            let end = find_cursor.pos();
+            raw_lines = text.lines_raw(find_cursor.pos()..text.len());
+            if start == end {
+                break;
+            }
+            if self.whole_words && !self.is_matching_whole_words(text, start, end) {
+                continue;
+            }
+            occurrences.push((start, end));
+        }
+        occurrences
+    }
+
+    /// The text an occurrence is replaced with
+    pub fn replacement(&self, occurrence: &str) -> String {
+        if self.preserve_case {
+            preserve_case(occurrence, &self.replace)
+        } else {
+            self.replace.clone()
+        }
+    }
+
     /// Execute the search on the provided text in the range provided by `start` and `end`.
     pub fn update_find(
         &mut self,
@@ -1,6 +1,7 @@
 use std::{
     cell::RefCell,
     collections::HashMap,
+    path::{Path, PathBuf},
     rc::Rc,
     sync::{atomic, Arc},
 };
@@ -26,6 +27,42 @@
     document::{BufferContent, Document, TextLayoutCache, TextLayoutLine},
 };
 
+/// The prefix of the history versions that hold the content of another file,
+/// which the document is compared with
+const COMPARE_VERSION_PREFIX: &str = "compare:";
+
+/// The history versions of the sides of a merge of the unsaved changes of a
+/// document with the file that was changed on disk, which are the text as
+/// last loaded or saved, the unsaved text and the file on disk
+pub const MERGE_BASE_VERSION: &str = "merge:base";
+pub const MERGE_MINE_VERSION: &str = "merge:mine";
+pub const MERGE_THEIRS_VERSION: &str = "merge:theirs";
+
+/// The name of the side of a merge that the history `version` holds, if it
+/// is one
+pub fn merge_side_name(version: &str) -> Option<&'static str> {
+    match version {
+        MERGE_BASE_VERSION => Some("Base"),
+        MERGE_MINE_VERSION => Some("Unsaved"),
+        MERGE_THEIRS_VERSION => Some("On Disk"),
+        _ => None,
+    }
+}
+
+/// The history version of the file at `path` when a document is compared
+/// with it
+pub fn compare_version(path: &Path) -> String {
+    format!("{COMPARE_VERSION_PREFIX}{}", path.display())
+}
+
+/// The file that a document is compared with in the history `version`, if
+/// it's a comparison
+pub fn compared_path(version: &str) -> Option<PathBuf> {
+    version
+        .strip_prefix(COMPARE_VERSION_PREFIX)
+        .map(PathBuf::from)
+}
+
 #[derive(Clone)]
 pub struct DocumentHistory {
     version: String,
@@ -208,6 +245,7 @@ pub fn trigger_update_change(&self, doc: &Document) {
             let rev = doc.rev();
             let atomic_rev = doc.buffer().atomic_rev();
             let path = path.clone();
+            let version = self.version.clone();
             let left_rope = self.buffer.as_ref().unwrap().text().clone();
             let right_rope = doc.buffer().text().clone();
             let event_sink = doc.event_sink.clone();
@@ -232,7 +270,7 @@ pub fn trigger_update_change(&self, doc: &Document) {
                         id,
                         path,
                         rev,
-                        history: "head".to_string(),
+                        history: version,
                         changes: Arc::new(changes),
                     },
                     Target::Widget(tab_id),
@@ -241,6 +279,10 @@ pub fn trigger_update_change(&self, doc: &Document) {
         }
     }
 
+    pub fn buffer(&self) -> Option<&Buffer> {
+        self.buffer.as_ref()
+    }
+
     pub fn changes(&self) -> &[DiffLines] {
         &self.changes
     }
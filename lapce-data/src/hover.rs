@@ -43,6 +43,9 @@ pub struct HoverData {
     pub items: Arc<Vec<RichText>>,
     /// The text for the diagnostic(s) at the position
     pub diagnostic_content: Option<RichText>,
+    /// Whether the hover stays open when the mouse leaves it, until it's
+    /// closed
+    pub pinned: bool,
 }
 
 impl HoverData {
@@ -61,6 +64,7 @@ pub fn new() -> Self {
 
             items: Arc::new(Vec::new()),
             diagnostic_content: None,
+            pinned: false,
         }
     }
 
@@ -88,6 +92,7 @@ pub fn cancel(&mut self) {
         }
 
         self.status = HoverStatus::Inactive;
+        self.pinned = false;
         Arc::make_mut(&mut self.items).clear();
     }
 
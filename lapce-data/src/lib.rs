@@ -1,35 +1,50 @@
 pub mod about;
 pub mod alert;
+pub mod call_hierarchy;
 pub mod command;
 pub mod completion;
 pub mod config;
 pub mod container;
 pub mod data;
 pub mod db;
+pub mod debug;
+pub mod debug_console;
 pub mod document;
 pub mod editor;
+pub mod editorconfig;
 pub mod explorer;
 pub mod find;
 pub mod history;
 pub mod hover;
 pub mod keypress;
 pub mod list;
+pub mod lsp_log;
 pub mod markdown;
 pub mod menu;
 pub mod palette;
 pub mod panel;
+pub mod peek;
 pub mod picker;
 pub mod plugin;
 pub mod problem;
 pub mod proxy;
+pub mod release_notes;
 pub mod rename;
+pub mod rename_preview;
 pub mod rich_text;
 pub mod search;
 pub mod selection_range;
 pub mod settings;
+pub mod shell_integration;
 pub mod signature;
+pub mod snippet;
 pub mod source_control;
 pub mod split;
+pub mod task;
 pub mod terminal;
+pub mod terminal_link;
 pub mod title;
+pub mod type_hierarchy;
+pub mod undo_history;
+pub mod undo_tree;
 pub mod update;
@@ -0,0 +1,186 @@
+use chrono::Local;
+use druid::WidgetId;
+use lapce_rpc::{core::LspLogKind, plugin::PluginId};
+
+/// The number of lines kept for each language server, after which the
+/// oldest lines are dropped
+const MAX_LINES: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LspLogLine {
+    pub kind: LspLogKind,
+    /// Whether it's the first line of a message, which has the time
+    pub header: bool,
+    pub text: String,
+}
+
+/// The log of a language server, with one line for each line of the
+/// messages
+#[derive(Clone)]
+pub struct LspLogServer {
+    pub plugin_id: PluginId,
+    pub name: String,
+    pub lines: im::Vector<LspLogLine>,
+}
+
+#[derive(Clone)]
+pub struct LspLogData {
+    pub widget_id: WidgetId,
+    pub split_id: WidgetId,
+    pub content_id: WidgetId,
+    pub servers: Vec<LspLogServer>,
+    /// The index of the server whose log is shown
+    pub active: usize,
+    /// Whether the messages exchanged with the servers are logged, besides
+    /// what they write to stderr
+    pub trace: bool,
+}
+
+impl LspLogData {
+    pub fn new() -> Self {
+        Self {
+            widget_id: WidgetId::next(),
+            split_id: WidgetId::next(),
+            content_id: WidgetId::next(),
+            servers: Vec::new(),
+            active: 0,
+            trace: false,
+        }
+    }
+
+    /// Add the message to the log of the server, with the time it arrived
+    pub fn push(
+        &mut self,
+        plugin_id: PluginId,
+        server: &str,
+        kind: LspLogKind,
+        message: &str,
+    ) {
+        let time = Local::now().format("%H:%M:%S%.3f").to_string();
+        self.push_at(plugin_id, server, kind, message, &time);
+    }
+
+    fn push_at(
+        &mut self,
+        plugin_id: PluginId,
+        server: &str,
+        kind: LspLogKind,
+        message: &str,
+        time: &str,
+    ) {
+        let i = match self
+            .servers
+            .iter()
+            .position(|server| server.plugin_id == plugin_id)
+        {
+            Some(i) => i,
+            None => {
+                self.servers.push(LspLogServer {
+                    plugin_id,
+                    name: server.to_string(),
+                    lines: im::Vector::new(),
+                });
+                self.servers.len() - 1
+            }
+        };
+        let server = &mut self.servers[i];
+
+        let label = match kind {
+            LspLogKind::Stderr => "Stderr",
+            LspLogKind::Trace => "Trace",
+        };
+        for (i, line) in message.lines().enumerate() {
+            let text = if i == 0 {
+                format!("[{label} - {time}] {line}")
+            } else {
+                line.to_string()
+            };
+            server.lines.push_back(LspLogLine {
+                kind,
+                header: i == 0,
+                text,
+            });
+        }
+        while server.lines.len() > MAX_LINES {
+            server.lines.pop_front();
+        }
+    }
+
+    pub fn active_server(&self) -> Option<&LspLogServer> {
+        self.servers.get(self.active)
+    }
+
+    pub fn clear_active(&mut self) {
+        if let Some(server) = self.servers.get_mut(self.active) {
+            server.lines.clear();
+        }
+    }
+}
+
+impl Default for LspLogData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lsp_log_push() {
+        let mut log = LspLogData::new();
+        log.push_at(
+            PluginId(1),
+            "rust-analyzer",
+            LspLogKind::Trace,
+            "Sending request 'initialize - (0)'.\nParams: {}",
+            "10:00:00.000",
+        );
+        log.push_at(
+            PluginId(2),
+            "clangd",
+            LspLogKind::Stderr,
+            "I[10:00:00.000] clangd version 14",
+            "10:00:00.001",
+        );
+
+        assert_eq!(log.servers.len(), 2);
+        let server = log.active_server().unwrap();
+        assert_eq!(server.name, "rust-analyzer");
+        assert_eq!(
+            server
+                .lines
+                .iter()
+                .map(|l| l.text.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "[Trace - 10:00:00.000] Sending request 'initialize - (0)'.",
+                "Params: {}",
+            ]
+        );
+        assert!(!server.lines[1].header);
+        assert_eq!(log.servers[1].lines[0].kind, LspLogKind::Stderr);
+
+        log.clear_active();
+        assert!(log.servers[0].lines.is_empty());
+        assert_eq!(log.servers[1].lines.len(), 1);
+    }
+
+    #[test]
+    fn test_lsp_log_drops_old_lines() {
+        let mut log = LspLogData::new();
+        for i in 0..MAX_LINES + 2 {
+            log.push_at(
+                PluginId(1),
+                "server",
+                LspLogKind::Stderr,
+                &i.to_string(),
+                "10:00:00.000",
+            );
+        }
+        let lines = &log.servers[0].lines;
+        assert_eq!(lines.len(), MAX_LINES);
+        assert_eq!(lines[0].text, "[Stderr - 10:00:00.000] 2");
+    }
+}
@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use druid::{FontStyle, FontWeight};
+use druid::{Command, FontStyle, FontWeight, Target};
 use lapce_core::{language::LapceLanguage, syntax::Syntax};
 use lsp_types::MarkedString;
 use pulldown_cmark::{CodeBlockKind, Tag};
@@ -8,12 +8,13 @@
 use xi_rope::Rope;
 
 use crate::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
     config::{Config, LapceTheme},
     rich_text::{AttributesAdder, RichText, RichTextBuilder},
 };
 
 pub fn parse_markdown(text: &str, config: &Config) -> RichText {
-    use pulldown_cmark::{CowStr, Event, Options, Parser};
+    use pulldown_cmark::{Event, Options, Parser};
 
     let mut builder = RichTextBuilder::new();
     builder.set_line_height(1.5);
@@ -35,7 +36,8 @@ pub fn parse_markdown(text: &str, config: &Config) -> RichText {
             | Options::ENABLE_TASKLISTS
             | Options::ENABLE_HEADING_ATTRIBUTES,
     );
-    let mut last_text = CowStr::from("");
+    // The text of the code block we're in, to highlight it once it ends
+    let mut code_block_text = String::new();
     // Whether we should add a newline on the next entry
     // This is used so that we don't emit newlines at the very end of the generation
     let mut add_newline = false;
@@ -79,9 +81,10 @@ pub fn parse_markdown(text: &str, config: &Config) -> RichText {
                         let syntax = language.map(Syntax::from_language);
 
                         let styles = syntax.and_then(|mut syntax| {
-                            syntax.parse(0, Rope::from(&last_text), None);
+                            syntax.parse(0, Rope::from(&code_block_text), None);
                             syntax.styles
                         });
+                        code_block_text.clear();
 
                         if let Some(styles) = styles {
                             for (range, style) in styles.iter() {
@@ -111,7 +114,9 @@ pub fn parse_markdown(text: &str, config: &Config) -> RichText {
             Event::Text(text) => {
                 builder.push(&text);
                 pos += text.len();
-                last_text = text;
+                if let Some((_, Tag::CodeBlock(_))) = tag_stack.last() {
+                    code_block_text.push_str(&text);
+                }
             }
             Event::Code(text) => {
                 builder.push(&text).font_family(config.editor.font_family());
@@ -197,11 +202,17 @@ fn add_attribute_for_tag(tag: &Tag, mut attrs: AttributesAdder, config: &Config)
             attrs.weight(FontWeight::BOLD);
         }
         // TODO: Strikethrough support
-        Tag::Link(_link_type, _target, _title) => {
-            // TODO: Link support
-            attrs.underline(true).text_color(
-                config.get_color_unchecked(LapceTheme::EDITOR_LINK).clone(),
-            );
+        Tag::Link(_link_type, target, _title) => {
+            attrs
+                .underline(true)
+                .text_color(
+                    config.get_color_unchecked(LapceTheme::EDITOR_LINK).clone(),
+                )
+                .link(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::OpenURI(target.to_string()),
+                    Target::Auto,
+                ));
         }
         // All other tags are currently ignored
         _ => {}
@@ -217,6 +228,11 @@ fn should_add_newline_after_tag(tag: &Tag) -> bool {
 }
 
 fn md_language_to_lapce_language(lang: &str) -> Option<LapceLanguage> {
-    // TODO: There are many other names commonly used that should be supported
-    LapceLanguage::from_str(lang).ok()
+    // The language can be followed by attributes, like `rust,ignore`
+    let lang = lang.split(|c: char| c == ',' || c.is_whitespace()).next()?;
+    // Code blocks are often marked with the extension of the language,
+    // like `rs` or `py`, instead of its name
+    LapceLanguage::from_str(lang)
+        .ok()
+        .or_else(|| LapceLanguage::from_extension(lang))
 }
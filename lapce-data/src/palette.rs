@@ -7,9 +7,15 @@
 use fuzzy_matcher::FuzzyMatcher;
 use itertools::Itertools;
 use lapce_core::command::{EditCommand, FocusCommand};
+use lapce_core::indent::IndentStyle;
 use lapce_core::language::LapceLanguage;
+use lapce_core::line_ending::LineEnding;
 use lapce_core::mode::Mode;
+use lapce_rpc::buffer::ENCODINGS;
+use lapce_rpc::dap_types::{ProcessInfo, RunDebugConfig};
+use lapce_rpc::plugin::PluginId;
 use lapce_rpc::proxy::ProxyResponse;
+use lapce_rpc::terminal::{TermId, TerminalProfile};
 use lsp_types::{DocumentSymbolResponse, Position, Range, SymbolKind};
 use std::cmp::Ordering;
 use std::collections::HashSet;
@@ -19,11 +25,13 @@
 
 use crate::command::CommandKind;
 use crate::data::{LapceWorkspace, LapceWorkspaceType};
+use crate::debug::{BreakpointOptionEdit, LaunchConfigs, LAUNCH_CONFIGS_PATH};
 use crate::document::BufferContent;
 use crate::editor::EditorLocation;
 use crate::list::ListData;
 use crate::panel::PanelKind;
 use crate::proxy::path_from_url;
+use crate::task::{Task, TaskConfigs, TASKS_PATH};
 use crate::{
     command::LAPCE_UI_COMMAND,
     command::{CommandExecuted, LAPCE_COMMAND},
@@ -33,7 +41,7 @@
     find::Find,
     keypress::{KeyPressData, KeyPressFocus},
     proxy::LapceProxy,
-    terminal::TerminalSplitData,
+    terminal::{TerminalLaunch, TerminalSplitData, TERMINAL_COLORS, TERMINAL_ICONS},
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -49,6 +57,42 @@ pub enum PaletteType {
     Theme,
     SshHost,
     Language,
+    ReopenWithEncoding,
+    SaveWithEncoding,
+    LineEnding,
+    Indentation,
+    /// The files that the active file can be compared with
+    CompareFile,
+    /// The running language servers, to restart or stop one of them
+    RestartLanguageServer,
+    StopLanguageServer,
+    /// The debug configurations of the workspace, to debug the program of
+    /// one of them
+    RunAndDebug,
+    /// The running processes, to attach the debugger of the configuration
+    /// to one of them
+    AttachProcess(RunDebugConfig),
+    /// The terminal profiles, to start a terminal with one of them
+    TerminalProfile,
+    /// The tasks of the workspace, to run one of them in a terminal
+    RunTask,
+    /// The condition, hit count or log message of a breakpoint, which is
+    /// typed in
+    EditBreakpoint(BreakpointOptionEdit),
+    /// A watch expression to add, or to change the one at the index to
+    WatchExpression {
+        index: Option<usize>,
+        expression: String,
+    },
+    /// The name to give the terminal, which is typed in
+    RenameTerminal {
+        term_id: TermId,
+        name: String,
+    },
+    /// The colors to mark the header of the terminal with
+    TerminalColor(TermId),
+    /// The icons to give the header of the terminal
+    TerminalIcon(TermId),
 }
 
 impl PaletteType {
@@ -65,6 +109,22 @@ fn string(&self) -> String {
             PaletteType::Theme => "".to_string(),
             PaletteType::SshHost => "".to_string(),
             PaletteType::Language => "".to_string(),
+            PaletteType::ReopenWithEncoding => "".to_string(),
+            PaletteType::SaveWithEncoding => "".to_string(),
+            PaletteType::LineEnding => "".to_string(),
+            PaletteType::Indentation => "".to_string(),
+            PaletteType::CompareFile => "".to_string(),
+            PaletteType::RestartLanguageServer => "".to_string(),
+            PaletteType::StopLanguageServer => "".to_string(),
+            PaletteType::RunAndDebug => "".to_string(),
+            PaletteType::AttachProcess(_) => "".to_string(),
+            PaletteType::TerminalProfile => "".to_string(),
+            PaletteType::RunTask => "".to_string(),
+            PaletteType::EditBreakpoint(edit) => edit.value.clone(),
+            PaletteType::WatchExpression { expression, .. } => expression.clone(),
+            PaletteType::RenameTerminal { name, .. } => name.clone(),
+            PaletteType::TerminalColor(_) => "".to_string(),
+            PaletteType::TerminalIcon(_) => "".to_string(),
         }
     }
 
@@ -86,7 +146,23 @@ fn get_palette_type(current_type: &PaletteType, input: &str) -> PaletteType {
             PaletteType::Reference
             | PaletteType::SshHost
             | PaletteType::Theme
-            | PaletteType::Language => {
+            | PaletteType::Language
+            | PaletteType::ReopenWithEncoding
+            | PaletteType::SaveWithEncoding
+            | PaletteType::LineEnding
+            | PaletteType::Indentation
+            | PaletteType::CompareFile
+            | PaletteType::RestartLanguageServer
+            | PaletteType::StopLanguageServer
+            | PaletteType::RunAndDebug
+            | PaletteType::AttachProcess(_)
+            | PaletteType::TerminalProfile
+            | PaletteType::RunTask
+            | PaletteType::EditBreakpoint(_)
+            | PaletteType::WatchExpression { .. }
+            | PaletteType::RenameTerminal { .. }
+            | PaletteType::TerminalColor(_)
+            | PaletteType::TerminalIcon(_) => {
                 return current_type.clone();
             }
             _ => (),
@@ -122,6 +198,7 @@ pub enum PaletteStatus {
 #[derive(Clone, Debug, PartialEq)]
 pub enum PaletteItemContent {
     File(PathBuf, PathBuf),
+    CompareFile(PathBuf, PathBuf),
     Line(usize, String),
     TerminalLine(i32, String),
     DocumentSymbol {
@@ -143,9 +220,60 @@ pub enum PaletteItemContent {
     Command(LapceCommand),
     Theme(String),
     Language(String),
+    /// An encoding to reopen the file with, or to save it with
+    Encoding {
+        name: String,
+        save: bool,
+    },
+    LineEnding(LineEnding),
+    /// An indentation for new lines, or `None` to detect it from the content
+    Indentation(Option<IndentStyle>),
+    /// A running language server to restart, or to stop
+    LanguageServer {
+        plugin_id: PluginId,
+        name: String,
+        restart: bool,
+    },
+    RunAndDebug(RunDebugConfig),
+    /// Launch configurations that start together
+    RunCompound {
+        name: String,
+        configs: Vec<RunDebugConfig>,
+    },
+    /// A running process to attach the debugger of the configuration to
+    AttachProcess {
+        process: ProcessInfo,
+        config: RunDebugConfig,
+    },
+    /// A terminal profile to start a terminal with
+    TerminalProfile {
+        name: String,
+        profile: TerminalProfile,
+    },
+    /// A task to run in a new terminal
+    RunTask(Task),
+    /// A color to mark the header of a terminal with, or `None` for no
+    /// color
+    TerminalColor {
+        term_id: TermId,
+        color: Option<String>,
+    },
+    /// An icon to give the header of a terminal, or `None` for the icon of
+    /// its profile
+    TerminalIcon {
+        term_id: TermId,
+        icon: Option<String>,
+    },
 }
 
 impl PaletteItemContent {
+    pub fn indentation_label(indent_style: Option<IndentStyle>) -> String {
+        match indent_style {
+            Some(indent_style) => indent_style.label(),
+            None => "Detect From Content".to_string(),
+        }
+    }
+
     fn select(
         &self,
         ctx: &mut EventCtx,
@@ -162,6 +290,15 @@ fn select(
                     ));
                 }
             }
+            PaletteItemContent::CompareFile(_, full_path) => {
+                if !preview {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::CompareActiveFileWith(full_path.clone()),
+                        Target::Auto,
+                    ));
+                }
+            }
             PaletteItemContent::DocumentSymbol { range, .. } => {
                 let editor_id = if preview {
                     Some(preview_editor_id)
@@ -244,6 +381,125 @@ fn select(
                     ))
                 }
             }
+            PaletteItemContent::Encoding { name, save } => {
+                if !preview {
+                    let name = name.to_string();
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        if *save {
+                            LapceUICommand::SaveWithEncoding(name)
+                        } else {
+                            LapceUICommand::ReopenWithEncoding(name)
+                        },
+                        Target::Auto,
+                    ))
+                }
+            }
+            PaletteItemContent::LineEnding(line_ending) => {
+                if !preview {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::SetLineEnding(*line_ending),
+                        Target::Auto,
+                    ))
+                }
+            }
+            PaletteItemContent::Indentation(indent_style) => {
+                if !preview {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::SetIndentStyle(*indent_style),
+                        Target::Auto,
+                    ))
+                }
+            }
+            PaletteItemContent::LanguageServer {
+                plugin_id, restart, ..
+            } => {
+                if !preview {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        if *restart {
+                            LapceUICommand::RestartLanguageServer(*plugin_id)
+                        } else {
+                            LapceUICommand::StopLanguageServer(*plugin_id)
+                        },
+                        Target::Auto,
+                    ))
+                }
+            }
+            PaletteItemContent::RunAndDebug(config) => {
+                if !preview {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::DebugStart(config.clone()),
+                        Target::Auto,
+                    ))
+                }
+            }
+            PaletteItemContent::RunCompound { configs, .. } => {
+                if !preview {
+                    for config in configs {
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::DebugStart(config.clone()),
+                            Target::Auto,
+                        ))
+                    }
+                }
+            }
+            PaletteItemContent::AttachProcess { process, config } => {
+                if !preview {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::DebugAttachProcess {
+                            config: config.clone(),
+                            pid: process.pid,
+                        },
+                        Target::Auto,
+                    ))
+                }
+            }
+            PaletteItemContent::TerminalProfile { name, .. } => {
+                if !preview {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::NewTerminal(TerminalLaunch::Profile(Some(
+                            name.clone(),
+                        ))),
+                        Target::Auto,
+                    ))
+                }
+            }
+            PaletteItemContent::TerminalColor { term_id, color } => {
+                if !preview {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::SetTerminalColor(*term_id, color.clone()),
+                        Target::Auto,
+                    ))
+                }
+            }
+            PaletteItemContent::TerminalIcon { term_id, icon } => {
+                if !preview {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::SetTerminalIcon(*term_id, icon.clone()),
+                        Target::Auto,
+                    ))
+                }
+            }
+            PaletteItemContent::RunTask(task) => {
+                if !preview {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::NewTerminal(TerminalLaunch::Task(
+                            task.clone(),
+                        )),
+                        Target::Auto,
+                    ))
+                }
+            }
             PaletteItemContent::Command(command) => {
                 if !preview {
                     ctx.submit_command(Command::new(
@@ -474,6 +730,22 @@ pub fn get_input(&self) -> &str {
             PaletteType::Reference => &self.input,
             PaletteType::Theme => &self.input,
             PaletteType::Language => &self.input,
+            PaletteType::ReopenWithEncoding => &self.input,
+            PaletteType::SaveWithEncoding => &self.input,
+            PaletteType::LineEnding => &self.input,
+            PaletteType::Indentation => &self.input,
+            PaletteType::CompareFile => &self.input,
+            PaletteType::RestartLanguageServer => &self.input,
+            PaletteType::StopLanguageServer => &self.input,
+            PaletteType::RunAndDebug => &self.input,
+            PaletteType::AttachProcess(_) => &self.input,
+            PaletteType::TerminalProfile => &self.input,
+            PaletteType::RunTask => &self.input,
+            PaletteType::EditBreakpoint(_) => &self.input,
+            PaletteType::WatchExpression { .. } => &self.input,
+            PaletteType::RenameTerminal { .. } => &self.input,
+            PaletteType::TerminalColor(_) => &self.input,
+            PaletteType::TerminalIcon(_) => &self.input,
             PaletteType::SshHost => &self.input,
             PaletteType::Line => &self.input[1..],
             PaletteType::DocumentSymbol => &self.input[1..],
@@ -574,7 +846,7 @@ pub fn run(
         }
 
         match palette.palette_type {
-            PaletteType::File => {
+            PaletteType::File | PaletteType::CompareFile => {
                 self.get_files(ctx);
             }
             PaletteType::Line => {
@@ -615,6 +887,58 @@ pub fn run(
                     }
                 }
             }
+            PaletteType::ReopenWithEncoding | PaletteType::SaveWithEncoding => {
+                let save =
+                    self.palette.palette_type == PaletteType::SaveWithEncoding;
+                self.get_encodings(ctx, save);
+                if let Some(editor) = self.main_split.active_editor() {
+                    let doc = self.main_split.content_doc(&editor.content);
+                    let encoding = doc.encoding().to_string();
+                    self.preselect_matching(ctx, &encoding);
+                }
+            }
+            PaletteType::LineEnding => {
+                self.get_line_endings(ctx);
+                if let Some(editor) = self.main_split.active_editor() {
+                    let doc = self.main_split.content_doc(&editor.content);
+                    let line_ending = doc.buffer().line_ending().label();
+                    self.preselect_matching(ctx, line_ending);
+                }
+            }
+            PaletteType::RestartLanguageServer | PaletteType::StopLanguageServer => {
+                let restart =
+                    self.palette.palette_type == PaletteType::RestartLanguageServer;
+                self.get_language_servers(ctx, restart);
+            }
+            PaletteType::RunAndDebug => {
+                self.get_run_configs(ctx);
+            }
+            PaletteType::AttachProcess(_) => {
+                self.get_processes(ctx);
+            }
+            PaletteType::TerminalProfile => {
+                self.get_terminal_profiles(ctx);
+            }
+            PaletteType::RunTask => {
+                self.get_tasks(ctx);
+            }
+            PaletteType::EditBreakpoint(_)
+            | PaletteType::WatchExpression { .. }
+            | PaletteType::RenameTerminal { .. } => {}
+            PaletteType::TerminalColor(term_id) => {
+                self.get_terminal_colors(ctx, term_id);
+            }
+            PaletteType::TerminalIcon(term_id) => {
+                self.get_terminal_icons(ctx, term_id);
+            }
+            PaletteType::Indentation => {
+                self.get_indentations(ctx);
+                if let Some(editor) = self.main_split.active_editor() {
+                    let doc = self.main_split.content_doc(&editor.content);
+                    let indent_style = doc.buffer().indent_style().label();
+                    self.preselect_matching(ctx, &indent_style);
+                }
+            }
         }
 
         self.fill_list();
@@ -650,6 +974,22 @@ pub fn delete_to_beginning_of_line(&mut self, ctx: &mut EventCtx) {
             PaletteType::Reference => 0,
             PaletteType::Theme => 0,
             PaletteType::Language => 0,
+            PaletteType::ReopenWithEncoding => 0,
+            PaletteType::SaveWithEncoding => 0,
+            PaletteType::LineEnding => 0,
+            PaletteType::Indentation => 0,
+            PaletteType::CompareFile => 0,
+            PaletteType::RestartLanguageServer => 0,
+            PaletteType::StopLanguageServer => 0,
+            PaletteType::RunAndDebug => 0,
+            PaletteType::AttachProcess(_) => 0,
+            PaletteType::TerminalProfile => 0,
+            PaletteType::RunTask => 0,
+            PaletteType::EditBreakpoint(_) => 0,
+            PaletteType::WatchExpression { .. } => 0,
+            PaletteType::RenameTerminal { .. } => 0,
+            PaletteType::TerminalColor(_) => 0,
+            PaletteType::TerminalIcon(_) => 0,
             PaletteType::SshHost => 0,
             PaletteType::Line => 1,
             PaletteType::DocumentSymbol => 1,
@@ -722,6 +1062,43 @@ pub fn select(&mut self, ctx: &mut EventCtx) {
                 ));
                 return;
             }
+            if let PaletteType::EditBreakpoint(edit) = &self.palette.palette_type {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::SetBreakpointOption {
+                        path: edit.path.clone(),
+                        line: edit.line,
+                        kind: edit.kind,
+                        value: self.palette.input.clone(),
+                    },
+                    Target::Widget(*self.main_split.tab_id),
+                ));
+            }
+            if let PaletteType::WatchExpression { index, .. } =
+                &self.palette.palette_type
+            {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::SetWatchExpression {
+                        index: *index,
+                        expression: self.palette.input.clone(),
+                    },
+                    Target::Widget(*self.main_split.tab_id),
+                ));
+            }
+            if let PaletteType::RenameTerminal { term_id, .. } =
+                &self.palette.palette_type
+            {
+                let name = self.palette.input.trim();
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RenameTerminal(
+                        *term_id,
+                        Some(name.to_string()).filter(|name| !name.is_empty()),
+                    ),
+                    Target::Widget(*self.main_split.tab_id),
+                ));
+            }
             self.cancel(ctx);
         }
     }
@@ -729,12 +1106,19 @@ pub fn select(&mut self, ctx: &mut EventCtx) {
     pub fn update_input(&mut self, ctx: &mut EventCtx, input: String) {
         let palette = Arc::make_mut(&mut self.palette);
 
-        // WorkspaceSymbol requires sending the query to the lsp, so we refresh it when the input changes
-        // If the input changed and the palette type is still/now workspace-symbol then we rerun it
+        // WorkspaceSymbol requires sending the query to the lsp, so we ask again
+        // when the input changes, and keep filtering the symbols of the last
+        // query until the new ones come
         let palette_type =
             PaletteType::get_palette_type(&palette.palette_type, &input);
-        if input != palette.input && palette_type == PaletteType::WorkspaceSymbol {
-            self.run(ctx, Some(PaletteType::WorkspaceSymbol), Some(input));
+        if input != palette.input
+            && palette_type == PaletteType::WorkspaceSymbol
+            && palette.palette_type == PaletteType::WorkspaceSymbol
+        {
+            palette.input = input;
+            palette.run_id = Uuid::new_v4().to_string();
+            self.get_workspace_symbols(ctx);
+            self.update_palette(ctx);
             return;
         }
 
@@ -778,6 +1162,7 @@ fn get_files(&self, ctx: &mut EventCtx) {
         let run_id = self.palette.run_id.clone();
         let widget_id = self.palette.widget_id;
         let workspace = self.workspace.clone();
+        let compare = self.palette.palette_type == PaletteType::CompareFile;
         let event_sink = ctx.get_external_handle();
         self.palette.proxy.proxy_rpc.get_files(move |result| {
             if let Ok(ProxyResponse::GetFilesResponse { items }) = result {
@@ -794,8 +1179,13 @@ fn get_files(&self, ctx: &mut EventCtx) {
                                 .to_path_buf();
                         }
                         let filter_text = path.to_str().unwrap_or("").to_string();
+                        let content = if compare {
+                            PaletteItemContent::CompareFile(path, full_path)
+                        } else {
+                            PaletteItemContent::File(path, full_path)
+                        };
                         PaletteItem {
-                            content: PaletteItemContent::File(path, full_path),
+                            content,
                             filter_text,
                             score: 0,
                             indices: Vec::new(),
@@ -899,6 +1289,277 @@ fn get_languages(&mut self, _ctx: &mut EventCtx) {
             .collect();
     }
 
+    fn get_encodings(&mut self, _ctx: &mut EventCtx, save: bool) {
+        let palette = Arc::make_mut(&mut self.palette);
+        palette.total_items = ENCODINGS
+            .iter()
+            .map(|name| PaletteItem {
+                content: PaletteItemContent::Encoding {
+                    name: name.to_string(),
+                    save,
+                },
+                filter_text: name.to_string(),
+                score: 0,
+                indices: vec![],
+            })
+            .collect();
+    }
+
+    fn get_line_endings(&mut self, _ctx: &mut EventCtx) {
+        let palette = Arc::make_mut(&mut self.palette);
+        palette.total_items = LineEnding::ALL
+            .iter()
+            .map(|line_ending| PaletteItem {
+                content: PaletteItemContent::LineEnding(*line_ending),
+                filter_text: line_ending.label().to_string(),
+                score: 0,
+                indices: vec![],
+            })
+            .collect();
+    }
+
+    fn get_indentations(&mut self, _ctx: &mut EventCtx) {
+        let palette = Arc::make_mut(&mut self.palette);
+        palette.total_items = IndentStyle::ALL
+            .iter()
+            .map(|indent_style| Some(*indent_style))
+            .chain(Some(None))
+            .map(|indent_style| PaletteItem {
+                content: PaletteItemContent::Indentation(indent_style),
+                filter_text: PaletteItemContent::indentation_label(indent_style),
+                score: 0,
+                indices: vec![],
+            })
+            .collect();
+    }
+
+    fn get_language_servers(&mut self, ctx: &mut EventCtx, restart: bool) {
+        let widget_id = self.palette.widget_id;
+        let run_id = self.palette.run_id.clone();
+        let event_sink = ctx.get_external_handle();
+        self.palette
+            .proxy
+            .proxy_rpc
+            .get_language_servers(move |result| {
+                if let Ok(ProxyResponse::GetLanguageServersResponse { servers }) =
+                    result
+                {
+                    let items: im::Vector<PaletteItem> = servers
+                        .into_iter()
+                        .map(|server| PaletteItem {
+                            filter_text: server.name.clone(),
+                            content: PaletteItemContent::LanguageServer {
+                                plugin_id: server.plugin_id,
+                                name: server.name,
+                                restart,
+                            },
+                            score: 0,
+                            indices: vec![],
+                        })
+                        .collect();
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::UpdatePaletteItems(run_id, items),
+                        Target::Widget(widget_id),
+                    );
+                }
+            });
+    }
+
+    /// The launch configurations of the `.lapce/launch.toml` of the workspace
+    fn get_run_configs(&mut self, ctx: &mut EventCtx) {
+        let path = match self.workspace.path.as_ref() {
+            Some(workspace) => workspace.join(LAUNCH_CONFIGS_PATH),
+            None => return,
+        };
+        let widget_id = self.palette.widget_id;
+        let run_id = self.palette.run_id.clone();
+        let event_sink = ctx.get_external_handle();
+        self.palette.proxy.proxy_rpc.read_file(path, move |result| {
+            let configs = match result {
+                Ok(ProxyResponse::ReadFileResponse { content }) => {
+                    match toml_edit::easy::from_str::<LaunchConfigs>(&content) {
+                        Ok(configs) => configs,
+                        Err(e) => {
+                            log::error!("{LAUNCH_CONFIGS_PATH}: {e}");
+                            return;
+                        }
+                    }
+                }
+                _ => return,
+            };
+            let compounds = configs.compounds.iter().filter_map(|compound| {
+                match configs.compound_configs(compound) {
+                    Ok(compound_configs) => Some(PaletteItem {
+                        filter_text: compound.name.clone(),
+                        content: PaletteItemContent::RunCompound {
+                            name: compound.name.clone(),
+                            configs: compound_configs,
+                        },
+                        score: 0,
+                        indices: vec![],
+                    }),
+                    Err(e) => {
+                        log::error!("{LAUNCH_CONFIGS_PATH}: {e}");
+                        None
+                    }
+                }
+            });
+            let items: im::Vector<PaletteItem> = configs
+                .configs
+                .iter()
+                .map(|config| PaletteItem {
+                    filter_text: config.name.clone(),
+                    content: PaletteItemContent::RunAndDebug(config.clone()),
+                    score: 0,
+                    indices: vec![],
+                })
+                .chain(compounds)
+                .collect();
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::UpdatePaletteItems(run_id, items),
+                Target::Widget(widget_id),
+            );
+        });
+    }
+
+    /// The tasks of the `.lapce/tasks.toml` of the workspace
+    fn get_tasks(&mut self, ctx: &mut EventCtx) {
+        let path = match self.workspace.path.as_ref() {
+            Some(workspace) => workspace.join(TASKS_PATH),
+            None => return,
+        };
+        let widget_id = self.palette.widget_id;
+        let run_id = self.palette.run_id.clone();
+        let event_sink = ctx.get_external_handle();
+        self.palette.proxy.proxy_rpc.read_file(path, move |result| {
+            let configs = match result {
+                Ok(ProxyResponse::ReadFileResponse { content }) => {
+                    match toml_edit::easy::from_str::<TaskConfigs>(&content) {
+                        Ok(configs) => configs,
+                        Err(e) => {
+                            log::error!("{TASKS_PATH}: {e}");
+                            return;
+                        }
+                    }
+                }
+                _ => return,
+            };
+            let items: im::Vector<PaletteItem> = configs
+                .tasks
+                .iter()
+                .filter_map(|config| match configs.task(config) {
+                    Ok(task) => Some(PaletteItem {
+                        filter_text: config.name.clone(),
+                        content: PaletteItemContent::RunTask(task),
+                        score: 0,
+                        indices: vec![],
+                    }),
+                    Err(e) => {
+                        log::error!("{TASKS_PATH}: {e}");
+                        None
+                    }
+                })
+                .collect();
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::UpdatePaletteItems(run_id, items),
+                Target::Widget(widget_id),
+            );
+        });
+    }
+
+    /// The terminal profiles of the settings, with the default one first
+    fn get_terminal_profiles(&mut self, _ctx: &mut EventCtx) {
+        let default_profile = self.config.terminal.default_profile();
+        let palette = Arc::make_mut(&mut self.palette);
+        palette.total_items = self
+            .config
+            .terminal_profiles
+            .iter()
+            .sorted_by_key(|(name, _)| {
+                (Some(name.as_str()) != default_profile, name.to_string())
+            })
+            .map(|(name, profile)| PaletteItem {
+                filter_text: name.clone(),
+                content: PaletteItemContent::TerminalProfile {
+                    name: name.clone(),
+                    profile: profile.clone(),
+                },
+                score: 0,
+                indices: vec![],
+            })
+            .collect();
+    }
+
+    /// The colors of the terminal theme, after no color
+    fn get_terminal_colors(&mut self, _ctx: &mut EventCtx, term_id: TermId) {
+        let palette = Arc::make_mut(&mut self.palette);
+        palette.total_items = std::iter::once(None)
+            .chain(TERMINAL_COLORS.iter().map(|color| Some(color.to_string())))
+            .map(|color| PaletteItem {
+                filter_text: color.clone().unwrap_or_else(|| "none".to_string()),
+                content: PaletteItemContent::TerminalColor { term_id, color },
+                score: 0,
+                indices: vec![],
+            })
+            .collect();
+    }
+
+    /// The icons a terminal can be given, after the icon of its profile
+    fn get_terminal_icons(&mut self, _ctx: &mut EventCtx, term_id: TermId) {
+        let palette = Arc::make_mut(&mut self.palette);
+        palette.total_items = std::iter::once(None)
+            .chain(TERMINAL_ICONS.iter().map(|icon| Some(icon.to_string())))
+            .map(|icon| PaletteItem {
+                filter_text: icon.clone().unwrap_or_else(|| "profile".to_string()),
+                content: PaletteItemContent::TerminalIcon { term_id, icon },
+                score: 0,
+                indices: vec![],
+            })
+            .collect();
+    }
+
+    /// The processes running on the host of the workspace, to attach the
+    /// debugger of the configuration to
+    fn get_processes(&mut self, ctx: &mut EventCtx) {
+        let config = match &self.palette.palette_type {
+            PaletteType::AttachProcess(config) => config.clone(),
+            _ => return,
+        };
+        let widget_id = self.palette.widget_id;
+        let run_id = self.palette.run_id.clone();
+        let event_sink = ctx.get_external_handle();
+        self.palette.proxy.proxy_rpc.list_processes(move |result| {
+            let processes = match result {
+                Ok(ProxyResponse::ListProcessesResponse { processes }) => processes,
+                Ok(_) => return,
+                Err(e) => {
+                    log::error!("can't list the processes: {}", e.message);
+                    return;
+                }
+            };
+            let items: im::Vector<PaletteItem> = processes
+                .into_iter()
+                .map(|process| PaletteItem {
+                    filter_text: process.name.clone(),
+                    content: PaletteItemContent::AttachProcess {
+                        process,
+                        config: config.clone(),
+                    },
+                    score: 0,
+                    indices: vec![],
+                })
+                .collect();
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::UpdatePaletteItems(run_id, items),
+                Target::Widget(widget_id),
+            );
+        });
+    }
+
     fn get_commands(&mut self, _ctx: &mut EventCtx) {
         const EXCLUDED_ITEMS: &[&str] = &["palette.command"];
 
@@ -1074,65 +1735,52 @@ fn get_document_symbols(&mut self, ctx: &mut EventCtx) {
         }
     }
 
+    /// Ask the language servers for the symbols of the workspace matching the
+    /// input, which doesn't need a file to be open
     fn get_workspace_symbols(&mut self, ctx: &mut EventCtx) {
-        let editor = self.main_split.active_editor();
-        let editor = match editor {
-            Some(editor) => editor,
-            None => return,
-        };
-
         let widget_id = self.palette.widget_id;
+        let run_id = self.palette.run_id.clone();
+        let event_sink = ctx.get_external_handle();
 
-        // TODO: We'd like to be able to request symbols even when not in an editor.
-        if let BufferContent::File(_path) = &editor.content {
-            let run_id = self.palette.run_id.clone();
-            let event_sink = ctx.get_external_handle();
-
-            let query = self.palette.get_input().to_string();
-
-            self.palette.proxy.proxy_rpc.get_workspace_symbols(
-                query,
-                move |result| {
-                    if let Ok(ProxyResponse::GetWorkspaceSymbols { symbols }) =
-                        result
-                    {
-                        let items: im::Vector<PaletteItem> = symbols
-                            .iter()
-                            .map(|s| {
-                                // TODO: Should we be using filter text?
-                                let mut filter_text = s.name.clone();
-                                if let Some(container_name) =
-                                    s.container_name.as_ref()
-                                {
-                                    filter_text += container_name;
-                                }
-                                PaletteItem {
-                                    content: PaletteItemContent::WorkspaceSymbol {
-                                        kind: s.kind,
-                                        name: s.name.clone(),
-                                        location: EditorLocation {
-                                            path: path_from_url(&s.location.uri),
-                                            position: Some(s.location.range.start),
-                                            scroll_offset: None,
-                                            history: None,
-                                        },
-                                        container_name: s.container_name.clone(),
+        let query = self.palette.get_input().to_string();
+
+        self.palette
+            .proxy
+            .proxy_rpc
+            .get_workspace_symbols(query, move |result| {
+                if let Ok(ProxyResponse::GetWorkspaceSymbols { symbols }) = result {
+                    let items: im::Vector<PaletteItem> = symbols
+                        .iter()
+                        .map(|s| {
+                            let mut filter_text = s.name.clone();
+                            if let Some(container_name) = s.container_name.as_ref() {
+                                filter_text += container_name;
+                            }
+                            PaletteItem {
+                                content: PaletteItemContent::WorkspaceSymbol {
+                                    kind: s.kind,
+                                    name: s.name.clone(),
+                                    location: EditorLocation {
+                                        path: path_from_url(&s.location.uri),
+                                        position: Some(s.location.range.start),
+                                        scroll_offset: None,
+                                        history: None,
                                     },
-                                    filter_text,
-                                    score: 0,
-                                    indices: Vec::new(),
-                                }
-                            })
-                            .collect();
-                        let _ = event_sink.submit_command(
-                            LAPCE_UI_COMMAND,
-                            LapceUICommand::UpdatePaletteItems(run_id, items),
-                            Target::Widget(widget_id),
-                        );
-                    }
-                },
-            );
-        }
+                                    container_name: s.container_name.clone(),
+                                },
+                                filter_text,
+                                score: 0,
+                                indices: Vec::new(),
+                            }
+                        })
+                        .collect();
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::UpdatePaletteItems(run_id, items),
+                        Target::Widget(widget_id),
+                    );
+                }
+            });
     }
 
     pub fn update_process(
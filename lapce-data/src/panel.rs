@@ -11,6 +11,13 @@ pub enum PanelKind {
     Terminal,
     Search,
     Problem,
+    UndoTree,
+    CallHierarchy,
+    TypeHierarchy,
+    RenamePreview,
+    LspLog,
+    Debug,
+    DebugConsole,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -32,6 +39,13 @@ pub fn svg_name(&self) -> &'static str {
             PanelKind::Terminal => "terminal.svg",
             PanelKind::Search => "search.svg",
             PanelKind::Problem => "error.svg",
+            PanelKind::UndoTree => "history.svg",
+            PanelKind::CallHierarchy => "symbol-method.svg",
+            PanelKind::TypeHierarchy => "symbol-class.svg",
+            PanelKind::RenamePreview => "diff-renamed.svg",
+            PanelKind::LspLog => "symbol-misc.svg",
+            PanelKind::Debug => "triangle-right.svg",
+            PanelKind::DebugConsole => "debug-console.svg",
         }
     }
 }
@@ -0,0 +1,146 @@
+use std::{path::Path, sync::Arc};
+
+use druid::{Command, Env, EventCtx, Target, WidgetId};
+use lapce_core::{command::FocusCommand, mode::Mode};
+use lsp_types::Position;
+
+use crate::{
+    command::{
+        CommandExecuted, CommandKind, LapceCommand, LapceUICommand, LAPCE_UI_COMMAND,
+    },
+    config::Config,
+    editor::EditorLocation,
+    keypress::KeyPressFocus,
+    list::ListData,
+};
+
+/// The number of lines the peek view shows
+const PEEK_LINES: usize = 12;
+
+/// An inline view below a line of an editor, which shows the locations of a
+/// definition or the references in an embedded editor, without leaving the
+/// current file.
+#[derive(Clone)]
+pub struct PeekData {
+    pub widget_id: WidgetId,
+    /// The view id of the embedded editor that shows the selected location
+    pub view_id: WidgetId,
+    pub editor_id: WidgetId,
+    pub active: bool,
+    /// The editor view the peek is shown in
+    pub from_editor: WidgetId,
+    /// The offset the peek was asked for, which it's shown below the line of
+    pub offset: usize,
+    /// The locations, listed by their path and line
+    pub list: ListData<String, ()>,
+    pub locations: Arc<Vec<EditorLocation<Position>>>,
+}
+
+impl PeekData {
+    pub fn new(config: Arc<Config>) -> Self {
+        let widget_id = WidgetId::next();
+        let mut list = ListData::new(config, widget_id, ());
+        list.max_displayed_items = PEEK_LINES;
+        Self {
+            widget_id,
+            view_id: WidgetId::next(),
+            editor_id: WidgetId::next(),
+            active: false,
+            from_editor: WidgetId::next(),
+            offset: 0,
+            list,
+            locations: Arc::new(Vec::new()),
+        }
+    }
+
+    /// The height of the peek view, for the list to fit in it
+    pub fn height(&self, config: &Config) -> f64 {
+        (PEEK_LINES * config.editor.line_height()) as f64 + 5.0
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &mut EventCtx,
+        from_editor: WidgetId,
+        offset: usize,
+        locations: Vec<EditorLocation<Position>>,
+        workspace_path: Option<&Path>,
+    ) {
+        self.list.clear_items();
+        self.list.items = locations
+            .iter()
+            .map(|location| {
+                let path = workspace_path
+                    .and_then(|workspace| location.path.strip_prefix(workspace).ok())
+                    .unwrap_or(&location.path);
+                let line = location.position.map(|p| p.line + 1).unwrap_or(1);
+                format!("{}:{line}", path.display())
+            })
+            .collect();
+        self.locations = Arc::new(locations);
+        self.from_editor = from_editor;
+        self.offset = offset;
+        self.active = true;
+        self.preview(ctx);
+        ctx.submit_command(Command::new(
+            LAPCE_UI_COMMAND,
+            LapceUICommand::Focus,
+            Target::Widget(self.widget_id),
+        ));
+    }
+
+    pub fn cancel(&mut self) {
+        self.active = false;
+    }
+
+    pub fn current_location(&self) -> Option<&EditorLocation<Position>> {
+        self.locations.get(self.list.selected_index)
+    }
+
+    /// Show the selected location in the embedded editor
+    fn preview(&self, ctx: &mut EventCtx) {
+        if let Some(location) = self.current_location() {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::JumpToLspLocation(
+                    Some(self.view_id),
+                    location.clone(),
+                    true,
+                ),
+                Target::Auto,
+            ));
+        }
+    }
+}
+
+impl KeyPressFocus for PeekData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: &str) -> bool {
+        matches!(condition, "list_focus" | "modal_focus")
+    }
+
+    fn run_command(
+        &mut self,
+        ctx: &mut EventCtx,
+        command: &LapceCommand,
+        _count: Option<usize>,
+        _mods: druid::Modifiers,
+        _env: &Env,
+    ) -> CommandExecuted {
+        if let CommandKind::Focus(FocusCommand::ModalClose) = command.kind {
+            self.cancel();
+            return CommandExecuted::Yes;
+        }
+        let index = self.list.selected_index;
+        let executed = self.list.run_command(ctx, command);
+        if self.list.selected_index != index {
+            self.preview(ctx);
+        }
+        executed
+    }
+
+    fn receive_char(&mut self, _ctx: &mut EventCtx, _c: &str) {}
+}
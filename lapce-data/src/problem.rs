@@ -1,6 +1,7 @@
 use druid::WidgetId;
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Clone)]
 pub struct ProblemData {
@@ -8,6 +9,9 @@ pub struct ProblemData {
     pub split_id: WidgetId,
     pub error_widget_id: WidgetId,
     pub warning_widget_id: WidgetId,
+    /// The view id of the input the problems are filtered with
+    pub filter_view_id: WidgetId,
+    pub filter: ProblemFilter,
     pub collapsed: HashMap<PathBuf, bool>,
 }
 
@@ -18,6 +22,8 @@ pub fn new() -> Self {
             split_id: WidgetId::next(),
             error_widget_id: WidgetId::next(),
             warning_widget_id: WidgetId::next(),
+            filter_view_id: WidgetId::next(),
+            filter: ProblemFilter::default(),
             collapsed: HashMap::new(),
         }
     }
@@ -28,3 +34,193 @@ fn default() -> Self {
         Self::new()
     }
 }
+
+/// The filter of the problems panel. The words of the pattern are looked
+/// for in the message, the source and the code of the diagnostics, case
+/// insensitively, and `source:<name>` and `severity:<error|warning|info|hint>`
+/// only keep the diagnostics of those sources or severities.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProblemFilter {
+    pub pattern: String,
+    words: Vec<String>,
+    sources: Vec<String>,
+    severities: Vec<DiagnosticSeverity>,
+}
+
+impl ProblemFilter {
+    pub fn new(pattern: &str) -> Self {
+        let mut filter = Self {
+            pattern: pattern.to_string(),
+            ..Default::default()
+        };
+        for word in pattern.split_whitespace() {
+            let word = word.to_lowercase();
+            if let Some(source) = word.strip_prefix("source:") {
+                filter.sources.push(source.to_string());
+                continue;
+            }
+            let severity = match word.strip_prefix("severity:") {
+                Some("error") => Some(DiagnosticSeverity::ERROR),
+                Some("warning") => Some(DiagnosticSeverity::WARNING),
+                Some("info") | Some("information") => {
+                    Some(DiagnosticSeverity::INFORMATION)
+                }
+                Some("hint") => Some(DiagnosticSeverity::HINT),
+                _ => None,
+            };
+            match severity {
+                Some(severity) => filter.severities.push(severity),
+                None => filter.words.push(word),
+            }
+        }
+        filter
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+            && self.sources.is_empty()
+            && self.severities.is_empty()
+    }
+
+    pub fn matches(&self, diagnostic: &Diagnostic) -> bool {
+        if !self.severities.is_empty()
+            && !diagnostic
+                .severity
+                .map(|severity| self.severities.contains(&severity))
+                .unwrap_or(false)
+        {
+            return false;
+        }
+
+        let source = diagnostic.source.as_deref().unwrap_or("").to_lowercase();
+        if !self.sources.is_empty() && !self.sources.contains(&source) {
+            return false;
+        }
+
+        let message = diagnostic.message.to_lowercase();
+        let code = match diagnostic.code.as_ref() {
+            Some(NumberOrString::Number(code)) => code.to_string(),
+            Some(NumberOrString::String(code)) => code.to_lowercase(),
+            None => String::new(),
+        };
+        self.words.iter().all(|word| {
+            message.contains(word) || source.contains(word) || code.contains(word)
+        })
+    }
+}
+
+/// The problem after the position in the file, or before it when going
+/// backward, going on to the files after or before it and wrapping around.
+/// The files and the positions in them have to be sorted.
+pub fn next_problem(
+    problems: &[(&PathBuf, Vec<Position>)],
+    path: Option<&Path>,
+    position: Position,
+    forward: bool,
+) -> Option<(PathBuf, Position)> {
+    let is_after = |p: &Position| {
+        p.line > position.line
+            || (p.line == position.line && p.character > position.character)
+    };
+    let is_before = |p: &Position| {
+        p.line < position.line
+            || (p.line == position.line && p.character < position.character)
+    };
+
+    if forward {
+        for (current_path, positions) in problems {
+            let current_path = current_path.as_path();
+            if Some(current_path) == path {
+                if let Some(p) = positions.iter().find(|p| is_after(p)) {
+                    return Some((current_path.to_path_buf(), *p));
+                }
+            } else if path.map(|path| current_path > path).unwrap_or(true) {
+                if let Some(p) = positions.first() {
+                    return Some((current_path.to_path_buf(), *p));
+                }
+            }
+        }
+        let (path, positions) = problems.first()?;
+        positions.first().map(|p| ((*path).clone(), *p))
+    } else {
+        for (current_path, positions) in problems.iter().rev() {
+            let current_path = current_path.as_path();
+            if Some(current_path) == path {
+                if let Some(p) = positions.iter().rev().find(|p| is_before(p)) {
+                    return Some((current_path.to_path_buf(), *p));
+                }
+            } else if path.map(|path| current_path < path).unwrap_or(true) {
+                if let Some(p) = positions.last() {
+                    return Some((current_path.to_path_buf(), *p));
+                }
+            }
+        }
+        let (path, positions) = problems.last()?;
+        positions.last().map(|p| ((*path).clone(), *p))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_problem_filter() {
+        let diagnostic = Diagnostic {
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String("unused_variables".to_string())),
+            source: Some("rustc".to_string()),
+            message: "unused variable: `x`".to_string(),
+            ..Default::default()
+        };
+
+        assert!(ProblemFilter::new("").is_empty());
+        assert!(ProblemFilter::new("").matches(&diagnostic));
+        assert!(ProblemFilter::new("Unused").matches(&diagnostic));
+        assert!(ProblemFilter::new("unused_variables").matches(&diagnostic));
+        assert!(ProblemFilter::new("unused x").matches(&diagnostic));
+        assert!(!ProblemFilter::new("unused y").matches(&diagnostic));
+        assert!(ProblemFilter::new("source:rustc").matches(&diagnostic));
+        assert!(!ProblemFilter::new("source:clippy").matches(&diagnostic));
+        assert!(ProblemFilter::new("severity:warning").matches(&diagnostic));
+        assert!(!ProblemFilter::new("severity:error").matches(&diagnostic));
+        assert!(ProblemFilter::new("severity:error severity:warning rustc")
+            .matches(&diagnostic));
+    }
+
+    #[test]
+    fn test_next_problem() {
+        let a = PathBuf::from("/a.rs");
+        let b = PathBuf::from("/b.rs");
+        let c = PathBuf::from("/c.rs");
+        let problems = vec![
+            (&a, vec![Position::new(1, 0), Position::new(5, 2)]),
+            (&c, vec![Position::new(3, 0)]),
+        ];
+
+        let next = |path: &PathBuf, line, forward| {
+            next_problem(
+                &problems,
+                Some(path.as_path()),
+                Position::new(line, 0),
+                forward,
+            )
+        };
+        assert_eq!(next(&a, 1, true), Some((a.clone(), Position::new(5, 2))));
+        assert_eq!(next(&a, 6, true), Some((c.clone(), Position::new(3, 0))));
+        assert_eq!(next(&b, 0, true), Some((c.clone(), Position::new(3, 0))));
+        assert_eq!(next(&c, 3, true), Some((a.clone(), Position::new(1, 0))));
+        assert_eq!(next(&c, 3, false), Some((a.clone(), Position::new(5, 2))));
+        assert_eq!(next(&b, 0, false), Some((a.clone(), Position::new(5, 2))));
+        assert_eq!(next(&a, 1, false), Some((c.clone(), Position::new(3, 0))));
+        assert_eq!(
+            next_problem(&problems, None, Position::new(0, 0), true),
+            Some((a.clone(), Position::new(1, 0)))
+        );
+        assert_eq!(
+            next_problem(&problems, None, Position::new(0, 0), false),
+            Some((c, Position::new(3, 0)))
+        );
+        assert_eq!(next_problem(&[], Some(&a), Position::new(0, 0), true), None);
+    }
+}
@@ -17,9 +17,10 @@
 use lapce_proxy::APPLICATION_NAME;
 pub use lapce_proxy::VERSION;
 use lapce_rpc::core::{CoreHandler, CoreNotification, CoreRequest, CoreRpcHandler};
+use lapce_rpc::plugin::LanguageServerConfig;
 use lapce_rpc::proxy::{ProxyRpc, ProxyRpcHandler};
 use lapce_rpc::stdio::stdio_transport;
-use lapce_rpc::terminal::TermId;
+use lapce_rpc::terminal::{TermId, TerminalProfile};
 use lapce_rpc::RequestId;
 use lapce_rpc::RpcMessage;
 use lsp_types::Url;
@@ -93,6 +94,8 @@ enum HostArchitecture {
 #[derive(Clone)]
 pub struct LapceProxy {
     pub tab_id: WidgetId,
+    /// Where the workspace of the proxy is, locally or on a remote host
+    pub workspace_kind: LapceWorkspaceType,
     pub proxy_rpc: ProxyRpcHandler,
     pub core_rpc: CoreRpcHandler,
     term_tx: Sender<(TermId, TermEvent)>,
@@ -158,6 +161,13 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
                     Target::Widget(self.tab_id),
                 );
             }
+            WorkspaceSettingsChange {} => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::ReloadConfig,
+                    Target::Auto,
+                );
+            }
             PublishDiagnostics { diagnostics } => {
                 let _ = self.event_sink.submit_command(
                     LAPCE_UI_COMMAND,
@@ -165,10 +175,111 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
                     Target::Widget(self.tab_id),
                 );
             }
-            WorkDoneProgress { progress } => {
+            WorkDoneProgress {
+                plugin_id,
+                server,
+                progress,
+            } => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::WorkDoneProgress {
+                        plugin_id,
+                        server,
+                        progress,
+                    },
+                    Target::Widget(self.tab_id),
+                );
+            }
+            LspLog {
+                plugin_id,
+                server,
+                kind,
+                message,
+            } => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::LspLog {
+                        plugin_id,
+                        server,
+                        kind,
+                        message,
+                    },
+                    Target::Widget(self.tab_id),
+                );
+            }
+            DapStopped {
+                dap_id,
+                stopped,
+                threads,
+                stack_frames,
+                variables,
+            } => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::DapStopped {
+                        dap_id,
+                        stopped,
+                        threads,
+                        stack_frames,
+                        variables,
+                    },
+                    Target::Widget(self.tab_id),
+                );
+            }
+            DapCapabilities {
+                dap_id,
+                capabilities,
+            } => {
                 let _ = self.event_sink.submit_command(
                     LAPCE_UI_COMMAND,
-                    LapceUICommand::WorkDoneProgress(progress),
+                    LapceUICommand::DapCapabilities {
+                        dap_id,
+                        capabilities,
+                    },
+                    Target::Widget(self.tab_id),
+                );
+            }
+            DapContinued { dap_id } => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::DapContinued(dap_id),
+                    Target::Widget(self.tab_id),
+                );
+            }
+            DapTerminated { dap_id } => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::DapTerminated(dap_id),
+                    Target::Widget(self.tab_id),
+                );
+            }
+            DapBreakpoints {
+                dap_id,
+                path,
+                breakpoints,
+            } => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::DapBreakpoints {
+                        dap_id,
+                        path,
+                        breakpoints,
+                    },
+                    Target::Widget(self.tab_id),
+                );
+            }
+            DapOutput {
+                dap_id,
+                category,
+                output,
+            } => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::DapOutput {
+                        dap_id,
+                        category,
+                        output,
+                    },
                     Target::Widget(self.tab_id),
                 );
             }
@@ -234,16 +345,24 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
                     Target::Widget(self.tab_id),
                 );
             }
+            TerminalProcess { term_id, name } => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::UpdateTerminalProcess(term_id, name),
+                    Target::Widget(self.tab_id),
+                );
+            }
             CompletionResponse {
                 request_id,
                 input,
                 resp,
                 plugin_id,
+                priority,
             } => {
                 let _ = self.event_sink.submit_command(
                     LAPCE_UI_COMMAND,
                     LapceUICommand::UpdateCompletion(
-                        request_id, input, resp, plugin_id,
+                        request_id, input, resp, plugin_id, priority,
                     ),
                     Target::Widget(self.tab_id),
                 );
@@ -260,12 +379,14 @@ fn handle_request(&mut self, _id: RequestId, _rpc: CoreRequest) {}
 }
 
 impl LapceProxy {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         window_id: WindowId,
         tab_id: WidgetId,
         workspace: LapceWorkspace,
         disabled_volts: Vec<String>,
         plugin_configurations: HashMap<String, serde_json::Value>,
+        language_servers: HashMap<String, LanguageServerConfig>,
         term_tx: Sender<(TermId, TermEvent)>,
         event_sink: ExtEventSink,
     ) -> Self {
@@ -274,6 +395,7 @@ pub fn new(
 
         let proxy = Self {
             tab_id,
+            workspace_kind: workspace.kind.clone(),
             proxy_rpc,
             core_rpc,
             term_tx,
@@ -291,6 +413,7 @@ pub fn new(
                 workspace.clone(),
                 disabled_volts,
                 plugin_configurations,
+                language_servers,
                 window_id.to_usize(),
                 tab_id.to_usize(),
             );
@@ -309,6 +432,7 @@ fn start(
         workspace: LapceWorkspace,
         disabled_volts: Vec<String>,
         plugin_configurations: HashMap<String, serde_json::Value>,
+        language_servers: HashMap<String, LanguageServerConfig>,
         window_id: usize,
         tab_id: usize,
     ) -> Result<()> {
@@ -316,6 +440,7 @@ fn start(
             workspace.path.clone(),
             disabled_volts,
             plugin_configurations,
+            language_servers,
             window_id,
             tab_id,
         );
@@ -428,6 +553,8 @@ fn start_remote(&self, remote: impl Remote) -> Result<()> {
                         &proxy_version,
                         "-directory",
                         &remote_proxy_path,
+                        "-expected",
+                        *VERSION,
                     ])
                     .output()?;
                 log::debug!(target: "lapce_data::proxy::upload_file", "{}", String::from_utf8_lossy(&cmd.stderr));
@@ -457,7 +584,12 @@ fn start_remote(&self, remote: impl Remote) -> Result<()> {
 
                 let cmd = remote
                     .command_builder()
-                    .args([remote_proxy_script, &proxy_version, &remote_proxy_path])
+                    .args([
+                        remote_proxy_script,
+                        &proxy_version,
+                        &remote_proxy_path,
+                        *VERSION,
+                    ])
                     .output()?;
                 log::debug!(target: "lapce_data::proxy::upload_file", "{}", String::from_utf8_lossy(&cmd.stderr));
                 log::debug!(target: "lapce_data::proxy::upload_file", "{}", String::from_utf8_lossy(&cmd.stdout));
@@ -476,26 +608,22 @@ fn start_remote(&self, remote: impl Remote) -> Result<()> {
         log::debug!(target: "lapce_data::proxy::start_remote", "remote proxy path: {remote_proxy_path}");
 
         if !script_install.success() {
-            let cmd = match platform {
-                Windows => remote
-                    .command_builder()
-                    .args(["dir", &remote_proxy_file])
-                    .status()?,
-                _ => remote
-                    .command_builder()
-                    .arg("test")
-                    .arg("-e")
-                    .arg(&remote_proxy_file)
-                    .status()?,
+            // A proxy left from before Lapce updated itself has to be
+            // replaced, otherwise the two sides speak different protocol
+            // versions.
+            let installed_version =
+                remote_proxy_version(&remote, platform, &remote_proxy_file);
+            let up_to_date = match installed_version.as_deref() {
+                Some(version) => *VERSION == "debug" || version == *VERSION,
+                None => false,
             };
-            if !cmd.success() {
+            if !up_to_date {
+                log::info!(target: "lapce_data::proxy::start_remote", "remote proxy version {installed_version:?} doesn't match {}, installing", *VERSION);
                 let local_proxy_file = Directory::proxy_directory()
                     .ok_or_else(|| anyhow!("can't find proxy directory"))?
                     .join(&proxy_filename);
                 // remove possibly outdated proxy
                 if local_proxy_file.exists() {
-                    // TODO: add proper proxy version detection and update proxy
-                    // when needed
                     std::fs::remove_file(&local_proxy_file)?;
                 }
                 let url = format!("https://github.com/lapce/lapce/releases/download/{proxy_version}/{proxy_filename}.gz");
@@ -721,12 +849,14 @@ fn host_specification(
     pub fn new_terminal(
         &self,
         term_id: TermId,
-        cwd: Option<PathBuf>,
-        shell: String,
+        profile: TerminalProfile,
+        shell_integration: bool,
+        session: Option<String>,
         raw: Arc<Mutex<RawTerminal>>,
     ) {
         let _ = self.term_tx.send((term_id, TermEvent::NewTerminal(raw)));
-        self.proxy_rpc.new_terminal(term_id, cwd, shell);
+        self.proxy_rpc
+            .new_terminal(term_id, profile, shell_integration, session);
     }
 
     pub fn stop(&self) {
@@ -909,6 +1039,31 @@ pub fn path_from_url(url: &Url) -> PathBuf {
         .unwrap_or_else(|_| PathBuf::from(url.path()))
 }
 
+/// The version of the proxy installed at `file` on the remote host, if there
+/// is one.
+fn remote_proxy_version(
+    remote: &impl Remote,
+    platform: HostPlatform,
+    file: &str,
+) -> Option<String> {
+    let output = match platform {
+        HostPlatform::Windows => remote
+            .command_builder()
+            .args(["cmd", "/c", file, "--version"])
+            .output(),
+        _ => remote.command_builder().args([file, "--version"]).output(),
+    }
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // `lapce --version` prints "Lapce <version>"
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(1)
+        .map(|version| version.to_string())
+}
+
 fn parse_arch(arch: &str) -> HostArchitecture {
     use HostArchitecture::*;
     // processor architectures be like that
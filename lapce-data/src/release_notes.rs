@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use druid::{Command, Env, EventCtx, Modifiers, Target, WidgetId};
+use lapce_core::{command::FocusCommand, mode::Mode};
+
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand, LAPCE_COMMAND},
+    data::LapceTabData,
+    keypress::KeyPressFocus,
+    update::ReleaseInfo,
+};
+
+/// The dialog showing the notes of a new release, which is confirmed
+/// before restarting to update.
+#[derive(Clone)]
+pub struct ReleaseNotesData {
+    pub widget_id: WidgetId,
+    pub active: bool,
+    pub release: Option<ReleaseInfo>,
+}
+
+pub struct ReleaseNotesFocusData {
+    release_notes: Arc<ReleaseNotesData>,
+}
+
+impl Default for ReleaseNotesData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReleaseNotesData {
+    pub fn new() -> Self {
+        Self {
+            widget_id: WidgetId::next(),
+            active: false,
+            release: None,
+        }
+    }
+}
+
+impl KeyPressFocus for ReleaseNotesFocusData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn focus_only(&self) -> bool {
+        true
+    }
+
+    fn check_condition(&self, condition: &str) -> bool {
+        matches!(condition, "modal_focus")
+    }
+
+    fn run_command(
+        &mut self,
+        ctx: &mut EventCtx,
+        command: &LapceCommand,
+        _count: Option<usize>,
+        _mods: Modifiers,
+        _env: &Env,
+    ) -> CommandExecuted {
+        if let CommandKind::Focus(FocusCommand::ModalClose) = command.kind {
+            ctx.submit_command(Command::new(
+                LAPCE_COMMAND,
+                LapceCommand {
+                    kind: CommandKind::Focus(FocusCommand::ModalClose),
+                    data: None,
+                },
+                Target::Widget(self.release_notes.widget_id),
+            ));
+            CommandExecuted::Yes
+        } else {
+            CommandExecuted::No
+        }
+    }
+
+    fn receive_char(&mut self, _ctx: &mut EventCtx, _c: &str) {}
+}
+
+impl ReleaseNotesFocusData {
+    pub fn new(data: &LapceTabData) -> Self {
+        Self {
+            release_notes: data.release_notes.clone(),
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use druid::WidgetId;
+use lsp_types::{
+    DocumentChangeOperation, DocumentChanges, OneOf, ResourceOp, TextEdit, Url,
+    WorkspaceEdit,
+};
+
+use crate::proxy::path_from_url;
+
+/// An edit of the rename, which is only applied if it stays selected
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenamePreviewEdit {
+    pub edit: TextEdit,
+    pub selected: bool,
+}
+
+/// A file changed by the rename, with its edits in the order they come in
+/// the file
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenamePreviewFile {
+    pub path: PathBuf,
+    pub open: bool,
+    pub edits: Vec<RenamePreviewEdit>,
+}
+
+impl RenamePreviewFile {
+    /// Whether any edit of the file is selected
+    pub fn selected(&self) -> bool {
+        self.edits.iter().any(|edit| edit.selected)
+    }
+}
+
+/// A file operation of the rename, like renaming the file of a module
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenamePreviewOperation {
+    pub op: ResourceOp,
+    pub selected: bool,
+}
+
+/// A visible row of the rename preview panel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenamePreviewRow {
+    Operation(usize),
+    File(usize),
+    Edit(usize, usize),
+}
+
+/// The workspace edit of a rename, shown before it's applied so that the
+/// files and edits that shouldn't change can be deselected
+#[derive(Clone)]
+pub struct RenamePreviewData {
+    pub widget_id: WidgetId,
+    pub split_id: WidgetId,
+    pub tree_widget_id: WidgetId,
+    pub new_name: String,
+    pub operations: Vec<RenamePreviewOperation>,
+    pub files: Vec<RenamePreviewFile>,
+}
+
+impl RenamePreviewData {
+    pub fn new() -> Self {
+        Self {
+            widget_id: WidgetId::next(),
+            split_id: WidgetId::next(),
+            tree_widget_id: WidgetId::next(),
+            new_name: String::new(),
+            operations: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Show the edits of the rename, all selected, with the files sorted by
+    /// their path
+    pub fn set_edit(&mut self, new_name: String, edit: &WorkspaceEdit) {
+        self.new_name = new_name;
+        self.operations = workspace_operations(edit)
+            .into_iter()
+            .map(|op| RenamePreviewOperation { op, selected: true })
+            .collect();
+
+        let mut files = HashMap::new();
+        for (url, edits) in workspace_text_edits(edit) {
+            let file =
+                files
+                    .entry(url.clone())
+                    .or_insert_with(|| RenamePreviewFile {
+                        path: path_from_url(&url),
+                        open: true,
+                        edits: Vec::new(),
+                    });
+            file.edits
+                .extend(edits.into_iter().map(|edit| RenamePreviewEdit {
+                    edit,
+                    selected: true,
+                }));
+        }
+        let mut files = files.into_values().collect::<Vec<_>>();
+        for file in files.iter_mut() {
+            file.edits.sort_by_key(|edit| edit.edit.range.start);
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        self.files = files;
+    }
+
+    pub fn clear(&mut self) {
+        self.new_name.clear();
+        self.operations.clear();
+        self.files.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty() && self.files.is_empty()
+    }
+
+    /// The number of selected edits, and of the files they are in
+    pub fn selected_count(&self) -> (usize, usize) {
+        let mut edits = 0;
+        let mut files = 0;
+        for file in self.files.iter() {
+            let n = file.edits.iter().filter(|edit| edit.selected).count();
+            if n > 0 {
+                edits += n;
+                files += 1;
+            }
+        }
+        (edits, files)
+    }
+
+    /// The operations, then the files with the edits of the open ones
+    pub fn rows(&self) -> Vec<RenamePreviewRow> {
+        let mut rows = (0..self.operations.len())
+            .map(RenamePreviewRow::Operation)
+            .collect::<Vec<_>>();
+        for (i, file) in self.files.iter().enumerate() {
+            rows.push(RenamePreviewRow::File(i));
+            if file.open {
+                rows.extend(
+                    (0..file.edits.len()).map(|j| RenamePreviewRow::Edit(i, j)),
+                );
+            }
+        }
+        rows
+    }
+
+    pub fn toggle_open(&mut self, file: usize) {
+        if let Some(file) = self.files.get_mut(file) {
+            file.open = !file.open;
+        }
+    }
+
+    /// Select or deselect the row, which for a file is all of its edits
+    pub fn toggle_selected(&mut self, row: RenamePreviewRow) {
+        match row {
+            RenamePreviewRow::Operation(i) => {
+                if let Some(op) = self.operations.get_mut(i) {
+                    op.selected = !op.selected;
+                }
+            }
+            RenamePreviewRow::File(i) => {
+                if let Some(file) = self.files.get_mut(i) {
+                    let selected = !file.selected();
+                    for edit in file.edits.iter_mut() {
+                        edit.selected = selected;
+                    }
+                }
+            }
+            RenamePreviewRow::Edit(i, j) => {
+                if let Some(edit) =
+                    self.files.get_mut(i).and_then(|file| file.edits.get_mut(j))
+                {
+                    edit.selected = !edit.selected;
+                }
+            }
+        }
+    }
+
+    pub fn selected_operations(&self) -> Vec<ResourceOp> {
+        self.operations
+            .iter()
+            .filter(|op| op.selected)
+            .map(|op| op.op.clone())
+            .collect()
+    }
+
+    /// The selected edits of each file, leaving out the files that have none
+    pub fn selected_edits(&self) -> Vec<(PathBuf, Vec<TextEdit>)> {
+        self.files
+            .iter()
+            .filter_map(|file| {
+                let edits = file
+                    .edits
+                    .iter()
+                    .filter(|edit| edit.selected)
+                    .map(|edit| edit.edit.clone())
+                    .collect::<Vec<_>>();
+                if edits.is_empty() {
+                    None
+                } else {
+                    Some((file.path.clone(), edits))
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for RenamePreviewData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn workspace_operations(edit: &WorkspaceEdit) -> Vec<ResourceOp> {
+    match edit.document_changes.as_ref() {
+        Some(DocumentChanges::Operations(ops)) => ops
+            .iter()
+            .filter_map(|op| match op {
+                DocumentChangeOperation::Op(op) => Some(op.clone()),
+                DocumentChangeOperation::Edit(_) => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn workspace_text_edits(edit: &WorkspaceEdit) -> Vec<(Url, Vec<TextEdit>)> {
+    if let Some(changes) = edit.changes.as_ref() {
+        return changes
+            .iter()
+            .map(|(url, edits)| (url.clone(), edits.clone()))
+            .collect();
+    }
+
+    let edits = match edit.document_changes.as_ref() {
+        Some(DocumentChanges::Edits(edits)) => edits.iter().collect::<Vec<_>>(),
+        Some(DocumentChanges::Operations(ops)) => ops
+            .iter()
+            .filter_map(|op| match op {
+                DocumentChangeOperation::Op(_) => None,
+                DocumentChangeOperation::Edit(edit) => Some(edit),
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    edits
+        .into_iter()
+        .map(|edit| {
+            (
+                edit.text_document.uri.clone(),
+                edit.edits
+                    .iter()
+                    .map(|edit| match edit {
+                        OneOf::Left(edit) => edit.clone(),
+                        OneOf::Right(edit) => edit.text_edit.clone(),
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range};
+
+    use super::*;
+
+    fn text_edit(line: u32, new_text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(Position::new(line, 4), Position::new(line, 7)),
+            new_text: new_text.to_string(),
+        }
+    }
+
+    fn preview() -> RenamePreviewData {
+        let mut changes = HashMap::new();
+        changes.insert(
+            Url::parse("file:///b.rs").unwrap(),
+            vec![text_edit(3, "bar"), text_edit(1, "bar")],
+        );
+        changes.insert(
+            Url::parse("file:///a.rs").unwrap(),
+            vec![text_edit(0, "bar")],
+        );
+        let edit = WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+            change_annotations: None,
+        };
+        let mut preview = RenamePreviewData::new();
+        preview.set_edit("bar".to_string(), &edit);
+        preview
+    }
+
+    #[test]
+    fn test_rename_preview_rows() {
+        let mut preview = preview();
+        assert_eq!(preview.files[0].path, PathBuf::from("/a.rs"));
+        assert_eq!(preview.files[1].edits[0].edit.range.start.line, 1);
+        assert_eq!(
+            preview.rows(),
+            vec![
+                RenamePreviewRow::File(0),
+                RenamePreviewRow::Edit(0, 0),
+                RenamePreviewRow::File(1),
+                RenamePreviewRow::Edit(1, 0),
+                RenamePreviewRow::Edit(1, 1),
+            ]
+        );
+
+        preview.toggle_open(1);
+        assert_eq!(preview.rows().len(), 3);
+    }
+
+    #[test]
+    fn test_rename_preview_selected_edits() {
+        let mut preview = preview();
+        assert_eq!(preview.selected_count(), (3, 2));
+
+        preview.toggle_selected(RenamePreviewRow::Edit(1, 0));
+        let edits = preview.selected_edits();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[1].1, vec![text_edit(3, "bar")]);
+
+        // a file that is partly selected is deselected as a whole
+        preview.toggle_selected(RenamePreviewRow::File(1));
+        assert_eq!(preview.selected_count(), (1, 1));
+        assert_eq!(preview.selected_edits()[0].0, PathBuf::from("/a.rs"));
+
+        preview.toggle_selected(RenamePreviewRow::File(1));
+        assert_eq!(preview.selected_count(), (3, 2));
+    }
+}
@@ -1,15 +1,49 @@
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use druid::WidgetId;
+use lapce_core::transform::preserve_case;
+use regex::{Regex, RegexBuilder};
 
 pub type Match = (usize, (usize, usize), String);
+
+/// The replacement of one match of a global search
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchReplacement {
+    /// The zero based line of the match
+    pub line: usize,
+    /// The byte range of the match in the line
+    pub start: usize,
+    pub end: usize,
+    /// The text that was matched, so that the replacement can be skipped if
+    /// the file has changed since the search
+    pub matched: String,
+    pub text: String,
+}
+
 #[derive(Clone)]
 pub struct SearchData {
     pub active: WidgetId,
     pub widget_id: WidgetId,
     pub split_id: WidgetId,
     pub editor_view_id: WidgetId,
+    pub replace_view_id: WidgetId,
     pub matches: Arc<HashMap<PathBuf, Vec<Match>>>,
+    /// Whether the search input is a regex rather than plain text
+    pub is_regex: bool,
+    /// The regex the current matches were found with, used to expand the
+    /// capture groups of the replacement for each match
+    pub regex: Option<Regex>,
+    pub replace: String,
+    /// Whether the replacement follows the case of each match, so that
+    /// replacing "foo" with "bar" also turns "Foo" into "Bar"
+    pub preserve_case: bool,
+    /// The indices of the matches of each file that are left out of the
+    /// replacement
+    pub excluded: Arc<HashMap<PathBuf, HashSet<usize>>>,
 }
 
 impl SearchData {
@@ -20,9 +54,113 @@ pub fn new() -> Self {
             widget_id: WidgetId::next(),
             split_id: WidgetId::next(),
             editor_view_id,
+            replace_view_id: WidgetId::next(),
             matches: Arc::new(HashMap::new()),
+            is_regex: false,
+            regex: None,
+            replace: String::new(),
+            preserve_case: false,
+            excluded: Arc::new(HashMap::new()),
+        }
+    }
+
+    /// The regex matching what the proxy matches for the pattern, which is
+    /// case insensitive
+    pub fn search_regex(pattern: &str, is_regex: bool) -> Option<Regex> {
+        let pattern = if is_regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .ok()
+    }
+
+    pub fn set_matches(
+        &mut self,
+        pattern: &str,
+        matches: Arc<HashMap<PathBuf, Vec<Match>>>,
+    ) {
+        self.regex = Self::search_regex(pattern, self.is_regex);
+        self.matches = matches;
+        self.excluded = Arc::new(HashMap::new());
+    }
+
+    pub fn clear_matches(&mut self) {
+        self.regex = None;
+        self.matches = Arc::new(HashMap::new());
+        self.excluded = Arc::new(HashMap::new());
+    }
+
+    pub fn is_excluded(&self, path: &Path, index: usize) -> bool {
+        self.excluded
+            .get(path)
+            .map(|excluded| excluded.contains(&index))
+            .unwrap_or(false)
+    }
+
+    pub fn toggle_excluded(&mut self, path: &Path, index: usize) {
+        let excluded = Arc::make_mut(&mut self.excluded)
+            .entry(path.to_path_buf())
+            .or_insert_with(HashSet::new);
+        if !excluded.remove(&index) {
+            excluded.insert(index);
         }
     }
+
+    /// The text the match is replaced with, which has the capture groups of
+    /// the match, like `$1` or `${name}`, expanded when searching with a
+    /// regex, and the case of the match when preserving case
+    pub fn replacement(&self, m: &Match) -> Option<String> {
+        let (_, (start, end), line) = m;
+        let text = if self.is_regex {
+            let captures =
+                self.regex.as_ref()?.captures_iter(line).find(|captures| {
+                    captures.get(0).map(|m| m.start()) == Some(*start)
+                })?;
+            let mut text = String::new();
+            captures.expand(&self.replace, &mut text);
+            text
+        } else {
+            self.replace.clone()
+        };
+        if self.preserve_case {
+            Some(preserve_case(line.get(*start..*end)?, &text))
+        } else {
+            Some(text)
+        }
+    }
+
+    /// The replacements of all the matches that aren't excluded, by file
+    pub fn replacements(&self) -> HashMap<PathBuf, Vec<SearchReplacement>> {
+        self.matches
+            .iter()
+            .filter_map(|(path, matches)| {
+                let replacements = matches
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !self.is_excluded(path, *i))
+                    .filter_map(|(_, m)| {
+                        let (line, (start, end), content) = m;
+                        Some(SearchReplacement {
+                            line: line.saturating_sub(1),
+                            start: *start,
+                            end: *end,
+                            matched: content.get(*start..*end)?.to_string(),
+                            text: self.replacement(m)?,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                if replacements.is_empty() {
+                    None
+                } else {
+                    Some((path.clone(), replacements))
+                }
+            })
+            .collect()
+    }
 }
 
 impl Default for SearchData {
@@ -30,3 +168,63 @@ fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn search(pattern: &str, is_regex: bool, replace: &str) -> SearchData {
+        let mut search = SearchData::new();
+        search.is_regex = is_regex;
+        search.replace = replace.to_string();
+        let matches = vec![
+            (1, (4, 11), "let foo_bar = 1;\n".to_string()),
+            (3, (0, 7), "Foo_Baz();\n".to_string()),
+        ];
+        search.set_matches(
+            pattern,
+            Arc::new(HashMap::from([(PathBuf::from("a.rs"), matches)])),
+        );
+        search
+    }
+
+    #[test]
+    fn test_replacement_with_captures() {
+        let search = search(r"foo_(?P<name>\w+)", true, "${name}_$0");
+        let path = PathBuf::from("a.rs");
+        let replacements = search.replacements();
+        let replacements = replacements.get(&path).unwrap();
+        assert_eq!(replacements.len(), 2);
+        assert_eq!(replacements[0].line, 0);
+        assert_eq!(replacements[0].matched, "foo_bar");
+        assert_eq!(replacements[1].text, "Baz_Foo_Baz");
+    }
+
+    #[test]
+    fn test_replacement_is_literal_without_regex() {
+        let search = search("foo_", false, "$1");
+        let path = PathBuf::from("a.rs");
+        assert_eq!(search.replacements()[&path][1].text, "$1");
+    }
+
+    #[test]
+    fn test_replacement_preserving_case() {
+        let mut search = search("foo", false, "qux");
+        search.preserve_case = true;
+        let path = PathBuf::from("a.rs");
+        let replacements = search.replacements();
+        assert_eq!(replacements[&path][0].text, "qux");
+        assert_eq!(replacements[&path][1].text, "Qux");
+    }
+
+    #[test]
+    fn test_excluded_matches_are_not_replaced() {
+        let mut search = search("foo", false, "x");
+        let path = PathBuf::from("a.rs");
+        search.toggle_excluded(&path, 0);
+        assert_eq!(search.replacements()[&path].len(), 1);
+        assert_eq!(search.replacements()[&path][0].line, 2);
+        search.toggle_excluded(&path, 1);
+        assert!(search.replacements().is_empty());
+    }
+}
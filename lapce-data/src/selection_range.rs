@@ -1,3 +1,4 @@
+use lapce_core::{buffer::Buffer, selection::Selection};
 use lapce_rpc::buffer::BufferId;
 use lsp_types::{Range, SelectionRange};
 
@@ -91,9 +92,58 @@ fn count(&self) -> usize {
     }
 }
 
+/// The ranges of the lsp selection ranges, and all their parents, for the
+/// regions of an editor selection, used to expand that selection.
+#[derive(Clone, Debug)]
+pub struct ExpandSelectionRanges {
+    pub rev: u64,
+    /// The selection the ranges are for, which follows it as it's expanded
+    pub selection: Selection,
+    /// Sorted `(start, end)` offsets
+    pub ranges: Vec<(usize, usize)>,
+}
+
+impl ExpandSelectionRanges {
+    pub fn new(
+        buffer: &Buffer,
+        rev: u64,
+        selection: Selection,
+        ranges: &[SelectionRange],
+    ) -> Self {
+        let mut offsets = Vec::new();
+        for range in ranges {
+            let mut range = Some(range);
+            while let Some(r) = range {
+                offsets.push((
+                    buffer.offset_of_position(&r.range.start),
+                    buffer.offset_of_position(&r.range.end),
+                ));
+                range = r.parent.as_deref();
+            }
+        }
+        offsets.sort_unstable();
+        offsets.dedup();
+        Self {
+            rev,
+            selection,
+            ranges: offsets,
+        }
+    }
+
+    /// The smallest range that contains `start..end` and is larger than it
+    pub fn expand(&self, start: usize, end: usize) -> Option<(usize, usize)> {
+        self.ranges
+            .iter()
+            .filter(|(s, e)| *s <= start && end <= *e && (*s, *e) != (start, end))
+            .min_by_key(|(s, e)| e - s)
+            .copied()
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::selection_range::SyntaxSelectionRanges;
+    use crate::selection_range::{ExpandSelectionRanges, SyntaxSelectionRanges};
+    use lapce_core::selection::Selection;
     use lapce_rpc::buffer::BufferId;
     use lsp_types::{Position, Range, SelectionRange};
 
@@ -178,4 +228,19 @@ fn should_get_next_selection_range() {
         assert_eq!(range, Some(range_zero));
         assert_eq!(syntax_selection.current_selection, Some(0));
     }
+
+    #[test]
+    fn should_expand_to_the_smallest_larger_range() {
+        let ranges = ExpandSelectionRanges {
+            rev: 0,
+            selection: Selection::caret(5),
+            ranges: vec![(0, 20), (4, 6), (4, 12), (5, 5)],
+        };
+
+        assert_eq!(ranges.expand(5, 5), Some((4, 6)));
+        assert_eq!(ranges.expand(4, 6), Some((4, 12)));
+        assert_eq!(ranges.expand(4, 12), Some((0, 20)));
+        assert_eq!(ranges.expand(0, 20), None);
+        assert_eq!(ranges.expand(13, 14), Some((0, 20)));
+    }
 }
@@ -0,0 +1,426 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// The most commands kept for a terminal, the oldest being dropped first
+const MAX_COMMANDS: usize = 1000;
+
+/// The longest OSC sequence looked at, so that a sequence that never ends
+/// doesn't grow the buffer forever
+const MAX_SEQUENCE_LEN: usize = 64 * 1024;
+
+/// A mark that the shell integration puts in the output of the terminal,
+/// with an OSC 633 sequence, or the OSC 7 sequence of the working directory
+/// that some shells send by themselves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellMark {
+    /// `A`, where the prompt starts
+    PromptStart,
+    /// `B`, where the prompt ends and the command is typed
+    CommandStart,
+    /// `C`, where the command is run and its output starts
+    CommandExecuted,
+    /// `D;<exit code>`, where the command has finished
+    CommandFinished(Option<i32>),
+    /// `E;<command line>`, the command line that is run
+    CommandLine(String),
+    /// `P;Cwd=<path>`, or OSC 7 `file://<host>/<path>`, the working
+    /// directory of the shell
+    Cwd(PathBuf),
+}
+
+impl ShellMark {
+    fn parse(sequence: &[u8]) -> Option<ShellMark> {
+        if let Some(url) = sequence.strip_prefix(b"7;") {
+            let url = std::str::from_utf8(url).ok()?.strip_prefix("file://")?;
+            let path = &url[url.find('/')?..];
+            return Some(ShellMark::Cwd(PathBuf::from(percent_decode(path))));
+        }
+        let sequence = sequence.strip_prefix(b"633;")?;
+        let (kind, value) = match sequence.iter().position(|b| *b == b';') {
+            Some(i) => (&sequence[..i], Some(&sequence[i + 1..])),
+            None => (sequence, None),
+        };
+        let mark = match kind {
+            b"A" => ShellMark::PromptStart,
+            b"B" => ShellMark::CommandStart,
+            b"C" => ShellMark::CommandExecuted,
+            b"D" => ShellMark::CommandFinished(
+                value
+                    .and_then(|value| std::str::from_utf8(value).ok())
+                    .and_then(|value| value.trim().parse().ok()),
+            ),
+            b"E" => ShellMark::CommandLine(unescape(value?)),
+            b"P" => {
+                let cwd = unescape(value?.strip_prefix(b"Cwd=")?);
+                ShellMark::Cwd(PathBuf::from(cwd))
+            }
+            _ => return None,
+        };
+        Some(mark)
+    }
+}
+
+/// Undo the escaping of a value of a mark, where `\\` is a backslash and
+/// `\xAB` is the byte of the hex digits, like `\x3b` for a `;`
+fn unescape(value: &[u8]) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut i = 0;
+    while i < value.len() {
+        if value[i] == b'\\' {
+            if value.get(i + 1) == Some(&b'\\') {
+                bytes.push(b'\\');
+                i += 2;
+                continue;
+            }
+            if value.get(i + 1) == Some(&b'x') {
+                if let Some(byte) = value
+                    .get(i + 2..i + 4)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    bytes.push(byte);
+                    i += 4;
+                    continue;
+                }
+            }
+        }
+        bytes.push(value[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// Decode the `%AB` bytes of the path of a URL
+fn percent_decode(path: &str) -> String {
+    let path = path.as_bytes();
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut i = 0;
+    while i < path.len() {
+        if path[i] == b'%' {
+            if let Some(byte) = path
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                bytes.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        bytes.push(path[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParserState {
+    Ground,
+    Escape,
+    Osc,
+    /// An escape in an OSC sequence, which is its end if `\` follows
+    OscEscape,
+}
+
+/// Finds the marks in the output of the terminal, whose sequences can be
+/// split across the chunks of the output
+pub struct ShellMarkParser {
+    state: ParserState,
+    sequence: Vec<u8>,
+}
+
+impl Default for ShellMarkParser {
+    fn default() -> Self {
+        Self {
+            state: ParserState::Ground,
+            sequence: Vec::new(),
+        }
+    }
+}
+
+impl ShellMarkParser {
+    /// Look at the next byte of the output, which gives the mark whose
+    /// sequence it ends
+    pub fn advance(&mut self, byte: u8) -> Option<ShellMark> {
+        match (self.state, byte) {
+            (ParserState::Ground, 0x1b) => {
+                self.state = ParserState::Escape;
+            }
+            (ParserState::Ground, _) => {}
+            (ParserState::Escape, b']') => {
+                self.state = ParserState::Osc;
+                self.sequence.clear();
+            }
+            (ParserState::Escape, 0x1b) => {}
+            (ParserState::Escape, _) => {
+                self.state = ParserState::Ground;
+            }
+            (ParserState::Osc, 0x07) | (ParserState::OscEscape, b'\\') => {
+                self.state = ParserState::Ground;
+                let mark = ShellMark::parse(&self.sequence);
+                self.sequence.clear();
+                return mark;
+            }
+            (ParserState::Osc, 0x1b) => {
+                self.state = ParserState::OscEscape;
+            }
+            (ParserState::Osc, _) => {
+                if self.sequence.len() < MAX_SEQUENCE_LEN {
+                    self.sequence.push(byte);
+                }
+            }
+            (ParserState::OscEscape, b']') => {
+                self.state = ParserState::Osc;
+                self.sequence.clear();
+            }
+            (ParserState::OscEscape, _) => {
+                self.state = ParserState::Ground;
+                self.sequence.clear();
+            }
+        }
+        None
+    }
+}
+
+/// A command run in the terminal, as the shell integration marked it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalCommand {
+    /// The line of its prompt, counted from the top of the scrollback
+    pub line: usize,
+    /// The command line, for the shells that tell it
+    pub command: Option<String>,
+    pub started: Option<Instant>,
+    /// How long the command ran, once it has finished
+    pub duration: Option<Duration>,
+    pub exit_code: Option<i32>,
+}
+
+impl TerminalCommand {
+    fn new(line: usize) -> Self {
+        Self {
+            line,
+            command: None,
+            started: None,
+            duration: None,
+            exit_code: None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.duration.is_some()
+    }
+}
+
+/// The commands run in a terminal, from the marks of the shell integration
+#[derive(Default)]
+pub struct TerminalCommands {
+    parser: ShellMarkParser,
+    commands: Vec<TerminalCommand>,
+    /// The command of the latest prompt, until it has finished
+    current: Option<TerminalCommand>,
+    /// The working directory that the shell told last
+    cwd: Option<PathBuf>,
+    /// The commands that finished since they were last taken
+    finished: Vec<TerminalCommand>,
+}
+
+impl TerminalCommands {
+    /// Look at the next byte of the output, which is at the line counted
+    /// from the top of the scrollback
+    pub fn advance(&mut self, byte: u8, line: usize) {
+        if let Some(mark) = self.parser.advance(byte) {
+            self.mark(mark, line, Instant::now());
+        }
+    }
+
+    fn mark(&mut self, mark: ShellMark, line: usize, now: Instant) {
+        match mark {
+            ShellMark::PromptStart => {
+                // A command that never told it finished, like when the shell
+                // was interrupted, is still kept
+                if let Some(command) = self.current.take() {
+                    if command.started.is_some() {
+                        self.push(command);
+                    }
+                }
+                self.current = Some(TerminalCommand::new(line));
+            }
+            ShellMark::CommandStart => {}
+            ShellMark::Cwd(cwd) => {
+                self.cwd = Some(cwd);
+            }
+            ShellMark::CommandLine(command_line) => {
+                self.current
+                    .get_or_insert_with(|| TerminalCommand::new(line))
+                    .command = Some(command_line);
+            }
+            ShellMark::CommandExecuted => {
+                self.current
+                    .get_or_insert_with(|| TerminalCommand::new(line))
+                    .started = Some(now);
+            }
+            ShellMark::CommandFinished(exit_code) => {
+                if let Some(mut command) = self.current.take() {
+                    if let Some(started) = command.started {
+                        command.duration = Some(now.duration_since(started));
+                        command.exit_code = exit_code;
+                        self.finished.push(command.clone());
+                        self.push(command);
+                    }
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, command: TerminalCommand) {
+        if self.commands.len() >= MAX_COMMANDS {
+            self.commands.remove(0);
+        }
+        self.commands.push(command);
+    }
+
+    /// The commands that were run, with the one that's still running last
+    pub fn iter(&self) -> impl Iterator<Item = &TerminalCommand> {
+        self.commands.iter().chain(
+            self.current
+                .iter()
+                .filter(|command| command.started.is_some()),
+        )
+    }
+
+    /// The command before the line, or after it, to scroll to
+    pub fn command_near(
+        &self,
+        line: usize,
+        previous: bool,
+    ) -> Option<&TerminalCommand> {
+        if previous {
+            self.iter().filter(|command| command.line < line).last()
+        } else {
+            self.iter().find(|command| command.line > line)
+        }
+    }
+
+    /// Forget the first lines, which are cleared, with the commands in
+    /// them, so that the lines of the others are counted from the new top
+    pub fn remove_lines(&mut self, count: usize) {
+        self.commands.retain(|command| command.line >= count);
+        for command in self.commands.iter_mut().chain(self.current.iter_mut()) {
+            command.line = command.line.saturating_sub(count);
+        }
+    }
+
+    /// The commands that finished since this was last called
+    pub fn take_finished(&mut self) -> Vec<TerminalCommand> {
+        std::mem::take(&mut self.finished)
+    }
+
+    /// The working directory of the shell, for the shells that tell it
+    pub fn cwd(&self) -> Option<&Path> {
+        self.cwd.as_deref()
+    }
+
+    /// The command line of the latest command, to run it again
+    pub fn last_command_line(&self) -> Option<&str> {
+        self.iter()
+            .filter_map(|command| command.command.as_deref())
+            .filter(|command| !command.trim().is_empty())
+            .last()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn marks(output: &[u8]) -> Vec<ShellMark> {
+        let mut parser = ShellMarkParser::default();
+        output.iter().filter_map(|b| parser.advance(*b)).collect()
+    }
+
+    #[test]
+    fn test_shell_marks() {
+        assert_eq!(
+            marks(b"\x1b]633;A\x07$ \x1b]633;B\x1b\\ls\r\n\x1b]0;title\x07"),
+            vec![ShellMark::PromptStart, ShellMark::CommandStart]
+        );
+        assert_eq!(
+            marks(
+                b"\x1b]633;E;echo a\\x3bb \\\\\x07\x1b]633;C\x07a\x1b]633;D;2\x07"
+            ),
+            vec![
+                ShellMark::CommandLine("echo a;b \\".to_string()),
+                ShellMark::CommandExecuted,
+                ShellMark::CommandFinished(Some(2)),
+            ]
+        );
+        assert_eq!(
+            marks(b"\x1b]633;D\x07\x1b]633;Z\x07"),
+            vec![ShellMark::CommandFinished(None)]
+        );
+        assert_eq!(
+            marks(b"\x1b]633;P;Cwd=/a\\x3bb\x07\x1b]7;file://host/c%20d\x1b\\"),
+            vec![
+                ShellMark::Cwd(PathBuf::from("/a;b")),
+                ShellMark::Cwd(PathBuf::from("/c d")),
+            ]
+        );
+
+        // A sequence split between two chunks of output
+        let mut parser = ShellMarkParser::default();
+        assert_eq!(parser.advance(0x1b), None);
+        assert_eq!(parser.advance(b']'), None);
+        assert!(b"633;".iter().all(|b| parser.advance(*b).is_none()));
+        assert_eq!(parser.advance(b'C'), None);
+        assert_eq!(parser.advance(0x07), Some(ShellMark::CommandExecuted));
+    }
+
+    #[test]
+    fn test_terminal_commands() {
+        let now = Instant::now();
+        let mut commands = TerminalCommands::default();
+        commands.mark(ShellMark::PromptStart, 0, now);
+        commands.mark(ShellMark::CommandLine("make".to_string()), 0, now);
+        commands.mark(ShellMark::CommandExecuted, 1, now);
+        commands.mark(
+            ShellMark::CommandFinished(Some(1)),
+            5,
+            now + Duration::from_secs(2),
+        );
+
+        // A prompt where nothing was run
+        commands.mark(ShellMark::PromptStart, 6, now);
+        commands.mark(ShellMark::CommandFinished(Some(0)), 7, now);
+
+        commands.mark(ShellMark::PromptStart, 7, now);
+        commands.mark(ShellMark::CommandLine("cargo run".to_string()), 7, now);
+        commands.mark(ShellMark::CommandExecuted, 8, now);
+
+        let lines: Vec<usize> = commands.iter().map(|c| c.line).collect();
+        assert_eq!(lines, vec![0, 7]);
+        let first = commands.iter().next().unwrap();
+        assert_eq!(first.exit_code, Some(1));
+        assert_eq!(first.duration, Some(Duration::from_secs(2)));
+        assert!(!commands.iter().last().unwrap().is_finished());
+
+        assert_eq!(commands.command_near(7, true).map(|c| c.line), Some(0));
+        assert_eq!(commands.command_near(0, true), None);
+        assert_eq!(commands.command_near(3, false).map(|c| c.line), Some(7));
+        assert_eq!(commands.last_command_line(), Some("cargo run"));
+
+        let finished = commands.take_finished();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].command.as_deref(), Some("make"));
+        assert!(commands.take_finished().is_empty());
+
+        // The terminal is cleared up to the prompt of the running command
+        commands.remove_lines(7);
+        let lines: Vec<usize> = commands.iter().map(|c| c.line).collect();
+        assert_eq!(lines, vec![0]);
+        assert_eq!(commands.last_command_line(), Some("cargo run"));
+    }
+}
@@ -1,65 +1,242 @@
-use lsp_types::ParameterLabel;
-use lsp_types::SignatureHelp;
+use std::{cell::RefCell, path::PathBuf, rc::Rc, sync::Arc};
+
+use druid::{ExtEventSink, Size, Target, WidgetId};
+use lapce_rpc::{buffer::BufferId, proxy::ProxyResponse};
+use lsp_types::{
+    ParameterLabel, Position, SignatureHelp, SignatureHelpContext,
+    SignatureHelpTriggerKind, SignatureInformation,
+};
+
+use crate::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    proxy::LapceProxy,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Inactive,
+    Started,
+    Done,
+}
 
 #[derive(Clone)]
-pub struct SignatureState {
-    pub offset: Option<usize>,
-    pub signature: Option<SignatureHelp>,
-    pub active: Option<(usize, usize)>,
+pub struct SignatureData {
+    pub id: WidgetId,
+    /// The editor view id that the signature is displayed for
+    pub editor_view_id: WidgetId,
+    pub status: SignatureStatus,
+    /// The offset of the cursor the signature was last asked for
+    pub offset: usize,
+    pub buffer_id: BufferId,
+    /// A counter to keep track of the active requests
+    pub request_id: usize,
+    /// The signature help of the server, with every overload of the call
+    pub signature: Option<Arc<SignatureHelp>>,
+    /// The overload that is shown, which starts at the one the server says
+    /// is active, and can be cycled through
+    pub active_signature: usize,
+    /// The maximum size of the signature box
+    pub size: Size,
+    /// Stores the actual size of the signature content
+    pub content_size: Rc<RefCell<Size>>,
 }
 
-impl SignatureState {
+impl SignatureData {
     pub fn new() -> Self {
         Self {
-            offset: None,
+            id: WidgetId::next(),
+            editor_view_id: WidgetId::next(),
+            status: SignatureStatus::Inactive,
+            offset: 0,
+            buffer_id: BufferId(0),
+            request_id: 0,
             signature: None,
-            active: None,
+            active_signature: 0,
+            size: Size::new(600.0, 300.0),
+            content_size: Rc::new(RefCell::new(Size::ZERO)),
         }
     }
 
-    pub fn update(&mut self, offset: usize, commas: Vec<usize>) -> Option<bool> {
-        let signature = self.signature.as_ref()?;
-        let params = signature.signatures[0].parameters.as_ref()?;
-        if params.is_empty() {
-            return None;
+    /// The number of overloads of the call
+    pub fn len(&self) -> usize {
+        self.signature
+            .as_ref()
+            .map(|signature| signature.signatures.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status != SignatureStatus::Inactive
+    }
+
+    pub fn cancel(&mut self) {
+        if self.status == SignatureStatus::Inactive {
+            return;
+        }
+
+        self.status = SignatureStatus::Inactive;
+        self.signature = None;
+        self.active_signature = 0;
+    }
+
+    /// The overload that is shown
+    pub fn current(&self) -> Option<&SignatureInformation> {
+        self.signature
+            .as_ref()?
+            .signatures
+            .get(self.active_signature)
+    }
+
+    pub fn next(&mut self) {
+        let len = self.len();
+        if len > 0 {
+            self.active_signature = (self.active_signature + 1) % len;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        let len = self.len();
+        if len > 0 {
+            self.active_signature = (self.active_signature + len - 1) % len;
         }
-        let mut index = commas.len();
-        for (i, c) in commas.iter().enumerate() {
-            if offset <= *c {
-                index = i;
-                break;
+    }
+
+    /// The range of the active parameter in the label of the overload that
+    /// is shown, in bytes
+    pub fn active_parameter(&self) -> Option<(usize, usize)> {
+        let signature = self.signature.as_ref()?;
+        let current = self.current()?;
+        let index = current.active_parameter.or(signature.active_parameter)?;
+        let parameter = current.parameters.as_ref()?.get(index as usize)?;
+        match &parameter.label {
+            ParameterLabel::Simple(s) => {
+                let start = current.label.find(s.as_str())?;
+                Some((start, start + s.len()))
+            }
+            ParameterLabel::LabelOffsets([start, end]) => {
+                let start = utf16_to_utf8(&current.label, *start as usize)?;
+                let end = utf16_to_utf8(&current.label, *end as usize)?;
+                Some((start, end))
             }
         }
+    }
 
-        let label = signature.signatures[0].label.clone();
-        let active = if index >= params.len() {
-            None
-        } else {
-            match &params[index].label {
-                ParameterLabel::Simple(s) => {
-                    let start = label.find(s)?;
-                    Some((start, start + s.len()))
-                }
-                ParameterLabel::LabelOffsets(offsets) => {
-                    Some((offsets[0] as usize, offsets[1] as usize))
+    /// Ask for the signature of the call at the position, because the
+    /// character was typed or, when there is none, because the text around
+    /// the signature that is shown changed
+    pub fn request(
+        &mut self,
+        proxy: Arc<LapceProxy>,
+        path: PathBuf,
+        position: Position,
+        trigger_character: Option<String>,
+        signature_widget_id: WidgetId,
+        event_sink: ExtEventSink,
+    ) {
+        let is_retrigger = self.signature.is_some();
+        let context = SignatureHelpContext {
+            trigger_kind: if trigger_character.is_some() {
+                SignatureHelpTriggerKind::TRIGGER_CHARACTER
+            } else {
+                SignatureHelpTriggerKind::CONTENT_CHANGE
+            },
+            trigger_character,
+            is_retrigger,
+            active_signature_help: self.signature.as_ref().map(|signature| {
+                SignatureHelp {
+                    active_signature: Some(self.active_signature as u32),
+                    ..(**signature).clone()
                 }
-            }
+            }),
         };
-
-        let changed = self.active != active;
-        self.active = active;
-        Some(changed)
+        let request_id = self.request_id;
+        proxy.proxy_rpc.get_signature(
+            request_id,
+            path,
+            position,
+            Some(context),
+            move |result| {
+                // the servers that have no signature there answer with an
+                // error
+                let signature = match result {
+                    Ok(ProxyResponse::SignatureResponse { signature, .. }) => {
+                        Some(signature)
+                    }
+                    _ => None,
+                };
+                let _ = event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::UpdateSignature(request_id, signature),
+                    Target::Widget(signature_widget_id),
+                );
+            },
+        );
     }
 
-    pub fn clear(&mut self) {
-        self.offset = None;
-        self.signature = None;
-        self.active = None;
+    /// Receive the result of a signature request, which is `None` when there
+    /// is no call around the position anymore
+    pub fn receive(&mut self, request_id: usize, signature: Option<SignatureHelp>) {
+        if self.status == SignatureStatus::Inactive || self.request_id != request_id
+        {
+            return;
+        }
+
+        let signature = match signature {
+            Some(signature) if !signature.signatures.is_empty() => signature,
+            _ => {
+                self.cancel();
+                return;
+            }
+        };
+
+        // keep the overload that was cycled to, unless the server changed
+        // the overloads
+        let same_overloads = self
+            .signature
+            .as_ref()
+            .map(|old| {
+                old.signatures.len() == signature.signatures.len()
+                    && old
+                        .signatures
+                        .iter()
+                        .zip(signature.signatures.iter())
+                        .all(|(old, new)| old.label == new.label)
+            })
+            .unwrap_or(false);
+        if !same_overloads {
+            self.active_signature = signature.active_signature.unwrap_or(0) as usize;
+        }
+        if self.active_signature >= signature.signatures.len() {
+            self.active_signature = 0;
+        }
+        self.status = SignatureStatus::Done;
+        self.signature = Some(Arc::new(signature));
     }
 }
 
-impl Default for SignatureState {
+impl Default for SignatureData {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// The byte offset in the text of the offset in UTF-16 code units the
+/// language server protocol uses
+fn utf16_to_utf8(text: &str, offset: usize) -> Option<usize> {
+    let mut utf16 = 0;
+    for (utf8, c) in text.char_indices() {
+        if utf16 >= offset {
+            return Some(utf8);
+        }
+        utf16 += c.len_utf16();
+    }
+    if utf16 >= offset {
+        Some(text.len())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,1077 @@
+use std::{collections::HashMap, fmt::Display, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, Error, Result};
+use itertools::Itertools;
+use lapce_proxy::directory::Directory;
+use lsp_types::{CompletionItem, CompletionItemKind, InsertTextFormat};
+use regex::{Captures, RegexBuilder};
+use serde::Deserialize;
+
+/// A snippet in the syntax of the LSP and TextMate, with tab stops,
+/// placeholders, choices and variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    elements: Vec<SnippetElement>,
+}
+
+impl Snippet {
+    pub fn text(&self) -> String {
+        self.elements.iter().map(|e| e.text()).join("")
+    }
+
+    /// The tab stops of the snippet inserted at `pos`, in the order they are
+    /// visited, which is by their number with `$0` last
+    pub fn tabs(&self, pos: usize) -> Vec<(usize, (usize, usize))> {
+        let mut tabs = Self::elements_tabs(&self.elements, pos);
+        tabs.sort_by_key(|(tab, _)| (*tab == 0, *tab));
+        tabs
+    }
+
+    pub fn elements_tabs(
+        elements: &[SnippetElement],
+        start: usize,
+    ) -> Vec<(usize, (usize, usize))> {
+        let mut tabs = Vec::new();
+        let mut pos = start;
+        for el in elements {
+            match el {
+                SnippetElement::Text(t) => {
+                    pos += t.len();
+                }
+                SnippetElement::PlaceHolder(tab, els) => {
+                    let placeholder_tabs = Self::elements_tabs(els, pos);
+                    let end = pos + els.iter().map(|e| e.len()).sum::<usize>();
+                    tabs.push((*tab, (pos, end)));
+                    tabs.extend_from_slice(&placeholder_tabs);
+                    pos = end;
+                }
+                SnippetElement::Tabstop(tab) => {
+                    tabs.push((*tab, (pos, pos)));
+                }
+                SnippetElement::Choice(tab, _) => {
+                    let end = pos + el.len();
+                    tabs.push((*tab, (pos, end)));
+                    pos = end;
+                }
+                SnippetElement::Variable(_, els, _) => {
+                    tabs.extend(Self::elements_tabs(els, pos));
+                    pos += el.len();
+                }
+            }
+        }
+        tabs
+    }
+
+    /// The range and the options of each choice of the snippet inserted at
+    /// `pos`
+    pub fn choices(&self, pos: usize) -> Vec<((usize, usize), Vec<String>)> {
+        fn collect(
+            elements: &[SnippetElement],
+            mut pos: usize,
+            choices: &mut Vec<((usize, usize), Vec<String>)>,
+        ) {
+            for el in elements {
+                match el {
+                    SnippetElement::Choice(_, options) => {
+                        choices.push(((pos, pos + el.len()), options.clone()));
+                    }
+                    SnippetElement::PlaceHolder(_, els)
+                    | SnippetElement::Variable(_, els, _) => {
+                        collect(els, pos, choices);
+                    }
+                    SnippetElement::Text(_) | SnippetElement::Tabstop(_) => {}
+                }
+                pos += el.len();
+            }
+        }
+
+        let mut choices = Vec::new();
+        collect(&self.elements, pos, &mut choices);
+        choices
+    }
+
+    /// Replace the variables of the snippet with their values. A variable
+    /// without a value is replaced with its default, or with its name if it
+    /// has no default and isn't one that `resolve` knows of.
+    pub fn resolve_variables(
+        self,
+        resolve: &dyn Fn(&str) -> Option<String>,
+    ) -> Snippet {
+        fn resolve_elements(
+            elements: Vec<SnippetElement>,
+            resolve: &dyn Fn(&str) -> Option<String>,
+        ) -> Vec<SnippetElement> {
+            let mut resolved = Vec::new();
+            for el in elements {
+                match el {
+                    SnippetElement::Variable(name, default, transform) => {
+                        match resolve(&name) {
+                            Some(value) if !value.is_empty() => {
+                                let value = match &transform {
+                                    Some(transform) => transform.apply(&value),
+                                    None => value,
+                                };
+                                resolved.push(SnippetElement::Text(value));
+                            }
+                            value => {
+                                if !default.is_empty() {
+                                    resolved
+                                        .extend(resolve_elements(default, resolve));
+                                } else if value.is_none() {
+                                    resolved.push(SnippetElement::Text(name));
+                                }
+                            }
+                        }
+                    }
+                    SnippetElement::PlaceHolder(tab, els) => {
+                        resolved.push(SnippetElement::PlaceHolder(
+                            tab,
+                            resolve_elements(els, resolve),
+                        ));
+                    }
+                    el => resolved.push(el),
+                }
+            }
+            resolved
+        }
+
+        Snippet {
+            elements: resolve_elements(self.elements, resolve),
+        }
+    }
+}
+
+impl FromStr for Snippet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = SnippetParser { s, pos: 0 };
+        let elements = parser.parse_elements(false);
+        Ok(Snippet { elements })
+    }
+}
+
+impl Display for Snippet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = self.elements.iter().map(|e| e.to_string()).join("");
+        f.write_str(&text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnippetElement {
+    Text(String),
+    PlaceHolder(usize, Vec<SnippetElement>),
+    Tabstop(usize),
+    /// A tab stop with a list of texts to pick from, the first of which is
+    /// inserted
+    Choice(usize, Vec<String>),
+    /// A variable like `$TM_FILENAME`, with the elements inserted when it has
+    /// no value and the transform of its value
+    Variable(String, Vec<SnippetElement>, Option<SnippetTransform>),
+}
+
+impl SnippetElement {
+    pub fn len(&self) -> usize {
+        match &self {
+            SnippetElement::Text(text) => text.len(),
+            SnippetElement::PlaceHolder(_, elements)
+            | SnippetElement::Variable(_, elements, _) => {
+                elements.iter().map(|e| e.len()).sum()
+            }
+            SnippetElement::Tabstop(_) => 0,
+            SnippetElement::Choice(_, options) => {
+                options.first().map(|o| o.len()).unwrap_or(0)
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn text(&self) -> String {
+        match &self {
+            SnippetElement::Text(t) => t.to_string(),
+            SnippetElement::PlaceHolder(_, elements)
+            | SnippetElement::Variable(_, elements, _) => {
+                elements.iter().map(|e| e.text()).join("")
+            }
+            SnippetElement::Tabstop(_) => "".to_string(),
+            SnippetElement::Choice(_, options) => {
+                options.first().cloned().unwrap_or_default()
+            }
+        }
+    }
+}
+
+impl Display for SnippetElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            SnippetElement::Text(text) => f.write_str(&escape(text, "$}\\")),
+            SnippetElement::PlaceHolder(tab, elements) => {
+                let elements = elements.iter().map(|e| e.to_string()).join("");
+                write!(f, "${{{}:{}}}", tab, elements)
+            }
+            SnippetElement::Tabstop(tab) => write!(f, "${}", tab),
+            SnippetElement::Choice(tab, options) => {
+                let options = options.iter().map(|o| escape(o, ",|\\")).join(",");
+                write!(f, "${{{}|{}|}}", tab, options)
+            }
+            SnippetElement::Variable(name, elements, transform) => {
+                if let Some(transform) = transform {
+                    write!(f, "${{{}{}}}", name, transform)
+                } else if elements.is_empty() {
+                    write!(f, "${}", name)
+                } else {
+                    let elements = elements.iter().map(|e| e.to_string()).join("");
+                    write!(f, "${{{}:{}}}", name, elements)
+                }
+            }
+        }
+    }
+}
+
+fn escape(text: &str, chars: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if chars.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A regex replacement applied to the value of a variable, written as
+/// `${VAR/regex/format/options}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetTransform {
+    pub regex: String,
+    pub format: Vec<FormatItem>,
+    /// `g` replaces every match instead of the first one, `i` ignores case
+    /// and `m` makes `^` and `$` match at line breaks
+    pub options: String,
+}
+
+impl SnippetTransform {
+    pub fn apply(&self, value: &str) -> String {
+        let regex = match RegexBuilder::new(&self.regex)
+            .case_insensitive(self.options.contains('i'))
+            .multi_line(self.options.contains('m'))
+            .build()
+        {
+            Ok(regex) => regex,
+            Err(_) => return value.to_string(),
+        };
+        let replace = |captures: &Captures| {
+            self.format
+                .iter()
+                .map(|item| item.format(captures))
+                .join("")
+        };
+        if self.options.contains('g') {
+            regex.replace_all(value, replace).to_string()
+        } else {
+            regex.replace(value, replace).to_string()
+        }
+    }
+}
+
+impl Display for SnippetTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = self.format.iter().map(|item| item.to_string()).join("");
+        write!(
+            f,
+            "/{}/{}/{}",
+            self.regex.replace('/', "\\/"),
+            format,
+            self.options
+        )
+    }
+}
+
+/// A part of the format of a transform
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatItem {
+    Text(String),
+    /// A capture group of the match, like `$1`
+    Group(usize),
+    /// A capture group with its case changed, like `${1:/upcase}`
+    Case(usize, CaseModifier),
+    /// `${1:+if}`, `${1:?if:else}` or `${1:-else}`, depending on whether the
+    /// capture group matched
+    Conditional(usize, Option<String>, Option<String>),
+}
+
+impl FormatItem {
+    fn format(&self, captures: &Captures) -> String {
+        let group = |n: &usize| {
+            captures
+                .get(*n)
+                .map(|m| m.as_str())
+                .filter(|s| !s.is_empty())
+        };
+        match self {
+            FormatItem::Text(text) => text.clone(),
+            FormatItem::Group(n) => group(n).unwrap_or("").to_string(),
+            FormatItem::Case(n, modifier) => {
+                group(n).map(|s| modifier.apply(s)).unwrap_or_default()
+            }
+            FormatItem::Conditional(n, if_matched, otherwise) => {
+                if group(n).is_some() {
+                    if_matched.clone().unwrap_or_default()
+                } else {
+                    match otherwise {
+                        Some(otherwise) => otherwise.clone(),
+                        None => String::new(),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Display for FormatItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatItem::Text(text) => f.write_str(&escape(text, "$/\\")),
+            FormatItem::Group(n) => write!(f, "${}", n),
+            FormatItem::Case(n, modifier) => {
+                write!(f, "${{{}:/{}}}", n, modifier)
+            }
+            FormatItem::Conditional(n, if_matched, otherwise) => {
+                match (if_matched, otherwise) {
+                    (Some(i), Some(o)) => write!(f, "${{{}:?{}:{}}}", n, i, o),
+                    (Some(i), None) => write!(f, "${{{}:+{}}}", n, i),
+                    (None, Some(o)) => write!(f, "${{{}:-{}}}", n, o),
+                    (None, None) => write!(f, "${{{}:-}}", n),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseModifier {
+    Upcase,
+    Downcase,
+    Capitalize,
+    Camelcase,
+    Pascalcase,
+}
+
+impl CaseModifier {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "upcase" => CaseModifier::Upcase,
+            "downcase" => CaseModifier::Downcase,
+            "capitalize" => CaseModifier::Capitalize,
+            "camelcase" => CaseModifier::Camelcase,
+            "pascalcase" => CaseModifier::Pascalcase,
+            _ => return None,
+        })
+    }
+
+    fn apply(&self, text: &str) -> String {
+        fn capitalize(word: &str) -> String {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+
+        let words = || {
+            text.split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+        };
+        match self {
+            CaseModifier::Upcase => text.to_uppercase(),
+            CaseModifier::Downcase => text.to_lowercase(),
+            CaseModifier::Capitalize => capitalize(text),
+            CaseModifier::Pascalcase => words().map(capitalize).join(""),
+            CaseModifier::Camelcase => words()
+                .enumerate()
+                .map(|(i, word)| {
+                    if i == 0 {
+                        word.to_lowercase()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .join(""),
+        }
+    }
+}
+
+impl Display for CaseModifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CaseModifier::Upcase => "upcase",
+            CaseModifier::Downcase => "downcase",
+            CaseModifier::Capitalize => "capitalize",
+            CaseModifier::Camelcase => "camelcase",
+            CaseModifier::Pascalcase => "pascalcase",
+        })
+    }
+}
+
+/// Parses the snippet syntax, where anything that isn't valid syntax is
+/// kept as text
+struct SnippetParser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> SnippetParser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.s[self.pos..].chars().nth(n)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_int(&mut self) -> Option<usize> {
+        let digits = self.s[self.pos..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.s.len() - self.pos);
+        let n = self.s[self.pos..self.pos + digits].parse().ok()?;
+        self.pos += digits;
+        Some(n)
+    }
+
+    fn parse_var_name(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+            _ => return None,
+        }
+        let len = self.s[self.pos..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(self.s.len() - self.pos);
+        let name = self.s[self.pos..self.pos + len].to_string();
+        self.pos += len;
+        Some(name)
+    }
+
+    /// Parse elements up to the end of the text, or up to the `}` that
+    /// closes the placeholder they are in
+    fn parse_elements(&mut self, in_placeholder: bool) -> Vec<SnippetElement> {
+        let mut elements = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '}' if in_placeholder => break,
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(c @ ('$' | '}' | '\\')) => {
+                            text.push(c);
+                            self.pos += 1;
+                        }
+                        _ => text.push('\\'),
+                    }
+                }
+                '$' => {
+                    let start = self.pos;
+                    if let Some(element) = self.parse_dollar() {
+                        if !text.is_empty() {
+                            elements.push(SnippetElement::Text(std::mem::take(
+                                &mut text,
+                            )));
+                        }
+                        elements.push(element);
+                    } else {
+                        self.pos = start + 1;
+                        text.push('$');
+                    }
+                }
+                c => {
+                    text.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        if !text.is_empty() {
+            elements.push(SnippetElement::Text(text));
+        }
+        elements
+    }
+
+    fn parse_dollar(&mut self) -> Option<SnippetElement> {
+        if !self.eat('$') {
+            return None;
+        }
+        if let Some(tab) = self.parse_int() {
+            return Some(SnippetElement::Tabstop(tab));
+        }
+        if let Some(name) = self.parse_var_name() {
+            return Some(SnippetElement::Variable(name, Vec::new(), None));
+        }
+        if !self.eat('{') {
+            return None;
+        }
+
+        if let Some(tab) = self.parse_int() {
+            if self.eat('}') {
+                return Some(SnippetElement::Tabstop(tab));
+            }
+            if self.eat(':') {
+                let elements = self.parse_elements(true);
+                return if self.eat('}') {
+                    Some(SnippetElement::PlaceHolder(tab, elements))
+                } else {
+                    None
+                };
+            }
+            if self.eat('|') {
+                let options = self.parse_choice()?;
+                return Some(SnippetElement::Choice(tab, options));
+            }
+            if self.peek() == Some('/') {
+                // A transform of a tab stop changes the text of the other tab
+                // stops with the same number, which aren't kept in sync, so
+                // the tab stop is kept without it
+                self.parse_transform()?;
+                return Some(SnippetElement::Tabstop(tab));
+            }
+            return None;
+        }
+
+        let name = self.parse_var_name()?;
+        if self.eat('}') {
+            return Some(SnippetElement::Variable(name, Vec::new(), None));
+        }
+        if self.eat(':') {
+            let elements = self.parse_elements(true);
+            return if self.eat('}') {
+                Some(SnippetElement::Variable(name, elements, None))
+            } else {
+                None
+            };
+        }
+        if self.peek() == Some('/') {
+            let transform = self.parse_transform()?;
+            return Some(SnippetElement::Variable(
+                name,
+                Vec::new(),
+                Some(transform),
+            ));
+        }
+        None
+    }
+
+    /// Parse the options of a choice after its `|`, up to the closing `|}`
+    fn parse_choice(&mut self) -> Option<Vec<String>> {
+        let mut options = Vec::new();
+        let mut option = String::new();
+        loop {
+            let c = self.peek()?;
+            self.pos += c.len_utf8();
+            match c {
+                '\\' => match self.peek() {
+                    Some(c @ (',' | '|' | '\\')) => {
+                        option.push(c);
+                        self.pos += 1;
+                    }
+                    _ => option.push('\\'),
+                },
+                ',' => options.push(std::mem::take(&mut option)),
+                '|' => {
+                    options.push(option);
+                    return if self.eat('}') { Some(options) } else { None };
+                }
+                c => option.push(c),
+            }
+        }
+    }
+
+    /// Parse `/regex/format/options}` including the closing brace
+    fn parse_transform(&mut self) -> Option<SnippetTransform> {
+        if !self.eat('/') {
+            return None;
+        }
+        let mut regex = String::new();
+        loop {
+            let c = self.peek()?;
+            self.pos += c.len_utf8();
+            match c {
+                '/' => break,
+                '\\' if self.peek() == Some('/') => {
+                    regex.push('/');
+                    self.pos += 1;
+                }
+                '\\' => {
+                    regex.push('\\');
+                    let c = self.peek()?;
+                    regex.push(c);
+                    self.pos += c.len_utf8();
+                }
+                c => regex.push(c),
+            }
+        }
+        let format = self.parse_format()?;
+        let end = self.pos + self.s[self.pos..].find('}')?;
+        let options = self.s[self.pos..end].to_string();
+        self.pos = end + 1;
+        Some(SnippetTransform {
+            regex,
+            format,
+            options,
+        })
+    }
+
+    /// Parse the format of a transform up to and including its closing `/`
+    fn parse_format(&mut self) -> Option<Vec<FormatItem>> {
+        let mut items = Vec::new();
+        let mut text = String::new();
+        loop {
+            let c = self.peek()?;
+            match c {
+                '/' => {
+                    self.pos += 1;
+                    break;
+                }
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(c @ ('$' | '/' | '\\')) => {
+                            text.push(c);
+                            self.pos += 1;
+                        }
+                        _ => text.push('\\'),
+                    }
+                }
+                '$' => {
+                    let start = self.pos;
+                    if let Some(item) = self.parse_format_item() {
+                        if !text.is_empty() {
+                            items.push(FormatItem::Text(std::mem::take(&mut text)));
+                        }
+                        items.push(item);
+                    } else {
+                        self.pos = start + 1;
+                        text.push('$');
+                    }
+                }
+                c => {
+                    text.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        if !text.is_empty() {
+            items.push(FormatItem::Text(text));
+        }
+        Some(items)
+    }
+
+    fn parse_format_item(&mut self) -> Option<FormatItem> {
+        if !self.eat('$') {
+            return None;
+        }
+        if let Some(n) = self.parse_int() {
+            return Some(FormatItem::Group(n));
+        }
+        if !self.eat('{') {
+            return None;
+        }
+        let n = self.parse_int()?;
+        if self.eat('}') {
+            return Some(FormatItem::Group(n));
+        }
+        if !self.eat(':') {
+            return None;
+        }
+        if self.eat('/') {
+            let name = self.parse_var_name()?;
+            let modifier = CaseModifier::from_name(&name)?;
+            return if self.eat('}') {
+                Some(FormatItem::Case(n, modifier))
+            } else {
+                None
+            };
+        }
+        if self.eat('+') {
+            let if_matched = self.parse_format_text(&['}'])?;
+            self.eat('}');
+            return Some(FormatItem::Conditional(n, Some(if_matched), None));
+        }
+        if self.eat('?') {
+            let if_matched = self.parse_format_text(&[':'])?;
+            self.eat(':');
+            let otherwise = self.parse_format_text(&['}'])?;
+            self.eat('}');
+            return Some(FormatItem::Conditional(
+                n,
+                Some(if_matched),
+                Some(otherwise),
+            ));
+        }
+        self.eat('-');
+        let otherwise = self.parse_format_text(&['}'])?;
+        self.eat('}');
+        Some(FormatItem::Conditional(n, None, Some(otherwise)))
+    }
+
+    /// Parse text up to one of the unescaped `ends`, without consuming it
+    fn parse_format_text(&mut self, ends: &[char]) -> Option<String> {
+        let mut text = String::new();
+        loop {
+            let c = self.peek()?;
+            if ends.contains(&c) {
+                return Some(text);
+            }
+            self.pos += c.len_utf8();
+            if c == '\\' {
+                if let Some(next) = self.peek() {
+                    if ends.contains(&next) || next == '\\' {
+                        text.push(next);
+                        self.pos += next.len_utf8();
+                        continue;
+                    }
+                }
+            }
+            text.push(c);
+        }
+    }
+}
+
+/// The values of the variables a snippet can use, taken from the editor it
+/// is inserted into
+#[derive(Debug, Clone, Default)]
+pub struct SnippetVariables {
+    pub selected_text: String,
+    pub current_line: String,
+    pub current_word: String,
+    /// The zero based line the snippet is inserted on
+    pub line: usize,
+    pub path: Option<PathBuf>,
+    pub workspace: Option<PathBuf>,
+    pub clipboard: Option<String>,
+}
+
+impl SnippetVariables {
+    /// The value of the variable, or `None` if there's no such variable
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        let now = chrono::Local::now();
+        let path = self.path.as_ref();
+        let file_name = || {
+            path.and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        Some(match name {
+            "TM_SELECTED_TEXT" => self.selected_text.clone(),
+            "TM_CURRENT_LINE" => self.current_line.clone(),
+            "TM_CURRENT_WORD" => self.current_word.clone(),
+            "TM_LINE_INDEX" => self.line.to_string(),
+            "TM_LINE_NUMBER" => (self.line + 1).to_string(),
+            "TM_FILENAME" => file_name(),
+            "TM_FILENAME_BASE" => path
+                .and_then(|p| p.file_stem())
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            "TM_DIRECTORY" => path
+                .and_then(|p| p.parent())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            "TM_FILEPATH" => path
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            "RELATIVE_FILEPATH" => path
+                .map(|p| {
+                    self.workspace
+                        .as_ref()
+                        .and_then(|w| p.strip_prefix(w).ok())
+                        .unwrap_or(p)
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .unwrap_or_default(),
+            "WORKSPACE_NAME" => self
+                .workspace
+                .as_ref()
+                .and_then(|w| w.file_name())
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            "WORKSPACE_FOLDER" => self
+                .workspace
+                .as_ref()
+                .map(|w| w.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            "CLIPBOARD" => self.clipboard.clone().unwrap_or_default(),
+            "CURRENT_YEAR" => now.format("%Y").to_string(),
+            "CURRENT_YEAR_SHORT" => now.format("%y").to_string(),
+            "CURRENT_MONTH" => now.format("%m").to_string(),
+            "CURRENT_MONTH_NAME" => now.format("%B").to_string(),
+            "CURRENT_MONTH_NAME_SHORT" => now.format("%b").to_string(),
+            "CURRENT_DATE" => now.format("%d").to_string(),
+            "CURRENT_DAY_NAME" => now.format("%A").to_string(),
+            "CURRENT_DAY_NAME_SHORT" => now.format("%a").to_string(),
+            "CURRENT_HOUR" => now.format("%H").to_string(),
+            "CURRENT_MINUTE" => now.format("%M").to_string(),
+            "CURRENT_SECOND" => now.format("%S").to_string(),
+            "CURRENT_SECONDS_UNIX" => now.timestamp().to_string(),
+            "RANDOM" => format!("{:06}", uuid::Uuid::new_v4().as_u128() % 1_000_000),
+            "RANDOM_HEX" => {
+                format!("{:06x}", uuid::Uuid::new_v4().as_u128() & 0xffffff)
+            }
+            "UUID" => uuid::Uuid::new_v4().to_string(),
+            _ => return None,
+        })
+    }
+}
+
+/// A snippet defined by the user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserSnippet {
+    pub name: String,
+    /// The words that the snippet is completed from
+    pub prefix: Vec<String>,
+    pub body: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(s) => vec![s],
+            OneOrMany::Many(v) => v,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UserSnippetFile {
+    prefix: OneOrMany,
+    body: OneOrMany,
+    description: Option<String>,
+}
+
+/// The snippets the user defines in the `snippets` folder of the config
+/// directory, in the JSON format of VS Code. The snippets of a language are
+/// in a file named after it, like `rust.json`, and `global.json` has the
+/// ones for every language.
+#[derive(Debug, Clone, Default)]
+pub struct UserSnippets {
+    snippets: HashMap<String, Vec<UserSnippet>>,
+}
+
+impl UserSnippets {
+    const GLOBAL: &'static str = "global";
+
+    pub fn load() -> Self {
+        let mut snippets = HashMap::new();
+        if let Some(entries) = Directory::snippets_directory()
+            .and_then(|dir| std::fs::read_dir(dir).ok())
+        {
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                    continue;
+                }
+                let language = match path.file_stem().and_then(|s| s.to_str()) {
+                    Some(language) => language.to_lowercase(),
+                    None => continue,
+                };
+                match std::fs::read_to_string(&path)
+                    .map_err(Error::from)
+                    .and_then(|content| Self::parse(&content))
+                {
+                    Ok(parsed) => {
+                        snippets.insert(language, parsed);
+                    }
+                    Err(err) => {
+                        log::error!("failed to load snippets {path:?}: {err}")
+                    }
+                }
+            }
+        }
+        Self { snippets }
+    }
+
+    /// Parse the snippets of a file, keyed by their names
+    pub fn parse(content: &str) -> Result<Vec<UserSnippet>> {
+        let file: HashMap<String, UserSnippetFile> =
+            serde_json::from_str(content)
+                .map_err(|e| anyhow!("invalid snippets: {e}"))?;
+        Ok(file
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(name, snippet)| UserSnippet {
+                name,
+                prefix: snippet.prefix.into_vec(),
+                body: snippet.body.into_vec().join("\n"),
+                description: snippet.description,
+            })
+            .collect())
+    }
+
+    /// The snippets for the language, by its lowercase name, and the global
+    /// ones
+    pub fn for_language<'a>(
+        &'a self,
+        language: Option<&str>,
+    ) -> impl Iterator<Item = &'a UserSnippet> {
+        let language = language.and_then(|language| self.snippets.get(language));
+        language
+            .into_iter()
+            .chain(self.snippets.get(Self::GLOBAL))
+            .flatten()
+    }
+
+    /// A completion item for each prefix of the snippets of the language
+    pub fn completion_items(&self, language: Option<&str>) -> Vec<CompletionItem> {
+        self.for_language(language)
+            .flat_map(|snippet| {
+                snippet.prefix.iter().map(move |prefix| CompletionItem {
+                    label: prefix.clone(),
+                    kind: Some(CompletionItemKind::SNIPPET),
+                    detail: Some(
+                        snippet
+                            .description
+                            .clone()
+                            .unwrap_or_else(|| snippet.name.clone()),
+                    ),
+                    insert_text: Some(snippet.body.clone()),
+                    insert_text_format: Some(InsertTextFormat::SNIPPET),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet() {
+        let s = "start $1${2:second ${3:third}} $0";
+        let parsed = Snippet::from_str(s).unwrap();
+        assert_eq!(s, parsed.to_string());
+
+        let text = "start second third ";
+        assert_eq!(text, parsed.text());
+
+        assert_eq!(
+            vec![(1, (6, 6)), (2, (6, 18)), (3, (13, 18)), (0, (19, 19))],
+            parsed.tabs(0)
+        );
+    }
+
+    #[test]
+    fn test_snippet_tabs_are_ordered_by_number() {
+        let parsed = Snippet::from_str("$0 ${2:b} ${1:a}").unwrap();
+        assert_eq!(vec![(1, (3, 4)), (2, (1, 2)), (0, (0, 0))], parsed.tabs(0));
+    }
+
+    #[test]
+    fn test_snippet_choice() {
+        let s = r"let ${1|mut x,y\,z|} = $0;";
+        let parsed = Snippet::from_str(s).unwrap();
+        assert_eq!(s, parsed.to_string());
+        assert_eq!("let mut x = ;", parsed.text());
+        assert_eq!(vec![(1, (4, 9)), (0, (12, 12))], parsed.tabs(0));
+        assert_eq!(
+            vec![((4, 9), vec!["mut x".to_string(), "y,z".to_string()])],
+            parsed.choices(0)
+        );
+    }
+
+    #[test]
+    fn test_snippet_escapes_and_invalid_syntax() {
+        let parsed = Snippet::from_str(r"a \$1 $ ${x \} b").unwrap();
+        assert_eq!(r"a $1 $ ${x } b", parsed.text());
+        assert!(parsed.tabs(0).is_empty());
+    }
+
+    #[test]
+    fn test_snippet_variables() {
+        let variables = SnippetVariables {
+            current_word: "word".to_string(),
+            line: 4,
+            path: Some(PathBuf::from("/a/b/file_name.rs")),
+            ..Default::default()
+        };
+        let parsed = Snippet::from_str(
+            "$TM_FILENAME ${TM_LINE_NUMBER} ${TM_SELECTED_TEXT:${1:none}} $UNKNOWN",
+        )
+        .unwrap()
+        .resolve_variables(&|name| variables.resolve(name));
+        assert_eq!("file_name.rs 5 none UNKNOWN", parsed.text());
+        assert_eq!(vec![(1, (15, 19))], parsed.tabs(0));
+    }
+
+    #[test]
+    fn test_snippet_transform() {
+        let variables = SnippetVariables {
+            path: Some(PathBuf::from("/a/b/file_name.rs")),
+            ..Default::default()
+        };
+        let resolve = |s: &str| {
+            Snippet::from_str(s)
+                .unwrap()
+                .resolve_variables(&|name| variables.resolve(name))
+                .text()
+        };
+        assert_eq!(
+            "FileName",
+            resolve("${TM_FILENAME_BASE/(.*)/${1:/pascalcase}/}")
+        );
+        assert_eq!("file-name.rs", resolve("${TM_FILENAME/_/-/g}"));
+        assert_eq!(
+            "rust file",
+            resolve(r"${TM_FILENAME/.*\.(rs)?$/${1:+rust}${1:-other} file/}")
+        );
+        assert_eq!(
+            "other file",
+            resolve(r"${TM_FILENAME/.*\.(py)?.*$/${1:?python:other} file/}")
+        );
+    }
+
+    #[test]
+    fn test_user_snippets() {
+        let content = r#"{
+            "For loop": {
+                "prefix": ["for", "fori"],
+                "body": ["for ${1:i} in ${2:0..n} {", "\t$0", "}"],
+                "description": "A for loop"
+            },
+            "Main": {
+                "prefix": "main",
+                "body": "fn main() {\n\t$0\n}"
+            }
+        }"#;
+        let snippets = UserSnippets::parse(content).unwrap();
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].name, "For loop");
+        assert_eq!(snippets[0].prefix, vec!["for", "fori"]);
+        assert_eq!(snippets[0].body, "for ${1:i} in ${2:0..n} {\n\t$0\n}");
+        assert_eq!(snippets[1].description, None);
+
+        let user_snippets = UserSnippets {
+            snippets: HashMap::from([("rust".to_string(), snippets)]),
+        };
+        assert_eq!(user_snippets.completion_items(Some("rust")).len(), 3);
+        assert!(user_snippets.completion_items(Some("go")).is_empty());
+    }
+}
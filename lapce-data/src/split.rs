@@ -1,7 +1,7 @@
 use druid::{Rect, Size};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum SplitMoveDirection {
     Up,
     Down,
@@ -0,0 +1,501 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+use serde::Deserialize;
+
+/// The file in the workspace that has the tasks, as `[[tasks]]` tables, and
+/// the problem matchers they can use besides the built-in ones, as
+/// `[[problem-matchers]]` tables
+pub const TASKS_PATH: &str = ".lapce/tasks.toml";
+
+/// The longest line of the output of a task that is matched, so that a
+/// line that never ends doesn't grow the buffer forever
+const MAX_LINE_LEN: usize = 64 * 1024;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TaskConfigs {
+    #[serde(default)]
+    pub tasks: Vec<TaskConfig>,
+    #[serde(default)]
+    pub problem_matchers: Vec<ProblemMatcherConfig>,
+}
+
+impl TaskConfigs {
+    /// The task with its problem matchers, which are looked up by their
+    /// names in this file first, then in the built-in ones
+    pub fn task(&self, config: &TaskConfig) -> Result<Task, String> {
+        let mut matchers = Vec::new();
+        for name in &config.problem_matchers {
+            match self.problem_matchers.iter().find(|m| &m.name == name) {
+                Some(matcher) => matchers.push(
+                    ProblemMatcher::new(matcher)
+                        .map_err(|e| format!("{}: {e}", config.name))?,
+                ),
+                None => {
+                    let builtin = builtin_problem_matchers(name);
+                    if builtin.is_empty() {
+                        return Err(format!(
+                            "{}: no problem matcher named {name}",
+                            config.name
+                        ));
+                    }
+                    matchers.extend(builtin);
+                }
+            }
+        }
+        Ok(Task {
+            config: config.clone(),
+            matchers,
+        })
+    }
+}
+
+/// A command, like a build or the tests, that is run in a terminal
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TaskConfig {
+    pub name: String,
+    /// The command line, which the shell of the terminal runs
+    pub command: String,
+    /// The directory it's run in, from the workspace
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// The names of the problem matchers that find the problems in the
+    /// output, like `rustc` or `tsc`
+    #[serde(default)]
+    pub problem_matchers: Vec<String>,
+}
+
+/// How the problems are found in the output of a task, with the patterns
+/// of the lines that one problem takes, one after the other
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProblemMatcherConfig {
+    pub name: String,
+    pub patterns: Vec<ProblemPatternConfig>,
+}
+
+/// A regular expression a line matches, with the numbers of its capture
+/// groups that have each part of the problem
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct ProblemPatternConfig {
+    pub regex: String,
+    #[serde(default)]
+    pub file: Option<usize>,
+    /// The line, which starts at 1
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// The column, which starts at 1
+    #[serde(default)]
+    pub column: Option<usize>,
+    /// `error`, `warning`, `info` or `hint`, which is an error when there's
+    /// none
+    #[serde(default)]
+    pub severity: Option<usize>,
+    #[serde(default)]
+    pub code: Option<usize>,
+    #[serde(default)]
+    pub message: Option<usize>,
+}
+
+/// A task with the problem matchers of its names
+#[derive(Clone, Debug, PartialEq)]
+pub struct Task {
+    pub config: TaskConfig,
+    pub matchers: Vec<ProblemMatcher>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProblemMatcher {
+    patterns: Vec<(Regex, ProblemPatternConfig)>,
+}
+
+impl ProblemMatcher {
+    pub fn new(config: &ProblemMatcherConfig) -> Result<ProblemMatcher, String> {
+        if config.patterns.is_empty() {
+            return Err(format!(
+                "the problem matcher {} has no patterns",
+                config.name
+            ));
+        }
+        let patterns = config
+            .patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(&pattern.regex)
+                    .map(|regex| (regex, pattern.clone()))
+                    .map_err(|e| format!("the problem matcher {}: {e}", config.name))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ProblemMatcher { patterns })
+    }
+}
+
+impl PartialEq for ProblemMatcher {
+    fn eq(&self, other: &Self) -> bool {
+        self.patterns.len() == other.patterns.len()
+            && self
+                .patterns
+                .iter()
+                .zip(other.patterns.iter())
+                .all(|((_, a), (_, b))| a == b)
+    }
+}
+
+/// The problem matchers that are built in with the name, which are those of
+/// the compilers that the problems of a lot of tasks come from
+pub fn builtin_problem_matchers(name: &str) -> Vec<ProblemMatcher> {
+    let pattern = |regex: &str, groups: [Option<usize>; 6]| ProblemPatternConfig {
+        regex: regex.to_string(),
+        file: groups[0],
+        line: groups[1],
+        column: groups[2],
+        severity: groups[3],
+        code: groups[4],
+        message: groups[5],
+    };
+    let matchers = match name {
+        // Cargo shows the problems of rustc as they are, like
+        // `error[E0425]: cannot find value` then `  --> src/main.rs:2:5`
+        "rustc" | "cargo" => vec![vec![
+            pattern(
+                r"^(error|warning)(?:\[(\w+)\])?: (.*)$",
+                [None, None, None, Some(1), Some(2), Some(3)],
+            ),
+            pattern(
+                r"^\s*--> (.+):(\d+):(\d+)$",
+                [Some(1), Some(2), Some(3), None, None, None],
+            ),
+        ]],
+        // Like `src/a.ts(3,5): error TS2322: ...`, or like
+        // `src/a.ts:3:5 - error TS2322: ...` when it's pretty
+        "tsc" => vec![
+            vec![pattern(
+                r"^(.+)\((\d+),(\d+)\): (error|warning) TS(\d+): (.*)$",
+                [Some(1), Some(2), Some(3), Some(4), Some(5), Some(6)],
+            )],
+            vec![pattern(
+                r"^(.+):(\d+):(\d+) - (error|warning) TS(\d+): (.*)$",
+                [Some(1), Some(2), Some(3), Some(4), Some(5), Some(6)],
+            )],
+        ],
+        _ => Vec::new(),
+    };
+    matchers
+        .into_iter()
+        .filter_map(|patterns| {
+            ProblemMatcher::new(&ProblemMatcherConfig {
+                name: name.to_string(),
+                patterns,
+            })
+            .ok()
+        })
+        .collect()
+}
+
+/// The parts of a problem that the lines matched so far had
+#[derive(Clone, Debug, Default)]
+struct ProblemParts {
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    severity: Option<String>,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+impl ProblemParts {
+    fn add(&mut self, pattern: &ProblemPatternConfig, line: &str, regex: &Regex) {
+        let captures = match regex.captures(line) {
+            Some(captures) => captures,
+            None => return,
+        };
+        let group = |index: Option<usize>| {
+            index
+                .and_then(|index| captures.get(index))
+                .map(|m| m.as_str().trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let number = |index: Option<usize>| group(index)?.parse::<u32>().ok();
+        self.file = group(pattern.file).or_else(|| self.file.take());
+        self.line = number(pattern.line).or(self.line);
+        self.column = number(pattern.column).or(self.column);
+        self.severity = group(pattern.severity).or_else(|| self.severity.take());
+        self.code = group(pattern.code).or_else(|| self.code.take());
+        self.message = group(pattern.message).or_else(|| self.message.take());
+    }
+
+    fn problem(
+        self,
+        source: &str,
+        cwd: Option<&Path>,
+    ) -> Option<(PathBuf, Diagnostic)> {
+        let file = PathBuf::from(self.file?);
+        let path = match cwd {
+            Some(cwd) if file.is_relative() => cwd.join(file),
+            _ => file,
+        };
+        let position = Position::new(
+            self.line.unwrap_or(1).saturating_sub(1),
+            self.column.unwrap_or(1).saturating_sub(1),
+        );
+        let severity = match self.severity.as_deref().map(|s| s.to_lowercase()) {
+            Some(s) if s.starts_with("warn") => DiagnosticSeverity::WARNING,
+            Some(s) if s.starts_with("info") || s == "note" => {
+                DiagnosticSeverity::INFORMATION
+            }
+            Some(s) if s.starts_with("hint") || s == "help" => {
+                DiagnosticSeverity::HINT
+            }
+            _ => DiagnosticSeverity::ERROR,
+        };
+        let diagnostic = Diagnostic {
+            range: Range::new(position, position),
+            severity: Some(severity),
+            code: self.code.map(NumberOrString::String),
+            source: Some(source.to_string()),
+            message: self.message?,
+            ..Default::default()
+        };
+        Some((path, diagnostic))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EscapeState {
+    None,
+    Escape,
+    /// In a control sequence, like the colors, which ends with a byte from
+    /// `@` to `~`
+    Csi,
+    /// In an operating system command, which ends with BEL or ST
+    Osc,
+}
+
+/// The problems found in the output of a task, as it comes, line by line
+/// with the escape sequences taken out
+pub struct TaskOutput {
+    pub name: String,
+    matchers: Vec<ProblemMatcher>,
+    /// The directory the relative paths of the problems are from
+    cwd: Option<PathBuf>,
+    line: Vec<u8>,
+    escape: EscapeState,
+    /// Whether the line is started again by the next byte, as after a `\r`
+    /// that isn't before a `\n`, like for a progress bar
+    carriage_return: bool,
+    /// The index of the next pattern of each matcher, with the parts of
+    /// the problem of the patterns before it
+    partial: Vec<Option<(usize, ProblemParts)>>,
+    problems: Vec<(PathBuf, Diagnostic)>,
+    changed: bool,
+}
+
+impl TaskOutput {
+    pub fn new(task: &Task, cwd: Option<PathBuf>) -> TaskOutput {
+        TaskOutput {
+            name: task.config.name.clone(),
+            partial: vec![None; task.matchers.len()],
+            matchers: task.matchers.clone(),
+            cwd,
+            line: Vec::new(),
+            escape: EscapeState::None,
+            carriage_return: false,
+            problems: Vec::new(),
+            // The problems of the last run are cleared when this one starts
+            changed: true,
+        }
+    }
+
+    pub fn advance(&mut self, byte: u8) {
+        match self.escape {
+            EscapeState::Escape => {
+                self.escape = match byte {
+                    b'[' => EscapeState::Csi,
+                    b']' => EscapeState::Osc,
+                    _ => EscapeState::None,
+                };
+                return;
+            }
+            EscapeState::Csi => {
+                if (0x40..=0x7e).contains(&byte) {
+                    self.escape = EscapeState::None;
+                }
+                return;
+            }
+            EscapeState::Osc => {
+                match byte {
+                    0x07 => self.escape = EscapeState::None,
+                    0x1b => self.escape = EscapeState::Escape,
+                    _ => {}
+                }
+                return;
+            }
+            EscapeState::None => {}
+        }
+
+        match byte {
+            0x1b => self.escape = EscapeState::Escape,
+            b'\n' => {
+                self.carriage_return = false;
+                let line = String::from_utf8_lossy(&self.line).to_string();
+                self.line.clear();
+                self.match_line(&line);
+            }
+            b'\r' => self.carriage_return = true,
+            _ => {
+                if self.carriage_return {
+                    self.carriage_return = false;
+                    self.line.clear();
+                }
+                if self.line.len() < MAX_LINE_LEN {
+                    self.line.push(byte);
+                }
+            }
+        }
+    }
+
+    fn match_line(&mut self, line: &str) {
+        for (matcher, partial) in self.matchers.iter().zip(self.partial.iter_mut()) {
+            let (index, mut parts) = match partial.take() {
+                Some((index, parts)) if matcher.patterns[index].0.is_match(line) => {
+                    (index, parts)
+                }
+                _ if matcher.patterns[0].0.is_match(line) => {
+                    (0, ProblemParts::default())
+                }
+                _ => continue,
+            };
+            let (regex, pattern) = &matcher.patterns[index];
+            parts.add(pattern, line, regex);
+            if index + 1 < matcher.patterns.len() {
+                *partial = Some((index + 1, parts));
+            } else if let Some(problem) =
+                parts.problem(&self.name, self.cwd.as_deref())
+            {
+                self.problems.push(problem);
+                self.changed = true;
+            }
+        }
+    }
+
+    /// All the problems found so far, if there are new ones since the last
+    /// time
+    pub fn take_changed(&mut self) -> Option<Vec<(PathBuf, Diagnostic)>> {
+        if !self.changed {
+            return None;
+        }
+        self.changed = false;
+        Some(self.problems.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use lsp_types::{DiagnosticSeverity, NumberOrString, Position};
+
+    use super::*;
+
+    fn output(matcher: &str, text: &str) -> Vec<(PathBuf, Diagnostic)> {
+        let task = Task {
+            config: TaskConfig {
+                name: "build".to_string(),
+                command: String::new(),
+                cwd: None,
+                env: HashMap::new(),
+                problem_matchers: vec![matcher.to_string()],
+            },
+            matchers: builtin_problem_matchers(matcher),
+        };
+        let mut output = TaskOutput::new(&task, Some(PathBuf::from("/w")));
+        for byte in text.bytes() {
+            output.advance(byte);
+        }
+        output.take_changed().unwrap()
+    }
+
+    #[test]
+    fn test_rustc_problems() {
+        let problems = output(
+            "cargo",
+            "   Compiling a v0.1.0\r\n\x1b[0m\x1b[1m\x1b[38;5;9merror[E0425]\x1b[0m: cannot find value `x`\r\n \x1b[1m-->\x1b[0m src/main.rs:2:5\r\n  |\r\nwarning: unused import\n  --> src/lib.rs:1:4\nerror: aborting due to previous error\n\n",
+        );
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].0, Path::new("/w/src/main.rs"));
+        assert_eq!(problems[0].1.range.start, Position::new(1, 4));
+        assert_eq!(problems[0].1.severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(
+            problems[0].1.code,
+            Some(NumberOrString::String("E0425".to_string()))
+        );
+        assert_eq!(problems[0].1.message, "cannot find value `x`");
+        assert_eq!(problems[0].1.source.as_deref(), Some("build"));
+        assert_eq!(problems[1].0, Path::new("/w/src/lib.rs"));
+        assert_eq!(problems[1].1.severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    #[test]
+    fn test_tsc_problems() {
+        let problems = output(
+            "tsc",
+            "src/a.ts(3,5): error TS2322: Type 'string' is not assignable.\n/b.ts:1:2 - warning TS6133: 'x' is declared.\nBuilding 10%\rFound 2 errors.\n",
+        );
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].0, Path::new("/w/src/a.ts"));
+        assert_eq!(problems[0].1.range.start, Position::new(2, 4));
+        assert_eq!(
+            problems[0].1.code,
+            Some(NumberOrString::String("2322".to_string()))
+        );
+        assert_eq!(problems[1].0, Path::new("/b.ts"));
+        assert_eq!(problems[1].1.severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    #[test]
+    fn test_task_problem_matchers() {
+        let configs: TaskConfigs = toml_edit::easy::from_str(
+            r#"
+[[tasks]]
+name = "lint"
+command = "eslint ."
+problem-matchers = ["eslint", "tsc"]
+
+[[tasks]]
+name = "test"
+command = "make test"
+problem-matchers = ["make"]
+
+[[problem-matchers]]
+name = "eslint"
+[[problem-matchers.patterns]]
+regex = '^(.+): line (\d+), col (\d+), (\w+) - (.*)$'
+file = 1
+line = 2
+column = 3
+severity = 4
+message = 5
+"#,
+        )
+        .unwrap();
+        let task = configs.task(&configs.tasks[0]).unwrap();
+        assert_eq!(task.matchers.len(), 3);
+        assert!(configs.task(&configs.tasks[1]).is_err());
+
+        let mut output = TaskOutput::new(&task, None);
+        for byte in "a.js: line 1, col 2, Warning - no semi\n".bytes() {
+            output.advance(byte);
+        }
+        let problems = output.take_changed().unwrap();
+        assert_eq!(problems[0].0, Path::new("a.js"));
+        assert_eq!(problems[0].1.severity, Some(DiagnosticSeverity::WARNING));
+        assert!(output.take_changed().is_none());
+    }
+}
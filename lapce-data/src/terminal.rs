@@ -1,12 +1,12 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use alacritty_terminal::{
     ansi,
     event::EventListener,
     grid::{Dimensions, Scroll},
-    index::{Direction, Side},
+    index::{Column, Direction, Line, Point, Side},
     selection::{Selection, SelectionType},
-    term::{search::RegexSearch, SizeInfo, TermMode},
+    term::{cell::Flags, search::RegexSearch, SizeInfo, TermMode},
     vi_mode::ViMotion,
     Term,
 };
@@ -23,6 +23,7 @@
 };
 use lapce_rpc::terminal::TermId;
 use parking_lot::Mutex;
+use uuid::Uuid;
 
 use crate::{
     command::{
@@ -30,23 +31,139 @@
     },
     config::{Config, LapceTheme},
     data::LapceWorkspace,
+    db::TerminalInfo,
     document::SystemClipboard,
-    find::Find,
     keypress::KeyPressFocus,
     proxy::LapceProxy,
+    shell_integration::TerminalCommands,
     split::SplitMoveDirection,
+    task::{Task, TaskOutput},
+    terminal_link::{terminal_links, TerminalLink},
 };
 
 pub type TermConfig = alacritty_terminal::config::Config;
 
+/// The most lines of scrollback a terminal keeps, whatever the setting is,
+/// so that the memory of a terminal stays bounded
+const MAX_SCROLLBACK_LINES: usize = 100_000;
+
+/// Something that happened in a terminal while it wasn't focused, which is
+/// shown until it is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TerminalAlert {
+    Bell,
+    CommandFinished {
+        command: Option<String>,
+        duration: Duration,
+        exit_code: Option<i32>,
+    },
+}
+
+impl TerminalAlert {
+    pub fn label(&self) -> String {
+        match self {
+            TerminalAlert::Bell => "Bell".to_string(),
+            TerminalAlert::CommandFinished {
+                command,
+                duration,
+                exit_code,
+            } => {
+                let command = command
+                    .as_deref()
+                    .map(|command| command.trim())
+                    .filter(|command| !command.is_empty())
+                    .unwrap_or("Command");
+                let status = match exit_code {
+                    Some(0) | None => "finished".to_string(),
+                    Some(code) => format!("failed with {code}"),
+                };
+                format!("{command} {status} after {}s", duration.as_secs())
+            }
+        }
+    }
+}
+
+/// The colors of the terminal theme that a terminal can be marked with
+pub const TERMINAL_COLORS: &[&str] =
+    &["red", "green", "yellow", "blue", "magenta", "cyan"];
+
+/// The icons that a terminal can be given, besides the ones its profile
+/// names
+pub const TERMINAL_ICONS: &[&str] = &[
+    "terminal",
+    "debug-console",
+    "remote",
+    "git-icon",
+    "search",
+    "settings",
+    "history",
+    "lock",
+    "lightbulb",
+    "warning",
+    "file_type_rust",
+    "file_type_python",
+    "file_type_js",
+    "file_type_go",
+];
+
+/// What a new terminal is started with
+#[derive(Clone, Debug)]
+pub enum TerminalLaunch {
+    /// The profile of the name, or the default profile
+    Profile(Option<String>),
+    /// The default profile, whose shell runs the task
+    Task(Task),
+    /// The default profile, whose shell is given the text as if it was
+    /// typed
+    Input(String),
+    /// A terminal of the workspace when it was last open, which is started
+    /// in the same directory, or attached to its session again
+    Restore(TerminalInfo),
+}
+
+/// The find bar of the terminal panel, which searches the screen and the
+/// scrollback of the active terminal
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct TerminalFind {
+    pub visible: bool,
+    pub search_string: String,
+    /// Whether the search string is a regular expression, rather than the
+    /// text to find
+    pub regex: bool,
+}
+
+impl TerminalFind {
+    /// The regular expression the terminal is searched with, if there's
+    /// anything to find
+    pub fn pattern(&self) -> Option<String> {
+        if self.search_string.is_empty() {
+            return None;
+        }
+        if self.regex {
+            Some(self.search_string.clone())
+        } else {
+            Some(regex::escape(&self.search_string))
+        }
+    }
+
+    pub fn dfas(&self) -> Option<RegexSearch> {
+        RegexSearch::new(&self.pattern()?).ok()
+    }
+}
+
 #[derive(Clone)]
 pub struct TerminalSplitData {
     pub active: WidgetId,
     pub active_term_id: TermId,
     pub widget_id: WidgetId,
     pub split_id: WidgetId,
+    pub find_view_id: WidgetId,
+    pub find: TerminalFind,
     pub terminals: im::HashMap<TermId, Arc<LapceTerminalData>>,
     pub indexed_colors: Arc<HashMap<u8, Color>>,
+    /// The terminals the workspace had when it was last open, which the
+    /// terminal panel starts instead of a new terminal
+    pub restore: Vec<TerminalInfo>,
 }
 
 impl TerminalSplitData {
@@ -59,8 +176,19 @@ pub fn new(_proxy: Arc<LapceProxy>) -> Self {
             active: WidgetId::next(),
             widget_id: WidgetId::next(),
             split_id,
+            find_view_id: WidgetId::next(),
+            find: TerminalFind::default(),
             terminals,
             indexed_colors: Arc::new(Self::get_indexed_colors()),
+            restore: Vec::new(),
+        }
+    }
+
+    /// Go to the next match of the find bar in the active terminal
+    pub fn find_next(&self, direction: Direction) {
+        if let Some(terminal) = self.terminals.get(&self.active_term_id) {
+            let mut raw = terminal.raw.lock();
+            terminal.search_next(&mut raw.term, &self.find, direction);
         }
     }
 
@@ -186,7 +314,7 @@ fn get_named_color(&self, color: &ansi::NamedColor, config: &Config) -> Color {
 pub struct LapceTerminalViewData {
     pub terminal: Arc<LapceTerminalData>,
     pub config: Arc<Config>,
-    pub find: Arc<Find>,
+    pub find: TerminalFind,
 }
 
 impl LapceTerminalViewData {
@@ -404,7 +532,22 @@ fn run_command(
                 FocusCommand::SplitVertical => {
                     ctx.submit_command(Command::new(
                         LAPCE_UI_COMMAND,
-                        LapceUICommand::SplitTerminal(true, self.terminal.widget_id),
+                        LapceUICommand::SplitTerminal(
+                            true,
+                            self.terminal.widget_id,
+                            TerminalLaunch::Profile(None),
+                        ),
+                        Target::Widget(self.terminal.split_id),
+                    ));
+                }
+                FocusCommand::SplitHorizontal => {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::SplitTerminal(
+                            false,
+                            self.terminal.widget_id,
+                            TerminalLaunch::Profile(None),
+                        ),
                         Target::Widget(self.terminal.split_id),
                     ));
                 }
@@ -428,6 +571,26 @@ fn run_command(
                         Target::Widget(self.terminal.split_id),
                     ));
                 }
+                FocusCommand::SplitUp => {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::SplitEditorMove(
+                            SplitMoveDirection::Up,
+                            self.terminal.widget_id,
+                        ),
+                        Target::Widget(self.terminal.split_id),
+                    ));
+                }
+                FocusCommand::SplitDown => {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::SplitEditorMove(
+                            SplitMoveDirection::Down,
+                            self.terminal.widget_id,
+                        ),
+                        Target::Widget(self.terminal.split_id),
+                    ));
+                }
                 FocusCommand::SplitExchange => {
                     ctx.submit_command(Command::new(
                         LAPCE_UI_COMMAND,
@@ -435,27 +598,23 @@ fn run_command(
                         Target::Widget(self.terminal.split_id),
                     ));
                 }
+                FocusCommand::Search => {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::OpenTerminalFind,
+                        Target::Auto,
+                    ));
+                }
                 FocusCommand::SearchForward => {
-                    if let Some(search_string) = self.find.search_string.as_ref() {
-                        let mut raw = self.terminal.raw.lock();
-                        let term = &mut raw.term;
-                        self.terminal.search_next(
-                            term,
-                            search_string,
-                            Direction::Right,
-                        );
-                    }
+                    let mut raw = self.terminal.raw.lock();
+                    let term = &mut raw.term;
+                    self.terminal
+                        .search_next(term, &self.find, Direction::Right);
                 }
                 FocusCommand::SearchBackward => {
-                    if let Some(search_string) = self.find.search_string.as_ref() {
-                        let mut raw = self.terminal.raw.lock();
-                        let term = &mut raw.term;
-                        self.terminal.search_next(
-                            term,
-                            search_string,
-                            Direction::Left,
-                        );
-                    }
+                    let mut raw = self.terminal.raw.lock();
+                    let term = &mut raw.term;
+                    self.terminal.search_next(term, &self.find, Direction::Left);
                 }
                 _ => return CommandExecuted::No,
             },
@@ -479,6 +638,9 @@ pub struct RawTerminal {
     pub parser: ansi::Processor,
     pub term: Term<EventProxy>,
     pub scroll_delta: f64,
+    pub commands: TerminalCommands,
+    /// The problems found in the output, when the terminal runs a task
+    pub task: Option<TaskOutput>,
 }
 
 impl RawTerminal {
@@ -486,8 +648,113 @@ pub fn update_content(&mut self, content: &str) {
         if let Ok(content) = base64::decode(content) {
             for byte in content {
                 self.parser.advance(&mut self.term, byte);
+                let line = self.cursor_line();
+                self.commands.advance(byte, line);
+                if let Some(task) = self.task.as_mut() {
+                    task.advance(byte);
+                }
+            }
+        }
+    }
+
+    /// Keep as many lines above the screen, dropping the oldest ones when
+    /// there are more
+    pub fn set_scrollback_lines(&mut self, lines: usize) {
+        self.term.update_config(&term_config(lines));
+    }
+
+    /// Clear the scrollback and the screen, except for the line of the
+    /// cursor, which is usually the prompt, and which moves to the top
+    pub fn clear(&mut self) {
+        let cursor = self.term.grid().cursor.point;
+        let removed = self.cursor_line();
+        // Scroll the line of the cursor to the top, clear the scrollback
+        // and move the cursor up along with its line
+        let mut sequence = String::new();
+        if cursor.line.0 > 0 {
+            sequence.push_str(&format!("\x1b[{}S", cursor.line.0));
+        }
+        sequence.push_str(&format!("\x1b[3J\x1b[1;{}H", cursor.column.0 + 1));
+        for byte in sequence.bytes() {
+            self.parser.advance(&mut self.term, byte);
+        }
+        self.commands.remove_lines(removed);
+        self.term.scroll_display(Scroll::Bottom);
+    }
+
+    /// The line of the cursor, counted from the top of the scrollback
+    fn cursor_line(&self) -> usize {
+        let grid = self.term.grid();
+        (grid.history_size() as i32 + grid.cursor.point.line.0).max(0) as usize
+    }
+
+    /// The line of the screen that the line counted from the top of the
+    /// scrollback is shown at, which is negative above the screen
+    pub fn screen_line(&self, line: usize) -> i32 {
+        let grid = self.term.grid();
+        line as i32 - grid.history_size() as i32 + grid.display_offset() as i32
+    }
+
+    /// Scroll to the prompt of the command before the top of the screen, or
+    /// of the one after it
+    pub fn scroll_to_command(&mut self, previous: bool) {
+        let grid = self.term.grid();
+        let top = grid.history_size() - grid.display_offset();
+        match self.commands.command_near(top, previous) {
+            Some(command) => {
+                let offset = self.term.grid().history_size() as i32
+                    - command.line as i32
+                    - self.term.grid().display_offset() as i32;
+                self.term.scroll_display(Scroll::Delta(offset));
+            }
+            None if !previous => {
+                self.term.scroll_display(Scroll::Bottom);
+            }
+            None => {}
+        }
+    }
+
+    /// The link of the output at the point, with its first and last points,
+    /// which can go over the lines that are wrapped together
+    pub fn link_at(&self, point: Point) -> Option<(Point, Point, TerminalLink)> {
+        let grid = self.term.grid();
+        let last_column = self.term.last_column();
+        let wraps = |line: i32| {
+            grid[Line(line)][last_column]
+                .flags
+                .contains(Flags::WRAPLINE)
+        };
+        let mut start = point.line.0;
+        while start > self.term.topmost_line().0 && wraps(start - 1) {
+            start -= 1;
+        }
+        let mut end = point.line.0;
+        while end < self.term.bottommost_line().0 && wraps(end) {
+            end += 1;
+        }
+
+        // The point of each byte of the text
+        let mut text = String::new();
+        let mut points = Vec::new();
+        for line in start..=end {
+            for column in 0..self.term.columns() {
+                let cell_point = Point::new(Line(line), Column(column));
+                let cell = &grid[cell_point];
+                if cell.flags.intersects(
+                    Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER,
+                ) {
+                    continue;
+                }
+                text.push(cell.c);
+                points.extend(std::iter::repeat(cell_point).take(cell.c.len_utf8()));
             }
         }
+
+        let index = points.iter().rposition(|p| *p <= point)?;
+        terminal_links(&text)
+            .into_iter()
+            .find(|(range, _)| range.contains(&index))
+            .map(|(range, link)| (points[range.start], points[range.end - 1], link))
     }
 }
 
@@ -496,8 +763,9 @@ pub fn new(
         term_id: TermId,
         proxy: Arc<LapceProxy>,
         event_sink: ExtEventSink,
+        scrollback_lines: usize,
     ) -> Self {
-        let config = TermConfig::default();
+        let config = term_config(scrollback_lines);
         let size = SizeInfo::new(50.0, 30.0, 1.0, 1.0, 0.0, 0.0, true);
         let event_proxy = EventProxy {
             proxy,
@@ -512,6 +780,8 @@ pub fn new(
             parser,
             term,
             scroll_delta: 0.0,
+            commands: TerminalCommands::default(),
+            task: None,
         }
     }
 }
@@ -523,6 +793,24 @@ pub struct LapceTerminalData {
     pub widget_id: WidgetId,
     pub split_id: WidgetId,
     pub title: String,
+    /// The name the terminal was given, which replaces its title
+    pub name: Option<String>,
+    /// The process in the foreground of the terminal
+    pub process: Option<String>,
+    /// The directory the terminal was started in
+    pub cwd: Option<PathBuf>,
+    /// The icon of the header, from the profile the terminal was started
+    /// with unless it was set
+    pub icon: Option<String>,
+    /// The color of the terminal theme that marks the header, like `red`
+    pub color: Option<String>,
+    /// What happened in the terminal since it was last focused
+    pub alert: Option<TerminalAlert>,
+    /// The name of the profile the terminal was started with, or `None`
+    /// for the default profile
+    pub profile: Option<String>,
+    /// The tmux session the shell runs in, which outlives the terminal
+    pub session: Option<String>,
     pub mode: Mode,
     pub visual_mode: VisualMode,
     pub raw: Arc<Mutex<RawTerminal>>,
@@ -530,14 +818,70 @@ pub struct LapceTerminalData {
 }
 
 impl LapceTerminalData {
+    /// Start a terminal with a profile, or with the default profile to run
+    /// a task
     pub fn new(
         workspace: Arc<LapceWorkspace>,
         split_id: WidgetId,
         event_sink: ExtEventSink,
         proxy: Arc<LapceProxy>,
         config: &Config,
+        launch: &TerminalLaunch,
     ) -> Self {
-        let cwd = workspace.path.as_ref().cloned();
+        let (name, mut profile) = match launch {
+            TerminalLaunch::Profile(profile) => {
+                config.terminal_profile(profile.as_deref())
+            }
+            TerminalLaunch::Input(_) => config.terminal_profile(None),
+            TerminalLaunch::Restore(info) => {
+                let (name, mut profile) =
+                    config.terminal_profile(info.profile.as_deref());
+                if info.cwd.is_some() {
+                    profile.workdir = info.cwd.clone();
+                }
+                (name, profile)
+            }
+            TerminalLaunch::Task(task) => {
+                let (_, mut profile) = config.terminal_profile(None);
+                if task.config.cwd.is_some() {
+                    profile.workdir = task.config.cwd.clone();
+                }
+                profile.env.extend(task.config.env.clone());
+                (Some(task.config.name.clone()), profile)
+            }
+        };
+        profile.workdir = match (workspace.path.as_ref(), profile.workdir) {
+            (Some(path), Some(workdir)) => Some(path.join(workdir)),
+            (path, workdir) => workdir.or_else(|| path.cloned()),
+        };
+        let mut icon = profile.icon.clone();
+        let mut color = profile.color.clone();
+        let mut terminal_name = None;
+        if let TerminalLaunch::Restore(info) = launch {
+            terminal_name = info.name.clone();
+            icon = info.icon.clone().or(icon);
+            color = info.color.clone().or(color);
+        }
+        let cwd = profile.workdir.clone();
+        let profile_name = match launch {
+            TerminalLaunch::Profile(profile) => profile.clone(),
+            TerminalLaunch::Restore(info) => info.profile.clone(),
+            TerminalLaunch::Task(_) | TerminalLaunch::Input(_) => None,
+        };
+        // The output of a task is matched line by line, which tmux redraws
+        // rather than passes along, so a task never runs in a session
+        let session = match launch {
+            TerminalLaunch::Restore(info) => info.session.clone(),
+            TerminalLaunch::Task(_) => None,
+            TerminalLaunch::Profile(_) | TerminalLaunch::Input(_) => {
+                if workspace.kind.is_remote() && config.terminal.persistent_sessions
+                {
+                    Some(format!("lapce-{}", Uuid::new_v4()))
+                } else {
+                    None
+                }
+            }
+        };
         let widget_id = WidgetId::next();
         let view_id = WidgetId::next();
         let term_id = TermId::next();
@@ -545,13 +889,33 @@ pub fn new(
             term_id,
             proxy.clone(),
             event_sink,
+            config.terminal.scrollback_lines,
         )));
+        let input = match launch {
+            TerminalLaunch::Task(task) => {
+                raw.lock().task = Some(TaskOutput::new(task, cwd.clone()));
+                Some(format!("{}\r", task.config.command))
+            }
+            TerminalLaunch::Input(input) => Some(input.clone()),
+            TerminalLaunch::Profile(_) | TerminalLaunch::Restore(_) => None,
+        };
 
         let local_proxy = proxy.clone();
         let local_raw = raw.clone();
-        let shell = config.terminal.shell.clone();
+        let shell_integration = config.terminal.shell_integration;
+        let local_session = session.clone();
         std::thread::spawn(move || {
-            local_proxy.new_terminal(term_id, cwd, shell, local_raw);
+            local_proxy.new_terminal(
+                term_id,
+                profile,
+                shell_integration,
+                local_session,
+                local_raw,
+            );
+            // The shell reads the input once it has started
+            if let Some(input) = input {
+                local_proxy.proxy_rpc.terminal_write(term_id, &input);
+            }
         });
 
         Self {
@@ -559,7 +923,15 @@ pub fn new(
             widget_id,
             view_id,
             split_id,
-            title: "".to_string(),
+            title: name.unwrap_or_default(),
+            name: terminal_name,
+            process: None,
+            cwd,
+            icon,
+            color,
+            alert: None,
+            profile: profile_name,
+            session,
             mode: Mode::Terminal,
             visual_mode: VisualMode::Normal,
             raw,
@@ -580,6 +952,58 @@ pub fn resize(&self, width: usize, height: usize) {
         });
     }
 
+    /// The working directory of the shell, as the shell integration told
+    /// it, or else the one it was started in
+    pub fn cwd(&self) -> Option<PathBuf> {
+        self.raw
+            .lock()
+            .commands
+            .cwd()
+            .map(|cwd| cwd.to_path_buf())
+            .or_else(|| self.cwd.clone())
+    }
+
+    /// What the workspace saves of the terminal to start it again
+    pub fn info(&self) -> TerminalInfo {
+        TerminalInfo {
+            profile: self.profile.clone(),
+            cwd: self.cwd(),
+            session: self.session.clone(),
+            name: self.name.clone(),
+            color: self.color.clone(),
+            icon: self.icon.clone(),
+        }
+    }
+
+    /// The title of the header, which is the name the terminal was given,
+    /// or else its title along with the process in its foreground
+    pub fn display_title(&self) -> String {
+        match (&self.name, &self.process) {
+            (Some(name), _) => name.clone(),
+            (None, Some(process)) if self.title.is_empty() => process.clone(),
+            (None, Some(process)) if self.title != *process => {
+                format!("{} - {process}", self.title)
+            }
+            (None, _) => self.title.clone(),
+        }
+    }
+
+    /// Run the latest command again, as the shell integration told it
+    pub fn rerun_last_command(&self) {
+        let command = self
+            .raw
+            .lock()
+            .commands
+            .last_command_line()
+            .map(|command| command.to_string());
+        if let Some(command) = command {
+            self.proxy
+                .proxy_rpc
+                .terminal_write(self.term_id, &format!("{command}\r"));
+            self.raw.lock().term.scroll_display(Scroll::Bottom);
+        }
+    }
+
     pub fn wheel_scroll(&self, delta: f64) {
         let mut raw = self.raw.lock();
         let step = 25.0;
@@ -592,14 +1016,25 @@ pub fn wheel_scroll(&self, delta: f64) {
         }
     }
 
+    /// Go to the next match of the find bar, from the vi cursor in vi mode,
+    /// and otherwise from the selected match, which the match then replaces
     pub fn search_next(
         &self,
         term: &mut Term<EventProxy>,
-        search_string: &str,
+        find: &TerminalFind,
         direction: Direction,
     ) {
-        if let Ok(dfas) = RegexSearch::new(&regex::escape(search_string)) {
-            let mut point = term.renderable_content().cursor.point;
+        if let Some(dfas) = find.dfas() {
+            let vi_mode = term.mode().contains(TermMode::VI);
+            let selection = term
+                .selection
+                .as_ref()
+                .filter(|_| !vi_mode)
+                .and_then(|selection| selection.to_range(term));
+            let mut point = match selection {
+                Some(range) => range.start,
+                None => term.renderable_content().cursor.point,
+            };
             if direction == Direction::Right {
                 if point.column.0 < term.last_column() {
                     point.column.0 += 1;
@@ -616,7 +1051,18 @@ pub fn search_next(
             if let Some(m) =
                 term.search_next(&dfas, point, direction, Side::Left, None)
             {
-                term.vi_goto_point(*m.start());
+                if vi_mode {
+                    term.vi_goto_point(*m.start());
+                } else {
+                    term.scroll_to_point(*m.start());
+                    let mut selection = Selection::new(
+                        SelectionType::Simple,
+                        *m.start(),
+                        Side::Left,
+                    );
+                    selection.update(*m.end(), Side::Right);
+                    term.selection = Some(selection);
+                }
             }
         }
     }
@@ -818,6 +1264,15 @@ macro_rules! term_sequence {
     }
 }
 
+/// The configuration of a terminal that keeps the lines of scrollback
+fn term_config(scrollback_lines: usize) -> TermConfig {
+    let mut config = TermConfig::default();
+    config
+        .scrolling
+        .set_history(scrollback_lines.min(MAX_SCROLLBACK_LINES) as u32);
+    config
+}
+
 #[derive(Clone)]
 pub struct EventProxy {
     pub term_id: TermId,
@@ -840,6 +1295,13 @@ fn send_event(&self, event: alacritty_terminal::event::Event) {
                     Target::Widget(self.proxy.tab_id),
                 );
             }
+            alacritty_terminal::event::Event::Bell => {
+                let _ = self.event_sink.submit_command(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::TerminalAlert(self.term_id, TerminalAlert::Bell),
+                    Target::Widget(self.proxy.tab_id),
+                );
+            }
             _ => (),
         }
     }
@@ -849,7 +1311,21 @@ fn send_event(&self, event: alacritty_terminal::event::Event) {
 mod test {
     use druid::{KbKey, KeyEvent, Modifiers};
 
-    use crate::terminal::LapceTerminalData;
+    use std::time::Duration;
+
+    use crate::terminal::{LapceTerminalData, TerminalAlert, TerminalFind};
+
+    #[test]
+    fn test_find_pattern() {
+        let mut find = TerminalFind::default();
+        assert_eq!(find.pattern(), None);
+
+        find.search_string = "a.b(c)".to_string();
+        assert_eq!(find.pattern().as_deref(), Some(r"a\.b\(c\)"));
+
+        find.regex = true;
+        assert_eq!(find.pattern().as_deref(), Some("a.b(c)"));
+    }
 
     #[test]
     fn test_arrow_without_modifier() {
@@ -883,4 +1359,21 @@ fn test_arrow_with_two_modifiers() {
             ))
         );
     }
+
+    #[test]
+    fn test_alert_label() {
+        assert_eq!(TerminalAlert::Bell.label(), "Bell");
+        let alert = TerminalAlert::CommandFinished {
+            command: Some("cargo build ".to_string()),
+            duration: Duration::from_millis(61_500),
+            exit_code: Some(101),
+        };
+        assert_eq!(alert.label(), "cargo build failed with 101 after 61s");
+        let alert = TerminalAlert::CommandFinished {
+            command: None,
+            duration: Duration::from_secs(12),
+            exit_code: Some(0),
+        };
+        assert_eq!(alert.label(), "Command finished after 12s");
+    }
 }
@@ -0,0 +1,186 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::document::web_addresses;
+
+/// What a link in the output of a terminal opens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalLink {
+    Url(String),
+    /// A file reference like `src/main.rs:12:5`, whose line and column
+    /// start at 1
+    File {
+        path: PathBuf,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+}
+
+impl TerminalLink {
+    /// The link with its path made absolute, from the working directory of
+    /// the terminal, or the home directory for a path starting with `~`
+    pub fn resolve(self, cwd: Option<&Path>, home: &Path) -> TerminalLink {
+        match self {
+            TerminalLink::File { path, line, column } => {
+                let path = match (path.strip_prefix("~"), cwd) {
+                    (Ok(rest), _) => home.join(rest),
+                    (Err(_), Some(cwd)) => cwd.join(path),
+                    (Err(_), None) => path,
+                };
+                TerminalLink::File { path, line, column }
+            }
+            link => link,
+        }
+    }
+}
+
+/// The links in a line of the output of a terminal, with their byte ranges,
+/// which are web addresses and references to files
+pub fn terminal_links(text: &str) -> Vec<(Range<usize>, TerminalLink)> {
+    let mut links: Vec<(Range<usize>, TerminalLink)> = web_addresses(text)
+        .into_iter()
+        .map(|(start, end)| {
+            (start..end, TerminalLink::Url(text[start..end].to_string()))
+        })
+        .collect();
+
+    for word in text.split(is_separator) {
+        let start = word.as_ptr() as usize - text.as_ptr() as usize;
+        let range = start..start + word.len();
+        if links
+            .iter()
+            .any(|(link, _)| link.start < range.end && range.start < link.end)
+        {
+            continue;
+        }
+
+        let word = word
+            .trim_end_matches(|c| matches!(c, '.' | ',' | ':' | ';' | '!' | '?'));
+        if let Some(link) = file_reference(word) {
+            links.push((range.start..range.start + word.len(), link));
+        }
+    }
+    links.sort_by_key(|(range, _)| range.start);
+    links
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace()
+        || matches!(
+            c,
+            '"' | '\''
+                | '`'
+                | '<'
+                | '>'
+                | '('
+                | ')'
+                | '['
+                | ']'
+                | '{'
+                | '}'
+                | ','
+                | '|'
+        )
+}
+
+/// A reference to a file, which has a line after a `:` or a path with a
+/// separator and an extension, so that plain words aren't taken for one
+fn file_reference(word: &str) -> Option<TerminalLink> {
+    let mut path = word;
+    let mut numbers = Vec::new();
+    while numbers.len() < 2 {
+        match path.rsplit_once(':') {
+            Some((rest, number))
+                if !number.is_empty()
+                    && number.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                numbers.push(number.parse::<usize>().ok()?);
+                path = rest;
+            }
+            _ => break,
+        }
+    }
+    numbers.reverse();
+    let line = numbers.first().copied();
+    let column = numbers.get(1).copied();
+
+    if !path.chars().any(|c| c.is_alphabetic()) {
+        return None;
+    }
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let has_extension = name
+        .rsplit_once('.')
+        .map(|(stem, extension)| !stem.is_empty() && !extension.is_empty())
+        .unwrap_or(false);
+    let has_separator = path.contains(['/', '\\']);
+    if !(has_extension && (has_separator || line.is_some())) {
+        return None;
+    }
+
+    Some(TerminalLink::File {
+        path: PathBuf::from(path),
+        line,
+        column,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    fn file(path: &str, line: Option<usize>, column: Option<usize>) -> TerminalLink {
+        TerminalLink::File {
+            path: PathBuf::from(path),
+            line,
+            column,
+        }
+    }
+
+    #[test]
+    fn test_terminal_links() {
+        assert_eq!(
+            terminal_links("  --> src/main.rs:12:5"),
+            vec![(6..22, file("src/main.rs", Some(12), Some(5)))]
+        );
+        assert_eq!(
+            terminal_links("main.c:3: error, see https://a.b/c."),
+            vec![
+                (0..8, file("main.c", Some(3), None)),
+                (21..34, TerminalLink::Url("https://a.b/c".to_string())),
+            ]
+        );
+        assert_eq!(
+            terminal_links("at (/home/a/b.js:1:2) in ./lib/x.py"),
+            vec![
+                (4..20, file("/home/a/b.js", Some(1), Some(2))),
+                (25..35, file("./lib/x.py", None, None)),
+            ]
+        );
+        assert!(
+            terminal_links("Finished in 12:30, ran 3.5 tests on host:8080")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_resolve_link() {
+        let cwd = Some(Path::new("/w"));
+        let home = Path::new("/h");
+        assert_eq!(
+            file("src/a.rs", Some(1), None).resolve(cwd, home),
+            file("/w/src/a.rs", Some(1), None)
+        );
+        assert_eq!(
+            file("/src/a.rs", None, None).resolve(cwd, home),
+            file("/src/a.rs", None, None)
+        );
+        assert_eq!(
+            file("~/a.rs", None, None).resolve(cwd, home),
+            file("/h/a.rs", None, None)
+        );
+    }
+}
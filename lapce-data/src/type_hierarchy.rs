@@ -0,0 +1,107 @@
+use druid::{ExtEventSink, Target, WidgetId};
+use lapce_rpc::{
+    plugin::PluginId, proxy::ProxyResponse, type_hierarchy::TypeHierarchyItem,
+    RpcError,
+};
+
+use crate::{
+    call_hierarchy::{CallHierarchyNode, HierarchyTree},
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    proxy::LapceProxy,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeHierarchyDirection {
+    /// The types the item extends or implements
+    Supertypes,
+    /// The types extending or implementing the item
+    Subtypes,
+}
+
+#[derive(Clone)]
+pub struct TypeHierarchyData {
+    pub widget_id: WidgetId,
+    pub split_id: WidgetId,
+    pub tree_widget_id: WidgetId,
+    pub direction: TypeHierarchyDirection,
+    /// The language server that prepared the root, which is asked for the
+    /// supertypes and subtypes
+    pub plugin_id: PluginId,
+    pub tree: HierarchyTree,
+}
+
+impl TypeHierarchyData {
+    pub fn new() -> Self {
+        Self {
+            widget_id: WidgetId::next(),
+            split_id: WidgetId::next(),
+            tree_widget_id: WidgetId::next(),
+            direction: TypeHierarchyDirection::Subtypes,
+            plugin_id: PluginId(0),
+            tree: HierarchyTree::default(),
+        }
+    }
+
+    pub fn set_root(&mut self, plugin_id: PluginId, item: TypeHierarchyItem) {
+        self.plugin_id = plugin_id;
+        self.tree.set_root(item);
+    }
+
+    /// Switch between the supertypes and subtypes, which are asked for again
+    /// from the root
+    pub fn set_direction(&mut self, direction: TypeHierarchyDirection) {
+        if self.direction == direction {
+            return;
+        }
+        self.direction = direction;
+        self.tree.reset();
+    }
+
+    /// Ask the language server for the supertypes or subtypes of the item at
+    /// the node, in the current direction
+    pub fn request_types(
+        &self,
+        proxy: &LapceProxy,
+        indices: Vec<usize>,
+        item: TypeHierarchyItem,
+        event_sink: ExtEventSink,
+        tab_id: WidgetId,
+    ) {
+        let generation = self.tree.generation;
+        let f = move |result: Result<ProxyResponse, RpcError>| {
+            let types = match result {
+                Ok(ProxyResponse::TypeHierarchyResponse { items }) => {
+                    items.into_iter().map(CallHierarchyNode::new).collect()
+                }
+                _ => Vec::new(),
+            };
+            let _ = event_sink.submit_command(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::UpdateTypeHierarchyTypes {
+                    generation,
+                    indices,
+                    types,
+                },
+                Target::Widget(tab_id),
+            );
+        };
+        match self.direction {
+            TypeHierarchyDirection::Supertypes => {
+                proxy
+                    .proxy_rpc
+                    .type_hierarchy_supertypes(self.plugin_id, item, f);
+            }
+            TypeHierarchyDirection::Subtypes => {
+                proxy
+                    .proxy_rpc
+                    .type_hierarchy_subtypes(self.plugin_id, item, f);
+            }
+        }
+    }
+}
+
+impl Default for TypeHierarchyData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
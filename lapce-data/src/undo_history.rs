@@ -0,0 +1,145 @@
+//! Undo history that outlives the editor session.
+//!
+//! When a file is saved, the deltas that undo its recent edits are written to
+//! the undo directory, in a file named after a hash of the file's path and,
+//! for remote workspaces, of the host the file is on. The
+//! hash of the saved content is kept with them, so that when the file is
+//! opened again the history is only restored if the file hasn't been changed
+//! by something else in the meantime. Otherwise the history is dropped.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use lapce_core::buffer::Buffer;
+use lapce_proxy::directory::Directory;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use xi_rope::{Rope, RopeDelta};
+
+use crate::data::LapceWorkspaceType;
+
+/// The most undo groups that are kept for a file
+const MAX_UNDO_GROUPS: usize = 1000;
+
+/// The most bytes the history of a file can take on disk. Older undo groups
+/// are dropped until the history fits.
+const MAX_HISTORY_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize)]
+struct UndoHistory {
+    /// The hash of the content the deltas apply to
+    content_hash: String,
+    /// The deltas undoing each undo group, newest first
+    deltas: Vec<RopeDelta>,
+}
+
+fn hex_hash(data: impl IntoIterator<Item = impl AsRef<[u8]>>) -> String {
+    let mut hasher = Sha256::new();
+    for chunk in data {
+        hasher.update(chunk.as_ref());
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn content_hash(text: &Rope) -> String {
+    hex_hash(text.iter_chunks(..))
+}
+
+/// The file the history of `path` is kept in. Local files are only keyed by
+/// their path, while remote ones are keyed by their host too, so that the
+/// same path on another machine doesn't share the history.
+fn history_path(workspace: &LapceWorkspaceType, path: &Path) -> Option<PathBuf> {
+    let path = path.to_string_lossy();
+    let name = match workspace {
+        LapceWorkspaceType::Local => hex_hash([path.as_bytes()]),
+        _ => hex_hash([workspace.to_string().as_bytes(), b"\0", path.as_bytes()]),
+    };
+    Some(Directory::undo_directory()?.join(format!("{name}.json")))
+}
+
+/// Serialize the history, dropping the oldest undo groups until it's small
+/// enough to keep
+fn serialize_history(
+    content_hash: String,
+    mut deltas: Vec<RopeDelta>,
+) -> Option<Vec<u8>> {
+    loop {
+        if deltas.is_empty() {
+            return None;
+        }
+        let history = UndoHistory {
+            content_hash: content_hash.clone(),
+            deltas,
+        };
+        let data = serde_json::to_vec(&history).ok()?;
+        if data.len() <= MAX_HISTORY_SIZE {
+            return Some(data);
+        }
+        deltas = history.deltas;
+        deltas.truncate(deltas.len() / 2);
+    }
+}
+
+/// Write the undo history of the file at `path` in a workspace of the kind
+/// `workspace`, which has just been saved with the content of `buffer`. This
+/// is done on another thread, since it undoes the whole history to collect
+/// it.
+pub fn save_async(workspace: LapceWorkspaceType, path: PathBuf, buffer: Buffer) {
+    std::thread::spawn(move || {
+        if let Err(err) = save(&workspace, &path, &buffer) {
+            log::error!("can't save undo history of {path:?}: {err}");
+        }
+    });
+}
+
+fn save(workspace: &LapceWorkspaceType, path: &Path, buffer: &Buffer) -> Result<()> {
+    let history_path = match history_path(workspace, path) {
+        Some(history_path) => history_path,
+        None => return Ok(()),
+    };
+    let deltas = buffer.undo_deltas(MAX_UNDO_GROUPS);
+    match serialize_history(content_hash(buffer.text()), deltas) {
+        Some(data) => std::fs::write(&history_path, data)?,
+        None => {
+            if history_path.exists() {
+                std::fs::remove_file(&history_path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Load the undo history of the file at `path` in a workspace of the kind
+/// `workspace`, which has just been fetched with `content`, for
+/// `Buffer::restore_undo_deltas`. It reads and hashes up to
+/// `MAX_HISTORY_SIZE` bytes, so it's done on the thread that fetched the
+/// file. A history that was saved for other content is removed, since the
+/// file changed outside of Lapce.
+pub fn load(
+    workspace: &LapceWorkspaceType,
+    path: &Path,
+    content: &Rope,
+) -> Vec<RopeDelta> {
+    let history_path = match history_path(workspace, path) {
+        Some(history_path) => history_path,
+        None => return Vec::new(),
+    };
+    let data = match std::fs::read(&history_path) {
+        Ok(data) => data,
+        Err(_) => return Vec::new(),
+    };
+
+    match serde_json::from_slice::<UndoHistory>(&data) {
+        Ok(history) if history.content_hash == content_hash(content) => {
+            history.deltas
+        }
+        _ => {
+            let _ = std::fs::remove_file(&history_path);
+            Vec::new()
+        }
+    }
+}
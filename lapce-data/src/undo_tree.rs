@@ -0,0 +1,102 @@
+use std::time::{Duration, SystemTime};
+
+use druid::WidgetId;
+use lapce_core::buffer::UndoTreeNode;
+
+#[derive(Clone)]
+pub struct UndoTreeData {
+    pub widget_id: WidgetId,
+    pub split_id: WidgetId,
+    pub tree_widget_id: WidgetId,
+}
+
+impl UndoTreeData {
+    pub fn new() -> Self {
+        Self {
+            widget_id: WidgetId::next(),
+            split_id: WidgetId::next(),
+            tree_widget_id: WidgetId::next(),
+        }
+    }
+}
+
+impl Default for UndoTreeData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A row of the undo tree panel
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UndoTreeRow {
+    pub group: usize,
+    /// How many branches off the newest history the group is
+    pub depth: usize,
+}
+
+/// The rows of the undo tree in depth first order from the initial state.
+/// The newest child of a group continues its branch, and the older ones are
+/// shown after it, indented one level deeper.
+pub fn undo_tree_rows(tree: &[UndoTreeNode]) -> Vec<UndoTreeRow> {
+    let mut children = vec![Vec::new(); tree.len()];
+    for (group, node) in tree.iter().enumerate().skip(1) {
+        children[node.parent].push(group);
+    }
+
+    let mut rows = Vec::with_capacity(tree.len());
+    let mut stack = vec![UndoTreeRow { group: 0, depth: 0 }];
+    while let Some(row) = stack.pop() {
+        if let Some((newest, older)) = children[row.group].split_last() {
+            for group in older {
+                stack.push(UndoTreeRow {
+                    group: *group,
+                    depth: row.depth + 1,
+                });
+            }
+            stack.push(UndoTreeRow {
+                group: *newest,
+                depth: row.depth,
+            });
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+/// How long ago `time` was, in the largest unit that fits
+pub fn format_time_ago(time: SystemTime) -> String {
+    let secs = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    if secs < 60 {
+        format!("{secs}s ago")
+    } else if secs < 60 * 60 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 60 * 60 * 24 {
+        format!("{}h ago", secs / (60 * 60))
+    } else {
+        format!("{}d ago", secs / (60 * 60 * 24))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_tree_rows() {
+        let tree = [0, 0, 1, 1, 0, 3]
+            .into_iter()
+            .map(|parent| UndoTreeNode {
+                parent,
+                time: SystemTime::now(),
+            })
+            .collect::<Vec<_>>();
+        let rows = undo_tree_rows(&tree)
+            .into_iter()
+            .map(|row| (row.group, row.depth))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec![(0, 0), (4, 0), (1, 1), (3, 1), (5, 1), (2, 2)]);
+    }
+}
@@ -0,0 +1,131 @@
+//! Delta updates, so that a new release doesn't always require downloading
+//! the full release asset again.
+//!
+//! The last downloaded asset is kept in the updates directory, together with
+//! a `<asset>.version` file recording which release it came from. A release
+//! can publish zstd patches named `<asset>.<from version>.patch.zst`, created
+//! with `zstd --patch-from=<old asset> <new asset>`. When a patch from the
+//! version we have on disk exists, it's applied to the old asset to rebuild
+//! the new one. Otherwise the caller falls back to a full download.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use reqwest::blocking::Client;
+
+use super::{download_asset, ReleaseInfo, UpdateProgress};
+
+/// zstd's `--long` mode allows windows up to 2GB, which `--patch-from`
+/// needs so that the whole old asset can be referenced.
+const PATCH_WINDOW_LOG_MAX: u32 = 31;
+
+fn patch_name(asset_name: &str, from_version: &str) -> String {
+    format!("{asset_name}.{from_version}.patch.zst")
+}
+
+fn version_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(".version");
+    PathBuf::from(path)
+}
+
+/// Remember which release the asset at `file_path` belongs to, so that the
+/// next update can patch from it.
+pub(super) fn record_version(file_path: &Path, version: &str) {
+    if let Err(err) = std::fs::write(version_path(file_path), version) {
+        log::error!("can't record downloaded release version: {err}");
+    }
+}
+
+/// Rebuild the asset of `release` at `file_path` by patching the previously
+/// downloaded asset. Errors when there's no usable patch, in which case the
+/// full asset needs to be downloaded.
+pub(super) fn download_patched(
+    client: &Client,
+    release: &ReleaseInfo,
+    asset_name: &str,
+    file_path: &Path,
+    progress: &mut dyn FnMut(UpdateProgress),
+) -> Result<()> {
+    if !file_path.exists() {
+        return Err(anyhow!("no previous download of {asset_name}"));
+    }
+    let from_version = std::fs::read_to_string(version_path(file_path))?;
+    let from_version = from_version.trim();
+    if from_version == release.version {
+        return Ok(());
+    }
+
+    let name = patch_name(asset_name, from_version);
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .ok_or_else(|| anyhow!("no patch from version {from_version}"))?;
+
+    let patch_path = file_path.with_file_name(&name);
+    download_asset(client, asset, &patch_path, progress)?;
+
+    let new_path = file_path.with_file_name(format!("{asset_name}.new"));
+    let result = (|| -> Result<()> {
+        let old = std::fs::read(file_path)?;
+        let patch = BufReader::new(File::open(&patch_path)?);
+        let mut out = File::create(&new_path)?;
+        apply_patch(&old, patch, &mut out)?;
+        out.flush()?;
+        Ok(())
+    })();
+    let _ = std::fs::remove_file(&patch_path);
+    if let Err(err) = result {
+        let _ = std::fs::remove_file(&new_path);
+        return Err(err);
+    }
+
+    std::fs::rename(&new_path, file_path)?;
+    Ok(())
+}
+
+fn apply_patch(old: &[u8], patch: impl BufRead, out: &mut impl Write) -> Result<()> {
+    let mut decoder = zstd::stream::read::Decoder::with_dictionary(patch, old)?;
+    decoder.window_log_max(PATCH_WINDOW_LOG_MAX)?;
+    std::io::copy(&mut decoder, out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use super::{apply_patch, patch_name};
+
+    #[test]
+    fn test_patch_name() {
+        assert_eq!(
+            patch_name("Lapce-linux.tar.gz", "0.2.0"),
+            "Lapce-linux.tar.gz.0.2.0.patch.zst"
+        );
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        let old = "fn main() {}\n".repeat(1000);
+        let new = format!("{old}fn other() {{}}\n");
+
+        let mut encoder = zstd::stream::write::Encoder::with_dictionary(
+            Vec::new(),
+            3,
+            old.as_bytes(),
+        )
+        .unwrap();
+        encoder.write_all(new.as_bytes()).unwrap();
+        let patch = encoder.finish().unwrap();
+
+        let mut out = Vec::new();
+        apply_patch(old.as_bytes(), patch.as_slice(), &mut out).unwrap();
+        assert_eq!(out, new.as_bytes());
+    }
+}
@@ -0,0 +1,197 @@
+//! Detect installs that are managed by a package manager. Lapce replacing
+//! its own binary would fight with the package manager, so the updater only
+//! notifies about new releases for those.
+//!
+//! Packagers can set `LAPCE_DISTRIBUTION` at build time (e.g. `apt`, `dnf`,
+//! `aur`, `homebrew`, `winget`) to skip the detection. `standalone` marks a
+//! build that updates itself.
+
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+    Homebrew,
+    Scoop,
+    Winget,
+    Flatpak,
+    Snap,
+    Nix,
+    /// Installed into the system directories, by a package manager we
+    /// couldn't identify
+    System,
+}
+
+impl PackageManager {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Pacman => "pacman",
+            PackageManager::Homebrew => "Homebrew",
+            PackageManager::Scoop => "Scoop",
+            PackageManager::Winget => "winget",
+            PackageManager::Flatpak => "Flatpak",
+            PackageManager::Snap => "Snap",
+            PackageManager::Nix => "Nix",
+            PackageManager::System => "the system package manager",
+        }
+    }
+
+    /// The command which upgrades Lapce, if there's a well known one.
+    pub fn upgrade_command(&self) -> Option<&'static str> {
+        match self {
+            PackageManager::Apt => {
+                Some("sudo apt update && sudo apt install --only-upgrade lapce")
+            }
+            PackageManager::Dnf => Some("sudo dnf upgrade lapce"),
+            PackageManager::Pacman => Some("sudo pacman -Syu lapce"),
+            PackageManager::Homebrew => Some("brew upgrade lapce"),
+            PackageManager::Scoop => Some("scoop update lapce"),
+            PackageManager::Winget => Some("winget upgrade lapce"),
+            PackageManager::Flatpak => Some("flatpak update dev.lapce.lapce"),
+            PackageManager::Snap => Some("sudo snap refresh lapce"),
+            PackageManager::Nix | PackageManager::System => None,
+        }
+    }
+
+    /// The page with the upgrade instructions of the package.
+    pub fn upgrade_url(&self) -> &'static str {
+        match self {
+            PackageManager::Flatpak => {
+                "https://flathub.org/apps/details/dev.lapce.lapce"
+            }
+            _ => "https://github.com/lapce/lapce#installation",
+        }
+    }
+
+    fn from_distribution(distribution: &str) -> Option<Self> {
+        Some(match distribution.trim().to_lowercase().as_str() {
+            "apt" | "deb" | "debian" | "ubuntu" => PackageManager::Apt,
+            "dnf" | "rpm" | "fedora" => PackageManager::Dnf,
+            "aur" | "pacman" | "arch" => PackageManager::Pacman,
+            "homebrew" | "brew" => PackageManager::Homebrew,
+            "scoop" => PackageManager::Scoop,
+            "winget" => PackageManager::Winget,
+            "flatpak" => PackageManager::Flatpak,
+            "snap" => PackageManager::Snap,
+            "nix" => PackageManager::Nix,
+            "" | "standalone" => return None,
+            _ => PackageManager::System,
+        })
+    }
+
+    fn from_path(exe: &Path) -> Option<Self> {
+        let path = exe.to_string_lossy().replace('\\', "/").to_lowercase();
+        let manager = if path.contains("/nix/store/") {
+            PackageManager::Nix
+        } else if path.starts_with("/snap/") {
+            PackageManager::Snap
+        } else if path.starts_with("/app/") || path.contains("/flatpak/") {
+            PackageManager::Flatpak
+        } else if path.starts_with("/opt/homebrew/")
+            || path.starts_with("/usr/local/cellar/")
+            || path.starts_with("/home/linuxbrew/")
+            || path.contains("/caskroom/")
+        {
+            PackageManager::Homebrew
+        } else if path.contains("/scoop/apps/") {
+            PackageManager::Scoop
+        } else if path.contains("/winget/packages/") {
+            PackageManager::Winget
+        } else if path.starts_with("/usr/bin/")
+            || path.starts_with("/usr/lib/")
+            || path.starts_with("/usr/share/")
+        {
+            PackageManager::System
+        } else {
+            return None;
+        };
+        Some(manager)
+    }
+
+    /// Find out which package manager owns the system directories.
+    fn system() -> Self {
+        if Path::new("/etc/arch-release").exists() {
+            PackageManager::Pacman
+        } else if Path::new("/etc/debian_version").exists() {
+            PackageManager::Apt
+        } else if Path::new("/etc/fedora-release").exists()
+            || Path::new("/etc/redhat-release").exists()
+        {
+            PackageManager::Dnf
+        } else {
+            PackageManager::System
+        }
+    }
+}
+
+/// The package manager this Lapce was installed with, if any.
+pub fn package_manager() -> Option<PackageManager> {
+    if let Some(distribution) = option_env!("LAPCE_DISTRIBUTION") {
+        return PackageManager::from_distribution(distribution);
+    }
+    if std::env::var_os("FLATPAK_ID").is_some() {
+        return Some(PackageManager::Flatpak);
+    }
+    if std::env::var_os("SNAP").is_some() {
+        return Some(PackageManager::Snap);
+    }
+
+    let exe = std::env::current_exe().ok()?;
+    match PackageManager::from_path(&exe)? {
+        PackageManager::System => Some(PackageManager::system()),
+        manager => Some(manager),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::PackageManager;
+
+    #[test]
+    fn test_from_distribution() {
+        assert_eq!(
+            PackageManager::from_distribution("AUR"),
+            Some(PackageManager::Pacman)
+        );
+        assert_eq!(
+            PackageManager::from_distribution("winget"),
+            Some(PackageManager::Winget)
+        );
+        assert_eq!(PackageManager::from_distribution("standalone"), None);
+    }
+
+    #[test]
+    fn test_from_path() {
+        let from_path = |path: &str| PackageManager::from_path(Path::new(path));
+        assert_eq!(from_path("/usr/bin/lapce"), Some(PackageManager::System));
+        assert_eq!(
+            from_path("/opt/homebrew/Caskroom/lapce/0.2.0/Lapce.app"),
+            Some(PackageManager::Homebrew)
+        );
+        assert_eq!(
+            from_path(r"C:\Users\me\scoop\apps\lapce\current\lapce.exe"),
+            Some(PackageManager::Scoop)
+        );
+        assert_eq!(
+            from_path(
+                r"C:\Users\me\AppData\Local\Microsoft\WinGet\Packages\Lapce\lapce.exe"
+            ),
+            Some(PackageManager::Winget)
+        );
+        assert_eq!(
+            from_path("/nix/store/abc-lapce/bin/lapce"),
+            Some(PackageManager::Nix)
+        );
+        assert_eq!(from_path("/home/me/.local/bin/lapce"), None);
+        assert_eq!(
+            from_path("/Applications/Lapce.app/Contents/MacOS/lapce"),
+            None
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! The network side of the updater. All update traffic goes through one
+//! shared client, and requests which fail for reasons that may go away,
+//! like a dropped connection or an overloaded server, are retried with
+//! exponential backoff.
+
+use std::{
+    io,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use reqwest::{
+    blocking::{Client, Response},
+    StatusCode,
+};
+
+use super::{is_download_cancelled, UpdateSettings};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// The timeout of the small requests, i.e. everything but the downloads of
+/// the release assets.
+pub(super) const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const MAX_ATTEMPTS: u32 = 5;
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The client is kept until the settings it was built from change.
+static CLIENT: Mutex<Option<(ClientSettings, Client)>> =
+    parking_lot::const_mutex(None);
+
+#[derive(Clone, PartialEq, Eq)]
+struct ClientSettings {
+    proxy: Option<String>,
+    ca_certificate: Option<PathBuf>,
+}
+
+/// Whether an update failure may go away by trying again later.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateErrorKind {
+    Transient,
+    Permanent,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{url} returned {status}")]
+pub struct HttpStatusError {
+    pub status: StatusCode,
+    pub url: String,
+}
+
+pub(super) fn client(settings: &UpdateSettings) -> Result<Client> {
+    let key = ClientSettings {
+        proxy: settings.proxy.clone(),
+        ca_certificate: settings.ca_certificate.clone(),
+    };
+    let mut cached = CLIENT.lock();
+    if let Some((cached_key, client)) = cached.as_ref() {
+        if *cached_key == key {
+            return Ok(client.clone());
+        }
+    }
+
+    let client = build_client(&key)?;
+    *cached = Some((key, client.clone()));
+    Ok(client)
+}
+
+fn build_client(settings: &ClientSettings) -> Result<Client> {
+    let mut builder = Client::builder()
+        .user_agent(format!("Lapce/{}", *lapce_proxy::VERSION))
+        .connect_timeout(CONNECT_TIMEOUT);
+    if let Some(proxy) = settings.proxy.as_ref() {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    if let Some(path) = settings.ca_certificate.as_ref() {
+        let pem = std::fs::read(path).map_err(|e| {
+            anyhow!("can't read CA certificate {}: {e}", path.display())
+        })?;
+        builder =
+            builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+    }
+    Ok(builder.build()?)
+}
+
+/// Turn an unsuccessful response into an `HttpStatusError`.
+pub(super) fn check_status(resp: Response) -> Result<Response> {
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(HttpStatusError {
+            status,
+            url: resp.url().to_string(),
+        }
+        .into());
+    }
+    Ok(resp)
+}
+
+pub fn error_kind(err: &anyhow::Error) -> UpdateErrorKind {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<HttpStatusError>() {
+            return if err.status.is_server_error()
+                || err.status == StatusCode::TOO_MANY_REQUESTS
+                || err.status == StatusCode::REQUEST_TIMEOUT
+            {
+                UpdateErrorKind::Transient
+            } else {
+                UpdateErrorKind::Permanent
+            };
+        }
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            if err.is_timeout() || err.is_connect() || err.is_body() {
+                return UpdateErrorKind::Transient;
+            }
+        }
+        if let Some(err) = cause.downcast_ref::<io::Error>() {
+            if matches!(
+                err.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::UnexpectedEof
+                    | io::ErrorKind::Interrupted
+            ) {
+                return UpdateErrorKind::Transient;
+            }
+        }
+    }
+    UpdateErrorKind::Permanent
+}
+
+/// Run `f` until it succeeds, it fails permanently, the download gets
+/// cancelled or it ran out of attempts.
+pub(super) fn retry<T>(mut f: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut attempt = 0;
+    loop {
+        let err = match f() {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        attempt += 1;
+        if attempt >= MAX_ATTEMPTS
+            || error_kind(&err) == UpdateErrorKind::Permanent
+            || is_download_cancelled()
+        {
+            return Err(err);
+        }
+
+        let delay = backoff(attempt - 1, jitter());
+        log::warn!("update request failed, retrying in {delay:?}: {err}");
+        std::thread::sleep(delay);
+    }
+}
+
+/// The delay before retrying after `attempt` failed attempts. It doubles
+/// with every attempt, and `jitter` (between 0.0 and 1.0) spreads it between
+/// half and the full delay, so that clients don't retry in lockstep.
+fn backoff(attempt: u32, jitter: f64) -> Duration {
+    let delay = BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF);
+    delay.mul_f64(0.5 + jitter.clamp(0.0, 1.0) / 2.0)
+}
+
+fn jitter() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as f64 / 1_000_000_000.0)
+        .unwrap_or(0.5)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io, time::Duration};
+
+    use reqwest::StatusCode;
+
+    use super::{backoff, error_kind, HttpStatusError, UpdateErrorKind};
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(0, 1.0), Duration::from_secs(1));
+        assert_eq!(backoff(3, 1.0), Duration::from_secs(8));
+        assert_eq!(backoff(3, 0.0), Duration::from_secs(4));
+        assert_eq!(backoff(20, 1.0), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_error_kind() {
+        let status = |status| {
+            anyhow::Error::from(HttpStatusError {
+                status,
+                url: "https://example.com".to_string(),
+            })
+        };
+        assert_eq!(
+            error_kind(&status(StatusCode::SERVICE_UNAVAILABLE)),
+            UpdateErrorKind::Transient
+        );
+        assert_eq!(
+            error_kind(&status(StatusCode::NOT_FOUND)),
+            UpdateErrorKind::Permanent
+        );
+        assert_eq!(
+            error_kind(&anyhow::Error::from(io::Error::from(
+                io::ErrorKind::UnexpectedEof
+            ))),
+            UpdateErrorKind::Transient
+        );
+        assert_eq!(
+            error_kind(&anyhow::anyhow!("checksum mismatch")),
+            UpdateErrorKind::Permanent
+        );
+    }
+}
@@ -0,0 +1,1145 @@
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use lapce_proxy::{directory::Directory, VERSION};
+use minisign_verify::{PublicKey, Signature};
+use reqwest::{blocking::Client, header, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use strum_macros::{Display, EnumString};
+
+use crate::config::Config;
+
+mod delta;
+mod distribution;
+mod http;
+mod schedule;
+
+pub use distribution::{package_manager, PackageManager};
+pub use http::{error_kind, UpdateErrorKind};
+pub use schedule::{
+    last_check, record_check, time_until_check, UpdateCheckFrequency,
+    UpdateDismissal,
+};
+
+use http::{check_status, retry, REQUEST_TIMEOUT};
+
+/// The minisign public key of the Lapce release signing key, set by the
+/// release pipeline at build time. Only binaries signed by the matching
+/// secret key are ever extracted and run, so builds without it can't update
+/// themselves.
+const LAPCE_PUBLIC_KEY: Option<&str> = option_env!("LAPCE_UPDATE_PUBLIC_KEY");
+
+/// How often the progress of a download is reported.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Set when the user cancels the update download that's in progress.
+static DOWNLOAD_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+const RELEASES_URL: &str = "https://api.github.com/repos/lapce/lapce/releases";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    /// Pre-releases as well as stable releases
+    Beta,
+    Nightly,
+}
+
+impl UpdateChannel {
+    /// The channel the running build was released on.
+    pub fn current() -> Self {
+        if VERSION.starts_with("nightly") {
+            UpdateChannel::Nightly
+        } else {
+            UpdateChannel::Stable
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct ReleaseInfo {
+    pub tag_name: String,
+    pub target_commitish: String,
+    pub assets: Vec<ReleaseAsset>,
+    #[serde(default)]
+    pub draft: bool,
+    /// The release notes, in markdown
+    #[serde(default)]
+    pub body: String,
+    #[serde(skip)]
+    pub version: String,
+    /// The channel this release was looked up on
+    #[serde(skip)]
+    pub channel: UpdateChannel,
+    /// The user skipped this release, or asked to be reminded later
+    #[serde(skip)]
+    pub dismissed: bool,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+/// The settings which control how the updater talks to the network.
+#[derive(Clone, Debug)]
+pub struct UpdateSettings {
+    /// Proxy used for all update traffic. When not set, the proxy from the
+    /// `HTTP_PROXY`/`HTTPS_PROXY` environment variables is used.
+    pub proxy: Option<String>,
+    /// An extra root certificate (PEM) to trust, for proxies which
+    /// intercept TLS traffic. Falls back to `SSL_CERT_FILE`.
+    pub ca_certificate: Option<PathBuf>,
+    pub channel: UpdateChannel,
+    /// The releases endpoint, which is GitHub unless the user points it at
+    /// a self-hosted mirror.
+    pub server_url: String,
+    /// No update traffic at all is allowed
+    pub offline: bool,
+}
+
+impl UpdateSettings {
+    pub fn from_config(config: &Config) -> Self {
+        let non_empty = |s: &str| {
+            let s = s.trim();
+            (!s.is_empty()).then(|| s.to_string())
+        };
+        Self {
+            proxy: non_empty(&config.lapce.update_proxy),
+            ca_certificate: non_empty(&config.lapce.update_ca_certificate)
+                .or_else(|| std::env::var("SSL_CERT_FILE").ok())
+                .map(PathBuf::from),
+            channel: config
+                .lapce
+                .update_channel
+                .trim()
+                .to_lowercase()
+                .parse()
+                .unwrap_or_else(|_| UpdateChannel::current()),
+            server_url: non_empty(&config.lapce.update_server_url)
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| RELEASES_URL.to_string()),
+            offline: config.lapce.is_offline(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpdateProgress {
+    pub downloaded: u64,
+    /// The total size of the download, if the server reported it
+    pub total: Option<u64>,
+}
+
+impl UpdateProgress {
+    /// The downloaded fraction, between 0.0 and 1.0
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| (self.downloaded as f64 / total as f64).min(1.0))
+    }
+}
+
+/// Cancel the download started by `download_release`. The partially
+/// downloaded file gets removed and `download_release` returns an error.
+pub fn cancel_download() {
+    DOWNLOAD_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_download_cancelled() -> bool {
+    DOWNLOAD_CANCELLED.load(Ordering::SeqCst)
+}
+
+pub fn get_latest_release(settings: &UpdateSettings) -> Result<ReleaseInfo> {
+    if *VERSION == "debug" {
+        return Err(anyhow!("no release for debug"));
+    }
+    if settings.offline {
+        return Err(anyhow!("update checks are disabled in offline mode"));
+    }
+
+    let client = http::client(settings)?;
+    let get = |url: &str| -> Result<String> {
+        retry(|| {
+            let resp =
+                check_status(client.get(url).timeout(REQUEST_TIMEOUT).send()?)?;
+            Ok(resp.text()?)
+        })
+    };
+
+    let server_url = &settings.server_url;
+    let mut release: ReleaseInfo = match settings.channel {
+        UpdateChannel::Stable => {
+            serde_json::from_str(&get(&format!("{server_url}/latest"))?)?
+        }
+        UpdateChannel::Nightly => {
+            serde_json::from_str(&get(&format!("{server_url}/tags/nightly"))?)?
+        }
+        UpdateChannel::Beta => {
+            let releases: Vec<ReleaseInfo> =
+                serde_json::from_str(&get(server_url)?)?;
+            releases
+                .into_iter()
+                .find(|r| !r.draft && r.tag_name != "nightly")
+                .ok_or_else(|| anyhow!("no beta release"))?
+        }
+    };
+
+    release.version = match release.tag_name.as_str() {
+        "nightly" => format!("nightly-{}", &release.target_commitish[..7]),
+        tag => tag.strip_prefix('v').unwrap_or(tag).to_string(),
+    };
+    release.channel = settings.channel;
+    for asset in release.assets.iter_mut() {
+        asset.browser_download_url =
+            resolve_url(server_url, &asset.browser_download_url);
+    }
+
+    Ok(release)
+}
+
+/// Mirrors may list asset URLs relative to the server URL, rather than the
+/// absolute URLs GitHub uses.
+fn resolve_url(server_url: &str, url: &str) -> String {
+    if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("{server_url}/{}", url.trim_start_matches('/'))
+    }
+}
+
+/// The names of the release asset for `os` and `arch`, most preferred first.
+/// The names without an architecture are from releases which only had
+/// x86_64 builds, except on macOS where the dmg is a universal bundle.
+fn asset_names(os: &str, arch: &str, musl: bool) -> Vec<String> {
+    let arch_names = match arch {
+        "x86_64" => vec!["x86_64", "amd64"],
+        "aarch64" => vec!["aarch64", "arm64"],
+        arch => vec![arch],
+    };
+    match os {
+        "macos" => {
+            let mut names = vec!["Lapce-macos-universal.dmg".to_string()];
+            names.extend(arch_names.iter().map(|a| format!("Lapce-macos-{a}.dmg")));
+            names.push("Lapce-macos.dmg".to_string());
+            names
+        }
+        // The glibc builds don't run on musl, so there's no fallback to them
+        "linux" if musl => arch_names
+            .iter()
+            .map(|a| format!("Lapce-linux-{a}-musl.tar.gz"))
+            .collect(),
+        "linux" => {
+            let mut names: Vec<String> = arch_names
+                .iter()
+                .map(|a| format!("Lapce-linux-{a}.tar.gz"))
+                .collect();
+            if arch == "x86_64" {
+                names.push("Lapce-linux.tar.gz".to_string());
+            }
+            names
+        }
+        "windows" => {
+            let mut names: Vec<String> = arch_names
+                .iter()
+                .map(|a| format!("Lapce-windows-{a}-portable.zip"))
+                .collect();
+            if arch == "x86_64" {
+                names.push("Lapce-windows-portable.zip".to_string());
+            }
+            names
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Pick the asset of a release that runs on `os` and `arch`.
+fn select_asset<'a>(
+    assets: &'a [ReleaseAsset],
+    os: &str,
+    arch: &str,
+    musl: bool,
+) -> Option<&'a ReleaseAsset> {
+    asset_names(os, arch, musl)
+        .iter()
+        .find_map(|name| assets.iter().find(|asset| &asset.name == name))
+}
+
+/// Whether this system's C library is musl (e.g. Alpine or Void Linux)
+/// rather than glibc.
+fn is_musl() -> bool {
+    if cfg!(target_env = "musl") {
+        return true;
+    }
+    if std::env::consts::OS != "linux" {
+        return false;
+    }
+    // musl's dynamic loader is /lib/ld-musl-<arch>.so.1
+    std::fs::read_dir("/lib")
+        .map(|entries| {
+            entries.flatten().any(|entry| {
+                entry.file_name().to_string_lossy().starts_with("ld-musl-")
+            })
+        })
+        .unwrap_or(false)
+}
+
+pub fn download_release(
+    release: &ReleaseInfo,
+    settings: &UpdateSettings,
+    mut progress: impl FnMut(UpdateProgress),
+) -> Result<PathBuf> {
+    if settings.offline {
+        return Err(anyhow!("update downloads are disabled in offline mode"));
+    }
+    if let Some(manager) = package_manager() {
+        return Err(anyhow!("Lapce is managed by {}", manager.name()));
+    }
+    let _ = release_public_key()?;
+    DOWNLOAD_CANCELLED.store(false, Ordering::SeqCst);
+    let client = http::client(settings)?;
+    let dir =
+        Directory::updates_directory().ok_or_else(|| anyhow!("no directory"))?;
+    let (os, arch) = (std::env::consts::OS, std::env::consts::ARCH);
+    let musl = is_musl();
+    let asset = select_asset(&release.assets, os, arch, musl).ok_or_else(|| {
+        if musl {
+            anyhow!(
+                "Lapce {} has no build for musl based systems on {arch}. \
+                 Install Lapce with your distribution's package manager or \
+                 build it from source instead.",
+                release.version
+            )
+        } else {
+            anyhow!("no release asset for {os} {arch}")
+        }
+    })?;
+    let name = asset.name.as_str();
+    let file_path = dir.join(name);
+
+    let patched = match delta::download_patched(
+        &client,
+        release,
+        name,
+        &file_path,
+        &mut progress,
+    ) {
+        Ok(()) => verify_checksum(&client, release, name, &file_path).is_ok(),
+        Err(err) => {
+            log::info!("delta update not available: {err}");
+            false
+        }
+    };
+    if is_download_cancelled() {
+        return Err(anyhow!("update download cancelled"));
+    }
+
+    if !patched {
+        download_asset(&client, asset, &file_path, &mut progress)?;
+
+        if let Err(err) = verify_checksum(&client, release, name, &file_path) {
+            let _ = std::fs::remove_file(&file_path);
+            return Err(err);
+        }
+    }
+    delta::record_version(&file_path, &release.version);
+
+    let signature_name = format!("{name}.minisig");
+    let signature_asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == signature_name)
+        .ok_or_else(|| anyhow!("no signature published for {name}"))?;
+    download_asset(
+        &client,
+        signature_asset,
+        &signature_path(&file_path),
+        &mut |_| {},
+    )?;
+
+    Ok(file_path)
+}
+
+/// What was requested for a partial download, so that resuming it can
+/// check that the server is still serving the same file.
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+struct PartialDownload {
+    url: String,
+    etag: Option<String>,
+    total: Option<u64>,
+}
+
+fn partial_path(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// Parse a `Content-Range: bytes <start>-<end>/<total>` header into the
+/// start offset and the total size, if the server knows it.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (range, total) = range.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    let start = start.trim().parse().ok()?;
+    let total = match total.trim() {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    Some((start, total))
+}
+
+/// Download `asset` to `path`. The data is written to `<path>.partial` first,
+/// which is kept when the connection drops so that the next attempt can
+/// resume it with a range request.
+fn download_asset(
+    client: &Client,
+    asset: &ReleaseAsset,
+    path: &Path,
+    progress: &mut dyn FnMut(UpdateProgress),
+) -> Result<()> {
+    retry(|| download_asset_once(client, asset, path, progress))
+}
+
+fn download_asset_once(
+    client: &Client,
+    asset: &ReleaseAsset,
+    path: &Path,
+    progress: &mut dyn FnMut(UpdateProgress),
+) -> Result<()> {
+    let url = &asset.browser_download_url;
+    let partial = partial_path(path, ".partial");
+    let meta_path = partial_path(path, ".partial.json");
+
+    let previous: Option<PartialDownload> = std::fs::read(&meta_path)
+        .ok()
+        .and_then(|meta| serde_json::from_slice(&meta).ok())
+        .filter(|meta: &PartialDownload| &meta.url == url);
+    let existing = match (&previous, std::fs::metadata(&partial)) {
+        (Some(_), Ok(metadata)) => metadata.len(),
+        _ => 0,
+    };
+
+    let mut req = client.get(url);
+    if existing > 0 {
+        req = req.header(header::RANGE, format!("bytes={existing}-"));
+        if let Some(etag) = previous.as_ref().and_then(|p| p.etag.as_ref()) {
+            req = req.header(header::IF_RANGE, etag);
+        }
+    }
+    let resp = req.send()?;
+
+    if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // The partial file doesn't fit the file on the server anymore
+        let _ = std::fs::remove_file(&partial);
+        let _ = std::fs::remove_file(&meta_path);
+        return download_asset_once(client, asset, path, progress);
+    }
+    let mut resp = check_status(resp)?;
+
+    let etag = resp
+        .headers()
+        .get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(|etag| etag.to_string());
+    let resumed = resp.status() == StatusCode::PARTIAL_CONTENT;
+    let total = if resumed {
+        let (start, total) = resp
+            .headers()
+            .get(header::CONTENT_RANGE)
+            .and_then(|range| range.to_str().ok())
+            .and_then(parse_content_range)
+            .ok_or_else(|| anyhow!("invalid content range"))?;
+        let changed = match previous.as_ref() {
+            Some(previous) => {
+                start != existing
+                    || (previous.total.is_some() && total != previous.total)
+                    || (previous.etag.is_some() && etag != previous.etag)
+            }
+            None => true,
+        };
+        if changed {
+            drop(resp);
+            let _ = std::fs::remove_file(&partial);
+            let _ = std::fs::remove_file(&meta_path);
+            return download_asset_once(client, asset, path, progress);
+        }
+        total
+    } else {
+        resp.content_length()
+    };
+
+    let mut out = if resumed {
+        std::fs::OpenOptions::new().append(true).open(&partial)?
+    } else {
+        let meta = PartialDownload {
+            url: url.to_string(),
+            etag,
+            total,
+        };
+        std::fs::write(&meta_path, serde_json::to_vec(&meta)?)?;
+        std::fs::File::create(&partial)?
+    };
+    let mut downloaded = if resumed { existing } else { 0 };
+    let mut last_report = Instant::now();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        if is_download_cancelled() {
+            drop(out);
+            let _ = std::fs::remove_file(&partial);
+            let _ = std::fs::remove_file(&meta_path);
+            return Err(anyhow!("update download cancelled"));
+        }
+
+        let n = resp.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        out.write_all(&buf[..n])?;
+        downloaded += n as u64;
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            last_report = Instant::now();
+            progress(UpdateProgress { downloaded, total });
+        }
+    }
+    progress(UpdateProgress { downloaded, total });
+    out.flush()?;
+    drop(out);
+
+    if let Some(total) = total {
+        if downloaded != total {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("download interrupted at {downloaded} of {total} bytes"),
+            )
+            .into());
+        }
+    }
+
+    std::fs::rename(&partial, path)?;
+    let _ = std::fs::remove_file(&meta_path);
+
+    Ok(())
+}
+
+/// Verify the downloaded asset against the `<asset>.sha256` file published
+/// alongside it in the same release.
+fn verify_checksum(
+    client: &Client,
+    release: &ReleaseInfo,
+    asset_name: &str,
+    file_path: &Path,
+) -> Result<()> {
+    let checksum_name = format!("{asset_name}.sha256");
+    let checksum_asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == checksum_name)
+        .ok_or_else(|| anyhow!("no checksum published for {asset_name}"))?;
+
+    let content = retry(|| {
+        let resp = check_status(
+            client
+                .get(&checksum_asset.browser_download_url)
+                .timeout(REQUEST_TIMEOUT)
+                .send()?,
+        )?;
+        Ok(resp.text()?)
+    })?;
+    let expected = parse_checksum(&content, asset_name)
+        .ok_or_else(|| anyhow!("invalid checksum file {checksum_name}"))?;
+
+    let actual = sha256_file(file_path)?;
+    if !actual.eq_ignore_ascii_case(&expected) {
+        return Err(anyhow!(
+            "checksum mismatch for {asset_name}: expected {expected}, got {actual}"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Find the hash for `asset_name` in a checksum file. Both a bare hash and
+/// the `sha256sum` output format (`<hash>  <file name>`) are accepted.
+fn parse_checksum(content: &str, asset_name: &str) -> Option<String> {
+    let is_hash =
+        |s: &str| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit());
+
+    let lines: Vec<&str> = content
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+    if let [line] = lines.as_slice() {
+        if is_hash(line) {
+            return Some(line.to_string());
+        }
+    }
+
+    lines.iter().find_map(|line| {
+        let (hash, file) = line.split_once(char::is_whitespace)?;
+        let file = file.trim().trim_start_matches('*');
+        (is_hash(hash) && file == asset_name).then(|| hash.to_string())
+    })
+}
+
+/// The minisign signature of an asset is stored next to it, with a
+/// `.minisig` suffix appended to the file name.
+fn signature_path(src: &Path) -> PathBuf {
+    let mut path = src.as_os_str().to_owned();
+    path.push(".minisig");
+    PathBuf::from(path)
+}
+
+/// Check that `src` was signed by the Lapce release key. This must succeed
+/// before anything from the downloaded asset gets extracted.
+pub fn verify_signature(src: &Path) -> Result<()> {
+    let signature = std::fs::read_to_string(signature_path(src))
+        .map_err(|e| anyhow!("can't read signature for {src:?}: {e}"))?;
+    let data = std::fs::read(src)?;
+    verify_signature_with_key(release_public_key()?, &data, &signature)
+}
+
+fn release_public_key() -> Result<&'static str> {
+    LAPCE_PUBLIC_KEY.ok_or_else(|| {
+        anyhow!("this build of Lapce has no release key to verify updates with")
+    })
+}
+
+fn verify_signature_with_key(
+    public_key: &str,
+    data: &[u8],
+    signature: &str,
+) -> Result<()> {
+    let public_key = PublicKey::from_base64(public_key)
+        .map_err(|e| anyhow!("invalid public key: {e}"))?;
+    let signature = Signature::decode(signature)
+        .map_err(|e| anyhow!("invalid signature: {e}"))?;
+    public_key
+        .verify(data, &signature, false)
+        .map_err(|e| anyhow!("signature verification failed: {e}"))
+}
+
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// The previous installation is kept here when an update gets extracted,
+/// so that it can be restored with `rollback`.
+fn previous_directory() -> Result<PathBuf> {
+    let dir = Directory::updates_directory()
+        .ok_or_else(|| anyhow!("no directory"))?
+        .join("previous");
+    if !dir.exists() {
+        std::fs::create_dir_all(&dir)?;
+    }
+    Ok(dir)
+}
+
+/// The version of the installation that `rollback` would restore, if any.
+pub fn previous_version() -> Option<String> {
+    let dir = previous_directory().ok()?;
+    let version = std::fs::read_to_string(dir.join("version")).ok()?;
+    Some(version.trim().to_string())
+}
+
+fn record_previous_version(dir: &Path) -> Result<()> {
+    std::fs::write(dir.join("version"), *VERSION)?;
+    Ok(())
+}
+
+/// Extra free space required on top of what an install needs, so that
+/// updating never fills up the disk completely.
+const DISK_SPACE_MARGIN: u64 = 50 * 1024 * 1024;
+
+fn path_size(path: &Path) -> Result<u64> {
+    Ok(if path.is_dir() {
+        fs_extra::dir::get_size(path)?
+    } else {
+        std::fs::metadata(path)?.len()
+    })
+}
+
+/// Fail early when `dir` doesn't have room for `needed` bytes, before any
+/// part of the current installation is touched.
+fn ensure_disk_space(dir: &Path, needed: u64) -> Result<()> {
+    let available = fs2::available_space(dir)?;
+    if available < needed + DISK_SPACE_MARGIN {
+        return Err(anyhow!(
+            "not enough disk space in {}: {needed} bytes needed, {available} available",
+            dir.display()
+        ));
+    }
+    Ok(())
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// A path next to `target`, so that renaming between the two stays on the
+/// same filesystem and is atomic.
+fn sibling_path(target: &Path, suffix: &str) -> PathBuf {
+    let mut path = target.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Replace `target` with the already staged `staged`. The current `target`
+/// is moved aside first and moved back if the swap fails, so `target` is
+/// never left missing or half written.
+fn swap_into_place(staged: &Path, target: &Path) -> Result<()> {
+    let old = sibling_path(target, ".old");
+    if old.exists() {
+        remove_path(&old)?;
+    }
+    if target.exists() {
+        // Renaming works for a running executable, even on Windows
+        std::fs::rename(target, &old)?;
+    }
+    if let Err(err) = std::fs::rename(staged, target) {
+        if old.exists() {
+            std::fs::rename(&old, target)?;
+        }
+        return Err(err.into());
+    }
+    // On Windows the old executable is still running and can't be removed;
+    // it's cleaned up by the next update instead.
+    let _ = remove_path(&old);
+    Ok(())
+}
+
+/// Copy `src` next to `target` and swap it into place.
+fn install(src: &Path, target: &Path) -> Result<()> {
+    let staged = sibling_path(target, ".new");
+    if staged.exists() {
+        remove_path(&staged)?;
+    }
+    let result = if src.is_dir() {
+        std::fs::create_dir_all(&staged)?;
+        let mut options = fs_extra::dir::CopyOptions::new();
+        options.content_only = true;
+        fs_extra::dir::copy(src, &staged, &options)
+            .map(|_| ())
+            .map_err(anyhow::Error::from)
+    } else {
+        std::fs::copy(src, &staged)
+            .map(|_| ())
+            .map_err(anyhow::Error::from)
+    };
+    if let Err(err) = result.and_then(|_| swap_into_place(&staged, target)) {
+        let _ = remove_path(&staged);
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn app_bundle_parent(process_path: &Path) -> Result<&Path> {
+    let dest = process_path.parent().ok_or_else(|| anyhow!("no parent"))?;
+    Ok(
+        if dest.file_name().and_then(|s| s.to_str()) == Some("MacOS") {
+            dest.parent().unwrap().parent().unwrap().parent().unwrap()
+        } else {
+            dest
+        },
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn copy_app_bundle(app: &Path, dest: &Path) -> Result<()> {
+    fs_extra::copy_items(
+        &[app],
+        dest,
+        &fs_extra::dir::CopyOptions {
+            overwrite: true,
+            skip_exist: false,
+            buffer_size: 64000,
+            copy_inside: true,
+            content_only: false,
+            depth: 0,
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn extract(src: &Path, process_path: &Path) -> Result<PathBuf> {
+    verify_signature(src)?;
+    let info = dmg::Attach::new(src).with()?;
+    let dest = app_bundle_parent(process_path)?;
+
+    let app = dest.join("Lapce.app");
+    let new_app = info.mount_point.join("Lapce.app");
+
+    let previous = previous_directory()?;
+    ensure_disk_space(&previous, path_size(&app)?)?;
+    ensure_disk_space(dest, path_size(&new_app)?)?;
+
+    let _ = std::fs::remove_dir_all(previous.join("Lapce.app"));
+    copy_app_bundle(&app, &previous)?;
+    record_previous_version(&previous)?;
+
+    install(&new_app, &app)?;
+    Ok(app)
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
+pub fn extract(src: &Path, process_path: &Path) -> Result<PathBuf> {
+    verify_signature(src)?;
+    let tar_gz = std::fs::File::open(src)?;
+    let tar = flate2::read::GzDecoder::new(tar_gz);
+    let mut archive = tar::Archive::new(tar);
+    let parent = src.parent().ok_or_else(|| anyhow::anyhow!("no parent"))?;
+    archive.unpack(parent)?;
+
+    let new_bin = parent.join("Lapce").join("lapce");
+
+    let previous = previous_directory()?;
+    ensure_disk_space(&previous, path_size(process_path)?)?;
+    ensure_disk_space(
+        process_path.parent().ok_or_else(|| anyhow!("no parent"))?,
+        path_size(&new_bin)?,
+    )?;
+
+    std::fs::copy(process_path, previous.join("lapce"))?;
+    record_previous_version(&previous)?;
+
+    install(&new_bin, process_path)?;
+    Ok(process_path.to_path_buf())
+}
+
+#[cfg(target_os = "windows")]
+pub fn extract(src: &Path, process_path: &Path) -> Result<PathBuf> {
+    verify_signature(src)?;
+    let parent = src.parent().ok_or_else(|| anyhow::anyhow!("no parent"))?;
+    {
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(src)?)?;
+        archive.extract(parent)?;
+    }
+
+    let new_bin = parent.join("lapce.exe");
+
+    let previous = previous_directory()?;
+    ensure_disk_space(&previous, path_size(process_path)?)?;
+    ensure_disk_space(
+        process_path.parent().ok_or_else(|| anyhow!("no parent"))?,
+        path_size(&new_bin)?,
+    )?;
+
+    std::fs::copy(process_path, previous.join("lapce.exe"))?;
+    record_previous_version(&previous)?;
+
+    install(&new_bin, process_path)?;
+    Ok(process_path.to_path_buf())
+}
+
+/// Restore the installation which was replaced by the last update. Returns
+/// the path to pass to `restart`.
+#[cfg(target_os = "macos")]
+pub fn rollback(process_path: &Path) -> Result<PathBuf> {
+    let previous = previous_directory()?;
+    let app = previous.join("Lapce.app");
+    if !app.exists() {
+        return Err(anyhow!("no previous version to roll back to"));
+    }
+    let dest = app_bundle_parent(process_path)?;
+    install(&app, &dest.join("Lapce.app"))?;
+    std::fs::remove_dir_all(&previous)?;
+    Ok(dest.join("Lapce.app"))
+}
+
+/// Restore the installation which was replaced by the last update. Returns
+/// the path to pass to `restart`.
+#[cfg(not(target_os = "macos"))]
+pub fn rollback(process_path: &Path) -> Result<PathBuf> {
+    let previous = previous_directory()?;
+    let bin = previous.join(if cfg!(target_os = "windows") {
+        "lapce.exe"
+    } else {
+        "lapce"
+    });
+    if !bin.exists() {
+        return Err(anyhow!("no previous version to roll back to"));
+    }
+    install(&bin, process_path)?;
+    std::fs::remove_dir_all(&previous)?;
+    Ok(process_path.to_path_buf())
+}
+
+#[cfg(target_os = "macos")]
+pub fn restart(path: &Path) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+    std::process::Command::new("open")
+        .arg("-n")
+        .arg(path)
+        .arg("--args")
+        .arg("-n")
+        .exec();
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
+pub fn restart(path: &Path) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+    std::process::Command::new(path).arg("-n").exec();
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn restart(path: &Path) -> Result<()> {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x00000008;
+    let process_id = std::process::id();
+    let path = path
+        .to_str()
+        .ok_or_else(|| anyhow!("can't get path to str"))?;
+    // The path is quoted, as portable installs often live in folders with
+    // spaces in their names. cmd doesn't understand the escaping `arg` would
+    // apply to those quotes, hence `raw_arg`.
+    std::process::Command::new("cmd")
+        .arg("/C")
+        .raw_arg(format!(
+            "taskkill /PID {} & start \"\" \"{}\" -n",
+            process_id, path
+        ))
+        .creation_flags(DETACHED_PROCESS)
+        .spawn()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        parse_checksum, parse_content_range, resolve_url, select_asset,
+        swap_into_place, verify_signature_with_key, ReleaseAsset,
+    };
+
+    const HASH: &str =
+        "3f786850e387550fdab836ed7e6dc881de23001b9c3a3ec4b4a8f5bd9d5bd29c";
+
+    const TEST_PUBLIC_KEY: &str =
+        "RWSkwdvHqRfosyYK0Hbo+K5YcyHivcSwzI7HcRkhecXeaosyJADdce1T";
+    const OTHER_PUBLIC_KEY: &str =
+        "RWTaekzKhmVsILS3P8csOolGfU66gri4xQ67PQCAluoqufJZ9fm4bH7L";
+    const TEST_ASSET: &[u8] = b"lapce release asset\n";
+    const TEST_SIGNATURE: &str = "untrusted comment: signature from lapce test key
+RWSkwdvHqRfos1/1D3XBxuKHqaQWJxrunEr8sjotF6eZwkl4fp4tiLaFEB/P8PLsl6C9S0Sfp8gTDrhNDxrFYJsbpW1RuHmHNQE=
+trusted comment: timestamp:1665792000\tfile:Lapce-linux.tar.gz
+hlrUPS480jeSj40PcDobik0fmxc7u0lbKExgZCLZjVMEJgQs3CLh8d6nDzPgoBT50702YG2m46zOOjdH6FZ2CA==
+";
+
+    #[test]
+    fn test_parse_bare_checksum() {
+        assert_eq!(
+            parse_checksum(&format!("{HASH}\n"), "Lapce-linux.tar.gz"),
+            Some(HASH.to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_sha256sum_checksum() {
+        let content = format!(
+            "{}  Lapce-macos.dmg\n{HASH} *Lapce-linux.tar.gz\n",
+            "0".repeat(64)
+        );
+        assert_eq!(
+            parse_checksum(&content, "Lapce-linux.tar.gz"),
+            Some(HASH.to_string())
+        );
+        assert_eq!(parse_checksum(&content, "Lapce-windows-portable.zip"), None);
+    }
+
+    #[test]
+    fn test_parse_invalid_checksum() {
+        assert_eq!(parse_checksum("not a hash", "Lapce-linux.tar.gz"), None);
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 1024-2047/4096"),
+            Some((1024, Some(4096)))
+        );
+        assert_eq!(parse_content_range("bytes 1024-2047/*"), Some((1024, None)));
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+        assert_eq!(parse_content_range("bytes */4096"), None);
+    }
+
+    fn assets(names: &[&str]) -> Vec<ReleaseAsset> {
+        names
+            .iter()
+            .map(|name| ReleaseAsset {
+                name: name.to_string(),
+                browser_download_url: format!("https://example.com/{name}"),
+            })
+            .collect()
+    }
+
+    fn selected(assets: &[ReleaseAsset], os: &str, arch: &str) -> Option<String> {
+        select_asset(assets, os, arch, false).map(|asset| asset.name.clone())
+    }
+
+    #[test]
+    fn test_select_asset_by_arch() {
+        let assets = assets(&[
+            "Lapce-linux-x86_64.tar.gz",
+            "Lapce-linux-aarch64.tar.gz",
+            "Lapce-windows-x86_64-portable.zip",
+        ]);
+        assert_eq!(
+            selected(&assets, "linux", "x86_64").as_deref(),
+            Some("Lapce-linux-x86_64.tar.gz")
+        );
+        assert_eq!(
+            selected(&assets, "linux", "aarch64").as_deref(),
+            Some("Lapce-linux-aarch64.tar.gz")
+        );
+        assert_eq!(selected(&assets, "windows", "aarch64"), None);
+    }
+
+    #[test]
+    fn test_select_legacy_asset() {
+        let assets = assets(&[
+            "Lapce-linux.tar.gz",
+            "Lapce-macos.dmg",
+            "Lapce-windows-portable.zip",
+        ]);
+        assert_eq!(
+            selected(&assets, "linux", "x86_64").as_deref(),
+            Some("Lapce-linux.tar.gz")
+        );
+        // The old linux builds were x86_64 only
+        assert_eq!(selected(&assets, "linux", "aarch64"), None);
+        assert_eq!(
+            selected(&assets, "macos", "aarch64").as_deref(),
+            Some("Lapce-macos.dmg")
+        );
+    }
+
+    #[test]
+    fn test_select_musl_asset() {
+        let mut assets = assets(&[
+            "Lapce-linux-x86_64.tar.gz",
+            "Lapce-linux.tar.gz",
+            "Lapce-linux-x86_64-musl.tar.gz",
+        ]);
+        assert_eq!(
+            select_asset(&assets, "linux", "x86_64", true).map(|a| a.name.as_str()),
+            Some("Lapce-linux-x86_64-musl.tar.gz")
+        );
+        // Never fall back to a glibc build
+        assets.pop();
+        assert!(select_asset(&assets, "linux", "x86_64", true).is_none());
+    }
+
+    #[test]
+    fn test_select_macos_universal_asset() {
+        let assets = assets(&[
+            "Lapce-macos-x86_64.dmg",
+            "Lapce-macos-arm64.dmg",
+            "Lapce-macos-universal.dmg",
+        ]);
+        assert_eq!(
+            selected(&assets, "macos", "aarch64").as_deref(),
+            Some("Lapce-macos-universal.dmg")
+        );
+        assert_eq!(
+            selected(&assets[..2], "macos", "aarch64").as_deref(),
+            Some("Lapce-macos-arm64.dmg")
+        );
+    }
+
+    #[test]
+    fn test_swap_into_place() {
+        let dir = std::env::temp_dir()
+            .join(format!("lapce-test-swap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("lapce");
+        let staged = dir.join("lapce.new");
+        std::fs::write(&target, "old").unwrap();
+        std::fs::write(&staged, "new").unwrap();
+
+        swap_into_place(&staged, &target).unwrap();
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+        assert!(!staged.exists());
+        assert!(!dir.join("lapce.old").exists());
+
+        // A failed swap keeps the current install
+        assert!(swap_into_place(&staged, &target).is_err());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let server = "https://mirror.example.com/lapce/releases";
+        assert_eq!(
+            resolve_url(server, "https://github.com/lapce/lapce/a.zip"),
+            "https://github.com/lapce/lapce/a.zip"
+        );
+        assert_eq!(
+            resolve_url(server, "/download/v0.2.0/Lapce-linux.tar.gz"),
+            "https://mirror.example.com/lapce/releases/download/v0.2.0/Lapce-linux.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_verify_good_signature() {
+        assert!(verify_signature_with_key(
+            TEST_PUBLIC_KEY,
+            TEST_ASSET,
+            TEST_SIGNATURE
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_verify_tampered_asset() {
+        assert!(verify_signature_with_key(
+            TEST_PUBLIC_KEY,
+            b"lapce release asset, tampered\n",
+            TEST_SIGNATURE
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_verify_wrong_key() {
+        assert!(verify_signature_with_key(
+            OTHER_PUBLIC_KEY,
+            TEST_ASSET,
+            TEST_SIGNATURE
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! When to check for new releases. The time of the last check is kept in the
+//! config directory, so that a daily check doesn't run on every launch.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use lapce_proxy::directory::Directory;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::config::Config;
+
+const LAST_CHECK_FILE: &str = "last-update-check";
+
+/// How long "Remind me later" hides the update notification.
+const REMIND_LATER: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum UpdateCheckFrequency {
+    /// Once every time Lapce starts
+    Startup,
+    #[default]
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl UpdateCheckFrequency {
+    pub fn from_config(config: &Config) -> Self {
+        config
+            .lapce
+            .update_check_frequency
+            .trim()
+            .to_lowercase()
+            .parse()
+            .unwrap_or_default()
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        match self {
+            UpdateCheckFrequency::Hourly => Some(Duration::from_secs(60 * 60)),
+            UpdateCheckFrequency::Daily => Some(Duration::from_secs(24 * 60 * 60)),
+            UpdateCheckFrequency::Startup | UpdateCheckFrequency::Never => None,
+        }
+    }
+}
+
+fn last_check_path() -> Option<PathBuf> {
+    Directory::config_directory().map(|dir| dir.join(LAST_CHECK_FILE))
+}
+
+/// When the last successful update check happened.
+pub fn last_check() -> Option<SystemTime> {
+    let content = std::fs::read_to_string(last_check_path()?).ok()?;
+    let secs = content.trim().parse().ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+pub fn record_check() {
+    if let (Some(path), Ok(now)) = (
+        last_check_path(),
+        SystemTime::now().duration_since(UNIX_EPOCH),
+    ) {
+        if let Err(err) = std::fs::write(path, now.as_secs().to_string()) {
+            log::error!("can't record update check time: {err}");
+        }
+    }
+}
+
+/// The user dismissed the update notification for a release, either for
+/// good ("Skip this version") or for a while ("Remind me later").
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateDismissal {
+    pub version: String,
+    /// Seconds since the unix epoch until which the notification is hidden,
+    /// or `None` when the version was skipped.
+    pub until: Option<u64>,
+}
+
+impl UpdateDismissal {
+    pub fn skip(version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            until: None,
+        }
+    }
+
+    pub fn remind_later(version: &str) -> Self {
+        let until = SystemTime::now() + REMIND_LATER;
+        Self {
+            version: version.to_string(),
+            until: until
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|until| until.as_secs()),
+        }
+    }
+
+    /// Whether the notification for `version` should be hidden at `now`.
+    /// A newer release is always notified about again.
+    pub fn hides(&self, version: &str, now: SystemTime) -> bool {
+        if self.version != version {
+            return false;
+        }
+        match self.until {
+            Some(until) => now < UNIX_EPOCH + Duration::from_secs(until),
+            None => true,
+        }
+    }
+}
+
+/// How long to wait until the next update check is due, or `None` if no
+/// more checks should happen. `checked` tells whether there was a check
+/// since Lapce started.
+pub fn time_until_check(
+    frequency: UpdateCheckFrequency,
+    last_check: Option<SystemTime>,
+    now: SystemTime,
+    checked: bool,
+) -> Option<Duration> {
+    match frequency {
+        UpdateCheckFrequency::Never => None,
+        UpdateCheckFrequency::Startup => (!checked).then(|| Duration::ZERO),
+        UpdateCheckFrequency::Hourly | UpdateCheckFrequency::Daily => {
+            let interval = frequency.interval()?;
+            let elapsed = match last_check {
+                Some(last_check) => {
+                    now.duration_since(last_check).unwrap_or_default()
+                }
+                None => return Some(Duration::ZERO),
+            };
+            Some(interval.saturating_sub(elapsed))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use super::{time_until_check, UpdateCheckFrequency, UpdateDismissal};
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    #[test]
+    fn test_time_until_check() {
+        let now = SystemTime::now();
+        assert_eq!(
+            time_until_check(UpdateCheckFrequency::Daily, None, now, false),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            time_until_check(
+                UpdateCheckFrequency::Daily,
+                Some(now - HOUR * 2),
+                now,
+                true
+            ),
+            Some(HOUR * 22)
+        );
+        assert_eq!(
+            time_until_check(
+                UpdateCheckFrequency::Hourly,
+                Some(now - HOUR * 2),
+                now,
+                true
+            ),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            time_until_check(UpdateCheckFrequency::Never, None, now, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_check_on_startup() {
+        let now = SystemTime::now();
+        assert_eq!(
+            time_until_check(UpdateCheckFrequency::Startup, Some(now), now, false),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            time_until_check(UpdateCheckFrequency::Startup, Some(now), now, true),
+            None
+        );
+    }
+
+    #[test]
+    fn test_update_dismissal() {
+        let now = SystemTime::now();
+        let skipped = UpdateDismissal::skip("0.2.1");
+        assert!(skipped.hides("0.2.1", now));
+        assert!(!skipped.hides("0.2.2", now));
+
+        let later = UpdateDismissal::remind_later("0.2.1");
+        assert!(later.hides("0.2.1", now));
+        assert!(!later.hides("0.2.1", now + HOUR * 25));
+    }
+}
@@ -0,0 +1,47 @@
+# Shell integration of the Lapce terminal, which marks the prompts and the
+# commands with OSC 633 sequences. Bash reads it instead of ~/.bashrc.
+
+if [ -f ~/.bashrc ]; then
+    . ~/.bashrc
+fi
+
+__lapce_escape() {
+    local value="$1"
+    value="${value//\\/\\\\}"
+    value="${value//;/\\x3b}"
+    value="${value//$'\n'/\\x0a}"
+    printf '%s' "$value"
+}
+
+# 0 while the prompt commands run, 1 at the prompt, and 2 while a command runs
+__lapce_state=0
+
+__lapce_precmd() {
+    local ret=$?
+    if [ "$__lapce_state" = 2 ]; then
+        printf '\e]633;D;%s\a' "$ret"
+    fi
+    printf '\e]633;P;Cwd=%s\a' "$(__lapce_escape "$PWD")"
+    __lapce_state=0
+    return $ret
+}
+
+__lapce_prompt() {
+    if [[ "$PS1" != *'633;B'* ]]; then
+        PS1="\[\e]633;A\a\]$PS1\[\e]633;B\a\]"
+    fi
+    __lapce_state=1
+}
+
+__lapce_preexec() {
+    if [ "$__lapce_state" = 1 ] && [ "$BASH_COMMAND" != "__lapce_precmd" ]; then
+        __lapce_state=2
+        local command
+        command="$(HISTTIMEFORMAT= builtin history 1)"
+        command="${command#*[0-9]  }"
+        printf '\e]633;E;%s\a\e]633;C\a' "$(__lapce_escape "$command")"
+    fi
+}
+
+PROMPT_COMMAND="__lapce_precmd${PROMPT_COMMAND:+; $PROMPT_COMMAND}; __lapce_prompt"
+trap '__lapce_preexec' DEBUG
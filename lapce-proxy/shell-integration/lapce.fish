@@ -0,0 +1,25 @@
+# Shell integration of the Lapce terminal, which marks the prompts and the
+# commands with OSC 633 sequences
+
+function __lapce_escape
+    string replace -a '\\' '\\\\' -- $argv \
+        | string replace -a ';' '\\x3b' \
+        | string join '\\x0a'
+end
+
+function __lapce_preexec --on-event fish_preexec
+    printf '\e]633;E;%s\a\e]633;C\a' (__lapce_escape $argv[1])
+end
+
+function __lapce_postexec --on-event fish_postexec
+    printf '\e]633;D;%s\a' $status
+end
+
+functions -c fish_prompt __lapce_user_prompt
+
+function fish_prompt
+    printf '\e]633;P;Cwd=%s\a' (__lapce_escape $PWD)
+    printf '\e]633;A\a'
+    __lapce_user_prompt
+    printf '\e]633;B\a'
+end
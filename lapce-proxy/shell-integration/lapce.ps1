@@ -0,0 +1,46 @@
+# Shell integration of the Lapce terminal, which marks the prompts and the
+# commands with OSC 633 sequences
+
+$Global:__LapceEsc = [char]27
+$Global:__LapceBel = [char]7
+$Global:__LapceRunning = $false
+$Global:__LapceUserPrompt = $function:Prompt
+
+function Global:__LapceEscape([string]$Value) {
+    $Value.Replace('\', '\\').Replace(';', '\x3b').Replace("`n", '\x0a').Replace("`r", '\x0d')
+}
+
+function Global:Prompt() {
+    $Success = $?
+    $ExitCode = $LASTEXITCODE
+    $Result = ""
+    if ($Global:__LapceRunning) {
+        $Global:__LapceRunning = $false
+        if ($Success) {
+            $ExitCode = 0
+        } elseif (-not $ExitCode) {
+            $ExitCode = 1
+        }
+        $Result += "$Global:__LapceEsc]633;D;$ExitCode$Global:__LapceBel"
+    }
+    $Cwd = __LapceEscape $PWD.ProviderPath
+    $Result += "$Global:__LapceEsc]633;P;Cwd=$Cwd$Global:__LapceBel"
+    $Result += "$Global:__LapceEsc]633;A$Global:__LapceBel"
+    $Result += $Global:__LapceUserPrompt.Invoke()
+    $Result += "$Global:__LapceEsc]633;B$Global:__LapceBel"
+    $Result
+}
+
+if (Get-Module -Name PSReadLine) {
+    Set-PSReadLineKeyHandler -Chord Enter -ScriptBlock {
+        $Line = $null
+        $Cursor = $null
+        [Microsoft.PowerShell.PSConsoleReadLine]::GetBufferState([ref]$Line, [ref]$Cursor)
+        if ($Line.Trim()) {
+            $Global:__LapceRunning = $true
+            [Console]::Write("$Global:__LapceEsc]633;E;$(__LapceEscape $Line)$Global:__LapceBel")
+            [Console]::Write("$Global:__LapceEsc]633;C$Global:__LapceBel")
+        }
+        [Microsoft.PowerShell.PSConsoleReadLine]::AcceptLine()
+    }
+}
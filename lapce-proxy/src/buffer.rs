@@ -1,4 +1,8 @@
 use anyhow::{anyhow, Result};
+use encoding_rs::{
+    Encoding, BIG5, EUC_JP, EUC_KR, GBK, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8,
+    WINDOWS_1252,
+};
 use lapce_core::buffer::rope_text::CharIndicesJoin;
 use lapce_core::encoding::offset_utf8_to_utf16;
 use lapce_rpc::buffer::BufferId;
@@ -9,7 +13,12 @@
 use std::io::{Read, Write};
 use std::path::PathBuf;
 use std::{borrow::Cow, path::Path, time::SystemTime};
-use xi_rope::{interval::IntervalBounds, rope::Rope, RopeDelta};
+use xi_rope::{
+    interval::IntervalBounds,
+    rope::{Rope, RopeInfo},
+    tree::TreeBuilder,
+    RopeDelta,
+};
 
 #[derive(Clone)]
 pub struct Buffer {
@@ -19,14 +28,47 @@ pub struct Buffer {
     pub path: PathBuf,
     pub rev: u64,
     pub mod_time: Option<SystemTime>,
+    /// The encoding of the file on disk, which the rope is decoded from and
+    /// encoded to when saving
+    pub encoding: &'static Encoding,
+    /// Whether the file starts with a byte order mark
+    pub bom: bool,
+    /// Whether the file is too large for the language servers, which aren't
+    /// told about it
+    pub large_file: bool,
+    /// Whether the file can't be written to
+    pub read_only: bool,
 }
 
 impl Buffer {
-    pub fn new(id: BufferId, path: PathBuf) -> Buffer {
-        let rope = Rope::from(load_file(&path).unwrap_or_default());
+    /// Load the file at `path`, as a large file if it's bigger than
+    /// `large_file_size` bytes, unless that's 0
+    pub fn new(id: BufferId, path: PathBuf, large_file_size: u64) -> Buffer {
+        let large_file = large_file_size > 0
+            && fs::metadata(&path)
+                .map(|metadata| metadata.len() > large_file_size)
+                .unwrap_or(false);
+        let file = if large_file {
+            load_file_chunked(&path, None)
+        } else {
+            load_file(&path, None).map(|file| DecodedRope {
+                rope: Rope::from(file.content),
+                encoding: file.encoding,
+                bom: file.bom,
+            })
+        }
+        .unwrap_or_else(|_| DecodedRope {
+            rope: Rope::from(""),
+            encoding: UTF_8,
+            bom: false,
+        });
+        let rope = file.rope;
         let rev = if rope.is_empty() { 0 } else { 1 };
         let language_id = language_id_from_path(&path).unwrap_or("");
         let mod_time = get_mod_time(&path);
+        let read_only = fs::metadata(&path)
+            .map(|metadata| metadata.permissions().readonly())
+            .unwrap_or(false);
         Buffer {
             id,
             rope,
@@ -34,9 +76,56 @@ pub fn new(id: BufferId, path: PathBuf) -> Buffer {
             language_id,
             rev,
             mod_time,
+            encoding: file.encoding,
+            bom: file.bom,
+            large_file,
+            read_only,
+        }
+    }
+
+    /// Decode the file again with the given encoding, which becomes the
+    /// encoding of the buffer. The rope is left as it is, because the new
+    /// content is sent as an update from the editor.
+    pub fn reopen_with_encoding(
+        &mut self,
+        encoding: &'static Encoding,
+    ) -> Result<String> {
+        let file = load_file(&self.path, Some(encoding))?;
+        self.encoding = file.encoding;
+        self.bom = file.bom;
+        Ok(file.content)
+    }
+
+    /// Set the encoding the buffer is saved with
+    pub fn set_encoding(&mut self, encoding: &'static Encoding) {
+        if encoding != self.encoding {
+            self.encoding = encoding;
+            // UTF-16 can't be told apart without the byte order mark
+            self.bom = encoding == UTF_16LE || encoding == UTF_16BE;
         }
     }
 
+    /// The chunk of the text from `offset`, of at most
+    /// `LARGE_FILE_CHUNK_SIZE` bytes, and whether it's the last one. The
+    /// core loads large files a chunk at a time, so that opening them
+    /// doesn't send the whole file at once.
+    pub fn chunk(&self, offset: usize) -> (String, bool) {
+        let len = self.rope.len();
+        let start = offset.min(len);
+        let end = self
+            .rope
+            .at_or_next_codepoint_boundary((start + LARGE_FILE_CHUNK_SIZE).min(len))
+            .unwrap_or(len);
+        (self.rope.slice_to_cow(start..end).into_owned(), end >= len)
+    }
+
+    /// Whether the file was modified on disk since it was last loaded or
+    /// saved, or since the core was told about the modification
+    pub fn is_changed_on_disk(&self) -> bool {
+        let mod_time = get_mod_time(&self.path);
+        mod_time.is_some() && mod_time != self.mod_time
+    }
+
     pub fn save(&mut self, rev: u64) -> Result<()> {
         if self.rev != rev {
             return Err(anyhow!("not the right rev"));
@@ -51,9 +140,24 @@ pub fn save(&mut self, rev: u64) -> Result<()> {
         );
         let tmp_path = &self.path.with_extension(tmp_extension);
 
+        // Encode before creating the file, so that a failure leaves nothing
+        // behind
+        let encoded = if self.encoding == UTF_8 {
+            None
+        } else {
+            Some(encode(&self.rope.to_string(), self.encoding, self.bom)?)
+        };
+
         let mut f = File::create(tmp_path)?;
-        for chunk in self.rope.iter_chunks(..self.rope.len()) {
-            f.write_all(chunk.as_bytes())?;
+        if let Some(bytes) = encoded {
+            f.write_all(&bytes)?;
+        } else {
+            if self.bom {
+                f.write_all(BOM_UTF_8)?;
+            }
+            for chunk in self.rope.iter_chunks(..self.rope.len()) {
+                f.write_all(chunk.as_bytes())?;
+            }
         }
 
         if let Ok(metadata) = fs::metadata(&self.path) {
@@ -75,6 +179,11 @@ pub fn update(
             return None;
         }
         self.rev += 1;
+        if self.large_file {
+            // There's no language server to send the change to
+            self.rope = delta.apply(&self.rope);
+            return None;
+        }
         let content_change = get_document_content_changes(delta, self);
         self.rope = delta.apply(&self.rope);
         Some(
@@ -140,25 +249,213 @@ pub fn is_empty(&self) -> bool {
     }
 }
 
-pub fn load_file(path: &Path) -> Result<String> {
-    Ok(read_path_to_string_lossy(path)?)
+const BOM_UTF_8: &[u8] = b"\xEF\xBB\xBF";
+
+/// The legacy multi-byte encodings that a file that isn't valid UTF-8 is
+/// checked against, before falling back to Windows-1252
+const LEGACY_ENCODINGS: &[&Encoding] = &[SHIFT_JIS, EUC_JP, GBK, BIG5, EUC_KR];
+
+pub struct DecodedFile {
+    pub content: String,
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+}
+
+/// Read the file and decode it with `encoding`, or with the encoding that's
+/// detected from its content if it's `None`
+pub fn load_file(
+    path: &Path,
+    encoding: Option<&'static Encoding>,
+) -> Result<DecodedFile> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    Ok(decode(&bytes, encoding))
 }
 
-pub fn read_path_to_string_lossy<P: AsRef<Path>>(
-    path: P,
-) -> Result<String, std::io::Error> {
-    let path = path.as_ref();
+pub fn decode(bytes: &[u8], encoding: Option<&'static Encoding>) -> DecodedFile {
+    let (encoding, bom_len) = encoding_of(bytes, encoding);
 
+    // Malformed sequences are replaced with the replacement character
+    let (content, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+    DecodedFile {
+        content: content.into_owned(),
+        encoding,
+        bom: bom_len > 0,
+    }
+}
+
+pub struct DecodedRope {
+    pub rope: Rope,
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+}
+
+/// How many bytes of a large file are read at a time, and sent to the core
+/// at a time
+const LARGE_FILE_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Like `load_file`, but the file is read and decoded one chunk at a time
+/// straight into the rope, so that the whole file is never held as a single
+/// string. The encoding is detected from the first chunk.
+pub fn load_file_chunked(
+    path: &Path,
+    encoding: Option<&'static Encoding>,
+) -> Result<DecodedRope> {
     let mut file = File::open(path)?;
-    // Read the file in as bytes
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
+    let mut bytes = vec![0; LARGE_FILE_CHUNK_SIZE];
+    let mut len = read_chunk(&mut file, &mut bytes)?;
+    let (encoding, bom_len) = encoding_of(&bytes[..len], encoding);
 
-    // Parse the file contents as utf8, replacing non-utf8 data with the
-    // replacement character
-    let contents = String::from_utf8_lossy(&buffer);
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut builder = TreeBuilder::<RopeInfo>::new();
+    let mut text = String::new();
+    let mut start = bom_len;
+    loop {
+        let last = len == 0;
+        text.clear();
+        text.reserve(
+            decoder
+                .max_utf8_buffer_length(len - start)
+                .unwrap_or(LARGE_FILE_CHUNK_SIZE * 3),
+        );
+        // Malformed sequences are replaced with the replacement character,
+        // and a sequence split across chunks is kept by the decoder
+        let _ = decoder.decode_to_string(&bytes[start..len], &mut text, last);
+        builder.push_str(&text);
+        if last {
+            break;
+        }
+        len = read_chunk(&mut file, &mut bytes)?;
+        start = 0;
+    }
 
-    Ok(contents.to_string())
+    Ok(DecodedRope {
+        rope: builder.build(),
+        encoding,
+        bom: bom_len > 0,
+    })
+}
+
+/// Fill the buffer as far as the file allows, returning how many bytes were
+/// read, which is only less than the buffer's length at the end of the file
+fn read_chunk(file: &mut File, bytes: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < bytes.len() {
+        match file.read(&mut bytes[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    Ok(len)
+}
+
+/// The encoding of the bytes and the length of their byte order mark
+fn encoding_of(
+    bytes: &[u8],
+    encoding: Option<&'static Encoding>,
+) -> (&'static Encoding, usize) {
+    let bom = Encoding::for_bom(bytes);
+    match (encoding, bom) {
+        (Some(encoding), Some((bom_encoding, bom_len)))
+            if encoding == bom_encoding =>
+        {
+            (encoding, bom_len)
+        }
+        (Some(encoding), _) => (encoding, 0),
+        (None, Some((bom_encoding, bom_len))) => (bom_encoding, bom_len),
+        (None, None) => (detect_encoding(bytes), 0),
+    }
+}
+
+fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return UTF_8,
+        // The bytes can be the start of a file, cut in the middle of a
+        // character
+        Err(e) if e.error_len().is_none() && bytes.len() - e.valid_up_to() < 4 => {
+            return UTF_8;
+        }
+        Err(_) => {}
+    }
+
+    LEGACY_ENCODINGS
+        .iter()
+        .filter_map(|encoding| {
+            let (content, had_errors) = encoding.decode_without_bom_handling(bytes);
+            if had_errors {
+                return None;
+            }
+            let mut non_ascii = 0;
+            let mut score = 0;
+            for c in content.chars().filter(|c| !c.is_ascii()) {
+                non_ascii += 1;
+                score += script_score(encoding, c);
+            }
+            // Text in a single-byte encoding can often be decoded as a
+            // multi-byte one too, but not into characters of its scripts
+            if score >= non_ascii {
+                Some((*encoding, score))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|(_, score)| *score)
+        .map(|(encoding, _)| encoding)
+        .unwrap_or(WINDOWS_1252)
+}
+
+/// How likely it is that the character is in text in the encoding
+fn script_score(encoding: &'static Encoding, c: char) -> usize {
+    match c {
+        // Hiragana and Katakana
+        '\u{3040}'..='\u{30FF}' if encoding == SHIFT_JIS || encoding == EUC_JP => 2,
+        // Hangul syllables
+        '\u{AC00}'..='\u{D7AF}' if encoding == EUC_KR => 2,
+        // CJK unified ideographs and the CJK punctuation
+        '\u{4E00}'..='\u{9FFF}' | '\u{3000}'..='\u{303F}' => 1,
+        _ => 0,
+    }
+}
+
+pub fn encoding_for_name(name: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(name.as_bytes())
+        .ok_or_else(|| anyhow!("unknown encoding {name}"))
+}
+
+/// Encode the text, failing if it has characters that can't be
+/// represented in the encoding
+pub fn encode(
+    text: &str,
+    encoding: &'static Encoding,
+    bom: bool,
+) -> Result<Vec<u8>> {
+    // encoding_rs only decodes UTF-16, following the Encoding Standard
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let mut bytes = Vec::with_capacity(text.len() * 2 + 2);
+        let units = bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+        for unit in units {
+            if encoding == UTF_16LE {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        return Ok(bytes);
+    }
+
+    let (bytes, _, had_unmappable) = encoding.encode(text);
+    if had_unmappable {
+        return Err(anyhow!(
+            "the file has characters that can't be saved in {}",
+            encoding.name()
+        ));
+    }
+    let mut result = Vec::with_capacity(bytes.len() + 3);
+    if bom && encoding == UTF_8 {
+        result.extend_from_slice(BOM_UTF_8);
+    }
+    result.extend_from_slice(&bytes);
+    Ok(result)
 }
 
 pub fn language_id_from_path(path: &Path) -> Option<&'static str> {
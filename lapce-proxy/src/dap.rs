@@ -0,0 +1,709 @@
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use crossbeam_channel::{Receiver, Sender};
+use lapce_rpc::{
+    core::CoreRpcHandler,
+    dap_types::{
+        Breakpoint, Capabilities, CompletionTarget, DapId, DataBreakpoint,
+        DataBreakpointInfo, DebugRequest, DebuggerConfig, EvaluateContext,
+        EvaluateResult, ProcessInfo, ReadMemoryResult, RunDebugConfig, Scope,
+        SourceBreakpoint, StackFrame, Stopped, Thread, ThreadId, Variable,
+    },
+};
+use lsp_types::notification::Notification;
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::plugin::lsp::read_message;
+
+/// How long a request waits for the adapter to respond
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The notification a plugin registers the debug adapter of a type with
+pub enum RegisterDebugger {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterDebuggerParams {
+    /// The type of the debug configurations the adapter debugs
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(flatten)]
+    pub debugger: DebuggerConfig,
+}
+
+impl Notification for RegisterDebugger {
+    type Params = RegisterDebuggerParams;
+    const METHOD: &'static str = "lapce/registerDebugger";
+}
+
+#[derive(Deserialize)]
+struct ThreadsResponse {
+    threads: Vec<Thread>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StackTraceResponse {
+    stack_frames: Vec<StackFrame>,
+}
+
+#[derive(Deserialize)]
+struct ScopesResponse {
+    scopes: Vec<Scope>,
+}
+
+#[derive(Deserialize)]
+struct VariablesResponse {
+    variables: Vec<Variable>,
+}
+
+#[derive(Deserialize)]
+struct CompletionsResponse {
+    targets: Vec<CompletionTarget>,
+}
+
+#[derive(Deserialize)]
+struct SetBreakpointsResponse {
+    breakpoints: Vec<Breakpoint>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadMemoryResponse {
+    address: String,
+    /// The bytes in base64, which are missing if none could be read
+    data: Option<String>,
+}
+
+/// The processes running on this host, which a debugger can attach to,
+/// sorted by name
+pub fn list_processes() -> Result<Vec<ProcessInfo>> {
+    #[cfg(not(target_os = "windows"))]
+    let mut processes = {
+        let output = Command::new("ps")
+            .args(["-axww", "-o", "pid=,args="])
+            .output()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (pid, command) = line.trim_start().split_once(' ')?;
+                let command = command.trim().to_string();
+                let program = command.split_whitespace().next()?;
+                let name = Path::new(program)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| program.to_string());
+                Some(ProcessInfo {
+                    pid: pid.parse().ok()?,
+                    name,
+                    command,
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    #[cfg(target_os = "windows")]
+    let mut processes = {
+        let output = Command::new("tasklist")
+            .args(["/fo", "csv", "/nh"])
+            .creation_flags(0x08000000)
+            .output()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                // "name.exe","1234","Console","1","10,000 K"
+                let mut fields = line.trim().trim_matches('"').split("\",\"");
+                let name = fields.next()?.to_string();
+                Some(ProcessInfo {
+                    pid: fields.next()?.parse().ok()?,
+                    command: name.clone(),
+                    name,
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    processes.sort_by(|a, b| {
+        a.name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then(a.pid.cmp(&b.pid))
+    });
+    Ok(processes)
+}
+
+/// The handle to a debug adapter, which talks the debug adapter protocol
+/// with it over its stdio
+#[derive(Clone)]
+pub struct DapRpcHandler {
+    dap_id: DapId,
+    core_rpc: CoreRpcHandler,
+    io_tx: Sender<Value>,
+    seq: Arc<AtomicU64>,
+    #[allow(clippy::type_complexity)]
+    pending: Arc<Mutex<HashMap<u64, Sender<Result<Value>>>>>,
+    process: Arc<Mutex<Option<Child>>>,
+    terminated: Arc<AtomicBool>,
+}
+
+impl DapRpcHandler {
+    /// Start the adapter, and have it launch or attach to the program of the
+    /// configuration, which stops at the breakpoints
+    pub fn start(
+        core_rpc: CoreRpcHandler,
+        dap_id: DapId,
+        debugger: DebuggerConfig,
+        config: RunDebugConfig,
+        breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+        workspace: Option<PathBuf>,
+    ) -> Result<Self> {
+        let mut process = Command::new(&debugger.program);
+        if let Some(workspace) = workspace.as_ref() {
+            process.current_dir(workspace);
+        }
+        process.args(&debugger.args);
+
+        #[cfg(target_os = "windows")]
+        let process = process.creation_flags(0x08000000);
+        let mut child = process
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("can't start {}: {e}", debugger.program))?;
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("no stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("no stdout"))?;
+        let stderr = child.stderr.take();
+
+        let (io_tx, io_rx) = crossbeam_channel::unbounded::<Value>();
+        let dap = Self {
+            dap_id,
+            core_rpc: core_rpc.clone(),
+            io_tx,
+            seq: Arc::new(AtomicU64::new(1)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            process: Arc::new(Mutex::new(Some(child))),
+            terminated: Arc::new(AtomicBool::new(false)),
+        };
+
+        thread::spawn(move || {
+            let mut writer = BufWriter::new(stdin);
+            for msg in io_rx {
+                let msg = msg.to_string();
+                let msg = format!("Content-Length: {}\r\n\r\n{}", msg.len(), msg);
+                if writer
+                    .write_all(msg.as_bytes())
+                    .and_then(|_| writer.flush())
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+
+        let (event_tx, event_rx) = crossbeam_channel::unbounded();
+        let local_dap = dap.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            while let Ok(message) = read_message(&mut reader) {
+                if let Ok(message) = serde_json::from_str::<Value>(&message) {
+                    local_dap.handle_message(message, &event_tx);
+                }
+            }
+            // The adapter exited, so the requests waiting for it fail
+            local_dap.pending.lock().clear();
+            let _ = event_tx.send(("terminated".to_string(), Value::Null));
+        });
+
+        if let Some(stderr) = stderr {
+            let core_rpc = core_rpc.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stderr);
+                let mut line = String::new();
+                while let Ok(n) = reader.read_line(&mut line) {
+                    if n == 0 {
+                        return;
+                    }
+                    core_rpc.log(
+                        log::Level::Error,
+                        format!("debug adapter stderr: {}", line.trim_end()),
+                    );
+                    line.clear();
+                }
+            });
+        }
+
+        let local_dap = dap.clone();
+        thread::spawn(move || {
+            local_dap.handle_events(event_rx, breakpoints);
+        });
+
+        let local_dap = dap.clone();
+        thread::spawn(move || {
+            if let Err(e) = local_dap.launch(&config, workspace.as_deref()) {
+                core_rpc.log(
+                    log::Level::Error,
+                    format!("can't debug {}: {e}", config.name),
+                );
+                local_dap.stop();
+            }
+        });
+
+        Ok(dap)
+    }
+
+    fn handle_message(&self, message: Value, event_tx: &Sender<(String, Value)>) {
+        let body = message.get("body").cloned().unwrap_or(Value::Null);
+        match message.get("type").and_then(|t| t.as_str()) {
+            Some("response") => {
+                let tx = message
+                    .get("request_seq")
+                    .and_then(|seq| seq.as_u64())
+                    .and_then(|seq| self.pending.lock().remove(&seq));
+                if let Some(tx) = tx {
+                    let success = message
+                        .get("success")
+                        .and_then(|success| success.as_bool())
+                        .unwrap_or(false);
+                    let result = if success {
+                        Ok(body)
+                    } else {
+                        Err(anyhow!(
+                            "{}",
+                            message
+                                .get("message")
+                                .and_then(|m| m.as_str())
+                                .unwrap_or("request failed")
+                        ))
+                    };
+                    let _ = tx.send(result);
+                }
+            }
+            Some("event") => {
+                if let Some(event) = message.get("event").and_then(|e| e.as_str()) {
+                    let _ = event_tx.send((event.to_string(), body));
+                }
+            }
+            Some("request") => {
+                // The reverse requests, like runInTerminal, aren't supported
+                let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+                let _ = self.io_tx.send(json!({
+                    "seq": seq,
+                    "type": "response",
+                    "request_seq": message.get("seq").cloned().unwrap_or(Value::Null),
+                    "command": message.get("command").cloned().unwrap_or(Value::Null),
+                    "success": false,
+                    "message": "not supported",
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_events(
+        &self,
+        event_rx: Receiver<(String, Value)>,
+        mut breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+    ) {
+        for (event, body) in event_rx {
+            match event.as_str() {
+                "initialized" => {
+                    for (path, breakpoints) in breakpoints.drain() {
+                        if let Err(e) = self.set_breakpoints(path, breakpoints) {
+                            self.log_error("setBreakpoints", e);
+                        }
+                    }
+                    if let Err(e) =
+                        self.request::<Value>("configurationDone", Value::Null)
+                    {
+                        self.log_error("configurationDone", e);
+                    }
+                }
+                "stopped" => {
+                    if let Ok(stopped) = serde_json::from_value::<Stopped>(body) {
+                        if let Err(e) = self.stopped(stopped) {
+                            self.log_error("stopped", e);
+                        }
+                    }
+                }
+                "continued" => {
+                    self.core_rpc.dap_continued(self.dap_id);
+                }
+                "output" => {
+                    let category = body
+                        .get("category")
+                        .and_then(|category| category.as_str())
+                        .map(|category| category.to_string());
+                    if category.as_deref() == Some("telemetry") {
+                        continue;
+                    }
+                    if let Some(output) =
+                        body.get("output").and_then(|output| output.as_str())
+                    {
+                        self.core_rpc.dap_output(
+                            self.dap_id,
+                            category,
+                            output.to_string(),
+                        );
+                    }
+                }
+                "terminated" => {
+                    self.stop();
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Get the stack of the thread that stopped, with the variables of its
+    /// top frame, for the editor to show
+    fn stopped(&self, stopped: Stopped) -> Result<()> {
+        let threads = self
+            .request::<ThreadsResponse>("threads", Value::Null)?
+            .threads;
+        let thread_id = match stopped.thread_id {
+            Some(thread_id) => thread_id,
+            None => threads
+                .first()
+                .map(|thread| thread.id)
+                .ok_or_else(|| anyhow!("no threads"))?,
+        };
+        let stack_frames = self.stack_trace(thread_id)?;
+        let variables = match stack_frames.first() {
+            Some(frame) => self.scopes(frame.id)?,
+            None => Vec::new(),
+        };
+
+        self.core_rpc.dap_stopped(
+            self.dap_id,
+            Stopped {
+                thread_id: Some(thread_id),
+                ..stopped
+            },
+            threads,
+            stack_frames,
+            variables,
+        );
+        Ok(())
+    }
+
+    fn launch(
+        &self,
+        config: &RunDebugConfig,
+        workspace: Option<&Path>,
+    ) -> Result<()> {
+        let capabilities = self.request::<Option<Capabilities>>(
+            "initialize",
+            json!({
+                "clientID": "lapce",
+                "clientName": "Lapce",
+                "adapterID": config.ty,
+                "pathFormat": "path",
+                "linesStartAt1": true,
+                "columnsStartAt1": true,
+                "supportsVariableType": true,
+                "supportsRunInTerminalRequest": false,
+                "supportsMemoryReferences": true,
+            }),
+        )?;
+        self.core_rpc
+            .dap_capabilities(self.dap_id, capabilities.unwrap_or_default());
+
+        let program = config.program.as_ref().map(|program| match workspace {
+            Some(workspace) if Path::new(program).is_relative() => {
+                workspace.join(program)
+            }
+            _ => PathBuf::from(program),
+        });
+        let cwd = config
+            .cwd
+            .as_ref()
+            .map(|cwd| match workspace {
+                Some(workspace) => workspace.join(cwd),
+                None => cwd.clone(),
+            })
+            .or_else(|| workspace.map(|workspace| workspace.to_path_buf()));
+        let request = config.request.as_str();
+        let mut arguments = json!({
+            "name": config.name,
+            "type": config.ty,
+            "request": request,
+            "cwd": cwd,
+        });
+        if let Some(arguments) = arguments.as_object_mut() {
+            if let Some(program) = program {
+                arguments.insert("program".to_string(), json!(program));
+            }
+            if config.request == DebugRequest::Launch {
+                arguments.insert("args".to_string(), json!(config.args));
+                arguments.insert("env".to_string(), json!(config.env));
+            }
+            if let Some(Value::Object(extra)) = config.arguments.clone() {
+                arguments.extend(extra);
+            }
+        }
+
+        // The adapters answer the launch or attach request after the
+        // configuration is done, which is on the event thread, so it's not
+        // waited for
+        let dap = self.clone();
+        let name = config.name.clone();
+        thread::spawn(move || {
+            if let Err(e) = dap.request::<Value>(request, arguments) {
+                dap.core_rpc
+                    .log(log::Level::Error, format!("can't {request} {name}: {e}"));
+                dap.stop();
+            }
+        });
+        Ok(())
+    }
+
+    /// Send the request, and wait for the adapter to respond to it
+    fn request<T: DeserializeOwned>(
+        &self,
+        command: &str,
+        arguments: Value,
+    ) -> Result<T> {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        self.pending.lock().insert(seq, tx);
+        let mut request = json!({
+            "seq": seq,
+            "type": "request",
+            "command": command,
+        });
+        if !arguments.is_null() {
+            request["arguments"] = arguments;
+        }
+        self.io_tx
+            .send(request)
+            .map_err(|_| anyhow!("the debug adapter stopped"))?;
+        let body = rx.recv_timeout(REQUEST_TIMEOUT).map_err(|_| {
+            self.pending.lock().remove(&seq);
+            anyhow!("no response to {command}")
+        })??;
+        Ok(serde_json::from_value(body)?)
+    }
+
+    /// Send the request on another thread, and log it if it fails
+    fn request_async(&self, command: &'static str, arguments: Value) {
+        let dap = self.clone();
+        thread::spawn(move || {
+            if let Err(e) = dap.request::<Value>(command, arguments) {
+                dap.log_error(command, e);
+            }
+        });
+    }
+
+    fn log_error(&self, command: &str, e: anyhow::Error) {
+        self.core_rpc
+            .log(log::Level::Error, format!("debug adapter {command}: {e}"));
+    }
+
+    /// Set the breakpoints of the file, replacing the ones it had, and tell
+    /// the editor which of them the adapter could set
+    pub fn set_breakpoints(
+        &self,
+        path: PathBuf,
+        breakpoints: Vec<SourceBreakpoint>,
+    ) -> Result<()> {
+        let response = self.request::<SetBreakpointsResponse>(
+            "setBreakpoints",
+            json!({
+                "source": { "path": path },
+                "breakpoints": breakpoints,
+            }),
+        )?;
+        self.core_rpc
+            .dap_breakpoints(self.dap_id, path, response.breakpoints);
+        Ok(())
+    }
+
+    /// Evaluate the expression in the frame, or globally without one
+    pub fn evaluate(
+        &self,
+        expression: &str,
+        frame_id: Option<usize>,
+        context: EvaluateContext,
+    ) -> Result<EvaluateResult> {
+        let mut arguments = json!({
+            "expression": expression,
+            "context": context,
+        });
+        if let Some(frame_id) = frame_id {
+            arguments["frameId"] = json!(frame_id);
+        }
+        self.request::<EvaluateResult>("evaluate", arguments)
+    }
+
+    /// The completions of the text at the column, in the frame
+    pub fn completions(
+        &self,
+        text: &str,
+        column: usize,
+        frame_id: Option<usize>,
+    ) -> Result<Vec<CompletionTarget>> {
+        let mut arguments = json!({
+            "text": text,
+            "column": column,
+        });
+        if let Some(frame_id) = frame_id {
+            arguments["frameId"] = json!(frame_id);
+        }
+        Ok(self
+            .request::<CompletionsResponse>("completions", arguments)?
+            .targets)
+    }
+
+    /// The stack of the thread, from the top frame
+    pub fn stack_trace(&self, thread_id: ThreadId) -> Result<Vec<StackFrame>> {
+        Ok(self
+            .request::<StackTraceResponse>(
+                "stackTrace",
+                json!({ "threadId": thread_id }),
+            )?
+            .stack_frames)
+    }
+
+    /// The variables of the frame by scope, without the ones of the scopes
+    /// that are expensive to get
+    pub fn scopes(&self, frame_id: usize) -> Result<Vec<(Scope, Vec<Variable>)>> {
+        let scopes = self
+            .request::<ScopesResponse>("scopes", json!({ "frameId": frame_id }))?
+            .scopes;
+        let mut variables = Vec::new();
+        for scope in scopes {
+            let scope_variables = if scope.expensive {
+                Vec::new()
+            } else {
+                self.variables(scope.variables_reference)?
+            };
+            variables.push((scope, scope_variables));
+        }
+        Ok(variables)
+    }
+
+    /// The children of the variable or the value the reference is of
+    pub fn variables(&self, variables_reference: usize) -> Result<Vec<Variable>> {
+        Ok(self
+            .request::<VariablesResponse>(
+                "variables",
+                json!({ "variablesReference": variables_reference }),
+            )?
+            .variables)
+    }
+
+    /// Read the bytes of the memory from the offset of the reference, which
+    /// can be fewer than asked for if some couldn't be read
+    pub fn read_memory(
+        &self,
+        memory_reference: &str,
+        offset: i64,
+        count: usize,
+    ) -> Result<ReadMemoryResult> {
+        let response = self.request::<ReadMemoryResponse>(
+            "readMemory",
+            json!({
+                "memoryReference": memory_reference,
+                "offset": offset,
+                "count": count,
+            }),
+        )?;
+        let address = response.address.trim_start_matches("0x");
+        let address = u64::from_str_radix(address, 16)
+            .map_err(|_| anyhow!("invalid address {}", response.address))?;
+        let data = match response.data {
+            Some(data) => base64::decode(data)?,
+            None => Vec::new(),
+        };
+        Ok(ReadMemoryResult { address, data })
+    }
+
+    /// Whether a data breakpoint can be set on the variable, which is a child
+    /// of the reference
+    pub fn data_breakpoint_info(
+        &self,
+        variables_reference: usize,
+        name: &str,
+    ) -> Result<DataBreakpointInfo> {
+        self.request::<DataBreakpointInfo>(
+            "dataBreakpointInfo",
+            json!({
+                "variablesReference": variables_reference,
+                "name": name,
+            }),
+        )
+    }
+
+    pub fn set_data_breakpoints(&self, breakpoints: Vec<DataBreakpoint>) {
+        self.request_async(
+            "setDataBreakpoints",
+            json!({ "breakpoints": breakpoints }),
+        );
+    }
+
+    pub fn continue_thread(&self, thread_id: ThreadId) {
+        self.request_async("continue", json!({ "threadId": thread_id }));
+    }
+
+    pub fn pause(&self, thread_id: ThreadId) {
+        self.request_async("pause", json!({ "threadId": thread_id }));
+    }
+
+    pub fn step_over(&self, thread_id: ThreadId) {
+        self.request_async("next", json!({ "threadId": thread_id }));
+    }
+
+    pub fn step_into(&self, thread_id: ThreadId) {
+        self.request_async("stepIn", json!({ "threadId": thread_id }));
+    }
+
+    pub fn step_out(&self, thread_id: ThreadId) {
+        self.request_async("stepOut", json!({ "threadId": thread_id }));
+    }
+
+    pub fn restart_frame(&self, frame_id: usize) {
+        self.request_async("restartFrame", json!({ "frameId": frame_id }));
+    }
+
+    /// Disconnect from the adapter, which ends the debugged program, and
+    /// stop it
+    pub fn stop(&self) {
+        if self.terminated.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let running = self
+            .process
+            .lock()
+            .as_mut()
+            .map(|process| matches!(process.try_wait(), Ok(None)))
+            .unwrap_or(false);
+        if running {
+            let _ = self.request::<Value>(
+                "disconnect",
+                json!({ "terminateDebuggee": true }),
+            );
+        }
+        if let Some(mut process) = self.process.lock().take() {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+        self.core_rpc.dap_terminated(self.dap_id);
+    }
+}
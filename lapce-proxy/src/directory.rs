@@ -4,6 +4,9 @@
 
 use crate::APPLICATION_NAME;
 
+/// The file next to the executable which turns on portable mode
+const PORTABLE_MARKER: &str = "portable";
+
 pub struct Directory {}
 
 impl Directory {
@@ -11,11 +14,36 @@ fn project_dirs() -> Option<ProjectDirs> {
         ProjectDirs::from("dev", "lapce", *APPLICATION_NAME)
     }
 
+    /// In portable mode, which is turned on by a `portable` file next to
+    /// lapce.exe, settings, plugins and updates are all kept in the
+    /// application folder instead of the user profile
+    pub fn portable_directory() -> Option<PathBuf> {
+        if !cfg!(target_os = "windows") {
+            return None;
+        }
+        let exe = std::env::current_exe().ok()?;
+        let dir = exe.parent()?;
+        if dir.join(PORTABLE_MARKER).exists() {
+            Some(dir.to_path_buf())
+        } else {
+            None
+        }
+    }
+
     // Get path of local data directory
     // Local data directory differs from data directory
     // on some platforms and is not transferred across
     // machines
     pub fn data_local_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::portable_directory() {
+            let dir = dir.join("data");
+            if !dir.exists() {
+                let _ = std::fs::create_dir_all(&dir);
+            }
+
+            return Some(dir);
+        }
+
         match Self::project_dirs() {
             Some(dir) => {
                 let dir = dir.data_local_dir();
@@ -93,6 +121,15 @@ pub fn plugins_directory() -> Option<PathBuf> {
 
     // Config directory contain only configuration files
     pub fn config_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::portable_directory() {
+            let dir = dir.join("config");
+            if !dir.exists() {
+                let _ = std::fs::create_dir_all(&dir);
+            }
+
+            return Some(dir);
+        }
+
         match Self::project_dirs() {
             Some(dir) => {
                 let dir = dir.config_dir();
@@ -106,10 +143,40 @@ pub fn config_directory() -> Option<PathBuf> {
         }
     }
 
+    /// Get the path to the folder of the user's snippets, which has a JSON
+    /// file of snippets for each language
+    pub fn snippets_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::config_directory() {
+            let dir = dir.join("snippets");
+            if !dir.exists() {
+                let _ = std::fs::create_dir(&dir);
+            }
+
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
     pub fn local_socket() -> Option<PathBuf> {
         Self::data_local_directory().map(|dir| dir.join("local.sock"))
     }
 
+    /// Directory to store the scripts that the shells of the terminal are
+    /// started with, to mark their prompts and commands
+    pub fn shell_integration_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("shell-integration");
+            if !dir.exists() {
+                let _ = std::fs::create_dir(&dir);
+            }
+
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
     pub fn updates_directory() -> Option<PathBuf> {
         if let Some(dir) = Self::data_local_directory() {
             let dir = dir.join("updates");
@@ -122,4 +189,19 @@ pub fn updates_directory() -> Option<PathBuf> {
             None
         }
     }
+
+    /// Directory to store the undo history of saved files, so that it's
+    /// still there after restarting
+    pub fn undo_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::config_directory() {
+            let dir = dir.join("undo");
+            if !dir.exists() {
+                let _ = std::fs::create_dir(&dir);
+            }
+
+            Some(dir)
+        } else {
+            None
+        }
+    }
 }
@@ -1,7 +1,9 @@
-use crate::buffer::{get_mod_time, load_file, Buffer};
+use crate::buffer::{encoding_for_name, get_mod_time, load_file, Buffer};
+use crate::dap::{list_processes, DapRpcHandler};
+use crate::format::{edits_in_range, format_with_command};
 use crate::plugin::catalog::PluginCatalog;
 use crate::plugin::{remove_volt, PluginCatalogRpcHandler};
-use crate::terminal::Terminal;
+use crate::terminal::{kill_tmux_session, Terminal};
 use crate::watcher::{FileWatcher, Notify, WatchToken};
 use alacritty_terminal::event_loop::Msg;
 use alacritty_terminal::term::SizeInfo;
@@ -14,9 +16,11 @@
 use grep_searcher::sinks::UTF8;
 use grep_searcher::SearcherBuilder;
 use lapce_rpc::core::{CoreNotification, CoreRpcHandler};
+use lapce_rpc::dap_types::DapId;
 use lapce_rpc::file::FileNodeItem;
 use lapce_rpc::proxy::{
-    ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse, ProxyRpcHandler,
+    DocumentFormatter, ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse,
+    ProxyRpcHandler,
 };
 use lapce_rpc::source_control::{DiffInfo, FileDiff};
 use lapce_rpc::style::{LineStyle, SemanticStyles};
@@ -44,6 +48,10 @@ pub struct Dispatcher {
     buffers: HashMap<PathBuf, Buffer>,
     #[allow(deprecated)]
     terminals: HashMap<TermId, mio::channel::Sender<Msg>>,
+    /// The tmux sessions the shells of the terminals run in
+    terminal_sessions: HashMap<TermId, String>,
+    /// The running debug adapters
+    daps: HashMap<DapId, DapRpcHandler>,
     file_watcher: FileWatcher,
 
     window_id: usize,
@@ -58,6 +66,7 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 workspace,
                 disabled_volts,
                 plugin_configurations,
+                language_servers,
                 window_id,
                 tab_id,
             } => {
@@ -81,6 +90,7 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                         workspace,
                         disabled_volts,
                         plugin_configurations,
+                        language_servers,
                         plugin_rpc.clone(),
                     );
                     plugin_rpc.mainloop(&mut plugin);
@@ -95,12 +105,17 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 });
             }
             OpenFileChanged { path } => {
-                if let Some(buffer) = self.buffers.get(&path) {
-                    if get_mod_time(&buffer.path) == buffer.mod_time {
+                if let Some(buffer) = self.buffers.get_mut(&path) {
+                    if !buffer.is_changed_on_disk() {
                         return;
                     }
-                    if let Ok(content) = load_file(&buffer.path) {
-                        self.core_rpc.open_file_changed(path, content);
+                    if let Ok(file) = load_file(&buffer.path, Some(buffer.encoding))
+                    {
+                        // The core keeps the change as a conflict if the
+                        // buffer has unsaved changes, so saving doesn't need
+                        // to report it again
+                        buffer.mod_time = get_mod_time(&buffer.path);
+                        self.core_rpc.open_file_changed(path, file.content);
                     }
                 }
             }
@@ -119,28 +134,44 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     #[allow(deprecated)]
                     let _ = sender.send(Msg::Shutdown);
                 }
+                for (_, dap) in self.daps.drain() {
+                    thread::spawn(move || dap.stop());
+                }
                 self.proxy_rpc.shutdown();
             }
             Update { path, delta, rev } => {
                 let buffer = self.buffers.get_mut(&path).unwrap();
                 let old_text = buffer.rope.clone();
                 buffer.update(&delta, rev);
-                self.catalog_rpc.did_change_text_document(
-                    &path,
-                    rev,
-                    delta,
-                    old_text,
-                    buffer.rope.clone(),
-                );
+                if !buffer.large_file {
+                    self.catalog_rpc.did_change_text_document(
+                        &path,
+                        rev,
+                        delta,
+                        old_text,
+                        buffer.rope.clone(),
+                    );
+                }
             }
             NewTerminal {
                 term_id,
-                cwd,
-                shell,
+                profile,
+                shell_integration,
+                session,
             } => {
-                let mut terminal = Terminal::new(term_id, cwd, shell, 50, 10);
+                let mut terminal = Terminal::new(
+                    term_id,
+                    profile,
+                    shell_integration,
+                    session.as_deref(),
+                    50,
+                    10,
+                );
                 let tx = terminal.tx.clone();
                 self.terminals.insert(term_id, tx);
+                if let Some(session) = session {
+                    self.terminal_sessions.insert(term_id, session);
+                }
                 let rpc = self.core_rpc.clone();
                 thread::spawn(move || {
                     terminal.run(rpc);
@@ -177,6 +208,102 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     #[allow(deprecated)]
                     let _ = tx.send(Msg::Shutdown);
                 }
+                // Only a terminal that is closed ends its session, while the
+                // ones of a workspace that is closed keep running
+                if let Some(session) = self.terminal_sessions.remove(&term_id) {
+                    kill_tmux_session(&session);
+                }
+            }
+            DapStart {
+                dap_id,
+                config,
+                debugger,
+                breakpoints,
+            } => {
+                let debugger =
+                    debugger.or_else(|| self.catalog_rpc.debugger(&config.ty));
+                let debugger = match debugger {
+                    Some(debugger) => debugger,
+                    None => {
+                        self.core_rpc.log(
+                            log::Level::Error,
+                            format!("no debugger for the type {}", config.ty),
+                        );
+                        self.core_rpc.dap_terminated(dap_id);
+                        return;
+                    }
+                };
+                match DapRpcHandler::start(
+                    self.core_rpc.clone(),
+                    dap_id,
+                    debugger,
+                    config,
+                    breakpoints,
+                    self.workspace.clone(),
+                ) {
+                    Ok(dap) => {
+                        self.daps.insert(dap_id, dap);
+                    }
+                    Err(e) => {
+                        self.core_rpc.log(log::Level::Error, e.to_string());
+                        self.core_rpc.dap_terminated(dap_id);
+                    }
+                }
+            }
+            DapContinue { dap_id, thread_id } => {
+                if let Some(dap) = self.daps.get(&dap_id) {
+                    dap.continue_thread(thread_id);
+                }
+            }
+            DapPause { dap_id, thread_id } => {
+                if let Some(dap) = self.daps.get(&dap_id) {
+                    dap.pause(thread_id);
+                }
+            }
+            DapStepOver { dap_id, thread_id } => {
+                if let Some(dap) = self.daps.get(&dap_id) {
+                    dap.step_over(thread_id);
+                }
+            }
+            DapStepInto { dap_id, thread_id } => {
+                if let Some(dap) = self.daps.get(&dap_id) {
+                    dap.step_into(thread_id);
+                }
+            }
+            DapStepOut { dap_id, thread_id } => {
+                if let Some(dap) = self.daps.get(&dap_id) {
+                    dap.step_out(thread_id);
+                }
+            }
+            DapStop { dap_id } => {
+                if let Some(dap) = self.daps.remove(&dap_id) {
+                    thread::spawn(move || dap.stop());
+                }
+            }
+            DapSetBreakpoints {
+                dap_id,
+                path,
+                breakpoints,
+            } => {
+                if let Some(dap) = self.daps.get(&dap_id) {
+                    let dap = dap.clone();
+                    thread::spawn(move || {
+                        let _ = dap.set_breakpoints(path, breakpoints);
+                    });
+                }
+            }
+            DapRestartFrame { dap_id, frame_id } => {
+                if let Some(dap) = self.daps.get(&dap_id) {
+                    dap.restart_frame(frame_id);
+                }
+            }
+            DapSetDataBreakpoints {
+                dap_id,
+                breakpoints,
+            } => {
+                if let Some(dap) = self.daps.get(&dap_id) {
+                    dap.set_data_breakpoints(breakpoints);
+                }
             }
             InstallVolt { volt } => {
                 let catalog_rpc = self.catalog_rpc.clone();
@@ -195,6 +322,18 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             EnableVolt { volt } => {
                 let _ = self.catalog_rpc.start_volt(volt);
             }
+            SetLspTrace { enabled } => {
+                self.catalog_rpc.set_lsp_trace(enabled);
+            }
+            UpdateLanguageServers { language_servers } => {
+                self.catalog_rpc.update_language_servers(language_servers);
+            }
+            RestartLanguageServer { plugin_id } => {
+                let _ = self.catalog_rpc.restart_server(plugin_id);
+            }
+            StopLanguageServer { plugin_id } => {
+                let _ = self.catalog_rpc.stop_server(plugin_id);
+            }
             GitCommit { message, diffs } => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_commit(workspace, &message, diffs) {
@@ -244,22 +383,60 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
     fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
         use ProxyRequest::*;
         match rpc {
-            NewBuffer { buffer_id, path } => {
-                let buffer = Buffer::new(buffer_id, path.clone());
-                let content = buffer.rope.to_string();
-                self.catalog_rpc.document_did_open(
-                    &path,
-                    buffer.language_id.to_string(),
-                    buffer.rev as i32,
-                    content.clone(),
-                );
+            NewBuffer {
+                buffer_id,
+                path,
+                large_file_size,
+            } => {
+                let buffer = Buffer::new(buffer_id, path.clone(), large_file_size);
+                let large_file = buffer.large_file;
+                // The rest of a large file is sent when the core asks for it
+                let content = if large_file {
+                    buffer.chunk(0).0
+                } else {
+                    buffer.rope.to_string()
+                };
+                let encoding = buffer.encoding.name().to_string();
+                let read_only = buffer.read_only;
+                if !large_file {
+                    self.catalog_rpc.document_did_open(
+                        &path,
+                        buffer.language_id.to_string(),
+                        buffer.rev as i32,
+                        content.clone(),
+                    );
+                }
                 self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(
                     id,
-                    Ok(ProxyResponse::NewBufferResponse { content }),
+                    Ok(ProxyResponse::NewBufferResponse {
+                        content,
+                        encoding,
+                        large_file,
+                        read_only,
+                    }),
                 );
             }
+            ReopenWithEncoding { path, encoding } => {
+                let result = encoding_for_name(&encoding)
+                    .and_then(|encoding| {
+                        let buffer = self
+                            .buffers
+                            .get_mut(&path)
+                            .ok_or_else(|| anyhow!("buffer isn't open"))?;
+                        let content = buffer.reopen_with_encoding(encoding)?;
+                        Ok(ProxyResponse::ReopenWithEncodingResponse {
+                            content,
+                            encoding: buffer.encoding.name().to_string(),
+                        })
+                    })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
             BufferHead { path } => {
                 let result = if let Some(workspace) = self.workspace.as_ref() {
                     let result = file_get_head(workspace, &path);
@@ -282,17 +459,49 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 };
                 self.respond_rpc(id, result);
             }
-            GlobalSearch { pattern } => {
+            BufferChunk { path, offset } => {
+                let result = self
+                    .buffers
+                    .get_mut(&path)
+                    .map(|buffer| {
+                        let (content, last) = buffer.chunk(offset);
+                        // The core adds each chunk as a revision
+                        if !content.is_empty() {
+                            buffer.rev += 1;
+                        }
+                        ProxyResponse::BufferChunkResponse { content, last }
+                    })
+                    .ok_or_else(|| RpcError {
+                        code: 0,
+                        message: "buffer isn't open".to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
+            ReadFile { path } => {
+                let result = load_file(&path, None)
+                    .map(|file| ProxyResponse::ReadFileResponse {
+                        content: file.content,
+                    })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
+            GlobalSearch { pattern, is_regex } => {
                 let workspace = self.workspace.clone();
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
                     let result = if let Some(workspace) = workspace.as_ref() {
                         let mut matches = HashMap::new();
-                        let pattern = regex::escape(&pattern);
-                        if let Ok(matcher) = RegexMatcherBuilder::new()
-                            .case_insensitive(true)
-                            .build_literals(&[&pattern])
-                        {
+                        let mut builder = RegexMatcherBuilder::new();
+                        builder.case_insensitive(true);
+                        let matcher = if is_regex {
+                            builder.build(&pattern)
+                        } else {
+                            builder.build_literals(&[&regex::escape(&pattern)])
+                        };
+                        if let Ok(matcher) = matcher {
                             let mut searcher = SearcherBuilder::new().build();
                             for path in ignore::Walk::new(workspace).flatten() {
                                 if let Some(file_type) = path.file_type() {
@@ -303,14 +512,24 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                                             &matcher,
                                             path.clone(),
                                             UTF8(|lnum, line| {
-                                                let mymatch = matcher
-                                                    .find(line.as_bytes())?
-                                                    .unwrap();
-                                                line_matches.push((
-                                                    lnum as usize,
-                                                    (mymatch.start(), mymatch.end()),
-                                                    line.to_string(),
-                                                ));
+                                                // every match of the line, so each
+                                                // of them can be replaced
+                                                matcher.find_iter(
+                                                    line.as_bytes(),
+                                                    |mymatch| {
+                                                        if !mymatch.is_empty() {
+                                                            line_matches.push((
+                                                                lnum as usize,
+                                                                (
+                                                                    mymatch.start(),
+                                                                    mymatch.end(),
+                                                                ),
+                                                                line.to_string(),
+                                                            ));
+                                                        }
+                                                        true
+                                                    },
+                                                )?;
                                                 Ok(true)
                                             }),
                                         );
@@ -364,7 +583,28 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     proxy_rpc.handle_response(id, result);
                 });
             }
-            GetSignature { .. } => {}
+            GetSignature {
+                request_id,
+                path,
+                position,
+                context,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.signature_help(
+                    &path,
+                    position,
+                    context,
+                    move |_, result| {
+                        let result = result.map(|signature| {
+                            ProxyResponse::SignatureResponse {
+                                request_id,
+                                signature,
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetReferences { path, position } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc.get_references(
@@ -378,6 +618,90 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            PrepareCallHierarchy { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.prepare_call_hierarchy(
+                    &path,
+                    position,
+                    move |plugin_id, result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::PrepareCallHierarchyResponse {
+                                plugin_id,
+                                items,
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            CallHierarchyIncoming { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.call_hierarchy_incoming(
+                    plugin_id,
+                    *item,
+                    move |result| {
+                        let result = result.map(|calls| {
+                            ProxyResponse::CallHierarchyIncomingResponse { calls }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            CallHierarchyOutgoing { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.call_hierarchy_outgoing(
+                    plugin_id,
+                    *item,
+                    move |result| {
+                        let result = result.map(|calls| {
+                            ProxyResponse::CallHierarchyOutgoingResponse { calls }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            PrepareTypeHierarchy { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.prepare_type_hierarchy(
+                    &path,
+                    position,
+                    move |plugin_id, result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::PrepareTypeHierarchyResponse {
+                                plugin_id,
+                                items,
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            TypeHierarchySupertypes { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.type_hierarchy_supertypes(
+                    plugin_id,
+                    *item,
+                    move |result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::TypeHierarchyResponse { items }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            TypeHierarchySubtypes { plugin_id, item } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.type_hierarchy_subtypes(
+                    plugin_id,
+                    *item,
+                    move |result| {
+                        let result = result.map(|items| {
+                            ProxyResponse::TypeHierarchyResponse { items }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetDefinition {
                 request_id,
                 path,
@@ -418,6 +742,46 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            GetDeclaration {
+                request_id,
+                path,
+                position,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_declaration(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|declaration| {
+                            ProxyResponse::GetDeclaration {
+                                request_id,
+                                declaration,
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetImplementation {
+                request_id,
+                path,
+                position,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_implementation(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|implementation| {
+                            ProxyResponse::GetImplementation {
+                                request_id,
+                                implementation,
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetInlayHints { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 let buffer = self.buffers.get(&path).unwrap();
@@ -432,6 +796,52 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         proxy_rpc.handle_response(id, result);
                     });
             }
+            GetCodeLens { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                let catalog_rpc = self.catalog_rpc.clone();
+                self.catalog_rpc
+                    .get_code_lens(&path, move |plugin_id, result| match result {
+                        Ok(lenses) => {
+                            catalog_rpc.resolve_code_lenses(
+                                plugin_id,
+                                lenses,
+                                move |lenses| {
+                                    proxy_rpc.handle_response(
+                                        id,
+                                        Ok(ProxyResponse::GetCodeLens {
+                                            plugin_id,
+                                            lenses,
+                                        }),
+                                    );
+                                },
+                            );
+                        }
+                        Err(e) => proxy_rpc.handle_response(id, Err(e)),
+                    });
+            }
+            ExecuteCommand { plugin_id, command } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.execute_command(
+                    plugin_id,
+                    command,
+                    move |result| {
+                        let result = result.map(|result| {
+                            ProxyResponse::ExecuteCommandResponse { result }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetFoldingRanges { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .get_folding_ranges(&path, move |_, result| {
+                        let result = result.map(|ranges| {
+                            ProxyResponse::GetFoldingRanges { ranges }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
             GetSemanticTokens { path } => {
                 let buffer = self.buffers.get(&path).unwrap();
                 let text = buffer.rope.clone();
@@ -492,6 +902,47 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            GetCodeActionsOfKinds { path, range, kinds } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_code_actions_of_kinds(
+                    &path,
+                    range,
+                    kinds,
+                    move |_, result| {
+                        let result = result.map(|resp| {
+                            ProxyResponse::GetCodeActionsResponse { resp }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetOnTypeFormatting { path, position, ch } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_on_type_formatting(
+                    &path,
+                    position,
+                    ch,
+                    move |_, result| {
+                        let result = result.map(|edits| {
+                            ProxyResponse::GetDocumentFormatting { edits }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetQuickFixes { path, diagnostic } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_quick_fixes(
+                    &path,
+                    diagnostic,
+                    move |_, result| {
+                        let result = result.map(|resp| {
+                            ProxyResponse::GetCodeActionsResponse { resp }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetDocumentSymbols { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc
@@ -504,22 +955,109 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
             GetWorkspaceSymbols { query } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc
-                    .get_workspace_symbols(query, move |_, result| {
+                    .get_workspace_symbols(query, move |result| {
                         let result = result.map(|symbols| {
                             ProxyResponse::GetWorkspaceSymbols { symbols }
                         });
                         proxy_rpc.handle_response(id, result);
                     });
             }
-            GetDocumentFormatting { path } => {
+            GetDocumentFormatting {
+                path,
+                range,
+                formatter,
+                timeout,
+            } => {
                 let proxy_rpc = self.proxy_rpc.clone();
-                self.catalog_rpc
-                    .get_document_formatting(&path, move |_, result| {
-                        let result = result.map(|edits| {
-                            ProxyResponse::GetDocumentFormatting { edits }
+                let server = match formatter {
+                    DocumentFormatter::LanguageServer => None,
+                    DocumentFormatter::NamedLanguageServer(name) => Some(name),
+                    DocumentFormatter::Command(command) => {
+                        let text = match self.buffers.get(&path) {
+                            Some(buffer) => buffer.get_document(),
+                            None => {
+                                proxy_rpc.handle_response(
+                                    id,
+                                    Err(RpcError {
+                                        code: 0,
+                                        message: "buffer isn't open".to_string(),
+                                    }),
+                                );
+                                return;
+                            }
+                        };
+                        let workspace = self.workspace.clone();
+                        thread::spawn(move || {
+                            let result = format_with_command(
+                                workspace.as_deref(),
+                                &command,
+                                &text,
+                                Duration::from_millis(timeout),
+                            )
+                            .map(|edits| match range {
+                                Some(range) => edits_in_range(edits, range),
+                                None => edits,
+                            })
+                            .map(|edits| ProxyResponse::GetDocumentFormatting {
+                                edits,
+                            })
+                            .map_err(|e| RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            });
+                            proxy_rpc.handle_response(id, result);
                         });
-                        proxy_rpc.handle_response(id, result);
-                    });
+                        return;
+                    }
+                };
+
+                let range = match range {
+                    Some(range) => range,
+                    None => {
+                        self.catalog_rpc.get_document_formatting(
+                            &path,
+                            server,
+                            move |_, result| {
+                                let result = result.map(|edits| {
+                                    ProxyResponse::GetDocumentFormatting { edits }
+                                });
+                                proxy_rpc.handle_response(id, result);
+                            },
+                        );
+                        return;
+                    }
+                };
+                // the servers that can't format a range format the whole
+                // document, and only the edits within the range are kept
+                let catalog_rpc = self.catalog_rpc.clone();
+                let document = path.clone();
+                self.catalog_rpc.get_document_range_formatting(
+                    &path,
+                    range,
+                    server.clone(),
+                    move |_, result| match result {
+                        Ok(edits) => {
+                            proxy_rpc.handle_response(
+                                id,
+                                Ok(ProxyResponse::GetDocumentFormatting { edits }),
+                            );
+                        }
+                        Err(_) => {
+                            catalog_rpc.get_document_formatting(
+                                &document,
+                                server,
+                                move |_, result| {
+                                    let result = result.map(|edits| {
+                                        ProxyResponse::GetDocumentFormatting {
+                                            edits: edits_in_range(edits, range),
+                                        }
+                                    });
+                                    proxy_rpc.handle_response(id, result);
+                                },
+                            );
+                        }
+                    },
+                );
             }
             PrepareRename { path, position } => {
                 let proxy_rpc = self.proxy_rpc.clone();
@@ -587,6 +1125,11 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 let resp = ProxyResponse::GetOpenFilesContentResponse { items };
                 self.proxy_rpc.handle_response(id, Ok(resp));
             }
+            GetLanguageServers {} => {
+                let servers = self.catalog_rpc.language_server_statuses();
+                let resp = ProxyResponse::GetLanguageServersResponse { servers };
+                self.proxy_rpc.handle_response(id, Ok(resp));
+            }
             ReadDir { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
@@ -622,13 +1165,35 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     proxy_rpc.handle_response(id, result);
                 });
             }
-            Save { rev, path } => {
+            Save {
+                rev,
+                path,
+                encoding,
+            } => {
                 let buffer = self.buffers.get_mut(&path).unwrap();
-                let result = buffer
-                    .save(rev)
+                if buffer.is_changed_on_disk() {
+                    if let Ok(file) = load_file(&buffer.path, Some(buffer.encoding))
+                    {
+                        buffer.mod_time = get_mod_time(&buffer.path);
+                        self.respond_rpc(
+                            id,
+                            Ok(ProxyResponse::SaveConflictResponse {
+                                content: file.content,
+                            }),
+                        );
+                        return;
+                    }
+                }
+                let result = encoding_for_name(&encoding)
+                    .and_then(|encoding| {
+                        buffer.set_encoding(encoding);
+                        buffer.save(rev)
+                    })
                     .map(|_r| {
-                        self.catalog_rpc
-                            .did_save_text_document(&path, buffer.rope.clone());
+                        if !buffer.large_file {
+                            self.catalog_rpc
+                                .did_save_text_document(&path, buffer.rope.clone());
+                        }
                         ProxyResponse::SaveResponse {}
                     })
                     .map_err(|e| RpcError {
@@ -642,12 +1207,16 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 path,
                 rev,
                 content,
+                encoding,
             } => {
-                let mut buffer = Buffer::new(buffer_id, path);
+                let mut buffer = Buffer::new(buffer_id, path, 0);
                 buffer.rope = Rope::from(content);
                 buffer.rev = rev;
-                let result = buffer
-                    .save(rev)
+                let result = encoding_for_name(&encoding)
+                    .and_then(|encoding| {
+                        buffer.set_encoding(encoding);
+                        buffer.save(rev)
+                    })
                     .map(|_| ProxyResponse::Success {})
                     .map_err(|e| RpcError {
                         code: 0,
@@ -721,6 +1290,130 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            GetDocumentHighlights { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_document_highlights(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|highlights| {
+                            ProxyResponse::GetDocumentHighlights {
+                                highlights: highlights.unwrap_or_default(),
+                            }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            GetDocumentLinks { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .get_document_links(&path, move |_, result| {
+                        let result =
+                            result.map(|links| ProxyResponse::GetDocumentLinks {
+                                links: links.unwrap_or_default(),
+                            });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
+            GetLinkedEditingRanges { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_linked_editing_ranges(
+                    &path,
+                    position,
+                    move |_, result| {
+                        let result = result.map(|ranges| {
+                            ProxyResponse::GetLinkedEditingRanges { ranges }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            DapEvaluate {
+                dap_id,
+                expression,
+                frame_id,
+                context,
+            } => {
+                self.dap_request(id, dap_id, move |dap| {
+                    dap.evaluate(&expression, frame_id, context)
+                        .map(|result| ProxyResponse::DapEvaluateResponse { result })
+                });
+            }
+            DapCompletions {
+                dap_id,
+                text,
+                column,
+                frame_id,
+            } => {
+                self.dap_request(id, dap_id, move |dap| {
+                    dap.completions(&text, column, frame_id).map(|targets| {
+                        ProxyResponse::DapCompletionsResponse { targets }
+                    })
+                });
+            }
+            DapVariables {
+                dap_id,
+                variables_reference,
+            } => {
+                self.dap_request(id, dap_id, move |dap| {
+                    dap.variables(variables_reference).map(|variables| {
+                        ProxyResponse::DapVariablesResponse { variables }
+                    })
+                });
+            }
+            ListProcesses {} => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = list_processes()
+                        .map(|processes| ProxyResponse::ListProcessesResponse {
+                            processes,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            DapStackTrace { dap_id, thread_id } => {
+                self.dap_request(id, dap_id, move |dap| {
+                    dap.stack_trace(thread_id).map(|stack_frames| {
+                        ProxyResponse::DapStackTraceResponse { stack_frames }
+                    })
+                });
+            }
+            DapScopes { dap_id, frame_id } => {
+                self.dap_request(id, dap_id, move |dap| {
+                    dap.scopes(frame_id).map(|variables| {
+                        ProxyResponse::DapScopesResponse { variables }
+                    })
+                });
+            }
+            DapReadMemory {
+                dap_id,
+                memory_reference,
+                offset,
+                count,
+            } => {
+                self.dap_request(id, dap_id, move |dap| {
+                    dap.read_memory(&memory_reference, offset, count)
+                        .map(|result| ProxyResponse::DapReadMemoryResponse {
+                            result,
+                        })
+                });
+            }
+            DapDataBreakpointInfo {
+                dap_id,
+                variables_reference,
+                name,
+            } => {
+                self.dap_request(id, dap_id, move |dap| {
+                    dap.data_breakpoint_info(variables_reference, &name).map(
+                        |info| ProxyResponse::DapDataBreakpointInfoResponse { info },
+                    )
+                });
+            }
         }
     }
 }
@@ -739,6 +1432,8 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             catalog_rpc: plugin_rpc,
             buffers: HashMap::new(),
             terminals: HashMap::new(),
+            terminal_sessions: HashMap::new(),
+            daps: HashMap::new(),
             file_watcher,
             window_id: 1,
             tab_id: 1,
@@ -748,6 +1443,37 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
     fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
         self.proxy_rpc.handle_response(id, result);
     }
+
+    /// Respond with what is asked of the debug adapter, which is waited for
+    /// on another thread
+    fn dap_request(
+        &self,
+        id: RequestId,
+        dap_id: DapId,
+        f: impl FnOnce(&DapRpcHandler) -> Result<ProxyResponse> + Send + 'static,
+    ) {
+        let dap = match self.daps.get(&dap_id) {
+            Some(dap) => dap.clone(),
+            None => {
+                self.respond_rpc(
+                    id,
+                    Err(RpcError {
+                        code: 0,
+                        message: "the debugger stopped".to_string(),
+                    }),
+                );
+                return;
+            }
+        };
+        let proxy_rpc = self.proxy_rpc.clone();
+        thread::spawn(move || {
+            let result = f(&dap).map_err(|e| RpcError {
+                code: 0,
+                message: e.to_string(),
+            });
+            proxy_rpc.handle_response(id, result);
+        });
+    }
 }
 
 struct FileWatchNotifier {
@@ -820,6 +1546,13 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             _ => return,
         };
 
+        if let Some(workspace) = self.workspace.as_ref() {
+            let settings = workspace.join(".lapce").join("settings.toml");
+            if event.paths.iter().any(|path| path == &settings) {
+                self.core_rpc.workspace_settings_change();
+            }
+        }
+
         let mut handler = self.workspace_fs_change_handler.lock();
         if let Some(sender) = handler.as_mut() {
             if explorer_change {
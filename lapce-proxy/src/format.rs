@@ -0,0 +1,153 @@
+//! Formatting documents with an external command.
+
+use std::{
+    io::{Read, Write},
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use lsp_types::{Position, Range, TextEdit};
+use xi_rope::{
+    diff::{Diff, LineHashDiff},
+    DeltaElement, Rope,
+};
+
+/// Run the command in a shell with the text on its stdin, and give the edits
+/// that turn the text into what the command wrote to stdout. The command is
+/// killed if it takes longer than the timeout.
+pub fn format_with_command(
+    workspace: Option<&Path>,
+    command: &str,
+    text: &str,
+    timeout: Duration,
+) -> Result<Vec<TextEdit>> {
+    let mut process = shell_command(command);
+    if let Some(workspace) = workspace {
+        process.current_dir(workspace);
+    }
+    let mut child = process
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let mut stdin = child.stdin.take().unwrap();
+    let input = text.to_string();
+    thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let mut stdout = child.stdout.take().unwrap();
+    let output = thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+    let mut stderr = child.stderr.take().unwrap();
+    let errors = thread::spawn(move || {
+        let mut errors = String::new();
+        let _ = stderr.read_to_string(&mut errors);
+        errors
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() > timeout {
+            let _ = child.kill();
+            return Err(anyhow!("formatter {command} timed out"));
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    if !status.success() {
+        let errors = errors.join().unwrap_or_default();
+        return Err(anyhow!("formatter {command} failed: {}", errors.trim()));
+    }
+    let output = output
+        .join()
+        .map_err(|_| anyhow!("can't read the output of {command}"))??;
+
+    Ok(text_edits(text, &output))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell_command(command: &str) -> Command {
+    let mut process = Command::new("sh");
+    process.arg("-c").arg(command);
+    process
+}
+
+#[cfg(target_os = "windows")]
+fn shell_command(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    let mut process = Command::new("cmd");
+    process.arg("/C").arg(command);
+    process.creation_flags(0x08000000);
+    process
+}
+
+/// The edits turning the old text into the new one, one for each run of
+/// changed lines, so that the cursors outside of them stay where they are
+fn text_edits(old: &str, new: &str) -> Vec<TextEdit> {
+    if old == new {
+        return Vec::new();
+    }
+    let delta = LineHashDiff::compute_delta(&Rope::from(old), &Rope::from(new));
+
+    let mut edits = Vec::new();
+    let mut offset = 0;
+    let mut inserted = String::new();
+    let mut push_edit = |start: usize, end: usize, new_text: String| {
+        edits.push(TextEdit {
+            range: Range::new(position_of(old, start), position_of(old, end)),
+            new_text,
+        });
+    };
+    for el in delta.els {
+        match el {
+            // a line copied from before the previous one, which can't be
+            // left in place
+            DeltaElement::Copy(start, end) if start < offset => {
+                inserted.push_str(&old[start..end]);
+            }
+            DeltaElement::Copy(start, end) => {
+                if start > offset || !inserted.is_empty() {
+                    push_edit(offset, start, std::mem::take(&mut inserted));
+                }
+                offset = end;
+            }
+            DeltaElement::Insert(text) => {
+                inserted.push_str(&String::from(text));
+            }
+        }
+    }
+    if old.len() > offset || !inserted.is_empty() {
+        push_edit(offset, old.len(), inserted);
+    }
+    edits
+}
+
+/// The edits that are within the range, for when only part of the document
+/// should be formatted but the formatter can only format all of it
+pub fn edits_in_range(edits: Vec<TextEdit>, range: Range) -> Vec<TextEdit> {
+    edits
+        .into_iter()
+        .filter(|edit| {
+            edit.range.start >= range.start && edit.range.end <= range.end
+        })
+        .collect()
+}
+
+/// The LSP position of the offset, whose character is counted in UTF-16
+fn position_of(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
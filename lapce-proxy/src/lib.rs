@@ -1,7 +1,10 @@
 pub mod buffer;
+pub mod dap;
 pub mod directory;
 pub mod dispatch;
+pub mod format;
 pub mod plugin;
+pub mod shell_integration;
 pub mod terminal;
 pub mod watcher;
 
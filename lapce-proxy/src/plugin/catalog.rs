@@ -9,11 +9,14 @@
 };
 
 use lapce_rpc::{
-    plugin::PluginId, proxy::ProxyResponse, style::LineStyle, RpcError,
+    plugin::{LanguageServerConfig, PluginId},
+    proxy::ProxyResponse,
+    style::LineStyle,
+    RpcError,
 };
 use lsp_types::{
-    notification::DidOpenTextDocument, DidOpenTextDocumentParams, SemanticTokens,
-    TextDocumentIdentifier, VersionedTextDocumentIdentifier,
+    notification::DidOpenTextDocument, DidOpenTextDocumentParams, DocumentFilter,
+    SemanticTokens, TextDocumentIdentifier, VersionedTextDocumentIdentifier,
 };
 use parking_lot::Mutex;
 use psp_types::Notification;
@@ -23,7 +26,11 @@
 use crate::plugin::{install_volt, wasi::start_volt_from_info};
 
 use super::{
-    psp::{ClonableCallback, PluginServerRpc, PluginServerRpcHandler, RpcCallback},
+    lsp::{command_server_uri, LspClient},
+    psp::{
+        ClonableCallback, PluginHandlerNotification, PluginServerRpc,
+        PluginServerRpcHandler, RpcCallback,
+    },
     wasi::load_all_volts,
     PluginCatalogNotification, PluginCatalogRpcHandler,
 };
@@ -40,6 +47,7 @@ pub fn new(
         workspace: Option<PathBuf>,
         disabled_volts: Vec<String>,
         plugin_configurations: HashMap<String, serde_json::Value>,
+        language_servers: HashMap<String, LanguageServerConfig>,
         plugin_rpc: PluginCatalogRpcHandler,
     ) -> Self {
         let plugin = Self {
@@ -49,6 +57,13 @@ pub fn new(
             new_plugins: HashMap::new(),
         };
 
+        plugin_rpc.set_language_servers(language_servers.clone());
+        for (name, server) in language_servers {
+            if let Some(command) = server.command.clone() {
+                plugin.start_language_server(name, command, server);
+            }
+        }
+
         thread::spawn(move || {
             load_all_volts(
                 workspace,
@@ -61,6 +76,65 @@ pub fn new(
         plugin
     }
 
+    /// Start a language server from the settings, for the documents of its
+    /// languages
+    fn start_language_server(
+        &self,
+        name: String,
+        command: String,
+        server: LanguageServerConfig,
+    ) {
+        let server_uri =
+            match command_server_uri(&command, self.workspace.as_deref()) {
+                Ok(server_uri) => server_uri,
+                Err(e) => {
+                    self.plugin_rpc.core_rpc.log(
+                        log::Level::Error,
+                        format!(
+                        "invalid command {command} of language server {name}: {e}"
+                    ),
+                    );
+                    return;
+                }
+            };
+        let document_selector = server
+            .languages
+            .iter()
+            .map(|language| DocumentFilter {
+                language: Some(language.clone()),
+                scheme: None,
+                pattern: None,
+            })
+            .collect();
+        let workspace = self.workspace.clone();
+        let catalog_rpc = self.plugin_rpc.clone();
+        thread::spawn(move || {
+            if let Err(e) = LspClient::start(
+                catalog_rpc.clone(),
+                document_selector,
+                workspace,
+                name.clone(),
+                None,
+                server_uri,
+                server.args,
+                server.options,
+            ) {
+                catalog_rpc.core_rpc.log(
+                    log::Level::Error,
+                    format!("can't start language server {name}: {e}"),
+                );
+            }
+        });
+    }
+
+    /// The priority the settings give to the servers of the plugin
+    fn priority(&self, volt_id: &str) -> i64 {
+        self.plugin_rpc
+            .language_server_config(volt_id)
+            .map(|server| server.priority)
+            .unwrap_or(0)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn handle_server_request(
         &mut self,
@@ -231,6 +305,11 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                         );
                     }
                 }
+                self.plugin_rpc.server_started(
+                    plugin.plugin_id,
+                    plugin.volt_id.clone(),
+                    self.priority(&plugin.volt_id),
+                );
                 self.new_plugins.insert(plugin.plugin_id, plugin);
             }
             InstallVolt(volt) => {
@@ -251,6 +330,7 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                     if self.new_plugins.get(&id).unwrap().volt_id == volt_id {
                         let plugin = self.new_plugins.remove(&id).unwrap();
                         plugin.shutdown();
+                        self.plugin_rpc.server_stopped(id);
                     }
                 }
             }
@@ -274,6 +354,29 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                     );
                 });
             }
+            RestartServer(plugin_id) => {
+                if let Some(plugin) = self.new_plugins.remove(&plugin_id) {
+                    // the server starts a new one of itself before it stops
+                    plugin.handle_rpc(PluginServerRpc::Handler(
+                        PluginHandlerNotification::Restart,
+                    ));
+                    plugin.shutdown();
+                    self.plugin_rpc.server_stopped(plugin_id);
+                }
+            }
+            StopServer(plugin_id) => {
+                if let Some(plugin) = self.new_plugins.remove(&plugin_id) {
+                    plugin.shutdown();
+                    self.plugin_rpc.server_stopped(plugin_id);
+                }
+            }
+            LanguageServersChanged => {
+                for (_, plugin) in self.new_plugins.iter() {
+                    plugin.handle_rpc(PluginServerRpc::Handler(
+                        PluginHandlerNotification::DidChangeConfiguration,
+                    ));
+                }
+            }
             Shutdown => {
                 for (_, plugin) in self.new_plugins.iter() {
                     plugin.shutdown();
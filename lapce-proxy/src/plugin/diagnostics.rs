@@ -0,0 +1,326 @@
+//! The pull diagnostics of LSP 3.17, which the version of lsp-types we use
+//! doesn't have yet. The diagnostics of a document are asked for when it's
+//! opened or changed, instead of being published by the server.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use lapce_rpc::{plugin::PluginId, RpcError};
+use lsp_types::{
+    request::Request, Diagnostic, PartialResultParams, PublishDiagnosticsParams,
+    TextDocumentIdentifier, Url, WorkDoneProgressParams,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{psp::PluginServerRpcHandler, PluginCatalogRpcHandler};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiagnosticOptions {
+    pub identifier: Option<String>,
+    pub inter_file_dependencies: bool,
+    pub workspace_diagnostics: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentDiagnosticParams {
+    pub text_document: TextDocumentIdentifier,
+    pub identifier: Option<String>,
+    pub previous_result_id: Option<String>,
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+    #[serde(flatten)]
+    pub partial_result_params: PartialResultParams,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FullDocumentDiagnosticReport {
+    pub result_id: Option<String>,
+    pub items: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnchangedDocumentDiagnosticReport {
+    pub result_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DocumentDiagnosticReport {
+    Full(FullDocumentDiagnosticReport),
+    Unchanged(UnchangedDocumentDiagnosticReport),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviousResultId {
+    pub uri: Url,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceDiagnosticParams {
+    pub identifier: Option<String>,
+    pub previous_result_ids: Vec<PreviousResultId>,
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+    #[serde(flatten)]
+    pub partial_result_params: PartialResultParams,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceFullDocumentDiagnosticReport {
+    pub uri: Url,
+    pub result_id: Option<String>,
+    pub items: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceUnchangedDocumentDiagnosticReport {
+    pub uri: Url,
+    pub result_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum WorkspaceDocumentDiagnosticReport {
+    Full(WorkspaceFullDocumentDiagnosticReport),
+    Unchanged(WorkspaceUnchangedDocumentDiagnosticReport),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WorkspaceDiagnosticReport {
+    pub items: Vec<WorkspaceDocumentDiagnosticReport>,
+}
+
+#[derive(Debug)]
+pub enum DocumentDiagnosticRequest {}
+
+impl Request for DocumentDiagnosticRequest {
+    type Params = DocumentDiagnosticParams;
+    type Result = DocumentDiagnosticReport;
+    const METHOD: &'static str = "textDocument/diagnostic";
+}
+
+#[derive(Debug)]
+pub enum WorkspaceDiagnosticRequest {}
+
+impl Request for WorkspaceDiagnosticRequest {
+    type Params = WorkspaceDiagnosticParams;
+    type Result = WorkspaceDiagnosticReport;
+    const METHOD: &'static str = "workspace/diagnostic";
+}
+
+#[derive(Debug)]
+pub enum WorkspaceDiagnosticRefresh {}
+
+impl Request for WorkspaceDiagnosticRefresh {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "workspace/diagnostic/refresh";
+}
+
+/// The diagnostics of a language server that has them pulled, which are
+/// published like the ones the servers push
+#[derive(Clone)]
+pub struct PullDiagnostics {
+    plugin_id: PluginId,
+    options: DiagnosticOptions,
+    server_rpc: PluginServerRpcHandler,
+    catalog_rpc: PluginCatalogRpcHandler,
+    /// The open documents of the server, with the number of the last pull
+    /// of each, so that the answers to older pulls are dropped
+    documents: Arc<Mutex<HashMap<Url, u64>>>,
+    /// The id of the last report of each document, which lets the server
+    /// answer that nothing changed since
+    result_ids: Arc<Mutex<HashMap<Url, String>>>,
+    /// Whether the workspace diagnostics are being pulled
+    pulling_workspace: Arc<AtomicBool>,
+}
+
+impl PullDiagnostics {
+    pub fn new(
+        plugin_id: PluginId,
+        options: DiagnosticOptions,
+        server_rpc: PluginServerRpcHandler,
+        catalog_rpc: PluginCatalogRpcHandler,
+    ) -> Self {
+        Self {
+            plugin_id,
+            options,
+            server_rpc,
+            catalog_rpc,
+            documents: Arc::new(Mutex::new(HashMap::new())),
+            result_ids: Arc::new(Mutex::new(HashMap::new())),
+            pulling_workspace: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn document_opened(&self, uri: Url) {
+        self.documents.lock().insert(uri.clone(), 0);
+        self.pull_document(uri);
+    }
+
+    /// Pull the diagnostics of the document again, if the server has it
+    /// open
+    pub fn document_changed(&self, uri: Url) {
+        if self.documents.lock().contains_key(&uri) {
+            self.pull_document(uri);
+        }
+    }
+
+    /// Pull the diagnostics of all the documents again, which the server
+    /// asks for when they changed because of something else than an edit
+    pub fn refresh(&self) {
+        let uris = self.documents.lock().keys().cloned().collect::<Vec<_>>();
+        for uri in uris {
+            self.pull_document(uri);
+        }
+        self.pull_workspace();
+    }
+
+    fn pull_document(&self, uri: Url) {
+        let pull = {
+            let mut documents = self.documents.lock();
+            let pull = documents.entry(uri.clone()).or_insert(0);
+            *pull += 1;
+            *pull
+        };
+        let params = DocumentDiagnosticParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            identifier: self.options.identifier.clone(),
+            previous_result_id: self.result_ids.lock().get(&uri).cloned(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let local = self.clone();
+        self.server_rpc.server_request_async(
+            DocumentDiagnosticRequest::METHOD,
+            params,
+            None,
+            None,
+            false,
+            move |result: Result<Value, RpcError>| {
+                if local.documents.lock().get(&uri) != Some(&pull) {
+                    return;
+                }
+                let report = match result
+                    .ok()
+                    .and_then(|value| serde_json::from_value(value).ok())
+                {
+                    Some(report) => report,
+                    None => return,
+                };
+                match report {
+                    DocumentDiagnosticReport::Full(report) => {
+                        local.publish(uri, report.result_id, report.items);
+                    }
+                    DocumentDiagnosticReport::Unchanged(report) => {
+                        local.result_ids.lock().insert(uri, report.result_id);
+                    }
+                }
+            },
+        );
+    }
+
+    /// Pull the diagnostics of the files of the workspace, for servers that
+    /// report on files that aren't open
+    pub fn pull_workspace(&self) {
+        if !self.options.workspace_diagnostics
+            || self.pulling_workspace.swap(true, Ordering::AcqRel)
+        {
+            return;
+        }
+        let previous_result_ids = self
+            .result_ids
+            .lock()
+            .iter()
+            .map(|(uri, value)| PreviousResultId {
+                uri: uri.clone(),
+                value: value.clone(),
+            })
+            .collect();
+        let params = WorkspaceDiagnosticParams {
+            identifier: self.options.identifier.clone(),
+            previous_result_ids,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let local = self.clone();
+        self.server_rpc.server_request_async(
+            WorkspaceDiagnosticRequest::METHOD,
+            params,
+            None,
+            None,
+            false,
+            move |result: Result<Value, RpcError>| {
+                local.pulling_workspace.store(false, Ordering::Release);
+                let report: WorkspaceDiagnosticReport = match result
+                    .ok()
+                    .and_then(|value| serde_json::from_value(value).ok())
+                {
+                    Some(report) => report,
+                    None => return,
+                };
+                for item in report.items {
+                    match item {
+                        WorkspaceDocumentDiagnosticReport::Full(report) => {
+                            // the pulls of open documents are more recent
+                            if local.documents.lock().contains_key(&report.uri) {
+                                continue;
+                            }
+                            local.publish(
+                                report.uri,
+                                report.result_id,
+                                report.items,
+                            );
+                        }
+                        WorkspaceDocumentDiagnosticReport::Unchanged(report) => {
+                            local
+                                .result_ids
+                                .lock()
+                                .insert(report.uri, report.result_id);
+                        }
+                    }
+                }
+            },
+        );
+    }
+
+    fn publish(
+        &self,
+        uri: Url,
+        result_id: Option<String>,
+        diagnostics: Vec<Diagnostic>,
+    ) {
+        match result_id {
+            Some(result_id) => {
+                self.result_ids.lock().insert(uri.clone(), result_id);
+            }
+            None => {
+                self.result_ids.lock().remove(&uri);
+            }
+        }
+        self.catalog_rpc.publish_diagnostics(
+            self.plugin_id,
+            PublishDiagnosticsParams {
+                uri,
+                diagnostics,
+                version: None,
+            },
+        );
+    }
+}
@@ -1,16 +1,17 @@
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use std::{
-    io::{BufRead, BufReader, BufWriter, Write},
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    net::TcpStream,
     path::{Path, PathBuf},
-    process::{self, Child, Command, Stdio},
+    process::{self, Child, ChildStderr, Command, Stdio},
     sync::Arc,
     thread,
 };
 
 use anyhow::{anyhow, Result};
 use jsonrpc_lite::{Id, Params};
-use lapce_rpc::{style::LineStyle, RpcError};
+use lapce_rpc::{plugin::LanguageServerStatus, style::LineStyle, RpcError};
 use lsp_types::{
     notification::{Initialized, Notification},
     request::{Initialize, Request},
@@ -22,9 +23,13 @@
 
 use crate::{buffer::Buffer, plugin::PluginCatalogRpcHandler};
 
-use super::psp::{
-    handle_plugin_server_message, PluginHandlerNotification, PluginHostHandler,
-    PluginServerHandler, PluginServerRpcHandler, RpcCallback,
+use super::{
+    diagnostics::{DiagnosticOptions, PullDiagnostics},
+    psp::{
+        handle_plugin_server_message, PluginHandlerNotification, PluginHostHandler,
+        PluginServerHandler, PluginServerRpcHandler, ResponseHandler, RpcCallback,
+    },
+    trace::LspTrace,
 };
 
 const HEADER_CONTENT_LENGTH: &str = "content-length";
@@ -50,13 +55,33 @@ pub enum LspRpc {
     },
 }
 
+/// How the client talks to the server, either the stdio of a process it
+/// starts, or a socket the server already listens on
+struct LspConnection {
+    process: Option<Child>,
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+    stderr: Option<ChildStderr>,
+}
+
 pub struct LspClient {
     plugin_rpc: PluginCatalogRpcHandler,
     server_rpc: PluginServerRpcHandler,
-    process: Child,
+    /// The process of the server, unless it's connected to over a socket
+    process: Option<Child>,
     workspace: Option<PathBuf>,
     host: PluginHostHandler,
+    /// What the server was started with, to start it again
+    document_selector: DocumentSelector,
+    volt_id: String,
+    pwd: Option<PathBuf>,
+    server_uri: Url,
+    args: Vec<String>,
     options: Option<Value>,
+    /// The command the server was started with, for its status
+    command: String,
+    command_overridden: bool,
+    override_error: Option<String>,
 }
 
 impl PluginServerHandler for LspClient {
@@ -81,6 +106,12 @@ fn handle_handler_notification(
             Initialize => {
                 self.initialize();
             }
+            DidChangeConfiguration => {
+                self.host.did_change_configuration();
+            }
+            Restart => {
+                self.restart();
+            }
             Shutdown => {
                 self.shutdown();
             }
@@ -95,6 +126,34 @@ fn handle_host_notification(&mut self, method: String, params: Params) {
         let _ = self.host.handle_notification(method, params);
     }
 
+    fn handle_did_open_text_document(&mut self, path: &Path) {
+        self.host.handle_did_open_text_document(path);
+    }
+
+    fn handle_semantic_tokens_request(
+        &mut self,
+        path: &Path,
+        rh: ResponseHandler<Value, RpcError>,
+    ) {
+        self.host.handle_semantic_tokens_request(path, rh);
+    }
+
+    fn handle_signature_help_request(
+        &mut self,
+        params: Value,
+        rh: ResponseHandler<Value, RpcError>,
+    ) {
+        self.host.handle_signature_help_request(params, rh);
+    }
+
+    fn handle_on_type_formatting_request(
+        &mut self,
+        params: Value,
+        rh: ResponseHandler<Value, RpcError>,
+    ) {
+        self.host.handle_on_type_formatting_request(params, rh);
+    }
+
     fn handle_did_save_text_document(
         &self,
         language_id: String,
@@ -156,30 +215,66 @@ fn new(
         args: Vec<String>,
         options: Option<Value>,
     ) -> Result<Self> {
-        let server = match server_uri.scheme() {
-            "file" => {
-                let path = server_uri.to_file_path().map_err(|_| anyhow!(""))?;
-                #[cfg(unix)]
-                let _ = std::process::Command::new("chmod")
-                    .arg("+x")
-                    .arg(&path)
-                    .output();
-                path.to_str().ok_or_else(|| anyhow!(""))?.to_string()
+        // The settings can replace the command the plugin starts the server
+        // with, and the plugin's is used if the server can't be started
+        // with theirs
+        let mut overridden = None;
+        let mut override_error = None;
+        if let Some(config) = plugin_rpc.language_server_config(&volt_id) {
+            if let Some(command) = config.override_command {
+                let override_args =
+                    config.override_args.unwrap_or_else(|| args.clone());
+                match command_server_uri(&command, workspace.as_deref()).and_then(
+                    |uri| Self::connect(workspace.as_ref(), &uri, &override_args),
+                ) {
+                    Ok(connected) => overridden = Some((connected, override_args)),
+                    Err(e) => {
+                        plugin_rpc.core_rpc.log(
+                            log::Level::Error,
+                            format!(
+                                "can't start language server {volt_id} with \
+                                 {command}: {e}"
+                            ),
+                        );
+                        override_error = Some(e.to_string());
+                    }
+                }
             }
-            "urn" => server_uri.path().to_string(),
-            _ => return Err(anyhow!("uri not supported")),
+        }
+        let command_overridden = overridden.is_some();
+        let ((server, connection), command_args) = match overridden {
+            Some(overridden) => overridden,
+            None => (
+                Self::connect(workspace.as_ref(), &server_uri, &args)?,
+                args.clone(),
+            ),
         };
+        let command = if command_args.is_empty() {
+            server.clone()
+        } else {
+            format!("{server} {}", command_args.join(" "))
+        };
+        let LspConnection {
+            process,
+            reader,
+            writer,
+            stderr,
+        } = connection;
 
-        let mut process = Self::process(workspace.as_ref(), &server, &args)?;
-        let stdin = process.stdin.take().unwrap();
-        let stdout = process.stdout.take().unwrap();
-        let stderr = process.stderr.take().unwrap();
-
-        let mut writer = Box::new(BufWriter::new(stdin));
+        let mut writer = Box::new(BufWriter::new(writer));
         let (io_tx, io_rx) = crossbeam_channel::unbounded();
         let server_rpc = PluginServerRpcHandler::new(volt_id.clone(), io_tx);
+        let trace = Arc::new(LspTrace::new(
+            server_rpc.plugin_id,
+            volt_id.clone(),
+            plugin_rpc.lsp_trace(),
+            plugin_rpc.core_rpc.clone(),
+        ));
+
+        let local_trace = trace.clone();
         thread::spawn(move || {
             for msg in io_rx {
+                local_trace.sending(&msg);
                 let msg = format!("Content-Length: {}\r\n\r\n{}", msg.len(), msg);
                 let _ = writer.write(msg.as_bytes());
                 let _ = writer.flush();
@@ -188,11 +283,13 @@ fn new(
 
         let local_server_rpc = server_rpc.clone();
         let core_rpc = plugin_rpc.core_rpc.clone();
+        let local_trace = trace.clone();
         thread::spawn(move || {
-            let mut reader = Box::new(BufReader::new(stdout));
+            let mut reader = Box::new(BufReader::new(reader));
             loop {
                 match read_message(&mut reader) {
                     Ok(message_str) => {
+                        local_trace.received(&message_str);
                         handle_plugin_server_message(
                             &local_server_rpc,
                             &message_str,
@@ -209,33 +306,36 @@ fn new(
             }
         });
 
-        let core_rpc = plugin_rpc.core_rpc.clone();
-        thread::spawn(move || {
-            let mut reader = Box::new(BufReader::new(stderr));
-            loop {
-                let mut line = String::new();
-                match reader.read_line(&mut line) {
-                    Ok(n) => {
-                        if n == 0 {
+        if let Some(stderr) = stderr {
+            let core_rpc = plugin_rpc.core_rpc.clone();
+            thread::spawn(move || {
+                let mut reader = Box::new(BufReader::new(stderr));
+                loop {
+                    let mut line = String::new();
+                    match reader.read_line(&mut line) {
+                        Ok(n) => {
+                            if n == 0 {
+                                return;
+                            }
+                            core_rpc.log(
+                                log::Level::Error,
+                                format!("lsp server stderr: {}", line.trim_end()),
+                            );
+                            trace.stderr(line.trim_end());
+                        }
+                        Err(_) => {
                             return;
                         }
-                        core_rpc.log(
-                            log::Level::Error,
-                            format!("lsp server stderr: {}", line.trim_end()),
-                        );
-                    }
-                    Err(_) => {
-                        return;
                     }
                 }
-            }
-        });
+            });
+        }
 
         let host = PluginHostHandler::new(
             workspace.clone(),
-            pwd,
-            volt_id,
-            document_selector,
+            pwd.clone(),
+            volt_id.clone(),
+            document_selector.clone(),
             server_rpc.clone(),
             plugin_rpc.clone(),
         );
@@ -246,7 +346,15 @@ fn new(
             process,
             workspace,
             host,
+            document_selector,
+            volt_id,
+            pwd,
+            server_uri,
+            args,
             options,
+            command,
+            command_overridden,
+            override_error,
         })
     }
 
@@ -312,6 +420,15 @@ fn initialize(&mut self) {
                 inlay_hint: Some(InlayHintClientCapabilities {
                     ..Default::default()
                 }),
+                document_link: Some(DocumentLinkClientCapabilities::default()),
+                linked_editing_range: Some(
+                    LinkedEditingRangeClientCapabilities::default(),
+                ),
+                selection_range: Some(SelectionRangeClientCapabilities::default()),
+                folding_range: Some(FoldingRangeClientCapabilities {
+                    line_folding_only: Some(true),
+                    ..Default::default()
+                }),
                 code_action: Some(CodeActionClientCapabilities {
                     code_action_literal_support: Some(CodeActionLiteralSupport {
                         code_action_kind: CodeActionKindLiteralSupport {
@@ -330,12 +447,45 @@ fn initialize(&mut self) {
                                 CodeActionKind::SOURCE_ORGANIZE_IMPORTS
                                     .as_str()
                                     .to_string(),
+                                CodeActionKind::SOURCE_FIX_ALL.as_str().to_string(),
                             ],
                         },
                     }),
                     ..Default::default()
                 }),
                 semantic_tokens: Some(SemanticTokensClientCapabilities {
+                    requests: SemanticTokensClientCapabilitiesRequests {
+                        range: None,
+                        full: Some(SemanticTokensFullOptions::Delta {
+                            delta: Some(true),
+                        }),
+                    },
+                    token_modifiers: vec![
+                        SemanticTokenModifier::DEPRECATED,
+                        SemanticTokenModifier::ASYNC,
+                        SemanticTokenModifier::new("mutable"),
+                    ],
+                    formats: vec![TokenFormat::RELATIVE],
+                    ..Default::default()
+                }),
+                signature_help: Some(SignatureHelpClientCapabilities {
+                    signature_information: Some(SignatureInformationSettings {
+                        documentation_format: Some(vec![
+                            MarkupKind::Markdown,
+                            MarkupKind::PlainText,
+                        ]),
+                        parameter_information: Some(ParameterInformationSettings {
+                            label_offset_support: Some(true),
+                        }),
+                        active_parameter_support: Some(true),
+                    }),
+                    context_support: Some(true),
+                    ..Default::default()
+                }),
+                call_hierarchy: Some(CallHierarchyClientCapabilities {
+                    ..Default::default()
+                }),
+                code_lens: Some(CodeLensClientCapabilities {
                     ..Default::default()
                 }),
                 type_definition: Some(GotoCapability {
@@ -344,6 +494,14 @@ fn initialize(&mut self) {
                     link_support: Some(false),
                     ..Default::default()
                 }),
+                declaration: Some(GotoCapability {
+                    link_support: Some(false),
+                    ..Default::default()
+                }),
+                implementation: Some(GotoCapability {
+                    link_support: Some(false),
+                    ..Default::default()
+                }),
                 ..Default::default()
             }),
             window: Some(WindowClientCapabilities {
@@ -359,7 +517,15 @@ fn initialize(&mut self) {
                 symbol: Some(WorkspaceSymbolClientCapabilities {
                     ..Default::default()
                 }),
-                configuration: Some(false),
+                configuration: Some(true),
+                did_change_configuration: Some(
+                    DynamicRegistrationClientCapabilities {
+                        dynamic_registration: Some(false),
+                    },
+                ),
+                execute_command: Some(DynamicRegistrationClientCapabilities {
+                    ..Default::default()
+                }),
                 ..Default::default()
             }),
 
@@ -373,7 +539,12 @@ fn initialize(&mut self) {
         let params = InitializeParams {
             process_id: Some(process::id()),
             root_uri: root_uri.clone(),
-            initialization_options: self.options.clone(),
+            initialization_options: merge_options(
+                self.options.clone(),
+                self.host
+                    .language_server_config()
+                    .and_then(|server| server.options),
+            ),
             capabilities: client_capabilities,
             trace: Some(TraceValue::Verbose),
             workspace_folders: root_uri.map(|uri| {
@@ -386,6 +557,15 @@ fn initialize(&mut self) {
             locale: None,
             root_path: None,
         };
+        // lsp-types doesn't have the client capabilities of pull diagnostics
+        let mut params = serde_json::to_value(params).unwrap();
+        params["capabilities"]["textDocument"]["diagnostic"] = json!({
+            "dynamicRegistration": false,
+            "relatedDocumentSupport": false,
+        });
+        params["capabilities"]["workspace"]["diagnostics"] = json!({
+            "refreshSupport": true,
+        });
         if let Ok(value) = self.server_rpc.server_request(
             Initialize::METHOD,
             params,
@@ -393,7 +573,47 @@ fn initialize(&mut self) {
             None,
             false,
         ) {
+            let capabilities = value
+                .get("capabilities")
+                .and_then(|capabilities| capabilities.as_object())
+                .map(|capabilities| {
+                    capabilities
+                        .iter()
+                        .filter(|(_, provider)| {
+                            !provider.is_null() && provider.as_bool() != Some(false)
+                        })
+                        .map(|(name, _)| name.clone())
+                        .collect()
+                })
+                .unwrap_or_default();
+            // lsp-types doesn't know about the type hierarchy capability yet
+            self.host.type_hierarchy_provider = value
+                .get("capabilities")
+                .and_then(|capabilities| capabilities.get("typeHierarchyProvider"))
+                .map(|provider| {
+                    !provider.is_null() && provider.as_bool() != Some(false)
+                })
+                .unwrap_or(false);
+            // nor about pull diagnostics
+            self.host.pull_diagnostics = value
+                .get("capabilities")
+                .and_then(|capabilities| capabilities.get("diagnosticProvider"))
+                .and_then(|provider| {
+                    serde_json::from_value::<DiagnosticOptions>(provider.clone())
+                        .ok()
+                })
+                .map(|options| {
+                    PullDiagnostics::new(
+                        self.server_rpc.plugin_id,
+                        options,
+                        self.server_rpc.clone(),
+                        self.plugin_rpc.clone(),
+                    )
+                });
             let result: InitializeResult = serde_json::from_value(value).unwrap();
+            if let Some(server_info) = result.server_info {
+                self.host.server_name = server_info.name;
+            }
             self.host.server_capabilities = result.capabilities;
             self.server_rpc.server_notification(
                 Initialized::METHOD,
@@ -409,6 +629,22 @@ fn initialize(&mut self) {
             {
                 self.server_rpc.shutdown();
                 self.shutdown();
+            } else {
+                self.plugin_rpc.server_status(LanguageServerStatus {
+                    plugin_id: self.server_rpc.plugin_id,
+                    name: self.host.server_name.clone(),
+                    volt_id: self.volt_id.clone(),
+                    pid: self.process.as_ref().map(|process| process.id()),
+                    command: self.command.clone(),
+                    command_overridden: self.command_overridden,
+                    override_error: self.override_error.clone(),
+                    root: self.workspace.clone(),
+                    capabilities,
+                    uptime: 0,
+                });
+                if let Some(pull_diagnostics) = self.host.pull_diagnostics.as_ref() {
+                    pull_diagnostics.pull_workspace();
+                }
             }
         }
         //     move |result| {
@@ -423,9 +659,106 @@ fn initialize(&mut self) {
         // );
     }
 
+    /// Start another process of the server, which gets loaded like a new
+    /// plugin
+    fn restart(&self) {
+        if let Err(err) = Self::start(
+            self.plugin_rpc.clone(),
+            self.document_selector.clone(),
+            self.workspace.clone(),
+            self.volt_id.clone(),
+            self.pwd.clone(),
+            self.server_uri.clone(),
+            self.args.clone(),
+            self.options.clone(),
+        ) {
+            self.plugin_rpc.core_rpc.log(
+                log::Level::Error,
+                format!("can't restart lsp server {}: {err}", self.volt_id),
+            );
+        }
+    }
+
     fn shutdown(&mut self) {
-        let _ = self.process.kill();
-        let _ = self.process.wait();
+        if let Some(process) = self.process.as_mut() {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+    }
+
+    /// Start the server, or connect to it when the uri is `tcp://host:port`
+    /// or, on unix, `pipe:path`. Returns the name of the server for the logs, with the
+    /// connection to it.
+    fn connect(
+        workspace: Option<&PathBuf>,
+        server_uri: &Url,
+        args: &[String],
+    ) -> Result<(String, LspConnection)> {
+        let server = match server_uri.scheme() {
+            "file" => {
+                let path = server_uri.to_file_path().map_err(|_| anyhow!(""))?;
+                #[cfg(unix)]
+                let _ = std::process::Command::new("chmod")
+                    .arg("+x")
+                    .arg(&path)
+                    .output();
+                path.to_str().ok_or_else(|| anyhow!(""))?.to_string()
+            }
+            "urn" => server_uri.path().to_string(),
+            "tcp" => {
+                let host = server_uri
+                    .host_str()
+                    .ok_or_else(|| anyhow!("no host in {server_uri}"))?;
+                let port = server_uri
+                    .port()
+                    .ok_or_else(|| anyhow!("no port in {server_uri}"))?;
+                let stream = TcpStream::connect((host, port))?;
+                let connection = LspConnection {
+                    process: None,
+                    reader: Box::new(stream.try_clone()?),
+                    writer: Box::new(stream),
+                    stderr: None,
+                };
+                return Ok((server_uri.to_string(), connection));
+            }
+            "pipe" => {
+                return Ok((
+                    server_uri.to_string(),
+                    Self::connect_pipe(server_uri)?,
+                ));
+            }
+            _ => return Err(anyhow!("uri not supported")),
+        };
+
+        let mut process = Self::process(workspace, &server, args)?;
+        let connection = LspConnection {
+            reader: Box::new(process.stdout.take().unwrap()),
+            writer: Box::new(process.stdin.take().unwrap()),
+            stderr: process.stderr.take(),
+            process: Some(process),
+        };
+        Ok((server, connection))
+    }
+
+    #[cfg(unix)]
+    fn connect_pipe(server_uri: &Url) -> Result<LspConnection> {
+        let stream = std::os::unix::net::UnixStream::connect(server_uri.path())?;
+        Ok(LspConnection {
+            process: None,
+            reader: Box::new(stream.try_clone()?),
+            writer: Box::new(stream),
+            stderr: None,
+        })
+    }
+
+    /// Reading and writing a named pipe from two threads blocks each other
+    /// with synchronous handles, so Windows named pipes aren't supported and
+    /// only tcp works there
+    #[cfg(not(unix))]
+    fn connect_pipe(server_uri: &Url) -> Result<LspConnection> {
+        Err(anyhow!(
+            "{server_uri}: pipes are only supported on unix, use tcp instead"
+        ))
     }
 
     fn process(
@@ -451,6 +784,42 @@ fn process(
     }
 }
 
+/// The uri of the server a command of the settings starts, or connects to
+/// when it's `tcp://host:port` or, on unix, `pipe:path`. The program has to exist, and
+/// a relative path to it is relative to the workspace.
+pub fn command_server_uri(command: &str, workspace: Option<&Path>) -> Result<Url> {
+    if command.starts_with("tcp://") || command.starts_with("pipe:") {
+        return Ok(Url::parse(command)?);
+    }
+
+    let path = Path::new(command);
+    if path.components().count() == 1 {
+        let in_path = std::env::var_os("PATH")
+            .map(|paths| {
+                std::env::split_paths(&paths).any(|dir| {
+                    let path = dir.join(command);
+                    path.is_file()
+                        || (cfg!(windows) && path.with_extension("exe").is_file())
+                })
+            })
+            .unwrap_or(false);
+        if !in_path {
+            return Err(anyhow!("{command} isn't in the PATH"));
+        }
+        return Ok(Url::parse(&format!("urn:{command}"))?);
+    }
+
+    let path = match workspace {
+        Some(workspace) if path.is_relative() => workspace.join(path),
+        _ => path.to_path_buf(),
+    };
+    if !path.is_file() {
+        return Err(anyhow!("{} doesn't exist", path.display()));
+    }
+    Url::from_file_path(&path)
+        .map_err(|_| anyhow!("{} isn't an absolute path", path.display()))
+}
+
 pub struct DocumentFilter {
     /// The document must have this language id, if it exists
     pub language_id: Option<String>,
@@ -477,6 +846,34 @@ pub(crate) fn from_lsp_filter_loose(
     }
 }
 
+/// The initialization options of the plugin, with the ones of the settings
+/// merged over them, key by key
+fn merge_options(options: Option<Value>, settings: Option<Value>) -> Option<Value> {
+    match (options, settings) {
+        (Some(mut options), Some(settings)) => {
+            merge_value(&mut options, settings);
+            Some(options)
+        }
+        (options, settings) => options.or(settings),
+    }
+}
+
+fn merge_value(value: &mut Value, other: Value) {
+    match (value, other) {
+        (Value::Object(value), Value::Object(other)) => {
+            for (key, other) in other {
+                match value.get_mut(&key) {
+                    Some(value) => merge_value(value, other),
+                    None => {
+                        value.insert(key, other);
+                    }
+                }
+            }
+        }
+        (value, other) => *value = other,
+    }
+}
+
 pub enum LspHeader {
     ContentType,
     ContentLength(usize),
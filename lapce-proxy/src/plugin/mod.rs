@@ -1,35 +1,59 @@
 pub mod catalog;
+pub mod diagnostics;
 pub mod lsp;
 pub mod psp;
+pub mod semantic_tokens;
+pub mod trace;
 pub mod wasi;
 
 use anyhow::{anyhow, Result};
 use crossbeam_channel::{Receiver, Sender};
 use dyn_clone::DynClone;
 use lapce_rpc::core::CoreRpcHandler;
-use lapce_rpc::plugin::{PluginId, VoltInfo, VoltMetadata};
+use lapce_rpc::dap_types::DebuggerConfig;
+use lapce_rpc::plugin::{
+    LanguageServerConfig, LanguageServerStatus, PluginId, VoltInfo, VoltMetadata,
+};
 use lapce_rpc::proxy::ProxyRpcHandler;
 use lapce_rpc::style::LineStyle;
+use lapce_rpc::type_hierarchy::{
+    TypeHierarchyItem, TypeHierarchyPrepare, TypeHierarchyPrepareParams,
+    TypeHierarchySubtypes, TypeHierarchySubtypesParams, TypeHierarchySupertypes,
+    TypeHierarchySupertypesParams,
+};
 use lapce_rpc::{RequestId, RpcError};
 use lsp_types::notification::{DidOpenTextDocument, Notification};
 use lsp_types::request::{
-    CodeActionRequest, Completion, DocumentSymbolRequest, Formatting,
-    GotoDefinition, GotoTypeDefinition, GotoTypeDefinitionParams,
-    GotoTypeDefinitionResponse, HoverRequest, InlayHintRequest,
-    PrepareRenameRequest, References, Rename, Request, ResolveCompletionItem,
-    SelectionRangeRequest, SemanticTokensFullRequest, WorkspaceSymbol,
+    CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare,
+    CodeActionRequest, CodeLensRequest, CodeLensResolve, Completion,
+    DocumentHighlightRequest, DocumentLinkRequest, DocumentSymbolRequest,
+    ExecuteCommand, FoldingRangeRequest, Formatting, GotoDeclaration,
+    GotoDeclarationParams, GotoDeclarationResponse, GotoDefinition,
+    GotoImplementation, GotoImplementationParams, GotoImplementationResponse,
+    GotoTypeDefinition, GotoTypeDefinitionParams, GotoTypeDefinitionResponse,
+    HoverRequest, InlayHintRequest, LinkedEditingRange, OnTypeFormatting,
+    PrepareRenameRequest, RangeFormatting, References, Rename, Request,
+    ResolveCompletionItem, SelectionRangeRequest, SemanticTokensFullRequest,
+    SignatureHelpRequest, WorkspaceSymbol,
 };
 use lsp_types::{
-    CodeActionContext, CodeActionParams, CodeActionResponse, CompletionItem,
-    CompletionParams, CompletionResponse, DidOpenTextDocumentParams,
-    DocumentFormattingParams, DocumentSymbolParams, DocumentSymbolResponse,
-    FormattingOptions, GotoDefinitionParams, GotoDefinitionResponse, Hover,
-    HoverParams, InlayHint, InlayHintParams, Location, PartialResultParams,
-    Position, PrepareRenameResponse, Range, ReferenceContext, ReferenceParams,
-    RenameParams, SelectionRange, SelectionRangeParams, SemanticTokens,
-    SemanticTokensParams, SymbolInformation, TextDocumentIdentifier,
-    TextDocumentItem, TextDocumentPositionParams, TextEdit, Url,
-    VersionedTextDocumentIdentifier, WorkDoneProgressParams, WorkspaceEdit,
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams,
+    CallHierarchyPrepareParams, CodeActionContext, CodeActionKind, CodeActionParams,
+    CodeActionResponse, CodeLens, CodeLensParams, CompletionItem, CompletionParams,
+    CompletionResponse, Diagnostic, DidOpenTextDocumentParams,
+    DocumentFormattingParams, DocumentHighlight, DocumentHighlightParams,
+    DocumentLink, DocumentLinkParams, DocumentOnTypeFormattingParams,
+    DocumentRangeFormattingParams, DocumentSymbolParams, DocumentSymbolResponse,
+    ExecuteCommandParams, FoldingRange, FoldingRangeParams, FormattingOptions,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverParams, InlayHint,
+    InlayHintParams, LinkedEditingRangeParams, LinkedEditingRanges, Location,
+    PartialResultParams, Position, PrepareRenameResponse, PublishDiagnosticsParams,
+    Range, ReferenceContext, ReferenceParams, RenameParams, SelectionRange,
+    SelectionRangeParams, SemanticTokens, SemanticTokensParams, SignatureHelp,
+    SignatureHelpContext, SignatureHelpParams, SymbolInformation,
+    TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams, TextEdit,
+    Url, VersionedTextDocumentIdentifier, WorkDoneProgressParams, WorkspaceEdit,
     WorkspaceSymbolParams,
 };
 use parking_lot::Mutex;
@@ -43,6 +67,7 @@
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
+use std::time::Instant;
 use xi_rope::{Rope, RopeDelta};
 
 use crate::buffer::language_id_from_path;
@@ -98,6 +123,9 @@ pub enum PluginCatalogNotification {
     InstallVolt(VoltInfo),
     StopVolt(VoltInfo),
     StartVolt(VoltInfo),
+    LanguageServersChanged,
+    RestartServer(PluginId),
+    StopServer(PluginId),
     Shutdown,
 }
 
@@ -111,6 +139,26 @@ pub struct PluginCatalogRpcHandler {
     id: Arc<AtomicU64>,
     #[allow(dead_code, clippy::type_complexity)]
     pending: Arc<Mutex<HashMap<u64, Sender<Result<Value, RpcError>>>>>,
+    /// The priority of each loaded language server, which decides whose
+    /// results are used when several servers handle the same document
+    priorities: Arc<Mutex<HashMap<PluginId, i64>>>,
+    /// The volt id of each loaded language server, which is how the settings
+    /// name it
+    volt_ids: Arc<Mutex<HashMap<PluginId, String>>>,
+    /// The diagnostics that each language server published for a document,
+    /// which are merged before they are sent to the editor
+    #[allow(clippy::type_complexity)]
+    diagnostics: Arc<Mutex<HashMap<Url, Vec<(PluginId, Vec<Diagnostic>)>>>>,
+    /// Whether the messages exchanged with the language servers are logged
+    lsp_trace: Arc<AtomicBool>,
+    /// The `[language-servers]` settings, with the user settings merged
+    /// under the ones of the workspace
+    language_servers: Arc<Mutex<HashMap<String, LanguageServerConfig>>>,
+    /// The status of each running language server, with when it started
+    statuses: Arc<Mutex<HashMap<PluginId, (LanguageServerStatus, Instant)>>>,
+    /// The debug adapters the plugins registered, by the type of the debug
+    /// configurations they debug
+    debuggers: Arc<Mutex<HashMap<String, DebuggerConfig>>>,
 }
 
 impl PluginCatalogRpcHandler {
@@ -123,6 +171,13 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             plugin_rx: Arc::new(Mutex::new(Some(plugin_rx))),
             id: Arc::new(AtomicU64::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            priorities: Arc::new(Mutex::new(HashMap::new())),
+            volt_ids: Arc::new(Mutex::new(HashMap::new())),
+            diagnostics: Arc::new(Mutex::new(HashMap::new())),
+            lsp_trace: Arc::new(AtomicBool::new(false)),
+            language_servers: Arc::new(Mutex::new(HashMap::new())),
+            statuses: Arc::new(Mutex::new(HashMap::new())),
+            debuggers: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -247,6 +302,9 @@ fn server_notification<P: Serialize>(
         let _ = self.plugin_tx.send(rpc);
     }
 
+    /// Send the request to all the language servers that can handle it, and
+    /// answer with the result of the server with the highest priority once
+    /// all of them have answered, or with an error if none of them succeeded
     fn send_request_to_all_plugins<P, Resp>(
         &self,
         method: &'static str,
@@ -256,11 +314,11 @@ fn send_request_to_all_plugins<P, Resp>(
         cb: impl FnOnce(PluginId, Result<Resp, RpcError>) + Clone + Send + 'static,
     ) where
         P: Serialize,
-        Resp: DeserializeOwned,
+        Resp: DeserializeOwned + Send + 'static,
     {
-        let got_success = Arc::new(AtomicBool::new(false));
         let request_sent = Arc::new(AtomicUsize::new(0));
-        let err_received = Arc::new(AtomicUsize::new(0));
+        let responses = Arc::new(Mutex::new(Some(Vec::new())));
+        let priorities = self.priorities.clone();
         self.send_request(
             None,
             Some(request_sent.clone()),
@@ -269,35 +327,77 @@ fn send_request_to_all_plugins<P, Resp>(
             language_id,
             path,
             move |plugin_id, result| {
-                if got_success.load(Ordering::Acquire) {
-                    return;
-                }
-                let result = match result {
-                    Ok(value) => {
-                        if let Ok(item) = serde_json::from_value::<Resp>(value) {
-                            got_success.store(true, Ordering::Release);
-                            Ok(item)
-                        } else {
-                            Err(RpcError {
-                                code: 0,
-                                message: "deserialize error".to_string(),
-                            })
+                let result = result.and_then(|value| {
+                    serde_json::from_value::<Resp>(value).map_err(|_| RpcError {
+                        code: 0,
+                        message: "deserialize error".to_string(),
+                    })
+                });
+                let best = {
+                    let mut responses = responses.lock();
+                    let priorities = priorities.lock();
+                    let priority = |plugin_id: &PluginId| {
+                        priorities.get(plugin_id).copied().unwrap_or(0)
+                    };
+                    // no other server can do better than a success of one with
+                    // the highest priority, so there's no need to wait for them
+                    let highest = result.is_ok()
+                        && priorities.values().all(|p| *p <= priority(&plugin_id));
+                    match responses.as_mut() {
+                        Some(pending) => {
+                            pending.push((plugin_id, result));
+                            if !highest
+                                && pending.len()
+                                    < request_sent.load(Ordering::Acquire)
+                            {
+                                return;
+                            }
                         }
+                        // already answered
+                        None => return,
                     }
-                    Err(e) => Err(e),
+                    responses.take().and_then(|pending| {
+                        pending.into_iter().max_by_key(|(plugin_id, result)| {
+                            (result.is_ok(), priority(plugin_id))
+                        })
+                    })
                 };
-                if result.is_ok() {
-                    cb(plugin_id, result)
-                } else {
-                    let rx = err_received.fetch_add(1, Ordering::Relaxed) + 1;
-                    if request_sent.load(Ordering::Acquire) == rx {
-                        cb(plugin_id, result)
-                    }
+                if let Some((plugin_id, result)) = best {
+                    cb(plugin_id, result);
                 }
             },
         );
     }
 
+    fn send_request_to_plugin<P, Resp>(
+        &self,
+        plugin_id: PluginId,
+        method: &'static str,
+        params: P,
+        cb: impl FnOnce(Result<Resp, RpcError>) + Send + Clone + 'static,
+    ) where
+        P: Serialize,
+        Resp: DeserializeOwned,
+    {
+        self.send_request(
+            Some(plugin_id),
+            None,
+            method,
+            params,
+            None,
+            None,
+            move |_, result| {
+                let result = result.and_then(|value| {
+                    serde_json::from_value::<Resp>(value).map_err(|_| RpcError {
+                        code: 0,
+                        message: "deserialize error".to_string(),
+                    })
+                });
+                cb(result)
+            },
+        );
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn send_request<P: Serialize>(
         &self,
@@ -435,27 +535,24 @@ pub fn get_type_definition(
         );
     }
 
-    pub fn get_references(
+    pub fn get_declaration(
         &self,
         path: &Path,
         position: Position,
-        cb: impl FnOnce(PluginId, Result<Vec<Location>, RpcError>)
+        cb: impl FnOnce(PluginId, Result<GotoDeclarationResponse, RpcError>)
             + Clone
             + Send
             + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = References::METHOD;
-        let params = ReferenceParams {
-            text_document_position: TextDocumentPositionParams {
+        let method = GotoDeclaration::METHOD;
+        let params = GotoDeclarationParams {
+            text_document_position_params: TextDocumentPositionParams {
                 text_document: TextDocumentIdentifier { uri },
                 position,
             },
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
-            context: ReferenceContext {
-                include_declaration: false,
-            },
         };
 
         let language_id =
@@ -469,27 +566,26 @@ pub fn get_references(
         );
     }
 
-    pub fn get_code_actions(
+    pub fn get_implementation(
         &self,
         path: &Path,
         position: Position,
-        cb: impl FnOnce(PluginId, Result<CodeActionResponse, RpcError>)
+        cb: impl FnOnce(PluginId, Result<GotoImplementationResponse, RpcError>)
             + Clone
             + Send
             + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = CodeActionRequest::METHOD;
-        let params = CodeActionParams {
-            text_document: TextDocumentIdentifier { uri },
-            range: Range {
-                start: position,
-                end: position,
+        let method = GotoImplementation::METHOD;
+        let params = GotoImplementationParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
             },
-            context: CodeActionContext::default(),
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
         };
+
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
         self.send_request_to_all_plugins(
@@ -501,22 +597,29 @@ pub fn get_code_actions(
         );
     }
 
-    pub fn get_inlay_hints(
+    pub fn get_references(
         &self,
         path: &Path,
-        range: Range,
-        cb: impl FnOnce(PluginId, Result<Vec<InlayHint>, RpcError>)
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Vec<Location>, RpcError>)
             + Clone
             + Send
             + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = InlayHintRequest::METHOD;
-        let params = InlayHintParams {
-            text_document: TextDocumentIdentifier { uri },
+        let method = References::METHOD;
+        let params = ReferenceParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
             work_done_progress_params: WorkDoneProgressParams::default(),
-            range,
+            partial_result_params: PartialResultParams::default(),
+            context: ReferenceContext {
+                include_declaration: false,
+            },
         };
+
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
         self.send_request_to_all_plugins(
@@ -528,20 +631,23 @@ pub fn get_inlay_hints(
         );
     }
 
-    pub fn get_document_symbols(
+    pub fn prepare_call_hierarchy(
         &self,
         path: &Path,
-        cb: impl FnOnce(PluginId, Result<DocumentSymbolResponse, RpcError>)
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Vec<CallHierarchyItem>, RpcError>)
             + Clone
             + Send
             + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = DocumentSymbolRequest::METHOD;
-        let params = DocumentSymbolParams {
-            text_document: TextDocumentIdentifier { uri },
+        let method = CallHierarchyPrepare::METHOD;
+        let params = CallHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
             work_done_progress_params: WorkDoneProgressParams::default(),
-            partial_result_params: PartialResultParams::default(),
         };
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
@@ -554,39 +660,60 @@ pub fn get_document_symbols(
         );
     }
 
-    pub fn get_workspace_symbols(
+    /// The calls to the item, asked to the language server that prepared it
+    pub fn call_hierarchy_incoming(
         &self,
-        query: String,
-        cb: impl FnOnce(PluginId, Result<Vec<SymbolInformation>, RpcError>)
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        cb: impl FnOnce(Result<Vec<CallHierarchyIncomingCall>, RpcError>)
+            + Send
             + Clone
+            + 'static,
+    ) {
+        let method = CallHierarchyIncomingCalls::METHOD;
+        let params = CallHierarchyIncomingCallsParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        self.send_request_to_plugin(plugin_id, method, params, cb);
+    }
+
+    /// The calls the item makes, asked to the language server that prepared
+    /// it
+    pub fn call_hierarchy_outgoing(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        cb: impl FnOnce(Result<Vec<CallHierarchyOutgoingCall>, RpcError>)
             + Send
+            + Clone
             + 'static,
     ) {
-        let method = WorkspaceSymbol::METHOD;
-        let params = WorkspaceSymbolParams {
-            query,
+        let method = CallHierarchyOutgoingCalls::METHOD;
+        let params = CallHierarchyOutgoingCallsParams {
+            item,
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
         };
-        self.send_request_to_all_plugins(method, params, None, None, cb);
+        self.send_request_to_plugin(plugin_id, method, params, cb);
     }
 
-    pub fn get_document_formatting(
+    pub fn prepare_type_hierarchy(
         &self,
         path: &Path,
-        cb: impl FnOnce(PluginId, Result<Vec<TextEdit>, RpcError>)
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Vec<TypeHierarchyItem>, RpcError>)
             + Clone
             + Send
             + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = Formatting::METHOD;
-        let params = DocumentFormattingParams {
-            text_document: TextDocumentIdentifier { uri },
-            options: FormattingOptions {
-                tab_size: 4,
-                insert_spaces: true,
-                ..Default::default()
+        let method = TypeHierarchyPrepare::METHOD;
+        let params = TypeHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
             },
             work_done_progress_params: WorkDoneProgressParams::default(),
         };
@@ -601,20 +728,66 @@ pub fn get_document_formatting(
         );
     }
 
-    pub fn prepare_rename(
+    /// The supertypes of the item, asked to the language server that
+    /// prepared it
+    pub fn type_hierarchy_supertypes(
+        &self,
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        cb: impl FnOnce(Result<Vec<TypeHierarchyItem>, RpcError>)
+            + Send
+            + Clone
+            + 'static,
+    ) {
+        let method = TypeHierarchySupertypes::METHOD;
+        let params = TypeHierarchySupertypesParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        self.send_request_to_plugin(plugin_id, method, params, cb);
+    }
+
+    /// The subtypes of the item, asked to the language server that prepared
+    /// it
+    pub fn type_hierarchy_subtypes(
+        &self,
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        cb: impl FnOnce(Result<Vec<TypeHierarchyItem>, RpcError>)
+            + Send
+            + Clone
+            + 'static,
+    ) {
+        let method = TypeHierarchySubtypes::METHOD;
+        let params = TypeHierarchySubtypesParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        self.send_request_to_plugin(plugin_id, method, params, cb);
+    }
+
+    pub fn get_code_actions(
         &self,
         path: &Path,
         position: Position,
-        cb: impl FnOnce(PluginId, Result<PrepareRenameResponse, RpcError>)
+        cb: impl FnOnce(PluginId, Result<CodeActionResponse, RpcError>)
             + Clone
             + Send
             + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = PrepareRenameRequest::METHOD;
-        let params = TextDocumentPositionParams {
+        let method = CodeActionRequest::METHOD;
+        let params = CodeActionParams {
             text_document: TextDocumentIdentifier { uri },
-            position,
+            range: Range {
+                start: position,
+                end: position,
+            },
+            context: CodeActionContext::default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
         };
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
@@ -627,25 +800,29 @@ pub fn prepare_rename(
         );
     }
 
-    pub fn rename(
+    /// The code actions of the kinds in the range, which the language
+    /// servers should filter their code actions with
+    pub fn get_code_actions_of_kinds(
         &self,
         path: &Path,
-        position: Position,
-        new_name: String,
-        cb: impl FnOnce(PluginId, Result<WorkspaceEdit, RpcError>)
+        range: Range,
+        kinds: Vec<CodeActionKind>,
+        cb: impl FnOnce(PluginId, Result<CodeActionResponse, RpcError>)
             + Clone
             + Send
             + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = Rename::METHOD;
-        let params = RenameParams {
-            text_document_position: TextDocumentPositionParams {
-                text_document: TextDocumentIdentifier { uri },
-                position,
+        let method = CodeActionRequest::METHOD;
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            context: CodeActionContext {
+                only: Some(kinds),
+                ..Default::default()
             },
-            new_name,
             work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
         };
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
@@ -658,18 +835,27 @@ pub fn rename(
         );
     }
 
-    pub fn get_semantic_tokens(
+    /// The quick fixes of the diagnostic, which the servers are given as the
+    /// context, since many of them only fix the diagnostics they're given
+    pub fn get_quick_fixes(
         &self,
         path: &Path,
-        cb: impl FnOnce(PluginId, Result<SemanticTokens, RpcError>)
+        diagnostic: Diagnostic,
+        cb: impl FnOnce(PluginId, Result<CodeActionResponse, RpcError>)
             + Clone
             + Send
             + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = SemanticTokensFullRequest::METHOD;
-        let params = SemanticTokensParams {
+        let method = CodeActionRequest::METHOD;
+        let params = CodeActionParams {
             text_document: TextDocumentIdentifier { uri },
+            range: diagnostic.range,
+            context: CodeActionContext {
+                diagnostics: vec![diagnostic],
+                only: Some(vec![CodeActionKind::QUICKFIX]),
+                ..Default::default()
+            },
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
         };
@@ -684,22 +870,21 @@ pub fn get_semantic_tokens(
         );
     }
 
-    pub fn get_selection_range(
+    pub fn get_inlay_hints(
         &self,
         path: &Path,
-        positions: Vec<Position>,
-        cb: impl FnOnce(PluginId, Result<Vec<SelectionRange>, RpcError>)
+        range: Range,
+        cb: impl FnOnce(PluginId, Result<Vec<InlayHint>, RpcError>)
             + Clone
             + Send
             + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = SelectionRangeRequest::METHOD;
-        let params = SelectionRangeParams {
+        let method = InlayHintRequest::METHOD;
+        let params = InlayHintParams {
             text_document: TextDocumentIdentifier { uri },
-            positions,
             work_done_progress_params: WorkDoneProgressParams::default(),
-            partial_result_params: Default::default(),
+            range,
         };
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
@@ -712,24 +897,23 @@ pub fn get_selection_range(
         );
     }
 
-    pub fn hover(
+    pub fn get_code_lens(
         &self,
         path: &Path,
-        position: Position,
-        cb: impl FnOnce(PluginId, Result<Hover, RpcError>) + Clone + Send + 'static,
+        cb: impl FnOnce(PluginId, Result<Vec<CodeLens>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = HoverRequest::METHOD;
-        let params = HoverParams {
-            text_document_position_params: TextDocumentPositionParams {
-                text_document: TextDocumentIdentifier { uri },
-                position,
-            },
+        let method = CodeLensRequest::METHOD;
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier { uri },
             work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
         };
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
-
         self.send_request_to_all_plugins(
             method,
             params,
@@ -739,42 +923,604 @@ pub fn hover(
         );
     }
 
-    pub fn completion(
+    /// Resolve the lenses that came without a command, and give all of them
+    /// back once every resolve has answered
+    pub fn resolve_code_lenses(
+        &self,
+        plugin_id: PluginId,
+        lenses: Vec<CodeLens>,
+        cb: impl FnOnce(Vec<CodeLens>) + Send + 'static,
+    ) {
+        let unresolved = lenses
+            .iter()
+            .enumerate()
+            .filter(|(_, lens)| lens.command.is_none())
+            .map(|(i, lens)| (i, lens.clone()))
+            .collect::<Vec<_>>();
+        if unresolved.is_empty() {
+            cb(lenses);
+            return;
+        }
+
+        let pending = Arc::new(Mutex::new((lenses, unresolved.len(), Some(cb))));
+        for (i, lens) in unresolved {
+            let pending = pending.clone();
+            self.send_request_to_plugin(
+                plugin_id,
+                CodeLensResolve::METHOD,
+                lens,
+                move |result: Result<CodeLens, RpcError>| {
+                    let done = {
+                        let mut pending = pending.lock();
+                        if let Ok(lens) = result {
+                            pending.0[i] = lens;
+                        }
+                        pending.1 -= 1;
+                        if pending.1 == 0 {
+                            pending
+                                .2
+                                .take()
+                                .map(|cb| (cb, std::mem::take(&mut pending.0)))
+                        } else {
+                            None
+                        }
+                    };
+                    if let Some((cb, lenses)) = done {
+                        cb(lenses);
+                    }
+                },
+            );
+        }
+    }
+
+    /// Run a command of the language server, like the one of a code lens
+    pub fn execute_command(
+        &self,
+        plugin_id: PluginId,
+        command: lsp_types::Command,
+        cb: impl FnOnce(Result<Value, RpcError>) + Send + Clone + 'static,
+    ) {
+        let method = ExecuteCommand::METHOD;
+        let params = ExecuteCommandParams {
+            command: command.command,
+            arguments: command.arguments.unwrap_or_default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        self.send_request_to_plugin(plugin_id, method, params, cb);
+    }
+
+    pub fn get_folding_ranges(
         &self,
-        request_id: usize,
         path: &Path,
-        input: String,
-        position: Position,
+        cb: impl FnOnce(PluginId, Result<Vec<FoldingRange>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
-        let method = Completion::METHOD;
-        let params = CompletionParams {
-            text_document_position: TextDocumentPositionParams {
-                text_document: TextDocumentIdentifier { uri },
-                position,
-            },
+        let method = FoldingRangeRequest::METHOD;
+        let params = FoldingRangeParams {
+            text_document: TextDocumentIdentifier { uri },
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
-            context: None,
         };
-
-        let core_rpc = self.core_rpc.clone();
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
-        self.send_request(
-            None,
-            None,
+        self.send_request_to_all_plugins(
             method,
             params,
             language_id,
             Some(path.to_path_buf()),
-            move |plugin_id, result| {
-                if let Ok(value) = result {
+            cb,
+        );
+    }
+
+    pub fn get_document_symbols(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(PluginId, Result<DocumentSymbolResponse, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = DocumentSymbolRequest::METHOD;
+        let params = DocumentSymbolParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    /// Ask every language server for the symbols of the workspace matching
+    /// the query, and give the symbols of all of them together once they
+    /// all answered
+    pub fn get_workspace_symbols(
+        &self,
+        query: String,
+        cb: impl FnOnce(Result<Vec<SymbolInformation>, RpcError>) + Send + 'static,
+    ) {
+        let method = WorkspaceSymbol::METHOD;
+        let params = WorkspaceSymbolParams {
+            query,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let request_sent = Arc::new(AtomicUsize::new(0));
+        // the number of answers, the symbols, and the error if none of the
+        // servers could answer
+        let received = Arc::new(Mutex::new((0, Vec::new(), None)));
+        let cb = Arc::new(Mutex::new(Some(cb)));
+        self.send_request(
+            None,
+            Some(request_sent.clone()),
+            method,
+            params,
+            None,
+            None,
+            move |_, result| {
+                let mut received = received.lock();
+                received.0 += 1;
+                match result.and_then(|value| {
+                    serde_json::from_value::<Option<Vec<SymbolInformation>>>(value)
+                        .map_err(|_| RpcError {
+                            code: 0,
+                            message: "deserialize error".to_string(),
+                        })
+                }) {
+                    Ok(symbols) => {
+                        received.1.extend(symbols.unwrap_or_default());
+                        received.2 = Some(Ok(()));
+                    }
+                    Err(err) => {
+                        if received.2.is_none() {
+                            received.2 = Some(Err(err));
+                        }
+                    }
+                }
+                if received.0 < request_sent.load(Ordering::Acquire) {
+                    return;
+                }
+                if let Some(cb) = cb.lock().take() {
+                    let symbols = std::mem::take(&mut received.1);
+                    cb(match received.2.take() {
+                        Some(Err(err)) => Err(err),
+                        _ => Ok(symbols),
+                    });
+                }
+            },
+        );
+    }
+
+    /// Ask the language servers for the edits formatting the document, or
+    /// only the server with the name if there is one
+    pub fn get_document_formatting(
+        &self,
+        path: &Path,
+        server: Option<String>,
+        cb: impl FnOnce(PluginId, Result<Vec<TextEdit>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = Formatting::METHOD;
+        let params = DocumentFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            options: formatting_options(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        self.send_formatting_request(method, params, path, server, cb);
+    }
+
+    /// Ask the language servers for the edits formatting the range of the
+    /// document, or only the server with the name if there is one
+    pub fn get_document_range_formatting(
+        &self,
+        path: &Path,
+        range: Range,
+        server: Option<String>,
+        cb: impl FnOnce(PluginId, Result<Vec<TextEdit>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = RangeFormatting::METHOD;
+        let params = DocumentRangeFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            options: formatting_options(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        self.send_formatting_request(method, params, path, server, cb);
+    }
+
+    /// Ask the language servers for the edits formatting the document around
+    /// the character that was just typed at the position, which only the
+    /// servers that have it as a trigger character answer
+    pub fn get_on_type_formatting(
+        &self,
+        path: &Path,
+        position: Position,
+        ch: String,
+        cb: impl FnOnce(PluginId, Result<Vec<TextEdit>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = OnTypeFormatting::METHOD;
+        let params = DocumentOnTypeFormattingParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            ch,
+            options: formatting_options(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    fn send_formatting_request<P: Serialize>(
+        &self,
+        method: &'static str,
+        params: P,
+        path: &Path,
+        server: Option<String>,
+        cb: impl FnOnce(PluginId, Result<Vec<TextEdit>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        if let Some(server) = server {
+            match self.server_named(&server) {
+                Some(plugin_id) => {
+                    self.send_request_to_plugin(
+                        plugin_id,
+                        method,
+                        params,
+                        move |result| cb(plugin_id, result),
+                    );
+                }
+                None => cb(
+                    PluginId(0),
+                    Err(RpcError {
+                        code: 0,
+                        message: format!("language server {server} isn't running"),
+                    }),
+                ),
+            }
+            return;
+        }
+
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn prepare_rename(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<PrepareRenameResponse, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = PrepareRenameRequest::METHOD;
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position,
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn rename(
+        &self,
+        path: &Path,
+        position: Position,
+        new_name: String,
+        cb: impl FnOnce(PluginId, Result<WorkspaceEdit, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = Rename::METHOD;
+        let params = RenameParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            new_name,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_semantic_tokens(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(PluginId, Result<SemanticTokens, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = SemanticTokensFullRequest::METHOD;
+        let params = SemanticTokensParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_selection_range(
+        &self,
+        path: &Path,
+        positions: Vec<Position>,
+        cb: impl FnOnce(PluginId, Result<Vec<SelectionRange>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = SelectionRangeRequest::METHOD;
+        let params = SelectionRangeParams {
+            text_document: TextDocumentIdentifier { uri },
+            positions,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: Default::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_document_highlights(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<DocumentHighlight>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = DocumentHighlightRequest::METHOD;
+        let params = DocumentHighlightParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_document_links(
+        &self,
+        path: &Path,
+        cb: impl FnOnce(PluginId, Result<Option<Vec<DocumentLink>>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = DocumentLinkRequest::METHOD;
+        let params = DocumentLinkParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    /// The ranges that are edited together with the one at the position,
+    /// like the names of an opening and a closing tag
+    pub fn get_linked_editing_ranges(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Option<LinkedEditingRanges>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = LinkedEditingRange::METHOD;
+        let params = LinkedEditingRangeParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    /// Ask the language servers for the signature of the call at the
+    /// position, which the ones that answer null don't have
+    pub fn signature_help(
+        &self,
+        path: &Path,
+        position: Position,
+        context: Option<SignatureHelpContext>,
+        cb: impl FnOnce(PluginId, Result<SignatureHelp, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = SignatureHelpRequest::METHOD;
+        let params = SignatureHelpParams {
+            context,
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn hover(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(PluginId, Result<Hover, RpcError>) + Clone + Send + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = HoverRequest::METHOD;
+        let params = HoverParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn completion(
+        &self,
+        request_id: usize,
+        path: &Path,
+        input: String,
+        position: Position,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = Completion::METHOD;
+        let params = CompletionParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+            context: None,
+        };
+
+        let core_rpc = self.core_rpc.clone();
+        let priorities = self.priorities.clone();
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request(
+            None,
+            None,
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            move |plugin_id, result| {
+                if let Ok(value) = result {
                     if let Ok(resp) =
                         serde_json::from_value::<CompletionResponse>(value)
                     {
-                        core_rpc
-                            .completion_response(request_id, input, resp, plugin_id);
+                        let priority =
+                            priorities.lock().get(&plugin_id).copied().unwrap_or(0);
+                        core_rpc.completion_response(
+                            request_id, input, resp, plugin_id, priority,
+                        );
                     }
                 }
             },
@@ -850,6 +1596,131 @@ pub fn plugin_server_loaded(
         ))
     }
 
+    /// Remember the loaded language server, with the priority the settings
+    /// give it
+    pub fn server_started(
+        &self,
+        plugin_id: PluginId,
+        volt_id: String,
+        priority: i64,
+    ) {
+        self.priorities.lock().insert(plugin_id, priority);
+        self.volt_ids.lock().insert(plugin_id, volt_id);
+    }
+
+    /// The loaded language server with the name, with or without the author
+    /// of its plugin
+    fn server_named(&self, name: &str) -> Option<PluginId> {
+        self.volt_ids
+            .lock()
+            .iter()
+            .find(|(_, volt_id)| {
+                volt_id.as_str() == name
+                    || volt_id.split_once('.').map(|(_, n)| n) == Some(name)
+            })
+            .map(|(plugin_id, _)| *plugin_id)
+    }
+
+    /// Merge the diagnostics the language server published for the document
+    /// with those of the other servers, and send all of them to the editor
+    pub fn publish_diagnostics(
+        &self,
+        plugin_id: PluginId,
+        params: PublishDiagnosticsParams,
+    ) {
+        let mut diagnostics = self.diagnostics.lock();
+        let published = diagnostics.entry(params.uri.clone()).or_default();
+        published.retain(|(id, _)| *id != plugin_id);
+        if !params.diagnostics.is_empty() {
+            published.push((plugin_id, params.diagnostics));
+        }
+        let merged = self.merged_diagnostics(published);
+        if published.is_empty() {
+            diagnostics.remove(&params.uri);
+        }
+        self.core_rpc.publish_diagnostics(PublishDiagnosticsParams {
+            uri: params.uri,
+            diagnostics: merged,
+            version: params.version,
+        });
+    }
+
+    /// Remember the status of the initialized language server, for the
+    /// editor to show
+    pub fn server_status(&self, status: LanguageServerStatus) {
+        self.statuses
+            .lock()
+            .insert(status.plugin_id, (status, Instant::now()));
+    }
+
+    /// The status of the running language servers, in the order they were
+    /// started
+    pub fn language_server_statuses(&self) -> Vec<LanguageServerStatus> {
+        let locked = self.statuses.lock();
+        let mut statuses = locked.values().collect::<Vec<_>>();
+        statuses.sort_by_key(|(_, started)| *started);
+        statuses
+            .into_iter()
+            .map(|(status, started)| LanguageServerStatus {
+                uptime: started.elapsed().as_secs(),
+                ..status.clone()
+            })
+            .collect()
+    }
+
+    pub fn restart_server(&self, plugin_id: PluginId) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::RestartServer(
+            plugin_id,
+        ))
+    }
+
+    pub fn stop_server(&self, plugin_id: PluginId) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::StopServer(plugin_id))
+    }
+
+    /// Forget the stopped language server, and remove its diagnostics
+    pub fn server_stopped(&self, plugin_id: PluginId) {
+        self.priorities.lock().remove(&plugin_id);
+        self.statuses.lock().remove(&plugin_id);
+        self.volt_ids.lock().remove(&plugin_id);
+        let mut diagnostics = self.diagnostics.lock();
+        let mut emptied = Vec::new();
+        for (uri, published) in diagnostics.iter_mut() {
+            if !published.iter().any(|(id, _)| *id == plugin_id) {
+                continue;
+            }
+            published.retain(|(id, _)| *id != plugin_id);
+            self.core_rpc.publish_diagnostics(PublishDiagnosticsParams {
+                uri: uri.clone(),
+                diagnostics: self.merged_diagnostics(published),
+                version: None,
+            });
+            if published.is_empty() {
+                emptied.push(uri.clone());
+            }
+        }
+        for uri in emptied {
+            diagnostics.remove(&uri);
+        }
+    }
+
+    /// The diagnostics of all the servers, those of the servers with the
+    /// highest priority first
+    fn merged_diagnostics(
+        &self,
+        published: &[(PluginId, Vec<Diagnostic>)],
+    ) -> Vec<Diagnostic> {
+        let priorities = self.priorities.lock();
+        let mut published = published.iter().collect::<Vec<_>>();
+        published.sort_by_key(|(plugin_id, _)| {
+            std::cmp::Reverse(priorities.get(plugin_id).copied().unwrap_or(0))
+        });
+        published
+            .into_iter()
+            .flat_map(|(_, diagnostics)| diagnostics.iter().cloned())
+            .collect()
+    }
+
     pub fn install_volt(&self, volt: VoltInfo) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::InstallVolt(volt))
     }
@@ -861,6 +1732,57 @@ pub fn stop_volt(&self, volt: VoltInfo) -> Result<()> {
     pub fn start_volt(&self, volt: VoltInfo) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::StartVolt(volt))
     }
+
+    pub fn set_lsp_trace(&self, enabled: bool) {
+        self.lsp_trace.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn lsp_trace(&self) -> Arc<AtomicBool> {
+        self.lsp_trace.clone()
+    }
+
+    pub fn set_language_servers(
+        &self,
+        language_servers: HashMap<String, LanguageServerConfig>,
+    ) {
+        *self.language_servers.lock() = language_servers;
+    }
+
+    /// Replace the `[language-servers]` settings, and send the servers their
+    /// new settings
+    pub fn update_language_servers(
+        &self,
+        language_servers: HashMap<String, LanguageServerConfig>,
+    ) {
+        self.set_language_servers(language_servers);
+        let _ = self
+            .catalog_notification(PluginCatalogNotification::LanguageServersChanged);
+    }
+
+    /// The settings of the servers of the plugin, which name them by the
+    /// plugin, with or without its author
+    pub fn language_server_config(
+        &self,
+        volt_id: &str,
+    ) -> Option<LanguageServerConfig> {
+        let volt_name = volt_id.split_once('.').map(|(_, name)| name);
+        self.language_servers
+            .lock()
+            .iter()
+            .find(|(name, _)| {
+                name.as_str() == volt_id || Some(name.as_str()) == volt_name
+            })
+            .map(|(_, server)| server.clone())
+    }
+
+    pub fn register_debugger(&self, ty: String, debugger: DebuggerConfig) {
+        self.debuggers.lock().insert(ty, debugger);
+    }
+
+    /// The debug adapter a plugin registered for the type
+    pub fn debugger(&self, ty: &str) -> Option<DebuggerConfig> {
+        self.debuggers.lock().get(ty).cloned()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -885,6 +1807,14 @@ pub enum PluginNotification {
     },
 }
 
+fn formatting_options() -> FormattingOptions {
+    FormattingOptions {
+        tab_size: 4,
+        insert_spaces: true,
+        ..Default::default()
+    }
+}
+
 pub fn download_volt(
     volt: VoltInfo,
     wasm: bool,
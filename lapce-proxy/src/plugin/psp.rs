@@ -14,31 +14,41 @@
 use jsonrpc_lite::{Id, JsonRpc, Params};
 use lapce_core::{buffer::rope_text::RopeText, encoding::offset_utf16_to_utf8};
 use lapce_rpc::{
-    plugin::PluginId,
-    style::{LineStyle, Style},
+    plugin::{LanguageServerConfig, PluginId},
+    style::{LineStyle, Style, StyleModifiers},
+    type_hierarchy::{
+        TypeHierarchyPrepare, TypeHierarchySubtypes, TypeHierarchySupertypes,
+    },
     RpcError,
 };
 use lsp_types::{
     notification::{
-        DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument,
-        Initialized, Notification, Progress, PublishDiagnostics,
+        DidChangeConfiguration, DidChangeTextDocument, DidOpenTextDocument,
+        DidSaveTextDocument, Initialized, Notification, Progress,
+        PublishDiagnostics,
     },
     request::{
-        CodeActionRequest, Completion, DocumentSymbolRequest, Formatting,
-        GotoDefinition, GotoTypeDefinition, HoverRequest, Initialize,
-        InlayHintRequest, PrepareRenameRequest, References, RegisterCapability,
-        Rename, ResolveCompletionItem, SelectionRangeRequest,
-        SemanticTokensFullRequest, WorkDoneProgressCreate, WorkspaceSymbol,
+        CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls,
+        CallHierarchyPrepare, CodeActionRequest, CodeLensRequest, CodeLensResolve,
+        Completion, DocumentHighlightRequest, DocumentLinkRequest,
+        DocumentSymbolRequest, ExecuteCommand, FoldingRangeRequest, Formatting,
+        GotoDeclaration, GotoDefinition, GotoImplementation, GotoTypeDefinition,
+        HoverRequest, Initialize, InlayHintRequest, LinkedEditingRange,
+        OnTypeFormatting, PrepareRenameRequest, RangeFormatting, References,
+        RegisterCapability, Rename, ResolveCompletionItem, SelectionRangeRequest,
+        SemanticTokensFullRequest, SignatureHelpRequest, WorkDoneProgressCreate,
+        WorkspaceConfiguration, WorkspaceSymbol,
     },
-    CodeActionProviderCapability, DidChangeTextDocumentParams,
-    DidSaveTextDocumentParams, DocumentSelector, HoverProviderCapability, OneOf,
-    ProgressParams, PublishDiagnosticsParams, Range, Registration,
-    RegistrationParams, SemanticTokens, SemanticTokensLegend,
-    SemanticTokensServerCapabilities, ServerCapabilities,
-    TextDocumentContentChangeEvent, TextDocumentIdentifier,
-    TextDocumentSaveRegistrationOptions, TextDocumentSyncCapability,
-    TextDocumentSyncKind, TextDocumentSyncSaveOptions,
-    VersionedTextDocumentIdentifier,
+    CallHierarchyServerCapability, CodeActionProviderCapability,
+    ConfigurationParams, DidChangeConfigurationParams, DidChangeTextDocumentParams,
+    DidSaveTextDocumentParams, DocumentOnTypeFormattingParams, DocumentSelector,
+    HoverProviderCapability, OneOf, ProgressParams, PublishDiagnosticsParams, Range,
+    Registration, RegistrationParams, SemanticTokens, SemanticTokensFullOptions,
+    SemanticTokensLegend, SemanticTokensServerCapabilities, ServerCapabilities,
+    SignatureHelpParams, SignatureHelpTriggerKind, TextDocumentContentChangeEvent,
+    TextDocumentIdentifier, TextDocumentSaveRegistrationOptions,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncSaveOptions,
+    Url, VersionedTextDocumentIdentifier,
 };
 use parking_lot::Mutex;
 use psp_types::{Request, StartLspServer, StartLspServerParams};
@@ -46,8 +56,12 @@
 use serde_json::Value;
 use xi_rope::{Rope, RopeDelta};
 
+use crate::dap::{RegisterDebugger, RegisterDebuggerParams};
+
 use super::{
+    diagnostics::{PullDiagnostics, WorkspaceDiagnosticRefresh},
     lsp::{DocumentFilter, LspClient},
+    semantic_tokens::SemanticTokensCache,
     PluginCatalogRpcHandler,
 };
 
@@ -91,6 +105,10 @@ fn call(self: Box<F>, result: Result<Resp, Error>) {
 
 pub enum PluginHandlerNotification {
     Initialize,
+    /// The settings of the language servers changed
+    DidChangeConfiguration,
+    /// Start the server again, before it's shut down
+    Restart,
     Shutdown,
 }
 
@@ -170,6 +188,22 @@ fn handle_handler_notification(
         &mut self,
         notification: PluginHandlerNotification,
     );
+    fn handle_did_open_text_document(&mut self, path: &Path);
+    fn handle_semantic_tokens_request(
+        &mut self,
+        path: &Path,
+        rh: ResponseHandler<Value, RpcError>,
+    );
+    fn handle_signature_help_request(
+        &mut self,
+        params: Value,
+        rh: ResponseHandler<Value, RpcError>,
+    );
+    fn handle_on_type_formatting_request(
+        &mut self,
+        params: Value,
+        rh: ResponseHandler<Value, RpcError>,
+    );
     fn handle_did_save_text_document(
         &self,
         language_id: String,
@@ -403,7 +437,21 @@ pub fn mainloop<H>(&self, handler: &mut H)
                         .document_supported(language_id.as_deref(), path.as_deref())
                         && handler.method_registered(method)
                     {
-                        self.send_server_request(id, method, params, rh);
+                        match (method, path.as_deref()) {
+                            (SemanticTokensFullRequest::METHOD, Some(path)) => {
+                                handler.handle_semantic_tokens_request(path, rh);
+                            }
+                            (SignatureHelpRequest::METHOD, _) => {
+                                handler.handle_signature_help_request(params, rh);
+                            }
+                            (OnTypeFormatting::METHOD, _) => {
+                                handler
+                                    .handle_on_type_formatting_request(params, rh);
+                            }
+                            _ => {
+                                self.send_server_request(id, method, params, rh);
+                            }
+                        }
                     } else {
                         rh.invoke(Err(RpcError {
                             code: 0,
@@ -422,6 +470,11 @@ pub fn mainloop<H>(&self, handler: &mut H)
                         && handler.method_registered(method)
                     {
                         self.send_server_notification(method, params);
+                        if method == DidOpenTextDocument::METHOD {
+                            if let Some(path) = path.as_deref() {
+                                handler.handle_did_open_text_document(path);
+                            }
+                        }
                     }
                 }
                 PluginServerRpc::HostRequest { id, method, params } => {
@@ -532,6 +585,15 @@ pub struct PluginHostHandler {
     catalog_rpc: PluginCatalogRpcHandler,
     pub server_rpc: PluginServerRpcHandler,
     pub server_capabilities: ServerCapabilities,
+    pub type_hierarchy_provider: bool,
+    /// The diagnostics of the documents, when the server has them pulled
+    /// instead of publishing them
+    pub pull_diagnostics: Option<PullDiagnostics>,
+    semantic_tokens: SemanticTokensCache,
+    /// The name the language server gives itself, or the id of its plugin
+    pub server_name: String,
+    /// The settings the server was last given
+    settings: Option<Value>,
     server_registrations: ServerRegistrations,
 }
 
@@ -548,14 +610,22 @@ pub fn new(
             .iter()
             .map(DocumentFilter::from_lsp_filter_loose)
             .collect();
+        let settings = catalog_rpc
+            .language_server_config(&volt_id)
+            .and_then(|server| server.settings);
         Self {
             pwd,
             workspace,
+            settings,
+            server_name: volt_id.clone(),
             volt_id,
             document_selector,
             catalog_rpc,
             server_rpc,
             server_capabilities: ServerCapabilities::default(),
+            type_hierarchy_provider: false,
+            pull_diagnostics: None,
+            semantic_tokens: SemanticTokensCache::default(),
             server_registrations: ServerRegistrations::default(),
         }
     }
@@ -649,6 +719,12 @@ pub fn method_registered(&mut self, method: &'static str) -> bool {
             GotoTypeDefinition::METHOD => {
                 self.server_capabilities.type_definition_provider.is_some()
             }
+            GotoDeclaration::METHOD => {
+                self.server_capabilities.declaration_provider.is_some()
+            }
+            GotoImplementation::METHOD => {
+                self.server_capabilities.implementation_provider.is_some()
+            }
             References::METHOD => self
                 .server_capabilities
                 .references_provider
@@ -676,12 +752,47 @@ pub fn method_registered(&mut self, method: &'static str) -> bool {
                     OneOf::Right(_) => true,
                 })
                 .unwrap_or(false),
+            RangeFormatting::METHOD => self
+                .server_capabilities
+                .document_range_formatting_provider
+                .as_ref()
+                .map(|f| match f {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
             SemanticTokensFullRequest::METHOD => {
                 self.server_capabilities.semantic_tokens_provider.is_some()
             }
             InlayHintRequest::METHOD => {
                 self.server_capabilities.inlay_hint_provider.is_some()
             }
+            SignatureHelpRequest::METHOD => {
+                self.server_capabilities.signature_help_provider.is_some()
+            }
+            OnTypeFormatting::METHOD => self
+                .server_capabilities
+                .document_on_type_formatting_provider
+                .is_some(),
+            FoldingRangeRequest::METHOD => {
+                self.server_capabilities.folding_range_provider.is_some()
+            }
+            DocumentLinkRequest::METHOD => {
+                self.server_capabilities.document_link_provider.is_some()
+            }
+            LinkedEditingRange::METHOD => self
+                .server_capabilities
+                .linked_editing_range_provider
+                .is_some(),
+            DocumentHighlightRequest::METHOD => self
+                .server_capabilities
+                .document_highlight_provider
+                .as_ref()
+                .map(|h| match h {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
             DocumentSymbolRequest::METHOD => {
                 self.server_capabilities.document_symbol_provider.is_some()
             }
@@ -695,6 +806,32 @@ pub fn method_registered(&mut self, method: &'static str) -> bool {
             SelectionRangeRequest::METHOD => {
                 self.server_capabilities.selection_range_provider.is_some()
             }
+            CallHierarchyPrepare::METHOD
+            | CallHierarchyIncomingCalls::METHOD
+            | CallHierarchyOutgoingCalls::METHOD => self
+                .server_capabilities
+                .call_hierarchy_provider
+                .as_ref()
+                .map(|c| match c {
+                    CallHierarchyServerCapability::Simple(is_capable) => *is_capable,
+                    CallHierarchyServerCapability::Options(_) => true,
+                })
+                .unwrap_or(false),
+            CodeLensRequest::METHOD => {
+                self.server_capabilities.code_lens_provider.is_some()
+            }
+            CodeLensResolve::METHOD => self
+                .server_capabilities
+                .code_lens_provider
+                .as_ref()
+                .and_then(|c| c.resolve_provider)
+                .unwrap_or(false),
+            ExecuteCommand::METHOD => {
+                self.server_capabilities.execute_command_provider.is_some()
+            }
+            TypeHierarchyPrepare::METHOD
+            | TypeHierarchySupertypes::METHOD
+            | TypeHierarchySubtypes::METHOD => self.type_hierarchy_provider,
             _ => false,
         }
     }
@@ -789,6 +926,27 @@ pub fn handle_request(
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 self.register_capabilities(params.registrations);
             }
+            WorkspaceConfiguration::METHOD => {
+                let params: ConfigurationParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let items = params
+                    .items
+                    .iter()
+                    .map(|item| {
+                        configuration_section(
+                            self.settings.as_ref(),
+                            item.section.as_deref(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                self.server_rpc.send_host_success(id, items);
+            }
+            WorkspaceDiagnosticRefresh::METHOD => {
+                self.server_rpc.send_host_success(id, Value::Null);
+                if let Some(pull_diagnostics) = self.pull_diagnostics.as_ref() {
+                    pull_diagnostics.refresh();
+                }
+            }
             _ => {
                 self.server_rpc.send_host_error(
                     id,
@@ -828,15 +986,34 @@ pub fn handle_notification(
                     );
                 });
             }
+            RegisterDebugger::METHOD => {
+                let params: RegisterDebuggerParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let mut debugger = params.debugger;
+                // A relative program is one the plugin ships
+                if let Some(pwd) = self.pwd.as_ref() {
+                    let program = pwd.join(&debugger.program);
+                    if Path::new(&debugger.program).is_relative() && program.exists()
+                    {
+                        debugger.program = program.to_string_lossy().to_string();
+                    }
+                }
+                self.catalog_rpc.register_debugger(params.ty, debugger);
+            }
             PublishDiagnostics::METHOD => {
                 let diagnostics: PublishDiagnosticsParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
-                self.catalog_rpc.core_rpc.publish_diagnostics(diagnostics);
+                self.catalog_rpc
+                    .publish_diagnostics(self.server_rpc.plugin_id, diagnostics);
             }
             Progress::METHOD => {
                 let progress: ProgressParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
-                self.catalog_rpc.core_rpc.work_done_progress(progress);
+                self.catalog_rpc.core_rpc.work_done_progress(
+                    self.server_rpc.plugin_id,
+                    self.server_name.clone(),
+                    progress,
+                );
             }
             _ => {
                 eprintln!("host notificaton {method} not handled");
@@ -845,6 +1022,178 @@ pub fn handle_notification(
         Ok(())
     }
 
+    pub fn handle_did_open_text_document(&self, path: &Path) {
+        if let Some(pull_diagnostics) = self.pull_diagnostics.as_ref() {
+            if let Ok(uri) = Url::from_file_path(path) {
+                pull_diagnostics.document_opened(uri);
+            }
+        }
+    }
+
+    /// The options and settings the user and the workspace give to the
+    /// server
+    pub fn language_server_config(&self) -> Option<LanguageServerConfig> {
+        self.catalog_rpc.language_server_config(&self.volt_id)
+    }
+
+    /// Send the server its settings, if they changed
+    pub fn did_change_configuration(&mut self) {
+        let settings = self
+            .language_server_config()
+            .and_then(|server| server.settings);
+        if settings == self.settings {
+            return;
+        }
+        self.settings = settings.clone();
+        self.server_rpc.server_notification(
+            DidChangeConfiguration::METHOD,
+            DidChangeConfigurationParams {
+                settings: settings.unwrap_or(Value::Null),
+            },
+            None,
+            None,
+            false,
+        );
+    }
+
+    pub fn handle_semantic_tokens_request(
+        &self,
+        path: &Path,
+        rh: ResponseHandler<Value, RpcError>,
+    ) {
+        let uri = match Url::from_file_path(path) {
+            Ok(uri) => uri,
+            Err(_) => {
+                rh.invoke(Err(RpcError {
+                    code: 0,
+                    message: "invalid path".to_string(),
+                }));
+                return;
+            }
+        };
+        let delta = self
+            .server_capabilities
+            .semantic_tokens_provider
+            .as_ref()
+            .and_then(|provider| {
+                match provider {
+                SemanticTokensServerCapabilities::SemanticTokensOptions(
+                    options,
+                ) => options.full.as_ref(),
+                SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(
+                    options,
+                ) => options.semantic_tokens_options.full.as_ref(),
+            }
+            })
+            .map(|full| match full {
+                SemanticTokensFullOptions::Bool(_) => false,
+                SemanticTokensFullOptions::Delta { delta } => delta.unwrap_or(false),
+            })
+            .unwrap_or(false);
+        self.semantic_tokens
+            .request(&self.server_rpc, uri, delta, move |result| {
+                rh.invoke(result)
+            });
+    }
+
+    /// Send the request for the signature help, unless it's for a typed
+    /// character that isn't one of the trigger characters of the server
+    pub fn handle_signature_help_request(
+        &self,
+        params: Value,
+        rh: ResponseHandler<Value, RpcError>,
+    ) {
+        let mut params: SignatureHelpParams = match serde_json::from_value(params) {
+            Ok(params) => params,
+            Err(_) => {
+                rh.invoke(Err(RpcError {
+                    code: 0,
+                    message: "invalid params".to_string(),
+                }));
+                return;
+            }
+        };
+        if let Some(context) = params.context.as_mut() {
+            if let Some(character) = context.trigger_character.clone() {
+                let options =
+                    self.server_capabilities.signature_help_provider.as_ref();
+                let contains = |characters: Option<&Vec<String>>| {
+                    characters
+                        .map(|characters| characters.contains(&character))
+                        .unwrap_or(false)
+                };
+                let trigger =
+                    contains(options.and_then(|o| o.trigger_characters.as_ref()));
+                let retrigger = context.is_retrigger
+                    && contains(
+                        options.and_then(|o| o.retrigger_characters.as_ref()),
+                    );
+                if !trigger && !retrigger {
+                    if !context.is_retrigger {
+                        rh.invoke(Ok(Value::Null));
+                        return;
+                    }
+                    // the signature help that's shown follows the typing
+                    context.trigger_kind = SignatureHelpTriggerKind::CONTENT_CHANGE;
+                    context.trigger_character = None;
+                }
+            }
+        }
+        self.server_rpc.server_request_async(
+            SignatureHelpRequest::METHOD,
+            params,
+            None,
+            None,
+            false,
+            move |result| rh.invoke(result),
+        );
+    }
+
+    /// Send the request for the on type formatting, unless the typed
+    /// character isn't one of the trigger characters of the server
+    pub fn handle_on_type_formatting_request(
+        &self,
+        params: Value,
+        rh: ResponseHandler<Value, RpcError>,
+    ) {
+        let params: DocumentOnTypeFormattingParams =
+            match serde_json::from_value(params) {
+                Ok(params) => params,
+                Err(_) => {
+                    rh.invoke(Err(RpcError {
+                        code: 0,
+                        message: "invalid params".to_string(),
+                    }));
+                    return;
+                }
+            };
+        let trigger = self
+            .server_capabilities
+            .document_on_type_formatting_provider
+            .as_ref()
+            .map(|options| {
+                options.first_trigger_character == params.ch
+                    || options
+                        .more_trigger_character
+                        .as_ref()
+                        .map(|characters| characters.contains(&params.ch))
+                        .unwrap_or(false)
+            })
+            .unwrap_or(false);
+        if !trigger {
+            rh.invoke(Ok(Value::Null));
+            return;
+        }
+        self.server_rpc.server_request_async(
+            OnTypeFormatting::METHOD,
+            params,
+            None,
+            None,
+            false,
+            move |result| rh.invoke(result),
+        );
+    }
+
     pub fn handle_did_save_text_document(
         &self,
         language_id: String,
@@ -930,6 +1279,7 @@ pub fn handle_did_change_text_document(
         };
 
         let path = document.uri.to_file_path().ok();
+        let uri = document.uri.clone();
 
         let params = DidChangeTextDocumentParams {
             text_document: document,
@@ -943,6 +1293,9 @@ pub fn handle_did_change_text_document(
             path,
             false,
         );
+        if let Some(pull_diagnostics) = self.pull_diagnostics.as_ref() {
+            pull_diagnostics.document_changed(uri);
+        }
     }
 
     pub fn format_semantic_tokens(
@@ -964,6 +1317,27 @@ pub fn format_semantic_tokens(
     }
 }
 
+/// The value of a section of the settings, like `rust-analyzer.cargo`, or
+/// all of them without a section
+fn configuration_section(settings: Option<&Value>, section: Option<&str>) -> Value {
+    let settings = match settings {
+        Some(settings) => settings,
+        None => return Value::Null,
+    };
+    let section = match section {
+        Some(section) => section,
+        None => return settings.clone(),
+    };
+    if let Some(value) = settings.get(section) {
+        return value.clone();
+    }
+    section
+        .split('.')
+        .try_fold(settings, |value, key| value.get(key))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
 fn get_document_content_change(
     text: &Rope,
     delta: &RopeDelta,
@@ -1039,6 +1413,12 @@ fn format_semantic_styles(
         let kind = semantic_legends.token_types[semantic_token.token_type as usize]
             .as_str()
             .to_string();
+        let mut modifiers = StyleModifiers::default();
+        for (i, modifier) in semantic_legends.token_modifiers.iter().enumerate() {
+            if i < 32 && semantic_token.token_modifiers_bitset & (1 << i) != 0 {
+                modifiers.set(modifier.as_str());
+            }
+        }
         if start < last_start {
             continue;
         }
@@ -1048,6 +1428,7 @@ fn format_semantic_styles(
             end,
             style: Style {
                 fg_color: Some(kind),
+                modifiers,
             },
         });
     }
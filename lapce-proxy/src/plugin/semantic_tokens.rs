@@ -0,0 +1,193 @@
+//! The semantic tokens of the documents, kept for the language servers that
+//! can send the changes to the tokens of a document since their last
+//! response instead of all of them.
+
+use std::{collections::HashMap, sync::Arc};
+
+use lapce_rpc::RpcError;
+use lsp_types::{
+    request::{Request, SemanticTokensFullDeltaRequest, SemanticTokensFullRequest},
+    PartialResultParams, SemanticToken, SemanticTokens, SemanticTokensDeltaParams,
+    SemanticTokensEdit, SemanticTokensFullDeltaResult, SemanticTokensParams,
+    TextDocumentIdentifier, Url, WorkDoneProgressParams,
+};
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use super::psp::PluginServerRpcHandler;
+
+/// The number of integers a token takes in the data of the protocol, which
+/// the edits of a delta count in
+const TOKEN_LEN: usize = 5;
+
+#[derive(Clone, Default)]
+pub struct SemanticTokensCache {
+    /// The last tokens of each document, which have a result id the server
+    /// can send a delta against
+    tokens: Arc<Mutex<HashMap<Url, SemanticTokens>>>,
+}
+
+impl SemanticTokensCache {
+    /// Ask the server for the semantic tokens of the document, as a delta
+    /// to the last ones if it can do it. The callback always gets all the
+    /// tokens.
+    pub fn request(
+        &self,
+        server_rpc: &PluginServerRpcHandler,
+        uri: Url,
+        delta: bool,
+        f: impl FnOnce(Result<Value, RpcError>) + Send + 'static,
+    ) {
+        let previous_result_id = if delta {
+            self.tokens
+                .lock()
+                .get(&uri)
+                .and_then(|tokens| tokens.result_id.clone())
+        } else {
+            None
+        };
+        match previous_result_id {
+            Some(previous_result_id) => {
+                self.request_delta(server_rpc, uri, previous_result_id, f)
+            }
+            None => self.request_full(server_rpc, uri, f),
+        }
+    }
+
+    fn request_full(
+        &self,
+        server_rpc: &PluginServerRpcHandler,
+        uri: Url,
+        f: impl FnOnce(Result<Value, RpcError>) + Send + 'static,
+    ) {
+        let params = SemanticTokensParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let local = self.clone();
+        server_rpc.server_request_async(
+            SemanticTokensFullRequest::METHOD,
+            params,
+            None,
+            None,
+            false,
+            move |result: Result<Value, RpcError>| {
+                let tokens = result
+                    .as_ref()
+                    .ok()
+                    .and_then(|value| {
+                        serde_json::from_value::<SemanticTokens>(value.clone()).ok()
+                    })
+                    .filter(|tokens| tokens.result_id.is_some());
+                match tokens {
+                    Some(tokens) => {
+                        local.tokens.lock().insert(uri, tokens);
+                    }
+                    None => {
+                        local.tokens.lock().remove(&uri);
+                    }
+                }
+                f(result);
+            },
+        );
+    }
+
+    fn request_delta(
+        &self,
+        server_rpc: &PluginServerRpcHandler,
+        uri: Url,
+        previous_result_id: String,
+        f: impl FnOnce(Result<Value, RpcError>) + Send + 'static,
+    ) {
+        let params = SemanticTokensDeltaParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            previous_result_id: previous_result_id.clone(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let local = self.clone();
+        let server_rpc_clone = server_rpc.clone();
+        server_rpc.server_request_async(
+            SemanticTokensFullDeltaRequest::METHOD,
+            params,
+            None,
+            None,
+            false,
+            move |result: Result<Value, RpcError>| {
+                let result = result.ok().and_then(|value| {
+                    serde_json::from_value::<SemanticTokensFullDeltaResult>(value)
+                        .ok()
+                });
+                let tokens = result.and_then(|result| {
+                    local.apply_delta(&uri, &previous_result_id, result)
+                });
+                match tokens {
+                    Some(tokens) => {
+                        let value = serde_json::to_value(&tokens).unwrap();
+                        local.tokens.lock().insert(uri, tokens);
+                        f(Ok(value));
+                    }
+                    // the delta couldn't be used, so start again from all
+                    // the tokens
+                    None => {
+                        local.tokens.lock().remove(&uri);
+                        local.request_full(&server_rpc_clone, uri, f);
+                    }
+                }
+            },
+        );
+    }
+
+    fn apply_delta(
+        &self,
+        uri: &Url,
+        previous_result_id: &str,
+        result: SemanticTokensFullDeltaResult,
+    ) -> Option<SemanticTokens> {
+        let (result_id, edits) = match result {
+            SemanticTokensFullDeltaResult::Tokens(tokens) => {
+                return Some(tokens);
+            }
+            SemanticTokensFullDeltaResult::TokensDelta(delta) => {
+                (delta.result_id, delta.edits)
+            }
+            SemanticTokensFullDeltaResult::PartialTokensDelta { edits } => {
+                (None, edits)
+            }
+        };
+        let mut tokens = self.tokens.lock().get(uri).cloned()?;
+        // the tokens were asked for again since
+        if tokens.result_id.as_deref() != Some(previous_result_id) {
+            return None;
+        }
+        apply_edits(&mut tokens.data, edits)?;
+        tokens.result_id = result_id;
+        Some(tokens)
+    }
+}
+
+/// Apply the edits of a delta to the tokens they were made against, or
+/// nothing if they don't fall on whole tokens
+fn apply_edits(
+    data: &mut Vec<SemanticToken>,
+    mut edits: Vec<SemanticTokensEdit>,
+) -> Option<()> {
+    // the edits are all against the old tokens, so the last ones are
+    // applied first for the first ones to stay where they are
+    edits.sort_by_key(|edit| edit.start);
+    for edit in edits.into_iter().rev() {
+        let start = edit.start as usize;
+        let delete_count = edit.delete_count as usize;
+        if start % TOKEN_LEN != 0 || delete_count % TOKEN_LEN != 0 {
+            return None;
+        }
+        let start = start / TOKEN_LEN;
+        let end = start + delete_count / TOKEN_LEN;
+        if end > data.len() {
+            return None;
+        }
+        data.splice(start..end, edit.data.unwrap_or_default());
+    }
+    Some(())
+}
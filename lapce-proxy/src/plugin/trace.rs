@@ -0,0 +1,135 @@
+//! The log of a language server, with its stderr and, while tracing is on,
+//! the JSON-RPC messages exchanged with it.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use lapce_rpc::{
+    core::{CoreRpcHandler, LspLogKind},
+    plugin::PluginId,
+};
+use parking_lot::Mutex;
+use serde_json::Value;
+
+pub struct LspTrace {
+    plugin_id: PluginId,
+    server: String,
+    enabled: Arc<AtomicBool>,
+    core_rpc: CoreRpcHandler,
+    /// The method and time of the requests sent to the server that it
+    /// hasn't answered yet, by their id
+    sent: Mutex<HashMap<String, (String, Instant)>>,
+    /// The requests of the server that haven't been answered yet
+    received: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl LspTrace {
+    pub fn new(
+        plugin_id: PluginId,
+        server: String,
+        enabled: Arc<AtomicBool>,
+        core_rpc: CoreRpcHandler,
+    ) -> Self {
+        Self {
+            plugin_id,
+            server,
+            enabled,
+            core_rpc,
+            sent: Mutex::new(HashMap::new()),
+            received: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn stderr(&self, line: &str) {
+        self.core_rpc.lsp_log(
+            self.plugin_id,
+            self.server.clone(),
+            LspLogKind::Stderr,
+            line.to_string(),
+        );
+    }
+
+    pub fn sending(&self, msg: &str) {
+        self.trace(msg, true);
+    }
+
+    pub fn received(&self, msg: &str) {
+        self.trace(msg, false);
+    }
+
+    fn trace(&self, msg: &str, outgoing: bool) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let value: Value = match serde_json::from_str(msg) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+        let method = value.get("method").and_then(|method| method.as_str());
+        let id = value.get("id").map(|id| id.to_string());
+        let verb = if outgoing { "Sending" } else { "Received" };
+
+        let message = match (method, id) {
+            (Some(method), Some(id)) => {
+                let pending = if outgoing { &self.sent } else { &self.received };
+                pending
+                    .lock()
+                    .insert(id.clone(), (method.to_string(), Instant::now()));
+                format!("{verb} request '{method} - ({id})'.")
+                    + &json_field(&value, "params", "Params")
+            }
+            (Some(method), None) => {
+                format!("{verb} notification '{method}'.")
+                    + &json_field(&value, "params", "Params")
+            }
+            (None, Some(id)) => {
+                // we answer the requests of the server, and the server
+                // answers ours
+                let pending = if outgoing { &self.received } else { &self.sent };
+                let request = pending.lock().remove(&id);
+                let mut message = match request {
+                    Some((method, start)) if outgoing => format!(
+                        "Sending response '{method} - ({id})'. Processing \
+                         request took {}ms.",
+                        start.elapsed().as_millis()
+                    ),
+                    Some((method, start)) => format!(
+                        "Received response '{method} - ({id})' in {}ms.",
+                        start.elapsed().as_millis()
+                    ),
+                    None => format!("{verb} response '({id})'."),
+                };
+                if value.get("error").is_some() {
+                    message += &json_field(&value, "error", "Error");
+                } else {
+                    message += &json_field(&value, "result", "Result");
+                }
+                message
+            }
+            (None, None) => return,
+        };
+
+        self.core_rpc.lsp_log(
+            self.plugin_id,
+            self.server.clone(),
+            LspLogKind::Trace,
+            message,
+        );
+    }
+}
+
+/// The field of the message as pretty printed JSON on the lines after the
+/// label, or nothing if the message doesn't have it
+fn json_field(value: &Value, field: &str, label: &str) -> String {
+    value
+        .get(field)
+        .and_then(|v| serde_json::to_string_pretty(v).ok())
+        .map(|v| format!("\n{label}: {v}"))
+        .unwrap_or_default()
+}
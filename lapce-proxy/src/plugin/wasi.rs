@@ -32,7 +32,7 @@
 use super::{
     psp::{
         handle_plugin_server_message, PluginHandlerNotification, PluginHostHandler,
-        PluginServerHandler, RpcCallback,
+        PluginServerHandler, ResponseHandler, RpcCallback,
     },
     PluginCatalogRpcHandler,
 };
@@ -106,6 +106,9 @@ fn handle_handler_notification(
             Initialize => {
                 self.initialize();
             }
+            // only the language servers the plugin starts have settings, and
+            // can be restarted
+            DidChangeConfiguration | Restart => {}
             Shutdown => {
                 self.shutdown();
             }
@@ -120,6 +123,34 @@ fn handle_host_request(&mut self, id: Id, method: String, params: Params) {
         let _ = self.host.handle_request(id, method, params);
     }
 
+    fn handle_did_open_text_document(&mut self, path: &Path) {
+        self.host.handle_did_open_text_document(path);
+    }
+
+    fn handle_semantic_tokens_request(
+        &mut self,
+        path: &Path,
+        rh: ResponseHandler<serde_json::Value, RpcError>,
+    ) {
+        self.host.handle_semantic_tokens_request(path, rh);
+    }
+
+    fn handle_signature_help_request(
+        &mut self,
+        params: serde_json::Value,
+        rh: ResponseHandler<serde_json::Value, RpcError>,
+    ) {
+        self.host.handle_signature_help_request(params, rh);
+    }
+
+    fn handle_on_type_formatting_request(
+        &mut self,
+        params: serde_json::Value,
+        rh: ResponseHandler<serde_json::Value, RpcError>,
+    ) {
+        self.host.handle_on_type_formatting_request(params, rh);
+    }
+
     fn handle_did_save_text_document(
         &self,
         language_id: String,
@@ -0,0 +1,81 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::directory::Directory;
+
+const BASH_SCRIPT: &str = include_str!("../shell-integration/lapce.bash");
+const ZSH_RC: &str = include_str!("../shell-integration/lapce.zshrc");
+const ZSH_ENV: &str = include_str!("../shell-integration/lapce.zshenv");
+const FISH_SCRIPT: &str = include_str!("../shell-integration/lapce.fish");
+const POWERSHELL_SCRIPT: &str = include_str!("../shell-integration/lapce.ps1");
+
+/// How the shell is started with the shell integration script, which marks
+/// the prompts and the commands in its output
+pub struct ShellIntegration {
+    pub args: Vec<String>,
+    /// The environment variables the shell reads the script from
+    pub env: HashMap<String, String>,
+}
+
+impl ShellIntegration {
+    /// The arguments and the environment that start the program with the
+    /// script, for the shells that have one
+    pub fn new(program: &str) -> Option<ShellIntegration> {
+        let name = Path::new(program).file_stem()?.to_str()?.to_lowercase();
+        let dir = Directory::shell_integration_directory()?;
+        let mut env = HashMap::new();
+        let args = match name.as_str() {
+            "bash" => {
+                let path = write_script(&dir, "lapce.bash", BASH_SCRIPT)?;
+                vec!["--init-file".to_string(), path]
+            }
+            "zsh" => {
+                // Zsh reads its startup files from ZDOTDIR, whose scripts
+                // then read the ones of the user
+                let zsh_dir = dir.join("zsh");
+                if !zsh_dir.exists() {
+                    std::fs::create_dir(&zsh_dir).ok()?;
+                }
+                write_script(&zsh_dir, ".zshrc", ZSH_RC)?;
+                write_script(&zsh_dir, ".zshenv", ZSH_ENV)?;
+                // Lapce can be started from one of its own terminals, whose
+                // ZDOTDIR isn't the one of the user
+                let user_zdotdir = std::env::var("LAPCE_USER_ZDOTDIR")
+                    .or_else(|_| std::env::var("ZDOTDIR"))
+                    .ok()
+                    .filter(|user_zdotdir| Path::new(user_zdotdir) != zsh_dir);
+                if let Some(user_zdotdir) = user_zdotdir {
+                    env.insert("LAPCE_USER_ZDOTDIR".to_string(), user_zdotdir);
+                }
+                env.insert("ZDOTDIR".to_string(), zsh_dir.to_str()?.to_string());
+                Vec::new()
+            }
+            "fish" => {
+                let path = write_script(&dir, "lapce.fish", FISH_SCRIPT)?;
+                vec!["--init-command".to_string(), format!("source '{path}'")]
+            }
+            "pwsh" | "powershell" => {
+                let path = write_script(&dir, "lapce.ps1", POWERSHELL_SCRIPT)?;
+                vec![
+                    "-NoExit".to_string(),
+                    "-Command".to_string(),
+                    format!(". '{path}'"),
+                ]
+            }
+            _ => return None,
+        };
+        Some(ShellIntegration { args, env })
+    }
+}
+
+/// Write the script to the directory, unless it's already there, giving
+/// its path
+fn write_script(dir: &Path, name: &str, script: &str) -> Option<String> {
+    let path = dir.join(name);
+    if std::fs::read_to_string(&path).ok().as_deref() != Some(script) {
+        if let Err(e) = std::fs::write(&path, script) {
+            log::error!("can't write the shell integration script: {e}");
+            return None;
+        }
+    }
+    path.to_str().map(|path| path.to_string())
+}
@@ -1,10 +1,11 @@
-#[cfg(target_os = "linux")]
-use std::process::Command;
 use std::{
     borrow::Cow,
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
+    ffi::OsString,
     io::{self, ErrorKind, Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
 };
 
 use alacritty_terminal::{
@@ -15,7 +16,10 @@
     tty::{self, setup_env, EventedPty, EventedReadWrite},
 };
 use directories::BaseDirs;
-use lapce_rpc::{core::CoreRpcHandler, terminal::TermId};
+use lapce_rpc::{
+    core::CoreRpcHandler,
+    terminal::{TermId, TerminalProfile},
+};
 #[cfg(not(windows))]
 use mio::unix::UnixReady;
 #[allow(deprecated)]
@@ -24,14 +28,23 @@
     Events, PollOpt, Ready,
 };
 
+use crate::shell_integration::ShellIntegration;
+
 const READ_BUFFER_SIZE: usize = 0x10_0000;
 
+/// How often the process in the foreground of the terminal is looked up,
+/// as the terminal isn't told when it changes
+const PROCESS_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
 pub type TermConfig = alacritty_terminal::config::Config;
 
 pub struct Terminal {
     term_id: TermId,
     poll: mio::Poll,
     pty: alacritty_terminal::tty::Pty,
+    /// The scrollback of the tmux session the terminal attached to, which
+    /// is sent before its output
+    history: Option<Vec<u8>>,
 
     #[allow(deprecated)]
     rx: Receiver<Msg>,
@@ -43,54 +56,116 @@ pub struct Terminal {
 impl Terminal {
     pub fn new(
         term_id: TermId,
-        cwd: Option<PathBuf>,
-        shell: String,
+        profile: TerminalProfile,
+        shell_integration: bool,
+        session: Option<&str>,
         width: usize,
         height: usize,
     ) -> Terminal {
         let poll = mio::Poll::new().unwrap();
         let mut config = TermConfig::default();
-        config.pty_config.working_directory =
-            if cwd.is_some() && cwd.clone().unwrap().exists() {
-                cwd
-            } else {
-                BaseDirs::new().map(|d| PathBuf::from(d.home_dir()))
-            };
-        let shell = shell.trim();
+        config.pty_config.working_directory = match profile.workdir {
+            Some(workdir) if workdir.exists() => Some(workdir),
+            _ => BaseDirs::new().map(|d| PathBuf::from(d.home_dir())),
+        };
+        let command = profile
+            .command
+            .as_deref()
+            .map(|command| command.trim())
+            .filter(|command| !command.is_empty());
         let flatpak_use_host_terminal = flatpak_should_use_host_terminal();
+        let tmux = session
+            .filter(|_| !flatpak_use_host_terminal)
+            .and_then(|session| Some((which::which("tmux").ok()?, session)));
+
+        if flatpak_use_host_terminal {
+            let flatpak_spawn_path = "/usr/bin/flatpak-spawn".to_string();
+
+            // The host shell doesn't get the environment of the sandbox, so
+            // the variables of the profile are passed along
+            let mut args = vec!["--host".to_string()];
+            args.extend(
+                profile
+                    .env
+                    .iter()
+                    .map(|(key, value)| format!("--env={key}={value}")),
+            );
+            match command {
+                Some(command) => {
+                    args.push(command.to_string());
+                    args.extend(profile.arguments);
+                }
+                None => args.push(flatpak_get_default_host_shell()),
+            }
 
-        if !shell.is_empty() || flatpak_use_host_terminal {
-            let mut parts = shell.split(' ');
-
-            if flatpak_use_host_terminal {
-                let flatpak_spawn_path = "/usr/bin/flatpak-spawn".to_string();
-                let host_shell = flatpak_get_default_host_shell();
-
-                let args = if shell.is_empty() {
-                    vec!["--host".to_string(), host_shell]
-                } else {
-                    vec![
-                        "--host".to_string(),
-                        host_shell,
-                        "-c".to_string(),
-                        shell.to_string(),
-                    ]
-                };
-
-                config.pty_config.shell = Some(Program::WithArgs {
-                    program: flatpak_spawn_path,
-                    args,
-                })
-            } else {
-                let program = parts.next().unwrap();
-                if let Ok(p) = which::which(program) {
+            config.pty_config.shell = Some(Program::WithArgs {
+                program: flatpak_spawn_path,
+                args,
+            })
+        } else if let Some(command) = command {
+            match which::which(command) {
+                Ok(p) => {
                     config.pty_config.shell = Some(Program::WithArgs {
                         program: p.to_str().unwrap().to_string(),
-                        args: parts.map(|p| p.to_string()).collect::<Vec<String>>(),
+                        args: profile.arguments,
                     })
                 }
+                Err(e) => log::error!("can't find the shell {command}: {e}"),
             }
         }
+        if !flatpak_use_host_terminal {
+            config.env.extend(profile.env);
+        }
+        // tmux doesn't pass the marks of the shell integration along
+        if shell_integration && !flatpak_use_host_terminal && tmux.is_none() {
+            // Only a shell that is started without arguments is integrated,
+            // as the arguments could be a script to run instead
+            let program = match &config.pty_config.shell {
+                Some(Program::WithArgs { program, args }) if args.is_empty() => {
+                    Some(program.clone())
+                }
+                Some(_) => None,
+                None => default_shell(),
+            };
+            if let Some(integration) =
+                program.as_deref().and_then(ShellIntegration::new)
+            {
+                config.pty_config.shell = Some(Program::WithArgs {
+                    program: program.unwrap(),
+                    args: integration.args,
+                });
+                config.env.extend(integration.env);
+            }
+        }
+        let mut history = None;
+        if let Some((tmux, session)) = tmux {
+            history = tmux_history(&tmux, session);
+            let (program, args) = match config.pty_config.shell.take() {
+                Some(Program::WithArgs { program, args }) => (program, args),
+                Some(Program::Just(program)) => (program, Vec::new()),
+                None => (
+                    default_shell().unwrap_or_else(|| "/bin/sh".to_string()),
+                    Vec::new(),
+                ),
+            };
+            config.pty_config.shell = Some(Program::WithArgs {
+                program: tmux.to_string_lossy().to_string(),
+                args: tmux_args(
+                    session,
+                    config.pty_config.working_directory.as_deref(),
+                    &config.env,
+                    program,
+                    args,
+                ),
+            });
+        }
+        // The environment is set on the proxy, whose shells all inherit
+        // it, so the variables of this one are put back once it's started
+        let previous_env: Vec<(String, Option<OsString>)> = config
+            .env
+            .keys()
+            .map(|key| (key.clone(), std::env::var_os(key)))
+            .collect();
         setup_env(&config);
 
         #[cfg(target_os = "macos")]
@@ -101,6 +176,13 @@ pub fn new(
         let pty =
             alacritty_terminal::tty::new(&config.pty_config, &size, None).unwrap();
 
+        for (key, value) in previous_env {
+            match value {
+                Some(value) => std::env::set_var(key, value),
+                None => std::env::remove_var(key),
+            }
+        }
+
         #[allow(deprecated)]
         let (tx, rx) = channel();
 
@@ -108,6 +190,7 @@ pub fn new(
             term_id,
             poll,
             pty,
+            history,
             tx,
             rx,
         }
@@ -126,12 +209,18 @@ pub fn run(&mut self, core_rpc: CoreRpcHandler) {
             .register(&self.poll, &mut tokens, Ready::readable(), poll_opts)
             .unwrap();
 
+        if let Some(history) = self.history.take() {
+            core_rpc.update_terminal(self.term_id, base64::encode(&history));
+        }
+
         let mut buf = [0u8; READ_BUFFER_SIZE];
         let mut events = Events::with_capacity(1024);
         let mut state = State::default();
+        let mut process = None;
+        let mut process_checked: Option<Instant> = None;
 
         'event_loop: loop {
-            let _ = self.poll.poll(&mut events, None);
+            let _ = self.poll.poll(&mut events, Some(PROCESS_CHECK_INTERVAL));
             for event in events.iter() {
                 match event.token() {
                     token if token == channel_token => {
@@ -178,6 +267,17 @@ pub fn run(&mut self, core_rpc: CoreRpcHandler) {
                     _ => (),
                 }
             }
+            if process_checked
+                .map(|checked| checked.elapsed() >= PROCESS_CHECK_INTERVAL)
+                .unwrap_or(true)
+            {
+                process_checked = Some(Instant::now());
+                let name = self.foreground_process();
+                if name != process {
+                    core_rpc.terminal_process(self.term_id, name.clone());
+                    process = name;
+                }
+            }
             // Register write interest if necessary.
             let mut interest = Ready::readable();
             if state.needs_write() {
@@ -192,6 +292,25 @@ pub fn run(&mut self, core_rpc: CoreRpcHandler) {
         let _ = self.pty.deregister(&self.poll);
     }
 
+    /// The name of the process in the foreground of the terminal
+    #[cfg(unix)]
+    fn foreground_process(&mut self) -> Option<String> {
+        use std::os::unix::io::AsRawFd;
+
+        let fd = self.pty.reader().as_raw_fd();
+        let pid = unsafe { libc::tcgetpgrp(fd) };
+        if pid <= 0 {
+            return None;
+        }
+        process_name(pid)
+    }
+
+    /// The name of the process in the foreground of the terminal
+    #[cfg(windows)]
+    fn foreground_process(&mut self) -> Option<String> {
+        None
+    }
+
     /// Drain the channel.
     ///
     /// Returns `false` when a shutdown message was received.
@@ -326,6 +445,121 @@ fn set_current(&mut self, new: Option<Writing>) {
     }
 }
 
+/// The socket of the tmux server that runs the sessions of the terminals,
+/// which is separate from the server of the user, so that its options and
+/// sessions are left alone
+const TMUX_SOCKET: &str = "lapce";
+
+/// The arguments of tmux that attach to the session, or else start it with
+/// the program
+fn tmux_args(
+    session: &str,
+    workdir: Option<&Path>,
+    env: &HashMap<String, String>,
+    program: String,
+    args: Vec<String>,
+) -> Vec<String> {
+    let mut tmux_args: Vec<String> = [
+        "-L",
+        TMUX_SOCKET,
+        "start-server",
+        ";",
+        // Without the alternate screen, the output tmux scrolls goes to the
+        // scrollback of the terminal rather than only to the one of tmux
+        "set-option",
+        "-g",
+        "terminal-overrides",
+        ",*:smcup@:rmcup@",
+        ";",
+        "new-session",
+        "-A",
+        "-s",
+        session,
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    if let Some(workdir) = workdir {
+        tmux_args.push("-c".to_string());
+        tmux_args.push(workdir.to_string_lossy().to_string());
+    }
+    for (key, value) in env {
+        tmux_args.push("-e".to_string());
+        tmux_args.push(format!("{key}={value}"));
+    }
+    tmux_args.push(program);
+    tmux_args.extend(args);
+    tmux_args.extend(
+        [";", "set-option", "-t", session, "status", "off"]
+            .iter()
+            .map(|arg| arg.to_string()),
+    );
+    tmux_args
+}
+
+/// The scrollback of the tmux session, if it's running, with the colors
+/// of its output
+fn tmux_history(tmux: &Path, session: &str) -> Option<Vec<u8>> {
+    let output = Command::new(tmux)
+        .args([
+            "-L",
+            TMUX_SOCKET,
+            "capture-pane",
+            "-p",
+            "-e",
+            "-S",
+            "-",
+            "-E",
+            "-1",
+            "-t",
+            session,
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return None;
+    }
+    let history = String::from_utf8_lossy(&output.stdout).replace('\n', "\r\n");
+    Some(history.into_bytes())
+}
+
+/// End the tmux session along with the shell in it
+pub fn kill_tmux_session(session: &str) {
+    let tmux = match which::which("tmux") {
+        Ok(tmux) => tmux,
+        Err(_) => return,
+    };
+    if let Err(e) = Command::new(tmux)
+        .args(["-L", TMUX_SOCKET, "kill-session", "-t", session])
+        .output()
+    {
+        log::error!("can't end the tmux session {session}: {e}");
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_name(pid: libc::pid_t) -> Option<String> {
+    let name = std::fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    Some(name.trim_end().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn process_name(pid: libc::pid_t) -> Option<String> {
+    let mut buf = [0u8; 256];
+    let len = unsafe {
+        libc::proc_name(pid, buf.as_mut_ptr() as *mut libc::c_void, buf.len() as u32)
+    };
+    if len <= 0 {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&buf[..len as usize]).to_string())
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn process_name(_pid: libc::pid_t) -> Option<String> {
+    None
+}
+
 #[cfg(target_os = "macos")]
 fn set_locale_environment() {
     let locale = locale_config::Locale::global_default()
@@ -335,6 +569,18 @@ fn set_locale_environment() {
 }
 
 #[inline]
+/// The shell the terminal starts when none is set
+#[cfg(not(windows))]
+fn default_shell() -> Option<String> {
+    std::env::var("SHELL").ok()
+}
+
+/// The shell the terminal starts when none is set
+#[cfg(windows)]
+fn default_shell() -> Option<String> {
+    Some("powershell".to_string())
+}
+
 #[cfg(not(target_os = "linux"))]
 fn flatpak_get_default_host_shell() -> String {
     panic!(
@@ -376,8 +622,6 @@ fn flatpak_should_use_host_terminal() -> bool {
 #[inline]
 #[cfg(target_os = "linux")]
 fn flatpak_should_use_host_terminal() -> bool {
-    use std::path::Path;
-
     const FLATPAK_INFO_PATH: &str = "/.flatpak-info";
 
     // The de-facto way of checking whether one is inside of a Flatpak container is by checking for
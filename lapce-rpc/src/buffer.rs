@@ -12,9 +12,45 @@ pub fn next() -> Self {
     }
 }
 
+/// The names of the encodings that files can be opened and saved with, as
+/// defined by the Encoding Standard
+pub const ENCODINGS: &[&str] = &[
+    "UTF-8",
+    "UTF-16LE",
+    "UTF-16BE",
+    "windows-1252",
+    "ISO-8859-2",
+    "ISO-8859-5",
+    "ISO-8859-7",
+    "ISO-8859-15",
+    "windows-1250",
+    "windows-1251",
+    "windows-1253",
+    "windows-1254",
+    "windows-1255",
+    "windows-1256",
+    "windows-1257",
+    "windows-1258",
+    "KOI8-R",
+    "KOI8-U",
+    "Shift_JIS",
+    "EUC-JP",
+    "ISO-2022-JP",
+    "GBK",
+    "gb18030",
+    "Big5",
+    "EUC-KR",
+    "windows-874",
+    "IBM866",
+    "macintosh",
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewBufferResponse {
     pub content: String,
+    pub encoding: String,
+    pub large_file: bool,
+    pub read_only: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +12,10 @@
 };
 
 use crate::{
+    dap_types::{
+        Breakpoint, Capabilities, DapId, Scope, StackFrame, Stopped, Thread,
+        Variable,
+    },
     file::FileNodeItem,
     plugin::{PluginId, VoltInfo, VoltMetadata},
     source_control::DiffInfo,
@@ -39,6 +43,9 @@ pub enum CoreNotification {
         input: String,
         resp: CompletionResponse,
         plugin_id: PluginId,
+        /// The priority of the language server that completed, for ordering
+        /// its items among those of the other servers
+        priority: i64,
     },
     ReloadBuffer {
         path: PathBuf,
@@ -51,12 +58,23 @@ pub enum CoreNotification {
         files: Vec<PathBuf>,
     },
     WorkspaceFileChange {},
+    /// The settings file of the workspace changed
+    WorkspaceSettingsChange {},
     PublishDiagnostics {
         diagnostics: PublishDiagnosticsParams,
     },
     WorkDoneProgress {
+        plugin_id: PluginId,
+        /// The name of the language server doing the work
+        server: String,
         progress: ProgressParams,
     },
+    LspLog {
+        plugin_id: PluginId,
+        server: String,
+        kind: LspLogKind,
+        message: String,
+    },
     HomeDir {
         path: PathBuf,
     },
@@ -92,12 +110,61 @@ pub enum CoreNotification {
     CloseTerminal {
         term_id: TermId,
     },
+    /// The name of the process in the foreground of the terminal, which is
+    /// the shell itself when no command runs
+    TerminalProcess {
+        term_id: TermId,
+        name: Option<String>,
+    },
+    /// The debugged program stopped, with its threads, the stack of the
+    /// thread that stopped and the variables of the top frame
+    DapStopped {
+        dap_id: DapId,
+        stopped: Stopped,
+        threads: Vec<Thread>,
+        stack_frames: Vec<StackFrame>,
+        variables: Vec<(Scope, Vec<Variable>)>,
+    },
+    /// What the debug adapter supports, once it's initialized
+    DapCapabilities {
+        dap_id: DapId,
+        capabilities: Capabilities,
+    },
+    DapContinued {
+        dap_id: DapId,
+    },
+    DapTerminated {
+        dap_id: DapId,
+    },
+    /// The breakpoints of the file the debug adapter set
+    DapBreakpoints {
+        dap_id: DapId,
+        path: PathBuf,
+        breakpoints: Vec<Breakpoint>,
+    },
+    /// What the debugged program or the adapter printed, which can have
+    /// ANSI escapes
+    DapOutput {
+        dap_id: DapId,
+        category: Option<String>,
+        output: String,
+    },
     Log {
         level: String,
         message: String,
     },
 }
 
+/// What a line of the log of a language server is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LspLogKind {
+    /// A line the server wrote to its stderr
+    Stderr,
+    /// A JSON-RPC message sent to or received from the server
+    Trace,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CoreRequest {}
 
@@ -199,6 +266,10 @@ pub fn workspace_file_change(&self) {
         self.notification(CoreNotification::WorkspaceFileChange {});
     }
 
+    pub fn workspace_settings_change(&self) {
+        self.notification(CoreNotification::WorkspaceSettingsChange {});
+    }
+
     pub fn diff_info(&self, diff: DiffInfo) {
         self.notification(CoreNotification::DiffInfo { diff });
     }
@@ -213,12 +284,14 @@ pub fn completion_response(
         input: String,
         resp: CompletionResponse,
         plugin_id: PluginId,
+        priority: i64,
     ) {
         self.notification(CoreNotification::CompletionResponse {
             request_id,
             input,
             resp,
             plugin_id,
+            priority,
         });
     }
 
@@ -255,8 +328,32 @@ pub fn publish_diagnostics(&self, diagnostics: PublishDiagnosticsParams) {
         self.notification(CoreNotification::PublishDiagnostics { diagnostics });
     }
 
-    pub fn work_done_progress(&self, progress: ProgressParams) {
-        self.notification(CoreNotification::WorkDoneProgress { progress });
+    pub fn work_done_progress(
+        &self,
+        plugin_id: PluginId,
+        server: String,
+        progress: ProgressParams,
+    ) {
+        self.notification(CoreNotification::WorkDoneProgress {
+            plugin_id,
+            server,
+            progress,
+        });
+    }
+
+    pub fn lsp_log(
+        &self,
+        plugin_id: PluginId,
+        server: String,
+        kind: LspLogKind,
+        message: String,
+    ) {
+        self.notification(CoreNotification::LspLog {
+            plugin_id,
+            server,
+            kind,
+            message,
+        });
     }
 
     pub fn close_terminal(&self, term_id: TermId) {
@@ -266,6 +363,68 @@ pub fn close_terminal(&self, term_id: TermId) {
     pub fn update_terminal(&self, term_id: TermId, content: String) {
         self.notification(CoreNotification::UpdateTerminal { term_id, content });
     }
+
+    pub fn terminal_process(&self, term_id: TermId, name: Option<String>) {
+        self.notification(CoreNotification::TerminalProcess { term_id, name });
+    }
+
+    pub fn dap_stopped(
+        &self,
+        dap_id: DapId,
+        stopped: Stopped,
+        threads: Vec<Thread>,
+        stack_frames: Vec<StackFrame>,
+        variables: Vec<(Scope, Vec<Variable>)>,
+    ) {
+        self.notification(CoreNotification::DapStopped {
+            dap_id,
+            stopped,
+            threads,
+            stack_frames,
+            variables,
+        });
+    }
+
+    pub fn dap_capabilities(&self, dap_id: DapId, capabilities: Capabilities) {
+        self.notification(CoreNotification::DapCapabilities {
+            dap_id,
+            capabilities,
+        });
+    }
+
+    pub fn dap_continued(&self, dap_id: DapId) {
+        self.notification(CoreNotification::DapContinued { dap_id });
+    }
+
+    pub fn dap_terminated(&self, dap_id: DapId) {
+        self.notification(CoreNotification::DapTerminated { dap_id });
+    }
+
+    pub fn dap_breakpoints(
+        &self,
+        dap_id: DapId,
+        path: PathBuf,
+        breakpoints: Vec<Breakpoint>,
+    ) {
+        self.notification(CoreNotification::DapBreakpoints {
+            dap_id,
+            path,
+            breakpoints,
+        });
+    }
+
+    pub fn dap_output(
+        &self,
+        dap_id: DapId,
+        category: Option<String>,
+        output: String,
+    ) {
+        self.notification(CoreNotification::DapOutput {
+            dap_id,
+            category,
+            output,
+        });
+    }
 }
 
 impl Default for CoreRpcHandler {
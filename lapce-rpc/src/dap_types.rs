@@ -0,0 +1,260 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::counter::Counter;
+
+#[derive(
+    Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone, Debug, Serialize, Deserialize,
+)]
+pub struct DapId(pub u64);
+
+impl DapId {
+    pub fn next() -> Self {
+        static DAP_ID_COUNTER: Counter = Counter::new();
+        Self(DAP_ID_COUNTER.next())
+    }
+}
+
+/// How a debug adapter is started, from the `[debuggers]` settings or
+/// registered by a plugin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DebuggerConfig {
+    /// The program of the adapter, which talks the debug adapter protocol
+    /// on its stdio
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// A process running on the host of the proxy, which a debug adapter can
+/// attach to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// The name of the program of the process
+    pub name: String,
+    /// The command line of the process, or the name where it's not known
+    pub command: String,
+}
+
+/// Whether the debug adapter starts the program, or connects to one that
+/// is already running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DebugRequest {
+    #[default]
+    Launch,
+    Attach,
+}
+
+impl DebugRequest {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DebugRequest::Launch => "launch",
+            DebugRequest::Attach => "attach",
+        }
+    }
+}
+
+/// A configuration of the `.lapce/launch.toml` of the workspace, which tells
+/// what program to debug and with which adapter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunDebugConfig {
+    pub name: String,
+    #[serde(default)]
+    pub request: DebugRequest,
+    /// The type of the debug adapter, as the settings or the plugin name it
+    #[serde(rename = "adapter", alias = "type")]
+    pub ty: String,
+    /// The program to launch, which attaching doesn't need
+    pub program: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    pub env: Option<HashMap<String, String>>,
+    /// Other arguments of the launch or attach request, which depend on the
+    /// adapter, like the process id to attach to
+    #[serde(alias = "launch")]
+    pub arguments: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceBreakpoint {
+    /// The line of the breakpoint, starting at 1
+    pub line: usize,
+    /// The expression that has to be true for the program to stop
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    /// How many hits the program stops after, like `>= 3`, which the
+    /// adapter interprets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hit_condition: Option<String>,
+    /// The message logged instead of stopping, with expressions in `{}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Breakpoint {
+    pub id: Option<usize>,
+    /// Whether the adapter could set the breakpoint
+    pub verified: bool,
+    pub message: Option<String>,
+    pub line: Option<usize>,
+}
+
+/// What the debug adapter supports, which it tells when it's initialized
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Capabilities {
+    pub supports_read_memory_request: bool,
+    pub supports_data_breakpoints: bool,
+    pub supports_restart_frame: bool,
+}
+
+/// What access to the data of a data breakpoint stops the program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataBreakpointAccessType {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+/// Whether a data breakpoint can be set on a variable, which the adapter
+/// tells from its name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataBreakpointInfo {
+    /// The id the breakpoint is set with, or `None` if it can't be set
+    pub data_id: Option<String>,
+    /// What the data is, or why the breakpoint can't be set
+    pub description: String,
+    pub access_types: Option<Vec<DataBreakpointAccessType>>,
+}
+
+/// A breakpoint that stops the program when the data is accessed, like a
+/// variable that changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataBreakpoint {
+    pub data_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_type: Option<DataBreakpointAccessType>,
+}
+
+/// The bytes of the memory read from the address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadMemoryResult {
+    pub address: u64,
+    pub data: Vec<u8>,
+}
+
+pub type ThreadId = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Thread {
+    pub id: ThreadId,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Source {
+    pub name: Option<String>,
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackFrame {
+    pub id: usize,
+    pub name: String,
+    pub source: Option<Source>,
+    /// The line of the frame, starting at 1
+    pub line: usize,
+    pub column: usize,
+    /// How the frame is shown, which is `label` for a frame that only
+    /// separates the others, and `subtle` for one that's less important,
+    /// like a frame of the runtime
+    pub presentation_hint: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scope {
+    pub name: String,
+    /// What the scope has, like `locals`, `arguments` or `registers`
+    pub presentation_hint: Option<String>,
+    pub variables_reference: usize,
+    /// Whether the variables are expensive to get, which are then only
+    /// shown when asked for
+    pub expensive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+    /// The reference to the children of the variable, or 0 if it has none
+    pub variables_reference: usize,
+    /// The reference to the memory the variable is in, for the adapters
+    /// that can read memory
+    pub memory_reference: Option<String>,
+}
+
+/// The value of an expression that the adapter evaluated
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateResult {
+    pub result: String,
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+    /// The reference to the children of the value, or 0 if it has none
+    #[serde(default)]
+    pub variables_reference: usize,
+}
+
+/// What an expression is evaluated for, which adapters can evaluate
+/// differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EvaluateContext {
+    Watch,
+    /// Typed in the debug console
+    Repl,
+}
+
+/// A completion of the text of the debug console
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionTarget {
+    pub label: String,
+    /// The text inserted, if it isn't the label
+    pub text: Option<String>,
+    /// Where in the text the completion starts, starting at 1, or the
+    /// column it was asked for at if it's not given
+    pub start: Option<usize>,
+    /// How many characters the completion replaces from the start
+    pub length: Option<usize>,
+}
+
+/// The body of the `stopped` event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stopped {
+    /// Why the program stopped, like `breakpoint`, `step` or `pause`
+    pub reason: String,
+    pub description: Option<String>,
+    pub thread_id: Option<ThreadId>,
+    #[serde(default)]
+    pub all_threads_stopped: bool,
+    pub text: Option<String>,
+}
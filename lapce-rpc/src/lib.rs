@@ -1,6 +1,7 @@
 pub mod buffer;
 pub mod core;
 pub mod counter;
+pub mod dap_types;
 pub mod file;
 mod parse;
 pub mod plugin;
@@ -9,6 +10,7 @@
 pub mod stdio;
 pub mod style;
 pub mod terminal;
+pub mod type_hierarchy;
 
 pub use parse::Call;
 pub use parse::RequestId;
@@ -23,6 +23,71 @@ pub struct PluginConfiguration {
     pub description: String,
 }
 
+/// A language server from the `[language-servers.<name>]` section of the
+/// settings. With a command, the server is started for the languages, next
+/// to the servers of the plugins, and without one it only sets the priority,
+/// the options and the settings of the servers started by the plugin with
+/// that name, or replaces the command the plugin starts them with.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LanguageServerConfig {
+    /// The command that starts the server, or `tcp://host:port` to connect
+    /// to a server that's already listening there. On unix, `pipe:path`
+    /// connects to one listening on a unix socket; Windows named pipes
+    /// aren't supported.
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// The command to start the servers of the plugin with instead of its
+    /// own, like a locally built server or a wrapper script
+    #[serde(default)]
+    pub override_command: Option<String>,
+    /// The arguments of the override command, which are the ones the plugin
+    /// gives if they're not set
+    #[serde(default)]
+    pub override_args: Option<Vec<String>>,
+    /// The language ids of the documents the server is started for
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// The initialization options of the server, merged over the ones the
+    /// plugin gives
+    #[serde(default)]
+    pub options: Option<Value>,
+    /// The settings the server asks for with `workspace/configuration`,
+    /// which it's sent again when they change
+    #[serde(default)]
+    pub settings: Option<Value>,
+    /// When several servers handle a document, the results of the one with
+    /// the highest priority are preferred, and listed first when merged
+    #[serde(default)]
+    pub priority: i64,
+}
+
+/// A running language server, as its status shows it
+#[derive(Deserialize, Clone, Debug, Serialize)]
+pub struct LanguageServerStatus {
+    pub plugin_id: PluginId,
+    /// The name the server gives itself
+    pub name: String,
+    /// The plugin that started the server, or its name in the settings
+    pub volt_id: String,
+    /// The process id of the server, unless it's connected to over a socket
+    pub pid: Option<u32>,
+    /// The command the server was started with, or the address it's
+    /// connected to
+    pub command: String,
+    /// Whether the command is the override command of the settings
+    pub command_overridden: bool,
+    /// Why the override command couldn't be used, so the plugin's was
+    pub override_error: Option<String>,
+    pub root: Option<PathBuf>,
+    /// The capabilities the server has, like `hoverProvider`
+    pub capabilities: Vec<String>,
+    /// How long the server has been running, in seconds
+    pub uptime: u64,
+}
+
 #[derive(Deserialize, Clone, Debug, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct VoltInfo {
@@ -9,10 +9,17 @@
 
 use crossbeam_channel::{Receiver, Sender};
 use lsp_types::{
-    request::GotoTypeDefinitionResponse, CodeActionResponse, CompletionItem,
-    DocumentSymbolResponse, GotoDefinitionResponse, Hover, InlayHint, Location,
-    Position, PrepareRenameResponse, SelectionRange, SymbolInformation,
-    TextDocumentItem, TextEdit, WorkspaceEdit,
+    request::{
+        GotoDeclarationResponse, GotoImplementationResponse,
+        GotoTypeDefinitionResponse,
+    },
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall,
+    CodeActionKind, CodeActionResponse, CodeLens, CompletionItem, Diagnostic,
+    DocumentHighlight, DocumentLink, DocumentSymbolResponse, FoldingRange,
+    GotoDefinitionResponse, Hover, InlayHint, LinkedEditingRanges, Location,
+    Position, PrepareRenameResponse, Range, SelectionRange, SignatureHelp,
+    SignatureHelpContext, SymbolInformation, TextDocumentItem, TextEdit,
+    WorkspaceEdit,
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
@@ -20,14 +27,35 @@
 
 use crate::{
     buffer::BufferId,
+    dap_types::{
+        CompletionTarget, DapId, DataBreakpoint, DataBreakpointInfo, DebuggerConfig,
+        EvaluateContext, EvaluateResult, ProcessInfo, ReadMemoryResult,
+        RunDebugConfig, Scope, SourceBreakpoint, StackFrame, ThreadId, Variable,
+    },
     file::FileNodeItem,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{
+        LanguageServerConfig, LanguageServerStatus, PluginId, VoltInfo, VoltMetadata,
+    },
     source_control::FileDiff,
     style::SemanticStyles,
-    terminal::TermId,
+    terminal::{TermId, TerminalProfile},
+    type_hierarchy::TypeHierarchyItem,
     RequestId, RpcError, RpcMessage,
 };
 
+/// What formats a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentFormatter {
+    /// The language server with the highest priority that formats it
+    LanguageServer,
+    /// The language server with the name, as in the language server settings
+    NamedLanguageServer(String),
+    /// A command run in a shell, which reads the document from stdin and
+    /// writes it formatted to stdout
+    Command(String),
+}
+
 pub enum ProxyRpc {
     Request(RequestId, ProxyRequest),
     Notification(ProxyNotification),
@@ -41,12 +69,29 @@ pub enum ProxyRequest {
     NewBuffer {
         buffer_id: BufferId,
         path: PathBuf,
+        /// The size in bytes above which the file is opened as a large file,
+        /// or 0 if files are never opened as large files
+        large_file_size: u64,
+    },
+    /// The chunk of a large file that starts at `offset`, since the core
+    /// loads large files a chunk at a time
+    BufferChunk {
+        path: PathBuf,
+        offset: usize,
     },
     BufferHead {
         path: PathBuf,
     },
+    ReadFile {
+        path: PathBuf,
+    },
+    ReopenWithEncoding {
+        path: PathBuf,
+        encoding: String,
+    },
     GlobalSearch {
         pattern: String,
+        is_regex: bool,
     },
     CompletionResolve {
         plugin_id: PluginId,
@@ -58,17 +103,56 @@ pub enum ProxyRequest {
         position: Position,
     },
     GetSignature {
-        buffer_id: BufferId,
+        request_id: usize,
+        path: PathBuf,
         position: Position,
+        /// Why the signature help is asked for, which the language server
+        /// can ignore when it wasn't typed one of its trigger characters
+        context: Option<SignatureHelpContext>,
     },
     GetSelectionRange {
         path: PathBuf,
         positions: Vec<Position>,
     },
+    GetDocumentHighlights {
+        path: PathBuf,
+        position: Position,
+    },
+    GetLinkedEditingRanges {
+        path: PathBuf,
+        position: Position,
+    },
+    GetDocumentLinks {
+        path: PathBuf,
+    },
     GetReferences {
         path: PathBuf,
         position: Position,
     },
+    PrepareCallHierarchy {
+        path: PathBuf,
+        position: Position,
+    },
+    CallHierarchyIncoming {
+        plugin_id: PluginId,
+        item: Box<CallHierarchyItem>,
+    },
+    CallHierarchyOutgoing {
+        plugin_id: PluginId,
+        item: Box<CallHierarchyItem>,
+    },
+    PrepareTypeHierarchy {
+        path: PathBuf,
+        position: Position,
+    },
+    TypeHierarchySupertypes {
+        plugin_id: PluginId,
+        item: Box<TypeHierarchyItem>,
+    },
+    TypeHierarchySubtypes {
+        plugin_id: PluginId,
+        item: Box<TypeHierarchyItem>,
+    },
     GetDefinition {
         request_id: usize,
         path: PathBuf,
@@ -79,9 +163,29 @@ pub enum ProxyRequest {
         path: PathBuf,
         position: Position,
     },
+    GetDeclaration {
+        request_id: usize,
+        path: PathBuf,
+        position: Position,
+    },
+    GetImplementation {
+        request_id: usize,
+        path: PathBuf,
+        position: Position,
+    },
     GetInlayHints {
         path: PathBuf,
     },
+    GetCodeLens {
+        path: PathBuf,
+    },
+    ExecuteCommand {
+        plugin_id: PluginId,
+        command: lsp_types::Command,
+    },
+    GetFoldingRanges {
+        path: PathBuf,
+    },
     GetSemanticTokens {
         path: PathBuf,
     },
@@ -98,6 +202,19 @@ pub enum ProxyRequest {
         path: PathBuf,
         position: Position,
     },
+    /// The code actions of the kinds in the range, like the ones that run
+    /// on save over the whole document
+    GetCodeActionsOfKinds {
+        path: PathBuf,
+        range: Range,
+        kinds: Vec<CodeActionKind>,
+    },
+    /// The quick fixes of a diagnostic, which is sent to the servers as the
+    /// context of the code actions
+    GetQuickFixes {
+        path: PathBuf,
+        diagnostic: Diagnostic,
+    },
     GetDocumentSymbols {
         path: PathBuf,
     },
@@ -107,8 +224,21 @@ pub enum ProxyRequest {
     },
     GetDocumentFormatting {
         path: PathBuf,
+        /// Only format this part of the document
+        range: Option<Range>,
+        formatter: DocumentFormatter,
+        /// How long an external command may take, in milliseconds
+        timeout: u64,
+    },
+    /// The edits formatting the document around the character that was just
+    /// typed at the position
+    GetOnTypeFormatting {
+        path: PathBuf,
+        position: Position,
+        ch: String,
     },
     GetOpenFilesContent {},
+    GetLanguageServers {},
     GetFiles {
         path: String,
     },
@@ -118,12 +248,14 @@ pub enum ProxyRequest {
     Save {
         rev: u64,
         path: PathBuf,
+        encoding: String,
     },
     SaveBufferAs {
         buffer_id: BufferId,
         path: PathBuf,
         rev: u64,
         content: String,
+        encoding: String,
     },
     CreateFile {
         path: PathBuf,
@@ -138,6 +270,52 @@ pub enum ProxyRequest {
         from: PathBuf,
         to: PathBuf,
     },
+    /// Evaluate the expression in the frame of the stopped program
+    DapEvaluate {
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: EvaluateContext,
+    },
+    /// The completions of the text of the debug console at the column,
+    /// which starts at 1
+    DapCompletions {
+        dap_id: DapId,
+        text: String,
+        column: usize,
+        frame_id: Option<usize>,
+    },
+    /// The children of a variable or of the value of an expression
+    DapVariables {
+        dap_id: DapId,
+        variables_reference: usize,
+    },
+    /// The processes running on the host, to attach a debugger to
+    ListProcesses {},
+    /// The stack of the thread of the stopped program
+    DapStackTrace {
+        dap_id: DapId,
+        thread_id: ThreadId,
+    },
+    /// The variables of the frame, by scope
+    DapScopes {
+        dap_id: DapId,
+        frame_id: usize,
+    },
+    /// Read the bytes of the memory from the offset of the reference
+    DapReadMemory {
+        dap_id: DapId,
+        memory_reference: String,
+        offset: i64,
+        count: usize,
+    },
+    /// Whether a data breakpoint can be set on the variable, which is a
+    /// child of the reference
+    DapDataBreakpointInfo {
+        dap_id: DapId,
+        variables_reference: usize,
+        name: String,
+    },
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -147,6 +325,7 @@ pub enum ProxyNotification {
         workspace: Option<PathBuf>,
         disabled_volts: Vec<String>,
         plugin_configurations: HashMap<String, serde_json::Value>,
+        language_servers: HashMap<String, LanguageServerConfig>,
         window_id: usize,
         tab_id: usize,
     },
@@ -171,8 +350,13 @@ pub enum ProxyNotification {
     },
     NewTerminal {
         term_id: TermId,
-        cwd: Option<PathBuf>,
-        shell: String,
+        profile: TerminalProfile,
+        /// Whether the shell is started with the shell integration, which
+        /// marks where each command starts and ends
+        shell_integration: bool,
+        /// The tmux session the shell runs in, which is attached to if it's
+        /// still running, so that the shell outlives the proxy
+        session: Option<String>,
     },
     InstallVolt {
         volt: VoltInfo,
@@ -210,19 +394,100 @@ pub enum ProxyNotification {
     TerminalClose {
         term_id: TermId,
     },
+    /// Start the debug adapter and launch the program of the configuration
+    /// with it, with the breakpoints set. The adapter is the one of the
+    /// settings, or else the one a plugin registered for the type.
+    DapStart {
+        dap_id: DapId,
+        config: RunDebugConfig,
+        debugger: Option<DebuggerConfig>,
+        breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+    },
+    DapContinue {
+        dap_id: DapId,
+        thread_id: ThreadId,
+    },
+    DapPause {
+        dap_id: DapId,
+        thread_id: ThreadId,
+    },
+    DapStepOver {
+        dap_id: DapId,
+        thread_id: ThreadId,
+    },
+    DapStepInto {
+        dap_id: DapId,
+        thread_id: ThreadId,
+    },
+    DapStepOut {
+        dap_id: DapId,
+        thread_id: ThreadId,
+    },
+    /// Stop the debugged program and the adapter
+    DapStop {
+        dap_id: DapId,
+    },
+    DapSetBreakpoints {
+        dap_id: DapId,
+        path: PathBuf,
+        breakpoints: Vec<SourceBreakpoint>,
+    },
+    /// Run the frame again from its start
+    DapRestartFrame {
+        dap_id: DapId,
+        frame_id: usize,
+    },
+    /// Set the data breakpoints of the program, replacing the ones it had
+    DapSetDataBreakpoints {
+        dap_id: DapId,
+        breakpoints: Vec<DataBreakpoint>,
+    },
+    /// Log the messages exchanged with the language servers, or stop it
+    SetLspTrace {
+        enabled: bool,
+    },
+    /// The `[language-servers]` settings changed, by the user or the
+    /// workspace settings
+    UpdateLanguageServers {
+        language_servers: HashMap<String, LanguageServerConfig>,
+    },
+    /// Stop the language server and start it again
+    RestartLanguageServer {
+        plugin_id: PluginId,
+    },
+    StopLanguageServer {
+        plugin_id: PluginId,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
 pub enum ProxyResponse {
+    /// The content of a new buffer, which is only its first chunk for a
+    /// large file
     NewBufferResponse {
         content: String,
+        encoding: String,
+        large_file: bool,
+        read_only: bool,
+    },
+    /// A chunk of a large file, and whether it's the last one
+    BufferChunkResponse {
+        content: String,
+        last: bool,
+    },
+    ReopenWithEncodingResponse {
+        content: String,
+        encoding: String,
     },
     BufferHeadResponse {
         version: String,
         content: String,
     },
+    ReadFileResponse {
+        content: String,
+    },
     ReadDirResponse {
         items: HashMap<PathBuf, FileNodeItem>,
     },
@@ -233,6 +498,10 @@ pub enum ProxyResponse {
         request_id: usize,
         hover: Hover,
     },
+    SignatureResponse {
+        request_id: usize,
+        signature: SignatureHelp,
+    },
     GetDefinitionResponse {
         request_id: usize,
         definition: GotoDefinitionResponse,
@@ -241,9 +510,38 @@ pub enum ProxyResponse {
         request_id: usize,
         definition: GotoTypeDefinitionResponse,
     },
+    GetDeclaration {
+        request_id: usize,
+        declaration: GotoDeclarationResponse,
+    },
+    GetImplementation {
+        request_id: usize,
+        implementation: GotoImplementationResponse,
+    },
     GetReferencesResponse {
         references: Vec<Location>,
     },
+    PrepareCallHierarchyResponse {
+        /// The language server the items are from, which is asked for their
+        /// calls
+        plugin_id: PluginId,
+        items: Vec<CallHierarchyItem>,
+    },
+    CallHierarchyIncomingResponse {
+        calls: Vec<CallHierarchyIncomingCall>,
+    },
+    CallHierarchyOutgoingResponse {
+        calls: Vec<CallHierarchyOutgoingCall>,
+    },
+    PrepareTypeHierarchyResponse {
+        /// The language server the items are from, which is asked for their
+        /// supertypes and subtypes
+        plugin_id: PluginId,
+        items: Vec<TypeHierarchyItem>,
+    },
+    TypeHierarchyResponse {
+        items: Vec<TypeHierarchyItem>,
+    },
     GetCodeActionsResponse {
         resp: CodeActionResponse,
     },
@@ -262,9 +560,29 @@ pub enum ProxyResponse {
     GetSelectionRange {
         ranges: Vec<SelectionRange>,
     },
+    GetDocumentHighlights {
+        highlights: Vec<DocumentHighlight>,
+    },
+    GetLinkedEditingRanges {
+        ranges: Option<LinkedEditingRanges>,
+    },
+    GetDocumentLinks {
+        links: Vec<DocumentLink>,
+    },
     GetInlayHints {
         hints: Vec<InlayHint>,
     },
+    GetCodeLens {
+        /// The language server the lenses are from, which runs their commands
+        plugin_id: PluginId,
+        lenses: Vec<CodeLens>,
+    },
+    ExecuteCommandResponse {
+        result: serde_json::Value,
+    },
+    GetFoldingRanges {
+        ranges: Vec<FoldingRange>,
+    },
     GetSemanticTokens {
         styles: SemanticStyles,
     },
@@ -277,12 +595,44 @@ pub enum ProxyResponse {
     GetOpenFilesContentResponse {
         items: Vec<TextDocumentItem>,
     },
+    GetLanguageServersResponse {
+        servers: Vec<LanguageServerStatus>,
+    },
+    DapEvaluateResponse {
+        result: EvaluateResult,
+    },
+    DapVariablesResponse {
+        variables: Vec<Variable>,
+    },
+    DapCompletionsResponse {
+        targets: Vec<CompletionTarget>,
+    },
+    ListProcessesResponse {
+        processes: Vec<ProcessInfo>,
+    },
+    DapStackTraceResponse {
+        stack_frames: Vec<StackFrame>,
+    },
+    DapScopesResponse {
+        variables: Vec<(Scope, Vec<Variable>)>,
+    },
+    DapReadMemoryResponse {
+        result: ReadMemoryResult,
+    },
+    DapDataBreakpointInfoResponse {
+        info: DataBreakpointInfo,
+    },
     GlobalSearchResponse {
         #[allow(clippy::type_complexity)]
         matches: HashMap<PathBuf, Vec<(usize, (usize, usize), String)>>,
     },
     Success {},
     SaveResponse {},
+    /// The file was changed on disk since it was last loaded or saved, so
+    /// it wasn't saved over, with its `content` on disk
+    SaveConflictResponse {
+        content: String,
+    },
 }
 
 pub type ProxyMessage = RpcMessage<ProxyRequest, ProxyNotification, ProxyResponse>;
@@ -430,6 +780,27 @@ pub fn enable_volt(&self, volt: VoltInfo) {
         self.notification(ProxyNotification::EnableVolt { volt });
     }
 
+    pub fn set_lsp_trace(&self, enabled: bool) {
+        self.notification(ProxyNotification::SetLspTrace { enabled });
+    }
+
+    pub fn update_language_servers(
+        &self,
+        language_servers: HashMap<String, LanguageServerConfig>,
+    ) {
+        self.notification(ProxyNotification::UpdateLanguageServers {
+            language_servers,
+        });
+    }
+
+    pub fn restart_language_server(&self, plugin_id: PluginId) {
+        self.notification(ProxyNotification::RestartLanguageServer { plugin_id });
+    }
+
+    pub fn stop_language_server(&self, plugin_id: PluginId) {
+        self.notification(ProxyNotification::StopLanguageServer { plugin_id });
+    }
+
     pub fn shutdown(&self) {
         self.notification(ProxyNotification::Shutdown {});
         let _ = self.tx.send(ProxyRpc::Shutdown);
@@ -440,6 +811,7 @@ pub fn initialize(
         workspace: Option<PathBuf>,
         disabled_volts: Vec<String>,
         plugin_configurations: HashMap<String, serde_json::Value>,
+        language_servers: HashMap<String, LanguageServerConfig>,
         window_id: usize,
         tab_id: usize,
     ) {
@@ -447,6 +819,7 @@ pub fn initialize(
             workspace,
             disabled_volts,
             plugin_configurations,
+            language_servers,
             window_id,
             tab_id,
         });
@@ -470,13 +843,15 @@ pub fn completion(
     pub fn new_terminal(
         &self,
         term_id: TermId,
-        cwd: Option<PathBuf>,
-        shell: String,
+        profile: TerminalProfile,
+        shell_integration: bool,
+        session: Option<String>,
     ) {
         self.notification(ProxyNotification::NewTerminal {
             term_id,
-            cwd,
-            shell,
+            profile,
+            shell_integration,
+            session,
         })
     }
 
@@ -499,13 +874,199 @@ pub fn terminal_write(&self, term_id: TermId, content: &str) {
         });
     }
 
+    pub fn dap_start(
+        &self,
+        dap_id: DapId,
+        config: RunDebugConfig,
+        debugger: Option<DebuggerConfig>,
+        breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
+    ) {
+        self.notification(ProxyNotification::DapStart {
+            dap_id,
+            config,
+            debugger,
+            breakpoints,
+        });
+    }
+
+    pub fn dap_continue(&self, dap_id: DapId, thread_id: ThreadId) {
+        self.notification(ProxyNotification::DapContinue { dap_id, thread_id });
+    }
+
+    pub fn dap_pause(&self, dap_id: DapId, thread_id: ThreadId) {
+        self.notification(ProxyNotification::DapPause { dap_id, thread_id });
+    }
+
+    pub fn dap_step_over(&self, dap_id: DapId, thread_id: ThreadId) {
+        self.notification(ProxyNotification::DapStepOver { dap_id, thread_id });
+    }
+
+    pub fn dap_step_into(&self, dap_id: DapId, thread_id: ThreadId) {
+        self.notification(ProxyNotification::DapStepInto { dap_id, thread_id });
+    }
+
+    pub fn dap_step_out(&self, dap_id: DapId, thread_id: ThreadId) {
+        self.notification(ProxyNotification::DapStepOut { dap_id, thread_id });
+    }
+
+    pub fn dap_stop(&self, dap_id: DapId) {
+        self.notification(ProxyNotification::DapStop { dap_id });
+    }
+
+    pub fn dap_set_breakpoints(
+        &self,
+        dap_id: DapId,
+        path: PathBuf,
+        breakpoints: Vec<SourceBreakpoint>,
+    ) {
+        self.notification(ProxyNotification::DapSetBreakpoints {
+            dap_id,
+            path,
+            breakpoints,
+        });
+    }
+
+    pub fn dap_restart_frame(&self, dap_id: DapId, frame_id: usize) {
+        self.notification(ProxyNotification::DapRestartFrame { dap_id, frame_id });
+    }
+
+    pub fn dap_set_data_breakpoints(
+        &self,
+        dap_id: DapId,
+        breakpoints: Vec<DataBreakpoint>,
+    ) {
+        self.notification(ProxyNotification::DapSetDataBreakpoints {
+            dap_id,
+            breakpoints,
+        });
+    }
+
+    pub fn dap_evaluate(
+        &self,
+        dap_id: DapId,
+        expression: String,
+        frame_id: Option<usize>,
+        context: EvaluateContext,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapEvaluate {
+                dap_id,
+                expression,
+                frame_id,
+                context,
+            },
+            f,
+        );
+    }
+
+    pub fn dap_completions(
+        &self,
+        dap_id: DapId,
+        text: String,
+        column: usize,
+        frame_id: Option<usize>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapCompletions {
+                dap_id,
+                text,
+                column,
+                frame_id,
+            },
+            f,
+        );
+    }
+
+    pub fn dap_variables(
+        &self,
+        dap_id: DapId,
+        variables_reference: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapVariables {
+                dap_id,
+                variables_reference,
+            },
+            f,
+        );
+    }
+
+    pub fn list_processes(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::ListProcesses {}, f);
+    }
+
+    pub fn dap_stack_trace(
+        &self,
+        dap_id: DapId,
+        thread_id: ThreadId,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::DapStackTrace { dap_id, thread_id }, f);
+    }
+
+    pub fn dap_scopes(
+        &self,
+        dap_id: DapId,
+        frame_id: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::DapScopes { dap_id, frame_id }, f);
+    }
+
+    pub fn dap_read_memory(
+        &self,
+        dap_id: DapId,
+        memory_reference: String,
+        offset: i64,
+        count: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapReadMemory {
+                dap_id,
+                memory_reference,
+                offset,
+                count,
+            },
+            f,
+        );
+    }
+
+    pub fn dap_data_breakpoint_info(
+        &self,
+        dap_id: DapId,
+        variables_reference: usize,
+        name: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::DapDataBreakpointInfo {
+                dap_id,
+                variables_reference,
+                name,
+            },
+            f,
+        );
+    }
+
     pub fn new_buffer(
         &self,
         buffer_id: BufferId,
         path: PathBuf,
+        large_file_size: u64,
         f: impl ProxyCallback + 'static,
     ) {
-        self.request_async(ProxyRequest::NewBuffer { buffer_id, path }, f);
+        self.request_async(
+            ProxyRequest::NewBuffer {
+                buffer_id,
+                path,
+                large_file_size,
+            },
+            f,
+        );
     }
 
     pub fn get_buffer_head(
@@ -517,6 +1078,28 @@ pub fn get_buffer_head(
         self.request_async(ProxyRequest::BufferHead { path }, f);
     }
 
+    pub fn buffer_chunk(
+        &self,
+        path: PathBuf,
+        offset: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::BufferChunk { path, offset }, f);
+    }
+
+    pub fn read_file(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::ReadFile { path }, f);
+    }
+
+    pub fn reopen_with_encoding(
+        &self,
+        path: PathBuf,
+        encoding: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ReopenWithEncoding { path, encoding }, f);
+    }
+
     pub fn create_file(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::CreateFile { path }, f);
     }
@@ -544,6 +1127,7 @@ pub fn save_buffer_as(
         path: PathBuf,
         rev: u64,
         content: String,
+        encoding: String,
         f: impl ProxyCallback + 'static,
     ) {
         self.request_async(
@@ -552,17 +1136,36 @@ pub fn save_buffer_as(
                 path,
                 rev,
                 content,
+                encoding,
             },
             f,
         );
     }
 
-    pub fn global_search(&self, pattern: String, f: impl ProxyCallback + 'static) {
-        self.request_async(ProxyRequest::GlobalSearch { pattern }, f);
+    pub fn global_search(
+        &self,
+        pattern: String,
+        is_regex: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GlobalSearch { pattern, is_regex }, f);
     }
 
-    pub fn save(&self, rev: u64, path: PathBuf, f: impl ProxyCallback + 'static) {
-        self.request_async(ProxyRequest::Save { rev, path }, f);
+    pub fn save(
+        &self,
+        rev: u64,
+        path: PathBuf,
+        encoding: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::Save {
+                rev,
+                path,
+                encoding,
+            },
+            f,
+        );
     }
 
     pub fn get_files(&self, f: impl ProxyCallback + 'static) {
@@ -578,6 +1181,10 @@ pub fn get_open_files_content(&self) -> Result<ProxyResponse, RpcError> {
         self.request(ProxyRequest::GetOpenFilesContent {})
     }
 
+    pub fn get_language_servers(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GetLanguageServers {}, f);
+    }
+
     pub fn read_dir(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::ReadDir { path }, f);
     }
@@ -614,6 +1221,25 @@ pub fn get_hover(
         );
     }
 
+    pub fn get_signature(
+        &self,
+        request_id: usize,
+        path: PathBuf,
+        position: Position,
+        context: Option<SignatureHelpContext>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetSignature {
+                request_id,
+                path,
+                position,
+                context,
+            },
+            f,
+        );
+    }
+
     pub fn get_definition(
         &self,
         request_id: usize,
@@ -648,6 +1274,40 @@ pub fn get_type_definition(
         );
     }
 
+    pub fn get_declaration(
+        &self,
+        request_id: usize,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetDeclaration {
+                request_id,
+                path,
+                position,
+            },
+            f,
+        );
+    }
+
+    pub fn get_implementation(
+        &self,
+        request_id: usize,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetImplementation {
+                request_id,
+                path,
+                position,
+            },
+            f,
+        );
+    }
+
     pub fn get_references(
         &self,
         path: PathBuf,
@@ -657,6 +1317,84 @@ pub fn get_references(
         self.request_async(ProxyRequest::GetReferences { path, position }, f);
     }
 
+    pub fn prepare_call_hierarchy(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::PrepareCallHierarchy { path, position }, f);
+    }
+
+    pub fn call_hierarchy_incoming(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CallHierarchyIncoming {
+                plugin_id,
+                item: Box::new(item),
+            },
+            f,
+        );
+    }
+
+    pub fn call_hierarchy_outgoing(
+        &self,
+        plugin_id: PluginId,
+        item: CallHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::CallHierarchyOutgoing {
+                plugin_id,
+                item: Box::new(item),
+            },
+            f,
+        );
+    }
+
+    pub fn prepare_type_hierarchy(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::PrepareTypeHierarchy { path, position }, f);
+    }
+
+    pub fn type_hierarchy_supertypes(
+        &self,
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::TypeHierarchySupertypes {
+                plugin_id,
+                item: Box::new(item),
+            },
+            f,
+        );
+    }
+
+    pub fn type_hierarchy_subtypes(
+        &self,
+        plugin_id: PluginId,
+        item: TypeHierarchyItem,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::TypeHierarchySubtypes {
+                plugin_id,
+                item: Box::new(item),
+            },
+            f,
+        );
+    }
+
     pub fn get_code_actions(
         &self,
         path: PathBuf,
@@ -666,12 +1404,58 @@ pub fn get_code_actions(
         self.request_async(ProxyRequest::GetCodeActions { path, position }, f);
     }
 
+    pub fn get_code_actions_of_kinds(
+        &self,
+        path: PathBuf,
+        range: Range,
+        kinds: Vec<CodeActionKind>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetCodeActionsOfKinds { path, range, kinds },
+            f,
+        );
+    }
+
+    pub fn get_on_type_formatting(
+        &self,
+        path: PathBuf,
+        position: Position,
+        ch: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetOnTypeFormatting { path, position, ch },
+            f,
+        );
+    }
+
+    pub fn get_quick_fixes(
+        &self,
+        path: PathBuf,
+        diagnostic: Diagnostic,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetQuickFixes { path, diagnostic }, f);
+    }
+
     pub fn get_document_formatting(
         &self,
         path: PathBuf,
+        range: Option<Range>,
+        formatter: DocumentFormatter,
+        timeout: u64,
         f: impl ProxyCallback + 'static,
     ) {
-        self.request_async(ProxyRequest::GetDocumentFormatting { path }, f);
+        self.request_async(
+            ProxyRequest::GetDocumentFormatting {
+                path,
+                range,
+                formatter,
+                timeout,
+            },
+            f,
+        );
     }
 
     pub fn get_semantic_tokens(
@@ -728,6 +1512,27 @@ pub fn get_inlay_hints(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::GetInlayHints { path }, f);
     }
 
+    pub fn get_code_lens(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GetCodeLens { path }, f);
+    }
+
+    pub fn execute_command(
+        &self,
+        plugin_id: PluginId,
+        command: lsp_types::Command,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ExecuteCommand { plugin_id, command }, f);
+    }
+
+    pub fn get_folding_ranges(
+        &self,
+        path: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetFoldingRanges { path }, f);
+    }
+
     pub fn update(&self, path: PathBuf, delta: RopeDelta, rev: u64) {
         self.notification(ProxyNotification::Update { path, delta, rev });
     }
@@ -748,6 +1553,38 @@ pub fn get_selection_range(
     ) {
         self.request_async(ProxyRequest::GetSelectionRange { path, positions }, f);
     }
+
+    pub fn get_document_highlights(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetDocumentHighlights { path, position },
+            f,
+        );
+    }
+
+    pub fn get_linked_editing_ranges(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetLinkedEditingRanges { path, position },
+            f,
+        );
+    }
+
+    pub fn get_document_links(
+        &self,
+        path: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetDocumentLinks { path }, f);
+    }
 }
 
 impl Default for ProxyRpcHandler {
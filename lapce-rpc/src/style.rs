@@ -14,6 +14,34 @@ pub struct LineStyle {
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Style {
     pub fg_color: Option<String>,
+    #[serde(default)]
+    pub modifiers: StyleModifiers,
+}
+
+/// The semantic token modifiers a language server gives to a token, which
+/// are drawn on top of the color of the token
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StyleModifiers {
+    pub mutable: bool,
+    pub is_async: bool,
+    pub deprecated: bool,
+}
+
+impl StyleModifiers {
+    pub fn is_empty(&self) -> bool {
+        !self.mutable && !self.is_async && !self.deprecated
+    }
+
+    /// Set the modifier of the given name, ignoring the ones that aren't
+    /// drawn
+    pub fn set(&mut self, name: &str) {
+        match name {
+            "mutable" => self.mutable = true,
+            "async" => self.is_async = true,
+            "deprecated" => self.deprecated = true,
+            _ => {}
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -1,3 +1,5 @@
+use std::{collections::HashMap, path::PathBuf};
+
 use serde::{Deserialize, Serialize};
 
 use crate::counter::Counter;
@@ -11,3 +13,23 @@ pub fn next() -> Self {
         Self(TERMINAL_ID_COUNTER.next())
     }
 }
+
+/// How a terminal is started, from a profile of the `[terminal-profiles]`
+/// settings, or from the `shell` setting
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalProfile {
+    /// The shell to run, or the default shell of the system if it's not set
+    pub command: Option<String>,
+    pub arguments: Vec<String>,
+    /// The environment variables that the shell is started with, besides
+    /// the ones of Lapce
+    pub env: HashMap<String, String>,
+    /// The directory the shell starts in, relative to the workspace
+    pub workdir: Option<PathBuf>,
+    /// The icon shown in the header of the terminal
+    pub icon: Option<String>,
+    /// The color of the terminal theme, like `red`, that marks the header of
+    /// the terminal
+    pub color: Option<String>,
+}
@@ -3,20 +3,25 @@
 use clap::Parser;
 use druid::{
     AppDelegate, AppLauncher, Command, Env, Event, LocalizedString, Point, Region,
-    Size, Target, Widget, WidgetExt, WidgetPod, WindowDesc, WindowHandle, WindowId,
-    WindowState,
+    Size, Target, Widget, WidgetExt, WidgetId, WidgetPod, WindowDesc, WindowHandle,
+    WindowId, WindowState,
 };
 #[cfg(target_os = "macos")]
 use druid::{Menu, MenuItem, SysMods};
 use lapce_data::{
-    command::{LapceUICommand, LAPCE_UI_COMMAND},
-    config::Config,
+    alert::AlertContentData,
+    command::{
+        CommandKind, LapceCommand, LapceUICommand, LapceWorkbenchCommand,
+        LAPCE_UI_COMMAND,
+    },
+    config::{AutoSave, Config},
     data::{
         LapceData, LapceTabLens, LapceWindowData, LapceWindowLens, LapceWorkspace,
         LapceWorkspaceType,
     },
     db::{TabsInfo, WindowInfo},
     proxy::VERSION,
+    update::UpdateErrorKind,
 };
 
 use crate::{logging::override_log_levels, tab::LAPCE_TAB_META};
@@ -35,6 +40,34 @@ pub fn build_window(data: &mut LapceWindowData) -> impl Widget<LapceData> {
     LapceWindow::new(data).lens(LapceWindowLens(data.window_id))
 }
 
+/// The alert shown when an update fails. Transient failures, like network
+/// problems, can be retried.
+fn update_error_alert(err: &anyhow::Error, tab_id: WidgetId) -> AlertContentData {
+    match lapce_data::update::error_kind(err) {
+        UpdateErrorKind::Transient => AlertContentData {
+            title: "The update couldn't be downloaded".to_string(),
+            msg: format!(
+                "{err}\n\nThis might be a temporary network problem, please try again later."
+            ),
+            buttons: vec![(
+                "Try Again".to_string(),
+                tab_id,
+                LapceCommand {
+                    kind: CommandKind::Workbench(
+                        LapceWorkbenchCommand::RestartToUpdate,
+                    ),
+                    data: None,
+                },
+            )],
+        },
+        UpdateErrorKind::Permanent => AlertContentData {
+            title: "The update failed".to_string(),
+            msg: err.to_string(),
+            buttons: Vec::new(),
+        },
+    }
+}
+
 pub fn launch() {
     let cli = Cli::parse();
     let pwd = std::env::current_dir().unwrap_or_default();
@@ -265,6 +298,20 @@ fn event(
                 return None;
             }
             Event::WindowGotFocus(window_id) => {
+                if *data.active_window != window_id {
+                    if let Some(window) = data.windows.get(&data.active_window) {
+                        for (_, tab) in window.tabs.iter() {
+                            ctx.submit_command(Command::new(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::AutoSave(
+                                    AutoSave::OnWindowChange,
+                                    None,
+                                ),
+                                Target::Widget(tab.id),
+                            ));
+                        }
+                    }
+                }
                 data.active_window = Arc::new(window_id);
                 return Some(event);
             }
@@ -371,15 +418,36 @@ fn command(
                             Some(release.clone());
                         return druid::Handled::Yes;
                     }
-                    LapceUICommand::RestartToUpdate(process_path, release) => {
+                    LapceUICommand::RestartToUpdate(
+                        process_path,
+                        release,
+                        settings,
+                    ) => {
                         let _ = data.db.save_app(data);
                         let process_path = process_path.clone();
                         let release = release.clone();
+                        let settings = settings.clone();
+                        let tab_id = data
+                            .windows
+                            .get(&*data.active_window)
+                            .map(|window| *window.active_id);
+                        let event_sink = ctx.get_external_handle();
                         std::thread::spawn(move || {
+                            let report_progress = |progress| {
+                                let _ = event_sink.submit_command(
+                                    LAPCE_UI_COMMAND,
+                                    LapceUICommand::UpdateDownloadProgress(progress),
+                                    Target::Global,
+                                );
+                            };
                             let do_update = || -> anyhow::Result<()> {
                                 log::info!("start to down new versoin");
-                                let src =
-                                    lapce_data::update::download_release(&release)?;
+                                let src = lapce_data::update::download_release(
+                                    &release,
+                                    &settings,
+                                    |progress| report_progress(Some(progress)),
+                                )?;
+                                report_progress(None);
 
                                 log::info!("start to extract");
                                 let path = lapce_data::update::extract(
@@ -395,10 +463,72 @@ fn command(
 
                             if let Err(err) = do_update() {
                                 log::error!("Failed to update: {err}");
+                                report_progress(None);
+
+                                if lapce_data::update::is_download_cancelled() {
+                                    return;
+                                }
+                                if let Some(tab_id) = tab_id {
+                                    let _ = event_sink.submit_command(
+                                        LAPCE_UI_COMMAND,
+                                        LapceUICommand::ShowAlert(
+                                            update_error_alert(&err, tab_id),
+                                        ),
+                                        Target::Widget(tab_id),
+                                    );
+                                }
                             }
                         });
                         return druid::Handled::Yes;
                     }
+                    LapceUICommand::DismissUpdate(dismissal) => {
+                        if let Err(err) = data.db.save_update_dismissal(dismissal) {
+                            log::error!("can't save update dismissal: {err}");
+                        }
+                        if let Some(release) =
+                            Arc::make_mut(&mut data.latest_release)
+                        {
+                            if release.version == dismissal.version {
+                                release.dismissed = true;
+                            }
+                        }
+                        return druid::Handled::Yes;
+                    }
+                    LapceUICommand::RollbackUpdate(process_path) => {
+                        let _ = data.db.save_app(data);
+                        let process_path = process_path.clone();
+                        std::thread::spawn(move || {
+                            let do_rollback = || -> anyhow::Result<()> {
+                                log::info!(
+                                    "roll back to {:?}",
+                                    lapce_data::update::previous_version()
+                                );
+                                let path =
+                                    lapce_data::update::rollback(&process_path)?;
+
+                                log::info!("now restart {path:?}");
+                                lapce_data::update::restart(&path)?;
+
+                                Ok(())
+                            };
+
+                            if let Err(err) = do_rollback() {
+                                log::error!("Failed to roll back update: {err}");
+                            }
+                        });
+                        return druid::Handled::Yes;
+                    }
+                    LapceUICommand::UpdateDownloadProgress(progress) => {
+                        let progress = Arc::new(progress.clone());
+                        data.update_progress = progress.clone();
+                        for (_, window) in data.windows.iter_mut() {
+                            window.update_progress = progress.clone();
+                            for (_, tab) in window.tabs.iter_mut() {
+                                tab.update_progress = progress.clone();
+                            }
+                        }
+                        return druid::Handled::Yes;
+                    }
                     LapceUICommand::OpenPaths {
                         window_tab_id,
                         folders,
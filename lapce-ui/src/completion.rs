@@ -254,18 +254,22 @@ fn update_documentation(&mut self, data: &LapceTabData) {
             return;
         }
 
-        let documentation = if data.config.editor.completion_show_documentation {
-            let current_item = (!data.completion.is_empty())
+        let current_item = if data.config.editor.completion_show_documentation {
+            (!data.completion.is_empty())
                 .then(|| data.completion.current_item())
-                .flatten();
-
-            current_item.and_then(|item| item.item.documentation.as_ref())
+                .flatten()
         } else {
             None
         };
 
-        let text = if let Some(documentation) = documentation {
-            parse_documentation(documentation, &data.config)
+        let text = if let Some(item) = current_item.filter(|item| {
+            item.item.documentation.is_some() || item.item.detail.is_some()
+        }) {
+            parse_documentation(
+                item.item.detail.as_deref(),
+                item.item.documentation.as_ref(),
+                &data.config,
+            )
         } else {
             // There is no documentation so we clear the text
             RichText::new(ArcStr::from(""))
@@ -302,6 +306,20 @@ fn event(
         match event {
             Event::Command(cmd) if cmd.is(LAPCE_UI_COMMAND) => {
                 let command = cmd.get_unchecked(LAPCE_UI_COMMAND);
+                if let LapceUICommand::CompletionItemResolved {
+                    plugin_id,
+                    item,
+                    resolved,
+                } = command
+                {
+                    Arc::make_mut(&mut data.completion).set_resolved_item(
+                        *plugin_id,
+                        item,
+                        *resolved.clone(),
+                    );
+                    ctx.set_handled();
+                    return;
+                }
                 if let LapceUICommand::ListItemSelected = command {
                     if let Some(editor) = data
                         .main_split
@@ -380,6 +398,12 @@ fn update(
             let completion_list_changed = !completion
                 .completion_list
                 .same(&old_completion.completion_list);
+            if completion_list_changed
+                && old_completion.current_item() != completion.current_item()
+            {
+                completion
+                    .resolve_current_item(&data.proxy, ctx.get_external_handle());
+            }
             if old_data.completion.input != data.completion.input
                 || old_data.completion.request_id != data.completion.request_id
                 || old_data.completion.status != data.completion.status
@@ -682,23 +706,40 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
     }
 }
 
-fn parse_documentation(doc: &Documentation, config: &Config) -> RichText {
-    match doc {
+/// The detail of the item, in the editor font, and its documentation below it
+fn parse_documentation(
+    detail: Option<&str>,
+    doc: Option<&Documentation>,
+    config: &Config,
+) -> RichText {
+    let text = match doc {
+        Some(Documentation::MarkupContent(content))
+            if content.kind == MarkupKind::Markdown =>
+        {
+            return match detail {
+                Some(detail) => parse_markdown(
+                    &format!("```\n{detail}\n```\n{}", content.value),
+                    config,
+                ),
+                None => parse_markdown(&content.value, config),
+            };
+        }
         // We assume this is plain text
-        Documentation::String(text) => {
-            let mut builder = RichTextBuilder::new();
-            builder.set_line_height(1.5);
-            builder.push(text);
-            builder.build()
+        Some(Documentation::String(text)) => text.as_str(),
+        Some(Documentation::MarkupContent(content)) => content.value.as_str(),
+        None => "",
+    };
+
+    let mut builder = RichTextBuilder::new();
+    builder.set_line_height(1.5);
+    if let Some(detail) = detail {
+        builder
+            .push(detail)
+            .font_family(config.editor.font_family());
+        if !text.is_empty() {
+            builder.push("\n\n");
         }
-        Documentation::MarkupContent(content) => match content.kind {
-            MarkupKind::PlainText => {
-                let mut builder = RichTextBuilder::new();
-                builder.set_line_height(1.5);
-                builder.push(&content.value);
-                builder.build()
-            }
-            MarkupKind::Markdown => parse_markdown(&content.value, config),
-        },
     }
+    builder.push(text);
+    builder.build()
 }
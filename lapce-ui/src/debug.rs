@@ -0,0 +1,1187 @@
+use std::{path::Path, sync::Arc};
+
+use druid::{
+    piet::{Text, TextAttribute, TextLayout as PietTextLayout, TextLayoutBuilder},
+    Application, BoxConstraints, Command, Cursor, Env, Event, EventCtx, LayoutCtx,
+    LifeCycle, LifeCycleCtx, Menu, MenuItem, MouseEvent, PaintCtx, Point, Rect,
+    RenderContext, Size, Target, UpdateCtx, Widget, WidgetExt,
+};
+use lapce_data::{
+    command::{
+        CommandKind, LapceCommand, LapceUICommand, LapceWorkbenchCommand,
+        LAPCE_COMMAND, LAPCE_UI_COMMAND,
+    },
+    config::LapceTheme,
+    data::{LapceData, LapceTabData},
+    debug::{memory_lines, CallStackRow, DebugData, WatchRow, MEMORY_PAGE_BYTES},
+    editor::EditorLocation,
+    palette::PaletteType,
+    panel::PanelKind,
+};
+use lapce_rpc::dap_types::{DapId, Variable};
+use lsp_types::Position;
+
+use crate::{
+    panel::{LapcePanel, PanelHeaderKind, PanelSizing},
+    svg::get_svg,
+};
+
+pub fn new_debug_panel(data: &DebugData) -> LapcePanel {
+    LapcePanel::new(
+        PanelKind::Debug,
+        data.widget_id,
+        data.split_id,
+        vec![
+            (
+                data.content_id,
+                PanelHeaderKind::None,
+                DebugContent::new().boxed(),
+                PanelSizing::Flex(true),
+            ),
+            (
+                data.watch_id,
+                PanelHeaderKind::Simple("Watch".into()),
+                WatchContent::new().boxed(),
+                PanelSizing::Flex(true),
+            ),
+            (
+                data.memory_id,
+                PanelHeaderKind::Simple("Memory".into()),
+                MemoryContent::new().boxed(),
+                PanelSizing::Flex(true),
+            ),
+        ],
+    )
+}
+
+/// A line of the panel below the buttons
+struct DebugRow {
+    text: String,
+    /// Whether it's the heading of a section
+    heading: bool,
+    /// What clicking the line does
+    command: Option<LapceUICommand>,
+    /// The id of the frame of the line
+    frame_id: Option<usize>,
+    /// The variable of the line, with the reference it's a child of
+    variable: Option<(usize, Variable)>,
+    /// The index of the data breakpoint of the line
+    data_breakpoint: Option<usize>,
+}
+
+impl DebugRow {
+    fn heading(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            heading: true,
+            command: None,
+            frame_id: None,
+            variable: None,
+            data_breakpoint: None,
+        }
+    }
+
+    fn line(text: String, command: Option<LapceUICommand>) -> Self {
+        Self {
+            text,
+            heading: false,
+            command,
+            frame_id: None,
+            variable: None,
+            data_breakpoint: None,
+        }
+    }
+}
+
+/// The command that goes to the line, starting at 0, of the file
+fn jump_to(path: &Path, line: usize) -> LapceUICommand {
+    LapceUICommand::JumpToLspLocation(
+        None,
+        EditorLocation {
+            path: path.to_path_buf(),
+            position: Some(Position::new(line as u32, 0)),
+            scroll_offset: None,
+            history: None,
+        },
+        false,
+    )
+}
+
+/// The threads, the stack and the variables of the program being debugged,
+/// and the breakpoints, below a row of buttons to run and step it and a row
+/// to switch between the programs
+struct DebugContent {
+    mouse_pos: Point,
+    button_rects: Vec<(Rect, LapceWorkbenchCommand)>,
+    session_rects: Vec<(Rect, DapId)>,
+}
+
+impl DebugContent {
+    fn new() -> Self {
+        Self {
+            mouse_pos: Point::ZERO,
+            button_rects: Vec::new(),
+            session_rects: Vec::new(),
+        }
+    }
+
+    fn line_height(data: &LapceTabData) -> f64 {
+        data.config.editor.line_height() as f64
+    }
+
+    /// The lines above the rows, which are the buttons, and the programs
+    /// being debugged if there are any
+    fn header_lines(data: &LapceTabData) -> usize {
+        if data.debug.daps.is_empty() {
+            1
+        } else {
+            2
+        }
+    }
+
+    fn rows(data: &LapceTabData) -> Vec<DebugRow> {
+        let debug = &data.debug;
+        let workspace = data.workspace.path.as_ref();
+        let mut rows = Vec::new();
+
+        if let Some(dap) = debug.active() {
+            if dap.threads.len() > 1 {
+                rows.push(DebugRow::heading("Threads"));
+                for thread in dap.threads.iter() {
+                    let marker = if Some(thread.id) == dap.thread_id {
+                        "▸ "
+                    } else {
+                        "  "
+                    };
+                    rows.push(DebugRow::line(
+                        format!("{marker}{}", thread.name),
+                        dap.stopped
+                            .as_ref()
+                            .map(|_| LapceUICommand::SelectDebugThread(thread.id)),
+                    ));
+                }
+            }
+
+            rows.push(DebugRow::heading("Call Stack"));
+            for row in dap.call_stack_rows() {
+                match row {
+                    CallStackRow::Frame(i) => {
+                        let frame = &dap.stack_frames[i];
+                        let marker =
+                            if i == dap.frame_index { "▸ " } else { "  " };
+                        let path =
+                            frame.source.as_ref().and_then(|s| s.path.as_ref());
+                        let text = match path {
+                            Some(path) => {
+                                let relative = workspace
+                                    .and_then(|w| path.strip_prefix(w).ok())
+                                    .unwrap_or(path);
+                                format!(
+                                    "{marker}{}  {}:{}",
+                                    frame.name,
+                                    relative.display(),
+                                    frame.line
+                                )
+                            }
+                            None => format!("{marker}{}", frame.name),
+                        };
+                        rows.push(DebugRow {
+                            frame_id: Some(frame.id),
+                            ..DebugRow::line(
+                                text,
+                                Some(LapceUICommand::SelectDebugFrame(i)),
+                            )
+                        });
+                    }
+                    CallStackRow::Label(i) => {
+                        rows.push(DebugRow::line(
+                            format!("  ── {} ──", dap.stack_frames[i].name),
+                            None,
+                        ));
+                    }
+                    CallStackRow::Subtle { start, count } => {
+                        rows.push(DebugRow::line(
+                            format!("  ⋯ {count} more frames"),
+                            Some(LapceUICommand::ToggleDebugFramesExpanded(start)),
+                        ));
+                    }
+                }
+            }
+
+            rows.push(DebugRow::heading("Variables"));
+            for (scope, variables) in dap.variables.iter() {
+                rows.push(DebugRow::line(scope.name.clone(), None));
+                for variable in variables {
+                    let text = match variable.ty.as_ref() {
+                        Some(ty) if !ty.is_empty() => format!(
+                            "    {}: {} = {}",
+                            variable.name, ty, variable.value
+                        ),
+                        _ => format!("    {} = {}", variable.name, variable.value),
+                    };
+                    rows.push(DebugRow {
+                        variable: Some((
+                            scope.variables_reference,
+                            variable.clone(),
+                        )),
+                        ..DebugRow::line(text, None)
+                    });
+                }
+            }
+        }
+
+        rows.push(DebugRow::heading("Breakpoints"));
+        for (path, breakpoints) in debug.breakpoints.iter() {
+            let relative = workspace
+                .and_then(|w| path.strip_prefix(w).ok())
+                .unwrap_or(path);
+            for (line, breakpoint) in breakpoints.iter() {
+                let mut text = format!("{}:{}", relative.display(), line + 1);
+                if let Some(condition) = breakpoint.condition.as_ref() {
+                    text.push_str(&format!("  if {condition}"));
+                }
+                if let Some(hit_condition) = breakpoint.hit_condition.as_ref() {
+                    text.push_str(&format!("  hits {hit_condition}"));
+                }
+                if let Some(log_message) = breakpoint.log_message.as_ref() {
+                    text.push_str(&format!("  log \"{log_message}\""));
+                }
+                if !debug.daps.is_empty() && !breakpoint.verified {
+                    text.push_str("  (not set)");
+                }
+                rows.push(DebugRow::line(text, Some(jump_to(path, *line))));
+            }
+        }
+        if let Some(dap) = debug.active() {
+            for (i, breakpoint) in dap.data_breakpoints.iter().enumerate() {
+                rows.push(DebugRow {
+                    data_breakpoint: Some(i),
+                    ..DebugRow::line(
+                        format!("{}  on change", breakpoint.description),
+                        None,
+                    )
+                });
+            }
+        }
+
+        rows
+    }
+
+    fn row_at(&self, data: &LapceTabData, pos: Point) -> Option<DebugRow> {
+        let line = (pos.y / Self::line_height(data)).floor() as usize;
+        let header_lines = Self::header_lines(data);
+        if line < header_lines {
+            return None;
+        }
+        Self::rows(data).into_iter().nth(line - header_lines)
+    }
+
+    fn is_clickable(&self, data: &LapceTabData, pos: Point) -> bool {
+        self.button_rects.iter().any(|(rect, _)| rect.contains(pos))
+            || self
+                .session_rects
+                .iter()
+                .any(|(rect, _)| rect.contains(pos))
+            || self
+                .row_at(data, pos)
+                .map(|row| row.command.is_some())
+                .unwrap_or(false)
+    }
+
+    fn mouse_down(
+        &self,
+        ctx: &mut EventCtx,
+        mouse_event: &MouseEvent,
+        data: &mut LapceTabData,
+    ) {
+        if mouse_event.button.is_right() {
+            if let Some(menu) = self
+                .row_at(data, mouse_event.pos)
+                .and_then(|row| Self::menu(data, row))
+            {
+                ctx.show_context_menu::<LapceData>(
+                    menu,
+                    ctx.to_window(mouse_event.pos),
+                );
+            }
+            return;
+        }
+
+        if let Some((_, cmd)) = self
+            .button_rects
+            .iter()
+            .find(|(rect, _)| rect.contains(mouse_event.pos))
+        {
+            ctx.submit_command(Command::new(
+                LAPCE_COMMAND,
+                LapceCommand {
+                    kind: CommandKind::Workbench(cmd.clone()),
+                    data: None,
+                },
+                Target::Widget(data.id),
+            ));
+            return;
+        }
+
+        if let Some((_, dap_id)) = self
+            .session_rects
+            .iter()
+            .find(|(rect, _)| rect.contains(mouse_event.pos))
+        {
+            data.select_debug_session(ctx, *dap_id);
+            return;
+        }
+
+        if let Some(command) = self
+            .row_at(data, mouse_event.pos)
+            .and_then(|row| row.command)
+        {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                command,
+                Target::Widget(data.id),
+            ));
+        }
+    }
+
+    /// The menu of a frame, a variable or a data breakpoint, with what the
+    /// adapter supports doing with them
+    fn menu(data: &LapceTabData, row: DebugRow) -> Option<Menu<LapceData>> {
+        let capabilities = &data.debug.active()?.capabilities;
+        if let Some(frame_id) = row.frame_id {
+            if !capabilities.supports_restart_frame {
+                return None;
+            }
+            return Some(Menu::new("Frame").entry(
+                MenuItem::new("Restart Frame").command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RestartDebugFrame(frame_id),
+                    Target::Widget(data.id),
+                )),
+            ));
+        }
+        if let Some(index) = row.data_breakpoint {
+            return Some(Menu::new("Data Breakpoint").entry(
+                MenuItem::new("Remove Breakpoint").command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RemoveDataBreakpoint(index),
+                    Target::Widget(data.id),
+                )),
+            ));
+        }
+
+        let (variables_reference, variable) = row.variable?;
+        let value = variable.value.clone();
+        let mut menu =
+            Menu::new("Variable").entry(MenuItem::new("Copy Value").on_activate(
+                move |_ctx, _data: &mut LapceData, _env| {
+                    Application::global().clipboard().put_string(&value);
+                },
+            ));
+        if let Some(memory_reference) = variable.memory_reference.clone() {
+            if capabilities.supports_read_memory_request {
+                menu =
+                    menu.entry(MenuItem::new("View Memory").command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::ReadDebugMemory {
+                            memory_reference,
+                            offset: 0,
+                        },
+                        Target::Widget(data.id),
+                    )));
+            }
+        }
+        if capabilities.supports_data_breakpoints {
+            menu = menu.entry(MenuItem::new("Break on Value Change").command(
+                Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::AddDataBreakpoint {
+                        variables_reference,
+                        name: variable.name,
+                    },
+                    Target::Widget(data.id),
+                ),
+            ));
+        }
+        Some(menu)
+    }
+
+    fn paint_header(&mut self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        let line_height = Self::line_height(data);
+        let padding = 10.0;
+
+        let buttons = match data.debug.active() {
+            Some(dap) => {
+                let run = if dap.stopped.is_some() {
+                    ("Continue", LapceWorkbenchCommand::DebugContinue)
+                } else {
+                    ("Pause", LapceWorkbenchCommand::DebugPause)
+                };
+                vec![
+                    run,
+                    ("Step Over", LapceWorkbenchCommand::DebugStepOver),
+                    ("Step Into", LapceWorkbenchCommand::DebugStepInto),
+                    ("Step Out", LapceWorkbenchCommand::DebugStepOut),
+                    ("Stop", LapceWorkbenchCommand::DebugStop),
+                ]
+            }
+            None => vec![("Start Debugging", LapceWorkbenchCommand::DebugStart)],
+        };
+
+        let mut x = padding;
+        self.button_rects.clear();
+        for (label, cmd) in buttons {
+            let text_layout = ctx
+                .text()
+                .new_text_layout(label)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOCUS)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let rect = Size::new(text_layout.size().width, line_height)
+                .to_rect()
+                .with_origin(Point::new(x, 0.0))
+                .inflate(padding / 2.0, 0.0);
+            if rect.contains(self.mouse_pos) {
+                ctx.fill(
+                    rect,
+                    data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+                );
+            }
+            self.button_rects.push((rect, cmd));
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, text_layout.y_offset(line_height)),
+            );
+            x += text_layout.size().width + padding * 2.0;
+        }
+
+        if let Some(dap) = data.debug.active() {
+            let status = match dap.stopped.as_ref() {
+                Some(stopped) => format!(
+                    "{}: paused on {}",
+                    dap.config.name,
+                    stopped.description.as_ref().unwrap_or(&stopped.reason)
+                ),
+                None => format!("{}: running", dap.config.name),
+            };
+            let text_layout = ctx
+                .text()
+                .new_text_layout(status)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, text_layout.y_offset(line_height)),
+            );
+        }
+
+        self.paint_sessions(ctx, data);
+    }
+
+    /// The row of the programs being debugged, with the active one
+    /// highlighted
+    fn paint_sessions(&mut self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        let line_height = Self::line_height(data);
+        let padding = 10.0;
+        let y = line_height;
+
+        let mut x = padding;
+        self.session_rects.clear();
+        for dap in data.debug.sessions() {
+            let active = data.debug.active_dap == Some(dap.dap_id);
+            let label = if dap.stopped.is_some() {
+                format!("{} (paused)", dap.config.name)
+            } else {
+                dap.config.name.clone()
+            };
+            let text_layout = ctx
+                .text()
+                .new_text_layout(label)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(if active {
+                            LapceTheme::EDITOR_FOREGROUND
+                        } else {
+                            LapceTheme::EDITOR_DIM
+                        })
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let rect = Size::new(text_layout.size().width, line_height)
+                .to_rect()
+                .with_origin(Point::new(x, y))
+                .inflate(padding / 2.0, 0.0);
+            if active || rect.contains(self.mouse_pos) {
+                ctx.fill(
+                    rect,
+                    data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+                );
+            }
+            self.session_rects.push((rect, dap.dap_id));
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, y + text_layout.y_offset(line_height)),
+            );
+            x += text_layout.size().width + padding * 2.0;
+        }
+    }
+}
+
+impl Widget<LapceTabData> for DebugContent {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(mouse_event) => {
+                self.mouse_pos = mouse_event.pos;
+                if self.is_clickable(data, mouse_event.pos) {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+                ctx.request_paint();
+            }
+            Event::MouseDown(mouse_event) => {
+                self.mouse_down(ctx, mouse_event, data);
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if !Arc::ptr_eq(&old_data.debug, &data.debug) {
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let rows = Self::rows(data).len();
+        let height =
+            Self::line_height(data) * (rows + Self::header_lines(data)) as f64;
+        Size::new(bc.max().width, height.max(bc.max().height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        self.paint_header(ctx, data);
+
+        let line_height = Self::line_height(data);
+        let header_lines = Self::header_lines(data);
+        let rect = ctx.region().bounding_box();
+        let min = ((rect.y0 / line_height).floor() as usize).max(header_lines);
+        let max = (rect.y1 / line_height) as usize + 1;
+        let rows = Self::rows(data);
+        for (i, row) in rows
+            .iter()
+            .enumerate()
+            .skip(min - header_lines)
+            .take(max.saturating_sub(min) + 1)
+        {
+            let y = line_height * (i + header_lines) as f64;
+            if row.command.is_some()
+                && Rect::new(0.0, y, ctx.size().width, y + line_height)
+                    .contains(self.mouse_pos)
+            {
+                ctx.fill(
+                    Size::new(ctx.size().width, line_height)
+                        .to_rect()
+                        .with_origin(Point::new(0.0, y)),
+                    data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+                );
+            }
+            let text_layout = ctx
+                .text()
+                .new_text_layout(row.text.clone())
+                .font(
+                    if row.heading {
+                        data.config.ui.font_family()
+                    } else {
+                        data.config.editor.font_family()
+                    },
+                    if row.heading {
+                        data.config.ui.font_size() as f64
+                    } else {
+                        data.config.editor.font_size as f64
+                    },
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(if row.heading {
+                            LapceTheme::EDITOR_FOREGROUND
+                        } else {
+                            LapceTheme::EDITOR_DIM
+                        })
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(10.0, y + text_layout.y_offset(line_height)),
+            );
+        }
+    }
+}
+
+/// The watch expressions with their values where the program stopped,
+/// below a button to add one
+struct WatchContent {
+    mouse_pos: Point,
+}
+
+impl WatchContent {
+    fn new() -> Self {
+        Self {
+            mouse_pos: Point::ZERO,
+        }
+    }
+
+    /// The row at the position, which is `None` for the button to add an
+    /// expression
+    fn row_at(&self, data: &LapceTabData, pos: Point) -> Option<Option<WatchRow>> {
+        let line = (pos.y / data.config.editor.line_height() as f64).floor();
+        if line < 0.0 {
+            return None;
+        }
+        match line as usize {
+            0 => Some(None),
+            line => data.debug.watch_rows().into_iter().nth(line - 1).map(Some),
+        }
+    }
+
+    fn mouse_down(
+        &self,
+        ctx: &mut EventCtx,
+        mouse_event: &MouseEvent,
+        data: &LapceTabData,
+    ) {
+        let row = match self.row_at(data, mouse_event.pos) {
+            Some(row) => row,
+            None => return,
+        };
+        if mouse_event.button.is_right() {
+            ctx.show_context_menu::<LapceData>(
+                Self::menu(data, row.as_ref()),
+                ctx.to_window(mouse_event.pos),
+            );
+            return;
+        }
+        if !mouse_event.button.is_left() {
+            return;
+        }
+        match row {
+            None => {
+                ctx.submit_command(Command::new(
+                    LAPCE_COMMAND,
+                    LapceCommand {
+                        kind: CommandKind::Workbench(
+                            LapceWorkbenchCommand::DebugAddWatchExpression,
+                        ),
+                        data: None,
+                    },
+                    Target::Widget(data.id),
+                ));
+            }
+            Some(row) if row.variables_reference > 0 => {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::ToggleWatchExpanded {
+                        path: row.path,
+                        variables_reference: row.variables_reference,
+                    },
+                    Target::Widget(data.id),
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+
+    fn menu(data: &LapceTabData, row: Option<&WatchRow>) -> Menu<LapceData> {
+        let mut menu = Menu::new("Watch");
+        if let Some(row) = row {
+            if let Some(Ok(value)) = row.value.clone() {
+                menu = menu.entry(MenuItem::new("Copy Value").on_activate(
+                    move |_ctx, _data: &mut LapceData, _env| {
+                        Application::global().clipboard().put_string(&value);
+                    },
+                ));
+            }
+            if let Some(index) = row.index {
+                menu = menu.entry(MenuItem::new("Edit Expression").command(
+                    Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::RunPalette(Some(
+                            PaletteType::WatchExpression {
+                                index: Some(index),
+                                expression: row.name().to_string(),
+                            },
+                        )),
+                        Target::Auto,
+                    ),
+                ));
+                menu = menu.entry(MenuItem::new("Remove Expression").command(
+                    Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::RemoveWatchExpression(index),
+                        Target::Widget(data.id),
+                    ),
+                ));
+            }
+            menu = menu.separator();
+        }
+        menu.entry(MenuItem::new("Add Expression").command(Command::new(
+            LAPCE_COMMAND,
+            LapceCommand {
+                kind: CommandKind::Workbench(
+                    LapceWorkbenchCommand::DebugAddWatchExpression,
+                ),
+                data: None,
+            },
+            Target::Widget(data.id),
+        )))
+    }
+}
+
+impl Widget<LapceTabData> for WatchContent {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(mouse_event) => {
+                self.mouse_pos = mouse_event.pos;
+                let clickable = match self.row_at(data, mouse_event.pos) {
+                    Some(None) => true,
+                    Some(Some(row)) => row.variables_reference > 0,
+                    None => false,
+                };
+                if clickable {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+                ctx.request_paint();
+            }
+            Event::MouseDown(mouse_event) => {
+                self.mouse_down(ctx, mouse_event, data);
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if !Arc::ptr_eq(&old_data.debug, &data.debug) {
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let rows = data.debug.watch_rows().len();
+        let height = data.config.editor.line_height() as f64 * (rows + 1) as f64;
+        Size::new(bc.max().width, height.max(bc.max().height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        let line_height = data.config.editor.line_height() as f64;
+        let size = ctx.size();
+
+        let line = (self.mouse_pos.y / line_height).floor();
+        if ctx.is_hot() && line >= 0.0 {
+            ctx.fill(
+                Size::new(size.width, line_height)
+                    .to_rect()
+                    .with_origin(Point::new(0.0, line * line_height)),
+                data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+            );
+        }
+
+        let text_layout = ctx
+            .text()
+            .new_text_layout("Add Expression")
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOCUS)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &text_layout,
+            Point::new(10.0, text_layout.y_offset(line_height)),
+        );
+
+        for (i, row) in data.debug.watch_rows().iter().enumerate() {
+            let y = line_height * (i + 1) as f64;
+            let x = 10.0 + row.depth() as f64 * 16.0;
+            if row.variables_reference > 0 {
+                let svg = if row.expanded {
+                    "chevron-down.svg"
+                } else {
+                    "chevron-right.svg"
+                };
+                let rect = Size::new(12.0, 12.0)
+                    .to_rect()
+                    .with_origin(Point::new(x, y + (line_height - 12.0) / 2.0));
+                ctx.draw_svg(
+                    &get_svg(svg).unwrap(),
+                    rect,
+                    Some(data.config.get_color_unchecked(LapceTheme::EDITOR_DIM)),
+                );
+            }
+
+            let (value, color) = match row.value.as_ref() {
+                Some(Ok(value)) => (format!(" = {value}"), LapceTheme::EDITOR_DIM),
+                Some(Err(e)) => (format!(": {e}"), LapceTheme::LAPCE_ERROR),
+                None => (": not available".to_string(), LapceTheme::EDITOR_DIM),
+            };
+            let name = row.name().to_string();
+            let name_len = name.len();
+            let text_layout = ctx
+                .text()
+                .new_text_layout(name + &value)
+                .font(
+                    data.config.editor.font_family(),
+                    data.config.editor.font_size as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                        .clone(),
+                )
+                .range_attribute(
+                    name_len..,
+                    TextAttribute::TextColor(
+                        data.config.get_color_unchecked(color).clone(),
+                    ),
+                )
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x + 16.0, y + text_layout.y_offset(line_height)),
+            );
+        }
+    }
+}
+
+/// The memory read from a reference, in hex, below buttons to page through
+/// it
+struct MemoryContent {
+    mouse_pos: Point,
+    /// The buttons, with the offset they read the memory from, or `None`
+    /// for the one that closes it
+    button_rects: Vec<(Rect, Option<i64>)>,
+}
+
+impl MemoryContent {
+    fn new() -> Self {
+        Self {
+            mouse_pos: Point::ZERO,
+            button_rects: Vec::new(),
+        }
+    }
+
+    /// The line of the buttons, which there is only with memory to page
+    /// through
+    fn header_lines(data: &LapceTabData) -> usize {
+        if data.debug.memory.is_some() {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// The lines below the buttons, which are the memory or why there's
+    /// none, with whether they're an error
+    fn lines(data: &LapceTabData) -> Vec<(String, bool)> {
+        match data
+            .debug
+            .memory
+            .as_ref()
+            .map(|memory| memory.result.as_ref())
+        {
+            None => vec![(
+                "View the memory of a variable from its menu".to_string(),
+                false,
+            )],
+            Some(None) => vec![("Reading memory".to_string(), false)],
+            Some(Some(Err(e))) => vec![(e.clone(), true)],
+            Some(Some(Ok(result))) if result.data.is_empty() => {
+                vec![("The memory can't be read".to_string(), true)]
+            }
+            Some(Some(Ok(result))) => memory_lines(result.address, &result.data)
+                .into_iter()
+                .map(|line| (line, false))
+                .collect(),
+        }
+    }
+
+    fn paint_buttons(&mut self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        self.button_rects.clear();
+        let memory = match data.debug.memory.as_ref() {
+            Some(memory) => memory,
+            None => return,
+        };
+        let line_height = data.config.editor.line_height() as f64;
+        let padding = 10.0;
+        let page = MEMORY_PAGE_BYTES as i64;
+        let buttons = [
+            ("Previous", Some(memory.offset - page)),
+            ("Next", Some(memory.offset + page)),
+            ("Close", None),
+        ];
+
+        let mut x = padding;
+        for (label, offset) in buttons {
+            let text_layout = ctx
+                .text()
+                .new_text_layout(label)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOCUS)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let rect = Size::new(text_layout.size().width, line_height)
+                .to_rect()
+                .with_origin(Point::new(x, 0.0))
+                .inflate(padding / 2.0, 0.0);
+            if rect.contains(self.mouse_pos) {
+                ctx.fill(
+                    rect,
+                    data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+                );
+            }
+            self.button_rects.push((rect, offset));
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, text_layout.y_offset(line_height)),
+            );
+            x += text_layout.size().width + padding * 2.0;
+        }
+
+        let text_layout = ctx
+            .text()
+            .new_text_layout(format!(
+                "{} {:+}",
+                memory.memory_reference, memory.offset
+            ))
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &text_layout,
+            Point::new(x, text_layout.y_offset(line_height)),
+        );
+    }
+}
+
+impl Widget<LapceTabData> for MemoryContent {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(mouse_event) => {
+                self.mouse_pos = mouse_event.pos;
+                if self
+                    .button_rects
+                    .iter()
+                    .any(|(rect, _)| rect.contains(mouse_event.pos))
+                {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+                ctx.request_paint();
+            }
+            Event::MouseDown(mouse_event) => {
+                let offset = self
+                    .button_rects
+                    .iter()
+                    .find(|(rect, _)| rect.contains(mouse_event.pos))
+                    .map(|(_, offset)| *offset);
+                let memory_reference = data
+                    .debug
+                    .memory
+                    .as_ref()
+                    .map(|memory| memory.memory_reference.clone());
+                if let (Some(offset), Some(memory_reference)) =
+                    (offset, memory_reference)
+                {
+                    let cmd = match offset {
+                        Some(offset) => LapceUICommand::ReadDebugMemory {
+                            memory_reference,
+                            offset,
+                        },
+                        None => LapceUICommand::CloseDebugMemory,
+                    };
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        cmd,
+                        Target::Widget(data.id),
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if old_data.debug.memory != data.debug.memory {
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let lines = Self::lines(data).len() + Self::header_lines(data);
+        let height = data.config.editor.line_height() as f64 * lines as f64;
+        Size::new(bc.max().width, height.max(bc.max().height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        self.paint_buttons(ctx, data);
+
+        let line_height = data.config.editor.line_height() as f64;
+        let header_lines = Self::header_lines(data);
+        for (i, (line, error)) in Self::lines(data).into_iter().enumerate() {
+            let text_layout = ctx
+                .text()
+                .new_text_layout(line)
+                .font(
+                    data.config.editor.font_family(),
+                    data.config.editor.font_size as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(if error {
+                            LapceTheme::LAPCE_ERROR
+                        } else {
+                            LapceTheme::EDITOR_DIM
+                        })
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let y = line_height * (i + header_lines) as f64;
+            ctx.draw_text(
+                &text_layout,
+                Point::new(10.0, y + text_layout.y_offset(line_height)),
+            );
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use druid::{
+    piet::{Text, TextAttribute, TextLayout as PietTextLayout, TextLayoutBuilder},
+    Application, BoxConstraints, Command, Env, Event, EventCtx, LayoutCtx,
+    LifeCycle, LifeCycleCtx, Menu, MenuItem, PaintCtx, Point, Size, Target,
+    UpdateCtx, Widget, WidgetExt, WidgetId,
+};
+use lapce_data::{
+    command::{CommandKind, LapceCommand, LapceWorkbenchCommand, LAPCE_COMMAND},
+    config::LapceTheme,
+    data::{LapceData, LapceTabData},
+    debug_console::ConsoleLineKind,
+    panel::PanelKind,
+};
+
+use crate::{
+    editor::view::LapceEditorView,
+    panel::{LapcePanel, PanelHeaderKind, PanelSizing},
+};
+
+pub fn new_debug_console_panel(data: &LapceTabData) -> LapcePanel {
+    let input = LapceEditorView::new(
+        data.debug_console.input_view_id,
+        WidgetId::next(),
+        None,
+    )
+    .hide_header()
+    .hide_gutter()
+    .set_placeholder("Evaluate Expression".to_string())
+    .padding((15.0, 15.0));
+    let data = &data.debug_console;
+    LapcePanel::new(
+        PanelKind::DebugConsole,
+        data.widget_id,
+        data.split_id,
+        vec![
+            (
+                data.content_id,
+                PanelHeaderKind::None,
+                DebugConsoleContent::new().boxed(),
+                PanelSizing::Flex(true),
+            ),
+            (
+                data.input_view_id,
+                PanelHeaderKind::None,
+                input.boxed(),
+                PanelSizing::Size(50.0),
+            ),
+        ],
+    )
+}
+
+/// The output of the programs being debugged and the evaluated expressions,
+/// kept scrolled to the latest line unless scrolled up
+struct DebugConsoleContent {
+    /// How many lines the view is scrolled up from the latest one
+    scroll_lines: usize,
+}
+
+impl DebugConsoleContent {
+    fn new() -> Self {
+        Self { scroll_lines: 0 }
+    }
+
+    fn line_height(data: &LapceTabData) -> f64 {
+        data.config.editor.line_height() as f64
+    }
+
+    fn show_context_menu(
+        &self,
+        ctx: &mut EventCtx,
+        pos: Point,
+        data: &LapceTabData,
+    ) {
+        let text = data
+            .debug_console
+            .lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let menu = Menu::<LapceData>::new("")
+            .entry(MenuItem::new("Copy All").on_activate(
+                move |_ctx, _data: &mut LapceData, _env| {
+                    Application::global().clipboard().put_string(&text);
+                },
+            ))
+            .entry(MenuItem::new("Clear Console").command(Command::new(
+                LAPCE_COMMAND,
+                LapceCommand {
+                    kind: CommandKind::Workbench(
+                        LapceWorkbenchCommand::DebugClearConsole,
+                    ),
+                    data: None,
+                },
+                Target::Widget(data.id),
+            )));
+        ctx.show_context_menu::<LapceData>(menu, ctx.to_window(pos));
+    }
+}
+
+impl Widget<LapceTabData> for DebugConsoleContent {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::Wheel(wheel_event) => {
+                let line_height = Self::line_height(data);
+                let lines =
+                    (wheel_event.wheel_delta.y.abs() / line_height).ceil() as usize;
+                let visible = (ctx.size().height / line_height) as usize;
+                let max = data.debug_console.lines.len().saturating_sub(visible);
+                self.scroll_lines = if wheel_event.wheel_delta.y < 0.0 {
+                    (self.scroll_lines + lines).min(max)
+                } else {
+                    self.scroll_lines.saturating_sub(lines)
+                };
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::MouseDown(mouse_event) if mouse_event.button.is_right() => {
+                self.show_context_menu(ctx, mouse_event.pos, data);
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if !Arc::ptr_eq(&old_data.debug_console, &data.debug_console) {
+            if data.debug_console.lines.is_empty() {
+                self.scroll_lines = 0;
+            }
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        let line_height = Self::line_height(data);
+        let size = ctx.size();
+        let lines = &data.debug_console.lines;
+        let visible = (size.height / line_height) as usize;
+        let end = lines.len().saturating_sub(self.scroll_lines);
+        let start = end.saturating_sub(visible);
+        // Once the lines fill the view, they are kept to the bottom, where
+        // the latest output is
+        let top = if end - start < visible {
+            0.0
+        } else {
+            size.height - line_height * (end - start) as f64
+        };
+
+        for (i, line) in lines.iter().skip(start).take(end - start).enumerate() {
+            let (color, prefix) = match line.kind {
+                ConsoleLineKind::Output | ConsoleLineKind::Result => {
+                    (LapceTheme::EDITOR_FOREGROUND, "")
+                }
+                ConsoleLineKind::Stderr | ConsoleLineKind::Error => {
+                    (LapceTheme::LAPCE_ERROR, "")
+                }
+                ConsoleLineKind::Info => (LapceTheme::EDITOR_DIM, ""),
+                ConsoleLineKind::Input => (LapceTheme::EDITOR_FOCUS, "> "),
+            };
+            let mut text_layout = ctx
+                .text()
+                .new_text_layout(format!("{prefix}{}", line.text))
+                .font(
+                    data.config.editor.font_family(),
+                    data.config.editor.font_size as f64,
+                )
+                .text_color(data.config.get_color_unchecked(color).clone());
+            for (range, ansi) in line.colors.iter() {
+                text_layout = text_layout.range_attribute(
+                    range.start + prefix.len()..range.end + prefix.len(),
+                    TextAttribute::TextColor(
+                        data.config.get_color_unchecked(ansi.theme_key()).clone(),
+                    ),
+                );
+            }
+            let text_layout = text_layout.build().unwrap();
+            let y = top + line_height * i as f64;
+            ctx.draw_text(
+                &text_layout,
+                Point::new(10.0, y + text_layout.y_offset(line_height)),
+            );
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::ops::Range;
 use std::time::Duration;
 use std::{iter::Iterator, sync::Arc};
 
@@ -10,16 +11,18 @@
     Rect, RenderContext, Size, Target, UpdateCtx, Widget, WidgetId,
 };
 use druid::{Modifiers, TimerToken};
-use lapce_core::buffer::DiffLines;
+use lapce_core::buffer::{line_diff_ranges, DiffLines};
 use lapce_core::command::EditCommand;
 use lapce_core::{
     command::FocusCommand,
     cursor::{ColPosition, CursorMode},
     mode::{Mode, VisualMode},
+    selection::Selection,
 };
 use lapce_data::command::CommandKind;
-use lapce_data::data::{EditorView, LapceData};
-use lapce_data::document::{BufferContent, LocalBufferKind};
+use lapce_data::data::{DiffSide, EditorView, LapceData};
+use lapce_data::debug::{line_inline_values, INLINE_VALUE_LINES};
+use lapce_data::document::{BufferContent, LocalBufferKind, WrappedLine};
 use lapce_data::history::DocumentHistory;
 use lapce_data::hover::HoverStatus;
 use lapce_data::keypress::KeyPressFocus;
@@ -36,6 +39,7 @@
     editor::{LapceEditorBufferData, Syntax},
     menu::MenuItem,
 };
+use lapce_rpc::plugin::PluginId;
 use lsp_types::{CodeActionOrCommand, DiagnosticSeverity};
 
 pub mod bread_crumb;
@@ -57,6 +61,67 @@ struct LineInfo {
     x: f64,
     y: f64,
     line_height: f64,
+    wrap: Option<WrappedLine>,
+}
+
+impl LineInfo {
+    fn num_segments(&self) -> usize {
+        self.wrap.map(|wrap| wrap.num_segments()).unwrap_or(1)
+    }
+
+    /// The height of the line, which spans several visual lines when it's
+    /// soft wrapped
+    fn height(&self) -> f64 {
+        self.line_height * self.num_segments() as f64
+    }
+
+    /// Where the text layout position `x` is drawn, at the top of its
+    /// visual line
+    fn point_of_x(&self, x: f64) -> Point {
+        match self.wrap {
+            Some(wrap) => {
+                let segment = wrap.segment_of_x(x);
+                Point::new(
+                    self.x + x + wrap.x_shift(segment),
+                    self.y + segment as f64 * self.line_height,
+                )
+            }
+            None => Point::new(self.x + x, self.y),
+        }
+    }
+
+    /// The rects covering the text layout range from `x0` to `x1`, one for
+    /// each visual line it's drawn on
+    fn rects(&self, x0: f64, x1: f64) -> Vec<Rect> {
+        let wrap = match self.wrap {
+            Some(wrap) => wrap,
+            None => {
+                return vec![Rect::new(
+                    self.x + x0,
+                    self.y,
+                    self.x + x1,
+                    self.y + self.line_height,
+                )]
+            }
+        };
+
+        let first = wrap.segment_of_x(x0);
+        let mut last = wrap.segment_of_x(x1).max(first);
+        if last > first && x1 - wrap.x_range(last).0 < wrap.char_width / 2.0 {
+            // The range ends right where the visual line starts
+            last -= 1;
+        }
+        (first..last + 1)
+            .map(|segment| {
+                let (start, end) = wrap.x_range(segment);
+                let left = if segment == first { x0 } else { start };
+                let right = if segment == last { x1 } else { end };
+                let x = self.x + wrap.x_shift(segment);
+                let y = self.y + segment as f64 * self.line_height;
+                Rect::new(left + x, y, right + x, y + self.line_height)
+            })
+            .collect()
+    }
 }
 
 pub struct LapceEditor {
@@ -66,10 +131,19 @@ pub struct LapceEditor {
 
     mouse_pos: Point,
     mouse_mods: Modifiers,
+    /// Where the left button was last pressed, which is a corner of the
+    /// block selected by dragging with alt held
+    mouse_down_pos: Point,
     /// A timer for listening for when the user has hovered for long enough to trigger showing
     /// of hover info (if there is any)
     mouse_hover_timer: TimerToken,
     drag_timer: TimerToken,
+    /// Where the code lenses of the language server were painted, to run
+    /// their commands when they are clicked
+    code_lenses: Vec<(Rect, PluginId, lsp_types::Command)>,
+    /// The range of the link under the mouse while the key that makes a
+    /// click open it is held, which is underlined
+    link: Option<(usize, usize)>,
 }
 
 impl LapceEditor {
@@ -80,11 +154,22 @@ pub fn new(view_id: WidgetId, editor_id: WidgetId) -> Self {
             placeholder: None,
             mouse_pos: Point::ZERO,
             mouse_mods: Modifiers::empty(),
+            mouse_down_pos: Point::ZERO,
             mouse_hover_timer: TimerToken::INVALID,
             drag_timer: TimerToken::INVALID,
+            code_lenses: Vec::new(),
+            link: None,
         }
     }
 
+    /// The code lens at `pos`, if it's over one
+    fn code_lens_at(&self, pos: Point) -> Option<(PluginId, &lsp_types::Command)> {
+        self.code_lenses
+            .iter()
+            .find(|(rect, _, _)| rect.contains(pos))
+            .map(|(_, plugin_id, command)| (*plugin_id, command))
+    }
+
     fn mouse_within_scroll(
         &self,
         editor_data: &LapceEditorBufferData,
@@ -177,6 +262,19 @@ fn mouse_move(
             return;
         }
 
+        if ctx.is_active() && mods.alt() {
+            // A small move is still an alt click that adds a cursor
+            if (mouse_pos - self.mouse_down_pos).hypot() > 3.0 {
+                editor_data.block_select(
+                    ctx.text(),
+                    self.mouse_down_pos,
+                    mouse_pos,
+                    config,
+                );
+            }
+            return;
+        }
+
         if ctx.is_active() {
             let (new_offset, _) = editor_data.doc.offset_of_point(
                 ctx.text(),
@@ -219,6 +317,26 @@ fn mouse_down(
         ctx.set_handled();
         match mouse_event.button {
             MouseButton::Left => {
+                if let Some(line) =
+                    Self::sticky_header_line_at(mouse_event.pos, editor_data, config)
+                {
+                    editor_data.jump_to_line_at_top(ctx, line);
+                    return;
+                }
+                if let Some((plugin_id, command)) =
+                    self.code_lens_at(mouse_event.pos)
+                {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::RunCodeLens {
+                            plugin_id,
+                            command: command.clone(),
+                        },
+                        Target::Widget(*editor_data.main_split.tab_id),
+                    ));
+                    return;
+                }
+                self.mouse_down_pos = mouse_event.pos;
                 self.left_click(ctx, mouse_event, editor_data, config);
                 editor_data.get_code_actions(ctx);
                 editor_data.cancel_completion();
@@ -238,6 +356,24 @@ fn mouse_down(
         }
     }
 
+    /// The line shown by the sticky header at `pos`, if it's over one
+    fn sticky_header_line_at(
+        pos: Point,
+        data: &LapceEditorBufferData,
+        config: &Config,
+    ) -> Option<usize> {
+        if !config.editor.sticky_header {
+            return None;
+        }
+        let info = data.editor.sticky_header.borrow();
+        let y = pos.y - data.editor.scroll_offset.y;
+        if y < 0.0 || y >= info.height {
+            return None;
+        }
+        let line_height = config.editor.line_height() as f64;
+        info.lines.get((y / line_height).floor() as usize).copied()
+    }
+
     fn left_click(
         &mut self,
         ctx: &mut EventCtx,
@@ -300,6 +436,30 @@ fn right_click(
                 },
                 enabled: true,
             }),
+            MenuKind::Item(MenuItem {
+                desc: None,
+                command: LapceCommand {
+                    kind: CommandKind::Focus(FocusCommand::GotoDeclaration),
+                    data: None,
+                },
+                enabled: true,
+            }),
+            MenuKind::Item(MenuItem {
+                desc: None,
+                command: LapceCommand {
+                    kind: CommandKind::Focus(FocusCommand::GotoImplementation),
+                    data: None,
+                },
+                enabled: true,
+            }),
+            MenuKind::Item(MenuItem {
+                desc: None,
+                command: LapceCommand {
+                    kind: CommandKind::Focus(FocusCommand::ShowTypeHierarchy),
+                    data: None,
+                },
+                enabled: true,
+            }),
             MenuKind::Separator,
             MenuKind::Item(MenuItem {
                 desc: None,
@@ -412,18 +572,22 @@ pub fn get_size(
                         },
                     )
                 } else {
-                    Size::new(
+                    let num_lines = data.doc.num_visual_lines(&data.config);
+                    // Soft wrapped lines don't need horizontal scrolling
+                    let content_width = if data.doc.is_wrapped(&data.config) {
+                        editor_size.width
+                    } else {
                         (width * data.doc.buffer().max_len() as f64)
                             .max(data.doc.text_layouts.borrow().max_width)
-                            .max(editor_size.width),
+                            .max(editor_size.width)
+                    };
+                    Size::new(
+                        content_width,
                         if data.config.editor.scroll_beyond_last_line {
-                            (line_height * data.doc.buffer().num_lines() as f64
-                                - line_height)
-                                .max(0.0)
+                            (line_height * num_lines as f64 - line_height).max(0.0)
                                 + editor_size.height
                         } else {
-                            (line_height * data.doc.buffer().num_lines() as f64)
-                                .max(editor_size.height)
+                            (line_height * num_lines as f64).max(editor_size.height)
                         },
                     )
                 }
@@ -581,6 +745,7 @@ fn code_lens_lines(
                         x,
                         y,
                         line_height,
+                        wrap: None,
                     },
                 );
                 y += line_height;
@@ -654,42 +819,14 @@ fn content_history_lines(
                     }
                 }
                 DiffLines::Skip(left, right) => {
-                    let rect = Size::new(self_size.width, line_height)
-                        .to_rect()
-                        .with_origin(Point::new(0.0, line_height * line as f64));
-                    ctx.fill(
-                        rect,
-                        data.config
-                            .get_color_unchecked(LapceTheme::PANEL_BACKGROUND),
-                    );
-                    ctx.stroke(
-                        rect,
-                        data.config
-                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
-                        1.0,
-                    );
-                    let text_layout = ctx
-                        .text()
-                        .new_text_layout(format!(
-                            " -{}, +{}",
-                            left.end + 1,
-                            right.end + 1
-                        ))
-                        .font(data.config.editor.font_family(), font_size as f64)
-                        .text_color(
-                            data.config
-                                .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
-                                .clone(),
-                        )
-                        .build()
-                        .unwrap();
-                    ctx.draw_text(
-                        &text_layout,
-                        Point::new(
-                            0.0,
-                            line_height * line as f64
-                                + text_layout.y_offset(line_height),
-                        ),
+                    Self::paint_diff_skip(
+                        ctx,
+                        data,
+                        line,
+                        left,
+                        right,
+                        line_height,
+                        font_size,
                     );
                     line += 1;
                 }
@@ -713,6 +850,7 @@ fn content_history_lines(
                                 x: 0.0,
                                 y: l as f64 * line_height,
                                 line_height,
+                                wrap: None,
                             },
                         );
 
@@ -754,6 +892,7 @@ fn content_history_lines(
                                 x: 0.0,
                                 y: l as f64 * line_height,
                                 line_height,
+                                wrap: None,
                             },
                         );
 
@@ -767,6 +906,272 @@ fn content_history_lines(
         ScreenLines { lines, info }
     }
 
+    /// The lines of one side of a comparison, where the rows of the lines
+    /// that only the other side has are left empty, so that the hunks of
+    /// both sides line up. The left side draws the lines of the history
+    /// itself, so it has no lines of the document.
+    fn content_compare_lines(
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        history: &DocumentHistory,
+        side: DiffSide,
+        env: &Env,
+    ) -> ScreenLines {
+        let line_height = Self::line_height(data, env);
+        let font_size = data.config.editor.font_size;
+
+        let self_size = ctx.size();
+        let rect = ctx.region().bounding_box();
+        let start_line = (rect.y0 / line_height).floor() as usize;
+        let end_line = (rect.y1 / line_height).ceil() as usize;
+
+        let changes = history.changes();
+        let mut line = 0;
+        let mut lines = Vec::new();
+        let mut info = HashMap::new();
+        for (i, change) in changes.iter().enumerate() {
+            if line > end_line {
+                break;
+            }
+            let (left, right) = match change {
+                DiffLines::Skip(left, right) => {
+                    Self::paint_diff_skip(
+                        ctx,
+                        data,
+                        line,
+                        left,
+                        right,
+                        line_height,
+                        font_size,
+                    );
+                    line += 1;
+                    continue;
+                }
+                DiffLines::Left(left) => (Some(left), None),
+                DiffLines::Both(left, right) => (Some(left), Some(right)),
+                DiffLines::Right(right) => (None, Some(right)),
+            };
+            let len = right.or(left).map(|r| r.len()).unwrap_or(0);
+            let first_line = line;
+            line += len;
+            if line < start_line {
+                continue;
+            }
+
+            let background = match (side, left, right) {
+                (_, Some(_), Some(_)) => None,
+                (DiffSide::Left, Some(_), None) => {
+                    Some(LapceTheme::SOURCE_CONTROL_REMOVED)
+                }
+                (DiffSide::Right, None, Some(_)) => {
+                    Some(LapceTheme::SOURCE_CONTROL_ADDED)
+                }
+                _ => Some(LapceTheme::PANEL_BACKGROUND),
+            };
+            if let Some(background) = background {
+                ctx.fill(
+                    Size::new(self_size.width, line_height * len as f64)
+                        .to_rect()
+                        .with_origin(Point::new(
+                            0.0,
+                            line_height * first_line as f64,
+                        )),
+                    data.config.get_color_unchecked(background),
+                );
+            }
+
+            // The lines of a hunk that replaced lines are paired up with them
+            // to show which parts of the lines changed
+            let paired = match (left, right) {
+                (Some(left), None) => match changes.get(i + 1) {
+                    Some(DiffLines::Right(right)) => Some((left, right)),
+                    _ => None,
+                },
+                (None, Some(right)) => match i.checked_sub(1).map(|i| &changes[i]) {
+                    Some(DiffLines::Left(left)) => Some((left, right)),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            for l in first_line..line {
+                if l < start_line {
+                    continue;
+                }
+                if l > end_line {
+                    break;
+                }
+                let k = l - first_line;
+                match side {
+                    DiffSide::Left => {
+                        let left = match left {
+                            Some(left) => left,
+                            None => break,
+                        };
+                        let text_layout = history.get_text_layout(
+                            ctx.text(),
+                            left.start + k,
+                            &data.config,
+                        );
+                        if let Some((left, right)) = paired {
+                            if let Some((changed, _)) = Self::changed_line_ranges(
+                                data, history, left, right, k,
+                            ) {
+                                let x0 = text_layout
+                                    .text
+                                    .hit_test_text_position(changed.start)
+                                    .point
+                                    .x;
+                                let x1 = text_layout
+                                    .text
+                                    .hit_test_text_position(changed.end)
+                                    .point
+                                    .x;
+                                ctx.fill(
+                                    Rect::new(
+                                        x0,
+                                        line_height * l as f64,
+                                        x1,
+                                        line_height * (l + 1) as f64,
+                                    ),
+                                    data.config.get_color_unchecked(
+                                        LapceTheme::SOURCE_CONTROL_REMOVED,
+                                    ),
+                                );
+                            }
+                        }
+                        ctx.draw_text(
+                            &text_layout.text,
+                            Point::new(
+                                0.0,
+                                line_height * l as f64
+                                    + text_layout.text.y_offset(line_height),
+                            ),
+                        );
+                    }
+                    DiffSide::Right => {
+                        let right = match right {
+                            Some(right) => right,
+                            None => break,
+                        };
+                        let rope_line = right.start + k;
+                        if let Some((left, right)) = paired {
+                            if let Some((_, changed)) = Self::changed_line_ranges(
+                                data, history, left, right, k,
+                            ) {
+                                let phantom_text = data
+                                    .doc
+                                    .line_phantom_text(&data.config, rope_line);
+                                let [x0, x1] =
+                                    [changed.start, changed.end].map(|col| {
+                                        data.doc
+                                            .line_point_of_line_col(
+                                                ctx.text(),
+                                                rope_line,
+                                                phantom_text.col_after(col, false),
+                                                font_size,
+                                                &data.config,
+                                            )
+                                            .x
+                                    });
+                                ctx.fill(
+                                    Rect::new(
+                                        x0,
+                                        line_height * l as f64,
+                                        x1,
+                                        line_height * (l + 1) as f64,
+                                    ),
+                                    data.config.get_color_unchecked(
+                                        LapceTheme::SOURCE_CONTROL_ADDED,
+                                    ),
+                                );
+                            }
+                        }
+                        lines.push(rope_line);
+                        info.insert(
+                            rope_line,
+                            LineInfo {
+                                font_size,
+                                x: 0.0,
+                                y: l as f64 * line_height,
+                                line_height,
+                                wrap: None,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        ScreenLines { lines, info }
+    }
+
+    /// The changed parts of the `k`th lines of a hunk of the history and
+    /// the hunk of the document that replaced it, if both hunks have a
+    /// `k`th line and the lines differ
+    fn changed_line_ranges(
+        data: &LapceEditorBufferData,
+        history: &DocumentHistory,
+        left: &Range<usize>,
+        right: &Range<usize>,
+        k: usize,
+    ) -> Option<(Range<usize>, Range<usize>)> {
+        if k >= left.len() || k >= right.len() {
+            return None;
+        }
+        let left_content = history.buffer()?.line_content(left.start + k);
+        let right_content = data.doc.buffer().line_content(right.start + k);
+        let (left, right) = line_diff_ranges(&left_content, &right_content);
+        if left.is_empty() && right.is_empty() {
+            return None;
+        }
+        Some((left, right))
+    }
+
+    /// Paint the row that stands for the unchanged lines that are hidden
+    /// between the hunks of a diff
+    fn paint_diff_skip(
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        line: usize,
+        left: &Range<usize>,
+        right: &Range<usize>,
+        line_height: f64,
+        font_size: usize,
+    ) {
+        let rect = Size::new(ctx.size().width, line_height)
+            .to_rect()
+            .with_origin(Point::new(0.0, line_height * line as f64));
+        ctx.fill(
+            rect,
+            data.config
+                .get_color_unchecked(LapceTheme::PANEL_BACKGROUND),
+        );
+        ctx.stroke(
+            rect,
+            data.config
+                .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+            1.0,
+        );
+        let text_layout = ctx
+            .text()
+            .new_text_layout(format!(" -{}, +{}", left.end + 1, right.end + 1))
+            .font(data.config.editor.font_family(), font_size as f64)
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &text_layout,
+            Point::new(
+                0.0,
+                line_height * line as f64 + text_layout.y_offset(line_height),
+            ),
+        );
+    }
+
     fn paint_content(
         &mut self,
         data: &LapceEditorBufferData,
@@ -798,17 +1203,27 @@ fn paint_content(
 
                 let mut lines = Vec::new();
                 let mut info = HashMap::new();
-                for line in start_line..end_line + 1 {
+                let mut visual_line = start_line;
+                while visual_line <= end_line {
+                    let (line, _) =
+                        data.doc.line_of_visual_line(visual_line, &data.config);
+                    if lines.last() == Some(&line) {
+                        // Past the end of the wrapped lines
+                        break;
+                    }
+                    // the text starts below the virtual lines of the line
+                    let text_line = data.doc.visual_line(line, &data.config);
+                    let wrap = data.doc.wrapped_line(ctx.text(), line, &data.config);
+                    let line_info = LineInfo {
+                        font_size,
+                        x: 0.0,
+                        y: text_line as f64 * line_height + line_padding,
+                        line_height,
+                        wrap,
+                    };
+                    visual_line = text_line + line_info.num_segments();
                     lines.push(line);
-                    info.insert(
-                        line,
-                        LineInfo {
-                            font_size,
-                            x: 0.0,
-                            y: line as f64 * line_height + line_padding,
-                            line_height,
-                        },
-                    );
+                    info.insert(line, line_info);
                 }
                 ScreenLines { lines, info }
             }
@@ -819,13 +1234,28 @@ fn paint_content(
                     return;
                 }
             }
+            EditorView::Compare(version, side) => {
+                if let Some(history) = data.doc.get_history(version) {
+                    Self::content_compare_lines(ctx, data, history, *side, env)
+                } else {
+                    return;
+                }
+            }
             EditorView::Lens => Self::code_lens_lines(ctx, data, env),
         };
 
         Self::paint_current_line(ctx, data, &screen_lines);
+        Self::paint_stopped_line(ctx, data, &screen_lines);
+        Self::paint_rulers(ctx, data);
+        Self::paint_indent_guides(ctx, data, &screen_lines);
+        Self::paint_bracket_guide(ctx, data, &screen_lines);
         Self::paint_cursor_new(ctx, data, &screen_lines, is_focused, env);
+        Self::paint_document_highlights(ctx, data, &screen_lines);
         Self::paint_find(ctx, data, &screen_lines);
         Self::paint_text(ctx, data, &screen_lines, env);
+        self.paint_link(ctx, data, &screen_lines);
+        self.paint_code_lenses(ctx, data, &screen_lines);
+        self.paint_inline_values(ctx, data, &screen_lines);
         Self::paint_diagnostics(ctx, data, &screen_lines);
         Self::paint_snippet(ctx, data, &screen_lines);
         Self::paint_sticky_headers(ctx, data, env);
@@ -858,6 +1288,141 @@ fn paint_content(
         }
     }
 
+    /// Draw the code lenses of the language server on the virtual line above
+    /// their lines, lined up with the start of the text
+    fn paint_code_lenses(
+        &mut self,
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        screen_lines: &ScreenLines,
+    ) {
+        self.code_lenses.clear();
+        if data.editor.view != EditorView::Normal
+            || !data.config.editor.enable_lsp_code_lens
+        {
+            return;
+        }
+
+        let font_family = data.config.editor.inlay_hint_font_family();
+        let font_size = data.config.editor.inlay_hint_font_size() as f64;
+        let color = data.config.get_color_unchecked(LapceTheme::EDITOR_DIM);
+        for line in &screen_lines.lines {
+            let (plugin_id, commands) = match data.doc.line_code_lenses(*line) {
+                Some(lenses) => lenses,
+                None => continue,
+            };
+            let info = screen_lines.info.get(line).unwrap();
+            let buffer = data.doc.buffer();
+            let indent = buffer.first_non_blank_character_on_line(*line)
+                - buffer.offset_of_line(*line);
+            let mut x = info.x
+                + data
+                    .doc
+                    .line_point_of_line_col(
+                        ctx.text(),
+                        *line,
+                        indent,
+                        info.font_size,
+                        &data.config,
+                    )
+                    .x;
+            let y = info.y - info.line_height;
+
+            for (i, command) in commands.into_iter().enumerate() {
+                let title = if i == 0 {
+                    command.title.clone()
+                } else {
+                    format!(" | {}", command.title)
+                };
+                let title_layout = ctx
+                    .text()
+                    .new_text_layout(title)
+                    .font(font_family.clone(), font_size)
+                    .text_color(color.clone())
+                    .build()
+                    .unwrap();
+                let width = title_layout.size().width;
+                ctx.draw_text(
+                    &title_layout,
+                    Point::new(x, y + title_layout.y_offset(info.line_height)),
+                );
+                self.code_lenses.push((
+                    Rect::new(x, y, x + width, y + info.line_height),
+                    plugin_id,
+                    command.clone(),
+                ));
+                x += width;
+            }
+        }
+    }
+
+    /// Draw the values of the local variables after the lines above the one
+    /// the program stopped at
+    fn paint_inline_values(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        screen_lines: &ScreenLines,
+    ) {
+        if data.editor.view != EditorView::Normal {
+            return;
+        }
+        let (path, stopped_line) = match data.debug.stopped_location() {
+            Some(location) => location,
+            None => return,
+        };
+        if !matches!(data.doc.content(), BufferContent::File(p) if p == path) {
+            return;
+        }
+        let variables = data.debug.stopped_locals();
+        if variables.is_empty() {
+            return;
+        }
+
+        let font_family = data.config.editor.inlay_hint_font_family();
+        let font_size = data.config.editor.inlay_hint_font_size() as f64;
+        let color = data.config.get_color_unchecked(LapceTheme::EDITOR_DIM);
+        let first_line = stopped_line.saturating_sub(INLINE_VALUE_LINES);
+        for line in &screen_lines.lines {
+            if *line < first_line || *line > stopped_line {
+                continue;
+            }
+            let content = data.doc.buffer().line_content(*line);
+            let values = match line_inline_values(&variables, &content) {
+                Some(values) => values,
+                None => continue,
+            };
+            let info = screen_lines.info.get(line).unwrap();
+            let text_layout = data.doc.get_text_layout(
+                ctx.text(),
+                *line,
+                info.font_size,
+                &data.config,
+            );
+
+            // after the last visual line of a soft wrapped line
+            let segment = info.num_segments() - 1;
+            let mut x = info.x + text_layout.text.size().width;
+            let mut y = info.y;
+            if let Some(wrap) = info.wrap {
+                x += wrap.x_shift(segment);
+                y += segment as f64 * info.line_height;
+            }
+
+            let values_layout = ctx
+                .text()
+                .new_text_layout(format!("    {values}"))
+                .font(font_family.clone(), font_size)
+                .text_color(color.clone())
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &values_layout,
+                Point::new(x, y + values_layout.y_offset(info.line_height)),
+            );
+        }
+    }
+
     fn paint_text(
         ctx: &mut PaintCtx,
         data: &LapceEditorBufferData,
@@ -865,6 +1430,16 @@ fn paint_text(
         _env: &Env,
     ) {
         let self_size = ctx.size();
+        let is_normal = data.editor.view == EditorView::Normal;
+        let whitespace_selection =
+            if data.config.editor.render_whitespace == "selection" {
+                Some(match &data.editor.cursor.mode {
+                    CursorMode::Normal(_) => Selection::new(),
+                    _ => data.editor.cursor.edit_selection(data.doc.buffer()),
+                })
+            } else {
+                None
+            };
 
         for line in &screen_lines.lines {
             let line = *line;
@@ -880,38 +1455,150 @@ fn paint_text(
                 info.font_size,
                 &data.config,
             );
-            let y = info.y + text_layout.text.y_offset(info.line_height);
+            let y_offset = text_layout.text.y_offset(info.line_height);
             let height = text_layout.text.size().height;
-            for (x0, x1, style) in text_layout.extra_style.iter() {
-                if let Some(bg) = &style.bg_color {
-                    let x1 = x1.unwrap_or(self_size.width);
-                    ctx.fill(
-                        Rect::new(*x0 + info.x, y, x1 + info.x, y + height),
-                        bg,
-                    );
-                }
+            let whitespace_ranges = whitespace_selection.as_ref().map(|selection| {
+                Self::selected_x_ranges(data, selection, line, &text_layout.text)
+            });
+            for segment in 0..info.num_segments() {
+                ctx.with_save(|ctx| {
+                    // A soft wrapped line draws the whole text layout on each
+                    // of its visual lines, clipped to the part shown there
+                    let mut x = info.x;
+                    let mut y = info.y;
+                    if let Some(wrap) = info.wrap {
+                        let (start, end) = wrap.x_range(segment);
+                        x += wrap.x_shift(segment);
+                        y += segment as f64 * info.line_height;
+                        let end = if end.is_finite() {
+                            end + x
+                        } else {
+                            self_size.width
+                        };
+                        ctx.clip(Rect::new(start + x, y, end, y + info.line_height));
+                    }
+                    let y = y + y_offset;
+
+                    for (x0, x1, style) in text_layout.extra_style.iter() {
+                        if let Some(bg) = &style.bg_color {
+                            let x1 = x1.unwrap_or(self_size.width - x);
+                            ctx.fill(Rect::new(*x0 + x, y, x1 + x, y + height), bg);
+                        }
+                    }
+
+                    if let Some(whitespace) = &text_layout.whitespace {
+                        match &whitespace_ranges {
+                            Some(ranges) => {
+                                for (x0, x1) in ranges {
+                                    ctx.with_save(|ctx| {
+                                        ctx.clip(Rect::new(
+                                            x0 + x,
+                                            y,
+                                            x1 + x,
+                                            y + height,
+                                        ));
+                                        ctx.draw_text(whitespace, Point::new(x, y));
+                                    });
+                                }
+                            }
+                            None => ctx.draw_text(whitespace, Point::new(x, y)),
+                        }
+                    }
+
+                    ctx.draw_text(&text_layout.text, Point::new(x, y));
+                });
             }
 
-            if let Some(whitespace) = &text_layout.whitespace {
-                ctx.draw_text(whitespace, Point::new(info.x, y));
+            if is_normal && data.doc.folded_lines().is_folded(line) {
+                Self::paint_fold_marker(
+                    ctx,
+                    data,
+                    info,
+                    text_layout.text.size().width,
+                );
             }
+        }
+    }
 
-            ctx.draw_text(&text_layout.text, Point::new(info.x, y));
+    /// The x ranges of the line's text layout that are covered by the
+    /// selection, where whitespace is rendered in the selection mode
+    fn selected_x_ranges(
+        data: &LapceEditorBufferData,
+        selection: &Selection,
+        line: usize,
+        text_layout: &PietTextLayout,
+    ) -> Vec<(f64, f64)> {
+        let buffer = data.doc.buffer();
+        let line_start = buffer.offset_of_line(line);
+        let line_end = buffer.offset_of_line(line + 1);
+        let regions = selection.regions_in_range(line_start, line_end);
+        if regions.is_empty() {
+            return Vec::new();
         }
+
+        let phantom_text = data.doc.line_phantom_text(&data.config, line);
+        let x_of = |offset: usize| {
+            let col = phantom_text.col_after(offset - line_start, false);
+            text_layout.hit_test_text_position(col).point.x
+        };
+        regions
+            .iter()
+            .filter_map(|region| {
+                let start = region.min().max(line_start);
+                let end = region.max().min(line_end);
+                if start < end {
+                    Some((x_of(start), x_of(end)))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Show that the lines after `info`'s line are folded away
+    fn paint_fold_marker(
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        info: &LineInfo,
+        text_width: f64,
+    ) {
+        let text_layout = ctx
+            .text()
+            .new_text_layout("\u{22ef}")
+            .font(data.config.editor.font_family(), info.font_size as f64)
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        let size = text_layout.size();
+        let point = info.point_of_x(text_width);
+        let rect = Size::new(size.width + 8.0, size.height)
+            .to_rect()
+            .with_origin(Point::new(
+                point.x + 8.0,
+                point.y + text_layout.y_offset(info.line_height),
+            ));
+        ctx.fill(
+            rect.to_rounded_rect(3.0),
+            data.config
+                .get_color_unchecked(LapceTheme::EDITOR_CURRENT_LINE),
+        );
+        ctx.draw_text(&text_layout, rect.origin() + (4.0, 0.0));
     }
 
-    #[allow(clippy::too_many_arguments)]
     fn paint_cursor_caret(
         ctx: &mut PaintCtx,
         data: &LapceEditorBufferData,
         offset: usize,
-        font_size: usize,
-        x: f64,
-        y: f64,
-        line_height: f64,
+        info: &LineInfo,
         char_width: f64,
         block: bool,
     ) {
+        let font_size = info.font_size;
+        let line_height = info.line_height;
         let (line, col) = data.doc.buffer().offset_to_line_col(offset);
         let phantom_text = data.doc.line_phantom_text(&data.config, line);
 
@@ -943,7 +1630,7 @@ fn paint_cursor_caret(
             let char_width = if x1 > x0 { x1 - x0 } else { char_width };
             ctx.fill(
                 Rect::ZERO
-                    .with_origin(Point::new(x0 + x, y))
+                    .with_origin(info.point_of_x(x0))
                     .with_size(Size::new(char_width, line_height)),
                 data.config.get_color_unchecked(LapceTheme::EDITOR_CARET),
             );
@@ -958,11 +1645,9 @@ fn paint_cursor_caret(
                     &data.config,
                 )
                 .x;
+            let point = info.point_of_x(x0);
             ctx.stroke(
-                Line::new(
-                    Point::new(x0 + x, y),
-                    Point::new(x0 + x, y + line_height),
-                ),
+                Line::new(point, point + (0.0, line_height)),
                 data.config.get_color_unchecked(LapceTheme::EDITOR_CARET),
                 2.0,
             )
@@ -985,7 +1670,7 @@ fn paint_current_line(
                     ctx.fill(
                         Rect::ZERO
                             .with_origin(Point::new(0.0, info.y))
-                            .with_size(Size::new(self_size.width, info.line_height)),
+                            .with_size(Size::new(self_size.width, info.height())),
                         data.config
                             .get_color_unchecked(LapceTheme::EDITOR_CURRENT_LINE),
                     );
@@ -1011,7 +1696,7 @@ fn paint_current_line(
                                 .with_origin(Point::new(0.0, info.y))
                                 .with_size(Size::new(
                                     self_size.width,
-                                    info.line_height,
+                                    info.height(),
                                 )),
                             data.config.get_color_unchecked(
                                 LapceTheme::EDITOR_CURRENT_LINE,
@@ -1023,6 +1708,252 @@ fn paint_current_line(
         }
     }
 
+    /// Highlight the line the program being debugged is stopped at
+    fn paint_stopped_line(
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        screen_lines: &ScreenLines,
+    ) {
+        if data.editor.view != EditorView::Normal {
+            return;
+        }
+        let (path, line) = match data.debug.stopped_location() {
+            Some(location) => location,
+            None => return,
+        };
+        if !matches!(data.doc.content(), BufferContent::File(p) if p == path) {
+            return;
+        }
+        if let Some(info) = screen_lines.info.get(&line) {
+            ctx.fill(
+                Rect::ZERO
+                    .with_origin(Point::new(0.0, info.y))
+                    .with_size(Size::new(ctx.size().width, info.height())),
+                &data
+                    .config
+                    .get_color_unchecked(LapceTheme::LAPCE_WARN)
+                    .clone()
+                    .with_alpha(0.3),
+            );
+        }
+    }
+
+    /// The columns of leading whitespace of the line, or `None` for a blank
+    /// line
+    fn line_indent(
+        data: &LapceEditorBufferData,
+        line: usize,
+        tab_width: usize,
+    ) -> Option<usize> {
+        let mut cols = 0;
+        for c in data.doc.buffer().line_content(line).chars() {
+            match c {
+                ' ' => cols += 1,
+                '\t' => cols += tab_width - cols % tab_width,
+                '\r' | '\n' => return None,
+                _ => return Some(cols),
+            }
+        }
+        None
+    }
+
+    /// Paint a vertical line down the editor at each of the ruler columns
+    fn paint_rulers(ctx: &mut PaintCtx, data: &LapceEditorBufferData) {
+        let rulers = data.doc.rulers(&data.config);
+        if rulers.is_empty() {
+            return;
+        }
+        let char_width = data.config.editor_char_width(ctx.text());
+        let rect = ctx.region().bounding_box();
+        let color = data
+            .config
+            .get_color_unchecked(LapceTheme::EDITOR_RULER)
+            .clone();
+        for column in rulers {
+            let x = *column as f64 * char_width;
+            ctx.fill(Rect::new(x, rect.y0, x + 1.0, rect.y1), &color);
+        }
+    }
+
+    /// The number of indent guides shown on the line. A blank line takes the
+    /// indentation of the lines around it, so that guides aren't broken by
+    /// empty lines inside a block.
+    fn indent_level(
+        data: &LapceEditorBufferData,
+        line: usize,
+        indent_width: usize,
+        tab_width: usize,
+    ) -> usize {
+        const MAX_BLANK_LINES: usize = 100;
+
+        if let Some(indent) = Self::line_indent(data, line, tab_width) {
+            return indent / indent_width;
+        }
+        let last_line = data.doc.buffer().last_line();
+        let above = (line.saturating_sub(MAX_BLANK_LINES)..line)
+            .rev()
+            .find_map(|l| Self::line_indent(data, l, tab_width));
+        let below = (line + 1..(line + MAX_BLANK_LINES).min(last_line + 1))
+            .find_map(|l| Self::line_indent(data, l, tab_width));
+        match (above, below) {
+            (Some(above), Some(below)) => above.min(below) / indent_width,
+            _ => 0,
+        }
+    }
+
+    /// Paint a vertical guide at each level of indentation, colored by its
+    /// depth in the rainbow mode, and highlight the guide of the indented
+    /// block the cursor is in
+    fn paint_indent_guides(
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        screen_lines: &ScreenLines,
+    ) {
+        let rainbow = match data.config.editor.indent_guides.as_str() {
+            "plain" => false,
+            "rainbow" => true,
+            _ => return,
+        };
+        if data.editor.view != EditorView::Normal || screen_lines.lines.is_empty() {
+            return;
+        }
+
+        let buffer = data.doc.buffer();
+        let tab_width = data.config.editor.tab_width.max(1);
+        let indent_width = match buffer.indent_unit() {
+            "\t" => tab_width,
+            unit => unit.len().max(1),
+        };
+        let last_line = buffer.last_line();
+        let levels: Vec<(usize, usize)> = screen_lines
+            .lines
+            .iter()
+            .filter(|line| **line <= last_line)
+            .map(|line| {
+                (
+                    *line,
+                    Self::indent_level(data, *line, indent_width, tab_width),
+                )
+            })
+            .collect();
+
+        // The active guide runs along the visible lines around the cursor
+        // that are indented at least as deep as the cursor's line
+        let (cursor_line, _) =
+            buffer.offset_to_line_col(data.editor.cursor.offset());
+        let active = levels
+            .iter()
+            .position(|(line, _)| *line == cursor_line)
+            .and_then(|i| {
+                let level = levels[i].1;
+                if level == 0 {
+                    return None;
+                }
+                let start = levels[..i]
+                    .iter()
+                    .rposition(|(_, l)| *l < level)
+                    .map(|p| p + 1)
+                    .unwrap_or(0);
+                let end = levels[i..]
+                    .iter()
+                    .position(|(_, l)| *l < level)
+                    .map(|p| p + i)
+                    .unwrap_or(levels.len());
+                Some((level - 1, start..end))
+            });
+
+        let char_width = data.config.editor_char_width(ctx.text());
+        for (i, (line, level)) in levels.iter().enumerate() {
+            let info = screen_lines.info.get(line).unwrap();
+            for depth in 0..*level {
+                let is_active = active
+                    .as_ref()
+                    .map(|(d, range)| *d == depth && range.contains(&i))
+                    .unwrap_or(false);
+                let color = if rainbow {
+                    let color = data.config.get_color_unchecked(
+                        LapceTheme::EDITOR_BRACKET_COLORS
+                            [depth % LapceTheme::EDITOR_BRACKET_COLORS.len()],
+                    );
+                    if is_active {
+                        color.clone()
+                    } else {
+                        color.clone().with_alpha(0.4)
+                    }
+                } else if is_active {
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_BRACKET_GUIDE)
+                        .clone()
+                } else {
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_VISIBLE_WHITESPACE)
+                        .clone()
+                };
+                let x = info.x + (depth * indent_width) as f64 * char_width;
+                ctx.fill(
+                    Rect::new(x, info.y, x + 1.0, info.y + info.height()),
+                    &color,
+                );
+            }
+        }
+    }
+
+    /// Paint a vertical guide between the lines of the bracket pair that
+    /// encloses the cursor, at the indentation of the open bracket's line
+    fn paint_bracket_guide(
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        screen_lines: &ScreenLines,
+    ) {
+        if !data.config.editor.bracket_pair_guide
+            || data.editor.view != EditorView::Normal
+        {
+            return;
+        }
+
+        let offset = data.editor.cursor.offset();
+        let ((open_line, _), (close_line, _)) =
+            match data.doc.enclosing_bracket_pair(offset) {
+                Some(pair) => pair,
+                None => return,
+            };
+        if close_line <= open_line + 1 {
+            return;
+        }
+
+        let buffer = data.doc.buffer();
+        let indent_offset = buffer.first_non_blank_character_on_line(open_line);
+        let (_, indent_col) = buffer.offset_to_line_col(indent_offset);
+        let x = data
+            .doc
+            .line_point_of_line_col(
+                ctx.text(),
+                open_line,
+                indent_col,
+                data.config.editor.font_size,
+                &data.config,
+            )
+            .x;
+        let color = data
+            .config
+            .get_color_unchecked(LapceTheme::EDITOR_BRACKET_GUIDE);
+        for line in &screen_lines.lines {
+            if *line <= open_line || *line >= close_line {
+                continue;
+            }
+            let info = screen_lines.info.get(line).unwrap();
+            ctx.fill(
+                Rect::new(
+                    info.x + x,
+                    info.y,
+                    info.x + x + 1.0,
+                    info.y + info.height(),
+                ),
+                color,
+            );
+        }
+    }
+
     fn paint_cursor_new(
         ctx: &mut PaintCtx,
         data: &LapceEditorBufferData,
@@ -1039,15 +1970,7 @@ fn paint_cursor_new(
                         data.doc.buffer().offset_to_line_col(*offset);
                     if let Some(info) = screen_lines.info.get(&cursor_line) {
                         Self::paint_cursor_caret(
-                            ctx,
-                            data,
-                            *offset,
-                            info.font_size,
-                            info.x,
-                            info.y,
-                            info.line_height,
-                            char_width,
-                            true,
+                            ctx, data, *offset, info, char_width, true,
                         );
                     }
                 }
@@ -1164,24 +2087,16 @@ fn paint_cursor_new(
                         x1 += char_width;
                     }
 
-                    let y0 = info.y;
-                    let y1 = info.y + info.line_height;
-                    ctx.fill(
-                        Rect::new(x0 + info.x, y0, x1 + info.x, y1),
-                        data.config
-                            .get_color_unchecked(LapceTheme::EDITOR_SELECTION),
-                    );
+                    for rect in info.rects(x0, x1) {
+                        ctx.fill(
+                            rect,
+                            data.config
+                                .get_color_unchecked(LapceTheme::EDITOR_SELECTION),
+                        );
+                    }
                     if is_focused && line == cursor_line {
                         Self::paint_cursor_caret(
-                            ctx,
-                            data,
-                            *end,
-                            info.font_size,
-                            info.x,
-                            info.y,
-                            info.line_height,
-                            char_width,
-                            true,
+                            ctx, data, *end, info, char_width, true,
                         );
                     }
                 }
@@ -1260,25 +2175,22 @@ fn paint_cursor_new(
                             x1 += char_width;
                         }
 
-                        let y0 = info.y;
-                        let y1 = y0 + info.line_height;
                         if start != end {
-                            ctx.fill(
-                                Rect::new(x0 + info.x, y0, x1 + info.x, y1),
-                                data.config.get_color_unchecked(
-                                    LapceTheme::EDITOR_SELECTION,
-                                ),
-                            );
+                            for rect in info.rects(x0, x1) {
+                                ctx.fill(
+                                    rect,
+                                    data.config.get_color_unchecked(
+                                        LapceTheme::EDITOR_SELECTION,
+                                    ),
+                                );
+                            }
                         }
                         if is_focused && line == cursor_line {
                             Self::paint_cursor_caret(
                                 ctx,
                                 data,
                                 cursor_offset,
-                                info.font_size,
-                                info.x,
-                                info.y,
-                                info.line_height,
+                                info,
                                 char_width,
                                 false,
                             );
@@ -1289,6 +2201,152 @@ fn paint_cursor_new(
         }
     }
 
+    /// Fill the background of the occurrences of the symbol under the
+    /// cursor, with another color where it's written to
+    fn paint_document_highlights(
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        screen_lines: &ScreenLines,
+    ) {
+        if !data.editor.content.is_file() {
+            return;
+        }
+        let (first_line, last_line) =
+            match (screen_lines.lines.first(), screen_lines.lines.last()) {
+                (Some(first), Some(last)) => (*first, *last),
+                _ => return,
+            };
+
+        for highlight in data.doc.document_highlights() {
+            let (start_line, start_col) =
+                data.doc.buffer().offset_to_line_col(highlight.start);
+            let (end_line, end_col) =
+                data.doc.buffer().offset_to_line_col(highlight.end);
+            if end_line < first_line || start_line > last_line {
+                continue;
+            }
+            let color = data.config.get_color_unchecked(if highlight.write {
+                LapceTheme::EDITOR_DOCUMENT_HIGHLIGHT_WRITE
+            } else {
+                LapceTheme::EDITOR_DOCUMENT_HIGHLIGHT_READ
+            });
+
+            for line in &screen_lines.lines {
+                let line = *line;
+                if line < start_line {
+                    continue;
+                }
+                if line > end_line {
+                    break;
+                }
+
+                let info = screen_lines.info.get(&line).unwrap();
+                let left_col = if line == start_line { start_col } else { 0 };
+                let right_col = if line == end_line {
+                    end_col
+                } else {
+                    data.doc.buffer().line_end_col(line, true)
+                };
+
+                let phantom_text = data.doc.line_phantom_text(&data.config, line);
+                let left_col = phantom_text.col_at(left_col);
+                let right_col = phantom_text.col_at(right_col);
+
+                let x0 = data
+                    .doc
+                    .line_point_of_line_col(
+                        ctx.text(),
+                        line,
+                        left_col,
+                        info.font_size,
+                        &data.config,
+                    )
+                    .x;
+                let x1 = data
+                    .doc
+                    .line_point_of_line_col(
+                        ctx.text(),
+                        line,
+                        right_col,
+                        info.font_size,
+                        &data.config,
+                    )
+                    .x;
+                for rect in info.rects(x0, x1) {
+                    ctx.fill(rect, color);
+                }
+            }
+        }
+    }
+
+    /// Underline the link under the mouse, which opens when it's clicked
+    fn paint_link(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &LapceEditorBufferData,
+        screen_lines: &ScreenLines,
+    ) {
+        let (start, end) = match self.link {
+            Some(link) if link.1 <= data.doc.buffer().len() => link,
+            _ => return,
+        };
+        let (start_line, start_col) = data.doc.buffer().offset_to_line_col(start);
+        let (end_line, end_col) = data.doc.buffer().offset_to_line_col(end);
+        let color = data.config.get_color_unchecked(LapceTheme::EDITOR_LINK);
+
+        for line in &screen_lines.lines {
+            let line = *line;
+            if line < start_line {
+                continue;
+            }
+            if line > end_line {
+                break;
+            }
+
+            let info = screen_lines.info.get(&line).unwrap();
+            let left_col = if line == start_line { start_col } else { 0 };
+            let right_col = if line == end_line {
+                end_col
+            } else {
+                data.doc.buffer().line_end_col(line, true)
+            };
+
+            let phantom_text = data.doc.line_phantom_text(&data.config, line);
+            let left_col = phantom_text.col_at(left_col);
+            let right_col = phantom_text.col_at(right_col);
+
+            let x0 = data
+                .doc
+                .line_point_of_line_col(
+                    ctx.text(),
+                    line,
+                    left_col,
+                    info.font_size,
+                    &data.config,
+                )
+                .x;
+            let x1 = data
+                .doc
+                .line_point_of_line_col(
+                    ctx.text(),
+                    line,
+                    right_col,
+                    info.font_size,
+                    &data.config,
+                )
+                .x;
+            let scale = info.font_size as f64 / data.config.editor.font_size as f64;
+            for rect in info.rects(x0, x1) {
+                let y = rect.y1 - 4.0 * scale;
+                ctx.stroke(
+                    Line::new(Point::new(rect.x0, y), Point::new(rect.x1, y)),
+                    color,
+                    scale,
+                );
+            }
+        }
+    }
+
     fn paint_find(
         ctx: &mut PaintCtx,
         data: &LapceEditorBufferData,
@@ -1361,25 +2419,24 @@ fn paint_find(
                         text_layout.text.hit_test_text_position(left_col).point.x;
                     let x1 =
                         text_layout.text.hit_test_text_position(right_col).point.x;
-                    let y0 = info.y;
-                    let y1 = info.y + info.line_height;
-                    let rect = Rect::new(x0 + info.x, y0, x1 + info.x, y1);
-                    if active {
-                        ctx.fill(
+                    for rect in info.rects(x0, x1) {
+                        if active {
+                            ctx.fill(
+                                rect,
+                                &data
+                                    .config
+                                    .get_color_unchecked(LapceTheme::EDITOR_CARET)
+                                    .clone()
+                                    .with_alpha(0.5),
+                            );
+                        }
+                        ctx.stroke(
                             rect,
-                            &data
-                                .config
-                                .get_color_unchecked(LapceTheme::EDITOR_CARET)
-                                .clone()
-                                .with_alpha(0.5),
+                            data.config
+                                .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                            1.0,
                         );
                     }
-                    ctx.stroke(
-                        rect,
-                        data.config
-                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
-                        1.0,
-                    );
                 }
             }
         }
@@ -1448,6 +2505,13 @@ fn paint_sticky_headers(
             }
         }
 
+        let max_lines = data.config.editor.sticky_header_max_lines;
+        if sticky_lines.len() > max_lines {
+            // Keep the outermost scopes
+            sticky_lines.truncate(max_lines);
+            last_sticky_should_scroll = false;
+        }
+
         let total_sticky_lines = sticky_lines.len();
 
         let paint_last_line = total_sticky_lines > 0
@@ -1580,15 +2644,14 @@ fn paint_snippet(
                             &data.config,
                         )
                         .x;
-                    let y0 = info.y;
-                    let y1 = info.y + info.line_height;
-                    ctx.stroke(
-                        Rect::new(x0 + info.x, y0, x1 + info.x, y1)
-                            .inflate(1.0, -0.5),
-                        data.config
-                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
-                        1.0,
-                    );
+                    for rect in info.rects(x0, x1) {
+                        ctx.stroke(
+                            rect.inflate(1.0, -0.5),
+                            data.config
+                                .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                            1.0,
+                        );
+                    }
                 }
             }
         }
@@ -1655,7 +2718,6 @@ fn paint_diagnostics(
                     };
                     let scale =
                         info.font_size as f64 / data.config.editor.font_size as f64;
-                    let y0 = info.y + info.line_height - 4.0 * scale;
 
                     let severity = diagnostic
                         .diagnostic
@@ -1670,13 +2732,15 @@ fn paint_diagnostics(
                         }
                         _ => data.config.get_color_unchecked(LapceTheme::LAPCE_WARN),
                     };
-                    Self::paint_wave_line(
-                        ctx,
-                        Point::new(x0 + info.x, y0),
-                        x1 - x0,
-                        scale,
-                        color,
-                    );
+                    for rect in info.rects(x0, x1) {
+                        Self::paint_wave_line(
+                            ctx,
+                            Point::new(rect.x0, rect.y1 - 4.0 * scale),
+                            rect.width(),
+                            scale,
+                            color,
+                        );
+                    }
                 }
             }
         }
@@ -1737,7 +2801,7 @@ fn paint_diagnostics(
                         .unwrap_or_else(Vec::new);
 
                     let rect = Rect::ZERO
-                        .with_origin(Point::new(0.0, info.y + info.line_height))
+                        .with_origin(Point::new(0.0, info.y + info.height()))
                         .with_size(Size::new(
                             data.editor.size.borrow().width,
                             text_height + 20.0,
@@ -1766,7 +2830,7 @@ fn paint_diagnostics(
                         &text_layout,
                         Point::new(
                             10.0 + data.editor.scroll_offset.x,
-                            info.y + info.line_height + 10.0,
+                            info.y + info.height() + 10.0,
                         ),
                     );
                     let mut text_height = text_size.height;
@@ -1777,7 +2841,7 @@ fn paint_diagnostics(
                             &text,
                             Point::new(
                                 10.0 + data.editor.scroll_offset.x,
-                                info.y + info.line_height + 10.0 + text_height,
+                                info.y + info.height() + 10.0 + text_height,
                             ),
                         );
                         text_height += text.size().height;
@@ -1844,6 +2908,20 @@ fn event(
         data: &mut LapceTabData,
         _env: &Env,
     ) {
+        if let Event::MouseMove(_) | Event::MouseDown(_) = event {
+            // The left side of a comparison has no text of the document to
+            // put the cursor in or hover over
+            if data
+                .main_split
+                .editors
+                .get(&self.view_id)
+                .unwrap()
+                .view
+                .is_read_only()
+            {
+                return;
+            }
+        }
         match event {
             Event::Wheel(_) => {
                 if data.hover.status != HoverStatus::Inactive {
@@ -1851,11 +2929,44 @@ fn event(
                 }
             }
             Event::MouseMove(mouse_event) => {
-                ctx.set_cursor(&druid::Cursor::IBeam);
                 let doc = data.main_split.editor_doc(self.view_id);
                 let editor =
                     data.main_split.editors.get(&self.view_id).unwrap().clone();
                 let mut editor_data = data.editor_view_content(self.view_id);
+                let link = if !ctx.is_active() && is_link_modifier(mouse_event.mods)
+                {
+                    let (offset, is_inside) = editor_data.doc.offset_of_point(
+                        ctx.text(),
+                        Mode::Insert,
+                        mouse_event.pos,
+                        &editor_data.editor.view,
+                        &data.config,
+                    );
+                    is_inside
+                        .then(|| editor_data.doc.link_at(offset))
+                        .flatten()
+                        .map(|(start, end, _)| (start, end))
+                } else {
+                    None
+                };
+                if link != self.link {
+                    self.link = link;
+                    ctx.request_paint();
+                }
+                if !ctx.is_active()
+                    && Self::sticky_header_line_at(
+                        mouse_event.pos,
+                        &editor_data,
+                        &data.config,
+                    )
+                    .is_some()
+                    || self.code_lens_at(mouse_event.pos).is_some()
+                    || self.link.is_some()
+                {
+                    ctx.set_cursor(&druid::Cursor::Pointer);
+                } else {
+                    ctx.set_cursor(&druid::Cursor::IBeam);
+                }
                 self.mouse_move(
                     ctx,
                     mouse_event.pos,
@@ -2136,6 +3247,19 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
     }
 }
 
+/// Whether the modifiers are the ones that make a click open a link, or
+/// go to the definition
+pub(crate) fn is_link_modifier(mods: Modifiers) -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        mods.meta()
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        mods.ctrl()
+    }
+}
+
 #[derive(Clone)]
 pub struct RegisterContent {}
 
@@ -1,18 +1,28 @@
+use std::path::Path;
+
 use crate::svg::get_svg;
 use druid::{
     piet::{PietText, Text, TextLayout, TextLayoutBuilder},
     BoxConstraints, Command, Env, Event, EventCtx, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, Point, Rect, RenderContext, Size, Target, UpdateCtx,
-    Widget, WidgetId,
+    LifeCycleCtx, Menu, MenuItem, PaintCtx, Point, Rect, RenderContext, Size,
+    Target, UpdateCtx, Widget, WidgetId,
 };
 use lapce_core::buffer::DiffLines;
 use lapce_data::{
     command::{LapceUICommand, LAPCE_UI_COMMAND},
     config::LapceTheme,
-    data::{EditorView, LapceTabData},
+    data::{DiffSide, EditorView, LapceData, LapceTabData},
+    debug::{BreakpointOptionEdit, BreakpointOptionKind},
+    document::BufferContent,
     editor::{LapceEditorBufferData, Syntax},
+    palette::PaletteType,
 };
 
+/// The width of the column with the fold chevrons
+const FOLD_WIDTH: f64 = 12.0;
+/// The size of the breakpoint icons, right of the line numbers
+const BREAKPOINT_WIDTH: f64 = 12.0;
+
 pub struct LapceEditorGutter {
     view_id: WidgetId,
     width: f64,
@@ -40,9 +50,61 @@ fn event(
         match event {
             Event::MouseDown(mouse_event) => {
                 self.mouse_down_pos = mouse_event.pos;
+                if mouse_event.button.is_right() {
+                    let data = data.editor_view_content(self.view_id);
+                    if let Some(line) =
+                        self.breakpoint_line_at(ctx.text(), &data, mouse_event.pos)
+                    {
+                        if let BufferContent::File(path) = data.doc.content() {
+                            ctx.show_context_menu::<LapceData>(
+                                Self::breakpoint_menu(&data, path, line),
+                                ctx.to_window(mouse_event.pos),
+                            );
+                        }
+                    }
+                }
             }
             Event::MouseUp(mouse_event) => {
                 let data = data.editor_view_content(self.view_id);
+                if let Some(line) =
+                    self.fold_line_at(ctx.text(), &data, mouse_event.pos)
+                {
+                    if self.fold_line_at(ctx.text(), &data, self.mouse_down_pos)
+                        == Some(line)
+                    {
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::ToggleFold(line),
+                            Target::Widget(self.view_id),
+                        ));
+                    }
+                    return;
+                }
+                if let Some(line) =
+                    self.breakpoint_line_at(ctx.text(), &data, mouse_event.pos)
+                {
+                    if !mouse_event.button.is_left() {
+                        return;
+                    }
+                    if let BufferContent::File(path) = data.doc.content() {
+                        if self.breakpoint_line_at(
+                            ctx.text(),
+                            &data,
+                            self.mouse_down_pos,
+                        ) == Some(line)
+                        {
+                            ctx.submit_command(Command::new(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::ToggleBreakpoint {
+                                    path: path.to_path_buf(),
+                                    line,
+                                },
+                                Target::Auto,
+                            ));
+                        }
+                    }
+                    return;
+                }
                 if let Some(actions) = data.current_code_actions() {
                     if !actions.is_empty() {
                         let rect = self.code_actions_rect(ctx.text(), &data);
@@ -128,8 +190,9 @@ fn layout(
         let last_line = data.doc.buffer().last_line() + 1;
         let char_width = data.config.editor_char_width(ctx.text());
         self.width = (char_width * last_line.to_string().len() as f64).ceil();
-        let mut width = self.width + 16.0 + char_width * 2.0;
-        if data.editor.compare.is_some() {
+        let mut width = self.width + 16.0 + char_width * 2.0 + FOLD_WIDTH;
+        // The inline diff shows the line numbers of both versions
+        if let EditorView::Diff(_) = &data.editor.view {
             width += self.width + char_width * 2.0;
         }
         Size::new(width.ceil(), bc.max().height)
@@ -142,6 +205,110 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
 }
 
 impl LapceEditorGutter {
+    /// The x position of the fold chevrons, to the right of everything else
+    fn fold_x(&self, text: &mut PietText, data: &LapceEditorBufferData) -> f64 {
+        self.width + 16.0 + data.config.editor_char_width(text) * 2.0
+    }
+
+    /// The line with a folding range whose chevron is at `pos`
+    fn fold_line_at(
+        &self,
+        text: &mut PietText,
+        data: &LapceEditorBufferData,
+        pos: Point,
+    ) -> Option<usize> {
+        if data.editor.view != EditorView::Normal || pos.x < self.fold_x(text, data)
+        {
+            return None;
+        }
+        let line_height = data.config.editor.line_height() as f64;
+        let visual_line =
+            ((pos.y + data.editor.scroll_offset.y) / line_height).floor() as usize;
+        let (line, _) = data.doc.line_of_visual_line(visual_line, &data.config);
+        data.doc
+            .folding_range_at(line)
+            .map(|range| range.start_line)
+    }
+
+    /// The line whose number is at `pos`, which toggles a breakpoint when
+    /// it's clicked
+    fn breakpoint_line_at(
+        &self,
+        text: &mut PietText,
+        data: &LapceEditorBufferData,
+        pos: Point,
+    ) -> Option<usize> {
+        if data.editor.view != EditorView::Normal
+            || data.editor.is_code_lens()
+            || pos.x >= self.width + data.config.editor_char_width(text)
+        {
+            return None;
+        }
+        let line_height = data.config.editor.line_height() as f64;
+        let visual_line =
+            ((pos.y + data.editor.scroll_offset.y) / line_height).floor() as usize;
+        let (line, _) = data.doc.line_of_visual_line(visual_line, &data.config);
+        if line > data.doc.buffer().last_line() {
+            return None;
+        }
+        Some(line)
+    }
+
+    /// The menu to add or remove the breakpoint of the line, and to edit its
+    /// condition, hit count or log message in the palette
+    fn breakpoint_menu(
+        data: &LapceEditorBufferData,
+        path: &Path,
+        line: usize,
+    ) -> Menu<LapceData> {
+        let breakpoint = data
+            .debug
+            .breakpoints
+            .get(path)
+            .and_then(|breakpoints| breakpoints.get(&line));
+
+        let mut menu = Menu::new("Breakpoint");
+        menu = menu.entry(
+            MenuItem::new(if breakpoint.is_some() {
+                "Remove Breakpoint"
+            } else {
+                "Add Breakpoint"
+            })
+            .command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::ToggleBreakpoint {
+                    path: path.to_path_buf(),
+                    line,
+                },
+                Target::Auto,
+            )),
+        );
+        menu = menu.separator();
+        for (label, kind) in [
+            ("Edit Condition...", BreakpointOptionKind::Condition),
+            ("Edit Hit Count...", BreakpointOptionKind::HitCondition),
+            ("Edit Log Message...", BreakpointOptionKind::LogMessage),
+        ] {
+            let value = breakpoint
+                .and_then(|breakpoint| breakpoint.option(kind))
+                .cloned()
+                .unwrap_or_default();
+            menu = menu.entry(MenuItem::new(label).command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::RunPalette(Some(PaletteType::EditBreakpoint(
+                    BreakpointOptionEdit {
+                        path: path.to_path_buf(),
+                        line,
+                        kind,
+                        value,
+                    },
+                ))),
+                Target::Auto,
+            )));
+        }
+        menu
+    }
+
     fn paint_gutter_inline_diff(
         &self,
         data: &LapceEditorBufferData,
@@ -393,6 +560,125 @@ fn paint_gutter_inline_diff(
         }
     }
 
+    /// The line numbers of one side of a comparison, which are left out on
+    /// the rows of the lines that only the other side has
+    fn paint_gutter_compare(
+        &self,
+        data: &LapceEditorBufferData,
+        ctx: &mut PaintCtx,
+        version: &str,
+        side: DiffSide,
+    ) {
+        let history = match data.doc.get_history(version) {
+            Some(history) => history,
+            None => return,
+        };
+        let self_size = ctx.size();
+        let line_height = data.config.editor.line_height() as f64;
+        let scroll_offset = data.editor.scroll_offset;
+        let start_line = (scroll_offset.y / line_height).floor() as usize;
+        let end_line =
+            ((scroll_offset.y + self_size.height) / line_height).ceil() as usize;
+        let current_line = data
+            .doc
+            .buffer()
+            .line_of_offset(data.editor.cursor.offset());
+
+        let mut line = 0;
+        for change in history.changes().iter() {
+            if line > end_line {
+                break;
+            }
+            let (left, right) = match change {
+                DiffLines::Skip(_l, _r) => {
+                    let rect = Size::new(self_size.width, line_height)
+                        .to_rect()
+                        .with_origin(Point::new(
+                            0.0,
+                            line_height * line as f64 - scroll_offset.y,
+                        ));
+                    ctx.fill(
+                        rect,
+                        data.config
+                            .get_color_unchecked(LapceTheme::PANEL_BACKGROUND),
+                    );
+                    ctx.stroke(
+                        rect,
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                        1.0,
+                    );
+                    line += 1;
+                    continue;
+                }
+                DiffLines::Left(l) => (Some(l), None),
+                DiffLines::Both(l, r) => (Some(l), Some(r)),
+                DiffLines::Right(r) => (None, Some(r)),
+            };
+            let len = right.or(left).map(|r| r.len()).unwrap_or(0);
+            let first_line = line;
+            line += len;
+            if line < start_line {
+                continue;
+            }
+
+            let (range, background) = match side {
+                DiffSide::Left => (left, LapceTheme::SOURCE_CONTROL_REMOVED),
+                DiffSide::Right => (right, LapceTheme::SOURCE_CONTROL_ADDED),
+            };
+            let background = match range {
+                None => Some(LapceTheme::PANEL_BACKGROUND),
+                Some(_) if left.is_none() || right.is_none() => Some(background),
+                Some(_) => None,
+            };
+            if let Some(background) = background {
+                ctx.fill(
+                    Size::new(self_size.width, line_height * len as f64)
+                        .to_rect()
+                        .with_origin(Point::new(
+                            0.0,
+                            line_height * first_line as f64 - scroll_offset.y,
+                        )),
+                    data.config.get_color_unchecked(background),
+                );
+            }
+
+            let range = match range {
+                Some(range) => range,
+                None => continue,
+            };
+            for l in first_line.max(start_line)..line.min(end_line + 1) {
+                let actual_line = l - first_line + range.start;
+                let is_current =
+                    side == DiffSide::Right && actual_line == current_line;
+                let content = (actual_line + 1).to_string();
+                let text_layout = ctx
+                    .text()
+                    .new_text_layout(content)
+                    .font(
+                        data.config.editor.font_family(),
+                        data.config.editor.font_size as f64,
+                    )
+                    .text_color(
+                        data.config
+                            .get_color_unchecked(if is_current {
+                                LapceTheme::EDITOR_FOREGROUND
+                            } else {
+                                LapceTheme::EDITOR_DIM
+                            })
+                            .clone(),
+                    )
+                    .build()
+                    .unwrap();
+                // Horizontally right aligned
+                let x = self.width - text_layout.size().width;
+                let y = line_height * l as f64 + text_layout.y_offset(line_height)
+                    - scroll_offset.y;
+                ctx.draw_text(&text_layout, Point::new(x, y));
+            }
+        }
+    }
+
     fn paint_gutter_code_lens(
         &self,
         data: &LapceEditorBufferData,
@@ -586,6 +872,10 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
                 self.paint_gutter_inline_diff(data, ctx, version);
                 return;
             }
+            if let EditorView::Compare(version, side) = &data.editor.view {
+                self.paint_gutter_compare(data, ctx, version, *side);
+                return;
+            }
             if data.editor.is_code_lens() {
                 self.paint_gutter_code_lens(data, ctx);
                 return;
@@ -603,7 +893,10 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
 
             let line_label_length =
                 (last_line + 1).to_string().len() as f64 * char_width;
-            let last_displayed_line = (start_line + num_lines + 1).min(last_line);
+            let folded_lines = data.doc.folded_lines();
+            let last_displayed_line = (start_line + num_lines + 1)
+                .min(data.doc.visual_line(last_line, &data.config));
+            let fold_x = self.fold_x(ctx.text(), data);
 
             let sequential_line_numbers = *data.main_split.active
                 != Some(data.view_id)
@@ -612,18 +905,71 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
 
             let font_family = data.config.editor.font_family();
 
-            for line in start_line..last_displayed_line + 1 {
+            let breakpoints = match data.doc.content() {
+                BufferContent::File(path) => data.debug.breakpoints.get(path),
+                _ => None,
+            };
+
+            for visual_line in start_line..last_displayed_line + 1 {
+                let (line, segment) =
+                    data.doc.line_of_visual_line(visual_line, &data.config);
+                if segment > 0 || data.doc.is_virtual_line(visual_line, &data.config)
+                {
+                    // Soft wrapped lines are only numbered once, and the
+                    // virtual lines of code lenses aren't numbered
+                    continue;
+                }
                 let line_no = if sequential_line_numbers || line == current_line {
                     line + 1
                 } else {
                     // TODO: after Rust 1.60, this can be replaced with `line.abs_diff(current_line)`
-                    if line > current_line {
-                        line - current_line
+                    let current_line =
+                        data.doc.visual_line(current_line, &data.config);
+                    if visual_line > current_line {
+                        visual_line - current_line
                     } else {
-                        current_line - line
+                        current_line - visual_line
                     }
                 };
 
+                if let Some(breakpoint) =
+                    breakpoints.and_then(|breakpoints| breakpoints.get(&line))
+                {
+                    // Unverified breakpoints are dimmed until the debugger
+                    // sets them
+                    let alpha = if breakpoint.verified || data.debug.daps.is_empty()
+                    {
+                        1.0
+                    } else {
+                        0.4
+                    };
+                    let svg = if breakpoint.is_logpoint() {
+                        "debug-breakpoint-log.svg"
+                    } else if breakpoint.is_conditional() {
+                        "debug-breakpoint-conditional.svg"
+                    } else {
+                        "debug-breakpoint.svg"
+                    };
+                    let rect = Size::new(BREAKPOINT_WIDTH, BREAKPOINT_WIDTH)
+                        .to_rect()
+                        .with_origin(Point::new(
+                            self.width + char_width + 4.0,
+                            line_height * visual_line as f64 - scroll_offset.y
+                                + (line_height - BREAKPOINT_WIDTH) / 2.0,
+                        ));
+                    ctx.draw_svg(
+                        &get_svg(svg).unwrap(),
+                        rect,
+                        Some(
+                            &data
+                                .config
+                                .get_color_unchecked(LapceTheme::LAPCE_ERROR)
+                                .clone()
+                                .with_alpha(alpha),
+                        ),
+                    );
+                }
+
                 let content = line_no.to_string();
 
                 let text_layout = ctx
@@ -646,10 +992,32 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
                 let x = line_label_length as f64 - text_layout.size().width;
 
                 // Vertically centered
-                let y = line_height * line as f64 - scroll_offset.y
+                let y = line_height * visual_line as f64 - scroll_offset.y
                     + text_layout.y_offset(line_height);
 
                 ctx.draw_text(&text_layout, Point::new(x, y));
+
+                if data.doc.folding_range_at(line).is_some() {
+                    let svg = if folded_lines.is_folded(line) {
+                        "chevron-right.svg"
+                    } else {
+                        "chevron-down.svg"
+                    };
+                    let rect = Size::new(FOLD_WIDTH, FOLD_WIDTH)
+                        .to_rect()
+                        .with_origin(Point::new(
+                            fold_x,
+                            line_height * visual_line as f64 - scroll_offset.y
+                                + (line_height - FOLD_WIDTH) / 2.0,
+                        ));
+                    ctx.draw_svg(
+                        &get_svg(svg).unwrap(),
+                        rect.inflate(-1.0, -1.0),
+                        Some(
+                            data.config.get_color_unchecked(LapceTheme::EDITOR_DIM),
+                        ),
+                    );
+                }
             }
 
             if let Some(history) = data.doc.get_history("head") {
@@ -666,7 +1034,7 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
                         DiffLines::Right(range) => range.len(),
                     };
                     line += len;
-                    if line < start_line {
+                    if data.doc.visual_line(line, &data.config) < start_line {
                         last_change = Some(change);
                         continue;
                     }
@@ -698,8 +1066,9 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
                     if let Some(color) = color.cloned() {
                         let removed_height = 10.0;
                         let x = self.width + char_width;
-                        let mut y =
-                            (line - len) as f64 * line_height - scroll_offset.y;
+                        let start = data.doc.visual_line(line - len, &data.config);
+                        let len = data.doc.visual_line(line, &data.config) - start;
+                        let mut y = start as f64 * line_height - scroll_offset.y;
                         if len == 0 {
                             y -= removed_height / 2.0;
                         }
@@ -729,7 +1098,7 @@ fn paint_gutter(&self, data: &LapceEditorBufferData, ctx: &mut PaintCtx) {
                         ctx.fill(rect, &color.with_alpha(0.8));
                     }
 
-                    if line > end_line {
+                    if data.doc.visual_line(line, &data.config) > end_line {
                         break;
                     }
                     last_change = Some(change);
@@ -1,9 +1,9 @@
 use std::{cell::RefCell, rc::Rc, sync::Arc};
 
 use druid::{
-    kurbo::Line, BoxConstraints, Command, Env, Event, EventCtx, LayoutCtx,
-    LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point, Rect, RenderContext, Size,
-    Target, UpdateCtx, Widget, WidgetId, WidgetPod,
+    kurbo::Line, piet::TextLayout, BoxConstraints, Command, Env, Event, EventCtx,
+    LayoutCtx, LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point, Rect,
+    RenderContext, Size, Target, UpdateCtx, Widget, WidgetId, WidgetPod,
 };
 use lapce_core::command::FocusCommand;
 use lapce_data::{
@@ -649,6 +649,14 @@ fn paint(
             &self.text_layout,
             Point::new(rect.x1 + 5.0, self.text_layout.y_offset(size.height)),
         );
+        if self.read_only {
+            let x = rect.x1 + 5.0 + self.text_layout.size().width + 5.0;
+            ctx.draw_svg(
+                &get_svg("lock.svg").unwrap(),
+                rect.with_origin(Point::new(x, rect.y0)),
+                Some(data.config.get_color_unchecked(LapceTheme::EDITOR_DIM)),
+            );
+        }
         let x = self.rect.x1;
         ctx.stroke(
             Line::new(
@@ -13,9 +13,10 @@
         CommandKind, LapceCommand, LapceUICommand, LAPCE_COMMAND, LAPCE_UI_COMMAND,
     },
     config::LapceTheme,
-    data::{DragContent, EditorTabChild, LapceTabData},
+    data::{DiffSide, DragContent, EditorTabChild, EditorView, LapceTabData},
     document::BufferContent,
     editor::TabRect,
+    history::{compared_path, merge_side_name},
     proxy::VERSION,
 };
 
@@ -334,11 +335,24 @@ fn lifecycle(
 
     fn update(
         &mut self,
-        _ctx: &mut UpdateCtx,
+        ctx: &mut UpdateCtx,
         _old_data: &LapceTabData,
-        _data: &LapceTabData,
+        data: &LapceTabData,
         _env: &Env,
     ) {
+        // the lock of a read-only editor changes the width of its tab
+        let editor_tab = data.main_split.editor_tabs.get(&self.widget_id).unwrap();
+        let changed = editor_tab.children.iter().zip(self.rects.iter()).any(
+            |(child, rect)| match child {
+                EditorTabChild::Editor(view_id, _, _) => {
+                    data.main_split.is_editor_read_only(*view_id) != rect.read_only
+                }
+                _ => false,
+            },
+        );
+        if changed {
+            ctx.request_layout();
+        }
     }
 
     fn layout(
@@ -357,16 +371,35 @@ fn layout(
         for (_i, child) in editor_tab.children.iter().enumerate() {
             let mut text = "".to_string();
             let mut svg = get_svg("default_file.svg").unwrap();
+            let mut read_only = false;
             match child {
                 EditorTabChild::Editor(view_id, _, _) => {
+                    read_only = data.main_split.is_editor_read_only(*view_id);
                     let editor = data.main_split.editors.get(view_id).unwrap();
                     if let BufferContent::File(path) = &editor.content {
-                        (svg, _) = file_svg(path);
+                        // The left side of a comparison shows the file that
+                        // the document is compared with
+                        let path = match &editor.view {
+                            EditorView::Compare(version, DiffSide::Left) => {
+                                compared_path(version)
+                                    .unwrap_or_else(|| path.to_path_buf())
+                            }
+                            _ => path.to_path_buf(),
+                        };
+                        (svg, _) = file_svg(&path);
                         if let Some(file_name) = path.file_name() {
                             if let Some(s) = file_name.to_str() {
                                 text = s.to_string();
                             }
                         }
+                        // The sides of a merge are all the same file
+                        if let EditorView::Compare(version, DiffSide::Left) =
+                            &editor.view
+                        {
+                            if let Some(side) = merge_side_name(version) {
+                                text = format!("{text} ({side})");
+                            }
+                        }
                     } else if let BufferContent::Scratch(..) = &editor.content {
                         text = editor.content.file_name().to_string();
                     }
@@ -391,9 +424,13 @@ fn layout(
                 .build()
                 .unwrap();
             let text_size = text_layout.size();
-            let width =
-                (text_size.width + height + (height - font_size) / 2.0 + font_size)
-                    .max(data.config.ui.tab_min_width() as f64);
+            let lock_width = if read_only { font_size + 5.0 } else { 0.0 };
+            let width = (text_size.width
+                + lock_width
+                + height
+                + (height - font_size) / 2.0
+                + font_size)
+                .max(data.config.ui.tab_min_width() as f64);
             let close_size = 24.0;
             let inflate = (height - close_size) / 2.0;
             let tab_rect = TabRect {
@@ -406,6 +443,7 @@ fn layout(
                     .with_origin(Point::new(x + width - height, 0.0))
                     .inflate(-inflate, -inflate),
                 text_layout,
+                read_only,
             };
             x += width;
             self.rects.push(tab_rect);
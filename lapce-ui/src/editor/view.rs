@@ -17,7 +17,7 @@
         CommandExecuted, CommandKind, EnsureVisiblePosition, LapceCommand,
         LapceUICommand, LapceWorkbenchCommand, LAPCE_COMMAND, LAPCE_UI_COMMAND,
     },
-    config::{EditorConfig, LapceTheme},
+    config::{AutoSave, EditorConfig, LapceTheme},
     data::{EditorTabChild, EditorView, FocusArea, LapceTabData},
     document::{BufferContent, LocalBufferKind},
     editor::LapceEditorBufferData,
@@ -44,6 +44,9 @@ pub struct LapceEditorView {
     cursor_blink_timer: TimerToken,
     autosave_timer: TimerToken,
     last_idle_timer: TimerToken,
+    /// Fires when the cursor has rested, to highlight the occurrences of the
+    /// symbol under it
+    document_highlight_timer: TimerToken,
     display_border: bool,
     background_color_name: &'static str,
 }
@@ -81,14 +84,20 @@ impl LapceEditorView {
     pub fn new(
         view_id: WidgetId,
         editor_id: WidgetId,
-        find_view_id: Option<(WidgetId, WidgetId)>,
+        find_view_id: Option<(WidgetId, WidgetId, WidgetId)>,
     ) -> LapceEditorView {
         let header = LapceEditorHeader::new(view_id);
         let editor = LapceEditorContainer::new(view_id, editor_id);
-        let find = find_view_id.map(|(find_view_id, find_editor_id)| {
-            WidgetPod::new(FindBox::new(find_view_id, find_editor_id, view_id))
+        let find =
+            find_view_id.map(|(find_view_id, find_editor_id, replace_view_id)| {
+                WidgetPod::new(FindBox::new(
+                    find_view_id,
+                    find_editor_id,
+                    replace_view_id,
+                    view_id,
+                ))
                 .boxed()
-        });
+            });
         Self {
             view_id,
             header: WidgetPod::new(header),
@@ -97,6 +106,7 @@ pub fn new(
             cursor_blink_timer: TimerToken::INVALID,
             autosave_timer: TimerToken::INVALID,
             last_idle_timer: TimerToken::INVALID,
+            document_highlight_timer: TimerToken::INVALID,
             display_border: true,
             background_color_name: LapceTheme::EDITOR_BACKGROUND,
         }
@@ -179,14 +189,27 @@ pub fn request_focus(
                 LocalBufferKind::FilePicker => {
                     data.focus_area = FocusArea::FilePicker;
                 }
-                LocalBufferKind::Search => {
+                LocalBufferKind::Search | LocalBufferKind::SearchReplace => {
                     data.focus_area = FocusArea::Panel(PanelKind::Search);
+                    Arc::make_mut(&mut data.search).active = self.view_id;
                 }
                 LocalBufferKind::SourceControl => {
                     data.focus_area = FocusArea::Panel(PanelKind::SourceControl);
                     Arc::make_mut(&mut data.source_control).active = self.view_id;
                 }
                 LocalBufferKind::PathName => {}
+                LocalBufferKind::ProblemFilter => {
+                    data.focus_area = FocusArea::Panel(PanelKind::Problem);
+                }
+                LocalBufferKind::DebugConsole => {
+                    data.focus_area = FocusArea::Panel(PanelKind::DebugConsole);
+                }
+                LocalBufferKind::TerminalFind => {
+                    data.focus_area = FocusArea::Panel(PanelKind::Terminal);
+                }
+                LocalBufferKind::FindReplace => {
+                    data.focus_area = FocusArea::Editor;
+                }
                 LocalBufferKind::Rename => {
                     data.focus_area = FocusArea::Rename;
                 }
@@ -212,9 +235,6 @@ pub fn handle_lapce_ui_command(
             LapceUICommand::RunCodeAction(action) => {
                 data.run_code_action(ctx, action);
             }
-            LapceUICommand::ApplyWorkspaceEdit(edit) => {
-                data.apply_workspace_edit(ctx, edit);
-            }
             LapceUICommand::EnsureCursorVisible(position) => {
                 self.ensure_cursor_visible(ctx, data, panel, position.as_ref(), env);
             }
@@ -224,6 +244,12 @@ pub fn handle_lapce_ui_command(
             LapceUICommand::EnsureRectVisible(rect) => {
                 self.ensure_rect_visible(ctx, data, *rect, env);
             }
+            LapceUICommand::ToggleFold(line) => {
+                data.toggle_fold(*line);
+            }
+            LapceUICommand::JumpToUndoGroup(group) => {
+                data.jump_to_undo_group(*group);
+            }
             LapceUICommand::ResolveCompletion(buffer_id, rev, offset, item) => {
                 if data.doc.id() != *buffer_id {
                     return;
@@ -469,7 +495,7 @@ fn cursor_region(data: &LapceEditorBufferData, text: &mut PietText) -> Rect {
         let col = inlay_hints.col_at(col);
 
         let width = data.config.editor_char_width(text);
-        let cursor_x = data
+        let mut cursor_x = data
             .doc
             .line_point_of_line_col(
                 text,
@@ -517,13 +543,19 @@ fn cursor_region(data: &LapceEditorBufferData, text: &mut PietText) -> Rect {
                 }
             }
             y
+        } else if let EditorView::Diff(version) | EditorView::Compare(version, _) =
+            &data.editor.view
+        {
+            data.doc.history_visual_line(version, line) as f64 * line_height
         } else {
-            let line = if let EditorView::Diff(version) = &data.editor.view {
-                data.doc.history_visual_line(version, line)
-            } else {
-                line
-            };
-            line as f64 * line_height
+            let mut y =
+                data.doc.visual_line(line, &data.config) as f64 * line_height;
+            if let Some(wrapped) = data.doc.wrapped_line(text, line, &data.config) {
+                let segment = wrapped.segment_of_x(cursor_x);
+                cursor_x += wrapped.x_shift(segment);
+                y += segment as f64 * line_height;
+            }
+            y
         };
 
         Rect::ZERO
@@ -638,37 +670,27 @@ fn event(
                     }
                 }
             }
+            Event::Timer(id) if self.document_highlight_timer == *id => {
+                ctx.set_handled();
+                self.document_highlight_timer = TimerToken::INVALID;
+                if *data.focus == self.view_id {
+                    let editor_data = data.editor_view_content(self.view_id);
+                    editor_data
+                        .doc
+                        .get_document_highlights(editor_data.editor.cursor.offset());
+                }
+            }
             Event::Timer(id) if self.autosave_timer == *id => {
                 ctx.set_handled();
-                if let Some(editor) = data
-                    .main_split
-                    .active
-                    .and_then(|active| data.main_split.editors.get(&active))
-                    .cloned()
-                {
-                    if data.config.editor.autosave_interval > 0 {
-                        if ctx.is_focused() {
-                            let doc = data.main_split.editor_doc(self.view_id);
-                            if !doc.buffer().is_pristine() {
-                                ctx.submit_command(Command::new(
-                                    LAPCE_COMMAND,
-                                    LapceCommand {
-                                        kind: CommandKind::Focus(FocusCommand::Save),
-                                        data: None,
-                                    },
-                                    Target::Widget(editor.view_id),
-                                ));
-                            }
-                            self.autosave_timer = ctx.request_timer(
-                                Duration::from_millis(
-                                    data.config.editor.autosave_interval,
-                                ),
-                                None,
-                            );
-                        } else {
-                            self.cursor_blink_timer = TimerToken::INVALID;
-                        }
-                    }
+                self.autosave_timer = TimerToken::INVALID;
+                let doc = data.main_split.editor_doc(self.view_id);
+                if let BufferContent::File(path) = doc.content() {
+                    data.main_split.autosave(
+                        ctx,
+                        &data.config,
+                        AutoSave::AfterDelay,
+                        Some(path),
+                    );
                 }
             }
             _ => {}
@@ -744,6 +766,17 @@ fn event(
         if editor.scroll_offset != offset {
             Arc::make_mut(data.main_split.editors.get_mut(&self.view_id).unwrap())
                 .scroll_offset = offset;
+
+            // The sides of a comparison are aligned, so they scroll together
+            if let Some(sibling) = data.main_split.compare_sibling(self.view_id) {
+                if sibling.scroll_offset != offset {
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::ForceScrollTo(offset.x, offset.y),
+                        Target::Widget(sibling.view_id),
+                    ));
+                }
+            }
         }
     }
 
@@ -871,6 +904,17 @@ fn update(
             }
         }
 
+        if *data.focus == self.view_id
+            && editor_data.editor.content.is_file()
+            && (editor_data.editor.cursor.offset()
+                != old_editor_data.editor.cursor.offset()
+                || editor_data.doc.rev() != old_editor_data.doc.rev()
+                || *old_data.focus != self.view_id)
+        {
+            self.document_highlight_timer =
+                ctx.request_timer(Duration::from_millis(300), None);
+        }
+
         if data.config.editor.blink_interval > 0 && *data.focus == self.view_id {
             let reset = if *old_data.focus != self.view_id {
                 true
@@ -897,15 +941,28 @@ fn update(
             }
         }
 
-        if data.config.editor.autosave_interval > 0
+        if data.config.editor.autosave() == AutoSave::AfterDelay
             && editor_data.doc.rev() != old_editor_data.doc.rev()
         {
             self.autosave_timer = ctx.request_timer(
-                Duration::from_millis(data.config.editor.autosave_interval),
+                Duration::from_millis(data.config.editor.autosave_delay()),
                 None,
             );
         }
 
+        if *old_data.focus == self.view_id && *data.focus != self.view_id {
+            if let BufferContent::File(path) = editor_data.doc.content() {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::AutoSave(
+                        AutoSave::OnFocusChange,
+                        Some(path.clone()),
+                    ),
+                    Target::Widget(data.id),
+                ));
+            }
+        }
+
         if old_data.config.lapce.modal != data.config.lapce.modal
             && !editor_data.doc.content().is_input()
         {
@@ -960,7 +1017,9 @@ fn update(
         if editor_data.editor.view != old_editor_data.editor.view {
             ctx.request_layout();
         }
-        if let EditorView::Diff(version) = &editor_data.editor.view {
+        if let EditorView::Diff(version) | EditorView::Compare(version, _) =
+            &editor_data.editor.view
+        {
             let old_history = old_editor_data.doc.get_history(version);
             let history = editor_data.doc.get_history(version);
             match (history, old_history) {
@@ -988,19 +1047,27 @@ fn update(
         let old_doc = &old_editor_data.doc;
         if doc.buffer().max_len() != old_doc.buffer().max_len()
             || doc.buffer().num_lines() != old_doc.buffer().num_lines()
+            || doc.folded_lines() != old_doc.folded_lines()
+            || (doc.rev() != old_doc.rev() && doc.is_wrapped(&editor_data.config))
+            || doc.code_lenses_changed(old_doc)
         {
             ctx.request_layout();
         }
 
-        match (doc.styles(), old_doc.styles()) {
-            (None, None) => {}
-            (None, Some(_)) | (Some(_), None) => {
-                ctx.request_paint();
-            }
-            (Some(new), Some(old)) => {
-                if !new.same(old) {
+        for (new, old) in [
+            (doc.syntax_styles(), old_doc.syntax_styles()),
+            (doc.semantic_styles(), old_doc.semantic_styles()),
+        ] {
+            match (new, old) {
+                (None, None) => {}
+                (None, Some(_)) | (Some(_), None) => {
                     ctx.request_paint();
                 }
+                (Some(new), Some(old)) => {
+                    if !new.same(old) {
+                        ctx.request_paint();
+                    }
+                }
             }
         }
 
@@ -1013,6 +1080,10 @@ fn update(
         {
             ctx.request_paint();
         }
+
+        if !Arc::ptr_eq(&old_editor_data.debug, &editor_data.debug) {
+            ctx.request_paint();
+        }
         self.editor.update(ctx, data, env);
     }
 
@@ -1,24 +1,30 @@
 use druid::{
-    piet::{Text, TextLayoutBuilder},
-    BoxConstraints, Command, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    piet::{Text, TextAttribute, TextLayout, TextLayoutBuilder},
+    BoxConstraints, Command, Env, Event, EventCtx, FontWeight, LayoutCtx, LifeCycle,
     LifeCycleCtx, MouseEvent, PaintCtx, Point, Rect, RenderContext, Size, Target,
     UpdateCtx, Widget, WidgetExt, WidgetId, WidgetPod,
 };
 use lapce_core::command::FocusCommand;
 use lapce_data::{
-    command::{CommandKind, LapceCommand, LAPCE_COMMAND},
+    command::{CommandKind, LapceCommand, LapceWorkbenchCommand, LAPCE_COMMAND},
     config::LapceTheme,
     data::LapceTabData,
 };
 
 use crate::{editor::view::LapceEditorView, svg::get_svg, tab::LapceIcon};
 
+/// The find bar of an editor, with the replace input below the find input
+/// when replacing
 pub struct FindBox {
     parent_view_id: WidgetId,
     input_width: f64,
     result_width: f64,
     input: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
+    replace: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     icons: Vec<LapceIcon>,
+    /// The preserve case toggle and the replace buttons right of the
+    /// replace input, which are placed when they are painted
+    replace_icons: Vec<(Rect, Command)>,
     mouse_pos: Point,
 }
 
@@ -26,12 +32,18 @@ impl FindBox {
     pub fn new(
         view_id: WidgetId,
         editor_id: WidgetId,
+        replace_view_id: WidgetId,
         parent_view_id: WidgetId,
     ) -> Self {
         let input = LapceEditorView::new(view_id, editor_id, None)
             .hide_header()
             .hide_gutter()
             .padding((10.0, 5.0));
+        let replace = LapceEditorView::new(replace_view_id, WidgetId::next(), None)
+            .hide_header()
+            .hide_gutter()
+            .set_placeholder("Replace".to_string())
+            .padding((10.0, 5.0));
         let icons = vec![
             LapceIcon {
                 icon: "arrow-up.svg",
@@ -75,7 +87,9 @@ pub fn new(
             input_width: 200.0,
             result_width: 75.0,
             input: WidgetPod::new(input.boxed()),
+            replace: WidgetPod::new(replace.boxed()),
             icons,
+            replace_icons: Vec::new(),
             mouse_pos: Point::ZERO,
         }
     }
@@ -86,15 +100,96 @@ fn mouse_down(&self, ctx: &mut EventCtx, mouse_event: &MouseEvent) {
                 ctx.submit_command(icon.command.clone());
             }
         }
+        for (rect, command) in self.replace_icons.iter() {
+            if rect.contains(mouse_event.pos) {
+                ctx.submit_command(command.clone());
+            }
+        }
     }
 
     fn icon_hit_test(&self, mouse_event: &MouseEvent) -> bool {
-        for icon in self.icons.iter() {
-            if icon.rect.contains(mouse_event.pos) {
-                return true;
+        self.icons
+            .iter()
+            .any(|icon| icon.rect.contains(mouse_event.pos))
+            || self
+                .replace_icons
+                .iter()
+                .any(|(rect, _)| rect.contains(mouse_event.pos))
+    }
+
+    /// Paint the preserve case toggle and the buttons replacing the next
+    /// occurrence and all of them, right of the replace input
+    fn paint_replace_icons(&mut self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        self.replace_icons.clear();
+        let replace_rect = self.replace.layout_rect();
+        let height = replace_rect.height();
+        let padding = 5.0;
+        let mut x = replace_rect.x1 + padding;
+
+        let buttons = [
+            (
+                "AB",
+                data.find.preserve_case,
+                CommandKind::Workbench(
+                    LapceWorkbenchCommand::ToggleReplacePreserveCase,
+                ),
+                Target::Widget(data.id),
+            ),
+            (
+                "Replace",
+                false,
+                CommandKind::Focus(FocusCommand::ReplaceNext),
+                Target::Widget(self.parent_view_id),
+            ),
+            (
+                "All",
+                false,
+                CommandKind::Focus(FocusCommand::ReplaceAll),
+                Target::Widget(self.parent_view_id),
+            ),
+        ];
+        for (text, active, kind, target) in buttons {
+            let color = if active {
+                LapceTheme::EDITOR_FOCUS
+            } else {
+                LapceTheme::EDITOR_FOREGROUND
+            };
+            let text_layout = ctx
+                .text()
+                .new_text_layout(text)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(data.config.get_color_unchecked(color).clone())
+                .default_attribute(TextAttribute::Weight(FontWeight::BOLD))
+                .build()
+                .unwrap();
+            let rect = Size::new(text_layout.size().width, height)
+                .to_rect()
+                .with_origin(Point::new(x, replace_rect.y0))
+                .inflate(padding, 0.0);
+            if rect.contains(self.mouse_pos) {
+                ctx.fill(
+                    rect,
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_CURRENT_LINE),
+                );
             }
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, replace_rect.y0 + text_layout.y_offset(height)),
+            );
+            x = rect.x1 + padding;
+            self.replace_icons.push((
+                rect,
+                Command::new(
+                    LAPCE_COMMAND,
+                    LapceCommand { kind, data: None },
+                    target,
+                ),
+            ));
         }
-        false
     }
 }
 
@@ -107,6 +202,7 @@ fn event(
         env: &Env,
     ) {
         self.input.event(ctx, event, data, env);
+        self.replace.event(ctx, event, data, env);
         match event {
             Event::MouseMove(mouse_event) => {
                 ctx.set_handled();
@@ -158,6 +254,17 @@ fn layout(
                 .inflate(-5.0, -5.0);
         }
 
+        // the replace input is outside of the find bar while it's hidden, so
+        // that it doesn't take the clicks on the editor
+        let replace_bc =
+            BoxConstraints::tight(Size::new(input_size.width, bc.max().height));
+        let replace_size = self.replace.layout(ctx, &replace_bc, data, env);
+        self.replace
+            .set_origin(ctx, data, env, Point::new(0.0, height));
+        if data.find.replace_visual {
+            return Size::new(width, height + replace_size.height);
+        }
+
         Size::new(width, height)
     }
 
@@ -169,16 +276,23 @@ fn lifecycle(
         env: &Env,
     ) {
         self.input.lifecycle(ctx, event, data, env);
+        self.replace.lifecycle(ctx, event, data, env);
     }
 
     fn update(
         &mut self,
         ctx: &mut UpdateCtx,
-        _old_data: &LapceTabData,
+        old_data: &LapceTabData,
         data: &LapceTabData,
         env: &Env,
     ) {
         self.input.update(ctx, data, env);
+        self.replace.update(ctx, data, env);
+        if old_data.find.replace_visual != data.find.replace_visual {
+            ctx.request_layout();
+        } else if old_data.find.preserve_case != data.find.preserve_case {
+            ctx.request_paint();
+        }
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
@@ -213,6 +327,12 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
                 .get_color_unchecked(LapceTheme::EDITOR_BACKGROUND),
         );
         self.input.paint(ctx, data, env);
+        if data.find.replace_visual {
+            self.replace.paint(ctx, data, env);
+            self.paint_replace_icons(ctx, data);
+        } else {
+            self.replace_icons.clear();
+        }
 
         let mut index = None;
         let cursor_offset = buffer.editor.cursor.offset();
@@ -0,0 +1,460 @@
+use std::sync::Arc;
+
+use druid::{
+    piet::{Text, TextLayout as PietTextLayout, TextLayoutBuilder},
+    BoxConstraints, Command, Cursor, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, MouseEvent, PaintCtx, Point, Rect, RenderContext, Size, Target,
+    UpdateCtx, Widget, WidgetExt,
+};
+use lapce_data::{
+    call_hierarchy::{CallHierarchyData, CallHierarchyDirection, HierarchyTree},
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    config::LapceTheme,
+    data::LapceTabData,
+    editor::EditorLocation,
+    panel::PanelKind,
+    type_hierarchy::{TypeHierarchyData, TypeHierarchyDirection},
+};
+
+use crate::{
+    panel::{LapcePanel, PanelHeaderKind, PanelSizing},
+    svg::{get_svg, symbol_svg},
+};
+
+pub fn new_call_hierarchy_panel(data: &CallHierarchyData) -> LapcePanel {
+    LapcePanel::new(
+        PanelKind::CallHierarchy,
+        data.widget_id,
+        data.split_id,
+        vec![(
+            data.tree_widget_id,
+            PanelHeaderKind::None,
+            HierarchyContent::new(PanelKind::CallHierarchy).boxed(),
+            PanelSizing::Flex(false),
+        )],
+    )
+}
+
+pub fn new_type_hierarchy_panel(data: &TypeHierarchyData) -> LapcePanel {
+    LapcePanel::new(
+        PanelKind::TypeHierarchy,
+        data.widget_id,
+        data.split_id,
+        vec![(
+            data.tree_widget_id,
+            PanelHeaderKind::None,
+            HierarchyContent::new(PanelKind::TypeHierarchy).boxed(),
+            PanelSizing::Flex(false),
+        )],
+    )
+}
+
+/// The tree of the call or type hierarchy panel, below a row choosing between
+/// its two directions. Clicking the chevron of an item opens its children,
+/// and double clicking it goes to the item.
+struct HierarchyContent {
+    kind: PanelKind,
+    mouse_pos: Point,
+    content_height: f64,
+    direction_rects: [Rect; 2],
+}
+
+impl HierarchyContent {
+    fn new(kind: PanelKind) -> Self {
+        Self {
+            kind,
+            mouse_pos: Point::ZERO,
+            content_height: 0.0,
+            direction_rects: [Rect::ZERO; 2],
+        }
+    }
+
+    fn tree<'a>(&self, data: &'a LapceTabData) -> &'a HierarchyTree {
+        match self.kind {
+            PanelKind::TypeHierarchy => &data.type_hierarchy.tree,
+            _ => &data.call_hierarchy.tree,
+        }
+    }
+
+    /// The labels of the two directions, and whether they are the current one
+    fn directions(&self, data: &LapceTabData) -> [(&'static str, bool); 2] {
+        match self.kind {
+            PanelKind::TypeHierarchy => {
+                let direction = data.type_hierarchy.direction;
+                [
+                    (
+                        "Supertypes",
+                        direction == TypeHierarchyDirection::Supertypes,
+                    ),
+                    ("Subtypes", direction == TypeHierarchyDirection::Subtypes),
+                ]
+            }
+            _ => {
+                let direction = data.call_hierarchy.direction;
+                [
+                    (
+                        "Incoming Calls",
+                        direction == CallHierarchyDirection::Incoming,
+                    ),
+                    (
+                        "Outgoing Calls",
+                        direction == CallHierarchyDirection::Outgoing,
+                    ),
+                ]
+            }
+        }
+    }
+
+    /// Switch to the first or second direction, and ask for the children of
+    /// the root again
+    fn set_direction(
+        &self,
+        ctx: &mut EventCtx,
+        data: &mut LapceTabData,
+        second: bool,
+    ) {
+        let event_sink = ctx.get_external_handle();
+        match self.kind {
+            PanelKind::TypeHierarchy => {
+                let type_hierarchy = Arc::make_mut(&mut data.type_hierarchy);
+                type_hierarchy.set_direction(if second {
+                    TypeHierarchyDirection::Subtypes
+                } else {
+                    TypeHierarchyDirection::Supertypes
+                });
+                if let Some(root) = type_hierarchy.tree.root.as_ref() {
+                    type_hierarchy.request_types(
+                        &data.proxy,
+                        Vec::new(),
+                        root.item.clone(),
+                        event_sink,
+                        data.id,
+                    );
+                }
+            }
+            _ => {
+                let call_hierarchy = Arc::make_mut(&mut data.call_hierarchy);
+                call_hierarchy.set_direction(if second {
+                    CallHierarchyDirection::Outgoing
+                } else {
+                    CallHierarchyDirection::Incoming
+                });
+                if let Some(root) = call_hierarchy.tree.root.as_ref() {
+                    call_hierarchy.request_calls(
+                        &data.proxy,
+                        Vec::new(),
+                        root.item.clone(),
+                        event_sink,
+                        data.id,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Open or close the node, asking for its children the first time
+    fn toggle(
+        &self,
+        ctx: &mut EventCtx,
+        data: &mut LapceTabData,
+        indices: &[usize],
+    ) {
+        let event_sink = ctx.get_external_handle();
+        match self.kind {
+            PanelKind::TypeHierarchy => {
+                let type_hierarchy = Arc::make_mut(&mut data.type_hierarchy);
+                if let Some(item) = type_hierarchy.tree.toggle(indices) {
+                    type_hierarchy.request_types(
+                        &data.proxy,
+                        indices.to_vec(),
+                        item,
+                        event_sink,
+                        data.id,
+                    );
+                }
+            }
+            _ => {
+                let call_hierarchy = Arc::make_mut(&mut data.call_hierarchy);
+                if let Some(item) = call_hierarchy.tree.toggle(indices) {
+                    call_hierarchy.request_calls(
+                        &data.proxy,
+                        indices.to_vec(),
+                        item,
+                        event_sink,
+                        data.id,
+                    );
+                }
+            }
+        }
+    }
+
+    fn mouse_down(
+        &self,
+        ctx: &mut EventCtx,
+        mouse_event: &MouseEvent,
+        data: &mut LapceTabData,
+    ) {
+        let line_height = data.config.editor.line_height() as f64;
+        let n = (mouse_event.pos.y / line_height).floor() as usize;
+        if n == 0 {
+            if self.direction_rects[0].contains(mouse_event.pos) {
+                self.set_direction(ctx, data, false);
+            } else if self.direction_rects[1].contains(mouse_event.pos) {
+                self.set_direction(ctx, data, true);
+            }
+            return;
+        }
+
+        let rows = self.tree(data).rows();
+        let row = match rows.get(n - 1) {
+            Some(row) => row,
+            None => return,
+        };
+        if mouse_event.count == 2 {
+            if let Some(node) = self.tree(data).node(&row.indices) {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::JumpToLocation(
+                        None,
+                        EditorLocation {
+                            path: node.path.clone(),
+                            position: Some(node.position),
+                            scroll_offset: None,
+                            history: None,
+                        },
+                        false,
+                    ),
+                    Target::Widget(data.id),
+                ));
+            }
+            return;
+        }
+
+        let chevron_end = line_height * (row.depth as f64 + 1.0);
+        if mouse_event.pos.x < chevron_end {
+            self.toggle(ctx, data, &row.indices);
+        }
+    }
+
+    fn paint_directions(&mut self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        let line_height = data.config.editor.line_height() as f64;
+        let padding = 10.0;
+        let mut x = padding;
+        for (i, (label, active)) in self.directions(data).into_iter().enumerate() {
+            let color = if active {
+                LapceTheme::EDITOR_FOCUS
+            } else {
+                LapceTheme::EDITOR_DIM
+            };
+            let text_layout = ctx
+                .text()
+                .new_text_layout(label)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(data.config.get_color_unchecked(color).clone())
+                .build()
+                .unwrap();
+            let rect = Size::new(text_layout.size().width, line_height)
+                .to_rect()
+                .with_origin(Point::new(x, 0.0))
+                .inflate(padding / 2.0, 0.0);
+            self.direction_rects[i] = rect;
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, text_layout.y_offset(line_height)),
+            );
+            x += text_layout.size().width + padding * 2.0;
+        }
+    }
+}
+
+impl Widget<LapceTabData> for HierarchyContent {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(mouse_event) => {
+                self.mouse_pos = mouse_event.pos;
+
+                if mouse_event.pos.y < self.content_height {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+
+                ctx.request_paint();
+            }
+            Event::MouseDown(mouse_event) => {
+                self.mouse_down(ctx, mouse_event, data);
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        let changed = match self.kind {
+            PanelKind::TypeHierarchy => {
+                !Arc::ptr_eq(&old_data.type_hierarchy, &data.type_hierarchy)
+            }
+            _ => !Arc::ptr_eq(&old_data.call_hierarchy, &data.call_hierarchy),
+        };
+        if changed {
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let line_height = data.config.editor.line_height() as f64;
+        self.content_height =
+            line_height * (self.tree(data).rows().len() + 1) as f64;
+
+        Size::new(bc.max().width, self.content_height.max(bc.max().height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        self.paint_directions(ctx, data);
+
+        let line_height = data.config.editor.line_height() as f64;
+        let size = ctx.size();
+        let mouse_line = (self.mouse_pos.y / line_height).floor() as usize;
+
+        let rect = ctx.region().bounding_box();
+        let min = (rect.y0 / line_height).floor() as usize;
+        let max = (rect.y1 / line_height) as usize + 2;
+
+        let ui_font_family = data.config.ui.font_family();
+        let ui_font_size = data.config.ui.font_size() as f64;
+        let svg_size = 15.0;
+
+        let tree = self.tree(data);
+        let rows = tree.rows();
+        for (i, row) in rows.iter().enumerate() {
+            let line = i + 1;
+            if line < min || line > max {
+                continue;
+            }
+            let node = match tree.node(&row.indices) {
+                Some(node) => node,
+                None => continue,
+            };
+            let y = line_height * line as f64;
+
+            if ctx.is_hot() && line == mouse_line {
+                ctx.fill(
+                    Size::new(size.width, line_height)
+                        .to_rect()
+                        .with_origin(Point::new(0.0, y)),
+                    data.config.get_color_unchecked(LapceTheme::PANEL_HOVERED),
+                );
+            }
+
+            let x = line_height * row.depth as f64;
+            let svg_y = y + (line_height - svg_size) / 2.0;
+            // the children that weren't asked for yet may be empty
+            let has_calls = node
+                .children
+                .as_ref()
+                .map(|children| !children.is_empty())
+                .unwrap_or(true);
+            if has_calls {
+                let icon_name = if node.open {
+                    "chevron-down.svg"
+                } else {
+                    "chevron-right.svg"
+                };
+                let rect = Size::new(svg_size, svg_size)
+                    .to_rect()
+                    .with_origin(Point::new(x + 2.0, svg_y));
+                ctx.draw_svg(
+                    &get_svg(icon_name).unwrap(),
+                    rect,
+                    Some(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                    ),
+                );
+            }
+
+            let x = x + line_height;
+            if let Some(svg) = symbol_svg(&node.item.kind) {
+                let rect = Size::new(svg_size, svg_size)
+                    .to_rect()
+                    .with_origin(Point::new(x, svg_y));
+                ctx.draw_svg(&svg, rect, None);
+            }
+
+            let x = x + svg_size + 5.0;
+            let text_layout = ctx
+                .text()
+                .new_text_layout(node.item.name.clone())
+                .font(ui_font_family.clone(), ui_font_size)
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, y + text_layout.y_offset(line_height)),
+            );
+
+            let x = x + text_layout.size().width + 10.0;
+            let file_name = node
+                .path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("");
+            let location =
+                format!("{file_name}:{}", node.position.line as usize + 1);
+            let detail = match node.item.detail.as_ref() {
+                Some(detail) => format!("{detail} {location}"),
+                None => location,
+            };
+            let text_layout = ctx
+                .text()
+                .new_text_layout(detail)
+                .font(ui_font_family.clone(), ui_font_size)
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, y + text_layout.y_offset(line_height)),
+            );
+        }
+    }
+}
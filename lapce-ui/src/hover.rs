@@ -1,9 +1,10 @@
 use std::sync::Arc;
 
 use druid::{
-    kurbo::Line, theme, ArcStr, BoxConstraints, Command, Data, Env, Event, EventCtx,
-    FontDescriptor, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
-    RenderContext, Size, Target, TextLayout, UpdateCtx, Widget, WidgetId, WidgetPod,
+    kurbo::Line, text::Link, theme, ArcStr, BoxConstraints, Command, Cursor, Data,
+    Env, Event, EventCtx, FontDescriptor, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, RenderContext, Size, Target, TextLayout, UpdateCtx, Widget,
+    WidgetId, WidgetPod,
 };
 use lapce_data::{
     command::{LapceUICommand, LAPCE_UI_COMMAND},
@@ -206,17 +207,58 @@ fn new() -> Self {
             },
         }
     }
+
+    fn diagnostic_height(&self) -> f64 {
+        if self.active_diagnostic_layout.size().is_empty() {
+            0.0
+        } else {
+            self.active_diagnostic_layout.layout_metrics().size.height
+                + Hover::STARTING_Y * 3.0
+        }
+    }
+
+    /// The link of the hover items under the position
+    fn link_at(&self, pos: Point, line_height: f64) -> Option<&Link> {
+        let mut y = Hover::STARTING_Y + self.diagnostic_height();
+        for layout in self.active_layout.iter() {
+            let origin = Point::new(Hover::STARTING_X, y);
+            if let Some(link) = layout.link_for_pos(pos - origin.to_vec2()) {
+                return Some(link);
+            }
+            y += layout.layout_metrics().size.height + line_height;
+        }
+        None
+    }
 }
 impl Widget<LapceTabData> for Hover {
     fn event(
         &mut self,
         ctx: &mut EventCtx,
         event: &Event,
-        _data: &mut LapceTabData,
+        data: &mut LapceTabData,
         _env: &Env,
     ) {
-        if let Event::MouseMove(_) = event {
-            ctx.set_handled();
+        let line_height = data.config.editor.line_height() as f64;
+        match event {
+            Event::MouseMove(mouse_event) => {
+                if self.link_at(mouse_event.pos, line_height).is_some() {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+                ctx.set_handled();
+            }
+            Event::MouseDown(mouse_event) => {
+                // Clicking a link opens it, and clicking anywhere else in the
+                // hover pins it, so that it can be scrolled through
+                if let Some(link) = self.link_at(mouse_event.pos, line_height) {
+                    ctx.submit_command(link.command.clone());
+                } else {
+                    Arc::make_mut(&mut data.hover).pinned = true;
+                }
+                ctx.set_handled();
+            }
+            _ => {}
         }
     }
 
@@ -322,14 +364,7 @@ fn layout(
             height
         };
 
-        let diagnostic_height = if self.active_diagnostic_layout.size().is_empty() {
-            0.0
-        } else {
-            let diagnostic_text_metrics =
-                self.active_diagnostic_layout.layout_metrics();
-
-            diagnostic_text_metrics.size.height + Hover::STARTING_Y * 3.0
-        };
+        let diagnostic_height = self.diagnostic_height();
 
         Size::new(
             width,
@@ -3,21 +3,29 @@
 pub mod app;
 pub mod button;
 pub mod completion;
+pub mod debug;
+pub mod debug_console;
 pub mod editor;
 pub mod explorer;
 pub mod find;
+pub mod hierarchy;
 pub mod hover;
 pub mod keymap;
 pub mod list;
 mod logging;
+pub mod lsp_log;
 pub mod palette;
 pub mod panel;
+pub mod peek;
 pub mod picker;
 pub mod plugin;
 pub mod problem;
+pub mod release_notes;
+pub mod rename_preview;
 pub mod scroll;
 pub mod search;
 pub mod settings;
+pub mod signature;
 pub mod source_control;
 pub mod split;
 pub mod status;
@@ -25,4 +33,5 @@
 mod tab;
 pub mod terminal;
 pub mod title;
+pub mod undo_tree;
 pub mod window;
@@ -0,0 +1,281 @@
+use std::sync::Arc;
+
+use druid::{
+    piet::{Text, TextLayout as PietTextLayout, TextLayoutBuilder},
+    BoxConstraints, Command, Cursor, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, MouseEvent, PaintCtx, Point, Rect, RenderContext, Size, Target,
+    UpdateCtx, Widget, WidgetExt,
+};
+use lapce_data::{
+    command::{CommandKind, LapceCommand, LapceWorkbenchCommand, LAPCE_COMMAND},
+    config::LapceTheme,
+    data::LapceTabData,
+    lsp_log::LspLogData,
+    panel::PanelKind,
+};
+
+use crate::panel::{LapcePanel, PanelHeaderKind, PanelSizing};
+
+pub fn new_lsp_log_panel(data: &LspLogData) -> LapcePanel {
+    LapcePanel::new(
+        PanelKind::LspLog,
+        data.widget_id,
+        data.split_id,
+        vec![(
+            data.content_id,
+            PanelHeaderKind::None,
+            LspLogContent::new().boxed(),
+            PanelSizing::Flex(false),
+        )],
+    )
+}
+
+/// The log of the active language server, below a row to switch between
+/// the servers, turn tracing on or off and clear the log
+struct LspLogContent {
+    mouse_pos: Point,
+    server_rects: Vec<Rect>,
+    button_rects: [Rect; 2],
+}
+
+impl LspLogContent {
+    fn new() -> Self {
+        Self {
+            mouse_pos: Point::ZERO,
+            server_rects: Vec::new(),
+            button_rects: [Rect::ZERO; 2],
+        }
+    }
+
+    fn line_height(data: &LapceTabData) -> f64 {
+        data.config.editor.line_height() as f64
+    }
+
+    fn is_clickable(&self, pos: Point) -> bool {
+        self.server_rects
+            .iter()
+            .chain(self.button_rects.iter())
+            .any(|rect| rect.contains(pos))
+    }
+
+    fn mouse_down(
+        &self,
+        ctx: &mut EventCtx,
+        mouse_event: &MouseEvent,
+        data: &mut LapceTabData,
+    ) {
+        if let Some(i) = self
+            .server_rects
+            .iter()
+            .position(|rect| rect.contains(mouse_event.pos))
+        {
+            Arc::make_mut(&mut data.lsp_log).active = i;
+            return;
+        }
+
+        let cmd = if self.button_rects[0].contains(mouse_event.pos) {
+            LapceWorkbenchCommand::ToggleLspTrace
+        } else if self.button_rects[1].contains(mouse_event.pos) {
+            LapceWorkbenchCommand::ClearLspLog
+        } else {
+            return;
+        };
+        ctx.submit_command(Command::new(
+            LAPCE_COMMAND,
+            LapceCommand {
+                kind: CommandKind::Workbench(cmd),
+                data: None,
+            },
+            Target::Widget(data.id),
+        ));
+    }
+
+    fn paint_header(&mut self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        let line_height = Self::line_height(data);
+        let padding = 10.0;
+        let log = &data.lsp_log;
+
+        let mut x = padding;
+        self.server_rects.clear();
+        let servers = log.servers.iter().map(|server| server.name.clone());
+        let labels = if log.servers.is_empty() {
+            vec![("No language server output".to_string(), false)]
+        } else {
+            servers
+                .enumerate()
+                .map(|(i, name)| (name, i == log.active))
+                .collect()
+        };
+        for (label, active) in labels.iter() {
+            let text_layout = ctx
+                .text()
+                .new_text_layout(label.clone())
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(if *active {
+                            LapceTheme::EDITOR_FOREGROUND
+                        } else {
+                            LapceTheme::EDITOR_DIM
+                        })
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let rect = Size::new(text_layout.size().width, line_height)
+                .to_rect()
+                .with_origin(Point::new(x, 0.0))
+                .inflate(padding / 2.0, 0.0);
+            if *active {
+                ctx.fill(
+                    rect,
+                    data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+                );
+            }
+            if !log.servers.is_empty() {
+                self.server_rects.push(rect);
+            }
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, text_layout.y_offset(line_height)),
+            );
+            x += text_layout.size().width + padding * 2.0;
+        }
+
+        let trace = if log.trace { "Trace: On" } else { "Trace: Off" };
+        for (i, label) in [trace, "Clear"].into_iter().enumerate() {
+            let text_layout = ctx
+                .text()
+                .new_text_layout(label)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOCUS)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let rect = Size::new(text_layout.size().width, line_height)
+                .to_rect()
+                .with_origin(Point::new(x, 0.0))
+                .inflate(padding / 2.0, 0.0);
+            self.button_rects[i] = rect;
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, text_layout.y_offset(line_height)),
+            );
+            x += text_layout.size().width + padding * 2.0;
+        }
+    }
+}
+
+impl Widget<LapceTabData> for LspLogContent {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(mouse_event) => {
+                self.mouse_pos = mouse_event.pos;
+                if self.is_clickable(mouse_event.pos) {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+            }
+            Event::MouseDown(mouse_event) => {
+                self.mouse_down(ctx, mouse_event, data);
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if !Arc::ptr_eq(&old_data.lsp_log, &data.lsp_log) {
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let lines = data
+            .lsp_log
+            .active_server()
+            .map(|server| server.lines.len())
+            .unwrap_or(0);
+        let height = Self::line_height(data) * (lines + 1) as f64;
+        Size::new(bc.max().width, height.max(bc.max().height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        self.paint_header(ctx, data);
+
+        let server = match data.lsp_log.active_server() {
+            Some(server) => server,
+            None => return,
+        };
+        let line_height = Self::line_height(data);
+        let rect = ctx.region().bounding_box();
+        let min = ((rect.y0 / line_height).floor() as usize).max(1);
+        let max = ((rect.y1 / line_height) as usize + 1).min(server.lines.len());
+        for line in min..=max {
+            let log_line = match server.lines.get(line - 1) {
+                Some(log_line) => log_line,
+                None => break,
+            };
+            let text_layout = ctx
+                .text()
+                .new_text_layout(log_line.text.clone())
+                .font(
+                    data.config.editor.font_family(),
+                    data.config.editor.font_size as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(if log_line.header {
+                            LapceTheme::EDITOR_FOREGROUND
+                        } else {
+                            LapceTheme::EDITOR_DIM
+                        })
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let y = line_height * line as f64;
+            ctx.draw_text(
+                &text_layout,
+                Point::new(10.0, y + text_layout.y_offset(line_height)),
+            );
+        }
+    }
+}
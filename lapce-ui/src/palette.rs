@@ -27,7 +27,7 @@
 use crate::list::{List, ListPaint};
 use crate::{
     editor::view::LapceEditorView,
-    svg::{file_svg, symbol_svg},
+    svg::{file_svg, symbol_svg, terminal_svg},
 };
 
 pub struct Palette {
@@ -496,37 +496,47 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &PaletteViewData, _env: &Env) {
         let text = data.palette.input.clone();
         let cursor = data.palette.cursor;
 
-        let text_layout = if text.is_empty()
-            && data.palette.palette_type == PaletteType::SshHost
-        {
-            ctx.text()
-                .new_text_layout("Enter your SSH details, like user@host")
-                .font(
-                    data.config.ui.font_family(),
-                    data.config.ui.font_size() as f64,
-                )
-                .text_color(
-                    data.config
-                        .get_color_unchecked(LapceTheme::EDITOR_DIM)
-                        .clone(),
-                )
-                .build()
-                .unwrap()
-        } else {
-            ctx.text()
-                .new_text_layout(text)
-                .font(
-                    data.config.ui.font_family(),
-                    data.config.ui.font_size() as f64,
-                )
-                .text_color(
-                    data.config
-                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
-                        .clone(),
-                )
-                .build()
-                .unwrap()
+        let placeholder = match &data.palette.palette_type {
+            PaletteType::SshHost => Some("Enter your SSH details, like user@host"),
+            PaletteType::EditBreakpoint(edit) => Some(edit.kind.placeholder()),
+            PaletteType::WatchExpression { .. } => {
+                Some("Enter the expression to watch")
+            }
+            PaletteType::RenameTerminal { .. } => {
+                Some("Enter the name of the terminal, or nothing for its title")
+            }
+            _ => None,
         };
+        let text_layout =
+            if let Some(placeholder) = placeholder.filter(|_| text.is_empty()) {
+                ctx.text()
+                    .new_text_layout(placeholder)
+                    .font(
+                        data.config.ui.font_family(),
+                        data.config.ui.font_size() as f64,
+                    )
+                    .text_color(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                            .clone(),
+                    )
+                    .build()
+                    .unwrap()
+            } else {
+                ctx.text()
+                    .new_text_layout(text)
+                    .font(
+                        data.config.ui.font_family(),
+                        data.config.ui.font_size() as f64,
+                    )
+                    .text_color(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                            .clone(),
+                    )
+                    .build()
+                    .unwrap()
+            };
 
         let pos = text_layout.hit_test_text_position(cursor);
         let line_metric = text_layout.line_metric(0).unwrap();
@@ -638,7 +648,8 @@ fn paint(
             hint,
             hint_indices,
         } = match &self.content {
-            PaletteItemContent::File(path, _) => {
+            PaletteItemContent::File(path, _)
+            | PaletteItemContent::CompareFile(path, _) => {
                 file_paint_items(path, &self.indices)
             }
             PaletteItemContent::DocumentSymbol {
@@ -731,6 +742,93 @@ fn paint(
                 name.to_string(),
                 self.indices.to_vec(),
             ),
+            PaletteItemContent::LineEnding(line_ending) => {
+                PaletteItemPaintInfo::new_text(
+                    line_ending.label().to_string(),
+                    self.indices.to_vec(),
+                )
+            }
+            PaletteItemContent::Indentation(indent_style) => {
+                PaletteItemPaintInfo::new_text(
+                    PaletteItemContent::indentation_label(*indent_style),
+                    self.indices.to_vec(),
+                )
+            }
+            PaletteItemContent::LanguageServer { name, .. } => {
+                PaletteItemPaintInfo::new_text(
+                    name.to_string(),
+                    self.indices.to_vec(),
+                )
+            }
+            PaletteItemContent::RunAndDebug(config) => PaletteItemPaintInfo {
+                svg: None,
+                text: config.name.clone(),
+                text_indices: self.indices.to_vec(),
+                hint: format!("{} {}", config.request.as_str(), config.ty),
+                hint_indices: Vec::new(),
+            },
+            PaletteItemContent::RunCompound { name, configs } => {
+                PaletteItemPaintInfo {
+                    svg: None,
+                    text: name.clone(),
+                    text_indices: self.indices.to_vec(),
+                    hint: configs
+                        .iter()
+                        .map(|config| config.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    hint_indices: Vec::new(),
+                }
+            }
+            PaletteItemContent::AttachProcess { process, .. } => {
+                PaletteItemPaintInfo {
+                    svg: None,
+                    text: process.name.clone(),
+                    text_indices: self.indices.to_vec(),
+                    hint: format!("{}  {}", process.pid, process.command),
+                    hint_indices: Vec::new(),
+                }
+            }
+            PaletteItemContent::TerminalProfile { name, profile } => {
+                PaletteItemPaintInfo {
+                    svg: Some(terminal_svg(profile.icon.as_deref())),
+                    text: name.clone(),
+                    text_indices: self.indices.to_vec(),
+                    hint: std::iter::once(
+                        profile.command.as_deref().unwrap_or_default(),
+                    )
+                    .chain(profile.arguments.iter().map(|arg| arg.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                    hint_indices: Vec::new(),
+                }
+            }
+            PaletteItemContent::TerminalColor { color, .. } => {
+                PaletteItemPaintInfo::new_text(
+                    color.clone().unwrap_or_else(|| "none".to_string()),
+                    self.indices.to_vec(),
+                )
+            }
+            PaletteItemContent::TerminalIcon { icon, .. } => PaletteItemPaintInfo {
+                svg: Some(terminal_svg(icon.as_deref())),
+                text: icon.clone().unwrap_or_else(|| "profile".to_string()),
+                text_indices: self.indices.to_vec(),
+                hint: String::new(),
+                hint_indices: Vec::new(),
+            },
+            PaletteItemContent::RunTask(task) => PaletteItemPaintInfo {
+                svg: None,
+                text: task.config.name.clone(),
+                text_indices: self.indices.to_vec(),
+                hint: task.config.command.clone(),
+                hint_indices: Vec::new(),
+            },
+            PaletteItemContent::Encoding { name, .. } => {
+                PaletteItemPaintInfo::new_text(
+                    name.to_string(),
+                    self.indices.to_vec(),
+                )
+            }
             PaletteItemContent::TerminalLine(_line, content) => {
                 PaletteItemPaintInfo::new_text(
                     content.clone(),
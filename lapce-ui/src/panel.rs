@@ -930,6 +930,21 @@ fn panel_icon(kind: &PanelKind, data: &LapceTabData) -> (PanelKind, LapceIcon) {
             PanelKind::Terminal => LapceWorkbenchCommand::ToggleTerminalVisual,
             PanelKind::Search => LapceWorkbenchCommand::ToggleSearchVisual,
             PanelKind::Problem => LapceWorkbenchCommand::ToggleProblemVisual,
+            PanelKind::UndoTree => LapceWorkbenchCommand::ToggleUndoTreeVisual,
+            PanelKind::CallHierarchy => {
+                LapceWorkbenchCommand::ToggleCallHierarchyVisual
+            }
+            PanelKind::TypeHierarchy => {
+                LapceWorkbenchCommand::ToggleTypeHierarchyVisual
+            }
+            PanelKind::RenamePreview => {
+                LapceWorkbenchCommand::ToggleRenamePreviewVisual
+            }
+            PanelKind::LspLog => LapceWorkbenchCommand::ToggleLspLogVisual,
+            PanelKind::Debug => LapceWorkbenchCommand::ToggleDebugVisual,
+            PanelKind::DebugConsole => {
+                LapceWorkbenchCommand::ToggleDebugConsoleVisual
+            }
         };
         (
             *kind,
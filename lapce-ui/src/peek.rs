@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use druid::{
+    kurbo::Line, BoxConstraints, Command, Env, Event, EventCtx, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, Point, Rect, RenderContext, Size, Target,
+    UpdateCtx, Widget, WidgetExt, WidgetId, WidgetPod,
+};
+use lapce_data::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    config::LapceTheme,
+    data::{FocusArea, LapceTabData},
+    list::ListData,
+};
+
+use crate::{editor::view::LapceEditorView, list::List};
+
+/// The peek view, with the embedded editor that shows the selected location
+/// on the left and the list of the locations on the right
+pub struct Peek {
+    widget_id: WidgetId,
+    editor: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
+    list: WidgetPod<ListData<String, ()>, List<String, ()>>,
+}
+
+impl Peek {
+    pub fn new(data: &LapceTabData) -> Self {
+        let editor =
+            LapceEditorView::new(data.peek.view_id, data.peek.editor_id, None)
+                .hide_header();
+        Self {
+            widget_id: data.peek.widget_id,
+            editor: WidgetPod::new(editor.boxed()),
+            list: WidgetPod::new(List::new(WidgetId::next())),
+        }
+    }
+
+    fn request_focus(&self, ctx: &mut EventCtx, data: &mut LapceTabData) {
+        ctx.request_focus();
+        data.focus_area = FocusArea::Peek;
+        data.focus = Arc::new(self.widget_id);
+    }
+
+    /// Close the peek view and give the focus back to the editor it was
+    /// shown in
+    fn close(&self, ctx: &mut EventCtx, data: &mut LapceTabData) {
+        Arc::make_mut(&mut data.peek).cancel();
+        ctx.submit_command(Command::new(
+            LAPCE_UI_COMMAND,
+            LapceUICommand::Focus,
+            Target::Widget(data.peek.from_editor),
+        ));
+    }
+}
+
+impl Widget<LapceTabData> for Peek {
+    fn id(&self) -> Option<WidgetId> {
+        Some(self.widget_id)
+    }
+
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        env: &Env,
+    ) {
+        match event {
+            // The embedded editor is only there to look at the location, so
+            // it doesn't take the focus from the list
+            Event::MouseDown(mouse_event) | Event::MouseUp(mouse_event)
+                if self.editor.layout_rect().contains(mouse_event.pos) =>
+            {
+                self.request_focus(ctx, data);
+                ctx.set_handled();
+                return;
+            }
+            _ => {
+                self.editor.event(ctx, event, data, env);
+            }
+        }
+
+        let peek = Arc::make_mut(&mut data.peek);
+        peek.list.update_data(data.config.clone());
+        self.list.event(ctx, event, &mut peek.list, env);
+
+        match event {
+            Event::KeyDown(key_event) => {
+                let mut keypress = data.keypress.clone();
+                Arc::make_mut(&mut keypress).key_down(
+                    ctx,
+                    key_event,
+                    Arc::make_mut(&mut data.peek),
+                    env,
+                );
+                if !data.peek.active {
+                    self.close(ctx, data);
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(LAPCE_UI_COMMAND) => {
+                let command = cmd.get_unchecked(LAPCE_UI_COMMAND);
+                match command {
+                    LapceUICommand::Focus => {
+                        self.request_focus(ctx, data);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::Hide => {
+                        Arc::make_mut(&mut data.peek).cancel();
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ListItemSelected => {
+                        if let Some(location) = data.peek.current_location() {
+                            ctx.submit_command(Command::new(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::JumpToLspLocation(
+                                    None,
+                                    location.clone(),
+                                    true,
+                                ),
+                                Target::Auto,
+                            ));
+                        }
+                        self.close(ctx, data);
+                        ctx.set_handled();
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &LapceTabData,
+        env: &Env,
+    ) {
+        if let LifeCycle::FocusChanged(false) = event {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::Hide,
+                Target::Widget(self.widget_id),
+            ));
+        }
+        self.editor.lifecycle(ctx, event, data, env);
+        self.list.lifecycle(
+            ctx,
+            event,
+            &data.peek.list.clone_with(data.config.clone()),
+            env,
+        );
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        env: &Env,
+    ) {
+        if data.peek.active {
+            let old_editor = old_data.main_split.editors.get(&data.peek.from_editor);
+            let editor = data.main_split.editors.get(&data.peek.from_editor);
+            if let (Some(old_editor), Some(editor)) = (old_editor, editor) {
+                if old_editor.scroll_offset != editor.scroll_offset {
+                    ctx.request_layout();
+                }
+            }
+        }
+
+        self.editor.update(ctx, data, env);
+        self.list
+            .update(ctx, &data.peek.list.clone_with(data.config.clone()), env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        env: &Env,
+    ) -> Size {
+        let size = bc.max();
+        let list_width = (size.width * 0.3).min(400.0).round();
+        let editor_width = size.width - list_width;
+
+        self.editor.layout(
+            ctx,
+            &BoxConstraints::tight(Size::new(editor_width, size.height)),
+            data,
+            env,
+        );
+        self.editor.set_origin(ctx, data, env, Point::ZERO);
+
+        let list_data = data.peek.list.clone_with(data.config.clone());
+        self.list.layout(
+            ctx,
+            &BoxConstraints::tight(Size::new(list_width, size.height)),
+            &list_data,
+            env,
+        );
+        self.list
+            .set_origin(ctx, &list_data, env, Point::new(editor_width, 0.0));
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
+        let rect = ctx.size().to_rect();
+        let shadow_width = data.config.ui.drop_shadow_width() as f64;
+        if shadow_width > 0.0 {
+            ctx.blurred_rect(
+                rect,
+                shadow_width,
+                data.config
+                    .get_color_unchecked(LapceTheme::LAPCE_DROPDOWN_SHADOW),
+            );
+        } else {
+            ctx.stroke(
+                rect.inflate(0.5, 0.5),
+                data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+                1.0,
+            );
+        }
+
+        let list_rect =
+            Rect::new(self.list.layout_rect().x0, rect.y0, rect.x1, rect.y1);
+        ctx.fill(
+            list_rect,
+            data.config
+                .get_color_unchecked(LapceTheme::PANEL_BACKGROUND),
+        );
+        self.editor.paint(ctx, data, env);
+        self.list
+            .paint(ctx, &data.peek.list.clone_with(data.config.clone()), env);
+        ctx.stroke(
+            Line::new(
+                Point::new(list_rect.x0, list_rect.y0),
+                Point::new(list_rect.x0, list_rect.y1),
+            ),
+            data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+            1.0,
+        );
+    }
+}
@@ -1,10 +1,10 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use druid::{
     piet::{Text, TextLayout as PietTextLayout, TextLayoutBuilder},
     BoxConstraints, Command, Cursor, Data, Env, Event, EventCtx, LayoutCtx,
     LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point, RenderContext, Size,
-    Target, UpdateCtx, Widget, WidgetExt,
+    Target, UpdateCtx, Widget, WidgetExt, WidgetId,
 };
 use lapce_data::{
     command::{LapceUICommand, LAPCE_UI_COMMAND},
@@ -12,22 +12,35 @@
     data::{EditorDiagnostic, LapceTabData},
     editor::EditorLocation,
     panel::PanelKind,
-    problem::ProblemData,
     proxy::path_from_url,
 };
 use lsp_types::DiagnosticSeverity;
 
 use crate::{
+    editor::view::LapceEditorView,
     panel::{LapcePanel, PanelHeaderKind, PanelSizing},
     svg::{file_svg, get_svg},
 };
 
-pub fn new_problem_panel(data: &ProblemData) -> LapcePanel {
+pub fn new_problem_panel(data: &LapceTabData) -> LapcePanel {
+    let input =
+        LapceEditorView::new(data.problem.filter_view_id, WidgetId::next(), None)
+            .hide_header()
+            .hide_gutter()
+            .set_placeholder("Filter".to_string())
+            .padding((15.0, 15.0));
+    let data = &data.problem;
     LapcePanel::new(
         PanelKind::Problem,
         data.widget_id,
         data.split_id,
         vec![
+            (
+                data.filter_view_id,
+                PanelHeaderKind::None,
+                input.boxed(),
+                PanelSizing::Size(100.0),
+            ),
             (
                 data.error_widget_id,
                 PanelHeaderKind::Simple("Errors".into()),
@@ -48,6 +61,31 @@ fn is_collapsed(data: &LapceTabData, path: &Path) -> bool {
     data.problem.collapsed.get(path).copied().unwrap_or(false)
 }
 
+/// The diagnostics of the severity, by file, that match the filter
+fn problem_items(
+    data: &LapceTabData,
+    severity: DiagnosticSeverity,
+) -> Vec<(&PathBuf, Vec<&EditorDiagnostic>)> {
+    let items = data.main_split.diagnostics_items(severity);
+    if data.problem.filter.is_empty() {
+        return items;
+    }
+    items
+        .into_iter()
+        .filter_map(|(path, diagnostics)| {
+            let diagnostics: Vec<&EditorDiagnostic> = diagnostics
+                .into_iter()
+                .filter(|d| data.problem.filter.matches(&d.diagnostic))
+                .collect();
+            if diagnostics.is_empty() {
+                None
+            } else {
+                Some((path, diagnostics))
+            }
+        })
+        .collect()
+}
+
 struct ProblemContent {
     severity: DiagnosticSeverity,
     mouse_pos: Point,
@@ -77,7 +115,7 @@ fn mouse_down(
         let line_height = data.config.editor.line_height() as f64;
         let click_line = (mouse_event.pos.y / line_height).floor() as usize;
 
-        let items = data.main_split.diagnostics_items(self.severity);
+        let items = problem_items(data, self.severity);
 
         let mut line_cursor = 0;
 
@@ -156,6 +194,21 @@ fn mouse_down(
 
         let msg_lines = message_lines(file_diagnostic);
 
+        // The quick fix button at the end of the first line of the message.
+        if click_line == line_cursor
+            && mouse_event.pos.x >= ctx.size().width - line_height
+        {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::ApplyQuickFix {
+                    path: path.to_path_buf(),
+                    diagnostic: file_diagnostic.diagnostic.clone(),
+                },
+                Target::Widget(data.id),
+            ));
+            return;
+        }
+
         // Widget has mouse about it and line is clicked one.
         if (line_cursor..(line_cursor + msg_lines)).contains(&click_line) {
             // Rust example: description without location.
@@ -272,7 +325,9 @@ fn update(
             ctx.request_layout();
         }
 
-        if data.problem.collapsed != old_data.problem.collapsed {
+        if data.problem.collapsed != old_data.problem.collapsed
+            || data.problem.filter != old_data.problem.filter
+        {
             ctx.request_layout();
         }
     }
@@ -284,7 +339,7 @@ fn layout(
         data: &LapceTabData,
         _env: &Env,
     ) -> Size {
-        let items = data.main_split.diagnostics_items(self.severity);
+        let items = problem_items(data, self.severity);
         let lines = items
             .iter()
             .map(|(path, diagnostics)| {
@@ -314,7 +369,7 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
         let ui_font_family = data.config.ui.font_family();
         let ui_font_size = data.config.ui.font_size() as f64;
 
-        let items = data.main_split.diagnostics_items(self.severity);
+        let items = problem_items(data, self.severity);
         let mut current_line = 0;
         for (path, diagnostics) in items {
             let diagnostics_len =
@@ -419,6 +474,21 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
                         data.config
                             .get_color_unchecked(LapceTheme::EDITOR_CURRENT_LINE),
                     );
+
+                    let rect = Size::new(line_height, line_height)
+                        .to_rect()
+                        .with_origin(Point::new(
+                            size.width - line_height,
+                            line_height * (current_line + 1) as f64,
+                        ))
+                        .inflate(-padding, -padding);
+                    ctx.draw_svg(
+                        &get_svg("lightbulb.svg").unwrap(),
+                        rect,
+                        Some(
+                            data.config.get_color_unchecked(LapceTheme::LAPCE_WARN),
+                        ),
+                    );
                 }
 
                 let svg = match self.severity {
@@ -0,0 +1,502 @@
+use std::sync::Arc;
+
+use druid::{
+    piet::{
+        PietTextLayout, Text, TextAttribute, TextLayout as TextLayoutTrait,
+        TextLayoutBuilder,
+    },
+    ArcStr, BoxConstraints, Command, Env, Event, EventCtx, FontDescriptor,
+    FontWeight, LayoutCtx, LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point,
+    Rect, RenderContext, Size, Target, TextLayout, UpdateCtx, Widget, WidgetId,
+    WidgetPod,
+};
+use lapce_core::command::FocusCommand;
+use lapce_data::{
+    command::{
+        CommandKind, LapceCommand, LapceUICommand, LAPCE_COMMAND, LAPCE_UI_COMMAND,
+    },
+    config::LapceTheme,
+    data::LapceTabData,
+    markdown::parse_markdown,
+    release_notes::ReleaseNotesFocusData,
+    rich_text::RichText,
+    update::{package_manager, PackageManager, UpdateDismissal, UpdateSettings},
+};
+
+pub struct ReleaseNotesBox {
+    content: WidgetPod<LapceTabData, ReleaseNotesContent>,
+}
+
+impl ReleaseNotesBox {
+    pub fn new(data: &LapceTabData) -> Self {
+        let content = ReleaseNotesContent::new(data);
+        Self {
+            content: WidgetPod::new(content),
+        }
+    }
+}
+
+impl Widget<LapceTabData> for ReleaseNotesBox {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        env: &Env,
+    ) {
+        if !data.release_notes.active {
+            return;
+        }
+        self.content.event(ctx, event, data, env);
+        if !event.should_propagate_to_hidden() {
+            ctx.set_handled();
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &LapceTabData,
+        env: &Env,
+    ) {
+        self.content.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        _old_data: &LapceTabData,
+        data: &LapceTabData,
+        env: &Env,
+    ) {
+        self.content.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        env: &Env,
+    ) -> Size {
+        let self_size = bc.max();
+        let size = self.content.layout(ctx, bc, data, env);
+        let origin = Point::new(
+            (self_size.width - size.width) / 2.0,
+            (self_size.height - size.height) / 2.0,
+        );
+        self.content.set_origin(ctx, data, env, origin);
+
+        self_size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
+        if !data.release_notes.active {
+            return;
+        }
+        let rect = ctx.size().to_rect();
+        ctx.fill(
+            rect,
+            &data
+                .config
+                .get_color_unchecked(LapceTheme::LAPCE_DROPDOWN_SHADOW)
+                .clone()
+                .with_alpha(0.5),
+        );
+
+        self.content.paint(ctx, data, env);
+    }
+}
+
+/// The dialog content: a title, the markdown release notes, which can be
+/// scrolled when they don't fit, and the buttons to confirm the update, or
+/// to dismiss it for a while or for good.
+///
+/// When Lapce is managed by a package manager it can't update itself, so the
+/// dialog explains how to upgrade the package instead.
+pub struct ReleaseNotesContent {
+    widget_id: WidgetId,
+    package_manager: Option<PackageManager>,
+
+    width: f64,
+    padding: f64,
+    button_height: f64,
+
+    title_layout: Option<PietTextLayout>,
+    title_origin: Point,
+    msg_layout: Option<PietTextLayout>,
+    msg_origin: Point,
+    notes_layout: TextLayout<RichText>,
+    notes_rect: Rect,
+    notes_scroll: f64,
+    update_rect: Rect,
+    later_rect: Rect,
+    skip_rect: Rect,
+
+    mouse_down_point: Point,
+}
+
+impl ReleaseNotesContent {
+    pub fn new(data: &LapceTabData) -> Self {
+        let mut notes_layout = TextLayout::new();
+        notes_layout.set_text(RichText::new(ArcStr::from("")));
+        Self {
+            widget_id: data.release_notes.widget_id,
+            package_manager: package_manager(),
+            width: 500.0,
+            padding: 20.0,
+            button_height: 30.0,
+            title_layout: None,
+            title_origin: Point::ZERO,
+            msg_layout: None,
+            msg_origin: Point::ZERO,
+            notes_layout,
+            notes_rect: Rect::ZERO,
+            notes_scroll: 0.0,
+            update_rect: Rect::ZERO,
+            later_rect: Rect::ZERO,
+            skip_rect: Rect::ZERO,
+            mouse_down_point: Point::ZERO,
+        }
+    }
+
+    fn icon_hit_test(&self, mouse_event: &MouseEvent) -> bool {
+        self.update_rect.contains(mouse_event.pos)
+            || self.later_rect.contains(mouse_event.pos)
+            || self.skip_rect.contains(mouse_event.pos)
+    }
+
+    fn max_notes_scroll(&self) -> f64 {
+        (self.notes_layout.size().height - self.notes_rect.height()).max(0.0)
+    }
+
+    fn close(&self, ctx: &mut EventCtx) {
+        ctx.submit_command(Command::new(
+            LAPCE_COMMAND,
+            LapceCommand {
+                kind: CommandKind::Focus(FocusCommand::ModalClose),
+                data: None,
+            },
+            Target::Widget(self.widget_id),
+        ));
+    }
+
+    fn paint_button(
+        ctx: &mut PaintCtx,
+        data: &LapceTabData,
+        rect: Rect,
+        text: &str,
+    ) {
+        ctx.stroke(
+            rect,
+            data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+            1.0,
+        );
+        let text_layout = ctx
+            .text()
+            .new_text_layout(text.to_string())
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        let text_layout_size = text_layout.size();
+        let point =
+            rect.center() - (text_layout_size.width / 2.0, text_layout.cap_center());
+        ctx.draw_text(&text_layout, point);
+    }
+}
+
+impl Widget<LapceTabData> for ReleaseNotesContent {
+    fn id(&self) -> Option<WidgetId> {
+        Some(self.widget_id)
+    }
+
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        env: &Env,
+    ) {
+        match event {
+            Event::KeyDown(key_event) => {
+                let mut focus = ReleaseNotesFocusData::new(data);
+                Arc::make_mut(&mut data.keypress)
+                    .key_down(ctx, key_event, &mut focus, env);
+            }
+            Event::MouseMove(mouse_event) => {
+                if self.icon_hit_test(mouse_event) {
+                    ctx.set_cursor(&druid::Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+            }
+            Event::Wheel(wheel_event) => {
+                self.notes_scroll = (self.notes_scroll + wheel_event.wheel_delta.y)
+                    .clamp(0.0, self.max_notes_scroll());
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::MouseDown(mouse_event) => {
+                self.mouse_down_point = mouse_event.pos;
+            }
+            Event::MouseUp(mouse_event) => {
+                for (rect, skip) in
+                    [(self.later_rect, false), (self.skip_rect, true)]
+                {
+                    if rect.contains(self.mouse_down_point)
+                        && rect.contains(mouse_event.pos)
+                    {
+                        if let Some(release) = data.release_notes.release.as_ref() {
+                            let dismissal = if skip {
+                                UpdateDismissal::skip(&release.version)
+                            } else {
+                                UpdateDismissal::remind_later(&release.version)
+                            };
+                            ctx.submit_command(Command::new(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::DismissUpdate(dismissal),
+                                Target::Global,
+                            ));
+                        }
+                        self.close(ctx);
+                        ctx.set_handled();
+                        return;
+                    }
+                }
+
+                if self.update_rect.contains(self.mouse_down_point)
+                    && self.update_rect.contains(mouse_event.pos)
+                {
+                    if let Some(manager) = self.package_manager {
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::OpenURI(
+                                manager.upgrade_url().to_string(),
+                            ),
+                            Target::Widget(data.id),
+                        ));
+                    } else if let (Some(release), Ok(process_path)) =
+                        (data.release_notes.release.clone(), std::env::current_exe())
+                    {
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::RestartToUpdate(
+                                process_path,
+                                release,
+                                UpdateSettings::from_config(&data.config),
+                            ),
+                            Target::Global,
+                        ));
+                    }
+                    self.close(ctx);
+                    ctx.set_handled();
+                }
+            }
+            Event::Command(cmd) if cmd.is(LAPCE_COMMAND) => {
+                let command = cmd.get_unchecked(LAPCE_COMMAND);
+                if let CommandKind::Focus(FocusCommand::ModalClose) = &command.kind {
+                    let release_notes = Arc::make_mut(&mut data.release_notes);
+                    release_notes.active = false;
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::Focus,
+                        Target::Widget(*data.focus),
+                    ));
+                    ctx.set_handled();
+                }
+            }
+            Event::Command(cmd) if cmd.is(LAPCE_UI_COMMAND) => {
+                let command = cmd.get_unchecked(LAPCE_UI_COMMAND);
+                if let LapceUICommand::Focus = &command {
+                    ctx.request_focus();
+                    ctx.set_handled();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if !Arc::ptr_eq(&old_data.release_notes, &data.release_notes)
+            || old_data.config.id != data.config.id
+        {
+            let body = data
+                .release_notes
+                .release
+                .as_ref()
+                .map(|release| release.body.as_str())
+                .unwrap_or("");
+            self.notes_layout
+                .set_text(parse_markdown(body, &data.config));
+            self.notes_scroll = 0.0;
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        env: &Env,
+    ) -> Size {
+        let width = self.width.min(bc.max().width);
+        let version = data
+            .release_notes
+            .release
+            .as_ref()
+            .map(|release| release.version.as_str())
+            .unwrap_or("");
+
+        let title_layout = ctx
+            .text()
+            .new_text_layout(format!("Lapce {version} is available"))
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .default_attribute(TextAttribute::Weight(FontWeight::BOLD))
+            .max_width(width - self.padding * 2.0)
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        self.title_origin = Point::new(self.padding, self.padding);
+        let mut notes_y =
+            self.title_origin.y + title_layout.size().height + self.padding / 2.0;
+
+        self.msg_layout = self.package_manager.map(|manager| {
+            let msg = match manager.upgrade_command() {
+                Some(command) => format!(
+                    "Lapce was installed with {}. Update it by running `{command}`.",
+                    manager.name()
+                ),
+                None => format!(
+                    "Lapce was installed with {}. Update it from there.",
+                    manager.name()
+                ),
+            };
+            ctx.text()
+                .new_text_layout(msg)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .max_width(width - self.padding * 2.0)
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                        .clone(),
+                )
+                .build()
+                .unwrap()
+        });
+        if let Some(msg_layout) = self.msg_layout.as_ref() {
+            self.msg_origin = Point::new(self.padding, notes_y);
+            notes_y += msg_layout.size().height + self.padding / 2.0;
+        }
+
+        self.notes_layout.set_font(
+            FontDescriptor::new(data.config.ui.font_family())
+                .with_size(data.config.ui.font_size() as f64),
+        );
+        self.notes_layout.set_text_color(
+            data.config
+                .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                .clone(),
+        );
+        self.notes_layout.set_wrap_width(width - self.padding * 2.0);
+        self.notes_layout.rebuild_if_needed(ctx.text(), env);
+
+        let buttons_height = self.padding + self.button_height + self.padding;
+        let max_notes_height =
+            (bc.max().height * 0.8 - notes_y - buttons_height).max(0.0);
+        let notes_height = self.notes_layout.size().height.min(max_notes_height);
+        self.notes_rect = Rect::new(
+            self.padding,
+            notes_y,
+            width - self.padding,
+            notes_y + notes_height,
+        );
+        self.notes_scroll = self.notes_scroll.min(self.max_notes_scroll());
+
+        let y = self.notes_rect.y1 + self.padding;
+        let button_width = (width - self.padding * 4.0) / 3.0;
+        let button_rect = |i: f64| {
+            let x = self.padding + (button_width + self.padding) * i;
+            Rect::new(x, y, x + button_width, y + self.button_height)
+        };
+        self.update_rect = button_rect(0.0);
+        self.later_rect = button_rect(1.0);
+        self.skip_rect = button_rect(2.0);
+
+        self.title_layout = Some(title_layout);
+
+        Size::new(width, y + self.button_height + self.padding)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        let rect = ctx.size().to_rect();
+        let shadow_width = data.config.ui.drop_shadow_width() as f64;
+        if shadow_width > 0.0 {
+            ctx.blurred_rect(
+                rect,
+                shadow_width,
+                data.config
+                    .get_color_unchecked(LapceTheme::LAPCE_DROPDOWN_SHADOW),
+            );
+        }
+        ctx.fill(
+            rect,
+            data.config
+                .get_color_unchecked(LapceTheme::PANEL_BACKGROUND),
+        );
+
+        ctx.draw_text(self.title_layout.as_ref().unwrap(), self.title_origin);
+        if let Some(msg_layout) = self.msg_layout.as_ref() {
+            ctx.draw_text(msg_layout, self.msg_origin);
+        }
+
+        ctx.with_save(|ctx| {
+            ctx.clip(self.notes_rect);
+            self.notes_layout
+                .draw(ctx, self.notes_rect.origin() - (0.0, self.notes_scroll));
+        });
+
+        let update_text = if self.package_manager.is_some() {
+            "Upgrade Instructions"
+        } else {
+            "Restart to Update"
+        };
+        Self::paint_button(ctx, data, self.update_rect, update_text);
+        Self::paint_button(ctx, data, self.later_rect, "Remind Me Later");
+        Self::paint_button(ctx, data, self.skip_rect, "Skip This Version");
+    }
+}
@@ -0,0 +1,551 @@
+use std::{path::PathBuf, sync::Arc};
+
+use druid::{
+    piet::{Text, TextAttribute, TextLayout as PietTextLayout, TextLayoutBuilder},
+    BoxConstraints, Command, Cursor, Env, Event, EventCtx, FontWeight, LayoutCtx,
+    LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point, Rect, RenderContext, Size,
+    Target, UpdateCtx, Widget, WidgetExt,
+};
+use lapce_data::{
+    command::{
+        CommandKind, LapceCommand, LapceUICommand, LapceWorkbenchCommand,
+        LAPCE_COMMAND, LAPCE_UI_COMMAND,
+    },
+    config::LapceTheme,
+    data::LapceTabData,
+    editor::EditorLocation,
+    panel::PanelKind,
+    rename_preview::{RenamePreviewData, RenamePreviewFile, RenamePreviewRow},
+};
+use lsp_types::{ResourceOp, TextEdit};
+
+use crate::{
+    panel::{LapcePanel, PanelHeaderKind, PanelSizing},
+    svg::{file_svg, get_svg},
+};
+
+pub fn new_rename_preview_panel(data: &RenamePreviewData) -> LapcePanel {
+    LapcePanel::new(
+        PanelKind::RenamePreview,
+        data.widget_id,
+        data.split_id,
+        vec![(
+            data.tree_widget_id,
+            PanelHeaderKind::None,
+            RenamePreviewContent::new().boxed(),
+            PanelSizing::Flex(false),
+        )],
+    )
+}
+
+/// The files and edits of the rename, below a row to apply or discard them.
+/// Clicking the checkbox of a row selects or deselects it, and double
+/// clicking an edit goes to it.
+struct RenamePreviewContent {
+    mouse_pos: Point,
+    content_height: f64,
+    button_rects: [Rect; 2],
+}
+
+impl RenamePreviewContent {
+    fn new() -> Self {
+        Self {
+            mouse_pos: Point::ZERO,
+            content_height: 0.0,
+            button_rects: [Rect::ZERO; 2],
+        }
+    }
+
+    fn mouse_down(
+        &self,
+        ctx: &mut EventCtx,
+        mouse_event: &MouseEvent,
+        data: &mut LapceTabData,
+    ) {
+        let line_height = data.config.editor.line_height() as f64;
+        let n = (mouse_event.pos.y / line_height).floor() as usize;
+        if n == 0 {
+            let cmd = if self.button_rects[0].contains(mouse_event.pos) {
+                LapceWorkbenchCommand::ApplyRenamePreview
+            } else if self.button_rects[1].contains(mouse_event.pos) {
+                LapceWorkbenchCommand::DiscardRenamePreview
+            } else {
+                return;
+            };
+            ctx.submit_command(Command::new(
+                LAPCE_COMMAND,
+                LapceCommand {
+                    kind: CommandKind::Workbench(cmd),
+                    data: None,
+                },
+                Target::Widget(data.id),
+            ));
+            return;
+        }
+
+        let row = match data.rename_preview.rows().get(n - 1) {
+            Some(row) => *row,
+            None => return,
+        };
+        let x = mouse_event.pos.x;
+        match row {
+            RenamePreviewRow::Operation(_) => {
+                if x < line_height {
+                    Arc::make_mut(&mut data.rename_preview).toggle_selected(row);
+                }
+            }
+            RenamePreviewRow::File(i) => {
+                if x < line_height {
+                    Arc::make_mut(&mut data.rename_preview).toggle_open(i);
+                } else if x < line_height * 2.0 {
+                    Arc::make_mut(&mut data.rename_preview).toggle_selected(row);
+                }
+            }
+            RenamePreviewRow::Edit(i, j) => {
+                if mouse_event.count == 2 {
+                    let file = &data.rename_preview.files[i];
+                    ctx.submit_command(Command::new(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::JumpToLocation(
+                            None,
+                            EditorLocation {
+                                path: file.path.clone(),
+                                position: Some(file.edits[j].edit.range.start),
+                                scroll_offset: None,
+                                history: None,
+                            },
+                            false,
+                        ),
+                        Target::Widget(data.id),
+                    ));
+                } else if x >= line_height && x < line_height * 2.0 {
+                    Arc::make_mut(&mut data.rename_preview).toggle_selected(row);
+                }
+            }
+        }
+    }
+
+    fn paint_header(&mut self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        let line_height = data.config.editor.line_height() as f64;
+        let padding = 10.0;
+        let mut x = padding;
+        for (i, label) in ["Apply", "Discard"].into_iter().enumerate() {
+            let text_layout = ctx
+                .text()
+                .new_text_layout(label)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOCUS)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let rect = Size::new(text_layout.size().width, line_height)
+                .to_rect()
+                .with_origin(Point::new(x, 0.0))
+                .inflate(padding / 2.0, 0.0);
+            self.button_rects[i] = rect;
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, text_layout.y_offset(line_height)),
+            );
+            x += text_layout.size().width + padding * 2.0;
+        }
+
+        let preview = &data.rename_preview;
+        if preview.is_empty() {
+            return;
+        }
+        let (edits, files) = preview.selected_count();
+        let summary = format!(
+            "Rename to {}: {edits} {} in {files} {}",
+            preview.new_name,
+            if edits == 1 { "edit" } else { "edits" },
+            if files == 1 { "file" } else { "files" },
+        );
+        let text_layout = ctx
+            .text()
+            .new_text_layout(summary)
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &text_layout,
+            Point::new(x, text_layout.y_offset(line_height)),
+        );
+    }
+
+    fn paint_checkbox(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &LapceTabData,
+        x: f64,
+        y: f64,
+        selected: bool,
+    ) {
+        let line_height = data.config.editor.line_height() as f64;
+        let foreground_color = data
+            .config
+            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND);
+        let checkbox_size = 10.0;
+        let checkbox = Size::new(checkbox_size, checkbox_size)
+            .to_rect()
+            .with_origin(Point::new(
+                x + (line_height - checkbox_size) / 2.0,
+                y + (line_height - checkbox_size) / 2.0,
+            ));
+        ctx.stroke(checkbox, foreground_color, 1.0);
+        if selected {
+            ctx.fill(checkbox.inflate(-2.0, -2.0), foreground_color);
+        }
+    }
+
+    fn paint_operation(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &LapceTabData,
+        y: f64,
+        op: &ResourceOp,
+    ) {
+        let line_height = data.config.editor.line_height() as f64;
+        let text = match op {
+            ResourceOp::Create(op) => format!("Create {}", op.uri.path()),
+            ResourceOp::Rename(op) => {
+                format!("Rename {} to {}", op.old_uri.path(), op.new_uri.path())
+            }
+            ResourceOp::Delete(op) => format!("Delete {}", op.uri.path()),
+        };
+        let text_layout = ctx
+            .text()
+            .new_text_layout(text)
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &text_layout,
+            Point::new(line_height, y + text_layout.y_offset(line_height)),
+        );
+    }
+
+    fn paint_file(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &LapceTabData,
+        y: f64,
+        file: &RenamePreviewFile,
+    ) {
+        let line_height = data.config.editor.line_height() as f64;
+        let svg_size = 15.0;
+        let svg_y = y + (line_height - svg_size) / 2.0;
+
+        let icon_name = if file.open {
+            "chevron-down.svg"
+        } else {
+            "chevron-right.svg"
+        };
+        let rect = Size::new(svg_size, svg_size)
+            .to_rect()
+            .with_origin(Point::new((line_height - svg_size) / 2.0, svg_y));
+        ctx.draw_svg(
+            &get_svg(icon_name).unwrap(),
+            rect,
+            Some(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+            ),
+        );
+
+        self.paint_checkbox(ctx, data, line_height, y, file.selected());
+
+        let (svg, svg_color) = file_svg(&file.path);
+        let rect = Size::new(svg_size, svg_size)
+            .to_rect()
+            .with_origin(Point::new(line_height * 2.0, svg_y));
+        ctx.draw_svg(&svg, rect, svg_color);
+
+        let x = line_height * 2.0 + svg_size + 5.0;
+        let file_name = file
+            .path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("")
+            .to_string();
+        let text_layout = ctx
+            .text()
+            .new_text_layout(file_name)
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &text_layout,
+            Point::new(x, y + text_layout.y_offset(line_height)),
+        );
+
+        let mut path: PathBuf = file.path.clone();
+        if let Some(workspace_path) = data.workspace.path.as_ref() {
+            path = path
+                .strip_prefix(workspace_path)
+                .unwrap_or(&path)
+                .to_path_buf();
+        }
+        let folder = path
+            .parent()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        if !folder.is_empty() {
+            let x = x + text_layout.size().width + 5.0;
+            let text_layout = ctx
+                .text()
+                .new_text_layout(folder)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, y + text_layout.y_offset(line_height)),
+            );
+        }
+    }
+
+    /// The line of the edit with the replaced text followed by the new text,
+    /// when the file is loaded, or else only the new text
+    fn paint_edit(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &LapceTabData,
+        y: f64,
+        file: &RenamePreviewFile,
+        edit: &TextEdit,
+        selected: bool,
+    ) {
+        let line_height = data.config.editor.line_height() as f64;
+        self.paint_checkbox(ctx, data, line_height, y, selected);
+
+        let focus_color = data.config.get_color_unchecked(LapceTheme::EDITOR_FOCUS);
+        let dim_color = data.config.get_color_unchecked(LapceTheme::EDITOR_DIM);
+        let foreground_color = data
+            .config
+            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND);
+
+        let line = edit.range.start.line as usize;
+        let line_number = line + 1;
+        let prefix = format!("{line_number}: ");
+        let doc = data
+            .main_split
+            .open_docs
+            .get(&file.path)
+            .filter(|doc| doc.loaded());
+        let (text, old_range) = match doc {
+            Some(doc) if edit.range.start.line == edit.range.end.line => {
+                let buffer = doc.buffer();
+                let line_offset = buffer.offset_of_line(line);
+                let start =
+                    buffer.offset_of_position(&edit.range.start) - line_offset;
+                let end = buffer.offset_of_position(&edit.range.end) - line_offset;
+                let content = buffer.line_content(line);
+                let content = content.trim_end_matches(&['\r', '\n'][..]);
+                let indent = content.len() - content.trim_start().len();
+                let (start, end) = (
+                    start.saturating_sub(indent).min(content.len() - indent),
+                    end.saturating_sub(indent).min(content.len() - indent),
+                );
+                let content = &content[indent..];
+                (
+                    format!(
+                        "{prefix}{}{}{}",
+                        &content[..end],
+                        edit.new_text,
+                        &content[end..]
+                    ),
+                    Some(start + prefix.len()..end + prefix.len()),
+                )
+            }
+            _ => (format!("{prefix}{}", edit.new_text), None),
+        };
+        let new_start = old_range
+            .as_ref()
+            .map(|range| range.end)
+            .unwrap_or(prefix.len());
+        let new_range = new_start..new_start + edit.new_text.len();
+
+        let mut text_layout = ctx
+            .text()
+            .new_text_layout(text)
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(if selected {
+                foreground_color.clone()
+            } else {
+                dim_color.clone()
+            });
+        if let Some(old_range) = old_range {
+            text_layout = text_layout.range_attribute(
+                old_range,
+                TextAttribute::TextColor(dim_color.clone()),
+            );
+        }
+        if selected {
+            text_layout = text_layout.range_attribute(
+                new_range.clone(),
+                TextAttribute::TextColor(focus_color.clone()),
+            );
+        }
+        let text_layout = text_layout
+            .range_attribute(new_range, TextAttribute::Weight(FontWeight::BOLD))
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &text_layout,
+            Point::new(line_height * 2.0, y + text_layout.y_offset(line_height)),
+        );
+    }
+}
+
+impl Widget<LapceTabData> for RenamePreviewContent {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(mouse_event) => {
+                self.mouse_pos = mouse_event.pos;
+
+                if mouse_event.pos.y < self.content_height {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+
+                ctx.request_paint();
+            }
+            Event::MouseDown(mouse_event) => {
+                self.mouse_down(ctx, mouse_event, data);
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        if !Arc::ptr_eq(&old_data.rename_preview, &data.rename_preview) {
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let line_height = data.config.editor.line_height() as f64;
+        self.content_height =
+            line_height * (data.rename_preview.rows().len() + 1) as f64;
+
+        Size::new(bc.max().width, self.content_height.max(bc.max().height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        self.paint_header(ctx, data);
+
+        let line_height = data.config.editor.line_height() as f64;
+        let size = ctx.size();
+        let mouse_line = (self.mouse_pos.y / line_height).floor() as usize;
+
+        let rect = ctx.region().bounding_box();
+        let min = (rect.y0 / line_height).floor() as usize;
+        let max = (rect.y1 / line_height) as usize + 2;
+
+        let preview = &data.rename_preview;
+        for (i, row) in preview.rows().into_iter().enumerate() {
+            let line = i + 1;
+            if line < min || line > max {
+                continue;
+            }
+            let y = line_height * line as f64;
+
+            if ctx.is_hot() && line == mouse_line {
+                ctx.fill(
+                    Size::new(size.width, line_height)
+                        .to_rect()
+                        .with_origin(Point::new(0.0, y)),
+                    data.config.get_color_unchecked(LapceTheme::PANEL_HOVERED),
+                );
+            }
+
+            match row {
+                RenamePreviewRow::Operation(i) => {
+                    let op = &preview.operations[i];
+                    self.paint_checkbox(ctx, data, 0.0, y, op.selected);
+                    self.paint_operation(ctx, data, y, &op.op);
+                }
+                RenamePreviewRow::File(i) => {
+                    self.paint_file(ctx, data, y, &preview.files[i]);
+                }
+                RenamePreviewRow::Edit(i, j) => {
+                    let file = &preview.files[i];
+                    let edit = &file.edits[j];
+                    self.paint_edit(ctx, data, y, file, &edit.edit, edit.selected);
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,16 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
 use druid::{
     piet::{Text, TextAttribute, TextLayout as PietTextLayout, TextLayoutBuilder},
     BoxConstraints, Command, Cursor, Data, Env, Event, EventCtx, FontWeight,
-    LayoutCtx, LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point, RenderContext,
-    Size, Target, UpdateCtx, Widget, WidgetExt, WidgetId,
+    LayoutCtx, LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point, Rect,
+    RenderContext, Size, Target, UpdateCtx, Widget, WidgetExt, WidgetId,
 };
 use lapce_data::{
-    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    command::{
+        CommandKind, LapceCommand, LapceUICommand, LapceWorkbenchCommand,
+        LAPCE_COMMAND, LAPCE_UI_COMMAND,
+    },
     config::LapceTheme,
     data::LapceTabData,
     editor::{EditorLocation, LineCol},
@@ -31,10 +34,22 @@ pub fn new_search_panel(data: &LapceTabData) -> LapcePanel {
     let input = LapceEditorView::new(editor_data.view_id, WidgetId::next(), None)
         .hide_header()
         .hide_gutter()
+        .set_placeholder("Search".to_string())
         .padding((15.0, 15.0));
+    let replace_data = data
+        .main_split
+        .editors
+        .get(&data.search.replace_view_id)
+        .unwrap();
+    let replace = LapceEditorView::new(replace_data.view_id, WidgetId::next(), None)
+        .hide_header()
+        .hide_gutter()
+        .set_placeholder("Replace".to_string())
+        .padding((15.0, 0.0, 15.0, 15.0));
     let split = LapceSplit::new(data.search.split_id)
         .horizontal()
         .with_child(input.boxed(), None, 100.0)
+        .with_child(replace.boxed(), None, 100.0)
         .with_flex_child(
             LapceScroll::new(SearchContent::new().boxed())
                 .vertical()
@@ -57,9 +72,15 @@ pub fn new_search_panel(data: &LapceTabData) -> LapcePanel {
     )
 }
 
+/// The results of the global search, below a summary row with the regex and
+/// preserve case toggles and the button replacing all the matches that
+/// aren't excluded
 struct SearchContent {
     mouse_pos: Point,
     line_height: f64,
+    regex_rect: Rect,
+    preserve_case_rect: Rect,
+    replace_all_rect: Rect,
 }
 
 impl SearchContent {
@@ -67,17 +88,19 @@ pub fn new() -> Self {
         Self {
             mouse_pos: Point::ZERO,
             line_height: 25.0,
+            regex_rect: Rect::ZERO,
+            preserve_case_rect: Rect::ZERO,
+            replace_all_rect: Rect::ZERO,
         }
     }
 
-    fn mouse_down(
+    /// The file, index, line number and column of the match in the nth row
+    /// of the results
+    fn match_at(
         &self,
-        ctx: &mut EventCtx,
-        mouse_event: &MouseEvent,
         data: &LapceTabData,
-    ) {
-        let n = (mouse_event.pos.y / self.line_height).floor() as usize;
-
+        n: usize,
+    ) -> Option<(PathBuf, usize, usize, usize)> {
         let mut i = 0;
         for (path, matches) in data.search.matches.iter() {
             if matches.len() + 1 + i < n {
@@ -85,31 +108,185 @@ fn mouse_down(
                 continue;
             }
 
-            for (line_number, (start, _end), _line) in matches {
+            for (index, (line_number, (start, _end), _line)) in
+                matches.iter().enumerate()
+            {
                 i += 1;
                 if i == n {
-                    ctx.submit_command(Command::new(
-                        LAPCE_UI_COMMAND,
-                        LapceUICommand::JumpToLineColLocation(
-                            None,
-                            EditorLocation {
-                                path: path.clone(),
-                                position: Some(LineCol {
-                                    line: line_number.saturating_sub(1),
-                                    column: *start,
-                                }),
-                                scroll_offset: None,
-                                history: None,
-                            },
-                            false,
-                        ),
-                        Target::Widget(data.id),
-                    ));
-                    return;
+                    return Some((path.clone(), index, *line_number, *start));
                 }
             }
             i += 1;
         }
+        None
+    }
+
+    fn mouse_down(
+        &self,
+        ctx: &mut EventCtx,
+        mouse_event: &MouseEvent,
+        data: &mut LapceTabData,
+    ) {
+        let n = (mouse_event.pos.y / self.line_height).floor() as usize;
+        if n == 0 {
+            let command = if self.regex_rect.contains(mouse_event.pos) {
+                LapceWorkbenchCommand::ToggleGlobalSearchRegex
+            } else if self.preserve_case_rect.contains(mouse_event.pos) {
+                LapceWorkbenchCommand::ToggleGlobalReplacePreserveCase
+            } else if self.replace_all_rect.contains(mouse_event.pos) {
+                LapceWorkbenchCommand::GlobalReplaceAll
+            } else {
+                return;
+            };
+            ctx.submit_command(Command::new(
+                LAPCE_COMMAND,
+                LapceCommand {
+                    kind: CommandKind::Workbench(command),
+                    data: None,
+                },
+                Target::Widget(data.id),
+            ));
+            return;
+        }
+
+        if let Some((path, index, line_number, start)) = self.match_at(data, n - 1) {
+            if mouse_event.pos.x < self.line_height {
+                Arc::make_mut(&mut data.search).toggle_excluded(&path, index);
+                return;
+            }
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::JumpToLineColLocation(
+                    None,
+                    EditorLocation {
+                        path,
+                        position: Some(LineCol {
+                            line: line_number.saturating_sub(1),
+                            column: start,
+                        }),
+                        scroll_offset: None,
+                        history: None,
+                    },
+                    false,
+                ),
+                Target::Widget(data.id),
+            ));
+        }
+    }
+
+    fn paint_summary(&mut self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        let size = ctx.size();
+        let padding = (self.line_height - 14.0) / 2.0;
+
+        let regex_color = if data.search.is_regex {
+            LapceTheme::EDITOR_FOCUS
+        } else {
+            LapceTheme::EDITOR_DIM
+        };
+        let text_layout = ctx
+            .text()
+            .new_text_layout(".*")
+            .font(
+                data.config.editor.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(data.config.get_color_unchecked(regex_color).clone())
+            .default_attribute(TextAttribute::Weight(FontWeight::BOLD))
+            .build()
+            .unwrap();
+        let x = size.width - self.line_height - text_layout.size().width;
+        self.regex_rect = Size::new(text_layout.size().width, self.line_height)
+            .to_rect()
+            .with_origin(Point::new(x, 0.0))
+            .inflate(padding, 0.0);
+        ctx.draw_text(
+            &text_layout,
+            Point::new(x, text_layout.y_offset(self.line_height)),
+        );
+
+        let preserve_case_color = if data.search.preserve_case {
+            LapceTheme::EDITOR_FOCUS
+        } else {
+            LapceTheme::EDITOR_DIM
+        };
+        let text_layout = ctx
+            .text()
+            .new_text_layout("AB")
+            .font(
+                data.config.editor.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(data.config.get_color_unchecked(preserve_case_color).clone())
+            .default_attribute(TextAttribute::Weight(FontWeight::BOLD))
+            .build()
+            .unwrap();
+        let x = self.regex_rect.x0 - padding - text_layout.size().width;
+        self.preserve_case_rect =
+            Size::new(text_layout.size().width, self.line_height)
+                .to_rect()
+                .with_origin(Point::new(x, 0.0))
+                .inflate(padding, 0.0);
+        ctx.draw_text(
+            &text_layout,
+            Point::new(x, text_layout.y_offset(self.line_height)),
+        );
+
+        self.replace_all_rect = Rect::ZERO;
+        let match_count = data
+            .search
+            .matches
+            .iter()
+            .map(|(_, matches)| matches.len())
+            .sum::<usize>();
+        if match_count == 0 {
+            return;
+        }
+
+        let text_layout = ctx
+            .text()
+            .new_text_layout("Replace All")
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOCUS)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        let x = self.preserve_case_rect.x0 - padding - text_layout.size().width;
+        self.replace_all_rect =
+            Size::new(text_layout.size().width, self.line_height)
+                .to_rect()
+                .with_origin(Point::new(x, 0.0));
+        ctx.draw_text(
+            &text_layout,
+            Point::new(x, text_layout.y_offset(self.line_height)),
+        );
+
+        let text_layout = ctx
+            .text()
+            .new_text_layout(format!(
+                "{match_count} results in {} files",
+                data.search.matches.len()
+            ))
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &text_layout,
+            Point::new(padding, text_layout.y_offset(self.line_height)),
+        );
     }
 }
 
@@ -158,6 +335,12 @@ fn update(
     ) {
         if !old_data.search.matches.same(&data.search.matches) {
             ctx.request_layout();
+        } else if !old_data.search.excluded.same(&data.search.excluded)
+            || old_data.search.replace != data.search.replace
+            || old_data.search.is_regex != data.search.is_regex
+            || old_data.search.preserve_case != data.search.preserve_case
+        {
+            ctx.request_paint();
         }
     }
 
@@ -174,7 +357,8 @@ fn layout(
             .iter()
             .map(|(_, matches)| matches.len() + 1)
             .sum::<usize>();
-        let height = self.line_height * n as f64;
+        // the summary row is above the results
+        let height = self.line_height * (n + 1) as f64;
         Size::new(bc.max().width, height)
     }
 
@@ -191,11 +375,20 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
             );
         }
 
+        self.paint_summary(ctx, data);
+
+        // the rows of the results start below the summary row
+        let line_height = self.line_height;
+        let row_y = |i: usize| line_height * (i + 1) as f64;
         let rect = ctx.region().bounding_box();
-        let min = (rect.y0 / self.line_height).floor() as usize;
+        let min = ((rect.y0 / self.line_height).floor() as usize).saturating_sub(1);
         let max = (rect.y1 / self.line_height) as usize + 2;
 
         let focus_color = data.config.get_color_unchecked(LapceTheme::EDITOR_FOCUS);
+        let dim_color = data.config.get_color_unchecked(LapceTheme::EDITOR_DIM);
+        let foreground_color = data
+            .config
+            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND);
         let padding = (self.line_height - 14.0) / 2.0;
         let mut i = 0;
         for (path, matches) in data.search.matches.iter() {
@@ -207,7 +400,7 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
             let (svg, svg_color) = file_svg(path);
             let rect = Size::new(self.line_height, self.line_height)
                 .to_rect()
-                .with_origin(Point::new(0.0, self.line_height * i as f64))
+                .with_origin(Point::new(0.0, row_y(i)))
                 .inflate(-padding, -padding);
             ctx.draw_svg(&svg, rect, svg_color);
 
@@ -231,11 +424,11 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
                 &text_layout,
                 Point::new(
                     self.line_height,
-                    self.line_height * i as f64
-                        + text_layout.y_offset(self.line_height),
+                    row_y(i) + text_layout.y_offset(self.line_height),
                 ),
             );
 
+            let file_path = path;
             let mut path: PathBuf = path.clone();
             if let Some(workspace_path) = data.workspace.path.as_ref() {
                 path = path
@@ -267,49 +460,95 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
                     .unwrap();
                 ctx.draw_text(
                     &text_layout,
-                    Point::new(
-                        x,
-                        self.line_height * i as f64
-                            + text_layout.y_offset(self.line_height),
-                    ),
+                    Point::new(x, row_y(i) + text_layout.y_offset(self.line_height)),
                 );
             }
 
-            for (line_number, (start, end), line) in matches {
+            for (index, m) in matches.iter().enumerate() {
                 i += 1;
                 if i > max {
                     return;
                 }
 
                 if i >= min {
+                    let (line_number, (start, end), line) = m;
+                    let included = !data.search.is_excluded(file_path, index);
+
+                    let checkbox_size = 10.0;
+                    let checkbox = Size::new(checkbox_size, checkbox_size)
+                        .to_rect()
+                        .with_origin(Point::new(
+                            (self.line_height - checkbox_size) / 2.0,
+                            row_y(i) + (self.line_height - checkbox_size) / 2.0,
+                        ));
+                    ctx.stroke(checkbox, foreground_color, 1.0);
+                    if included {
+                        ctx.fill(checkbox.inflate(-2.0, -2.0), foreground_color);
+                    }
+
+                    // preview the replacement after the text it replaces
+                    let replacement = if included && !data.search.replace.is_empty()
+                    {
+                        data.search.replacement(m)
+                    } else {
+                        None
+                    };
+                    let prefix = line_number.to_string().len() + 2;
+                    let text = match replacement.as_ref() {
+                        Some(replacement) => format!(
+                            "{line_number}: {}{replacement}{}",
+                            &line[..*end],
+                            &line[*end..]
+                        ),
+                        None => format!("{line_number}: {line}"),
+                    };
                     let mut text_layout = ctx
                         .text()
-                        .new_text_layout(format!("{line_number}: {line}"))
+                        .new_text_layout(text)
                         .font(
                             data.config.ui.font_family(),
                             data.config.ui.font_size() as f64,
                         )
-                        .text_color(
-                            data.config
-                                .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
-                                .clone(),
+                        .text_color(if included {
+                            foreground_color.clone()
+                        } else {
+                            dim_color.clone()
+                        });
+                    let match_range = *start + prefix..*end + prefix;
+                    if let Some(replacement) = replacement.as_ref() {
+                        let replacement_range =
+                            match_range.end..match_range.end + replacement.len();
+                        text_layout = text_layout
+                            .range_attribute(
+                                match_range,
+                                TextAttribute::TextColor(dim_color.clone()),
+                            )
+                            .range_attribute(
+                                replacement_range.clone(),
+                                TextAttribute::TextColor(focus_color.clone()),
+                            )
+                            .range_attribute(
+                                replacement_range,
+                                TextAttribute::Weight(FontWeight::BOLD),
+                            );
+                    } else {
+                        if included {
+                            text_layout = text_layout.range_attribute(
+                                match_range.clone(),
+                                TextAttribute::TextColor(focus_color.clone()),
+                            );
+                        }
+                        text_layout = text_layout.range_attribute(
+                            match_range,
+                            TextAttribute::Weight(FontWeight::BOLD),
                         );
-                    let prefix = line_number.to_string().len() + 2;
-                    text_layout = text_layout.range_attribute(
-                        *start + prefix..*end + prefix,
-                        TextAttribute::TextColor(focus_color.clone()),
-                    );
-                    text_layout = text_layout.range_attribute(
-                        *start + prefix..*end + prefix,
-                        TextAttribute::Weight(FontWeight::BOLD),
-                    );
+                    }
                     let text_layout = text_layout.build().unwrap();
                     ctx.draw_text(
                         &text_layout,
                         Point::new(
                             self.line_height,
-                            self.line_height * i as f64
-                                + text_layout.y_offset(self.line_height),
+                            row_y(i) + text_layout.y_offset(self.line_height),
                         ),
                     );
                 }
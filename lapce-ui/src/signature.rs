@@ -0,0 +1,285 @@
+use std::sync::Arc;
+
+use druid::{
+    kurbo::Line, BoxConstraints, Data, Env, Event, EventCtx, FontDescriptor,
+    FontWeight, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point, RenderContext,
+    Size, TextLayout, UpdateCtx, Widget, WidgetId,
+};
+use lapce_data::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    config::{Config, LapceTheme},
+    data::LapceTabData,
+    markdown::parse_markdown,
+    rich_text::{RichText, RichTextBuilder},
+    signature::{SignatureData, SignatureStatus},
+};
+use lsp_types::Documentation;
+
+/// The signature of the call the cursor is in, with the active parameter in
+/// bold, and the documentation of the parameter and of the signature
+pub struct SignatureContainer {
+    id: WidgetId,
+    label: TextLayout<RichText>,
+    documentation: Option<TextLayout<RichText>>,
+}
+
+impl SignatureContainer {
+    const PADDING: f64 = 10.0;
+
+    pub fn new(data: &SignatureData) -> Self {
+        Self {
+            id: data.id,
+            label: TextLayout::new(),
+            documentation: None,
+        }
+    }
+
+    fn update_text(&mut self, data: &LapceTabData) {
+        let config = &data.config;
+        let signature = &data.signature;
+
+        self.label.set_text(label_text(signature, config));
+        self.label.set_font(
+            FontDescriptor::new(config.editor.font_family())
+                .with_size(config.editor.font_size as f64),
+        );
+        self.label.set_text_color(
+            config
+                .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                .clone(),
+        );
+
+        self.documentation = documentation_text(signature, config).map(|text| {
+            let mut layout = TextLayout::new();
+            layout.set_text(text);
+            layout.set_font(
+                FontDescriptor::new(config.ui.hover_font_family())
+                    .with_size(config.ui.hover_font_size() as f64),
+            );
+            layout.set_text_color(
+                config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                    .clone(),
+            );
+            layout
+        });
+    }
+}
+
+impl Widget<LapceTabData> for SignatureContainer {
+    fn id(&self) -> Option<WidgetId> {
+        Some(self.id)
+    }
+
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        if let Event::Command(cmd) = event {
+            if let Some(LapceUICommand::UpdateSignature(request_id, signature)) =
+                cmd.get(LAPCE_UI_COMMAND)
+            {
+                ctx.set_handled();
+                let data = Arc::make_mut(&mut data.signature);
+                data.receive(*request_id, signature.clone());
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        let old_signature = &old_data.signature;
+        let signature = &data.signature;
+
+        if old_signature.status != signature.status
+            || !old_signature.signature.same(&signature.signature)
+            || old_signature.active_signature != signature.active_signature
+            || !old_data.config.same(&data.config)
+        {
+            self.update_text(data);
+            ctx.request_layout();
+        }
+
+        if signature.status == SignatureStatus::Done {
+            if old_signature.offset != signature.offset {
+                ctx.request_layout();
+            }
+            if let (Some(old_editor), Some(editor)) = (
+                old_data.main_split.editors.get(&signature.editor_view_id),
+                data.main_split.editors.get(&signature.editor_view_id),
+            ) {
+                if old_editor.window_origin != editor.window_origin
+                    || old_editor.scroll_offset != editor.scroll_offset
+                {
+                    ctx.request_layout();
+                }
+            }
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        _bc: &BoxConstraints,
+        data: &LapceTabData,
+        env: &Env,
+    ) -> Size {
+        let max_width = data.signature.size.width - Self::PADDING * 2.0;
+
+        self.label.set_wrap_width(max_width);
+        self.label.rebuild_if_needed(ctx.text(), env);
+        let label_size = self.label.size();
+
+        let mut width = label_size.width;
+        let mut height = label_size.height;
+        if let Some(documentation) = self.documentation.as_mut() {
+            documentation.set_wrap_width(max_width);
+            documentation.rebuild_if_needed(ctx.text(), env);
+            let size = documentation.size();
+            width = width.max(size.width);
+            height += Self::PADDING + size.height;
+        }
+
+        let size = Size::new(
+            width + Self::PADDING * 2.0,
+            (height + Self::PADDING).min(data.signature.size.height),
+        );
+        *data.signature.content_size.borrow_mut() = size;
+        ctx.set_paint_insets((10.0, 10.0, 10.0, 10.0));
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        if data.signature.status != SignatureStatus::Done
+            || data.signature.is_empty()
+        {
+            return;
+        }
+
+        let rect = ctx.size().to_rect();
+        let shadow_width = data.config.ui.drop_shadow_width() as f64;
+        if shadow_width > 0.0 {
+            ctx.blurred_rect(
+                rect,
+                shadow_width,
+                data.config
+                    .get_color_unchecked(LapceTheme::LAPCE_DROPDOWN_SHADOW),
+            );
+        } else {
+            ctx.stroke(
+                rect.inflate(0.5, 0.5),
+                data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+                1.0,
+            );
+        }
+        ctx.fill(
+            rect,
+            data.config
+                .get_color_unchecked(LapceTheme::HOVER_BACKGROUND),
+        );
+
+        ctx.with_save(|ctx| {
+            ctx.clip(rect);
+
+            let origin = Point::new(Self::PADDING, Self::PADDING / 2.0);
+            self.label.draw(ctx, origin);
+
+            if let Some(documentation) = self.documentation.as_ref() {
+                let y = origin.y + self.label.size().height + Self::PADDING / 2.0;
+                ctx.stroke(
+                    Line::new(
+                        Point::new(Self::PADDING, y),
+                        Point::new(rect.x1 - Self::PADDING, y),
+                    ),
+                    data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+                    1.0,
+                );
+                documentation
+                    .draw(ctx, Point::new(Self::PADDING, y + Self::PADDING / 2.0));
+            }
+        });
+    }
+}
+
+/// The label of the overload that is shown, with which one it is out of how
+/// many there are, and the active parameter in bold
+fn label_text(signature: &SignatureData, config: &Config) -> RichText {
+    let mut builder = RichTextBuilder::new();
+    if signature.len() > 1 {
+        builder
+            .push(&format!(
+                "{}/{}  ",
+                signature.active_signature + 1,
+                signature.len()
+            ))
+            .text_color(config.get_color_unchecked(LapceTheme::EDITOR_DIM).clone());
+    }
+    if let Some(current) = signature.current() {
+        let label = current.label.as_str();
+        match signature.active_parameter() {
+            Some((start, end))
+                if start <= end
+                    && label.is_char_boundary(start)
+                    && label.is_char_boundary(end) =>
+            {
+                builder.push(&label[..start]);
+                builder
+                    .push(&label[start..end])
+                    .weight(FontWeight::BOLD)
+                    .text_color(
+                        config.get_color_unchecked(LapceTheme::EDITOR_FOCUS).clone(),
+                    );
+                builder.push(&label[end..]);
+            }
+            _ => {
+                builder.push(label);
+            }
+        }
+    }
+    builder.build()
+}
+
+/// The documentation of the active parameter and of the overload that is
+/// shown, if they have any
+fn documentation_text(
+    signature: &SignatureData,
+    config: &Config,
+) -> Option<RichText> {
+    let current = signature.current()?;
+    let parameter = current
+        .active_parameter
+        .or_else(|| signature.signature.as_ref()?.active_parameter)
+        .and_then(|index| current.parameters.as_ref()?.get(index as usize))
+        .and_then(|parameter| parameter.documentation.as_ref());
+    let texts = parameter
+        .into_iter()
+        .chain(current.documentation.as_ref())
+        .map(|documentation| match documentation {
+            Documentation::String(text) => text.trim(),
+            Documentation::MarkupContent(content) => content.value.trim(),
+        })
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>();
+    if texts.is_empty() {
+        return None;
+    }
+    // plain text reads the same when it's parsed as markdown
+    Some(parse_markdown(&texts.join("\n\n"), config))
+}
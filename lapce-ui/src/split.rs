@@ -27,7 +27,7 @@
     keypress::{Alignment, DefaultKeyPressHandler, KeyMap},
     panel::PanelKind,
     split::{SplitDirection, SplitMoveDirection},
-    terminal::LapceTerminalData,
+    terminal::{LapceTerminalData, TerminalLaunch},
 };
 use lapce_rpc::terminal::TermId;
 
@@ -179,6 +179,9 @@ pub struct LapceSplit {
     show_border: bool,
     commands: Vec<(LapceCommand, PietTextLayout, Rect, Option<KeyMap>)>,
     panel: Option<PanelKind>,
+    /// The split this one is nested in, like a terminal split in the other
+    /// direction, which focus moves on to past the edges of this one
+    parent_split: Option<WidgetId>,
     /// Whether the resize bar is hovered  
     /// Contains the [`WidgetId`] of the child we are resizing
     bar_hovered: Option<WidgetId>,
@@ -214,6 +217,7 @@ pub fn new(split_id: WidgetId) -> Self {
             show_border: true,
             commands: vec![],
             panel: None,
+            parent_split: None,
             bar_hovered: None,
             non_flex_total: 0.0,
             total_size: 0.0,
@@ -233,6 +237,11 @@ pub fn panel(mut self, panel: PanelKind) -> Self {
         self
     }
 
+    pub fn parent_split(mut self, parent_split: WidgetId) -> Self {
+        self.parent_split = Some(parent_split);
+        self
+    }
+
     pub fn horizontal(mut self) -> Self {
         self.direction = SplitDirection::Horizontal;
         self
@@ -724,6 +733,12 @@ pub fn split_editor_move(
                 LapceUICommand::EnsureCursorVisible(None),
                 Target::Widget(self.children_ids[new_index]),
             ));
+        } else if let Some(parent_split) = self.parent_split {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::SplitEditorMove(*direction, self.split_id),
+                Target::Widget(parent_split),
+            ));
         }
     }
 
@@ -731,8 +746,9 @@ pub fn split_terminal(
         &mut self,
         ctx: &mut EventCtx,
         data: &mut LapceTabData,
-        _vertical: bool,
+        vertical: bool,
         widget_id: WidgetId,
+        launch: &TerminalLaunch,
     ) {
         let mut index = 0;
         for (i, child_id) in self.children_ids.iter().enumerate() {
@@ -742,12 +758,26 @@ pub fn split_terminal(
             }
         }
 
+        let direction = if vertical {
+            SplitDirection::Vertical
+        } else {
+            SplitDirection::Horizontal
+        };
+        if self.children.len() <= 1 {
+            self.direction = direction;
+        }
+        if self.direction != direction {
+            self.split_terminal_nested(ctx, data, index, direction, launch);
+            return;
+        }
+
         let terminal_data = Arc::new(LapceTerminalData::new(
             data.workspace.clone(),
             self.split_id,
             ctx.get_external_handle(),
             data.proxy.clone(),
             &data.config,
+            launch,
         ));
         let terminal = LapceTerminalView::new(&terminal_data);
         Arc::make_mut(&mut data.terminal)
@@ -765,6 +795,67 @@ pub fn split_terminal(
         ctx.children_changed();
     }
 
+    /// Split the terminal at the index across the direction of this split,
+    /// by replacing it with a split of its own, which holds it and the new
+    /// terminal
+    fn split_terminal_nested(
+        &mut self,
+        ctx: &mut EventCtx,
+        data: &mut LapceTabData,
+        index: usize,
+        direction: SplitDirection,
+        launch: &TerminalLaunch,
+    ) {
+        let widget_id = self.children_ids[index];
+        let terminal_panel = Arc::make_mut(&mut data.terminal);
+        let term_id = match terminal_panel
+            .terminals
+            .values()
+            .find(|terminal| terminal.widget_id == widget_id)
+        {
+            Some(terminal) => terminal.term_id,
+            None => return,
+        };
+
+        let split_id = WidgetId::next();
+        let terminal_data = terminal_panel.terminals.get_mut(&term_id).unwrap();
+        Arc::make_mut(terminal_data).split_id = split_id;
+        let terminal = LapceTerminalView::new(terminal_data);
+        let new_terminal_data = Arc::new(LapceTerminalData::new(
+            data.workspace.clone(),
+            split_id,
+            ctx.get_external_handle(),
+            data.proxy.clone(),
+            &data.config,
+            launch,
+        ));
+        let new_terminal = LapceTerminalView::new(&new_terminal_data);
+        Arc::make_mut(&mut data.terminal)
+            .terminals
+            .insert(new_terminal_data.term_id, new_terminal_data.clone());
+
+        let split = LapceSplit::new(split_id)
+            .direction(direction)
+            .parent_split(self.split_id)
+            .with_flex_child(terminal.boxed(), Some(widget_id), 1.0, true)
+            .with_flex_child(
+                new_terminal.boxed(),
+                Some(new_terminal_data.widget_id),
+                1.0,
+                true,
+            );
+        let params = self.children[index].params;
+        self.children.remove(index);
+        self.children_ids.remove(index);
+        self.insert_flex_child(index, split.boxed(), Some(split_id), params, true);
+        ctx.submit_command(Command::new(
+            LAPCE_UI_COMMAND,
+            LapceUICommand::Focus,
+            Target::Widget(widget_id),
+        ));
+        ctx.children_changed();
+    }
+
     pub fn split_terminal_close(
         &mut self,
         ctx: &mut EventCtx,
@@ -781,6 +872,17 @@ pub fn split_terminal_close(
             self.children.remove(0);
             self.children_ids.remove(0);
 
+            // A nested split that's emptied is closed in the split it's in
+            if let Some(parent_split) = self.parent_split {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::SplitTerminalClose(term_id, self.split_id),
+                    Target::Widget(parent_split),
+                ));
+                ctx.children_changed();
+                return;
+            }
+
             self.even_flex_children();
             ctx.children_changed();
             if data.panel.is_panel_visible(&PanelKind::Terminal) {
@@ -1110,6 +1212,16 @@ fn event(
                                 data.focus = Arc::new(self.split_id);
                                 data.focus_area = FocusArea::Editor;
                             }
+                        } else if let Some(child_id) = self
+                            .children_ids
+                            .first()
+                            .filter(|_| self.parent_split.is_some())
+                        {
+                            ctx.submit_command(Command::new(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::Focus,
+                                Target::Widget(*child_id),
+                            ));
                         }
                     }
                     LapceUICommand::SplitAdd(usize, content, focus_new) => {
@@ -1139,42 +1251,69 @@ fn event(
                     LapceUICommand::SplitEditorClose(widget_id) => {
                         self.split_editor_close(ctx, data, *widget_id);
                     }
-                    LapceUICommand::SplitTerminal(vertical, widget_id) => {
-                        self.split_terminal(ctx, data, *vertical, *widget_id);
+                    LapceUICommand::SplitTerminal(vertical, widget_id, launch) => {
+                        self.split_terminal(
+                            ctx, data, *vertical, *widget_id, launch,
+                        );
                     }
                     LapceUICommand::SplitTerminalClose(term_id, widget_id) => {
                         self.split_terminal_close(ctx, data, *term_id, *widget_id);
                     }
-                    LapceUICommand::InitTerminalPanel(focus) => {
+                    LapceUICommand::InitTerminalPanel(focus, launch) => {
                         if data.terminal.terminals.is_empty() {
-                            let terminal_data = Arc::new(LapceTerminalData::new(
-                                data.workspace.clone(),
-                                data.terminal.split_id,
-                                ctx.get_external_handle(),
-                                data.proxy.clone(),
-                                &data.config,
-                            ));
-                            let terminal = LapceTerminalView::new(&terminal_data);
-                            self.insert_flex_child(
-                                0,
-                                terminal.boxed(),
-                                Some(terminal_data.widget_id),
-                                1.0,
-                                true,
+                            // The terminals the workspace had when it was last
+                            // open are started again, along with the one asked
+                            // for, unless that's only the default terminal
+                            let restore = std::mem::take(
+                                &mut Arc::make_mut(&mut data.terminal).restore,
                             );
+                            let mut launches: Vec<TerminalLaunch> = restore
+                                .into_iter()
+                                .map(TerminalLaunch::Restore)
+                                .collect();
+                            if launches.is_empty()
+                                || !matches!(launch, TerminalLaunch::Profile(None))
+                            {
+                                launches.push(launch.clone());
+                            }
+                            for (index, launch) in launches.iter().enumerate() {
+                                let terminal_data =
+                                    Arc::new(LapceTerminalData::new(
+                                        data.workspace.clone(),
+                                        data.terminal.split_id,
+                                        ctx.get_external_handle(),
+                                        data.proxy.clone(),
+                                        &data.config,
+                                        launch,
+                                    ));
+                                let terminal =
+                                    LapceTerminalView::new(&terminal_data);
+                                self.insert_flex_child(
+                                    index,
+                                    terminal.boxed(),
+                                    Some(terminal_data.widget_id),
+                                    1.0,
+                                    true,
+                                );
+                                let terminal_panel =
+                                    Arc::make_mut(&mut data.terminal);
+                                terminal_panel.active = terminal_data.widget_id;
+                                terminal_panel.active_term_id =
+                                    terminal_data.term_id;
+                                terminal_panel
+                                    .terminals
+                                    .insert(terminal_data.term_id, terminal_data);
+                            }
+                            if launches.len() > 1 {
+                                self.even_flex_children();
+                            }
                             if *focus {
                                 ctx.submit_command(Command::new(
                                     LAPCE_UI_COMMAND,
                                     LapceUICommand::Focus,
-                                    Target::Widget(terminal_data.widget_id),
+                                    Target::Widget(data.terminal.active),
                                 ));
                             }
-                            let terminal_panel = Arc::make_mut(&mut data.terminal);
-                            terminal_panel.active = terminal_data.widget_id;
-                            terminal_panel.active_term_id = terminal_data.term_id;
-                            terminal_panel
-                                .terminals
-                                .insert(terminal_data.term_id, terminal_data);
                             ctx.children_changed();
                         }
                     }
@@ -1,14 +1,20 @@
+use std::{f64::consts::PI, time::Duration};
+
 use druid::{
-    kurbo::Line,
+    kurbo::{Arc, Line},
     piet::{PietTextLayout, Svg, Text, TextLayout, TextLayoutBuilder},
     Command, Data, Event, EventCtx, MouseEvent, PaintCtx, Point, Rect,
-    RenderContext, Size, Target, Widget,
+    RenderContext, Size, Target, TimerToken, Vec2, Widget,
 };
+use itertools::Itertools;
 use lapce_core::mode::Mode;
 use lapce_data::{
-    command::{CommandKind, LapceCommand, LapceWorkbenchCommand, LAPCE_COMMAND},
+    command::{
+        CommandKind, LapceCommand, LapceUICommand, LapceWorkbenchCommand,
+        LAPCE_COMMAND, LAPCE_UI_COMMAND,
+    },
     config::{Config, LapceTheme},
-    data::{FocusArea, LapceTabData},
+    data::{FocusArea, LapceTabData, WorkProgress},
     panel::{PanelContainerPosition, PanelKind},
 };
 
@@ -20,6 +26,12 @@ pub struct LapceStatus {
     mouse_pos: Point,
     icon_size: f64,
     active_icon: Option<Rect>,
+    /// Where the language server progress is, which opens the list of all
+    /// the progresses when it's clicked
+    progress_rect: Option<Rect>,
+    show_progresses: bool,
+    spinner_timer: TimerToken,
+    spinner_step: usize,
 }
 
 impl LapceStatus {
@@ -30,9 +42,21 @@ pub fn new() -> Self {
             mouse_pos: Point::ZERO,
             icon_size: 13.0,
             active_icon: None,
+            progress_rect: None,
+            show_progresses: false,
+            spinner_timer: TimerToken::INVALID,
+            spinner_step: 0,
         }
     }
 
+    /// The height of the list of progresses shown above the status bar
+    fn progresses_height(&self, data: &LapceTabData) -> f64 {
+        if !self.show_progresses || data.progresses.is_empty() {
+            return 0.0;
+        }
+        data.progresses.len() as f64 * progress_row_height(data) + 10.0
+    }
+
     fn panel_icons(&self, self_size: Size, data: &LapceTabData) -> Vec<LapceIcon> {
         let icons = [
             (
@@ -95,6 +119,12 @@ fn panel_icons(&self, self_size: Size, data: &LapceTabData) -> Vec<LapceIcon> {
     }
 
     fn icon_hit_test(&mut self, mouse_event: &MouseEvent) -> bool {
+        if let Some(rect) = self.progress_rect {
+            if rect.contains(mouse_event.pos) {
+                self.active_icon = Some(rect);
+                return true;
+            }
+        }
         for icon in self.panel_icons.iter() {
             if icon.rect.contains(mouse_event.pos) {
                 self.active_icon = Some(icon.rect);
@@ -110,7 +140,15 @@ fn icon_hit_test(&mut self, mouse_event: &MouseEvent) -> bool {
         false
     }
 
-    fn mouse_down(&self, ctx: &mut EventCtx, mouse_event: &MouseEvent) {
+    fn mouse_down(&mut self, ctx: &mut EventCtx, mouse_event: &MouseEvent) {
+        if let Some(rect) = self.progress_rect {
+            if rect.contains(mouse_event.pos) {
+                self.show_progresses = !self.show_progresses;
+                ctx.request_layout();
+                ctx.request_paint();
+                return;
+            }
+        }
         for icon in self.panel_icons.iter() {
             if icon.rect.contains(mouse_event.pos) {
                 ctx.submit_command(icon.command.clone());
@@ -205,6 +243,233 @@ fn paint_icon_with_label_from_right(
         );
         (right - text_layout.size().width, svg, (point, text_layout))
     }
+
+    /// Paint a label that runs the command when it's clicked, returning
+    /// the left edge of it
+    fn paint_clickable_label_from_right(
+        &mut self,
+        ctx: &mut PaintCtx,
+        data: &LapceTabData,
+        right: f64,
+        label: String,
+        cmd: LapceWorkbenchCommand,
+    ) -> f64 {
+        let size = ctx.size();
+        let x1 = right;
+        let (right, svg, (point, text_layout)) = self
+            .paint_icon_with_label_from_right(
+                right - 5.0,
+                size.height,
+                "",
+                label,
+                ctx,
+                &data.config,
+            );
+        let x0 = right - 5.0;
+        let rect = Rect::ZERO
+            .with_origin(Point::new(x0, 0.0))
+            .with_size(Size::new(x1 - x0, size.height));
+        if rect.contains(self.mouse_pos) {
+            ctx.fill(
+                rect,
+                data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+            );
+        }
+        if let Some((rect, svg)) = svg {
+            ctx.draw_svg(
+                &svg,
+                rect,
+                Some(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                ),
+            );
+        }
+        ctx.draw_text(&text_layout, point);
+        self.clickable_items.push((
+            rect,
+            Command::new(
+                LAPCE_COMMAND,
+                LapceCommand {
+                    kind: CommandKind::Workbench(cmd),
+                    data: None,
+                },
+                Target::Widget(data.id),
+            ),
+        ));
+        x0
+    }
+
+    /// Paint a spinner with the latest language server progress, returning
+    /// the right edge of it
+    fn paint_progress(
+        &mut self,
+        ctx: &mut PaintCtx,
+        data: &LapceTabData,
+        left: f64,
+    ) -> f64 {
+        let progress = match data.progresses.last() {
+            Some(progress) => progress,
+            None => {
+                self.progress_rect = None;
+                return left;
+            }
+        };
+        let size = ctx.size();
+
+        let mut text = progress_label(progress);
+        if data.progresses.len() > 1 {
+            text += &format!(" (+{})", data.progresses.len() - 1);
+        }
+        let text_layout = ctx
+            .text()
+            .new_text_layout(text)
+            .font(
+                data.config.ui.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+
+        let x0 = left + 5.0;
+        let x1 = x0 + 5.0 + self.icon_size + 5.0 + text_layout.size().width + 5.0;
+        let rect = Rect::new(x0, 0.0, x1, size.height);
+        if rect.contains(self.mouse_pos) || self.show_progresses {
+            ctx.fill(
+                rect,
+                data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+            );
+        }
+
+        let radius = self.icon_size / 2.0 - 1.0;
+        let spinner = Arc {
+            center: Point::new(x0 + 5.0 + self.icon_size / 2.0, size.height / 2.0),
+            radii: Vec2::new(radius, radius),
+            start_angle: self.spinner_step as f64 * PI / 4.0,
+            sweep_angle: PI * 3.0 / 2.0,
+            x_rotation: 0.0,
+        };
+        ctx.stroke(
+            spinner,
+            data.config
+                .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+            1.5,
+        );
+        ctx.draw_text(
+            &text_layout,
+            Point::new(
+                x0 + 5.0 + self.icon_size + 5.0,
+                text_layout.y_offset(size.height),
+            ),
+        );
+
+        self.progress_rect = Some(rect);
+        x1
+    }
+
+    /// Paint the list of all the progresses above the status bar, with the
+    /// language server reporting each of them
+    fn paint_progresses(&self, ctx: &mut PaintCtx, data: &LapceTabData) {
+        const WIDTH: f64 = 400.0;
+        const BAR_HEIGHT: f64 = 3.0;
+
+        let height = self.progresses_height(data);
+        let progress_rect = match self.progress_rect {
+            Some(rect) if height > 0.0 => rect,
+            _ => return,
+        };
+        let x0 = progress_rect.x0.min(ctx.size().width - WIDTH).max(0.0);
+        let rect = Rect::new(x0, -height, x0 + WIDTH, 0.0);
+
+        let shadow_width = data.config.ui.drop_shadow_width() as f64;
+        if shadow_width > 0.0 {
+            ctx.blurred_rect(
+                rect,
+                shadow_width,
+                data.config
+                    .get_color_unchecked(LapceTheme::LAPCE_DROPDOWN_SHADOW),
+            );
+        } else {
+            ctx.stroke(
+                rect.inflate(0.5, 0.5),
+                data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+                1.0,
+            );
+        }
+        ctx.fill(
+            rect,
+            data.config
+                .get_color_unchecked(LapceTheme::PANEL_BACKGROUND),
+        );
+
+        let row_height = progress_row_height(data);
+        for (i, progress) in data.progresses.iter().enumerate() {
+            let y = rect.y0 + 5.0 + i as f64 * row_height;
+            let text = format!("{}: {}", progress.server, progress_label(progress));
+            let text_layout = ctx
+                .text()
+                .new_text_layout(text)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                        .clone(),
+                )
+                .max_width(WIDTH - 20.0)
+                .build()
+                .unwrap();
+            ctx.with_save(|ctx| {
+                ctx.clip(rect);
+                ctx.draw_text(&text_layout, Point::new(x0 + 10.0, y));
+            });
+
+            if let Some(percentage) = progress.percentage {
+                let bar = Rect::new(
+                    x0 + 10.0,
+                    y + row_height - BAR_HEIGHT - 4.0,
+                    x0 + WIDTH - 10.0,
+                    y + row_height - 4.0,
+                );
+                ctx.fill(
+                    bar,
+                    data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+                );
+                ctx.fill(
+                    bar.with_size(Size::new(
+                        bar.width() * percentage.min(100) as f64 / 100.0,
+                        BAR_HEIGHT,
+                    )),
+                    data.config.get_color_unchecked(LapceTheme::EDITOR_CARET),
+                );
+            }
+        }
+    }
+}
+
+fn progress_row_height(data: &LapceTabData) -> f64 {
+    data.config.ui.status_height() as f64 + 8.0
+}
+
+/// The title of the progress with its message and percentage, like
+/// "Indexing 3/10 (30%)"
+fn progress_label(progress: &WorkProgress) -> String {
+    let mut text = progress.title.clone();
+    if let Some(message) = progress.message.as_ref() {
+        text += " ";
+        text += message;
+    }
+    if let Some(percentage) = progress.percentage {
+        text += &format!(" ({percentage}%)");
+    }
+    text
 }
 
 impl Default for LapceStatus {
@@ -218,10 +483,18 @@ fn event(
         &mut self,
         ctx: &mut druid::EventCtx,
         event: &Event,
-        _data: &mut LapceTabData,
+        data: &mut LapceTabData,
         _env: &druid::Env,
     ) {
         match event {
+            Event::Timer(token) if *token == self.spinner_timer => {
+                if !data.progresses.is_empty() {
+                    self.spinner_step = self.spinner_step.wrapping_add(1);
+                    self.spinner_timer =
+                        ctx.request_timer(Duration::from_millis(100), None);
+                    ctx.request_paint();
+                }
+            }
             Event::MouseMove(mouse_event) => {
                 self.mouse_pos = mouse_event.pos;
                 let active_icon = self.active_icon;
@@ -258,13 +531,33 @@ fn update(
         data: &LapceTabData,
         _env: &druid::Env,
     ) {
+        if !old_data.progresses.same(&data.progresses) {
+            if data.progresses.is_empty() {
+                self.show_progresses = false;
+            } else if old_data.progresses.is_empty() {
+                self.spinner_timer =
+                    ctx.request_timer(Duration::from_millis(100), None);
+            }
+            ctx.request_layout();
+        }
+
         match (
             old_data.main_split.active_editor(),
             data.main_split.active_editor(),
         ) {
-            (Some(old_data), Some(data)) => {
-                if old_data.cursor.get_mode() != data.cursor.get_mode()
-                    || old_data.editor_id != data.editor_id
+            (Some(old_editor), Some(editor)) => {
+                if old_editor.cursor.get_mode() != editor.cursor.get_mode()
+                    || old_editor.editor_id != editor.editor_id
+                {
+                    ctx.request_paint();
+                    return;
+                }
+
+                let old_doc = old_data.main_split.content_doc(&old_editor.content);
+                let doc = data.main_split.content_doc(&editor.content);
+                if old_doc.encoding() != doc.encoding()
+                    || old_doc.buffer().line_ending() != doc.buffer().line_ending()
+                    || old_doc.buffer().indent_style() != doc.buffer().indent_style()
                 {
                     ctx.request_paint();
                 }
@@ -280,14 +573,18 @@ fn update(
             return;
         }
 
-        if !old_data.progresses.same(&data.progresses) {
+        if !old_data.progresses.same(&data.progresses)
+            || !old_data.update_progress.same(&data.update_progress)
+            || !old_data.status_message.same(&data.status_message)
+            || !old_data.terminal.same(&data.terminal)
+        {
             ctx.request_paint();
         }
     }
 
     fn layout(
         &mut self,
-        _ctx: &mut druid::LayoutCtx,
+        ctx: &mut druid::LayoutCtx,
         bc: &druid::BoxConstraints,
         data: &LapceTabData,
         _env: &druid::Env,
@@ -295,6 +592,7 @@ fn layout(
         let self_size =
             Size::new(bc.max().width, data.config.ui.status_height() as f64);
         self.panel_icons = self.panel_icons(self_size, data);
+        ctx.set_paint_insets((0.0, self.progresses_height(data) + 10.0, 0.0, 0.0));
         self_size
     }
 
@@ -437,12 +735,73 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &druid::Env)
             ),
         ));
 
-        for progress in data.progresses.iter() {
-            let mut text = progress.title.clone();
-            if let Some(message) = progress.message.as_ref() {
-                text += ": ";
-                text += message;
+        // The terminals where something happened while they weren't focused
+        let alerts: Vec<_> = data
+            .terminal
+            .terminals
+            .values()
+            .filter_map(|terminal| Some((terminal, terminal.alert.as_ref()?)))
+            .sorted_by_key(|(terminal, _)| terminal.term_id.0)
+            .collect();
+        if let Some((terminal, alert)) = alerts.first() {
+            let label = if alerts.len() == 1 {
+                format!("{}: {}", terminal.display_title(), alert.label())
+            } else {
+                format!("{} terminals", alerts.len())
+            };
+            let x = left + 5.0;
+            let (new_left, svg, (point, text_layout)) = self.paint_icon_with_label(
+                left,
+                size.height,
+                "terminal.svg",
+                label,
+                ctx,
+                &data.config,
+            );
+            left = new_left;
+            let rect = Rect::ZERO
+                .with_origin(Point::new(x, 0.0))
+                .with_size(Size::new(left + 5.0 - x, size.height));
+            if rect.contains(self.mouse_pos) {
+                ctx.fill(
+                    rect,
+                    data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+                );
+            }
+            if let Some((rect, svg)) = svg {
+                ctx.draw_svg(
+                    &svg,
+                    rect,
+                    Some(data.config.get_color_unchecked(LapceTheme::EDITOR_FOCUS)),
+                );
             }
+            ctx.draw_text(&text_layout, point);
+            self.clickable_items.push((
+                rect,
+                Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::ShowTerminalAlert,
+                    Target::Widget(data.id),
+                ),
+            ));
+        }
+
+        left = self.paint_progress(ctx, data, left);
+
+        let icon_padding = (size.height - self.icon_size) / 2.0;
+        if let Some(progress) = data.update_progress.as_ref() {
+            const BAR_WIDTH: f64 = 100.0;
+            const BAR_HEIGHT: f64 = 4.0;
+
+            let text = match progress.fraction() {
+                Some(fraction) => {
+                    format!("Downloading update {:.0}%", fraction * 100.0)
+                }
+                None => format!(
+                    "Downloading update {:.1} MB",
+                    progress.downloaded as f64 / 1024.0 / 1024.0
+                ),
+            };
             let text_layout = ctx
                 .text()
                 .new_text_layout(text)
@@ -461,50 +820,77 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &druid::Env)
                 &text_layout,
                 Point::new(left + 10.0, text_layout.y_offset(size.height)),
             );
-            left += 10.0 + text_layout.size().width;
-        }
+            left += 10.0 + text_layout.size().width + 10.0;
 
-        let icon_padding = (size.height - self.icon_size) / 2.0;
-        for icon in self.panel_icons.iter() {
-            if icon.rect.contains(self.mouse_pos) {
+            let bar =
+                Size::new(BAR_WIDTH, BAR_HEIGHT)
+                    .to_rect()
+                    .with_origin(Point::new(
+                        left,
+                        ((size.height - BAR_HEIGHT) / 2.0).round(),
+                    ));
+            ctx.fill(
+                bar,
+                data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+            );
+            if let Some(fraction) = progress.fraction() {
                 ctx.fill(
-                    &icon.rect,
+                    bar.with_size(Size::new(BAR_WIDTH * fraction, BAR_HEIGHT)),
+                    data.config.get_color_unchecked(LapceTheme::EDITOR_CARET),
+                );
+            }
+            left += BAR_WIDTH + 5.0;
+
+            let cancel_rect = Size::new(size.height, size.height)
+                .to_rect()
+                .with_origin(Point::new(left, 0.0));
+            if cancel_rect.contains(self.mouse_pos) {
+                ctx.fill(
+                    cancel_rect,
                     data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
                 );
             }
-            if let Some(svg) = get_svg(icon.icon) {
+            if let Some(svg) = get_svg("close.svg") {
                 ctx.draw_svg(
                     &svg,
-                    icon.rect.inflate(-icon_padding, -icon_padding),
+                    cancel_rect.inflate(-icon_padding, -icon_padding),
                     Some(
                         data.config
                             .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
                     ),
                 );
             }
+            self.clickable_items.push((
+                cancel_rect,
+                Command::new(
+                    LAPCE_COMMAND,
+                    LapceCommand {
+                        kind: CommandKind::Workbench(
+                            LapceWorkbenchCommand::CancelUpdate,
+                        ),
+                        data: None,
+                    },
+                    Target::Widget(data.id),
+                ),
+            ));
+            left += size.height;
         }
 
-        let mut right = size.width - 5.0;
-        if let Some(editor) = &data.main_split.active_editor() {
-            let lang = match data.main_split.content_doc(&editor.content).syntax() {
-                Some(v) => v.language.to_string(),
-                None => String::from("Plain Text"),
-            };
-            let x1 = right;
-            let (new_right, svg, (point, text_layout)) = self
-                .paint_icon_with_label_from_right(
-                    right - 5.0,
-                    size.height,
-                    "",
-                    lang,
-                    ctx,
-                    &data.config,
-                );
-            right = new_right;
-            let x0 = right - 5.0;
+        if let Some(message) = data.status_message.as_ref() {
+            // only the first line fits in the status bar
+            let message = message.lines().next().unwrap_or("").to_string();
+            let x = left + 5.0;
+            let (new_left, svg, (point, text_layout)) = self.paint_icon_with_label(
+                left,
+                size.height,
+                "warning.svg",
+                message,
+                ctx,
+                &data.config,
+            );
             let rect = Rect::ZERO
-                .with_origin(Point::new(x0, 0.0))
-                .with_size(Size::new(x1 - x0, size.height));
+                .with_origin(Point::new(x, 0.0))
+                .with_size(Size::new(new_left + 5.0 - x, size.height));
             if rect.contains(self.mouse_pos) {
                 ctx.fill(
                     rect,
@@ -522,19 +908,75 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &druid::Env)
                 );
             }
             ctx.draw_text(&text_layout, point);
+            // clicking the message dismisses it
             self.clickable_items.push((
                 rect,
                 Command::new(
-                    LAPCE_COMMAND,
-                    LapceCommand {
-                        kind: CommandKind::Workbench(
-                            LapceWorkbenchCommand::ChangeFileLanguage,
-                        ),
-                        data: None,
-                    },
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::ClearStatusMessage,
                     Target::Widget(data.id),
                 ),
             ));
         }
+
+        for icon in self.panel_icons.iter() {
+            if icon.rect.contains(self.mouse_pos) {
+                ctx.fill(
+                    &icon.rect,
+                    data.config.get_color_unchecked(LapceTheme::PANEL_CURRENT),
+                );
+            }
+            if let Some(svg) = get_svg(icon.icon) {
+                ctx.draw_svg(
+                    &svg,
+                    icon.rect.inflate(-icon_padding, -icon_padding),
+                    Some(
+                        data.config
+                            .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                    ),
+                );
+            }
+        }
+
+        if let Some(editor) = &data.main_split.active_editor() {
+            let doc = data.main_split.content_doc(&editor.content);
+            let lang = match doc.syntax() {
+                Some(v) => v.language.to_string(),
+                None => String::from("Plain Text"),
+            };
+            let right = self.paint_clickable_label_from_right(
+                ctx,
+                data,
+                size.width - 5.0,
+                lang,
+                LapceWorkbenchCommand::ChangeFileLanguage,
+            );
+
+            if doc.content().is_file() {
+                let right = self.paint_clickable_label_from_right(
+                    ctx,
+                    data,
+                    right,
+                    doc.encoding().to_string(),
+                    LapceWorkbenchCommand::ReopenWithEncoding,
+                );
+                let right = self.paint_clickable_label_from_right(
+                    ctx,
+                    data,
+                    right,
+                    doc.buffer().line_ending().label().to_string(),
+                    LapceWorkbenchCommand::ChangeLineEnding,
+                );
+                self.paint_clickable_label_from_right(
+                    ctx,
+                    data,
+                    right,
+                    doc.buffer().indent_style().label(),
+                    LapceWorkbenchCommand::ChangeIndentation,
+                );
+            }
+        }
+
+        self.paint_progresses(ctx, data);
     }
 }
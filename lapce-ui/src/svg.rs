@@ -33,7 +33,7 @@ fn new() -> Self {
         Self { svgs }
     }
 
-    fn get_svg(&self, name: &'static str) -> Option<Svg> {
+    fn get_svg(&self, name: &str) -> Option<Svg> {
         self.svgs.get(name).and_then(Clone::clone)
     }
 }
@@ -46,6 +46,17 @@ pub fn get_svg(name: &'static str) -> Option<Svg> {
     SVG_STORE.get_svg(name)
 }
 
+/// The icon of a terminal profile, which names one of the icons, with or
+/// without its `.svg`, or else the terminal icon
+pub fn terminal_svg(icon: Option<&str>) -> Svg {
+    icon.and_then(|icon| {
+        SVG_STORE
+            .get_svg(icon)
+            .or_else(|| SVG_STORE.get_svg(&format!("{icon}.svg")))
+    })
+    .unwrap_or_else(|| get_svg("terminal.svg").unwrap())
+}
+
 pub fn file_svg(path: &Path) -> (Svg, Option<&Color>) {
     let icon_name: Option<&str>;
     let icon_color: Option<&Color>;
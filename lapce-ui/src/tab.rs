@@ -1,5 +1,6 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
 
+use alacritty_terminal::{grid::Scroll, index::Direction};
 use druid::{
     kurbo::Line,
     piet::{PietTextLayout, Text, TextLayout, TextLayoutBuilder},
@@ -24,9 +25,10 @@
     completion::CompletionStatus,
     config::{Config, LapceTheme},
     data::{
-        DragContent, EditorDiagnostic, FocusArea, LapceData, LapceTabData,
-        LapceWindowData, LapceWorkspace, LapceWorkspaceType, WorkProgress,
+        DragContent, FocusArea, LapceData, LapceTabData, LapceWindowData,
+        LapceWorkspace, LapceWorkspaceType, LinkedEditing, WorkProgress,
     },
+    debug_console::ConsoleLineKind,
     document::{BufferContent, LocalBufferKind},
     editor::EditorLocation,
     hover::HoverStatus,
@@ -38,19 +40,43 @@
         PanelStyle,
     },
     plugin::plugin_install_status::{PluginInstallStatus, PluginInstallType},
+    problem::ProblemFilter,
     proxy::path_from_url,
+    selection_range::ExpandSelectionRanges,
+    signature::SignatureStatus,
+    terminal::{TerminalAlert, TerminalLaunch},
 };
 use lapce_rpc::proxy::ProxyResponse;
-use lsp_types::DiagnosticSeverity;
+use lsp_types::Location;
 use xi_rope::Rope;
 
 use crate::{
-    about::AboutBox, alert::AlertBox, completion::CompletionContainer,
-    editor::view::LapceEditorView, explorer::FileExplorer, hover::HoverContainer,
-    panel::PanelContainer, picker::FilePicker, plugin::Plugin,
-    problem::new_problem_panel, search::new_search_panel,
-    source_control::new_source_control_panel, split::split_data_widget,
-    status::LapceStatus, svg::get_svg, terminal::TerminalPanel, title::Title,
+    about::AboutBox,
+    alert::AlertBox,
+    completion::CompletionContainer,
+    debug::new_debug_panel,
+    debug_console::new_debug_console_panel,
+    editor::view::LapceEditorView,
+    explorer::FileExplorer,
+    hierarchy::{new_call_hierarchy_panel, new_type_hierarchy_panel},
+    hover::HoverContainer,
+    lsp_log::new_lsp_log_panel,
+    panel::PanelContainer,
+    peek::Peek,
+    picker::FilePicker,
+    plugin::Plugin,
+    problem::new_problem_panel,
+    release_notes::ReleaseNotesBox,
+    rename_preview::new_rename_preview_panel,
+    search::new_search_panel,
+    signature::SignatureContainer,
+    source_control::new_source_control_panel,
+    split::split_data_widget,
+    status::LapceStatus,
+    svg::get_svg,
+    terminal::TerminalPanel,
+    title::Title,
+    undo_tree::new_undo_tree_panel,
 };
 
 pub const LAPCE_TAB_META: Selector<SingleUse<LapceTabMeta>> =
@@ -79,11 +105,14 @@ pub struct LapceTab {
     main_split: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     completion: WidgetPod<LapceTabData, CompletionContainer>,
     hover: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
+    signature: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     rename: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
+    peek: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     status: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     picker: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     about: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     alert: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
+    release_notes: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
     panel_left: WidgetPod<LapceTabData, PanelContainer>,
     panel_bottom: WidgetPod<LapceTabData, PanelContainer>,
     panel_right: WidgetPod<LapceTabData, PanelContainer>,
@@ -107,16 +136,19 @@ pub fn new(data: &mut LapceTabData) -> Self {
 
         let completion = CompletionContainer::new(&data.completion);
         let hover = HoverContainer::new(&data.hover);
+        let signature = SignatureContainer::new(&data.signature);
         let rename =
             LapceEditorView::new(data.rename.view_id, data.rename.editor_id, None)
                 .hide_header()
                 .hide_gutter()
                 .padding((10.0, 5.0, 10.0, 5.0));
+        let peek = Peek::new(data);
         let status = LapceStatus::new();
         let picker = FilePicker::new(data);
 
         let about = AboutBox::new(data);
         let alert = AlertBox::new(data);
+        let release_notes = ReleaseNotesBox::new(data);
 
         let mut panel_left = PanelContainer::new(PanelContainerPosition::Left);
         let mut panel_bottom = PanelContainer::new(PanelContainerPosition::Bottom);
@@ -169,7 +201,60 @@ pub fn new(data: &mut LapceTabData) -> Self {
                     PanelKind::Problem => {
                         panel.insert_panel(
                             *kind,
-                            WidgetPod::new(new_problem_panel(&data.problem).boxed()),
+                            WidgetPod::new(new_problem_panel(data).boxed()),
+                        );
+                    }
+                    PanelKind::UndoTree => {
+                        panel.insert_panel(
+                            *kind,
+                            WidgetPod::new(
+                                new_undo_tree_panel(&data.undo_tree).boxed(),
+                            ),
+                        );
+                    }
+                    PanelKind::CallHierarchy => {
+                        panel.insert_panel(
+                            *kind,
+                            WidgetPod::new(
+                                new_call_hierarchy_panel(&data.call_hierarchy)
+                                    .boxed(),
+                            ),
+                        );
+                    }
+                    PanelKind::TypeHierarchy => {
+                        panel.insert_panel(
+                            *kind,
+                            WidgetPod::new(
+                                new_type_hierarchy_panel(&data.type_hierarchy)
+                                    .boxed(),
+                            ),
+                        );
+                    }
+                    PanelKind::RenamePreview => {
+                        panel.insert_panel(
+                            *kind,
+                            WidgetPod::new(
+                                new_rename_preview_panel(&data.rename_preview)
+                                    .boxed(),
+                            ),
+                        );
+                    }
+                    PanelKind::LspLog => {
+                        panel.insert_panel(
+                            *kind,
+                            WidgetPod::new(new_lsp_log_panel(&data.lsp_log).boxed()),
+                        );
+                    }
+                    PanelKind::Debug => {
+                        panel.insert_panel(
+                            *kind,
+                            WidgetPod::new(new_debug_panel(&data.debug).boxed()),
+                        );
+                    }
+                    PanelKind::DebugConsole => {
+                        panel.insert_panel(
+                            *kind,
+                            WidgetPod::new(new_debug_console_panel(data).boxed()),
                         );
                     }
                 }
@@ -182,11 +267,14 @@ pub fn new(data: &mut LapceTabData) -> Self {
             main_split: WidgetPod::new(main_split.boxed()),
             completion: WidgetPod::new(completion),
             hover: WidgetPod::new(hover.boxed()),
+            signature: WidgetPod::new(signature.boxed()),
             rename: WidgetPod::new(rename.boxed()),
+            peek: WidgetPod::new(peek.boxed()),
             picker: WidgetPod::new(picker.boxed()),
             status: WidgetPod::new(status.boxed()),
             about: WidgetPod::new(about.boxed()),
             alert: WidgetPod::new(alert.boxed()),
+            release_notes: WidgetPod::new(release_notes.boxed()),
             panel_left: WidgetPod::new(panel_left),
             panel_right: WidgetPod::new(panel_right),
             panel_bottom: WidgetPod::new(panel_bottom),
@@ -786,11 +874,11 @@ fn handle_command_event(
                     LapceUICommand::UpdateSearch(pattern) => {
                         if pattern.is_empty() {
                             Arc::make_mut(&mut data.find).unset();
-                            Arc::make_mut(&mut data.search).matches =
-                                Arc::new(HashMap::new());
+                            Arc::make_mut(&mut data.search).clear_matches();
                         } else {
+                            let is_regex = data.search.is_regex;
                             let find = Arc::make_mut(&mut data.find);
-                            find.set_find(pattern, false, false, false);
+                            find.set_find(pattern, false, is_regex, false);
                             find.visual = true;
                             if data.focus_area == FocusArea::Panel(PanelKind::Search)
                             {
@@ -812,6 +900,7 @@ fn handle_command_event(
                             let tab_id = data.id;
                             data.proxy.proxy_rpc.global_search(
                                 pattern.clone(),
+                                is_regex,
                                 Box::new(move |result| {
                                     if let Ok(
                                         ProxyResponse::GlobalSearchResponse {
@@ -823,6 +912,7 @@ fn handle_command_event(
                                             LAPCE_UI_COMMAND,
                                             LapceUICommand::GlobalSearchResult(
                                                 pattern,
+                                                is_regex,
                                                 Arc::new(matches),
                                             ),
                                             Target::Widget(tab_id),
@@ -835,15 +925,27 @@ fn handle_command_event(
                     LapceUICommand::OpenPluginInfo(volt) => {
                         data.main_split.open_plugin_info(ctx, volt);
                     }
-                    LapceUICommand::GlobalSearchResult(pattern, matches) => {
+                    LapceUICommand::UpdateSearchReplace(replace) => {
+                        Arc::make_mut(&mut data.search).replace = replace.clone();
+                    }
+                    LapceUICommand::UpdateFindReplace(replace) => {
+                        Arc::make_mut(&mut data.find).replace = replace.clone();
+                    }
+                    LapceUICommand::GlobalSearchResult(
+                        pattern,
+                        is_regex,
+                        matches,
+                    ) => {
                         let doc = data
                             .main_split
                             .local_docs
                             .get(&LocalBufferKind::Search)
                             .unwrap();
-                        if &doc.buffer().text().slice_to_cow(..) == pattern {
-                            Arc::make_mut(&mut data.search).matches =
-                                matches.clone();
+                        if &doc.buffer().text().slice_to_cow(..) == pattern
+                            && *is_regex == data.search.is_regex
+                        {
+                            Arc::make_mut(&mut data.search)
+                                .set_matches(pattern, matches.clone());
                         }
                     }
                     LapceUICommand::LoadBufferHead {
@@ -884,6 +986,89 @@ fn handle_command_event(
                             Arc::make_mut(terminal).title = title.to_string();
                         }
                     }
+                    LapceUICommand::UpdateTerminalProcess(term_id, name) => {
+                        let terminal_panel = Arc::make_mut(&mut data.terminal);
+                        if let Some(terminal) =
+                            terminal_panel.terminals.get_mut(term_id)
+                        {
+                            Arc::make_mut(terminal).process = name.clone();
+                        }
+                    }
+                    LapceUICommand::RenameTerminal(term_id, name) => {
+                        ctx.set_handled();
+                        let terminal_panel = Arc::make_mut(&mut data.terminal);
+                        if let Some(terminal) =
+                            terminal_panel.terminals.get_mut(term_id)
+                        {
+                            Arc::make_mut(terminal).name = name.clone();
+                        }
+                    }
+                    LapceUICommand::SetTerminalColor(term_id, color) => {
+                        ctx.set_handled();
+                        let terminal_panel = Arc::make_mut(&mut data.terminal);
+                        if let Some(terminal) =
+                            terminal_panel.terminals.get_mut(term_id)
+                        {
+                            Arc::make_mut(terminal).color = color.clone();
+                        }
+                    }
+                    LapceUICommand::TerminalAlert(term_id, alert) => {
+                        ctx.set_handled();
+                        let show = match alert {
+                            TerminalAlert::Bell => {
+                                data.config.terminal.bell_notification
+                            }
+                            TerminalAlert::CommandFinished { duration, .. } => {
+                                let seconds = data
+                                    .config
+                                    .terminal
+                                    .command_notification_seconds
+                                    as u64;
+                                seconds > 0 && duration.as_secs() >= seconds
+                            }
+                        };
+                        if show && !data.is_terminal_focused(*term_id) {
+                            let terminal_panel = Arc::make_mut(&mut data.terminal);
+                            if let Some(terminal) =
+                                terminal_panel.terminals.get_mut(term_id)
+                            {
+                                Arc::make_mut(terminal).alert = Some(alert.clone());
+                            }
+                        }
+                    }
+                    LapceUICommand::ShowTerminalAlert => {
+                        ctx.set_handled();
+                        let widget_id = data
+                            .terminal
+                            .terminals
+                            .values()
+                            .filter(|terminal| terminal.alert.is_some())
+                            .min_by_key(|terminal| terminal.term_id.0)
+                            .map(|terminal| terminal.widget_id);
+                        if let Some(widget_id) = widget_id {
+                            data.show_panel(ctx, PanelKind::Terminal);
+                            ctx.submit_command(Command::new(
+                                LAPCE_UI_COMMAND,
+                                LapceUICommand::Focus,
+                                Target::Widget(widget_id),
+                            ));
+                        }
+                    }
+                    LapceUICommand::SetTerminalIcon(term_id, icon) => {
+                        ctx.set_handled();
+                        let terminal_panel = Arc::make_mut(&mut data.terminal);
+                        if let Some(terminal) =
+                            terminal_panel.terminals.get_mut(term_id)
+                        {
+                            let icon = icon.clone().or_else(|| {
+                                data.config
+                                    .terminal_profile(terminal.profile.as_deref())
+                                    .1
+                                    .icon
+                            });
+                            Arc::make_mut(terminal).icon = icon;
+                        }
+                    }
                     LapceUICommand::CancelFilePicker => {
                         Arc::make_mut(&mut data.picker).active = false;
                         ctx.set_handled();
@@ -906,6 +1091,7 @@ fn handle_command_event(
                         input,
                         resp,
                         plugin_id,
+                        priority,
                     ) => {
                         let completion = Arc::make_mut(&mut data.completion);
                         completion.receive(
@@ -913,6 +1099,7 @@ fn handle_command_event(
                             input.to_owned(),
                             resp.to_owned(),
                             *plugin_id,
+                            *priority,
                         );
                     }
                     LapceUICommand::CancelCompletion(request_id) => {
@@ -1077,115 +1264,368 @@ fn handle_command_event(
                         }
                         ctx.set_handled();
                     }
-                    LapceUICommand::WorkDoneProgress(params) => {
-                        match &params.value {
-                            lsp_types::ProgressParamsValue::WorkDone(progress) => {
-                                match progress {
-                                    lsp_types::WorkDoneProgress::Begin(begin) => {
-                                        Arc::make_mut(&mut data.progresses).push(
-                                            WorkProgress {
-                                                token: params.token.clone(),
-                                                title: begin.title.clone(),
-                                                message: begin.message.clone(),
-                                                percentage: begin.percentage,
-                                            },
-                                        );
-                                    }
-                                    lsp_types::WorkDoneProgress::Report(report) => {
-                                        for p in Arc::make_mut(&mut data.progresses)
-                                            .iter_mut()
+                    LapceUICommand::LspLog {
+                        plugin_id,
+                        server,
+                        kind,
+                        message,
+                    } => {
+                        Arc::make_mut(&mut data.lsp_log)
+                            .push(*plugin_id, server, *kind, message);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DebugStart(config) => {
+                        data.start_debug(ctx, config.clone(), None);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DebugAttachProcess { config, pid } => {
+                        data.start_debug(ctx, config.clone(), Some(*pid));
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ToggleBreakpoint { path, line } => {
+                        data.toggle_breakpoint(path, *line);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::SetWatchExpression { index, expression } => {
+                        data.set_watch_expression(ctx, *index, expression);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::RemoveWatchExpression(index) => {
+                        Arc::make_mut(&mut data.debug).remove_watch(*index);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ToggleWatchExpanded {
+                        path,
+                        variables_reference,
+                    } => {
+                        data.toggle_watch_expanded(ctx, path, *variables_reference);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapWatchValue {
+                        dap_id,
+                        expression,
+                        value,
+                    } => {
+                        data.watch_value(ctx, *dap_id, expression, value.clone());
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapWatchChildren {
+                        dap_id,
+                        path,
+                        variables,
+                    } => {
+                        data.watch_children(ctx, *dap_id, path, variables.clone());
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapOutput {
+                        category, output, ..
+                    } => {
+                        Arc::make_mut(&mut data.debug_console)
+                            .push_output(category.as_deref(), output);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DebugConsoleEvaluate => {
+                        data.debug_console_evaluate(ctx);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DebugConsoleHistory { previous } => {
+                        data.debug_console_history(*previous);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DebugConsoleComplete => {
+                        data.debug_console_complete(ctx);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapConsoleResult { dap_id, result } => {
+                        if data.debug.active_dap == Some(*dap_id) {
+                            let (kind, text) = match result {
+                                Ok(result) => {
+                                    (ConsoleLineKind::Result, result.result.as_str())
+                                }
+                                Err(e) => (ConsoleLineKind::Error, e.as_str()),
+                            };
+                            Arc::make_mut(&mut data.debug_console)
+                                .push_line(kind, text);
+                        }
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapConsoleCompletions {
+                        dap_id,
+                        text,
+                        cursor,
+                        targets,
+                    } => {
+                        data.debug_console_completions(
+                            *dap_id, text, *cursor, targets,
+                        );
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::SetBreakpointOption {
+                        path,
+                        line,
+                        kind,
+                        value,
+                    } => {
+                        data.set_breakpoint_option(path, *line, *kind, value);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapStopped {
+                        dap_id,
+                        stopped,
+                        threads,
+                        stack_frames,
+                        variables,
+                    } => {
+                        Arc::make_mut(&mut data.debug).stopped(
+                            *dap_id,
+                            stopped.clone(),
+                            threads.clone(),
+                            stack_frames.clone(),
+                            variables.clone(),
+                        );
+                        data.jump_to_stopped_location(ctx);
+                        data.evaluate_watches(ctx);
+                        data.refresh_debug_memory(ctx, *dap_id);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapCapabilities {
+                        dap_id,
+                        capabilities,
+                    } => {
+                        Arc::make_mut(&mut data.debug)
+                            .set_capabilities(*dap_id, capabilities.clone());
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::SelectDebugThread(thread_id) => {
+                        data.select_debug_thread(ctx, *thread_id);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::SelectDebugFrame(index) => {
+                        data.select_debug_frame(ctx, *index);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ToggleDebugFramesExpanded(start) => {
+                        Arc::make_mut(&mut data.debug)
+                            .toggle_frames_expanded(*start);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::RestartDebugFrame(frame_id) => {
+                        data.restart_debug_frame(*frame_id);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapThreadStack {
+                        dap_id,
+                        thread_id,
+                        stack_frames,
+                    } => {
+                        data.thread_stack(
+                            ctx,
+                            *dap_id,
+                            *thread_id,
+                            stack_frames.clone(),
+                        );
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapFrameVariables {
+                        dap_id,
+                        frame_id,
+                        variables,
+                    } => {
+                        Arc::make_mut(&mut data.debug).set_frame_variables(
+                            *dap_id,
+                            *frame_id,
+                            variables.clone(),
+                        );
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapContinued(dap_id) => {
+                        Arc::make_mut(&mut data.debug).continued(*dap_id);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapTerminated(dap_id) => {
+                        Arc::make_mut(&mut data.debug).terminated(*dap_id);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapBreakpoints {
+                        path, breakpoints, ..
+                    } => {
+                        Arc::make_mut(&mut data.debug)
+                            .set_verified(path, breakpoints);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ReadDebugMemory {
+                        memory_reference,
+                        offset,
+                    } => {
+                        data.read_debug_memory(ctx, memory_reference, *offset);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::CloseDebugMemory => {
+                        Arc::make_mut(&mut data.debug).memory = None;
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapMemory {
+                        dap_id,
+                        memory_reference,
+                        offset,
+                        result,
+                    } => {
+                        Arc::make_mut(&mut data.debug).set_memory(
+                            *dap_id,
+                            memory_reference,
+                            *offset,
+                            result.clone(),
+                        );
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::AddDataBreakpoint {
+                        variables_reference,
+                        name,
+                    } => {
+                        data.request_data_breakpoint(
+                            ctx,
+                            *variables_reference,
+                            name.clone(),
+                        );
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::RemoveDataBreakpoint(index) => {
+                        data.remove_data_breakpoint(*index);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::DapDataBreakpointInfo { dap_id, name, info } => {
+                        data.add_data_breakpoint(*dap_id, name, info.clone());
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::WorkDoneProgress {
+                        plugin_id,
+                        server,
+                        progress: params,
+                    } => match &params.value {
+                        lsp_types::ProgressParamsValue::WorkDone(progress) => {
+                            match progress {
+                                lsp_types::WorkDoneProgress::Begin(begin) => {
+                                    Arc::make_mut(&mut data.progresses).push(
+                                        WorkProgress {
+                                            plugin_id: *plugin_id,
+                                            server: server.clone(),
+                                            token: params.token.clone(),
+                                            title: begin.title.clone(),
+                                            message: begin.message.clone(),
+                                            percentage: begin.percentage,
+                                        },
+                                    );
+                                }
+                                lsp_types::WorkDoneProgress::Report(report) => {
+                                    for p in Arc::make_mut(&mut data.progresses)
+                                        .iter_mut()
+                                    {
+                                        if p.plugin_id == *plugin_id
+                                            && p.token == params.token
                                         {
-                                            if p.token == params.token {
+                                            if report.message.is_some() {
                                                 p.message = report.message.clone();
+                                            }
+                                            if report.percentage.is_some() {
                                                 p.percentage = report.percentage;
                                             }
                                         }
                                     }
-                                    lsp_types::WorkDoneProgress::End(_end) => {
-                                        for view_id in data.main_split.editors.keys()
-                                        {
-                                            let editor_data =
-                                                data.editor_view_content(*view_id);
-                                            editor_data.doc.get_inlay_hints();
-                                        }
-                                        for i in data
-                                            .progresses
-                                            .iter()
-                                            .positions(|p| p.token == params.token)
-                                            .sorted()
-                                            .rev()
-                                        {
-                                            Arc::make_mut(&mut data.progresses)
-                                                .remove(i);
-                                        }
+                                }
+                                lsp_types::WorkDoneProgress::End(_end) => {
+                                    for view_id in data.main_split.editors.keys() {
+                                        let editor_data =
+                                            data.editor_view_content(*view_id);
+                                        editor_data.doc.get_inlay_hints();
+                                        editor_data.doc.get_code_lenses();
+                                    }
+                                    for i in data
+                                        .progresses
+                                        .iter()
+                                        .positions(|p| {
+                                            p.plugin_id == *plugin_id
+                                                && p.token == params.token
+                                        })
+                                        .sorted()
+                                        .rev()
+                                    {
+                                        Arc::make_mut(&mut data.progresses)
+                                            .remove(i);
                                     }
                                 }
                             }
                         }
-                    }
+                    },
                     LapceUICommand::PublishDiagnostics(diagnostics) => {
                         let path = path_from_url(&diagnostics.uri);
-                        let diagnostics = diagnostics
-                            .diagnostics
+                        data.main_split.server_diagnostics.insert(
+                            path.clone(),
+                            Arc::new(diagnostics.diagnostics.clone()),
+                        );
+                        data.main_split.update_diagnostics(&path);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::PublishTaskProblems(task, problems) => {
+                        let old_problems = data
+                            .main_split
+                            .task_problems
+                            .insert(task.clone(), Arc::new(problems.clone()));
+                        // The files that had problems in the last run are
+                        // updated too, as their problems are gone
+                        let paths: HashSet<PathBuf> = old_problems
                             .iter()
-                            .map(|d| EditorDiagnostic {
-                                range: (0, 0),
-                                diagnostic: d.clone(),
-                                lines: d
-                                    .related_information
-                                    .as_ref()
-                                    .map(|r| {
-                                        r.iter()
-                                            .map(|r| {
-                                                r.message.matches('\n').count()
-                                                    + 1
-                                                    + 1
-                                            })
-                                            .sum()
-                                    })
-                                    .unwrap_or(0)
-                                    + d.message.matches('\n').count()
-                                    + 1,
-                            })
-                            .sorted_by_key(|d| d.diagnostic.range.start)
+                            .flat_map(|problems| problems.iter())
+                            .chain(problems.iter())
+                            .map(|(path, _)| path.clone())
                             .collect();
-                        let diagnostics: Arc<Vec<EditorDiagnostic>> =
-                            Arc::new(diagnostics);
-
-                        // inform the document about the diagnostics
-                        if let Some(document) =
-                            data.main_split.open_docs.get_mut(&path)
-                        {
-                            let document = Arc::make_mut(document);
-                            document.set_diagnostics(&diagnostics);
-                        }
-
-                        data.main_split.diagnostics.insert(path, diagnostics);
-
-                        let mut errors = 0;
-                        let mut warnings = 0;
-                        for (_, diagnostics) in data.main_split.diagnostics.iter() {
-                            for diagnostic in diagnostics.iter() {
-                                if let Some(severity) =
-                                    diagnostic.diagnostic.severity
-                                {
-                                    match severity {
-                                        DiagnosticSeverity::ERROR => errors += 1,
-                                        DiagnosticSeverity::WARNING => warnings += 1,
-                                        _ => (),
-                                    }
-                                }
-                            }
+                        for path in paths {
+                            data.main_split.update_diagnostics(&path);
                         }
-                        data.main_split.error_count = errors;
-                        data.main_split.warning_count = warnings;
-
                         ctx.set_handled();
                     }
                     LapceUICommand::DocumentSave(path, exit) => {
                         data.main_split.document_save(ctx, path, *exit);
                         ctx.set_handled();
                     }
+                    LapceUICommand::AutoSave(trigger, path) => {
+                        ctx.set_handled();
+                        data.main_split.autosave(
+                            ctx,
+                            &data.config,
+                            *trigger,
+                            path.as_deref(),
+                        );
+                    }
+                    LapceUICommand::DocumentCodeActionsAndSave {
+                        path,
+                        rev,
+                        kind,
+                        result,
+                        kinds,
+                        exit_widget_id,
+                    } => {
+                        if let Err(e) =
+                            data.main_split.document_code_actions_and_save(
+                                ctx,
+                                path,
+                                *rev,
+                                kind,
+                                result,
+                                kinds,
+                                *exit_widget_id,
+                                &data.config,
+                            )
+                        {
+                            data.status_message = Arc::new(Some(format!(
+                                "{kind} on save failed: {e}"
+                            )));
+                        }
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ClearStatusMessage => {
+                        data.status_message = Arc::new(None);
+                        ctx.set_handled();
+                    }
                     LapceUICommand::DocumentFormatAndSave(
                         path,
                         rev,
@@ -1222,10 +1662,23 @@ fn handle_command_event(
                         ));
                         ctx.set_handled();
                     }
+                    LapceUICommand::ShowReleaseNotes(release) => {
+                        let release_notes = Arc::make_mut(&mut data.release_notes);
+                        release_notes.active = true;
+                        release_notes.release = Some(release.to_owned());
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::Focus,
+                            Target::Widget(release_notes.widget_id),
+                        ));
+                        ctx.set_handled();
+                    }
                     LapceUICommand::BufferSave(path, rev, exit_widget_id) => {
                         let doc = data.main_split.open_docs.get_mut(path).unwrap();
                         if doc.rev() == *rev {
-                            Arc::make_mut(doc).buffer_mut().set_pristine();
+                            let doc = Arc::make_mut(doc);
+                            doc.buffer_mut().set_pristine();
+                            doc.save_undo_history();
                             if let Some(widget_id) = exit_widget_id {
                                 ctx.submit_command(Command::new(
                                     LAPCE_COMMAND,
@@ -1292,11 +1745,124 @@ fn handle_command_event(
                         ));
                         ctx.set_handled();
                     }
+                    LapceUICommand::CompareActiveFileWith(path) => {
+                        data.main_split.compare_active_file_with(
+                            ctx,
+                            path,
+                            &data.config,
+                        );
+                        ctx.set_handled();
+                    }
                     LapceUICommand::UpdateKeymapsFilter(pattern) => {
                         ctx.set_handled();
                         let keypress = Arc::make_mut(&mut data.keypress);
                         keypress.filter_commands(pattern);
                     }
+                    LapceUICommand::UpdateProblemFilter(pattern) => {
+                        ctx.set_handled();
+                        Arc::make_mut(&mut data.problem).filter =
+                            ProblemFilter::new(pattern);
+                    }
+                    LapceUICommand::NewTerminal(launch) => {
+                        ctx.set_handled();
+                        data.show_panel(ctx, PanelKind::Terminal);
+                        let terminal = data
+                            .terminal
+                            .terminals
+                            .get(&data.terminal.active_term_id)
+                            .or_else(|| data.terminal.terminals.values().next());
+                        let (command, target) = match terminal {
+                            Some(terminal) => (
+                                LapceUICommand::SplitTerminal(
+                                    true,
+                                    terminal.widget_id,
+                                    launch.clone(),
+                                ),
+                                terminal.split_id,
+                            ),
+                            None => (
+                                LapceUICommand::InitTerminalPanel(
+                                    true,
+                                    launch.clone(),
+                                ),
+                                data.terminal.split_id,
+                            ),
+                        };
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            command,
+                            Target::Widget(target),
+                        ));
+                    }
+                    LapceUICommand::RunInTerminal(input) => {
+                        ctx.set_handled();
+                        data.show_panel(ctx, PanelKind::Terminal);
+                        let terminal = data
+                            .terminal
+                            .terminals
+                            .get(&data.terminal.active_term_id)
+                            .or_else(|| data.terminal.terminals.values().next());
+                        match terminal {
+                            Some(terminal) => {
+                                terminal
+                                    .proxy
+                                    .proxy_rpc
+                                    .terminal_write(terminal.term_id, input);
+                                terminal
+                                    .raw
+                                    .lock()
+                                    .term
+                                    .scroll_display(Scroll::Bottom);
+                            }
+                            None => {
+                                ctx.submit_command(Command::new(
+                                    LAPCE_UI_COMMAND,
+                                    LapceUICommand::InitTerminalPanel(
+                                        false,
+                                        TerminalLaunch::Input(input.clone()),
+                                    ),
+                                    Target::Widget(data.terminal.split_id),
+                                ));
+                            }
+                        }
+                    }
+                    LapceUICommand::OpenTerminalFind => {
+                        ctx.set_handled();
+                        Arc::make_mut(&mut data.terminal).find.visible = true;
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::Focus,
+                            Target::Widget(data.terminal.find_view_id),
+                        ));
+                    }
+                    LapceUICommand::CloseTerminalFind => {
+                        ctx.set_handled();
+                        Arc::make_mut(&mut data.terminal).find.visible = false;
+                        ctx.submit_command(Command::new(
+                            LAPCE_UI_COMMAND,
+                            LapceUICommand::Focus,
+                            Target::Widget(data.terminal.active),
+                        ));
+                    }
+                    LapceUICommand::UpdateTerminalFind(pattern) => {
+                        ctx.set_handled();
+                        Arc::make_mut(&mut data.terminal).find.search_string =
+                            pattern.to_string();
+                    }
+                    LapceUICommand::ToggleTerminalFindRegex => {
+                        ctx.set_handled();
+                        let find = &mut Arc::make_mut(&mut data.terminal).find;
+                        find.regex = !find.regex;
+                    }
+                    LapceUICommand::TerminalFindNext { backward } => {
+                        ctx.set_handled();
+                        data.terminal.find_next(if *backward {
+                            Direction::Left
+                        } else {
+                            Direction::Right
+                        });
+                        ctx.request_paint();
+                    }
                     LapceUICommand::FilterKeymaps(
                         pattern,
                         filtered_commands_with_keymap,
@@ -1425,6 +1991,21 @@ fn handle_command_event(
                             .or_insert(false);
                         *state = !*state;
                     }
+                    LapceUICommand::ApplyQuickFix { path, diagnostic } => {
+                        data.apply_quick_fix(ctx, path, diagnostic);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ApplyQuickFixResponse {
+                        path,
+                        rev,
+                        diagnostic,
+                        resp,
+                    } => {
+                        data.apply_quick_fix_response(
+                            ctx, path, *rev, diagnostic, resp,
+                        );
+                        ctx.set_handled();
+                    }
                     LapceUICommand::JumpToLineLocation(editor_view_id, location) => {
                         data.main_split.jump_to_location(
                             ctx,
@@ -1505,6 +2086,159 @@ fn handle_command_event(
                             }
                         }
                     }
+                    LapceUICommand::UpdateCodeLenses {
+                        path,
+                        rev,
+                        plugin_id,
+                        lenses,
+                    } => {
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            if doc.rev() == *rev {
+                                Arc::make_mut(doc)
+                                    .set_code_lenses(*plugin_id, lenses.clone());
+                            }
+                        }
+                    }
+                    LapceUICommand::UpdateDocumentLinks { path, rev, links } => {
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            if doc.rev() == *rev {
+                                Arc::make_mut(doc).set_document_links(links.clone());
+                            }
+                        }
+                    }
+                    LapceUICommand::UpdateDocumentHighlights {
+                        path,
+                        rev,
+                        highlights,
+                    } => {
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            if doc.rev() == *rev {
+                                Arc::make_mut(doc)
+                                    .set_document_highlights(highlights.clone());
+                            }
+                        }
+                    }
+                    LapceUICommand::RunCodeLens { plugin_id, command } => {
+                        match command.command.as_str() {
+                            // the references are shown by the client, with the
+                            // arguments of "editor.action.showReferences"
+                            "editor.action.showReferences"
+                            | "rust-analyzer.showReferences" => {
+                                let locations = command
+                                    .arguments
+                                    .as_ref()
+                                    .and_then(|args| args.get(2))
+                                    .and_then(|locations| {
+                                        serde_json::from_value::<Vec<Location>>(
+                                            locations.clone(),
+                                        )
+                                        .ok()
+                                    })
+                                    .unwrap_or_default();
+                                let locations = locations
+                                    .iter()
+                                    .map(|l| EditorLocation {
+                                        path: path_from_url(&l.uri),
+                                        position: Some(l.range.start),
+                                        scroll_offset: None,
+                                        history: None,
+                                    })
+                                    .collect();
+                                ctx.submit_command(Command::new(
+                                    LAPCE_UI_COMMAND,
+                                    LapceUICommand::RunPaletteReferences(locations),
+                                    Target::Widget(data.palette.widget_id),
+                                ));
+                            }
+                            _ => {
+                                data.proxy.proxy_rpc.execute_command(
+                                    *plugin_id,
+                                    command.clone(),
+                                    |result| {
+                                        if let Err(e) = result {
+                                            log::error!(
+                                                "failed to run the command of the code lens: {}",
+                                                e.message
+                                            );
+                                        }
+                                    },
+                                );
+                            }
+                        }
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::UpdateFoldingRanges { path, rev, ranges } => {
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            if doc.rev() == *rev {
+                                Arc::make_mut(doc)
+                                    .set_lsp_folding_ranges(ranges.clone());
+                            }
+                        }
+                    }
+                    LapceUICommand::UpdateLinkedEditingRanges {
+                        path,
+                        view_id,
+                        rev,
+                        ranges,
+                    } => {
+                        if let Some(doc) = data.main_split.open_docs.get(path) {
+                            if doc.rev() == *rev {
+                                if let Some(editor) =
+                                    data.main_split.editors.get_mut(view_id)
+                                {
+                                    Arc::make_mut(editor).linked_editing =
+                                        ranges.as_ref().map(|ranges| {
+                                            LinkedEditing::new(
+                                                doc.buffer(),
+                                                *rev,
+                                                ranges,
+                                            )
+                                        });
+                                }
+                            }
+                        }
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ExpandSelectionWithRanges {
+                        path,
+                        view_id,
+                        rev,
+                        selection,
+                        ranges,
+                    } => {
+                        if let Some(doc) = data.main_split.open_docs.get(path) {
+                            if let Some(editor) = data
+                                .main_split
+                                .editors
+                                .get_mut(view_id)
+                                .filter(|_| doc.rev() == *rev)
+                            {
+                                let editor = Arc::make_mut(editor);
+                                if editor.cursor.expandable_selection(doc.buffer())
+                                    == *selection
+                                {
+                                    editor.expand_selection_ranges =
+                                        Some(ExpandSelectionRanges::new(
+                                            doc.buffer(),
+                                            *rev,
+                                            selection.clone(),
+                                            ranges,
+                                        ));
+                                    ctx.submit_command(Command::new(
+                                        LAPCE_COMMAND,
+                                        LapceCommand {
+                                            kind: CommandKind::Focus(
+                                                FocusCommand::ExpandSelection,
+                                            ),
+                                            data: None,
+                                        },
+                                        Target::Widget(*view_id),
+                                    ));
+                                }
+                            }
+                        }
+                        ctx.set_handled();
+                    }
                     LapceUICommand::UpdateCodeActions(path, rev, offset, resp) => {
                         if let Some(doc) = data.main_split.open_docs.get_mut(path) {
                             if doc.rev() == *rev {
@@ -1514,6 +2248,83 @@ fn handle_command_event(
                             }
                         }
                     }
+                    LapceUICommand::ShowCallHierarchy { plugin_id, item } => {
+                        let call_hierarchy = Arc::make_mut(&mut data.call_hierarchy);
+                        call_hierarchy.set_root(*plugin_id, *item.clone());
+                        call_hierarchy.request_calls(
+                            &data.proxy,
+                            Vec::new(),
+                            *item.clone(),
+                            ctx.get_external_handle(),
+                            data.id,
+                        );
+                        data.show_panel(ctx, PanelKind::CallHierarchy);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::UpdateCallHierarchyCalls {
+                        generation,
+                        indices,
+                        calls,
+                    } => {
+                        Arc::make_mut(&mut data.call_hierarchy).tree.set_children(
+                            *generation,
+                            indices,
+                            calls.clone(),
+                        );
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ShowTypeHierarchy { plugin_id, item } => {
+                        let type_hierarchy = Arc::make_mut(&mut data.type_hierarchy);
+                        type_hierarchy.set_root(*plugin_id, *item.clone());
+                        type_hierarchy.request_types(
+                            &data.proxy,
+                            Vec::new(),
+                            *item.clone(),
+                            ctx.get_external_handle(),
+                            data.id,
+                        );
+                        data.show_panel(ctx, PanelKind::TypeHierarchy);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::UpdateTypeHierarchyTypes {
+                        generation,
+                        indices,
+                        types,
+                    } => {
+                        Arc::make_mut(&mut data.type_hierarchy).tree.set_children(
+                            *generation,
+                            indices,
+                            types.clone(),
+                        );
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ShowRenamePreview { new_name, edit } => {
+                        Arc::make_mut(&mut data.rename_preview)
+                            .set_edit(new_name.clone(), edit);
+                        data.show_panel(ctx, PanelKind::RenamePreview);
+                        ctx.set_handled();
+                    }
+                    LapceUICommand::ShowPeek {
+                        editor_view_id,
+                        offset,
+                        locations,
+                    } => {
+                        if let Some(editor) = data.main_split.active_editor() {
+                            if editor.view_id == *editor_view_id
+                                && *offset == editor.cursor.offset()
+                            {
+                                let workspace_path = data.workspace.path.clone();
+                                Arc::make_mut(&mut data.peek).show(
+                                    ctx,
+                                    *editor_view_id,
+                                    *offset,
+                                    locations.clone(),
+                                    workspace_path.as_deref(),
+                                );
+                            }
+                        }
+                        ctx.set_handled();
+                    }
                     LapceUICommand::PaletteReferences(offset, locations) => {
                         if let Some(editor) = data.main_split.active_editor() {
                             if *offset == editor.cursor.offset() {
@@ -1554,7 +2365,9 @@ fn handle_command_event(
                     LapceUICommand::OpenFileChanged { path, content } => {
                         let doc = data.main_split.open_docs.get_mut(path).unwrap();
                         let doc = Arc::make_mut(doc);
-                        doc.handle_file_changed(content.to_owned());
+                        if doc.handle_file_changed(content.to_owned()) {
+                            data.main_split.show_disk_conflict(ctx, path);
+                        }
                     }
                     LapceUICommand::ReloadBuffer { path, rev, content } => {
                         let doc = data.main_split.open_docs.get_mut(path).unwrap();
@@ -1670,6 +2483,90 @@ fn handle_command_event(
                             }
                         }
                     }
+                    LapceUICommand::ReopenWithEncoding(encoding) => {
+                        ctx.set_handled();
+                        data.main_split
+                            .reopen_with_encoding(ctx, encoding.to_string());
+                    }
+                    LapceUICommand::SaveWithEncoding(encoding) => {
+                        ctx.set_handled();
+                        data.main_split
+                            .save_with_encoding(ctx, encoding.to_string());
+                    }
+                    LapceUICommand::SetLineEnding(line_ending) => {
+                        ctx.set_handled();
+                        data.main_split.change_line_ending(*line_ending);
+                    }
+                    LapceUICommand::SetIndentStyle(indent_style) => {
+                        ctx.set_handled();
+                        data.main_split.change_indent_style(*indent_style);
+                    }
+                    LapceUICommand::RestartLanguageServer(plugin_id) => {
+                        ctx.set_handled();
+                        data.proxy.proxy_rpc.restart_language_server(*plugin_id);
+                    }
+                    LapceUICommand::StopLanguageServer(plugin_id) => {
+                        ctx.set_handled();
+                        data.proxy.proxy_rpc.stop_language_server(*plugin_id);
+                    }
+                    LapceUICommand::InitBufferInfo {
+                        path,
+                        encoding,
+                        large_file,
+                        read_only,
+                    } => {
+                        ctx.set_handled();
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            let doc = Arc::make_mut(doc);
+                            doc.set_encoding(encoding.to_string());
+                            if *read_only {
+                                doc.buffer_mut().set_read_only(true);
+                            }
+                            if *large_file {
+                                doc.set_large_file();
+                            }
+                        }
+                    }
+                    LapceUICommand::AppendBufferContent {
+                        path,
+                        content,
+                        last,
+                    } => {
+                        ctx.set_handled();
+                        if let Some(doc) = data.main_split.open_docs.get_mut(path) {
+                            Arc::make_mut(doc)
+                                .append_content(content.clone(), *last);
+                        }
+                    }
+                    LapceUICommand::ReopenedWithEncoding {
+                        path,
+                        content,
+                        encoding,
+                    } => {
+                        ctx.set_handled();
+                        let doc = if let Some(doc) =
+                            data.main_split.open_docs.get_mut(path)
+                        {
+                            Arc::make_mut(doc)
+                        } else {
+                            return;
+                        };
+                        doc.set_encoding(encoding.to_string());
+                        doc.reload(content.to_owned(), true);
+
+                        let modal = data.config.lapce.modal;
+                        for (_, editor) in data.main_split.editors.iter_mut() {
+                            if &editor.content == doc.content() {
+                                let offset = doc
+                                    .buffer()
+                                    .offset_line_end(doc.buffer().len(), !modal)
+                                    .min(editor.cursor.offset());
+                                Arc::make_mut(editor)
+                                    .cursor
+                                    .set_offset(offset, false, false);
+                            }
+                        }
+                    }
                     LapceUICommand::SetLanguage(name) => {
                         ctx.set_handled();
                         let editor =
@@ -1882,6 +2779,14 @@ fn event(
         data: &mut LapceTabData,
         env: &Env,
     ) {
+        if let Event::KeyDown(key_event) | Event::KeyUp(key_event) = event {
+            if data.config.editor.inlay_hints_while_held {
+                let held = key_event.mods.ctrl() && key_event.mods.alt();
+                if held != data.config.inlay_hints_held {
+                    Arc::make_mut(&mut data.config).set_inlay_hints_held(held);
+                }
+            }
+        }
         self.handle_command_event(ctx, event, data, env);
 
         if data.about.active || event.should_propagate_to_hidden() {
@@ -1890,6 +2795,9 @@ fn event(
         if data.alert.active || event.should_propagate_to_hidden() {
             self.alert.event(ctx, event, data, env);
         }
+        if data.release_notes.active || event.should_propagate_to_hidden() {
+            self.release_notes.event(ctx, event, data, env);
+        }
         if data.picker.active || event.should_propagate_to_hidden() {
             self.picker.event(ctx, event, data, env);
         }
@@ -1904,9 +2812,17 @@ fn event(
         {
             self.hover.event(ctx, event, data, env);
         }
+        if data.signature.status != SignatureStatus::Inactive
+            || event.should_propagate_to_hidden()
+        {
+            self.signature.event(ctx, event, data, env);
+        }
         if data.rename.active || event.should_propagate_to_hidden() {
             self.rename.event(ctx, event, data, env);
         }
+        if data.peek.active || event.should_propagate_to_hidden() {
+            self.peek.event(ctx, event, data, env);
+        }
 
         self.handle_mouse_event(ctx, event, data, env);
 
@@ -1933,9 +2849,18 @@ fn event(
             self.panel_bottom.event(ctx, event, data, env);
         }
 
+        if data.signature.status != SignatureStatus::Inactive
+            && !data.main_split.editor_tabs.iter().any(|(_, tab)| {
+                tab.active_child().widget_id() == data.signature.editor_view_id
+            })
+        {
+            Arc::make_mut(&mut data.signature).cancel();
+        }
+
         if data.hover.status != HoverStatus::Inactive {
             if let Event::MouseMove(mouse_event) = &event {
-                if !self.hover.layout_rect().contains(mouse_event.pos)
+                if !data.hover.pinned
+                    && !self.hover.layout_rect().contains(mouse_event.pos)
                     && !self.main_split.layout_rect().contains(mouse_event.pos)
                 {
                     Arc::make_mut(&mut data.hover).cancel();
@@ -2053,10 +2978,13 @@ fn lifecycle(
         self.status.lifecycle(ctx, event, data, env);
         self.completion.lifecycle(ctx, event, data, env);
         self.hover.lifecycle(ctx, event, data, env);
+        self.signature.lifecycle(ctx, event, data, env);
         self.rename.lifecycle(ctx, event, data, env);
+        self.peek.lifecycle(ctx, event, data, env);
         self.picker.lifecycle(ctx, event, data, env);
         self.about.lifecycle(ctx, event, data, env);
         self.alert.lifecycle(ctx, event, data, env);
+        self.release_notes.lifecycle(ctx, event, data, env);
         self.panel_left.lifecycle(ctx, event, data, env);
         self.panel_right.lifecycle(ctx, event, data, env);
         self.panel_bottom.lifecycle(ctx, event, data, env);
@@ -2087,6 +3015,9 @@ fn update(
         if old_data.alert.active != data.alert.active {
             ctx.request_layout();
         }
+        if old_data.release_notes.active != data.release_notes.active {
+            ctx.request_layout();
+        }
 
         if !old_data
             .main_split
@@ -2120,6 +3051,13 @@ fn update(
             ctx.request_layout();
         }
 
+        if old_data.peek.active != data.peek.active
+            || old_data.peek.offset != data.peek.offset
+            || old_data.peek.from_editor != data.peek.from_editor
+        {
+            ctx.request_layout();
+        }
+
         if old_data.picker.active != data.picker.active {
             ctx.request_layout();
         }
@@ -2128,11 +3066,14 @@ fn update(
         self.main_split.update(ctx, data, env);
         self.completion.update(ctx, data, env);
         self.hover.update(ctx, data, env);
+        self.signature.update(ctx, data, env);
         self.rename.update(ctx, data, env);
+        self.peek.update(ctx, data, env);
         self.status.update(ctx, data, env);
         self.picker.update(ctx, data, env);
         self.about.update(ctx, data, env);
         self.alert.update(ctx, data, env);
+        self.release_notes.update(ctx, data, env);
         self.panel_left.update(ctx, data, env);
         self.panel_right.update(ctx, data, env);
         self.panel_bottom.update(ctx, data, env);
@@ -2276,6 +3217,13 @@ fn layout(
             self.hover.set_origin(ctx, data, env, hover_origin);
         }
 
+        if data.signature.status == SignatureStatus::Done {
+            self.signature.layout(ctx, bc, data, env);
+            let signature_origin =
+                data.signature_origin(ctx.text(), self_size, &data.config);
+            self.signature.set_origin(ctx, data, env, signature_origin);
+        }
+
         if data.rename.active {
             let rename_size = self.rename.layout(
                 ctx,
@@ -2288,6 +3236,27 @@ fn layout(
             self.rename.set_origin(ctx, data, env, rename_origin);
         }
 
+        if data.peek.active {
+            let width = data
+                .main_split
+                .editors
+                .get(&data.peek.from_editor)
+                .map(|editor| editor.size.borrow().width)
+                .unwrap_or(0.0);
+            let peek_size = self.peek.layout(
+                ctx,
+                &BoxConstraints::tight(Size::new(
+                    width,
+                    data.peek.height(&data.config),
+                )),
+                data,
+                env,
+            );
+            let peek_origin =
+                data.peek_origin(ctx.text(), self_size, peek_size, &data.config);
+            self.peek.set_origin(ctx, data, env, peek_origin);
+        }
+
         if data.picker.active {
             let picker_size = self.picker.layout(ctx, bc, data, env);
             self.picker.set_origin(
@@ -2309,6 +3278,10 @@ fn layout(
             self.alert.layout(ctx, bc, data, env);
             self.alert.set_origin(ctx, data, env, Point::ZERO);
         }
+        if data.release_notes.active {
+            self.release_notes.layout(ctx, bc, data, env);
+            self.release_notes.set_origin(ctx, data, env, Point::ZERO);
+        }
 
         self_size
     }
@@ -2420,8 +3393,12 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
             );
             self.rename.paint(ctx, data, env);
         }
+        if data.peek.active {
+            self.peek.paint(ctx, data, env);
+        }
         self.completion.paint(ctx, data, env);
         self.hover.paint(ctx, data, env);
+        self.signature.paint(ctx, data, env);
         self.picker.paint(ctx, data, env);
         ctx.incr_alpha_depth();
         self.paint_drag_on_panel(ctx, data);
@@ -2429,6 +3406,7 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
         ctx.incr_alpha_depth();
         self.about.paint(ctx, data, env);
         self.alert.paint(ctx, data, env);
+        self.release_notes.paint(ctx, data, env);
     }
 }
 
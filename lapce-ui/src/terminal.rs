@@ -2,12 +2,13 @@
 
 use alacritty_terminal::{
     grid::{Dimensions, Scroll},
-    index::{Column, Direction, Line, Side},
+    index::{Column, Direction, Line, Point as TermPoint, Side},
     selection::{Selection, SelectionType},
-    term::{cell::Flags, search::RegexSearch, Term},
+    term::{cell::Flags, Term},
 };
 use druid::{
-    piet::{Text, TextAttribute, TextLayoutBuilder},
+    kurbo::Circle,
+    piet::{Text, TextAttribute, TextLayout, TextLayoutBuilder},
     BoxConstraints, Command, Cursor, Data, Env, Event, EventCtx, FontWeight,
     LayoutCtx, LifeCycle, LifeCycleCtx, MouseEvent, PaintCtx, Point, Rect,
     RenderContext, Size, Target, UpdateCtx, Widget, WidgetExt, WidgetId, WidgetPod,
@@ -16,19 +17,26 @@
 use lapce_data::{
     command::{LapceUICommand, LAPCE_UI_COMMAND},
     config::LapceTheme,
-    data::{FocusArea, LapceTabData},
+    data::{FocusArea, LapceTabData, LapceWorkspaceType},
     document::SystemClipboard,
+    editor::{EditorLocation, LineCol},
+    palette::PaletteType,
     panel::PanelKind,
-    terminal::{EventProxy, LapceTerminalData, LapceTerminalViewData},
+    terminal::{
+        EventProxy, LapceTerminalData, LapceTerminalViewData, RawTerminal,
+        TerminalLaunch,
+    },
+    terminal_link::TerminalLink,
 };
 use lapce_rpc::terminal::TermId;
 use unicode_width::UnicodeWidthChar;
 
 use crate::{
+    editor::{is_link_modifier, view::LapceEditorView},
     panel::{LapcePanel, PanelHeaderKind, PanelSizing},
     scroll::LapcePadding,
     split::LapceSplit,
-    svg::get_svg,
+    svg::{get_svg, terminal_svg},
     tab::LapceIcon,
 };
 
@@ -39,6 +47,7 @@
 pub struct TerminalPanel {
     widget_id: WidgetId,
     split: WidgetPod<LapceTabData, LapceSplit>,
+    find: WidgetPod<LapceTabData, TerminalFindBox>,
 }
 
 impl TerminalPanel {
@@ -47,6 +56,7 @@ pub fn new(data: &LapceTabData) -> Self {
         Self {
             widget_id: data.terminal.widget_id,
             split: WidgetPod::new(split),
+            find: WidgetPod::new(TerminalFindBox::new(data)),
         }
     }
 
@@ -93,6 +103,12 @@ fn event(
             }
             _ => (),
         }
+        if data.terminal.find.visible {
+            self.find.event(ctx, event, data, env);
+            if ctx.is_handled() {
+                return;
+            }
+        }
         self.split.event(ctx, event, data, env);
     }
 
@@ -104,6 +120,7 @@ fn lifecycle(
         env: &Env,
     ) {
         self.split.lifecycle(ctx, event, data, env);
+        self.find.lifecycle(ctx, event, data, env);
     }
 
     fn update(
@@ -116,7 +133,10 @@ fn update(
         if data.terminal.terminals.is_empty() {
             ctx.submit_command(Command::new(
                 LAPCE_UI_COMMAND,
-                LapceUICommand::InitTerminalPanel(true),
+                LapceUICommand::InitTerminalPanel(
+                    true,
+                    TerminalLaunch::Profile(None),
+                ),
                 Target::Widget(data.terminal.split_id),
             ));
         }
@@ -124,6 +144,7 @@ fn update(
             ctx.request_paint();
         }
         self.split.update(ctx, data, env);
+        self.find.update(ctx, data, env);
     }
 
     fn layout(
@@ -135,6 +156,18 @@ fn layout(
     ) -> Size {
         self.split.layout(ctx, bc, data, env);
         self.split.set_origin(ctx, data, env, Point::ZERO);
+        let find_size = self.find.layout(
+            ctx,
+            &BoxConstraints::new(Size::ZERO, Size::new(bc.max().width, 30.0)),
+            data,
+            env,
+        );
+        self.find.set_origin(
+            ctx,
+            data,
+            env,
+            Point::new((bc.max().width - find_size.width - 20.0).max(0.0), 30.0),
+        );
         bc.max()
     }
 
@@ -146,6 +179,242 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
                 .get_color_unchecked(LapceTheme::TERMINAL_BACKGROUND),
         );
         self.split.paint(ctx, data, env);
+        if data.terminal.find.visible {
+            self.find.paint(ctx, data, env);
+        }
+    }
+}
+
+/// The find bar of the terminal panel, over the top right of the terminals
+struct TerminalFindBox {
+    input: WidgetPod<LapceTabData, Box<dyn Widget<LapceTabData>>>,
+    input_width: f64,
+    /// The toggle of whether the search string is a regular expression
+    regex_rect: Rect,
+    regex_command: Command,
+    icons: Vec<LapceIcon>,
+    mouse_pos: Point,
+}
+
+impl TerminalFindBox {
+    fn new(data: &LapceTabData) -> Self {
+        let input =
+            LapceEditorView::new(data.terminal.find_view_id, WidgetId::next(), None)
+                .hide_header()
+                .hide_gutter()
+                .set_placeholder("Find".to_string())
+                .padding((10.0, 5.0));
+        let icons = [
+            (
+                "arrow-up.svg",
+                LapceUICommand::TerminalFindNext { backward: true },
+            ),
+            (
+                "arrow-down.svg",
+                LapceUICommand::TerminalFindNext { backward: false },
+            ),
+            ("close.svg", LapceUICommand::CloseTerminalFind),
+        ]
+        .into_iter()
+        .map(|(icon, command)| LapceIcon {
+            icon,
+            rect: Rect::ZERO,
+            command: Command::new(
+                LAPCE_UI_COMMAND,
+                command,
+                Target::Widget(data.id),
+            ),
+        })
+        .collect();
+        Self {
+            input: WidgetPod::new(input.boxed()),
+            input_width: 200.0,
+            regex_rect: Rect::ZERO,
+            regex_command: Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::ToggleTerminalFindRegex,
+                Target::Widget(data.id),
+            ),
+            icons,
+            mouse_pos: Point::ZERO,
+        }
+    }
+
+    fn rects(&self) -> impl Iterator<Item = &Rect> {
+        std::iter::once(&self.regex_rect).chain(self.icons.iter().map(|i| &i.rect))
+    }
+}
+
+impl Widget<LapceTabData> for TerminalFindBox {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        env: &Env,
+    ) {
+        self.input.event(ctx, event, data, env);
+        match event {
+            Event::MouseMove(mouse_event) => {
+                ctx.set_handled();
+                self.mouse_pos = mouse_event.pos;
+                if self.rects().any(|rect| rect.contains(mouse_event.pos)) {
+                    ctx.set_cursor(&druid::Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+                ctx.request_paint();
+            }
+            Event::MouseDown(mouse_event) => {
+                ctx.set_handled();
+                if self.regex_rect.contains(mouse_event.pos) {
+                    ctx.submit_command(self.regex_command.clone());
+                }
+                for icon in self.icons.iter() {
+                    if icon.rect.contains(mouse_event.pos) {
+                        ctx.submit_command(icon.command.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &LapceTabData,
+        env: &Env,
+    ) {
+        self.input.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        env: &Env,
+    ) {
+        if old_data.terminal.find != data.terminal.find {
+            ctx.request_paint();
+        }
+        self.input.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        env: &Env,
+    ) -> Size {
+        let height = bc.max().height;
+        let icons_width = height * (self.icons.len() + 1) as f64;
+        let input_width =
+            self.input_width.min(bc.max().width - icons_width).max(0.0);
+        let input_size = self.input.layout(
+            ctx,
+            &BoxConstraints::tight(Size::new(input_width, height)),
+            data,
+            env,
+        );
+        self.input.set_origin(ctx, data, env, Point::ZERO);
+
+        let height = input_size.height;
+        self.regex_rect = Size::new(height, height)
+            .to_rect()
+            .with_origin(Point::new(input_size.width, 0.0))
+            .inflate(-5.0, -5.0);
+        for (i, icon) in self.icons.iter_mut().enumerate() {
+            icon.rect = Size::new(height, height)
+                .to_rect()
+                .with_origin(Point::new(
+                    input_size.width + (i + 1) as f64 * height,
+                    0.0,
+                ))
+                .inflate(-5.0, -5.0);
+        }
+
+        Size::new(input_size.width + icons_width, height)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, env: &Env) {
+        let rect = ctx.size().to_rect();
+        let shadow_width = data.config.ui.drop_shadow_width() as f64;
+        if shadow_width > 0.0 {
+            ctx.blurred_rect(
+                rect,
+                shadow_width,
+                data.config
+                    .get_color_unchecked(LapceTheme::LAPCE_DROPDOWN_SHADOW),
+            );
+        } else {
+            ctx.stroke(
+                rect.inflate(0.5, 0.5),
+                data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+                1.0,
+            );
+        }
+        ctx.fill(
+            rect,
+            data.config
+                .get_color_unchecked(LapceTheme::EDITOR_BACKGROUND),
+        );
+        self.input.paint(ctx, data, env);
+
+        for rect in self.rects() {
+            if rect.contains(self.mouse_pos) {
+                ctx.fill(
+                    rect,
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_CURRENT_LINE),
+                );
+            }
+        }
+
+        if data.terminal.find.regex {
+            ctx.stroke(
+                self.regex_rect,
+                data.config.get_color_unchecked(LapceTheme::EDITOR_FOCUS),
+                1.0,
+            );
+        }
+        let text_layout = ctx
+            .text()
+            .new_text_layout(".*")
+            .font(
+                data.config.editor.font_family(),
+                data.config.ui.font_size() as f64,
+            )
+            .text_color(
+                data.config
+                    .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                    .clone(),
+            )
+            .build()
+            .unwrap();
+        let text_size = text_layout.size();
+        ctx.draw_text(
+            &text_layout,
+            Point::new(
+                self.regex_rect.center().x - text_size.width / 2.0,
+                self.regex_rect.y0 + text_layout.y_offset(self.regex_rect.height()),
+            ),
+        );
+
+        for icon in self.icons.iter() {
+            let svg = get_svg(icon.icon).unwrap();
+            ctx.draw_svg(
+                &svg,
+                icon.rect.inflate(-7.0, -7.0),
+                Some(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                ),
+            );
+        }
     }
 }
 
@@ -311,7 +580,11 @@ fn get_icons(&self, self_size: Size, data: &LapceTabData) -> Vec<LapceIcon> {
                 .with_origin(Point::new(x, gap)),
             command: Command::new(
                 LAPCE_UI_COMMAND,
-                LapceUICommand::SplitTerminal(true, terminal_data.widget_id),
+                LapceUICommand::SplitTerminal(
+                    true,
+                    terminal_data.widget_id,
+                    TerminalLaunch::Profile(None),
+                ),
                 Target::Widget(terminal_data.split_id),
             ),
         };
@@ -330,10 +603,29 @@ fn icon_hit_test(&mut self, mouse_event: &MouseEvent) -> bool {
         false
     }
 
-    fn mouse_down(&self, ctx: &mut EventCtx, mouse_event: &MouseEvent) {
+    fn mouse_down(
+        &self,
+        ctx: &mut EventCtx,
+        mouse_event: &MouseEvent,
+        data: &LapceTabData,
+    ) {
         for icon in self.icons.iter() {
             if icon.rect.contains(mouse_event.pos) {
                 ctx.submit_command(icon.command.clone());
+                return;
+            }
+        }
+        // A double click on the title renames the terminal
+        if mouse_event.count == 2 {
+            if let Some(terminal) = data.terminal.terminals.get(&self.term_id) {
+                ctx.submit_command(Command::new(
+                    LAPCE_UI_COMMAND,
+                    LapceUICommand::RunPalette(Some(PaletteType::RenameTerminal {
+                        term_id: self.term_id,
+                        name: terminal.display_title(),
+                    })),
+                    Target::Auto,
+                ));
             }
         }
     }
@@ -344,7 +636,7 @@ fn event(
         &mut self,
         ctx: &mut EventCtx,
         event: &Event,
-        _data: &mut LapceTabData,
+        data: &mut LapceTabData,
         _env: &Env,
     ) {
         match event {
@@ -362,7 +654,7 @@ fn event(
                 }
             }
             Event::MouseDown(mouse_event) => {
-                self.mouse_down(ctx, mouse_event);
+                self.mouse_down(ctx, mouse_event, data);
             }
             _ => {}
         }
@@ -408,7 +700,20 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
 
         ctx.with_save(|ctx| {
             ctx.clip(clip_rect);
-            let svg = get_svg("terminal.svg").unwrap();
+            let term = data.terminal.terminals.get(&self.term_id).unwrap();
+            let color = term
+                .color
+                .as_deref()
+                .and_then(|color| data.config.terminal_color(color));
+            if let Some(color) = color {
+                let size = ctx.size();
+                ctx.fill(
+                    Rect::new(0.0, size.height - 2.0, size.width, size.height),
+                    color,
+                );
+            }
+
+            let svg = terminal_svg(term.icon.as_deref());
             let width = data.config.terminal_font_size() as f64;
             let height = data.config.terminal_font_size() as f64;
             let rect = Size::new(width, height).to_rect().with_origin(Point::new(
@@ -418,16 +723,21 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
             ctx.draw_svg(
                 &svg,
                 rect,
-                Some(
+                Some(color.unwrap_or_else(|| {
                     data.config
-                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
-                ),
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                })),
             );
+            if term.alert.is_some() {
+                ctx.fill(
+                    Circle::new(Point::new(rect.x1, rect.y0), 3.0),
+                    data.config.get_color_unchecked(LapceTheme::EDITOR_FOCUS),
+                );
+            }
 
-            let term = data.terminal.terminals.get(&self.term_id).unwrap();
             let text_layout = ctx
                 .text()
-                .new_text_layout(term.title.clone())
+                .new_text_layout(term.display_title())
                 .font(
                     data.config.ui.font_family(),
                     data.config.ui.font_size() as f64,
@@ -472,6 +782,10 @@ struct LapceTerminal {
     widget_id: WidgetId,
     width: f64,
     height: f64,
+    mouse_pos: Point,
+    /// The first and last points of the link under the mouse while the key
+    /// that makes a click open it is held, which is underlined
+    link: Option<(TermPoint, TermPoint)>,
 }
 
 impl LapceTerminal {
@@ -481,6 +795,8 @@ pub fn new(data: &LapceTerminalData) -> Self {
             widget_id: data.widget_id,
             width: 0.0,
             height: 0.0,
+            mouse_pos: Point::ZERO,
+            link: None,
         }
     }
 
@@ -488,6 +804,14 @@ pub fn request_focus(&self, ctx: &mut EventCtx, data: &mut LapceTabData) {
         ctx.request_focus();
         Arc::make_mut(&mut data.terminal).active = self.widget_id;
         Arc::make_mut(&mut data.terminal).active_term_id = self.term_id;
+        if let Some(terminal) = Arc::make_mut(&mut data.terminal)
+            .terminals
+            .get_mut(&self.term_id)
+        {
+            if terminal.alert.is_some() {
+                Arc::make_mut(terminal).alert = None;
+            }
+        }
         data.focus = Arc::new(self.widget_id);
         data.focus_area = FocusArea::Panel(PanelKind::Terminal);
         if let Some((index, position)) =
@@ -501,31 +825,154 @@ pub fn request_focus(&self, ctx: &mut EventCtx, data: &mut LapceTabData) {
         }
     }
 
+    /// Mark the prompts of the commands that the shell integration told of
+    /// in the padding, with the color of how they exited, and label the
+    /// hovered one
+    fn paint_commands(
+        &self,
+        ctx: &mut PaintCtx,
+        data: &LapceTabData,
+        raw: &RawTerminal,
+        line_height: f64,
+    ) {
+        let screen_lines = raw.term.screen_lines() as i32;
+        let hovered_line = (self.mouse_pos.y / line_height).floor() as i32;
+        for command in raw.commands.iter() {
+            let line = raw.screen_line(command.line);
+            if line < 0 || line >= screen_lines {
+                continue;
+            }
+
+            let color = match (command.is_finished(), command.exit_code) {
+                (false, _) => LapceTheme::EDITOR_DIM,
+                (true, Some(0)) | (true, None) => LapceTheme::TERMINAL_GREEN,
+                (true, Some(_)) => LapceTheme::TERMINAL_RED,
+            };
+            let y = line as f64 * line_height;
+            ctx.fill(
+                Circle::new(Point::new(-5.0, y + line_height / 2.0), 2.5),
+                data.config.get_color_unchecked(color),
+            );
+
+            if line != hovered_line || !ctx.is_hot() {
+                continue;
+            }
+            let label = match (command.duration, command.exit_code) {
+                (None, _) => "Running".to_string(),
+                (Some(duration), Some(code)) => {
+                    format!("Exit code {code} \u{b7} {:.1}s", duration.as_secs_f64())
+                }
+                (Some(duration), None) => {
+                    format!("{:.1}s", duration.as_secs_f64())
+                }
+            };
+            let text_layout = ctx
+                .text()
+                .new_text_layout(label)
+                .font(
+                    data.config.ui.font_family(),
+                    data.config.ui.font_size() as f64,
+                )
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let text_size = text_layout.size();
+            let rect = Size::new(text_size.width + 10.0, line_height)
+                .to_rect()
+                .with_origin(Point::new(self.width - text_size.width - 10.0, y));
+            ctx.fill(
+                rect,
+                data.config
+                    .get_color_unchecked(LapceTheme::TERMINAL_BACKGROUND),
+            );
+            ctx.stroke(
+                rect,
+                data.config.get_color_unchecked(LapceTheme::LAPCE_BORDER),
+                1.0,
+            );
+            ctx.draw_text(
+                &text_layout,
+                Point::new(rect.x0 + 5.0, y + text_layout.y_offset(line_height)),
+            );
+        }
+    }
+
+    /// The point of the grid at the position
+    fn grid_point(&self, term: &Term<EventProxy>, pos: Point) -> TermPoint {
+        let row_size = self.height / term.screen_lines() as f64;
+        let col_size = self.width / term.columns() as f64;
+        let offset = term.grid().display_offset();
+        let column = Column((pos.x / col_size) as usize);
+        let line = Line((pos.y / row_size) as i32 - offset as i32);
+        TermPoint { line, column }
+    }
+
     fn select(
         &self,
         term: &mut Term<EventProxy>,
         mouse_event: &MouseEvent,
         ty: SelectionType,
     ) {
-        let row_size = self.height / term.screen_lines() as f64;
-        let col_size = self.width / term.columns() as f64;
-        let offset = term.grid().display_offset();
-        let column = Column((mouse_event.pos.x / col_size) as usize);
-        let line = Line((mouse_event.pos.y / row_size) as i32 - offset as i32);
+        let point = self.grid_point(term, mouse_event.pos);
         match &mut term.selection {
-            Some(selection) => selection.update(
-                alacritty_terminal::index::Point { line, column },
-                Direction::Left,
-            ),
+            Some(selection) => selection.update(point, Direction::Left),
             None => {
-                term.selection = Some(Selection::new(
-                    ty,
-                    alacritty_terminal::index::Point { line, column },
-                    Direction::Left,
-                ));
+                term.selection = Some(Selection::new(ty, point, Direction::Left));
             }
         }
     }
+
+    /// The link of the output at the position, with its path resolved from
+    /// the working directory of the shell. Files of a local workspace have
+    /// to exist, so that words that only look like paths aren't links
+    fn link_at(
+        &self,
+        data: &LapceTabData,
+        pos: Point,
+    ) -> Option<(TermPoint, TermPoint, TerminalLink)> {
+        let terminal = data.terminal.terminals.get(&self.term_id)?;
+        let (start, end, link) = {
+            let raw = terminal.raw.lock();
+            raw.link_at(self.grid_point(&raw.term, pos))?
+        };
+        let link = link.resolve(terminal.cwd().as_deref(), &data.picker.home);
+        if let TerminalLink::File { path, .. } = &link {
+            if data.workspace.kind == LapceWorkspaceType::Local && !path.is_file() {
+                return None;
+            }
+        }
+        Some((start, end, link))
+    }
+
+    fn open_link(ctx: &mut EventCtx, tab_id: WidgetId, link: TerminalLink) {
+        let command = match link {
+            TerminalLink::Url(url) => LapceUICommand::OpenURI(url),
+            TerminalLink::File { path, line, column } => {
+                LapceUICommand::JumpToLineColLocation(
+                    None,
+                    EditorLocation {
+                        path,
+                        position: line.map(|line| LineCol {
+                            line: line.saturating_sub(1),
+                            column: column.unwrap_or(1).saturating_sub(1),
+                        }),
+                        scroll_offset: None,
+                        history: None,
+                    },
+                    false,
+                )
+            }
+        };
+        ctx.submit_command(Command::new(
+            LAPCE_UI_COMMAND,
+            command,
+            Target::Widget(tab_id),
+        ));
+    }
 }
 
 impl Widget<LapceTabData> for LapceTerminal {
@@ -545,15 +992,24 @@ fn event(
         let mut term_data = LapceTerminalViewData {
             terminal: old_terminal_data.clone(),
             config: data.config.clone(),
-            find: data.find.clone(),
+            find: data.terminal.find.clone(),
         };
         ctx.set_cursor(&Cursor::IBeam);
         match event {
             Event::MouseDown(mouse_event) => {
                 self.request_focus(ctx, data);
+                let link = if mouse_event.button.is_left()
+                    && is_link_modifier(mouse_event.mods)
+                {
+                    self.link_at(data, mouse_event.pos)
+                } else {
+                    None
+                };
                 let terminal = data.terminal.terminals.get(&self.term_id).unwrap();
                 let term = &mut terminal.raw.lock().term;
-                if mouse_event.button.is_right() {
+                if let Some((_, _, link)) = link {
+                    Self::open_link(ctx, data.id, link);
+                } else if mouse_event.button.is_right() {
                     let mut clipboard = SystemClipboard {};
                     match term.selection_to_string() {
                         Some(selection) => {
@@ -583,6 +1039,29 @@ fn event(
                 }
             }
             Event::MouseMove(mouse_event) => {
+                // The label of a command is shown when its prompt is hovered
+                let line_height = data.config.terminal_line_height() as f64;
+                if (self.mouse_pos.y / line_height).floor()
+                    != (mouse_event.pos.y / line_height).floor()
+                {
+                    ctx.request_paint();
+                }
+                self.mouse_pos = mouse_event.pos;
+                let link = if !mouse_event.buttons.has_left()
+                    && is_link_modifier(mouse_event.mods)
+                {
+                    self.link_at(data, mouse_event.pos)
+                        .map(|(start, end, _)| (start, end))
+                } else {
+                    None
+                };
+                if link != self.link {
+                    self.link = link;
+                    ctx.request_paint();
+                }
+                if self.link.is_some() {
+                    ctx.set_cursor(&Cursor::Pointer);
+                }
                 if mouse_event.buttons.has_left() {
                     let term = &mut data
                         .terminal
@@ -832,64 +1311,90 @@ fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
                 );
             }
         }
-        if data.find.visual {
-            if let Some(search_string) = data.find.search_string.as_ref() {
-                if let Ok(dfas) = RegexSearch::new(&regex::escape(search_string)) {
-                    let mut start = alacritty_terminal::index::Point::new(
-                        alacritty_terminal::index::Line(
-                            -(content.display_offset as i32),
+        if data.terminal.find.visible {
+            if let Some(dfas) = data.terminal.find.dfas() {
+                let mut start = alacritty_terminal::index::Point::new(
+                    alacritty_terminal::index::Line(
+                        -(content.display_offset as i32),
+                    ),
+                    alacritty_terminal::index::Column(0),
+                );
+                let end_line =
+                    (start.line + term.screen_lines()).min(term.bottommost_line());
+                let mut max_lines = (end_line.0 - start.line.0) as usize;
+
+                while let Some(m) = term.search_next(
+                    &dfas,
+                    start,
+                    Direction::Right,
+                    Side::Left,
+                    Some(max_lines),
+                ) {
+                    let match_start = m.start();
+                    if match_start.line.0 < start.line.0
+                        || (match_start.line.0 == start.line.0
+                            && match_start.column.0 < start.column.0)
+                    {
+                        break;
+                    }
+                    let x = match_start.column.0 as f64 * char_width;
+                    let y = (match_start.line.0 as f64
+                        + content.display_offset as f64)
+                        * line_height;
+                    let rect = Rect::ZERO.with_origin(Point::new(x, y)).with_size(
+                        Size::new(
+                            (m.end().column.0 - m.start().column.0
+                                + term.grid()[*m.end()].c.width().unwrap_or(1))
+                                as f64
+                                * char_width,
+                            line_height,
                         ),
-                        alacritty_terminal::index::Column(0),
                     );
-                    let end_line = (start.line + term.screen_lines())
-                        .min(term.bottommost_line());
-                    let mut max_lines = (end_line.0 - start.line.0) as usize;
-
-                    while let Some(m) = term.search_next(
-                        &dfas,
-                        start,
-                        Direction::Right,
-                        Side::Left,
-                        Some(max_lines),
-                    ) {
-                        let match_start = m.start();
-                        if match_start.line.0 < start.line.0
-                            || (match_start.line.0 == start.line.0
-                                && match_start.column.0 < start.column.0)
-                        {
-                            break;
-                        }
-                        let x = match_start.column.0 as f64 * char_width;
-                        let y = (match_start.line.0 as f64
-                            + content.display_offset as f64)
-                            * line_height;
-                        let rect = Rect::ZERO
-                            .with_origin(Point::new(x, y))
-                            .with_size(Size::new(
-                                (m.end().column.0 - m.start().column.0
-                                    + term.grid()[*m.end()].c.width().unwrap_or(1))
-                                    as f64
-                                    * char_width,
-                                line_height,
-                            ));
-                        ctx.stroke(
-                            rect,
-                            data.config.get_color_unchecked(
-                                LapceTheme::TERMINAL_FOREGROUND,
-                            ),
-                            1.0,
-                        );
-                        start = *m.end();
-                        if start.column.0 < term.last_column() {
-                            start.column.0 += 1;
-                        } else if start.line.0 < term.bottommost_line() {
-                            start.column.0 = 0;
-                            start.line.0 += 1;
-                        }
-                        max_lines = (end_line.0 - start.line.0) as usize;
+                    ctx.stroke(
+                        rect,
+                        data.config
+                            .get_color_unchecked(LapceTheme::TERMINAL_FOREGROUND),
+                        1.0,
+                    );
+                    start = *m.end();
+                    if start.column.0 < term.last_column() {
+                        start.column.0 += 1;
+                    } else if start.line.0 < term.bottommost_line() {
+                        start.column.0 = 0;
+                        start.line.0 += 1;
                     }
+                    max_lines = (end_line.0 - start.line.0) as usize;
                 }
             }
         }
+
+        if let Some((start, end)) = self.link {
+            let color = data.config.get_color_unchecked(LapceTheme::EDITOR_LINK);
+            for line in start.line.0..=end.line.0 {
+                let left_col = if line == start.line.0 {
+                    start.column.0
+                } else {
+                    0
+                };
+                let right_col = if line == end.line.0 {
+                    end.column.0 + 1
+                } else {
+                    term.columns()
+                };
+                let y = (line + content.display_offset as i32 + 1) as f64
+                    * line_height
+                    - 1.0;
+                ctx.stroke(
+                    druid::kurbo::Line::new(
+                        Point::new(left_col as f64 * char_width, y),
+                        Point::new(right_col as f64 * char_width, y),
+                    ),
+                    color,
+                    1.0,
+                );
+            }
+        }
+
+        self.paint_commands(ctx, data, &raw, line_height);
     }
 }
@@ -28,6 +28,7 @@
     menu::{MenuItem, MenuKind},
     palette::PaletteStatus,
     proxy::ProxyStatus,
+    update::package_manager,
 };
 
 pub struct Title {
@@ -384,7 +385,10 @@ fn update_settings(
             .as_ref()
             .as_ref()
             .map(|r| r.version.as_str());
-        let menu_items = vec![
+        let latest_channel =
+            data.latest_release.as_ref().as_ref().map(|r| r.channel);
+        let offline = data.config.lapce.is_offline();
+        let mut menu_items = vec![
             MenuKind::Item(MenuItem {
                 desc: None,
                 command: LapceCommand {
@@ -416,11 +420,22 @@ fn update_settings(
                 },
                 enabled: true,
             }),
-            MenuKind::Separator,
-            MenuKind::Item(MenuItem {
+        ];
+        if !offline {
+            menu_items.push(MenuKind::Separator);
+            menu_items.push(MenuKind::Item(MenuItem {
                 desc: Some(
                     if latest_version.is_some() && latest_version != Some(*VERSION) {
-                        format!("Restart to update ({})", latest_version.unwrap())
+                        format!(
+                            "{} ({}, {})",
+                            if package_manager().is_some() {
+                                "Update available"
+                            } else {
+                                "Restart to update"
+                            },
+                            latest_version.unwrap(),
+                            latest_channel.unwrap()
+                        )
                     } else {
                         "No update available".to_string()
                     },
@@ -433,7 +448,9 @@ fn update_settings(
                 },
                 enabled: latest_version.is_some()
                     && latest_version != Some(*VERSION),
-            }),
+            }));
+        }
+        menu_items.extend([
             MenuKind::Separator,
             MenuKind::Item(MenuItem {
                 desc: None,
@@ -443,8 +460,18 @@ fn update_settings(
                 },
                 enabled: true,
             }),
-        ];
-        if latest_version.is_some() && latest_version != Some(*VERSION) {
+        ]);
+        let dismissed = data
+            .latest_release
+            .as_ref()
+            .as_ref()
+            .map(|r| r.dismissed)
+            .unwrap_or(false);
+        if !offline
+            && latest_version.is_some()
+            && latest_version != Some(*VERSION)
+            && !dismissed
+        {
             let text_layout = piet_text
                 .new_text_layout("1")
                 .font(data.config.ui.font_family(), 10.0)
@@ -0,0 +1,240 @@
+use std::sync::Arc;
+
+use druid::{
+    kurbo::Circle,
+    piet::{Text, TextLayout as PietTextLayout, TextLayoutBuilder},
+    BoxConstraints, Command, Cursor, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, MouseEvent, PaintCtx, Point, RenderContext, Size, Target,
+    UpdateCtx, Widget, WidgetExt, WidgetId,
+};
+use lapce_data::{
+    command::{LapceUICommand, LAPCE_UI_COMMAND},
+    config::LapceTheme,
+    data::LapceTabData,
+    document::Document,
+    panel::PanelKind,
+    undo_tree::{format_time_ago, undo_tree_rows, UndoTreeData},
+};
+
+use crate::panel::{LapcePanel, PanelHeaderKind, PanelSizing};
+
+pub fn new_undo_tree_panel(data: &UndoTreeData) -> LapcePanel {
+    LapcePanel::new(
+        PanelKind::UndoTree,
+        data.widget_id,
+        data.split_id,
+        vec![(
+            data.tree_widget_id,
+            PanelHeaderKind::None,
+            UndoTreeContent::new().boxed(),
+            PanelSizing::Flex(false),
+        )],
+    )
+}
+
+/// The editor the undo tree is shown for, and its document
+fn active_doc(data: &LapceTabData) -> Option<(WidgetId, Arc<Document>)> {
+    let editor = data.main_split.active_editor()?;
+    Some((editor.view_id, data.main_split.editor_doc(editor.view_id)))
+}
+
+struct UndoTreeContent {
+    mouse_pos: Point,
+    content_height: f64,
+}
+
+impl UndoTreeContent {
+    fn new() -> Self {
+        Self {
+            mouse_pos: Point::ZERO,
+            content_height: 0.0,
+        }
+    }
+
+    fn mouse_down(
+        &self,
+        ctx: &mut EventCtx,
+        mouse_event: &MouseEvent,
+        data: &LapceTabData,
+    ) {
+        let (view_id, doc) = if let Some(active) = active_doc(data) {
+            active
+        } else {
+            return;
+        };
+
+        let line_height = data.config.editor.line_height() as f64;
+        let click_line = (mouse_event.pos.y / line_height).floor() as usize;
+        let rows = undo_tree_rows(doc.buffer().undo_tree());
+        if let Some(row) = rows.get(click_line) {
+            ctx.submit_command(Command::new(
+                LAPCE_UI_COMMAND,
+                LapceUICommand::JumpToUndoGroup(row.group),
+                Target::Widget(view_id),
+            ));
+        }
+    }
+}
+
+impl Widget<LapceTabData> for UndoTreeContent {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut LapceTabData,
+        _env: &Env,
+    ) {
+        match event {
+            Event::MouseMove(mouse_event) => {
+                self.mouse_pos = mouse_event.pos;
+
+                if mouse_event.pos.y < self.content_height {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else {
+                    ctx.clear_cursor();
+                }
+
+                ctx.request_paint();
+            }
+            Event::MouseDown(mouse_event) => {
+                self.mouse_down(ctx, mouse_event, data);
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut LifeCycleCtx,
+        _event: &LifeCycle,
+        _data: &LapceTabData,
+        _env: &Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &LapceTabData,
+        data: &LapceTabData,
+        _env: &Env,
+    ) {
+        let state = |data: &LapceTabData| {
+            active_doc(data).map(|(view_id, doc)| (view_id, doc.id(), doc.rev()))
+        };
+        if state(old_data) != state(data) {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &LapceTabData,
+        _env: &Env,
+    ) -> Size {
+        let lines = active_doc(data)
+            .map(|(_, doc)| doc.buffer().undo_tree().len())
+            .unwrap_or(0);
+        let line_height = data.config.editor.line_height() as f64;
+        self.content_height = line_height * lines as f64;
+
+        Size::new(bc.max().width, self.content_height.max(bc.max().height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LapceTabData, _env: &Env) {
+        let doc = if let Some((_, doc)) = active_doc(data) {
+            doc
+        } else {
+            return;
+        };
+
+        let line_height = data.config.editor.line_height() as f64;
+        let size = ctx.size();
+        let mouse_line = (self.mouse_pos.y / line_height).floor() as usize;
+
+        let rect = ctx.region().bounding_box();
+        let min = (rect.y0 / line_height).floor() as usize;
+        let max = (rect.y1 / line_height) as usize + 2;
+
+        let ui_font_family = data.config.ui.font_family();
+        let ui_font_size = data.config.ui.font_size() as f64;
+
+        let tree = doc.buffer().undo_tree();
+        let current = doc.buffer().current_undo_group();
+        let rows = undo_tree_rows(tree);
+        for (line, row) in rows.iter().enumerate().take(max).skip(min) {
+            let y = line_height * line as f64;
+            let is_current = row.group == current;
+
+            if is_current || (ctx.is_hot() && line == mouse_line) {
+                ctx.fill(
+                    Size::new(size.width, line_height)
+                        .to_rect()
+                        .with_origin(Point::new(0.0, y)),
+                    data.config.get_color_unchecked(if is_current {
+                        LapceTheme::PANEL_CURRENT
+                    } else {
+                        LapceTheme::PANEL_HOVERED
+                    }),
+                );
+            }
+
+            let x = line_height * (row.depth as f64 + 0.5);
+            let circle = Circle::new(Point::new(x, y + line_height / 2.0), 3.5);
+            if is_current {
+                ctx.fill(
+                    circle,
+                    data.config.get_color_unchecked(LapceTheme::EDITOR_CARET),
+                );
+            } else {
+                ctx.stroke(
+                    circle,
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND),
+                    1.0,
+                );
+            }
+
+            let label = if row.group == 0 {
+                "Original".to_string()
+            } else {
+                format!("#{}", row.group)
+            };
+            let text_layout = ctx
+                .text()
+                .new_text_layout(label)
+                .font(ui_font_family.clone(), ui_font_size)
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_FOREGROUND)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            let x = x + line_height / 2.0;
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, y + text_layout.y_offset(line_height)),
+            );
+
+            let x = x + text_layout.size().width + 10.0;
+            let text_layout = ctx
+                .text()
+                .new_text_layout(format_time_ago(tree[row.group].time))
+                .font(ui_font_family.clone(), ui_font_size)
+                .text_color(
+                    data.config
+                        .get_color_unchecked(LapceTheme::EDITOR_DIM)
+                        .clone(),
+                )
+                .build()
+                .unwrap();
+            ctx.draw_text(
+                &text_layout,
+                Point::new(x, y + text_layout.y_offset(line_height)),
+            );
+        }
+    }
+}
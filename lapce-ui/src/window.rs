@@ -322,10 +322,26 @@ fn event(
                                 .unwrap_or_default(),
                         );
                         for (_, tab) in data.tabs.iter_mut() {
-                            tab.config = Arc::new(
+                            let config = Arc::new(
                                 Config::load(&tab.workspace.clone())
                                     .unwrap_or_default(),
                             );
+                            if config.language_servers != tab.config.language_servers
+                            {
+                                tab.proxy.proxy_rpc.update_language_servers(
+                                    config.language_servers.clone(),
+                                );
+                            }
+                            if config.terminal.scrollback_lines
+                                != tab.config.terminal.scrollback_lines
+                            {
+                                for terminal in tab.terminal.terminals.values() {
+                                    terminal.raw.lock().set_scrollback_lines(
+                                        config.terminal.scrollback_lines,
+                                    );
+                                }
+                            }
+                            tab.config = config;
                         }
                         Arc::make_mut(&mut data.keypress)
                             .update_keymaps(&data.config);